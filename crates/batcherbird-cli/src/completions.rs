@@ -0,0 +1,477 @@
+//! Shell completion scripts and the man page, generated from the clap command tree
+//! so they stay in sync with the CLI definition.
+
+use clap::{Arg, Command};
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+pub fn generate_completions(shell: Shell, cmd: &mut Command) -> String {
+    cmd.build();
+    match shell {
+        Shell::Bash => bash(cmd),
+        Shell::Zsh => zsh(cmd),
+        Shell::Fish => fish(cmd),
+        Shell::Powershell => powershell(cmd),
+    }
+}
+
+/// Subcommands worth completing (clap's generated `help` is left out)
+fn subcommands(cmd: &Command) -> impl Iterator<Item = &Command> {
+    cmd.get_subcommands().filter(|sub| sub.get_name() != "help" && !sub.is_hide_set())
+}
+
+fn options(cmd: &Command) -> impl Iterator<Item = &Arg> {
+    cmd.get_arguments().filter(|arg| !arg.is_positional() && !arg.is_hide_set())
+}
+
+fn positionals(cmd: &Command) -> impl Iterator<Item = &Arg> {
+    cmd.get_arguments().filter(|arg| arg.is_positional() && !arg.is_hide_set())
+}
+
+fn takes_value(arg: &Arg) -> bool {
+    arg.get_action().takes_values()
+}
+
+fn possible_values(arg: &Arg) -> Vec<String> {
+    arg.get_possible_values().iter()
+        .filter(|value| !value.is_hide_set())
+        .map(|value| value.get_name().to_string())
+        .collect()
+}
+
+/// First line of an argument's or command's help
+fn summary(help: Option<&clap::builder::StyledStr>) -> String {
+    help.map(|text| text.to_string().lines().next().unwrap_or_default().trim().to_string())
+        .unwrap_or_default()
+}
+
+fn flags(arg: &Arg) -> Vec<String> {
+    let mut flags = Vec::new();
+    if let Some(short) = arg.get_short() {
+        flags.push(format!("-{}", short));
+    }
+    if let Some(long) = arg.get_long() {
+        flags.push(format!("--{}", long));
+    }
+    flags
+}
+
+fn value_name(arg: &Arg) -> String {
+    arg.get_value_names()
+        .and_then(|names| names.first().map(|name| name.to_string()))
+        .unwrap_or_else(|| arg.get_id().to_string().to_uppercase())
+}
+
+fn bash(cmd: &Command) -> String {
+    let name = cmd.get_name();
+    let function = format!("_{}", name.replace('-', "_"));
+    let subcommand_names: Vec<&str> = subcommands(cmd).map(Command::get_name).collect();
+
+    let mut script = String::new();
+    let _ = writeln!(script, "# bash completion for {}", name);
+    let _ = writeln!(script, "{}() {{", function);
+    let _ = writeln!(script, "    local cur prev subcommand opts");
+    let _ = writeln!(script, "    cur=\"${{COMP_WORDS[COMP_CWORD]}}\"");
+    let _ = writeln!(script, "    prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"");
+    let _ = writeln!(script, "    subcommand=\"\"");
+    let _ = writeln!(script, "    for word in \"${{COMP_WORDS[@]:1:COMP_CWORD-1}}\"; do");
+    let _ = writeln!(script, "        case \" {} \" in", subcommand_names.join(" "));
+    let _ = writeln!(script, "            *\" $word \"*) subcommand=\"$word\"; break ;;");
+    let _ = writeln!(script, "        esac");
+    let _ = writeln!(script, "    done");
+    let _ = writeln!(script);
+
+    // Option values: fixed choices where clap knows them, files otherwise
+    let _ = writeln!(script, "    case \"$prev\" in");
+    let mut seen = Vec::new();
+    for arg in options(cmd).chain(subcommands(cmd).flat_map(options)).filter(|arg| takes_value(arg)) {
+        let pattern = flags(arg).join("|");
+        if pattern.is_empty() || seen.contains(&pattern) {
+            continue;
+        }
+        let values = possible_values(arg);
+        let reply = if values.is_empty() {
+            "COMPREPLY=( $(compgen -f -- \"$cur\") )".to_string()
+        } else {
+            format!("COMPREPLY=( $(compgen -W \"{}\" -- \"$cur\") )", values.join(" "))
+        };
+        let _ = writeln!(script, "        {}) {}; return 0 ;;", pattern, reply);
+        seen.push(pattern);
+    }
+    let _ = writeln!(script, "    esac");
+    let _ = writeln!(script);
+
+    // Global options are already propagated into each subcommand by `build`
+    let global: Vec<String> = options(cmd).flat_map(flags).collect();
+    let _ = writeln!(script, "    case \"$subcommand\" in");
+    for sub in subcommands(cmd) {
+        let mut words: Vec<String> = positionals(sub).flat_map(possible_values).collect();
+        words.extend(options(sub).flat_map(flags));
+        let _ = writeln!(script, "        {}) opts=\"{}\" ;;", sub.get_name(), words.join(" "));
+    }
+    let _ = writeln!(script, "        *) opts=\"{} {}\" ;;", subcommand_names.join(" "), global.join(" "));
+    let _ = writeln!(script, "    esac");
+    let _ = writeln!(script, "    COMPREPLY=( $(compgen -W \"$opts\" -- \"$cur\") )");
+    let _ = writeln!(script, "    if [[ ${{#COMPREPLY[@]}} -eq 0 && -n \"$subcommand\" ]]; then");
+    let _ = writeln!(script, "        COMPREPLY=( $(compgen -f -- \"$cur\") )");
+    let _ = writeln!(script, "    fi");
+    let _ = writeln!(script, "}}");
+    let _ = writeln!(script, "complete -F {} {}", function, name);
+    script
+}
+
+/// Escape text for a single-quoted zsh word inside an _arguments spec
+fn zsh_escape(text: &str) -> String {
+    text.replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+}
+
+fn zsh_arg_spec(arg: &Arg) -> String {
+    let help = zsh_escape(&summary(arg.get_help()));
+    let value = if takes_value(arg) {
+        let values = possible_values(arg);
+        let action = if values.is_empty() { "_files".to_string() } else { format!("({})", values.join(" ")) };
+        format!(":{}:{}", value_name(arg).to_lowercase(), action)
+    } else {
+        String::new()
+    };
+
+    let flags = flags(arg);
+    match flags.as_slice() {
+        [single] => format!("'{}[{}]{}'", single, help, value),
+        [short, long] => format!("'({} {})'{{{},{}}}'[{}]{}'", short, long, short, long, help, value),
+        _ => String::new(),
+    }
+}
+
+fn zsh_positional_spec(arg: &Arg) -> String {
+    let help = zsh_escape(&summary(arg.get_help()));
+    let name = value_name(arg).to_lowercase();
+    let description = if help.is_empty() { name } else { format!("{} -- {}", name, help) };
+    let values = possible_values(arg);
+    let action = if values.is_empty() { "_files".to_string() } else { format!("({})", values.join(" ")) };
+    let repeat = if arg.get_num_args().is_some_and(|range| range.max_values() > 1) { "*" } else { "" };
+    format!("'{}:{}:{}'", repeat, description, action)
+}
+
+fn zsh(cmd: &Command) -> String {
+    let name = cmd.get_name();
+    let mut script = String::new();
+    let _ = writeln!(script, "#compdef {}", name);
+    let _ = writeln!(script);
+    let _ = writeln!(script, "_{}() {{", name);
+    let _ = writeln!(script, "    local -a subcommands");
+    let _ = writeln!(script, "    subcommands=(");
+    for sub in subcommands(cmd) {
+        let _ = writeln!(script, "        '{}:{}'", sub.get_name(), zsh_escape(&summary(sub.get_about())));
+    }
+    let _ = writeln!(script, "    )");
+    let _ = writeln!(script);
+
+    let global: Vec<String> = options(cmd).map(zsh_arg_spec).filter(|spec| !spec.is_empty()).collect();
+    let _ = writeln!(script, "    local context state line");
+    let _ = write!(script, "    _arguments -C");
+    for spec in &global {
+        let _ = write!(script, " \\\n        {}", spec);
+    }
+    let _ = writeln!(script, " \\\n        '1: :->command' \\\n        '*:: :->args'");
+    let _ = writeln!(script);
+    let _ = writeln!(script, "    case $state in");
+    let _ = writeln!(script, "        command) _describe 'command' subcommands ;;");
+    let _ = writeln!(script, "        args)");
+    let _ = writeln!(script, "            case $line[1] in");
+    for sub in subcommands(cmd) {
+        let specs: Vec<String> = options(sub).map(zsh_arg_spec)
+            .chain(positionals(sub).map(zsh_positional_spec))
+            .filter(|spec| !spec.is_empty())
+            .collect();
+        let _ = write!(script, "                {}) _arguments", sub.get_name());
+        for spec in specs {
+            let _ = write!(script, " \\\n                    {}", spec);
+        }
+        let _ = writeln!(script, " ;;");
+    }
+    let _ = writeln!(script, "            esac ;;");
+    let _ = writeln!(script, "    esac");
+    let _ = writeln!(script, "}}");
+    let _ = writeln!(script);
+    let _ = writeln!(script, "_{} \"$@\"", name);
+    script
+}
+
+fn fish_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+fn fish_option(name: &str, condition: Option<&str>, arg: &Arg) -> String {
+    let mut line = format!("complete -c {}", name);
+    if let Some(condition) = condition {
+        let _ = write!(line, " -n '{}'", condition);
+    }
+    if let Some(short) = arg.get_short() {
+        let _ = write!(line, " -s {}", short);
+    }
+    if let Some(long) = arg.get_long() {
+        let _ = write!(line, " -l {}", long);
+    }
+    let help = summary(arg.get_help());
+    if !help.is_empty() {
+        let _ = write!(line, " -d '{}'", fish_escape(&help));
+    }
+    if takes_value(arg) {
+        let values = possible_values(arg);
+        if values.is_empty() {
+            line.push_str(" -r -F");
+        } else {
+            let _ = write!(line, " -r -f -a '{}'", values.join(" "));
+        }
+    }
+    line
+}
+
+fn fish(cmd: &Command) -> String {
+    let name = cmd.get_name();
+    let mut script = String::new();
+    let _ = writeln!(script, "# fish completion for {}", name);
+    let _ = writeln!(script, "complete -c {} -f", name);
+    for arg in options(cmd) {
+        let _ = writeln!(script, "{}", fish_option(name, None, arg));
+    }
+    for sub in subcommands(cmd) {
+        let _ = writeln!(script, "complete -c {} -n '__fish_use_subcommand' -a {} -d '{}'",
+            name, sub.get_name(), fish_escape(&summary(sub.get_about())));
+    }
+    for sub in subcommands(cmd) {
+        let condition = format!("__fish_seen_subcommand_from {}", sub.get_name());
+        for arg in options(sub).filter(|arg| !arg.is_global_set()) {
+            let _ = writeln!(script, "{}", fish_option(name, Some(&condition), arg));
+        }
+        for arg in positionals(sub) {
+            let values = possible_values(arg);
+            if values.is_empty() {
+                let _ = writeln!(script, "complete -c {} -n '{}' -F", name, condition);
+            } else {
+                let _ = writeln!(script, "complete -c {} -n '{}' -a '{}'", name, condition, values.join(" "));
+            }
+        }
+    }
+    script
+}
+
+/// A single-quoted PowerShell string
+fn powershell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+fn powershell_list(words: &[String]) -> String {
+    let quoted: Vec<String> = words.iter().map(|word| powershell_quote(word)).collect();
+    format!("@({})", quoted.join(", "))
+}
+
+fn powershell(cmd: &Command) -> String {
+    let name = cmd.get_name();
+    let subcommand_names: Vec<String> = subcommands(cmd).map(|sub| sub.get_name().to_string()).collect();
+
+    let mut script = String::new();
+    let _ = writeln!(script, "# PowerShell completion for {}", name);
+    let _ = writeln!(script, "Register-ArgumentCompleter -Native -CommandName {} -ScriptBlock {{", powershell_quote(name));
+    let _ = writeln!(script, "    param($wordToComplete, $commandAst, $cursorPosition)");
+    let _ = writeln!(script, "    $words = @($commandAst.CommandElements | Select-Object -Skip 1 | ForEach-Object {{ $_.ToString() }})");
+    let _ = writeln!(script, "    if ($wordToComplete) {{ $words = @($words | Select-Object -SkipLast 1) }}");
+    let _ = writeln!(script, "    $subcommands = {}", powershell_list(&subcommand_names));
+    let _ = writeln!(script, "    $subcommand = $words | Where-Object {{ $subcommands -contains $_ }} | Select-Object -First 1");
+    let _ = writeln!(script, "    $previous = $words | Select-Object -Last 1");
+    let _ = writeln!(script);
+
+    // Option values: fixed choices where clap knows them; returning nothing falls back to paths
+    let _ = writeln!(script, "    $candidates = switch -CaseSensitive ($previous) {{");
+    let mut seen = Vec::new();
+    for arg in options(cmd).chain(subcommands(cmd).flat_map(options)).filter(|arg| takes_value(arg)) {
+        for flag in flags(arg) {
+            if seen.contains(&flag) {
+                continue;
+            }
+            let values = possible_values(arg);
+            let reply = if values.is_empty() { "return".to_string() } else { powershell_list(&values) };
+            let _ = writeln!(script, "        {} {{ {} }}", powershell_quote(&flag), reply);
+            seen.push(flag);
+        }
+    }
+    let _ = writeln!(script, "        default {{");
+    let _ = writeln!(script, "            switch -CaseSensitive ($subcommand) {{");
+    for sub in subcommands(cmd) {
+        let mut words: Vec<String> = positionals(sub).flat_map(possible_values).collect();
+        words.extend(options(sub).flat_map(flags));
+        let _ = writeln!(script, "                {} {{ {} }}", powershell_quote(sub.get_name()), powershell_list(&words));
+    }
+    let mut top: Vec<String> = subcommand_names.clone();
+    top.extend(options(cmd).flat_map(flags));
+    let _ = writeln!(script, "                default {{ {} }}", powershell_list(&top));
+    let _ = writeln!(script, "            }}");
+    let _ = writeln!(script, "        }}");
+    let _ = writeln!(script, "    }}");
+    let _ = writeln!(script, "    $candidates | Where-Object {{ $_ -like \"$wordToComplete*\" }} | ForEach-Object {{");
+    let _ = writeln!(script, "        [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)");
+    let _ = writeln!(script, "    }}");
+    let _ = writeln!(script, "}}");
+    script
+}
+
+/// Escape text for roff, including a leading control character
+fn roff_escape(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    if escaped.starts_with('.') || escaped.starts_with('\'') {
+        format!("\\&{}", escaped)
+    } else {
+        escaped
+    }
+}
+
+fn roff_paragraphs(page: &mut String, text: &str) {
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            page.push_str(".PP\n");
+        } else {
+            let _ = writeln!(page, "{}", roff_escape(line.trim_end()));
+        }
+    }
+}
+
+fn roff_arguments<'a>(page: &mut String, args: impl Iterator<Item = &'a Arg>) {
+    for arg in args.filter(|arg| !arg.is_hide_set()) {
+        page.push_str(".TP\n");
+        let value = if arg.is_positional() {
+            format!("\\fI{}\\fR", roff_escape(&value_name(arg)))
+        } else {
+            let flags = flags(arg).iter()
+                .map(|flag| format!("\\fB{}\\fR", roff_escape(flag)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            if takes_value(arg) {
+                format!("{} \\fI{}\\fR", flags, roff_escape(&value_name(arg)))
+            } else {
+                flags
+            }
+        };
+        let _ = writeln!(page, "{}", value);
+
+        let mut help = arg.get_long_help().or(arg.get_help()).map(|text| text.to_string()).unwrap_or_default();
+        let defaults: Vec<String> = arg.get_default_values().iter().map(|v| v.to_string_lossy().to_string()).collect();
+        if !defaults.is_empty() && takes_value(arg) {
+            let _ = write!(help, " [default: {}]", defaults.join(","));
+        }
+        let values = possible_values(arg);
+        if !values.is_empty() {
+            let _ = write!(help, " [possible values: {}]", values.join(", "));
+        }
+        roff_paragraphs(page, help.trim());
+    }
+}
+
+/// Man page (section 1) covering the tool and every subcommand
+pub fn generate_man_page(cmd: &mut Command) -> String {
+    cmd.build();
+    let name = cmd.get_name().to_string();
+    let version = cmd.get_version().unwrap_or_default();
+
+    let mut page = String::new();
+    let _ = writeln!(page, ".TH {} 1 \"\" \"{} {}\" \"User Commands\"", name.to_uppercase(), name, version);
+    page.push_str(".SH NAME\n");
+    let _ = writeln!(page, "{} \\- {}", name, roff_escape(&summary(cmd.get_about())));
+    page.push_str(".SH SYNOPSIS\n");
+    let _ = writeln!(page, "\\fB{}\\fR [\\fIOPTIONS\\fR] \\fICOMMAND\\fR", name);
+    if let Some(about) = cmd.get_long_about().or(cmd.get_about()) {
+        page.push_str(".SH DESCRIPTION\n");
+        roff_paragraphs(&mut page, &about.to_string());
+    }
+
+    page.push_str(".SH OPTIONS\n");
+    roff_arguments(&mut page, cmd.get_arguments());
+
+    page.push_str(".SH COMMANDS\n");
+    for sub in subcommands(cmd) {
+        let _ = writeln!(page, ".SS \"{} {}\"", name, roff_escape(sub.get_name()));
+        if let Some(about) = sub.get_long_about().or(sub.get_about()) {
+            roff_paragraphs(&mut page, &about.to_string());
+        }
+        roff_arguments(&mut page, sub.get_arguments().filter(|arg| !arg.is_global_set() && arg.get_id() != "help"));
+        if let Some(examples) = sub.get_after_help() {
+            page.push_str(".PP\n.nf\n");
+            roff_paragraphs(&mut page, &examples.to_string());
+            page.push_str(".fi\n");
+        }
+    }
+
+    if let Some(examples) = cmd.get_after_help() {
+        page.push_str(".SH EXAMPLES\n.nf\n");
+        for line in examples.to_string().lines().skip_while(|line| line.trim_end().ends_with(':')) {
+            let _ = writeln!(page, "{}", roff_escape(line));
+        }
+        page.push_str(".fi\n");
+    }
+    page
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cli;
+    use clap::CommandFactory;
+    use std::io::Write as _;
+    use std::process::{Command as Process, Stdio};
+
+    /// Every shell's script names each subcommand, each value-taking option and its choices
+    #[test]
+    fn test_scripts_cover_the_command_tree() {
+        let mut cli = Cli::command();
+        cli.build();
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::Powershell] {
+            let script = generate_completions(shell, &mut Cli::command());
+            for sub in subcommands(&cli) {
+                assert!(script.contains(sub.get_name()), "{:?} is missing {}", shell, sub.get_name());
+                for arg in options(sub).filter(|arg| takes_value(arg)) {
+                    let flag = match (shell, arg.get_long()) {
+                        (Shell::Fish, Some(long)) => format!("-l {}", long),
+                        (Shell::Fish, None) => format!("-s {}", arg.get_short().unwrap()),
+                        (_, _) => flags(arg).remove(0),
+                    };
+                    assert!(script.contains(&flag), "{:?} is missing {} of {}", shell, flag, sub.get_name());
+                    for value in possible_values(arg) {
+                        assert!(script.contains(&value), "{:?} is missing {} for {}", shell, value, flag);
+                    }
+                }
+                for value in positionals(sub).flat_map(possible_values) {
+                    assert!(script.contains(&value), "{:?} is missing {} for {}", shell, value, sub.get_name());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_bash_script_parses() {
+        let script = generate_completions(Shell::Bash, &mut Cli::command());
+        let Ok(mut bash) = Process::new("bash").arg("-n").stdin(Stdio::piped()).stderr(Stdio::piped()).spawn() else {
+            return; // No bash to check against
+        };
+        bash.stdin.take().unwrap().write_all(script.as_bytes()).unwrap();
+        let output = bash.wait_with_output().unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    #[test]
+    fn test_man_page_lists_subcommands() {
+        let page = generate_man_page(&mut Cli::command());
+        assert!(page.starts_with(".TH BATCHERBIRD 1"));
+        assert!(page.contains(".SS \"batcherbird completions\""));
+        assert!(!page.contains(".SS \"batcherbird help\""));
+    }
+}
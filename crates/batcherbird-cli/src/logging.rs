@@ -0,0 +1,62 @@
+//! Terminal output for tracing events: the core logs structured events, the CLI prints
+//! them as plain lines with any fields in parentheses after the message.
+
+use std::fmt;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::registry::LookupSpan;
+
+pub fn init(level: Level) {
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .event_format(HumanFormat)
+        .init();
+}
+
+/// Message first, then `(key=value ...)`; no timestamps, targets or span prefixes
+struct HumanFormat;
+
+impl<S, N> FormatEvent<S, N> for HumanFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, _ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        let mut line = LineVisitor::default();
+        event.record(&mut line);
+        write!(writer, "{}", line.message)?;
+        if !line.fields.is_empty() {
+            if !line.message.is_empty() {
+                write!(writer, " ")?;
+            }
+            write!(writer, "({})", line.fields.join(" "))?;
+        }
+        writeln!(writer)
+    }
+}
+
+#[derive(Default)]
+struct LineVisitor {
+    message: String,
+    fields: Vec<String>,
+}
+
+impl Visit for LineVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.fields.push(format!("{}={}", field.name(), value));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            self.fields.push(format!("{}={:?}", field.name(), value));
+        }
+    }
+}
@@ -1,14 +1,109 @@
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
 use tracing::{info, Level};
-use batcherbird_core::{midi::MidiManager, audio::AudioManager};
+use batcherbird_core::{
+    midi::MidiManager,
+    audio::AudioManager,
+    multi_device::SecondaryInputConfig,
+    detection::DetectionConfig,
+    profiles::{ProfileLibrary, SynthProfile},
+    sampler::SamplingConfig,
+    analysis::DEFAULT_DRIFT_LIMIT_CENTS,
+    watchdog::WatchdogConfig,
+    hooks::HookConfig,
+    catalog::{CatalogConfig, CatalogQuery, SessionCatalog},
+    export::{ExportConfig, FolderLayout, OverwritePolicy},
+    dither::Dither,
+    edit::FadeCurve,
+    naming::NamingContext,
+};
+
+mod completions;
+mod logging;
 
 #[derive(Parser)]
 #[command(name = "batcherbird")]
 #[command(about = "Hardware synthesizer sampling automation tool")]
 #[command(version)]
+#[command(after_help = "Examples:
+  batcherbird list-midi
+  batcherbird sample-range --start 36 --end 72
+  batcherbird --profile modern-poly sample-sweep -s 48 -e 60 --cc 43 --values 0,64,127
+  batcherbird sample-kit --notes 36,38,42-46 --format decentsampler -o ./kit
+  batcherbird sample-assisted -s 48 -e 60 --input 1 --triggers cc64=next,cc67=retake
+  batcherbird completions zsh > ~/.zfunc/_batcherbird
+  batcherbird man > batcherbird.1")]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Synth profile id (see list-profiles) applied to sampling commands
+    #[arg(long, global = true)]
+    profile: Option<String>,
+    /// Send MIDI clock at this tempo while sampling (arpeggiators, synced LFOs)
+    #[arg(long, global = true)]
+    clock_bpm: Option<f32>,
+    /// Skip a note that takes longer than this (default: twice its expected length plus 5s)
+    #[arg(long, global = true)]
+    max_note_secs: Option<f32>,
+    /// Stop sampling once the whole batch has run this long
+    #[arg(long, global = true)]
+    max_batch_secs: Option<f32>,
+    /// Stop the batch at the first hung note instead of skipping it
+    #[arg(long, global = true)]
+    abort_on_timeout: bool,
+    /// Warn when captured audio and wall time drift apart by more than this many ppm over recent takes (default 2000)
+    #[arg(long, global = true)]
+    max_clock_drift_ppm: Option<f32>,
+    /// Reopen the audio stream when clock drift is detected instead of only warning
+    #[arg(long, global = true)]
+    resync_on_drift: bool,
+    /// Shell command or .rhai script to run before each note (repeatable; see BATCHERBIRD_* env vars)
+    #[arg(long, global = true)]
+    pre_note_hook: Vec<String>,
+    /// Shell command or .rhai script to run after each note is captured (repeatable)
+    #[arg(long, global = true)]
+    post_note_hook: Vec<String>,
+    /// Shell command or .rhai script to run after each sample file is written (repeatable)
+    #[arg(long, global = true)]
+    post_export_hook: Vec<String>,
+    /// Stop the batch when a hook fails instead of warning
+    #[arg(long, global = true)]
+    abort_on_hook_failure: bool,
+    /// Record exported sessions in the session catalog (see `sessions`)
+    #[arg(long, global = true)]
+    catalog: bool,
+    /// What exports do with files that already exist: overwrite, rename (add _2, _3...), skip or error
+    #[arg(long, global = true, default_value = "overwrite")]
+    overwrite: String,
+    /// Background image for Decent Sampler presets, copied next to the preset
+    #[arg(long, global = true)]
+    ui_background: Option<std::path::PathBuf>,
+    /// Crossfade velocity layers in instrument files over this percentage of each layer instead of hard splits
+    #[arg(long, global = true)]
+    velocity_crossfade: Option<f32>,
+    /// Even out loudness across notes with per-region volume in instrument files, leaving the audio as recorded
+    #[arg(long, global = true)]
+    gain_match: bool,
+    /// How 16 and 24-bit files are rounded from the float capture: off, tpdf or shaped (noise-shaped TPDF)
+    #[arg(long, global = true, default_value = "tpdf")]
+    dither: String,
+    /// Fade in curve of exported samples: linear, exponential or cosine (equal power)
+    #[arg(long, global = true, default_value = "linear")]
+    fade_in_curve: String,
+    /// Fade out curve of exported samples: linear, exponential or cosine (equal power)
+    #[arg(long, global = true, default_value = "linear")]
+    fade_out_curve: String,
+    /// Where exported samples go: flat (next to the instrument file), velocity (vel064/ folders) or samples (samples/ folder)
+    #[arg(long, global = true, default_value = "flat")]
+    layout: String,
+    /// Start sampling without first checking the output folder, disk space, input and synth
+    #[arg(long, global = true)]
+    skip_preflight: bool,
+    /// Audio host to record and play through, e.g. JACK, ALSA or WASAPI (see list-audio)
+    #[arg(long, global = true)]
+    audio_host: Option<String>,
+    /// Record stereo pairs with input 1 on the left and a second device on the right: DEVICE or DEVICE:INPUT (1-based)
+    #[arg(long, global = true)]
+    second_input: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -21,8 +116,67 @@ enum Commands {
     ListMidi,
     /// List available audio devices
     ListAudio,
-    /// Monitor MIDI input messages in real-time
-    MonitorMidi,
+    /// Monitor MIDI input messages in real-time, optionally saving them to a file
+    #[command(after_help = "Examples:
+  batcherbird monitor-midi
+  batcherbird monitor-midi -i 1 --filter notes,cc --channel 1
+  batcherbird monitor-midi -i KeyStep --duration 30 --output keystep.mid
+  batcherbird monitor-midi -i \"MIDI Clock Box\" --filter system --show-clock")]
+    MonitorMidi {
+        /// MIDI input device index or name, or part of it (see list-midi)
+        #[arg(short, long, default_value = "0")]
+        input: String,
+        /// Message kinds to show: notes, cc, program, pitchbend, aftertouch, sysex, system (default: all)
+        #[arg(long)]
+        filter: Option<String>,
+        /// Only show this MIDI channel (1-16, default: any)
+        #[arg(long)]
+        channel: Option<u8>,
+        /// Also show Timing Clock and Active Sensing, which are hidden by default
+        #[arg(long)]
+        show_clock: bool,
+        /// Stop after this many seconds (default: until Ctrl+C)
+        #[arg(long)]
+        duration: Option<f64>,
+        /// Save the shown messages with their timestamps: .json or .mid
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// List built-in and user synth profiles
+    ListProfiles,
+    /// Show every take a batch would record, with its recording time and disk use, without recording
+    #[command(after_help = "Examples:
+  batcherbird plan -s 36 -e 96 --velocities 40,80,127
+  batcherbird --profile minilogue plan -s 48 -e 72 --round-robins 3 --format sfz --takes")]
+    Plan {
+        /// Starting MIDI note number
+        #[arg(short, long)]
+        start: u8,
+        /// Ending MIDI note number
+        #[arg(short, long)]
+        end: u8,
+        /// Comma-separated velocities per note
+        #[arg(long, value_delimiter = ',', default_value = "100")]
+        velocities: Vec<u8>,
+        /// Takes of each note and velocity
+        #[arg(long, default_value_t = 1)]
+        round_robins: u8,
+        /// Export format: wav, sfz, decentsampler or another registered instrument format
+        #[arg(long, default_value = "wav")]
+        format: String,
+        /// Sample rate to plan for (default: the audio input's)
+        #[arg(long)]
+        sample_rate: Option<u32>,
+        /// Channels to plan for (default: the audio input's)
+        #[arg(long)]
+        channels: Option<u16>,
+        /// List every take with its start time
+        #[arg(long)]
+        takes: bool,
+        /// File naming pattern to check for names shared by several takes, e.g. "{synth}_{note_name}_{velocity}_{rr}.wav"
+        #[arg(long)]
+        naming_pattern: Option<String>,
+    },
     /// Sample a single note
     SampleNote {
         /// MIDI note number (0-127)
@@ -30,6 +184,9 @@ enum Commands {
         note: u8,
     },
     /// Sample a range of notes
+    #[command(after_help = "Examples:
+  batcherbird sample-range --start 60 --end 72
+  batcherbird --clock-bpm 120 sample-range -s 36 -e 48")]
     SampleRange {
         /// Starting MIDI note number
         #[arg(short, long)]
@@ -37,6 +194,9 @@ enum Commands {
         /// Ending MIDI note number
         #[arg(short, long)]
         end: u8,
+        /// Flag notes whose pitch drifts more than this many cents for a retake (0 = off)
+        #[arg(long, default_value_t = DEFAULT_DRIFT_LIMIT_CENTS)]
+        max_drift_cents: f32,
     },
     /// Sample a single note and export to WAV
     SampleExport {
@@ -46,16 +206,781 @@ enum Commands {
         /// Output directory for WAV files
         #[arg(short, long, default_value = "./samples")]
         output: String,
+        /// Warn when the note's pitch drifts more than this many cents (0 = off)
+        #[arg(long, default_value_t = DEFAULT_DRIFT_LIMIT_CENTS)]
+        max_drift_cents: f32,
+        /// Keep the raw take and save trim/fade decisions to an edit file (apply with `render`)
+        #[arg(long)]
+        sidecar: bool,
+    },
+    /// Sample a note range once per CC value (e.g. filter cutoff sweep) and export an SFZ
+    SampleSweep {
+        /// Starting MIDI note number
+        #[arg(short, long)]
+        start: u8,
+        /// Ending MIDI note number
+        #[arg(short, long)]
+        end: u8,
+        /// Controller number to sweep (74 = filter cutoff)
+        #[arg(long, default_value_t = 74)]
+        cc: u8,
+        /// Comma-separated controller values, one layer per value
+        #[arg(long, value_delimiter = ',', default_value = "0,32,64,96,127")]
+        values: Vec<u8>,
+        /// Settle time after each CC change in milliseconds
+        #[arg(long, default_value_t = 200)]
+        settle_ms: u64,
+        /// Output directory for WAV and SFZ files
+        #[arg(short, long, default_value = "./samples")]
+        output: String,
+    },
+    /// Record several takes of each note, score them and keep the best (or all)
+    #[command(after_help = "Examples:
+  batcherbird sample-takes -s 48 -e 72 --takes 3
+  batcherbird sample-takes -s 60 -e 60 --takes 5 --keep-all")]
+    SampleTakes {
+        /// Starting MIDI note number
+        #[arg(short, long)]
+        start: u8,
+        /// Ending MIDI note number
+        #[arg(short, long)]
+        end: u8,
+        /// Takes per note
+        #[arg(long, default_value_t = 3)]
+        takes: u8,
+        /// Keep every take (alternates get a _takeN suffix); only the best is mapped
+        #[arg(long)]
+        keep_all: bool,
+        /// Instrument format: sfz or decentsampler
+        #[arg(long, default_value = "sfz")]
+        format: String,
+        /// Output directory for WAV and instrument files
+        #[arg(short, long, default_value = "./samples")]
+        output: String,
+    },
+    /// Record a note range as one continuous take, then slice it into per-note samples
+    #[command(after_help = "Examples:
+  batcherbird sample-long-take -s 36 -e 84
+  batcherbird sample-long-take -s 48 -e 72 --velocities 64,127 --format decentsampler")]
+    SampleLongTake {
+        /// Starting MIDI note number
+        #[arg(short, long)]
+        start: u8,
+        /// Ending MIDI note number
+        #[arg(short, long)]
+        end: u8,
+        /// Comma-separated velocities per note
+        #[arg(long, value_delimiter = ',', default_value = "100")]
+        velocities: Vec<u8>,
+        /// How long after each note-on to look for the sound starting (ms)
+        #[arg(long, default_value_t = 250.0)]
+        search_ms: f32,
+        /// Instrument format: sfz or decentsampler
+        #[arg(long, default_value = "sfz")]
+        format: String,
+        /// Output directory for WAV and instrument files
+        #[arg(short, long, default_value = "./samples")]
+        output: String,
+    },
+    /// Sample drum hits with round robins, flag inconsistent takes and retake them
+    SampleDrums {
+        /// Starting MIDI note number
+        #[arg(short, long)]
+        start: u8,
+        /// Ending MIDI note number
+        #[arg(short, long)]
+        end: u8,
+        /// Takes per note
+        #[arg(long, default_value_t = 4)]
+        round_robins: u8,
+        /// Velocity for every hit
+        #[arg(long, default_value_t = 100)]
+        velocity: u8,
+        /// Allowed loudness/transient deviation from the median take in dB
+        #[arg(long, default_value_t = 3.0)]
+        tolerance_db: f32,
+        /// How many times to retake flagged hits before giving up (0 = only report)
+        #[arg(long, default_value_t = 1)]
+        retakes: u8,
+        /// Drum name overrides on top of the GM map, e.g. "36=808 Kick,38=Rim Snare"
+        #[arg(long)]
+        drum_names: Option<String>,
+        /// Output directory for WAV and SFZ files
+        #[arg(short, long, default_value = "./samples")]
+        output: String,
+    },
+    /// Sample a drum kit: only the notes in the drum map, exported as a one-shot kit
+    SampleKit {
+        /// Notes to sample, e.g. "36,38,42-46" (default: every note in the drum map)
+        #[arg(long)]
+        notes: Option<String>,
+        /// Velocity for every hit
+        #[arg(long, default_value_t = 100)]
+        velocity: u8,
+        /// Drum name overrides on top of the GM map, e.g. "36=808 Kick,38=Rim Snare"
+        #[arg(long)]
+        drum_names: Option<String>,
+        /// Instrument format: sfz or decentsampler
+        #[arg(long, default_value = "sfz")]
+        format: String,
+        /// Output directory for WAV and instrument files
+        #[arg(short, long, default_value = "./samples")]
+        output: String,
+        /// Fit the kit to a hardware sampler (e.g. mpc2000, sp404, s900) and write plain 16-bit WAVs
+        #[arg(long)]
+        target: Option<String>,
+        /// Word size of the instrument's WAVs: 16, 24 or 32f
+        #[arg(long, default_value = "24")]
+        bit_depth: String,
+        /// Also write 16-bit copies fitted to a hardware sampler into a subfolder in the same pass
+        #[arg(long)]
+        hardware_copy: Option<String>,
+    },
+    /// Record notes as you play them on a MIDI keyboard (for gear that can't be triggered over MIDI)
+    #[command(after_help = "Examples:
+  batcherbird sample-live --input 0 --takes 12
+  batcherbird sample-live -i 1 --channel 2 --pre-roll-ms 300 -o ./juno")]
+    SampleLive {
+        /// MIDI input device index (see list-midi)
+        #[arg(short, long, default_value_t = 0)]
+        input: usize,
+        /// Only listen on this MIDI channel (1-16, default: any)
+        #[arg(long)]
+        channel: Option<u8>,
+        /// Audio kept from before each note-on
+        #[arg(long, default_value_t = 200)]
+        pre_roll_ms: u64,
+        /// Recording time after each note-off
+        #[arg(long, default_value_t = 1500)]
+        tail_ms: u64,
+        /// Stop after this many takes (default: until Enter is pressed)
+        #[arg(long)]
+        takes: Option<usize>,
+        /// Instrument format: sfz or decentsampler
+        #[arg(long, default_value = "sfz")]
+        format: String,
+        /// Output directory for WAV and instrument files
+        #[arg(short, long, default_value = "./samples")]
+        output: String,
+    },
+    /// Guided manual sampling: play each note yourself, stepping with a footswitch or the keyboard
+    #[command(after_help = "Examples:
+  batcherbird sample-assisted -s 48 -e 72 --velocities 64,127
+  batcherbird sample-assisted -s 36 -e 60 --input 1 --triggers cc64=next,cc67=retake,note36/10=abort")]
+    SampleAssisted {
+        /// Starting MIDI note number
+        #[arg(short, long)]
+        start: u8,
+        /// Ending MIDI note number
+        #[arg(short, long)]
+        end: u8,
+        /// Comma-separated velocities per note
+        #[arg(long, value_delimiter = ',', default_value = "100")]
+        velocities: Vec<u8>,
+        /// MIDI input device index for the footswitch (see list-midi; default: keyboard only)
+        #[arg(short, long)]
+        input: Option<usize>,
+        /// Footswitch bindings, e.g. "cc64=next,cc67=retake,note36/10=abort"
+        #[arg(long, default_value = "cc64=next,cc67=retake")]
+        triggers: String,
+        /// Instrument format: sfz or decentsampler
+        #[arg(long, default_value = "sfz")]
+        format: String,
+        /// Output directory for WAV and instrument files
+        #[arg(short, long, default_value = "./samples")]
+        output: String,
+    },
+    /// Sample legato transitions between note pairs for an SFZ legato instrument
+    SampleLegato {
+        /// Starting MIDI note number
+        #[arg(short, long)]
+        start: u8,
+        /// Ending MIDI note number
+        #[arg(short, long)]
+        end: u8,
+        /// Comma-separated semitone intervals from source to target note
+        #[arg(long, value_delimiter = ',', allow_hyphen_values = true, default_value = "-2,-1,1,2")]
+        intervals: Vec<i8>,
+        /// How long the source note sounds before the transition in milliseconds
+        #[arg(long, default_value_t = 800)]
+        hold_ms: u64,
+        /// Time both notes overlap before the source note-off in milliseconds
+        #[arg(long, default_value_t = 60)]
+        overlap_ms: u64,
+        /// Skip the plain sustain per note (first-note regions)
+        #[arg(long)]
+        transitions_only: bool,
+        /// Output directory for WAV and SFZ files
+        #[arg(short, long, default_value = "./samples")]
+        output: String,
+    },
+    /// Sample release tails (audio after note-off) for trigger=release regions
+    SampleReleases {
+        /// Starting MIDI note number
+        #[arg(short, long)]
+        start: u8,
+        /// Ending MIDI note number
+        #[arg(short, long)]
+        end: u8,
+        /// Audio kept from before the note-off in milliseconds
+        #[arg(long, default_value_t = 20)]
+        pre_buffer_ms: u64,
+        /// Also export the full takes as the attack samples
+        #[arg(long)]
+        with_sustains: bool,
+        /// Instrument format: sfz or decentsampler
+        #[arg(long, default_value = "sfz")]
+        format: String,
+        /// Output directory for WAV and instrument files
+        #[arg(short, long, default_value = "./samples")]
+        output: String,
+    },
+    /// Play a Standard MIDI File to the synth and record the whole performance to one WAV
+    SampleMidiFile {
+        /// .mid file to play (format 0 or 1)
+        file: String,
+        /// Send every event on this channel (1-16) instead of the file's own channels
+        #[arg(long)]
+        channel: Option<u8>,
+        /// Keep recording this long after the last event for release tails
+        #[arg(long, default_value_t = 2000)]
+        tail_ms: u64,
+        /// Output directory for the WAV file
+        #[arg(short, long, default_value = "./samples")]
+        output: String,
+    },
+    /// Sample a note range for every program (patch), one subfolder and instrument file per patch
+    SamplePatches {
+        /// Starting MIDI note number
+        #[arg(short, long)]
+        start: u8,
+        /// Ending MIDI note number
+        #[arg(short, long)]
+        end: u8,
+        /// Programs to sample, e.g. "0-15" or "0,4,9-12"
+        #[arg(short, long)]
+        programs: String,
+        /// Comma-separated velocities per note
+        #[arg(long, value_delimiter = ',', default_value = "100")]
+        velocities: Vec<u8>,
+        /// Settle time after each Program Change in milliseconds (default: profile value or 500)
+        #[arg(long)]
+        settle_ms: Option<u64>,
+        /// Velocity curve applied on send: linear, exp:N, log:N or custom:in=out,...
+        #[arg(long, default_value = "linear")]
+        velocity_curve: String,
+        /// Instrument format per patch: sfz or decentsampler
+        #[arg(short, long, default_value = "sfz")]
+        format: String,
+        /// Output directory (one subfolder per patch is created inside)
+        #[arg(short, long, default_value = "./samples")]
+        output: String,
+        /// Identify the synth first and put the patches in a folder named after it
+        #[arg(long)]
+        identify: bool,
+    },
+    /// Sample every part of a multitimbral module, one subfolder and instrument file per MIDI channel
+    SampleChannels {
+        /// Starting MIDI note number
+        #[arg(short, long)]
+        start: u8,
+        /// Ending MIDI note number
+        #[arg(short, long)]
+        end: u8,
+        /// MIDI channels to sample (1-16), e.g. "1-8" or "1,2,10"
+        #[arg(short, long)]
+        channels: String,
+        /// Comma-separated velocities per note
+        #[arg(long, value_delimiter = ',', default_value = "100")]
+        velocities: Vec<u8>,
+        /// Pause before each channel in milliseconds
+        #[arg(long, default_value_t = 500)]
+        settle_ms: u64,
+        /// Instrument format per channel: sfz or decentsampler
+        #[arg(short, long, default_value = "sfz")]
+        format: String,
+        /// Output directory (one subfolder per channel is created inside)
+        #[arg(short, long, default_value = "./samples")]
+        output: String,
+    },
+    /// Record the synth direct and through an amp/pedal chain in one pass, one subfolder and instrument file per input
+    SampleDual {
+        /// Starting MIDI note number
+        #[arg(short, long)]
+        start: u8,
+        /// Ending MIDI note number
+        #[arg(short, long)]
+        end: u8,
+        /// Interface inputs (1-based) carrying the direct signal, e.g. "1" or "1-2"
+        #[arg(long, default_value = "1")]
+        direct_inputs: String,
+        /// Interface inputs (1-based) carrying the processed signal
+        #[arg(long, default_value = "2")]
+        processed_inputs: String,
+        /// Filename suffix token for the direct takes
+        #[arg(long, default_value = "di")]
+        direct_name: String,
+        /// Filename suffix token for the processed takes
+        #[arg(long, default_value = "amp")]
+        processed_name: String,
+        /// Instrument name used in filenames
+        #[arg(short, long, default_value = "Synth")]
+        name: String,
+        /// Instrument format per input: sfz or decentsampler
+        #[arg(short, long, default_value = "sfz")]
+        format: String,
+        /// Output directory (one subfolder per input is created inside)
+        #[arg(short, long, default_value = "./samples")]
+        output: String,
+    },
+    /// Send SysEx (a .syx dump or hex bytes), optionally waiting for the synth's reply
+    Sysex {
+        /// .syx file to send (may contain several messages)
+        #[arg(short, long)]
+        file: Option<String>,
+        /// Hex bytes to send, e.g. "F0 7E 7F 06 01 F7"
+        #[arg(long)]
+        hex: Option<String>,
+        /// Delay between messages from a .syx file in milliseconds
+        #[arg(long, default_value_t = 50)]
+        gap_ms: u64,
+        /// MIDI input device index to wait for a SysEx reply on
+        #[arg(long)]
+        reply_input: Option<usize>,
+        /// Hex prefix the reply must start with (default: any SysEx)
+        #[arg(long, default_value = "F0")]
+        reply_prefix: String,
+        /// Reply timeout in milliseconds
+        #[arg(long, default_value_t = 2000)]
+        timeout_ms: u64,
+    },
+    /// Send a WAV into a hardware sampler over MIDI with the Sample Dump Standard (SDS)
+    #[command(after_help = "Examples:
+  batcherbird send-sample ./pads/Pad_C4_60_vel127.wav --sample-number 3
+  batcherbird send-sample bass.wav --bits 12 --device-id 1 --reply-input 0")]
+    SendSample {
+        /// WAV file to send (stereo is mixed to mono, smpl loops are kept)
+        file: String,
+        /// Sample slot on the receiving sampler
+        #[arg(long, default_value_t = 0)]
+        sample_number: u16,
+        /// Word size in bits (12 for S900/Mirage-class samplers)
+        #[arg(long, default_value_t = 16)]
+        bits: u8,
+        /// SysEx device ID of the sampler
+        #[arg(long, default_value_t = 0)]
+        device_id: u8,
+        /// MIDI input device index connected to the sampler's MIDI out, for a handshaked transfer
+        #[arg(long)]
+        reply_input: Option<usize>,
+    },
+    /// Measure a note's velocity response and suggest layer velocities with evenly spaced loudness
+    MeasureVelocity {
+        /// MIDI note number to probe
+        #[arg(short, long, default_value_t = 60)]
+        note: u8,
+        /// Number of velocity layers to place
+        #[arg(short, long, default_value_t = 4)]
+        layers: usize,
+        /// Comma-separated probe velocities
+        #[arg(long, value_delimiter = ',', default_value = "1,16,32,48,64,80,100,127")]
+        probes: Vec<u8>,
+    },
+    /// Identify the connected synth via MIDI Device Inquiry (Universal SysEx Identity Request)
+    Identify {
+        /// MIDI input device index the synth replies on (default: input matching the output name)
+        #[arg(short, long)]
+        input: Option<usize>,
+        /// Reply timeout in milliseconds
+        #[arg(long, default_value_t = 2000)]
+        timeout_ms: u64,
+    },
+    /// Merge several exported session folders into one instrument (later folders win on duplicates)
+    Merge {
+        /// Session folders to combine, oldest first
+        #[arg(required = true, num_args = 2..)]
+        inputs: Vec<String>,
+        /// Instrument format: sfz or decentsampler
+        #[arg(long, default_value = "sfz")]
+        format: String,
+        /// Instrument name used for the merged file names
+        #[arg(long, default_value = "Merged")]
+        name: String,
+        /// Output directory for the merged instrument
+        #[arg(short, long, default_value = "./merged")]
+        output: String,
+        /// Split stereo samples into _L/_R mono files for samplers without stereo support
+        #[arg(long)]
+        dual_mono: bool,
+        /// Fit the merged bank to a hardware sampler (e.g. mpc2000, s900) and write plain 16-bit WAVs
+        #[arg(long)]
+        target: Option<String>,
+        /// Word size of the instrument's WAVs: 16, 24 or 32f
+        #[arg(long, default_value = "24")]
+        bit_depth: String,
+        /// Also write 16-bit copies fitted to a hardware sampler into a subfolder in the same pass
+        #[arg(long)]
+        hardware_copy: Option<String>,
+    },
+    /// Match levels across exported sessions of the same instrument
+    #[command(after_help = "Examples:
+  batcherbird renormalize ./library --dry-run
+  batcherbird renormalize ./pads-2023 ./pads-2024 --rms --opcodes
+  batcherbird renormalize ./library --lufs --target-db -18")]
+    Renormalize {
+        /// Session folders, or one library folder containing session folders
+        #[arg(required = true)]
+        inputs: Vec<String>,
+        /// Target level in dBFS, or LUFS with --lufs (default: median of the session levels)
+        #[arg(long, allow_hyphen_values = true)]
+        target_db: Option<f32>,
+        /// Match RMS loudness instead of peaks
+        #[arg(long)]
+        rms: bool,
+        /// Match BS.1770 integrated loudness (LUFS) instead of peaks, limited by true peak
+        #[arg(long, conflicts_with = "rms")]
+        lufs: bool,
+        /// Set volume in the .sfz/.dspreset files instead of rewriting WAVs
+        #[arg(long)]
+        opcodes: bool,
+        /// Only show the planned gain per session
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Loop a region of a WAV with a temporary crossfade and fine-tune the points by ear
+    AuditionLoop {
+        /// WAV file to audition
+        file: String,
+        /// Loop start in samples
+        start: usize,
+        /// Loop end in samples
+        end: usize,
+        /// Crossfade length in milliseconds (applied to playback only)
+        #[arg(long, default_value = "10")]
+        crossfade_ms: f32,
+    },
+    /// Write a trimmed copy of a WAV with optional loop points, leaving the original untouched
+    EditSample {
+        /// WAV file to edit
+        file: String,
+        /// First sample frame to keep
+        #[arg(long, default_value = "0")]
+        start: usize,
+        /// Frame to stop at (default: end of file)
+        #[arg(long)]
+        end: Option<usize>,
+        /// Loop start frame in the original file
+        #[arg(long, requires = "loop_end")]
+        loop_start: Option<usize>,
+        /// Loop end frame in the original file
+        #[arg(long, requires = "loop_start")]
+        loop_end: Option<usize>,
+        /// Output file (default: <name>_edit.wav next to the original)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Cut single-cycle waveforms from a sustained sample into a Serum/Vital wavetable WAV
+    #[command(after_help = "Examples:
+  batcherbird wavetable ./pads/Pad_C3_48_vel127.wav
+  batcherbird wavetable ./pads/Pad_C3_48_vel127.wav --frames 64 -o ./Tables/Pad.wav")]
+    Wavetable {
+        /// Sustained WAV to take the cycles from
+        file: String,
+        /// Cycles taken at evenly spaced points through the sound (1 = single-cycle waveform)
+        #[arg(long, default_value_t = 1)]
+        frames: usize,
+        /// Samples per frame
+        #[arg(long, default_value_t = 2048)]
+        frame_size: usize,
+        /// Output file (default: <name>_wavetable.wav next to the original)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Apply saved edit files to raw takes, writing finished samples to a new folder
+    Render {
+        /// Folder with raw takes and their .edit.json files
+        input: String,
+        /// Output folder for rendered samples (default: <input>/Rendered)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// List past sessions recorded with --catalog
+    #[command(after_help = "Examples:
+  batcherbird sessions --device minifuse --limit 5
+  batcherbird sessions --note 60 --issues
+  batcherbird sessions --show 3f2a9c1e")]
+    Sessions {
+        /// Sessions whose name contains this
+        #[arg(long)]
+        name: Option<String>,
+        /// Sessions recorded with a MIDI or audio device whose name contains this
+        #[arg(long)]
+        device: Option<String>,
+        /// Sessions that sampled this note
+        #[arg(long)]
+        note: Option<u8>,
+        /// Only sessions with QA issues
+        #[arg(long)]
+        issues: bool,
+        /// Newest sessions to list
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        /// List the samples of the session with this id (or id prefix)
+        #[arg(long)]
+        show: Option<String>,
+    },
+    /// Zip an exported instrument (WAVs, instrument file, README, license) for distribution
+    #[command(after_help = "Examples:
+  batcherbird package ./pads
+  batcherbird package ./pads --format dslibrary --license \"CC BY 4.0\"")]
+    Package {
+        /// Exported instrument folder
+        input: String,
+        /// Archive type: zip or dslibrary (Decent Sampler library)
+        #[arg(short, long, default_value = "zip")]
+        format: String,
+        /// Library name inside the archive (default: the folder name)
+        #[arg(short, long)]
+        name: Option<String>,
+        /// License text, or a file to read it from, included as LICENSE.txt
+        #[arg(long)]
+        license: Option<String>,
+        /// Archive path (default: next to the folder)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Check an exported folder for wrong notes, clipping, dropouts, DC offset and short takes
+    Qa {
+        /// Exported session folder
+        input: String,
+        /// Skip the pitch check (drum kits, noise patches)
+        #[arg(long)]
+        no_pitch: bool,
+    },
+    /// Record chosen notes again into an existing session folder, replacing or versioning the earlier takes
+    #[command(after_help = "Examples:
+  batcherbird rerecord ./pads --notes 60:127,62:100
+  batcherbird rerecord ./pads --from-qa --version")]
+    Rerecord {
+        /// Exported session folder
+        input: String,
+        /// Takes to record again as note:velocity pairs (e.g. 60:127,62:100)
+        #[arg(long)]
+        notes: Option<String>,
+        /// Record again the suspects listed in the folder's QA report
+        #[arg(long)]
+        from_qa: bool,
+        /// Keep earlier takes in <input>/Takes instead of overwriting them
+        #[arg(long)]
+        version: bool,
+        /// Instrument format, used when new notes are added: sfz, decentsampler or wav
+        #[arg(long, default_value = "sfz")]
+        format: String,
+        /// Normalize each new take's peak to 95%
+        #[arg(long)]
+        normalize: bool,
+    },
+    /// Re-run detection, trimming, normalization and fades over an exported folder with new settings
+    #[command(after_help = "Examples:
+  batcherbird reprocess ./pads --threshold-db -50 --normalize
+  batcherbird reprocess ./pads --no-detection --fade-out-ms 50 --format decentsampler
+  batcherbird reprocess ./strings --loops
+  batcherbird reprocess ./mixed --classify
+  batcherbird reprocess ./keys --align-onsets-ms 5
+  batcherbird reprocess ./pads --normalize-lufs -18
+  batcherbird reprocess ./bass --remove-hum")]
+    Reprocess {
+        /// Folder of WAVs to reprocess (left untouched)
+        input: String,
+        /// Output folder (default: <input>/Reprocessed)
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Instrument format: sfz, decentsampler or wav (no instrument file)
+        #[arg(long, default_value = "sfz")]
+        format: String,
+        /// Instrument name used for the instrument file
+        #[arg(long, default_value = "Reprocessed")]
+        name: String,
+        /// Silence threshold for detection in dB (default: profile or standard preset)
+        #[arg(long, allow_hyphen_values = true)]
+        threshold_db: Option<f32>,
+        /// Keep the full length instead of trimming to the detected sound
+        #[arg(long)]
+        no_detection: bool,
+        /// Normalize each sample's peak to 95%
+        #[arg(long)]
+        normalize: bool,
+        #[arg(long, default_value_t = 0.0)]
+        fade_in_ms: f32,
+        #[arg(long, default_value_t = 10.0)]
+        fade_out_ms: f32,
+        /// Find loop points in sustained samples and map them in the instrument
+        #[arg(long)]
+        loops: bool,
+        /// Classify each sample as sustained or percussive from its envelope: trim with the
+        /// matching preset and loop sustained samples, play percussive ones one-shot
+        #[arg(long)]
+        classify: bool,
+        /// Trim every sample to this much audio before its attack (ms), for even keyswitching
+        #[arg(long)]
+        align_onsets_ms: Option<f32>,
+        /// Normalize each sample to this integrated loudness (LUFS), true peak at most -1 dBTP
+        #[arg(long, allow_hyphen_values = true)]
+        normalize_lufs: Option<f32>,
+        /// Notch out 50/60 Hz mains hum measured in the samples' noise floor
+        #[arg(long)]
+        remove_hum: bool,
+        /// Fit the folder to a hardware sampler (e.g. mpc2000, s900) and write plain 16-bit WAVs
+        #[arg(long)]
+        target: Option<String>,
+    },
+    /// Map a folder tree of WAV/AIFF samples Batcherbird didn't record and write an instrument
+    /// per folder, using file names, smpl/inst chunks or the pitch detector
+    #[command(after_help = "Examples:
+  batcherbird import ./OldLibrary
+  batcherbird import ./Strings --format decentsampler --pattern \"{synth} {note_name} {articulation}.wav\"")]
+    Import {
+        /// Folder of samples; every folder below it holding audio becomes its own instrument
+        input: String,
+        /// Output folder (default: <input>/Imported)
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Instrument format: sfz, decentsampler or another registered instrument format
+        #[arg(long, default_value = "sfz")]
+        format: String,
+        /// Naming pattern to read file names with, tried before the built-in ones (repeatable)
+        #[arg(long = "pattern")]
+        patterns: Vec<String>,
+        /// Velocity of files whose name and chunks don't give one
+        #[arg(long, default_value_t = 127)]
+        velocity: u8,
+        /// Leave out files nothing else maps instead of finding their root note by pitch
+        #[arg(long)]
+        no_pitch: bool,
+    },
+    /// Render a PNG contact sheet with one waveform thumbnail per sample in an exported folder
+    #[cfg(feature = "image")]
+    Preview {
+        /// Exported session folder (the sheet is written into it)
+        input: String,
+        /// Thumbnails per row
+        #[arg(long, default_value_t = 8)]
+        columns: u32,
+    },
+    /// Generate a demo project (simulated samples, instruments, manifest, report) without hardware
+    Demo {
+        /// Output directory for the demo project
+        #[arg(short, long, default_value = "./batcherbird-demo")]
+        output: String,
+    },
+    /// Print a shell completion script
+    #[command(after_help = "Examples:
+  batcherbird completions bash > /etc/bash_completion.d/batcherbird
+  batcherbird completions zsh > ~/.zfunc/_batcherbird
+  batcherbird completions fish > ~/.config/fish/completions/batcherbird.fish
+  batcherbird completions powershell >> $PROFILE")]
+    Completions {
+        /// Shell to generate completions for
+        #[arg(value_enum)]
+        shell: completions::Shell,
+    },
+    /// Print or write the man page
+    Man {
+        /// Write the page to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
     },
 }
 
+impl Commands {
+    /// Output folder of a sampling command, and whether its notes are triggered over MIDI
+    /// (live and assisted sessions are played by hand)
+    fn preflight_target(&self) -> Option<(&str, bool)> {
+        match self {
+            Commands::SampleExport { output, .. }
+            | Commands::SampleSweep { output, .. }
+            | Commands::SampleTakes { output, .. }
+            | Commands::SampleLongTake { output, .. }
+            | Commands::SampleDrums { output, .. }
+            | Commands::SampleKit { output, .. }
+            | Commands::SampleLegato { output, .. }
+            | Commands::SampleReleases { output, .. }
+            | Commands::SampleMidiFile { output, .. }
+            | Commands::SamplePatches { output, .. }
+            | Commands::SampleChannels { output, .. }
+            | Commands::SampleDual { output, .. } => Some((output, true)),
+            Commands::SampleLive { output, .. } | Commands::SampleAssisted { output, .. } => Some((output, false)),
+            _ => None,
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    tracing_subscriber::fmt()
-        .with_max_level(Level::INFO)
-        .init();
-
     let cli = Cli::parse();
+    
+    // Generated scripts go to stdout, so handle them before logging starts
+    match &cli.command {
+        Commands::Completions { shell } => {
+            print!("{}", completions::generate_completions(*shell, &mut Cli::command()));
+            return Ok(());
+        }
+        Commands::Man { output } => {
+            let page = completions::generate_man_page(&mut Cli::command());
+            match output {
+                Some(path) => {
+                    std::fs::write(path, page)?;
+                    println!("📄 Man page written to {}", path);
+                }
+                None => print!("{}", page),
+            }
+            return Ok(());
+        }
+        _ => {}
+    }
+    
+    logging::init(Level::INFO);
+
+    let options = match ExportOptions::from_cli(&cli) {
+        Ok(options) => options,
+        Err(e) => {
+            println!("❌ {}", e);
+            return Ok(());
+        }
+    };
+    let watchdog = (cli.max_note_secs.is_some() || cli.max_batch_secs.is_some() || cli.abort_on_timeout
+        || cli.max_clock_drift_ppm.is_some() || cli.resync_on_drift).then_some(WatchdogConfig {
+        max_note_secs: cli.max_note_secs,
+        max_batch_secs: cli.max_batch_secs,
+        abort_on_timeout: cli.abort_on_timeout,
+        max_clock_drift_ppm: cli.max_clock_drift_ppm,
+        resync_on_drift: cli.resync_on_drift,
+    });
+    let hooks = HookConfig {
+        pre_note: cli.pre_note_hook,
+        post_note: cli.post_note_hook,
+        post_export: cli.post_export_hook,
+        timeout_secs: None,
+        abort_on_failure: cli.abort_on_hook_failure,
+    };
+    let hooks = (!hooks.is_empty()).then_some(hooks);
+    let profile = load_profile(cli.profile.as_deref(), cli.clock_bpm, watchdog, hooks)?;
+    let profile = profile.as_ref();
+    if let Err(e) = AudioManager::set_preferred_host(cli.audio_host.clone()) {
+        println!("❌ {}", e);
+        return Ok(());
+    }
+    if let Some(value) = &cli.second_input {
+        let Some(second) = SecondaryInputConfig::parse(value) else {
+            println!("❌ Invalid second input '{}'. Use DEVICE or DEVICE:INPUT, e.g. \"USB Audio CODEC:2\".", value);
+            return Ok(());
+        };
+        AudioManager::set_secondary_input(Some(second));
+    }
+
+    if let (Some((output, midi)), false) = (cli.command.preflight_target(), cli.skip_preflight) {
+        preflight(output, midi, profile).await?;
+    }
 
     match cli.command {
         Commands::TestMidi => {
@@ -74,21 +999,177 @@ async fn main() -> anyhow::Result<()> {
             info!("Listing audio devices...");
             list_audio_devices().await?;
         }
-        Commands::MonitorMidi => {
+        Commands::MonitorMidi { input, filter, channel, show_clock, duration, output } => {
             info!("Starting MIDI monitor...");
-            monitor_midi().await?;
+            monitor_midi(&input, filter.as_deref(), channel, show_clock, duration, output.as_deref()).await?;
+        }
+        Commands::ListProfiles => {
+            info!("Listing synth profiles...");
+            list_profiles();
+        }
+        Commands::Plan { start, end, velocities, round_robins, format, sample_rate, channels, takes, naming_pattern } => {
+            info!("Planning notes {} to {}", start, end);
+            let plan = PlanOptions { start, end, velocities, round_robins, format, sample_rate, channels, list_takes: takes, naming_pattern };
+            plan_batch(plan, profile)?;
         }
         Commands::SampleNote { note } => {
             info!("Sampling single note: {}", note);
-            sample_single_note(note)?;
+            sample_single_note(note, profile).await?;
         }
-        Commands::SampleRange { start, end } => {
+        Commands::SampleRange { start, end, max_drift_cents } => {
             info!("Sampling note range: {} to {}", start, end);
-            sample_note_range(start, end)?;
+            sample_note_range(start, end, max_drift_cents, profile).await?;
         }
-        Commands::SampleExport { note, output } => {
+        Commands::SampleExport { note, output, max_drift_cents, sidecar } => {
             info!("Sampling and exporting note: {} to {}", note, output);
-            sample_and_export(note, output)?;
+            sample_and_export(note, output, max_drift_cents, sidecar, &options, profile).await?;
+        }
+        Commands::SampleSweep { start, end, cc, values, settle_ms, output } => {
+            info!("Sampling CC{} sweep over notes {} to {}", cc, start, end);
+            let cc_sweep = SweepOptions { start, end, cc, values, settle_ms, output_dir: output };
+            sample_cc_sweep(cc_sweep, &options, profile).await?;
+        }
+        Commands::SampleLongTake { start, end, velocities, search_ms, format, output } => {
+            info!("Recording {} to {} as one long take", start, end);
+            let take = LongTakeOptions { start, end, velocities, search_ms, format, output_dir: output };
+            sample_long_take(take, &options, profile).await?;
+        }
+        Commands::SampleTakes { start, end, takes, keep_all, format, output } => {
+            info!("Recording {} takes per note over {} to {}", takes, start, end);
+            let run = TakesOptions { start, end, takes, keep_all, format, output_dir: output };
+            sample_takes(run, &options, profile).await?;
+        }
+        Commands::SampleDrums { start, end, round_robins, velocity, tolerance_db, retakes, drum_names, output } => {
+            info!("Sampling drum hits {} to {} with {} round robins", start, end, round_robins);
+            let drums = DrumOptions { start, end, round_robins, velocity, tolerance_db, retakes, drum_names, output_dir: output };
+            sample_drums(drums, &options, profile).await?;
+        }
+        Commands::SampleKit { notes, velocity, drum_names, format, output, target, bit_depth, hardware_copy } => {
+            info!("Sampling drum kit to {}", output);
+            let kit = KitOptions { notes, velocity, drum_names, format, output_dir: output, target, bit_depth, hardware_copy };
+            sample_kit(kit, &options, profile).await?;
+        }
+        Commands::SampleLive { input, channel, pre_roll_ms, tail_ms, takes, format, output } => {
+            info!("Recording live performance from MIDI input {}", input);
+            let live = LiveOptions { input, channel, pre_roll_ms, tail_ms, takes, format, output_dir: output };
+            sample_live(live, &options, profile)?;
+        }
+        Commands::SampleAssisted { start, end, velocities, input, triggers, format, output } => {
+            info!("Assisted sampling {} to {}", start, end);
+            let assisted = AssistedOptions { start, end, velocities, input, triggers, format, output_dir: output };
+            sample_assisted(assisted, &options, profile)?;
+        }
+        Commands::SampleLegato { start, end, intervals, hold_ms, overlap_ms, transitions_only, output } => {
+            info!("Sampling legato transitions {} to {} with intervals {:?}", start, end, intervals);
+            let legato = LegatoOptions { start, end, intervals, hold_ms, overlap_ms, transitions_only, output_dir: output };
+            sample_legato(legato, &options, profile).await?;
+        }
+        Commands::SampleReleases { start, end, pre_buffer_ms, with_sustains, format, output } => {
+            info!("Sampling release tails {} to {}", start, end);
+            let releases = ReleaseOptions { start, end, pre_buffer_ms, with_sustains, format, output_dir: output };
+            sample_releases(releases, &options, profile).await?;
+        }
+        Commands::SampleMidiFile { file, channel, tail_ms, output } => {
+            info!("Recording MIDI file playback: {}", file);
+            sample_midi_file(&file, channel, tail_ms, output, &options, profile).await?;
+        }
+        Commands::Merge { inputs, format, name, output, dual_mono, target, bit_depth, hardware_copy } => {
+            info!("Merging {} sessions into {}", inputs.len(), output);
+            let merge = MergeOptions { inputs, format, name, output_dir: output, dual_mono, target, bit_depth, hardware_copy };
+            merge_sessions(merge, &options)?;
+        }
+        Commands::Renormalize { inputs, target_db, rms, lufs, opcodes, dry_run } => {
+            info!("Renormalizing {} paths", inputs.len());
+            renormalize_library(&inputs, target_db, rms, lufs, opcodes, dry_run)?;
+        }
+        Commands::SampleChannels { start, end, channels, velocities, settle_ms, format, output } => {
+            info!("Sampling channels {} over notes {} to {}", channels, start, end);
+            let run = ChannelOptions { start, end, channels, velocities, settle_ms, format, output_dir: output };
+            sample_channels(run, &options, profile).await?;
+        }
+        Commands::SampleDual { start, end, direct_inputs, processed_inputs, direct_name, processed_name, name, format, output } => {
+            info!("Dual-input sampling notes {} to {}", start, end);
+            let dual = DualOptions { start, end, direct_inputs, processed_inputs, direct_name, processed_name, name, format, output_dir: output };
+            sample_dual(dual, &options, profile).await?;
+        }
+        Commands::Sysex { file, hex, gap_ms, reply_input, reply_prefix, timeout_ms } => {
+            info!("Sending SysEx...");
+            send_sysex(file, hex, gap_ms, reply_input, &reply_prefix, timeout_ms)?;
+        }
+        Commands::SendSample { file, sample_number, bits, device_id, reply_input } => {
+            info!("Sending {} with SDS", file);
+            send_sample_dump(&file, sample_number, bits, device_id, reply_input)?;
+        }
+        Commands::MeasureVelocity { note, layers, probes } => {
+            info!("Measuring velocity response of note {}", note);
+            measure_velocity(note, layers, probes, profile).await?;
+        }
+        Commands::Identify { input, timeout_ms } => {
+            info!("Identifying synth...");
+            identify_synth(input, timeout_ms)?;
+        }
+        Commands::AuditionLoop { file, start, end, crossfade_ms } => {
+            info!("Auditioning loop {}..{} of {}", start, end, file);
+            audition_loop(&file, start, end, crossfade_ms)?;
+        }
+        Commands::EditSample { file, start, end, loop_start, loop_end, output } => {
+            info!("Editing {}", file);
+            edit_sample(&file, start, end, loop_start.zip(loop_end), output)?;
+        }
+        Commands::Wavetable { file, frames, frame_size, output } => {
+            info!("Extracting wavetable from {}", file);
+            extract_wavetable(&file, frames, frame_size, output)?;
+        }
+        Commands::Render { input, output } => {
+            info!("Rendering edits in {}", input);
+            render_edits(&input, output)?;
+        }
+        Commands::Sessions { name, device, note, issues, limit, show } => {
+            let query = CatalogQuery { name, device, note, since: None, with_issues: issues, limit: Some(limit) };
+            list_sessions(&query, show.as_deref())?;
+        }
+        Commands::Package { input, format, name, license, output } => {
+            info!("Packaging {}", input);
+            package_instrument(&input, &format, name, license, output)?;
+        }
+        Commands::Qa { input, no_pitch } => {
+            info!("Checking {}", input);
+            qa_check(&input, no_pitch)?;
+        }
+        Commands::Rerecord { input, notes, from_qa, version, format, normalize } => {
+            info!("Re-recording takes in {}", input);
+            rerecord(&input, notes.as_deref(), from_qa, version, &format, normalize, profile)?;
+        }
+        Commands::Reprocess { input, output, format, name, threshold_db, no_detection, normalize, fade_in_ms, fade_out_ms, loops, classify, align_onsets_ms, normalize_lufs, remove_hum, target } => {
+            info!("Reprocessing {}", input);
+            let mut detection_config = profile_detection_config(profile);
+            if let Some(threshold_db) = threshold_db {
+                detection_config.threshold_db = threshold_db;
+            }
+            let detection = (!no_detection).then_some(detection_config);
+            let run = ReprocessOptions {
+                input, output, format, name, detection, normalize, fade_in_ms, fade_out_ms, loops, classify, align_onsets_ms, normalize_lufs, remove_hum, target,
+            };
+            reprocess(run, &options)?;
+        }
+        Commands::Import { input, output, format, patterns, velocity, no_pitch } => {
+            info!("Importing {}", input);
+            import_samples(&input, output, &format, patterns, velocity, no_pitch, &options)?;
+        }
+        #[cfg(feature = "image")]
+        Commands::Preview { input, columns } => {
+            info!("Rendering preview of {}", input);
+            render_preview(&input, columns);
+        }
+        Commands::Demo { output } => {
+            info!("Generating demo project in {}", output);
+            generate_demo(output)?;
+        }
+        Commands::Completions { .. } | Commands::Man { .. } => {} // Handled before logging starts
+        Commands::SamplePatches { start, end, programs, velocities, settle_ms, velocity_curve, format, output, identify } => {
+            info!("Sampling programs {} over notes {} to {}", programs, start, end);
+            let patches = PatchOptions { start, end, programs, velocities, settle_ms, velocity_curve, format, output_dir: output, identify };
+            sample_patches(patches, &options, profile).await?;
         }
     }
 
@@ -124,6 +1205,7 @@ async fn list_midi_devices() -> anyhow::Result<()> {
 async fn list_audio_devices() -> anyhow::Result<()> {
     let audio_manager = AudioManager::new()?;
     
+    println!("Audio Host: {} (available: {})\n", audio_manager.host_name(), AudioManager::list_hosts().join(", "));
     println!("Audio Input Devices:");
     let input_devices = audio_manager.list_input_devices()?;
     if input_devices.is_empty() {
@@ -131,6 +1213,11 @@ async fn list_audio_devices() -> anyhow::Result<()> {
     } else {
         for (i, device) in input_devices.iter().enumerate() {
             println!("  {}: {}", i, device);
+            if let Ok(caps) = audio_manager.describe_device(i) {
+                let rates: Vec<String> = caps.sample_rates.iter().map(|rate| format!("{}", *rate as f32 / 1000.0)).collect();
+                let channels: Vec<String> = caps.channel_counts.iter().map(|count| count.to_string()).collect();
+                println!("     {} kHz · {} ch · {}", rates.join("/"), channels.join("/"), caps.sample_formats.join(", "));
+            }
         }
     }
     
@@ -157,7 +1244,7 @@ async fn test_midi() -> anyhow::Result<()> {
     let output_devices = midi_manager.list_output_devices()?;
     
     if output_devices.is_empty() {
-        println!("❌ No MIDI output devices found. Connect a MIDI device or enable IAC Driver.");
+        println!("❌ No MIDI output devices found. {}", MIDI_SETUP_HINT);
         return Ok(());
     }
     
@@ -194,17 +1281,42 @@ async fn test_midi() -> anyhow::Result<()> {
     Ok(())
 }
 
-async fn monitor_midi() -> anyhow::Result<()> {
+async fn monitor_midi(
+    input: &str,
+    filter: Option<&str>,
+    channel: Option<u8>,
+    show_clock: bool,
+    duration: Option<f64>,
+    output: Option<&std::path::Path>,
+) -> anyhow::Result<()> {
     use batcherbird_core::midi::MidiManager;
+    use batcherbird_core::midi_monitor::{MidiCapture, MonitorFilter};
 
     println!("MIDI Monitor - Real-time MIDI message display");
     
+    if channel.is_some_and(|channel| !(1..=16).contains(&channel)) {
+        println!("❌ Invalid MIDI channel: {}. Use 1-16.", channel.unwrap_or_default());
+        return Ok(());
+    }
+    if duration.is_some_and(|seconds| seconds <= 0.0 || !seconds.is_finite()) {
+        println!("❌ Invalid duration: {}. Use a number of seconds above 0.", duration.unwrap_or_default());
+        return Ok(());
+    }
+    let kinds = match filter.map(MonitorFilter::parse_kinds).transpose() {
+        Ok(kinds) => kinds.unwrap_or_default(),
+        Err(e) => {
+            println!("❌ {}", e);
+            return Ok(());
+        }
+    };
+    let filter = MonitorFilter { kinds, channel: channel.map(|channel| channel - 1), pass_timing: show_clock };
+    
     let mut midi_manager = MidiManager::new()?;
     let input_devices = midi_manager.list_input_devices()?;
     
     if input_devices.is_empty() {
         println!("❌ No MIDI input devices found.");
-        println!("   Connect a MIDI device or enable IAC Driver in Audio MIDI Setup");
+        println!("   {}", MIDI_SETUP_HINT);
         return Ok(());
     }
     
@@ -212,18 +1324,54 @@ async fn monitor_midi() -> anyhow::Result<()> {
     for (i, device) in input_devices.iter().enumerate() {
         println!("  {}: {}", i, device);
     }
+    let input = match input.parse::<usize>() {
+        Ok(index) if index < input_devices.len() => index,
+        Ok(index) => {
+            println!("❌ No MIDI input device {}. Found {} input devices.", index, input_devices.len());
+            return Ok(());
+        }
+        Err(_) => match midi_manager.input_index_named(input) {
+            Ok(index) => index,
+            Err(e) => {
+                println!("❌ {}", e);
+                return Ok(());
+            }
+        },
+    };
     
-    // Use first available device
-    let device_index = 0;
-    println!("\n🎧 Monitoring device {}: {}", device_index, input_devices[device_index]);
-    println!("📡 Listening for MIDI messages... (Press Ctrl+C to stop)\n");
+    println!("\n🎧 Monitoring device {}: {}", input, input_devices[input]);
+    match duration {
+        Some(seconds) => println!("📡 Listening for MIDI messages for {}s... (Press Ctrl+C to stop early)\n", seconds),
+        None => println!("📡 Listening for MIDI messages... (Press Ctrl+C to stop)\n"),
+    }
     
-    let _conn = midi_manager.connect_input(device_index)?;
+    let (_conn, messages) = midi_manager.connect_input_monitor(input, filter)?;
+    let mut capture = MidiCapture::new(Some(input_devices[input].clone()));
+    let deadline = duration.map(|seconds| tokio::time::Instant::now() + std::time::Duration::from_secs_f64(seconds));
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
     
-    // Keep the connection alive
     loop {
-        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        tokio::select! {
+            _ = &mut ctrl_c => break,
+            _ = tokio::time::sleep(std::time::Duration::from_millis(20)) => {}
+        }
+        for message in messages.try_iter() {
+            println!("[{:>10.3}s] {}", message.time_ms / 1000.0, message.description);
+            capture.push(message);
+        }
+        if deadline.is_some_and(|deadline| tokio::time::Instant::now() >= deadline) {
+            break;
+        }
     }
+    
+    println!("\n⏹️ Monitor stopped after {} messages", capture.messages.len());
+    if let Some(path) = output {
+        capture.save(path)?;
+        println!("💾 Saved to {}", path.display());
+    }
+    
+    Ok(())
 }
 
 async fn test_audio() -> anyhow::Result<()> {
@@ -274,8 +1422,8 @@ async fn test_audio() -> anyhow::Result<()> {
     Ok(())
 }
 
-fn sample_single_note(note: u8) -> anyhow::Result<()> {
-    use batcherbird_core::{midi::MidiManager, sampler::{SamplingEngine, SamplingConfig}};
+async fn sample_single_note(note: u8, profile: Option<&SynthProfile>) -> anyhow::Result<()> {
+    use batcherbird_core::{midi::MidiManager, sampler::SamplingEngine};
 
     if note > 127 {
         println!("❌ Invalid note number: {}. Must be 0-127.", note);
@@ -289,7 +1437,7 @@ fn sample_single_note(note: u8) -> anyhow::Result<()> {
     let output_devices = midi_manager.list_output_devices()?;
     
     if output_devices.is_empty() {
-        println!("❌ No MIDI output devices found. Connect a MIDI device or enable IAC Driver.");
+        println!("❌ No MIDI output devices found. {}", MIDI_SETUP_HINT);
         return Ok(());
     }
     
@@ -300,18 +1448,18 @@ fn sample_single_note(note: u8) -> anyhow::Result<()> {
     println!("🎹 Using MIDI device: {}", output_devices[device_index]);
     let mut midi_conn = midi_manager.connect_output(device_index)?;
     
-    // Create sampling engine with default config
-    let config = SamplingConfig::default();
+    // Create sampling engine with default config (adjusted by the synth profile, if any)
+    let config = profile_sampling_config(profile);
     let engine = SamplingEngine::new(config)?;
     
     println!("🎤 Ready to sample note {} - ensure audio is connected!", note);
     println!("   Note: Connect synthesizer output to audio input");
     
     // Sample the note
-    let sample = engine.sample_single_note_blocking(&mut midi_conn, note)?;
+    let sample = engine.sample_single_note_async(&mut midi_conn, note).await?;
     
     // Analyze the sample
-    let (rms, rms_db, peak_db) = batcherbird_core::audio::AudioManager::analyze_audio_samples(&sample.audio_data);
+    let (_, rms_db, peak_db) = batcherbird_core::audio::AudioManager::analyze_audio_samples(&sample.audio_data);
     
     println!("\n📊 Sample Analysis:");
     println!("   Note: {} ({})", sample.note, sample_note_name(sample.note));
@@ -333,13 +1481,14 @@ fn sample_single_note(note: u8) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn sample_note_range(start: u8, end: u8) -> anyhow::Result<()> {
-    use batcherbird_core::{midi::MidiManager, sampler::{SamplingEngine, SamplingConfig}};
+async fn sample_note_range(start: u8, end: u8, max_drift_cents: f32, profile: Option<&SynthProfile>) -> anyhow::Result<()> {
+    use batcherbird_core::{midi::MidiManager, sampler::SamplingEngine};
 
     if start > 127 || end > 127 || start > end {
         println!("❌ Invalid note range: {}-{}. Notes must be 0-127 and start <= end.", start, end);
         return Ok(());
     }
+    warn_outside_profile_range(profile, start, end);
 
     let note_count = end - start + 1;
     println!("🎹 Batch sampling {} notes ({} to {})...", note_count, start, end);
@@ -349,7 +1498,7 @@ fn sample_note_range(start: u8, end: u8) -> anyhow::Result<()> {
     let output_devices = midi_manager.list_output_devices()?;
     
     if output_devices.is_empty() {
-        println!("❌ No MIDI output devices found. Connect a MIDI device or enable IAC Driver.");
+        println!("❌ No MIDI output devices found. {}", MIDI_SETUP_HINT);
         return Ok(());
     }
     
@@ -361,7 +1510,7 @@ fn sample_note_range(start: u8, end: u8) -> anyhow::Result<()> {
     let mut midi_conn = midi_manager.connect_output(device_index)?;
     
     // Create sampling engine
-    let config = SamplingConfig::default();
+    let config = profile_sampling_config(profile);
     let engine = SamplingEngine::new(config)?;
     
     println!("🎤 Ready to sample {} notes - ensure audio is connected!", note_count);
@@ -370,7 +1519,12 @@ fn sample_note_range(start: u8, end: u8) -> anyhow::Result<()> {
     );
     
     // Sample all notes
-    let samples = engine.sample_note_range_blocking(&mut midi_conn, start, end)?;
+    let mut samples = engine.sample_note_range_async(&mut midi_conn, start, end).await?;
+    let drift_limit = (max_drift_cents > 0.0).then_some(max_drift_cents);
+    for sample in &mut samples {
+        sample.track_pitch_drift(drift_limit);
+        sample.verify_played_note();
+    }
     
     // Analyze results
     println!("\n📊 Batch Sampling Results:");
@@ -392,6 +1546,14 @@ fn sample_note_range(start: u8, end: u8) -> anyhow::Result<()> {
     println!("   Successful captures: {}/{}", successful_samples, samples.len());
     println!("   Highest peak level: {:.1} dB", total_peak);
     
+    let retakes: Vec<String> = samples.iter()
+        .filter(|sample| sample.metadata.retake_reason.is_some())
+        .map(|sample| sample_note_name(sample.note))
+        .collect();
+    if !retakes.is_empty() {
+        println!("   🔁 Retake (pitch drift): {}", retakes.join(", "));
+    }
+    
     if successful_samples == samples.len() {
         println!("✅ All samples captured successfully!");
     } else {
@@ -401,10 +1563,10 @@ fn sample_note_range(start: u8, end: u8) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn sample_and_export(note: u8, output_dir: String) -> anyhow::Result<()> {
+async fn sample_and_export(note: u8, output_dir: String, max_drift_cents: f32, sidecar: bool, options: &ExportOptions, profile: Option<&SynthProfile>) -> anyhow::Result<()> {
     use batcherbird_core::{
         midi::MidiManager, 
-        sampler::{SamplingEngine, SamplingConfig},
+        sampler::SamplingEngine,
         export::{SampleExporter, ExportConfig, AudioFormat}
     };
     use std::path::PathBuf;
@@ -421,7 +1583,7 @@ fn sample_and_export(note: u8, output_dir: String) -> anyhow::Result<()> {
     let output_devices = midi_manager.list_output_devices()?;
     
     if output_devices.is_empty() {
-        println!("❌ No MIDI output devices found. Connect a MIDI device or enable IAC Driver.");
+        println!("❌ No MIDI output devices found. {}", MIDI_SETUP_HINT);
         return Ok(());
     }
     
@@ -433,7 +1595,7 @@ fn sample_and_export(note: u8, output_dir: String) -> anyhow::Result<()> {
     let mut midi_conn = midi_manager.connect_output(device_index)?;
     
     // Create sampling engine
-    let sampling_config = SamplingConfig::default();
+    let sampling_config = profile_sampling_config(profile);
     let engine = SamplingEngine::new(sampling_config)?;
     
     // Create export config
@@ -445,9 +1607,15 @@ fn sample_and_export(note: u8, output_dir: String) -> anyhow::Result<()> {
         fade_in_ms: 0.0,
         fade_out_ms: 10.0,
         apply_detection: true,
-        detection_config: Default::default(),
+        detection_config: profile_detection_config(profile),
         creator_name: None,
         instrument_description: None,
+        drum_map: profile_drum_map(profile),
+        sidecar_edits: sidecar,
+        hooks: profile_hooks(profile),
+        catalog: catalog_config(options, profile),
+        naming: naming_context(profile),
+        ..base_export_config(options)
     };
     
     let exporter = SampleExporter::new(export_config)?;
@@ -456,10 +1624,12 @@ fn sample_and_export(note: u8, output_dir: String) -> anyhow::Result<()> {
     println!("{}", exporter.get_export_info());
     
     // Sample the note
-    let sample = engine.sample_single_note_blocking(&mut midi_conn, note)?;
+    let mut sample = engine.sample_single_note_async(&mut midi_conn, note).await?;
     
     // Analyze the sample
     let (_, rms_db, peak_db) = batcherbird_core::audio::AudioManager::analyze_audio_samples(&sample.audio_data);
+    let drift = sample.track_pitch_drift((max_drift_cents > 0.0).then_some(max_drift_cents));
+    let played = sample.verify_played_note();
     
     println!("\n📊 Sample Analysis:");
     println!("   Note: {} ({})", sample.note, sample_note_name(sample.note));
@@ -469,6 +1639,10 @@ fn sample_and_export(note: u8, output_dir: String) -> anyhow::Result<()> {
     println!("   Channels: {}", sample.channels);
     println!("   RMS level: {:.1} dB", rms_db);
     println!("   Peak level: {:.1} dB", peak_db);
+    println!("   Pitch drift: {:.1} cents", drift);
+    if let Some(played) = played {
+        println!("   Detected pitch: {} ({:.1} Hz, {:+.0} cents)", sample_note_name(played.note), played.frequency_hz, played.cents);
+    }
     
     // Export the sample
     let exported_file = exporter.export_sample(&sample)?;
@@ -486,6 +1660,2337 @@ fn sample_and_export(note: u8, output_dir: String) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// The notes and CC values sample-sweep records, from its flags
+struct SweepOptions {
+    start: u8,
+    end: u8,
+    cc: u8,
+    /// One layer per value, sent before each pass over the notes
+    values: Vec<u8>,
+    settle_ms: u64,
+    output_dir: String,
+}
+
+async fn sample_cc_sweep(cc_sweep: SweepOptions, options: &ExportOptions, profile: Option<&SynthProfile>) -> anyhow::Result<()> {
+    use batcherbird_core::{
+        midi::MidiManager,
+        sampler::{SamplingEngine, CcSweepConfig},
+        export::{SampleExporter, ExportConfig, AudioFormat}
+    };
+    use std::path::PathBuf;
+
+    let SweepOptions { start, end, cc, values, settle_ms, output_dir } = cc_sweep;
+    if start > 127 || end > 127 || start > end || cc > 127 {
+        println!("❌ Invalid sweep: notes {}-{}, CC{}. Values must be 0-127 and start <= end.", start, end, cc);
+        return Ok(());
+    }
+    if values.is_empty() || values.iter().any(|&v| v > 127) {
+        println!("❌ Invalid CC values: {:?}. Provide one or more values in 0-127.", values);
+        return Ok(());
+    }
+    warn_outside_profile_range(profile, start, end);
+
+    let note_count = (end - start + 1) as usize;
+    println!("🎛️ CC{} sweep: {} layers x {} notes ({} to {})...", cc, values.len(), note_count, start, end);
+    
+    // Set up MIDI connection
+    let mut midi_manager = MidiManager::new()?;
+    let output_devices = midi_manager.list_output_devices()?;
+    
+    if output_devices.is_empty() {
+        println!("❌ No MIDI output devices found. {}", MIDI_SETUP_HINT);
+        return Ok(());
+    }
+    
+    // Use MiniFuse if available, otherwise first device
+    let device_index = output_devices.iter()
+        .position(|name| name.contains("MiniFuse"))
+        .unwrap_or(0);
+    println!("🎹 Using MIDI device: {}", output_devices[device_index]);
+    let mut midi_conn = midi_manager.connect_output(device_index)?;
+    
+    let sampling_config = profile_sampling_config(profile);
+    let engine = SamplingEngine::new(sampling_config.clone())?;
+    let sweep = CcSweepConfig {
+        controller: cc,
+        values,
+        settle_ms,
+    };
+    
+    println!("   Note: This will take approximately {:.1} minutes",
+        (note_count * sweep.values.len()) as f32 * 4.0 / 60.0  // Rough estimate: 4 seconds per note
+    );
+    
+    let samples = engine.sample_cc_sweep_async(&mut midi_conn, start, end, &sweep).await?;
+    
+    let export_config = ExportConfig {
+        output_directory: PathBuf::from(output_dir),
+        naming_pattern: "{note_name}_{note}_{velocity}_{cc}.wav".to_string(),
+        sample_format: AudioFormat::SFZ,
+        detection_config: profile_detection_config(profile),
+        instrument_description: Some(format!("CC{} sweep", cc)),
+        drum_map: profile_drum_map(profile),
+        readme: Some(readme_config(&sampling_config)),
+        hooks: profile_hooks(profile),
+        catalog: catalog_config(options, profile),
+        naming: naming_context(profile),
+        ..base_export_config(options)
+    };
+    
+    let exporter = SampleExporter::new(export_config)?;
+    let files = exporter.export_samples(&samples)?;
+    
+    println!("\n✅ CC sweep exported: {} files", files.len());
+    for file in &files {
+        println!("   📄 {}", file.display());
+    }
+    
+    Ok(())
+}
+
+/// The hits sample-drums records and how closely their round robins must match, from its flags
+struct DrumOptions {
+    start: u8,
+    end: u8,
+    round_robins: u8,
+    velocity: u8,
+    /// Loudness/transient deviation from the median take still counted as consistent
+    tolerance_db: f32,
+    /// Retake passes over flagged hits (0 = only report them)
+    retakes: u8,
+    drum_names: Option<String>,
+    output_dir: String,
+}
+
+async fn sample_drums(drums: DrumOptions, options: &ExportOptions, profile: Option<&SynthProfile>) -> anyhow::Result<()> {
+    use batcherbird_core::{
+        sampler::SamplingEngine,
+        drum_consistency::{analyze_round_robins, ConsistencyConfig},
+        drum_map::DrumMap,
+        export::{SampleExporter, ExportConfig, AudioFormat}
+    };
+    use std::path::PathBuf;
+
+    let DrumOptions { start, end, round_robins, velocity, tolerance_db, retakes, drum_names, output_dir } = drums;
+    if start > 127 || end > 127 || start > end || velocity == 0 || velocity > 127 {
+        println!("❌ Invalid drum range: notes {}-{}, velocity {}. Values must be 0-127 and start <= end.", start, end, velocity);
+        return Ok(());
+    }
+    if round_robins == 0 {
+        println!("❌ Need at least one round robin per note.");
+        return Ok(());
+    }
+    warn_outside_profile_range(profile, start, end);
+
+    let mut drum_map = DrumMap::load_default();
+    if let Some(spec) = &drum_names {
+        drum_map.apply_override_str(spec)?;
+    }
+
+    // Set up MIDI connection
+    let mut midi_manager = MidiManager::new()?;
+    let output_devices = midi_manager.list_output_devices()?;
+    
+    if output_devices.is_empty() {
+        println!("❌ No MIDI output devices found. {}", MIDI_SETUP_HINT);
+        return Ok(());
+    }
+    
+    // Use MiniFuse if available, otherwise first device
+    let device_index = output_devices.iter()
+        .position(|name| name.contains("MiniFuse"))
+        .unwrap_or(0);
+    println!("🎹 Using MIDI device: {}", output_devices[device_index]);
+    let mut midi_conn = midi_manager.connect_output(device_index)?;
+    
+    let sampling_config = SamplingConfig {
+        velocity,
+        ..profile_sampling_config(profile)
+    };
+    let engine = SamplingEngine::new(sampling_config.clone())?;
+    let consistency = ConsistencyConfig {
+        loudness_tolerance_db: tolerance_db,
+        transient_tolerance_db: tolerance_db,
+        ..ConsistencyConfig::default()
+    };
+    
+    let mut samples = engine.sample_round_robins_async(&mut midi_conn, start, end, round_robins).await?;
+    let mut report = analyze_round_robins(&samples, &consistency);
+    
+    for attempt in 1..=retakes {
+        if report.is_consistent() {
+            break;
+        }
+        println!("\n🔁 Retake pass {}/{}: {} inconsistent hits", attempt, retakes, report.flagged.len());
+        let replacements = engine.retake_round_robins_async(&mut midi_conn, &report.retake_list()).await?;
+        for replacement in replacements {
+            if let Some(slot) = samples.iter_mut().find(|s| {
+                s.note == replacement.note && s.metadata.round_robin == replacement.metadata.round_robin
+            }) {
+                *slot = replacement;
+            }
+        }
+        report = analyze_round_robins(&samples, &consistency);
+    }
+    
+    println!("\n🥁 Round robin consistency:");
+    for hit in &report.hits {
+        println!("   {} RR{}: peak {:>6.1} dB, rms {:>6.1} dB, attack {:>4.0}ms",
+            drum_map.name(hit.note).map(str::to_string).unwrap_or_else(|| sample_note_name(hit.note)), hit.round_robin, hit.peak_db, hit.rms_db, hit.attack_ms);
+    }
+    if report.is_consistent() {
+        println!("✅ All hits are consistent");
+    } else {
+        println!("⚠️ {} hits still flagged for retake:", report.flagged.len());
+        for hit in &report.flagged {
+            let issues: Vec<String> = hit.issues.iter().map(|issue| issue.describe()).collect();
+            println!("   {} RR{}: {}",
+                drum_map.name(hit.note).map(str::to_string).unwrap_or_else(|| sample_note_name(hit.note)),
+                hit.round_robin, issues.join(", "));
+        }
+    }
+    
+    let export_config = ExportConfig {
+        output_directory: PathBuf::from(output_dir),
+        naming_pattern: "{note_name}_{note}_{velocity}_{rr}.wav".to_string(),
+        sample_format: AudioFormat::SFZ,
+        detection_config: profile.map(|p| p.detection_config()).unwrap_or_else(DetectionConfig::percussive),
+        instrument_description: Some(format!("Drums, {} round robins", round_robins)),
+        drum_map: Some(drum_map),
+        readme: Some(readme_config(&sampling_config)),
+        hooks: profile_hooks(profile),
+        catalog: catalog_config(options, profile),
+        naming: naming_context(profile),
+        ..base_export_config(options)
+    };
+    
+    let exporter = SampleExporter::new(export_config)?;
+    let files = exporter.export_samples(&samples)?;
+    
+    println!("\n✅ Drum hits exported: {} files", files.len());
+    for file in &files {
+        println!("   📄 {}", file.display());
+    }
+    
+    Ok(())
+}
+
+/// What sample-long-take records, from its flags
+struct LongTakeOptions {
+    start: u8,
+    end: u8,
+    velocities: Vec<u8>,
+    search_ms: f32,
+    format: String,
+    output_dir: String,
+}
+
+async fn sample_long_take(take: LongTakeOptions, options: &ExportOptions, profile: Option<&SynthProfile>) -> anyhow::Result<()> {
+    use batcherbird_core::{
+        sampler::SamplingEngine,
+        slicing::SliceConfig,
+        export::{SampleExporter, ExportConfig}
+    };
+    use std::path::PathBuf;
+
+    let LongTakeOptions { start, end, velocities, search_ms, format, output_dir } = take;
+
+    if start > 127 || end > 127 || start > end {
+        println!("❌ Invalid note range: {}-{}. Notes must be 0-127 and start <= end.", start, end);
+        return Ok(());
+    }
+    if velocities.is_empty() || velocities.iter().any(|v| !(1..=127).contains(v)) {
+        println!("❌ Invalid velocities {:?}: use 1-127.", velocities);
+        return Ok(());
+    }
+    let Some(sample_format) = export_format(&format, false) else {
+        return Ok(());
+    };
+    warn_outside_profile_range(profile, start, end);
+
+    // Set up MIDI connection
+    let mut midi_manager = MidiManager::new()?;
+    let output_devices = midi_manager.list_output_devices()?;
+    
+    if output_devices.is_empty() {
+        println!("❌ No MIDI output devices found. {}", MIDI_SETUP_HINT);
+        return Ok(());
+    }
+    
+    // Use MiniFuse if available, otherwise first device
+    let device_index = output_devices.iter()
+        .position(|name| name.contains("MiniFuse"))
+        .unwrap_or(0);
+    println!("🎹 Using MIDI device: {}", output_devices[device_index]);
+    let mut midi_conn = midi_manager.connect_output(device_index)?;
+    
+    let entries: Vec<(u8, u8)> = (start..=end)
+        .flat_map(|note| velocities.iter().map(move |&velocity| (note, velocity)))
+        .collect();
+    let sampling_config = profile_sampling_config(profile);
+    let engine = SamplingEngine::new(sampling_config.clone())?;
+    let slice_config = SliceConfig { search_ms, ..SliceConfig::default() };
+    let samples = engine.sample_long_take_async(&mut midi_conn, &entries, &slice_config).await?;
+    
+    let export_config = ExportConfig {
+        output_directory: PathBuf::from(output_dir),
+        sample_format,
+        detection_config: profile_detection_config(profile),
+        instrument_description: Some(format!("{} notes sliced from one long take", samples.len())),
+        readme: Some(readme_config(&sampling_config)),
+        hooks: profile_hooks(profile),
+        catalog: catalog_config(options, profile),
+        naming: naming_context(profile),
+        ..base_export_config(options)
+    };
+    
+    let exporter = SampleExporter::new(export_config)?;
+    let files = exporter.export_samples(&samples)?;
+    
+    println!("\n✅ Long take export: {} files", files.len());
+    for file in &files {
+        println!("   📄 {}", file.display());
+    }
+    
+    Ok(())
+}
+
+/// The notes sample-takes records and which takes it keeps, from its flags
+struct TakesOptions {
+    start: u8,
+    end: u8,
+    /// Takes per note
+    takes: u8,
+    /// Keep every take, not just the best
+    keep_all: bool,
+    format: String,
+    output_dir: String,
+}
+
+async fn sample_takes(run: TakesOptions, options: &ExportOptions, profile: Option<&SynthProfile>) -> anyhow::Result<()> {
+    use batcherbird_core::{
+        sampler::SamplingEngine,
+        takes::{select_takes, TakeSelection},
+        export::{SampleExporter, ExportConfig}
+    };
+    use std::path::PathBuf;
+
+    let TakesOptions { start, end, takes, keep_all, format, output_dir } = run;
+    if start > 127 || end > 127 || start > end {
+        println!("❌ Invalid note range: {}-{}. Notes must be 0-127 and start <= end.", start, end);
+        return Ok(());
+    }
+    if takes == 0 {
+        println!("❌ Record at least one take per note.");
+        return Ok(());
+    }
+    let Some(sample_format) = export_format(&format, false) else {
+        return Ok(());
+    };
+    warn_outside_profile_range(profile, start, end);
+
+    // Set up MIDI connection
+    let mut midi_manager = MidiManager::new()?;
+    let output_devices = midi_manager.list_output_devices()?;
+    
+    if output_devices.is_empty() {
+        println!("❌ No MIDI output devices found. {}", MIDI_SETUP_HINT);
+        return Ok(());
+    }
+    
+    // Use MiniFuse if available, otherwise first device
+    let device_index = output_devices.iter()
+        .position(|name| name.contains("MiniFuse"))
+        .unwrap_or(0);
+    println!("🎹 Using MIDI device: {}", output_devices[device_index]);
+    let mut midi_conn = midi_manager.connect_output(device_index)?;
+    
+    let sampling_config = profile_sampling_config(profile);
+    let detection_config = profile_detection_config(profile);
+    let engine = SamplingEngine::new(sampling_config.clone())?;
+    let samples = engine.sample_takes_async(&mut midi_conn, start, end, takes).await?;
+    let selection = if keep_all { TakeSelection::KeepAll } else { TakeSelection::KeepBest };
+    let samples = select_takes(samples, selection, &detection_config);
+    
+    let export_config = ExportConfig {
+        output_directory: PathBuf::from(output_dir),
+        sample_format,
+        detection_config,
+        instrument_description: Some(format!("Best of {} takes per note", takes)),
+        readme: Some(readme_config(&sampling_config)),
+        hooks: profile_hooks(profile),
+        catalog: catalog_config(options, profile),
+        naming: naming_context(profile),
+        ..base_export_config(options)
+    };
+    
+    let exporter = SampleExporter::new(export_config)?;
+    let files = exporter.export_samples(&samples)?;
+    
+    println!("\n✅ Multi-take export: {} files", files.len());
+    for file in &files {
+        println!("   📄 {}", file.display());
+    }
+    
+    Ok(())
+}
+
+/// What sample-kit records and how it writes the kit, from its flags
+struct KitOptions {
+    /// Note list such as "36,38,42-46" (None = every note in the drum map)
+    notes: Option<String>,
+    velocity: u8,
+    drum_names: Option<String>,
+    format: String,
+    output_dir: String,
+    target: Option<String>,
+    bit_depth: String,
+    hardware_copy: Option<String>,
+}
+
+async fn sample_kit(kit: KitOptions, options: &ExportOptions, profile: Option<&SynthProfile>) -> anyhow::Result<()> {
+    use batcherbird_core::{
+        sampler::SamplingEngine,
+        drum_map::DrumMap,
+        export::{SampleExporter, ExportConfig, AudioFormat}
+    };
+    use std::path::PathBuf;
+
+    let KitOptions { notes, velocity, drum_names, format, output_dir, target, bit_depth, hardware_copy } = kit;
+    if velocity == 0 || velocity > 127 {
+        println!("❌ Invalid velocity: {}. Use 1-127.", velocity);
+        return Ok(());
+    }
+    let Some(mut sample_format) = export_format(&format, false) else {
+        return Ok(());
+    };
+    let target = match target.as_deref().map(hardware_target) {
+        Some(None) => return Ok(()),
+        Some(Some(target)) => {
+            sample_format = AudioFormat::Wav16Bit;
+            Some(target)
+        }
+        None => None,
+    };
+    let Some((instrument_bit_depth, additional_outputs)) = export_outputs(&bit_depth, hardware_copy.as_deref()) else {
+        return Ok(());
+    };
+
+    let mut drum_map = DrumMap::load_default();
+    if let Some(spec) = &drum_names {
+        drum_map.apply_override_str(spec)?;
+    }
+    let notes = match &notes {
+        Some(spec) => match parse_program_list(spec) {
+            Some(list) => list,
+            None => {
+                println!("❌ Invalid note list: {}. Use e.g. 36,38,42-46 with notes 0-127.", spec);
+                return Ok(());
+            }
+        },
+        None => drum_map.notes(),
+    };
+    if notes.is_empty() {
+        println!("❌ No notes to sample: the drum map is empty.");
+        return Ok(());
+    }
+    
+    println!("🥁 Kit pads:");
+    for &note in &notes {
+        println!("   {:>3} {}", note, drum_map.name(note).map(str::to_string).unwrap_or_else(|| sample_note_name(note)));
+    }
+
+    // Set up MIDI connection
+    let mut midi_manager = MidiManager::new()?;
+    let output_devices = midi_manager.list_output_devices()?;
+    
+    if output_devices.is_empty() {
+        println!("❌ No MIDI output devices found. {}", MIDI_SETUP_HINT);
+        return Ok(());
+    }
+    
+    // Use MiniFuse if available, otherwise first device
+    let device_index = output_devices.iter()
+        .position(|name| name.contains("MiniFuse"))
+        .unwrap_or(0);
+    println!("🎹 Using MIDI device: {}", output_devices[device_index]);
+    let mut midi_conn = midi_manager.connect_output(device_index)?;
+    
+    let sampling_config = SamplingConfig {
+        velocity,
+        ..profile_sampling_config(profile)
+    };
+    let engine = SamplingEngine::new(sampling_config.clone())?;
+    let mut samples = engine.sample_notes_async(&mut midi_conn, &notes).await?;
+    if let Some(target) = &target {
+        let (fitted, report) = target.fit(&samples);
+        print!("{}", report.summary());
+        samples = fitted;
+    }
+    
+    let export_config = ExportConfig {
+        output_directory: PathBuf::from(output_dir),
+        sample_format,
+        detection_config: profile.map(|p| p.detection_config()).unwrap_or_else(DetectionConfig::percussive),
+        instrument_description: Some(format!("Drum kit, {} pads", notes.len())),
+        drum_map: Some(drum_map),
+        readme: Some(readme_config(&sampling_config)),
+        instrument_bit_depth,
+        additional_outputs,
+        hooks: profile_hooks(profile),
+        catalog: catalog_config(options, profile),
+        naming: naming_context(profile),
+        ..base_export_config(options)
+    };
+    
+    let exporter = SampleExporter::new(export_config)?;
+    let files = exporter.export_samples(&samples)?;
+    
+    println!("\n✅ Drum kit exported: {} files", files.len());
+    for file in &files {
+        println!("   📄 {}", file.display());
+    }
+    
+    Ok(())
+}
+
+/// Where sample-live listens and how much of each played note it keeps, from its flags
+struct LiveOptions {
+    /// MIDI input device index
+    input: usize,
+    /// Only listen on this channel, 1-16 (None = any)
+    channel: Option<u8>,
+    pre_roll_ms: u64,
+    tail_ms: u64,
+    /// Stop after this many takes (None = when Enter is pressed)
+    takes: Option<usize>,
+    format: String,
+    output_dir: String,
+}
+
+fn sample_live(live: LiveOptions, options: &ExportOptions, profile: Option<&SynthProfile>) -> anyhow::Result<()> {
+    use batcherbird_core::{
+        sampler::{SamplingEngine, PassiveCaptureConfig},
+        export::{SampleExporter, ExportConfig}
+    };
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    let LiveOptions { input, channel, pre_roll_ms, tail_ms, takes, format, output_dir } = live;
+    if channel.is_some_and(|channel| !(1..=16).contains(&channel)) {
+        println!("❌ Invalid MIDI channel: {}. Use 1-16.", channel.unwrap_or_default());
+        return Ok(());
+    }
+    let Some(sample_format) = export_format(&format, false) else {
+        return Ok(());
+    };
+
+    let mut midi_manager = MidiManager::new()?;
+    let input_devices = midi_manager.list_input_devices()?;
+    if input >= input_devices.len() {
+        println!("❌ No MIDI input device {}. Found {} input devices.", input, input_devices.len());
+        return Ok(());
+    }
+    println!("🎹 Listening on MIDI input: {}", input_devices[input]);
+    let (_midi_in, note_events) = midi_manager.connect_input_notes(input)?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_on_enter = stop.clone();
+    std::thread::spawn(move || {
+        let mut line = String::new();
+        let _ = std::io::stdin().read_line(&mut line);
+        stop_on_enter.store(true, Ordering::Relaxed);
+    });
+    println!("🎵 Play notes to record them. Press Enter to finish.");
+
+    let passive = PassiveCaptureConfig {
+        pre_roll_ms,
+        tail_ms,
+        input_channel: channel.map(|channel| channel - 1),
+        max_takes: takes,
+        ..PassiveCaptureConfig::default()
+    };
+    let sampling_config = profile_sampling_config(profile);
+    let engine = SamplingEngine::new(sampling_config.clone())?;
+    let mut samples = Vec::new();
+    engine.capture_performance_blocking(&note_events, &passive, &stop, |sample| {
+        samples.push(sample);
+        Ok(())
+    })?;
+
+    if samples.is_empty() {
+        println!("⚠️ No notes recorded");
+        return Ok(());
+    }
+
+    // Repeated note/velocity pairs become round robins instead of overwriting each other
+    let mut counts: HashMap<(u8, u8), u8> = HashMap::new();
+    for sample in &samples {
+        *counts.entry((sample.note, sample.velocity)).or_default() += 1;
+    }
+    let mut seen: HashMap<(u8, u8), u8> = HashMap::new();
+    for sample in &mut samples {
+        let key = (sample.note, sample.velocity);
+        if counts[&key] > 1 {
+            let take = seen.entry(key).or_default();
+            *take += 1;
+            sample.metadata.round_robin = Some(*take);
+        }
+    }
+
+    let export_config = ExportConfig {
+        output_directory: PathBuf::from(output_dir),
+        sample_format,
+        detection_config: profile_detection_config(profile),
+        instrument_description: Some(format!("Played live, {} takes", samples.len())),
+        readme: Some(readme_config(&sampling_config)),
+        hooks: profile_hooks(profile),
+        catalog: catalog_config(options, profile),
+        naming: naming_context(profile),
+        ..base_export_config(options)
+    };
+    
+    let exporter = SampleExporter::new(export_config)?;
+    let files = exporter.export_samples(&samples)?;
+    
+    println!("\n✅ Live recording exported: {} files", files.len());
+    for file in &files {
+        println!("   📄 {}", file.display());
+    }
+    
+    Ok(())
+}
+
+/// The slots sample-assisted steps through and the footswitch that drives it, from its flags
+struct AssistedOptions {
+    start: u8,
+    end: u8,
+    velocities: Vec<u8>,
+    /// MIDI input device index for the footswitch (None = keyboard only)
+    input: Option<usize>,
+    /// Footswitch bindings such as "cc64=next,cc67=retake"
+    triggers: String,
+    format: String,
+    output_dir: String,
+}
+
+fn sample_assisted(assisted: AssistedOptions, options: &ExportOptions, profile: Option<&SynthProfile>) -> anyhow::Result<()> {
+    use batcherbird_core::{
+        sampler::SamplingEngine,
+        trigger::{TriggerAction, TriggerMap},
+        export::{SampleExporter, ExportConfig}
+    };
+    use std::path::PathBuf;
+    use std::sync::mpsc;
+
+    let AssistedOptions { start, end, velocities, input, triggers, format, output_dir } = assisted;
+    if start > 127 || end > 127 || start > end {
+        println!("❌ Invalid note range: {}-{}. Notes must be 0-127 and start <= end.", start, end);
+        return Ok(());
+    }
+    if velocities.is_empty() || velocities.iter().any(|&v| v == 0 || v > 127) {
+        println!("❌ Invalid velocities: {:?}. Use values 1-127.", velocities);
+        return Ok(());
+    }
+    let Some(sample_format) = export_format(&format, false) else {
+        return Ok(());
+    };
+    let trigger_map = match TriggerMap::parse(&triggers) {
+        Ok(map) => map,
+        Err(e) => {
+            println!("❌ {}", e);
+            return Ok(());
+        }
+    };
+
+    let (action_tx, action_rx) = mpsc::channel::<TriggerAction>();
+
+    // Footswitch on a MIDI input, if one was chosen
+    let mut midi_manager = MidiManager::new()?;
+    let _midi_in = match input {
+        Some(index) => {
+            let input_devices = midi_manager.list_input_devices()?;
+            if index >= input_devices.len() {
+                println!("❌ No MIDI input device {}. Found {} input devices.", index, input_devices.len());
+                return Ok(());
+            }
+            println!("🦶 Footswitch input: {} ({} bindings)", input_devices[index], trigger_map.bindings().len());
+            Some(midi_manager.connect_input_triggers(index, trigger_map, action_tx.clone())?)
+        }
+        None => None,
+    };
+
+    // Keyboard works alongside the footswitch
+    std::thread::spawn(move || {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if std::io::stdin().read_line(&mut line).unwrap_or(0) == 0 {
+                break;
+            }
+            let action = match line.trim() {
+                "r" => TriggerAction::Retake,
+                "q" => TriggerAction::Abort,
+                _ => TriggerAction::Next,
+            };
+            if action_tx.send(action).is_err() {
+                break;
+            }
+        }
+    });
+    println!("⌨️ Enter = start/keep take, r + Enter = retake, q + Enter = finish");
+
+    let slots: Vec<(u8, u8)> = (start..=end)
+        .flat_map(|note| velocities.iter().map(move |&velocity| (note, velocity)))
+        .collect();
+    let sampling_config = profile_sampling_config(profile);
+    let engine = SamplingEngine::new(sampling_config.clone())?;
+    let samples = engine.sample_assisted_blocking(&slots, &action_rx)?;
+
+    if samples.is_empty() {
+        println!("⚠️ No samples recorded");
+        return Ok(());
+    }
+
+    let export_config = ExportConfig {
+        output_directory: PathBuf::from(output_dir),
+        sample_format,
+        detection_config: profile_detection_config(profile),
+        instrument_description: Some(format!("Played by hand, {} samples", samples.len())),
+        readme: Some(readme_config(&sampling_config)),
+        hooks: profile_hooks(profile),
+        catalog: catalog_config(options, profile),
+        naming: naming_context(profile),
+        ..base_export_config(options)
+    };
+    
+    let exporter = SampleExporter::new(export_config)?;
+    let files = exporter.export_samples(&samples)?;
+    
+    println!("\n✅ Assisted session exported: {} files", files.len());
+    for file in &files {
+        println!("   📄 {}", file.display());
+    }
+    
+    Ok(())
+}
+
+/// The transitions sample-legato records, from its flags
+struct LegatoOptions {
+    start: u8,
+    end: u8,
+    /// Semitone steps from source to target note
+    intervals: Vec<i8>,
+    hold_ms: u64,
+    overlap_ms: u64,
+    /// Skip the plain sustain per note
+    transitions_only: bool,
+    output_dir: String,
+}
+
+async fn sample_legato(legato: LegatoOptions, options: &ExportOptions, profile: Option<&SynthProfile>) -> anyhow::Result<()> {
+    use batcherbird_core::{
+        sampler::{SamplingEngine, LegatoConfig},
+        export::{SampleExporter, ExportConfig, AudioFormat}
+    };
+    use std::path::PathBuf;
+
+    let LegatoOptions { start, end, intervals, hold_ms, overlap_ms, transitions_only, output_dir } = legato;
+    if start > 127 || end > 127 || start > end {
+        println!("❌ Invalid note range: {}-{}. Notes must be 0-127 and start <= end.", start, end);
+        return Ok(());
+    }
+    let legato = LegatoConfig {
+        intervals,
+        source_hold_ms: hold_ms,
+        overlap_ms,
+    };
+    if legato.transitions(start, end).is_empty() {
+        println!("❌ No transitions with intervals {:?} fit in notes {}-{}.", legato.intervals, start, end);
+        return Ok(());
+    }
+    warn_outside_profile_range(profile, start, end);
+
+    // Set up MIDI connection
+    let mut midi_manager = MidiManager::new()?;
+    let output_devices = midi_manager.list_output_devices()?;
+    
+    if output_devices.is_empty() {
+        println!("❌ No MIDI output devices found. {}", MIDI_SETUP_HINT);
+        return Ok(());
+    }
+    
+    // Use MiniFuse if available, otherwise first device
+    let device_index = output_devices.iter()
+        .position(|name| name.contains("MiniFuse"))
+        .unwrap_or(0);
+    println!("🎹 Using MIDI device: {}", output_devices[device_index]);
+    let mut midi_conn = midi_manager.connect_output(device_index)?;
+    
+    let sampling_config = profile_sampling_config(profile);
+    let engine = SamplingEngine::new(sampling_config.clone())?;
+    
+    // Plain sustains give the first note of a phrase something to play
+    let mut samples = if transitions_only {
+        Vec::new()
+    } else {
+        engine.sample_note_range_async(&mut midi_conn, start, end).await?
+    };
+    samples.extend(engine.sample_legato_async(&mut midi_conn, start, end, &legato).await?);
+    
+    let export_config = ExportConfig {
+        output_directory: PathBuf::from(output_dir),
+        sample_format: AudioFormat::SFZ,
+        apply_detection: false, // Transitions start mid-sound; trimming would cut the connection
+        instrument_description: Some(format!("Legato, intervals {:?}", legato.intervals)),
+        readme: Some(readme_config(&sampling_config)),
+        hooks: profile_hooks(profile),
+        catalog: catalog_config(options, profile),
+        naming: naming_context(profile),
+        ..base_export_config(options)
+    };
+    
+    let exporter = SampleExporter::new(export_config)?;
+    let files = exporter.export_samples(&samples)?;
+    
+    println!("\n✅ Legato instrument exported: {} files", files.len());
+    for file in &files {
+        println!("   📄 {}", file.display());
+    }
+    
+    Ok(())
+}
+
+/// The notes sample-releases records and what it keeps of each, from its flags
+struct ReleaseOptions {
+    start: u8,
+    end: u8,
+    /// Audio kept from before the note-off
+    pre_buffer_ms: u64,
+    /// Also export the full takes as the attack samples
+    with_sustains: bool,
+    format: String,
+    output_dir: String,
+}
+
+async fn sample_releases(releases: ReleaseOptions, options: &ExportOptions, profile: Option<&SynthProfile>) -> anyhow::Result<()> {
+    use batcherbird_core::{
+        sampler::{SamplingEngine, ReleaseCaptureConfig},
+        export::{SampleExporter, ExportConfig}
+    };
+    use std::path::PathBuf;
+
+    let ReleaseOptions { start, end, pre_buffer_ms, with_sustains, format, output_dir } = releases;
+    if start > 127 || end > 127 || start > end {
+        println!("❌ Invalid note range: {}-{}. Notes must be 0-127 and start <= end.", start, end);
+        return Ok(());
+    }
+    let Some(sample_format) = export_format(&format, false) else {
+        return Ok(());
+    };
+    warn_outside_profile_range(profile, start, end);
+
+    // Set up MIDI connection
+    let mut midi_manager = MidiManager::new()?;
+    let output_devices = midi_manager.list_output_devices()?;
+    
+    if output_devices.is_empty() {
+        println!("❌ No MIDI output devices found. {}", MIDI_SETUP_HINT);
+        return Ok(());
+    }
+    
+    // Use MiniFuse if available, otherwise first device
+    let device_index = output_devices.iter()
+        .position(|name| name.contains("MiniFuse"))
+        .unwrap_or(0);
+    println!("🎹 Using MIDI device: {}", output_devices[device_index]);
+    let mut midi_conn = midi_manager.connect_output(device_index)?;
+    
+    let sampling_config = profile_sampling_config(profile);
+    let engine = SamplingEngine::new(sampling_config.clone())?;
+    let release = ReleaseCaptureConfig {
+        pre_buffer_ms,
+        keep_sustain: with_sustains,
+    };
+    let samples = engine.sample_releases_async(&mut midi_conn, start, end, &release).await?;
+    
+    let export_config = ExportConfig {
+        output_directory: PathBuf::from(output_dir),
+        sample_format,
+        detection_config: profile_detection_config(profile),
+        instrument_description: Some("Release samples".to_string()),
+        readme: Some(readme_config(&sampling_config)),
+        hooks: profile_hooks(profile),
+        catalog: catalog_config(options, profile),
+        naming: naming_context(profile),
+        ..base_export_config(options)
+    };
+    
+    let exporter = SampleExporter::new(export_config)?;
+    let files = exporter.export_samples(&samples)?;
+    
+    println!("\n✅ Release samples exported: {} files", files.len());
+    for file in &files {
+        println!("   📄 {}", file.display());
+    }
+    
+    Ok(())
+}
+
+async fn sample_midi_file(file: &str, channel: Option<u8>, tail_ms: u64, output_dir: String, options: &ExportOptions, profile: Option<&SynthProfile>) -> anyhow::Result<()> {
+    use batcherbird_core::{
+        sampler::SamplingEngine,
+        midi_file::MidiFile,
+        export::{SampleExporter, ExportConfig}
+    };
+    use std::path::{Path, PathBuf};
+
+    if channel.is_some_and(|c| !(1..=16).contains(&c)) {
+        println!("❌ Invalid channel {:?}. Use 1-16.", channel);
+        return Ok(());
+    }
+
+    let path = Path::new(file);
+    let mut midi_file = MidiFile::load(path)?;
+    if let Some(channel) = channel.or_else(|| profile.map(|p| p.midi_channel)) {
+        midi_file.remap_channel(channel - 1);
+    }
+
+    let channels: Vec<String> = midi_file.channels().iter().map(|c| (c + 1).to_string()).collect();
+    println!("🎼 {}: format {}, {} tracks, {} notes on channel(s) {}, {:.1}s",
+        path.display(), midi_file.format, midi_file.track_count, midi_file.note_count(),
+        channels.join(","), midi_file.duration.as_secs_f32());
+
+    // Set up MIDI connection
+    let mut midi_manager = MidiManager::new()?;
+    let output_devices = midi_manager.list_output_devices()?;
+    
+    if output_devices.is_empty() {
+        println!("❌ No MIDI output devices found. {}", MIDI_SETUP_HINT);
+        return Ok(());
+    }
+    
+    // Use MiniFuse if available, otherwise first device
+    let device_index = output_devices.iter()
+        .position(|name| name.contains("MiniFuse"))
+        .unwrap_or(0);
+    println!("🎹 Using MIDI device: {}", output_devices[device_index]);
+    let mut midi_conn = midi_manager.connect_output(device_index)?;
+    
+    let engine = SamplingEngine::new(profile_sampling_config(profile))?;
+    let sample = engine.sample_midi_file_async(&mut midi_conn, &midi_file, tail_ms).await?;
+    
+    let stem = path.file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "performance".to_string());
+    let export_config = ExportConfig {
+        output_directory: PathBuf::from(output_dir),
+        naming_pattern: format!("{}_{{timestamp}}.wav", stem),
+        apply_detection: false, // Keep the recording aligned with the file's timeline
+        hooks: profile_hooks(profile),
+        catalog: catalog_config(options, profile),
+        naming: naming_context(profile),
+        ..base_export_config(options)
+    };
+    
+    let exporter = SampleExporter::new(export_config)?;
+    let file_path = exporter.export_sample(&sample)?;
+    
+    println!("\n✅ Performance recorded: {}", file_path.display());
+    Ok(())
+}
+
+/// What sample-patches records and where it writes each patch, from its flags
+struct PatchOptions {
+    start: u8,
+    end: u8,
+    /// Program list such as "0-15" or "0,4,9-12"
+    programs: String,
+    velocities: Vec<u8>,
+    /// None = the profile's program change settle time, or 500 ms without one
+    settle_ms: Option<u64>,
+    velocity_curve: String,
+    format: String,
+    output_dir: String,
+    /// Ask the synth to identify itself and name the library folder after it
+    identify: bool,
+}
+
+async fn sample_patches(patches: PatchOptions, options: &ExportOptions, profile: Option<&SynthProfile>) -> anyhow::Result<()> {
+    use batcherbird_core::{
+        midi::MidiManager,
+        sampler::{SamplingEngine, PatchIterationConfig},
+        export::{SampleExporter, ExportConfig},
+        velocity_curve::VelocityCurve
+    };
+    use std::path::PathBuf;
+
+    let PatchOptions { start, end, programs, velocities, settle_ms, velocity_curve, format, output_dir, identify } = patches;
+    if start > 127 || end > 127 || start > end {
+        println!("❌ Invalid note range: {}-{}. Notes must be 0-127 and start <= end.", start, end);
+        return Ok(());
+    }
+    let programs = match parse_program_list(&programs) {
+        Some(list) => list,
+        None => {
+            println!("❌ Invalid program list: {}. Use values 0-127 like \"0-15\" or \"0,4,9-12\".", programs);
+            return Ok(());
+        }
+    };
+    if velocities.is_empty() || velocities.iter().any(|&v| v == 0 || v > 127) {
+        println!("❌ Invalid velocities: {:?}. Provide one or more values in 1-127.", velocities);
+        return Ok(());
+    }
+    let velocity_curve = match VelocityCurve::parse(&velocity_curve) {
+        Ok(curve) => curve,
+        Err(e) => {
+            println!("❌ {}", e);
+            return Ok(());
+        }
+    };
+    let Some(sample_format) = export_format(&format, false) else {
+        return Ok(());
+    };
+
+    warn_outside_profile_range(profile, start, end);
+    let mut settle_ms = settle_ms.or(profile.map(|p| p.program_change_settle_ms)).unwrap_or(500);
+
+    let note_count = (end - start + 1) as usize;
+    let total_takes = programs.len() * velocities.len() * note_count;
+    println!("🎛️ Patch iteration: {} programs, {} takes in total", programs.len(), total_takes);
+    println!("   Note: This will take approximately {:.1} minutes",
+        (total_takes as f32 * 4.0 + programs.len() as f32 * settle_ms as f32 / 1000.0) / 60.0
+    );
+    
+    // Set up MIDI connection
+    let mut midi_manager = MidiManager::new()?;
+    let output_devices = midi_manager.list_output_devices()?;
+    
+    if output_devices.is_empty() {
+        println!("❌ No MIDI output devices found. {}", MIDI_SETUP_HINT);
+        return Ok(());
+    }
+    
+    // Use MiniFuse if available, otherwise first device
+    let device_index = output_devices.iter()
+        .position(|name| name.contains("MiniFuse"))
+        .unwrap_or(0);
+    println!("🎹 Using MIDI device: {}", output_devices[device_index]);
+    let mut midi_conn = midi_manager.connect_output(device_index)?;
+    
+    let mut output_root = PathBuf::from(output_dir);
+    let mut profile = profile.cloned();
+    
+    // Name the library folder after the synth when it answers a Device Inquiry,
+    // and pick a matching profile if none was chosen explicitly
+    if identify {
+        let input_index = matching_input_index(&mut midi_manager, &output_devices[device_index])?;
+        match midi_manager.identify_device(&mut midi_conn, input_index, std::time::Duration::from_secs(2)) {
+            Ok(identity) => {
+                output_root = output_root.join(identity.folder_name());
+                if profile.is_none() {
+                    if let Some(matched) = ProfileLibrary::load_default().find_for_identity(&identity) {
+                        println!("🎛️ Using matching synth profile: {} ({})", matched.name, matched.id);
+                        settle_ms = matched.program_change_settle_ms;
+                        profile = Some(matched.clone());
+                    }
+                }
+            }
+            Err(e) => println!("⚠️ Synth did not identify itself ({}), using {}", e, output_root.display()),
+        }
+    }
+    let profile = profile.as_ref();
+    
+    let sampling_config = SamplingConfig {
+        velocity_curve,
+        ..profile_sampling_config(profile)
+    };
+    let engine = SamplingEngine::new(sampling_config.clone())?;
+    let patches = PatchIterationConfig {
+        programs,
+        velocities,
+        settle_ms,
+    };
+    
+    // Export each patch as soon as it finishes recording
+    engine.sample_patches_async(&mut midi_conn, start, end, &patches, |program, samples| {
+        let patch_name = format!("Program_{:03}", program);
+        let export_config = ExportConfig {
+            output_directory: output_root.join(&patch_name),
+            naming_pattern: format!("{}_{{note_name}}_{{note}}_{{velocity}}.wav", patch_name),
+            sample_format: sample_format.clone(),
+            detection_config: profile_detection_config(profile),
+            instrument_description: Some(format!("Program {}", program)),
+            drum_map: profile_drum_map(profile),
+            readme: Some(readme_config(&sampling_config)),
+            hooks: profile_hooks(profile),
+            catalog: catalog_config(options, profile),
+            naming: naming_context(profile),
+            ..base_export_config(options)
+        };
+        
+        let exporter = SampleExporter::new(export_config)?;
+        let files = exporter.export_samples(&samples)?;
+        println!("✅ {}: {} files written", patch_name, files.len());
+        Ok(())
+    }).await?;
+    
+    println!("\n✅ Patch iteration complete: {}", output_root.display());
+    Ok(())
+}
+
+/// The channels sample-channels records and how, from its flags
+struct ChannelOptions {
+    start: u8,
+    end: u8,
+    /// Channel list such as "1-8" or "1,2,10" (1-16)
+    channels: String,
+    velocities: Vec<u8>,
+    /// Pause before each channel
+    settle_ms: u64,
+    format: String,
+    /// One subfolder per channel is written inside
+    output_dir: String,
+}
+
+async fn sample_channels(run: ChannelOptions, options: &ExportOptions, profile: Option<&SynthProfile>) -> anyhow::Result<()> {
+    use batcherbird_core::{
+        sampler::{SamplingEngine, MultiChannelConfig},
+        export::{SampleExporter, ExportConfig}
+    };
+    use std::path::PathBuf;
+
+    let ChannelOptions { start, end, channels, velocities, settle_ms, format, output_dir } = run;
+    if start > 127 || end > 127 || start > end {
+        println!("❌ Invalid note range: {}-{}. Notes must be 0-127 and start <= end.", start, end);
+        return Ok(());
+    }
+    let channels = match parse_program_list(&channels).filter(|list| list.iter().all(|c| (1..=16).contains(c))) {
+        Some(list) => list.into_iter().map(|c| c - 1).collect::<Vec<u8>>(),
+        None => {
+            println!("❌ Invalid channel list: {}. Use e.g. 1-8 or 1,2,10 with channels 1-16.", channels);
+            return Ok(());
+        }
+    };
+    if velocities.iter().any(|&v| v == 0 || v > 127) {
+        println!("❌ Invalid velocities: {:?}. Use 1-127.", velocities);
+        return Ok(());
+    }
+    let Some(sample_format) = export_format(&format, false) else {
+        return Ok(());
+    };
+    warn_outside_profile_range(profile, start, end);
+
+    // Set up MIDI connection
+    let mut midi_manager = MidiManager::new()?;
+    let output_devices = midi_manager.list_output_devices()?;
+    
+    if output_devices.is_empty() {
+        println!("❌ No MIDI output devices found. {}", MIDI_SETUP_HINT);
+        return Ok(());
+    }
+    
+    // Use MiniFuse if available, otherwise first device
+    let device_index = output_devices.iter()
+        .position(|name| name.contains("MiniFuse"))
+        .unwrap_or(0);
+    println!("🎹 Using MIDI device: {}", output_devices[device_index]);
+    let mut midi_conn = midi_manager.connect_output(device_index)?;
+    
+    let output_root = PathBuf::from(output_dir);
+    let sampling_config = profile_sampling_config(profile);
+    let engine = SamplingEngine::new(sampling_config.clone())?;
+    let multi_channel = MultiChannelConfig {
+        channels,
+        velocities,
+        settle_ms,
+    };
+    
+    // Export each part as soon as its channel finishes recording
+    engine.sample_channels_async(&mut midi_conn, start, end, &multi_channel, |channel, samples| {
+        let part_name = format!("Ch{:02}", channel + 1);
+        let export_config = ExportConfig {
+            output_directory: output_root.join(&part_name),
+            naming_pattern: format!("{}_{{note_name}}_{{note}}_{{velocity}}.wav", part_name),
+            sample_format: sample_format.clone(),
+            detection_config: profile_detection_config(profile),
+            instrument_description: Some(format!("MIDI channel {}", channel + 1)),
+            // The GM drum channel gets drum names and one-shot regions
+            drum_map: (channel == batcherbird_core::drum_map::GM_DRUM_CHANNEL)
+                .then(batcherbird_core::drum_map::DrumMap::load_default),
+            readme: Some(readme_config(&sampling_config)),
+            hooks: profile_hooks(profile),
+            catalog: catalog_config(options, profile),
+            naming: naming_context(profile),
+            ..base_export_config(options)
+        };
+        
+        let exporter = SampleExporter::new(export_config)?;
+        let files = exporter.export_samples(&samples)?;
+        println!("✅ {}: {} files written", part_name, files.len());
+        Ok(())
+    }).await?;
+    
+    println!("\n✅ Multi-channel sampling complete: {}", output_root.display());
+    Ok(())
+}
+
+/// The two input groups sample-dual records side by side and how they are named, from its flags
+struct DualOptions {
+    start: u8,
+    end: u8,
+    /// Interface inputs (1-based) such as "1" or "1-2"
+    direct_inputs: String,
+    processed_inputs: String,
+    /// Filename suffix tokens for each group's takes
+    direct_name: String,
+    processed_name: String,
+    name: String,
+    format: String,
+    /// One subfolder per input group is written inside
+    output_dir: String,
+}
+
+async fn sample_dual(dual: DualOptions, options: &ExportOptions, profile: Option<&SynthProfile>) -> anyhow::Result<()> {
+    use batcherbird_core::{
+        sampler::SamplingEngine,
+        dual_input::{DualInputConfig, InputSource},
+        export::{SampleExporter, ExportConfig}
+    };
+    use std::path::PathBuf;
+
+    let DualOptions { start, end, direct_inputs, processed_inputs, direct_name, processed_name, name, format, output_dir } = dual;
+    if start > 127 || end > 127 || start > end {
+        println!("❌ Invalid note range: {}-{}. Notes must be 0-127 and start <= end.", start, end);
+        return Ok(());
+    }
+    let direct = (direct_inputs.as_str(), direct_name.as_str());
+    let processed = (processed_inputs.as_str(), processed_name.as_str());
+    let source = |(inputs, source_name): (&str, &str)| {
+        parse_program_list(inputs)
+            .filter(|list| list.iter().all(|&input| input >= 1))
+            .map(|list| InputSource {
+                name: source_name.to_string(),
+                channels: list.into_iter().map(|input| input as u16 - 1).collect(),
+            })
+    };
+    let inputs = match (source(direct), source(processed)) {
+        (Some(direct), Some(processed)) => DualInputConfig { direct, processed },
+        _ => {
+            println!("❌ Invalid input list: {} / {}. Use e.g. 1 or 3-4 (1-based interface inputs).", direct.0, processed.0);
+            return Ok(());
+        }
+    };
+    let Some(sample_format) = export_format(&format, false) else {
+        return Ok(());
+    };
+    warn_outside_profile_range(profile, start, end);
+
+    // Set up MIDI connection
+    let mut midi_manager = MidiManager::new()?;
+    let output_devices = midi_manager.list_output_devices()?;
+    
+    if output_devices.is_empty() {
+        println!("❌ No MIDI output devices found. {}", MIDI_SETUP_HINT);
+        return Ok(());
+    }
+    
+    // Use MiniFuse if available, otherwise first device
+    let device_index = output_devices.iter()
+        .position(|name| name.contains("MiniFuse"))
+        .unwrap_or(0);
+    println!("🎹 Using MIDI device: {}", output_devices[device_index]);
+    let mut midi_conn = midi_manager.connect_output(device_index)?;
+    
+    let output_root = PathBuf::from(output_dir);
+    let sampling_config = profile_sampling_config(profile);
+    let engine = SamplingEngine::new(sampling_config.clone())?;
+    let samples = engine.sample_dual_input_async(&mut midi_conn, start, end, &inputs).await?;
+    
+    // Each source is a complete instrument of its own, so they get separate folders
+    for source in inputs.sources() {
+        let source_samples: Vec<_> = samples.iter()
+            .filter(|sample| sample.metadata.input_source.as_deref() == Some(source.name.as_str()))
+            .cloned()
+            .collect();
+        let export_config = ExportConfig {
+            output_directory: output_root.join(&source.name),
+            naming_pattern: format!("{}_{{note_name}}_{{note}}_{{velocity}}_{{input}}.wav", name),
+            sample_format: sample_format.clone(),
+            detection_config: profile_detection_config(profile),
+            instrument_description: Some(format!("{} ({})", name, source.name)),
+            drum_map: profile_drum_map(profile),
+            readme: Some(readme_config(&sampling_config)),
+            hooks: profile_hooks(profile),
+            catalog: catalog_config(options, profile),
+            naming: naming_context(profile),
+            ..base_export_config(options)
+        };
+        
+        let exporter = SampleExporter::new(export_config)?;
+        let files = exporter.export_samples(&source_samples)?;
+        println!("✅ {}: {} files written", source.name, files.len());
+    }
+    
+    println!("\n✅ Dual-input sampling complete: {}", output_root.display());
+    Ok(())
+}
+
+fn send_sysex(file: Option<String>, hex: Option<String>, gap_ms: u64, reply_input: Option<usize>, reply_prefix: &str, timeout_ms: u64) -> anyhow::Result<()> {
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    let mut midi_manager = MidiManager::new()?;
+    let output_devices = midi_manager.list_output_devices()?;
+    
+    if output_devices.is_empty() {
+        println!("❌ No MIDI output devices found. {}", MIDI_SETUP_HINT);
+        return Ok(());
+    }
+    
+    // Use MiniFuse if available, otherwise first device
+    let device_index = output_devices.iter()
+        .position(|name| name.contains("MiniFuse"))
+        .unwrap_or(0);
+    println!("🎹 Using MIDI device: {}", output_devices[device_index]);
+    let mut midi_conn = midi_manager.connect_output(device_index)?;
+    
+    match (file, hex) {
+        (Some(file), None) => {
+            let sent = MidiManager::send_sysex_file(&mut midi_conn, &PathBuf::from(file), Duration::from_millis(gap_ms))?;
+            println!("✅ Sent {} SysEx message(s)", sent);
+        }
+        (None, Some(hex)) => {
+            let Some(request) = parse_hex_bytes(&hex) else {
+                println!("❌ Invalid hex bytes: {}", hex);
+                return Ok(());
+            };
+            
+            match reply_input {
+                Some(input_index) => {
+                    let Some(prefix) = parse_hex_bytes(reply_prefix) else {
+                        println!("❌ Invalid reply prefix: {}", reply_prefix);
+                        return Ok(());
+                    };
+                    let reply = midi_manager.sysex_request(&mut midi_conn, input_index, &request, &prefix, Duration::from_millis(timeout_ms))?;
+                    let reply_hex: Vec<String> = reply.iter().map(|b| format!("{:02X}", b)).collect();
+                    println!("📥 Reply ({} bytes): {}", reply.len(), reply_hex.join(" "));
+                }
+                None => {
+                    MidiManager::send_sysex(&mut midi_conn, &request)?;
+                    println!("✅ Sent SysEx ({} bytes)", request.len());
+                }
+            }
+        }
+        _ => println!("❌ Provide exactly one of --file or --hex"),
+    }
+    
+    Ok(())
+}
+
+fn send_sample_dump(file: &str, sample_number: u16, bits: u8, device_id: u8, reply_input: Option<usize>) -> anyhow::Result<()> {
+    use batcherbird_core::sds::{SampleDump, SdsConfig};
+    use std::path::Path;
+
+    let config = SdsConfig { device_id, sample_number, bits };
+    let dump = match SampleDump::from_wav_file(Path::new(file), &config) {
+        Ok(dump) => dump,
+        Err(e) => {
+            println!("❌ {}", e);
+            return Ok(());
+        }
+    };
+
+    let mut midi_manager = MidiManager::new()?;
+    let output_devices = midi_manager.list_output_devices()?;
+    if output_devices.is_empty() {
+        println!("❌ No MIDI output devices found. {}", MIDI_SETUP_HINT);
+        return Ok(());
+    }
+    let device_index = output_devices.iter()
+        .position(|name| name.contains("MiniFuse"))
+        .unwrap_or(0);
+    println!("🎹 Using MIDI device: {}", output_devices[device_index]);
+    let mut midi_conn = midi_manager.connect_output(device_index)?;
+
+    // The input connection must stay open for the whole transfer
+    let replies = reply_input.map(|index| midi_manager.connect_input_sysex(index)).transpose()?;
+    if let Err(e) = dump.send(&mut midi_conn, replies.as_ref().map(|(_, rx)| rx), device_id) {
+        println!("❌ {}", e);
+        return Ok(());
+    }
+    println!("✅ Sent {} to sample slot {}", file, sample_number);
+    Ok(())
+}
+
+/// Parse "F0 7E 7F" / "F07E7F" style hex strings
+fn parse_hex_bytes(hex: &str) -> Option<Vec<u8>> {
+    let digits: String = hex.chars().filter(|c| !c.is_whitespace() && *c != ',').collect();
+    if digits.is_empty() || !digits.len().is_multiple_of(2) || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).ok())
+        .collect()
+}
+
+async fn measure_velocity(note: u8, layers: usize, probes: Vec<u8>, profile: Option<&SynthProfile>) -> anyhow::Result<()> {
+    use batcherbird_core::sampler::SamplingEngine;
+
+    if note > 127 || layers == 0 || probes.len() < 2 || probes.iter().any(|&v| v == 0 || v > 127) {
+        println!("❌ Need a note 0-127, at least one layer and two or more probe velocities in 1-127.");
+        return Ok(());
+    }
+    
+    let mut midi_manager = MidiManager::new()?;
+    let output_devices = midi_manager.list_output_devices()?;
+    
+    if output_devices.is_empty() {
+        println!("❌ No MIDI output devices found. {}", MIDI_SETUP_HINT);
+        return Ok(());
+    }
+    
+    // Use MiniFuse if available, otherwise first device
+    let device_index = output_devices.iter()
+        .position(|name| name.contains("MiniFuse"))
+        .unwrap_or(0);
+    println!("🎹 Using MIDI device: {}", output_devices[device_index]);
+    let mut midi_conn = midi_manager.connect_output(device_index)?;
+    
+    let engine = SamplingEngine::new(profile_sampling_config(profile))?;
+    let response = engine.measure_velocity_response_async(&mut midi_conn, note, &probes).await?;
+    let suggested = response.place_layers(layers);
+    
+    let as_list: Vec<String> = suggested.iter().map(|v| v.to_string()).collect();
+    println!("\n✅ Suggested {} velocity layers: {}", suggested.len(), as_list.join(","));
+    println!("   Use with: --velocities {}", as_list.join(","));
+    Ok(())
+}
+
+fn identify_synth(input: Option<usize>, timeout_ms: u64) -> anyhow::Result<()> {
+    use std::time::Duration;
+
+    let mut midi_manager = MidiManager::new()?;
+    let output_devices = midi_manager.list_output_devices()?;
+    
+    if output_devices.is_empty() {
+        println!("❌ No MIDI output devices found. {}", MIDI_SETUP_HINT);
+        return Ok(());
+    }
+    
+    // Use MiniFuse if available, otherwise first device
+    let device_index = output_devices.iter()
+        .position(|name| name.contains("MiniFuse"))
+        .unwrap_or(0);
+    println!("🎹 Using MIDI device: {}", output_devices[device_index]);
+    
+    let input_index = match input {
+        Some(index) => index,
+        None => matching_input_index(&mut midi_manager, &output_devices[device_index])?,
+    };
+    let mut midi_conn = midi_manager.connect_output(device_index)?;
+    
+    let identity = midi_manager.identify_device(&mut midi_conn, input_index, Duration::from_millis(timeout_ms))?;
+    println!("   Manufacturer: {}", identity.manufacturer_name());
+    println!("   Family: {:04X}  Model: {:04X}", identity.family, identity.model);
+    println!("   Version: {}.{}.{}.{}", identity.version[0], identity.version[1], identity.version[2], identity.version[3]);
+    println!("   Suggested folder: {}", identity.folder_name());
+    Ok(())
+}
+
+/// MIDI input with the same name as the output (a synth's USB port shows up as both), else the first input
+fn matching_input_index(midi_manager: &mut MidiManager, output_name: &str) -> anyhow::Result<usize> {
+    let input_devices = midi_manager.list_input_devices()?;
+    if input_devices.is_empty() {
+        anyhow::bail!("No MIDI input devices found - the synth's reply needs a MIDI input");
+    }
+    
+    let index = input_devices.iter()
+        .position(|name| name == output_name)
+        .unwrap_or(0);
+    println!("🎹 Listening for reply on: {}", input_devices[index]);
+    Ok(index)
+}
+
+fn list_profiles() {
+    let library = ProfileLibrary::load_default();
+    
+    println!("🎛️ Synth profiles:");
+    for profile in library.profiles() {
+        println!("  {:<16} {} (ch {}, notes {}-{}, settle {}ms, panic {:?}, detection {:?})",
+            profile.id, profile.name, profile.midi_channel,
+            profile.note_range.start, profile.note_range.end,
+            profile.program_change_settle_ms, profile.panic_style, profile.detection_preset);
+    }
+    if let Some(dir) = ProfileLibrary::user_profile_dir() {
+        println!("\n   Add your own as [[profile]] tables in .toml files in: {}", dir.display());
+    }
+}
+
+/// Check the setup before a batch; stops with the consolidated report when a check fails
+async fn preflight(output_dir: &str, midi: bool, profile: Option<&SynthProfile>) -> anyhow::Result<()> {
+    use batcherbird_core::preflight::{self, PreflightConfig};
+    use std::path::PathBuf;
+
+    // The test note goes to the same device the sampling commands pick; a missing device is
+    // reported with the other checks
+    let connect = || -> Option<_> {
+        let mut midi_manager = MidiManager::new().ok()?;
+        let output_devices = midi_manager.list_output_devices().ok()?;
+        let device_index = output_devices.iter().position(|name| name.contains("MiniFuse")).or((!output_devices.is_empty()).then_some(0))?;
+        midi_manager.connect_output(device_index).ok()
+    };
+    let mut midi_conn = if midi { connect() } else { None };
+    let test_note = profile.map_or(60, |profile| profile.note_range.start / 2 + profile.note_range.end / 2);
+    let config = PreflightConfig {
+        output_directory: PathBuf::from(output_dir),
+        test_note: midi.then_some(test_note),
+        ..PreflightConfig::default()
+    };
+    let report = preflight::run_async(&profile_sampling_config(profile), midi_conn.as_mut(), &config).await;
+    print!("{}", report.summary());
+    report.into_result()?;
+    Ok(())
+}
+
+/// The batch plan describes and the audio it assumes, from its flags
+struct PlanOptions {
+    start: u8,
+    end: u8,
+    velocities: Vec<u8>,
+    round_robins: u8,
+    format: String,
+    /// None = the input interface's rate, or the plan default without one
+    sample_rate: Option<u32>,
+    channels: Option<u16>,
+    /// Print every take, not just the totals
+    list_takes: bool,
+    /// Check this naming pattern gives every take its own file name
+    naming_pattern: Option<String>,
+}
+
+fn plan_batch(plan: PlanOptions, profile: Option<&SynthProfile>) -> anyhow::Result<()> {
+    use batcherbird_core::{export::{BitDepth, ExportConfig, SampleExporter}, plan::{PlanAudio, PlanMatrix, SamplingPlan}};
+
+    let PlanOptions { start, end, velocities, round_robins, format, sample_rate, channels, list_takes, naming_pattern } = plan;
+    let Some(format) = export_format(&format, true) else {
+        return Ok(());
+    };
+    // Plan for the interface that would record, unless told otherwise
+    let input = AudioManager::new().ok().and_then(|audio| audio.input_device_and_config().ok());
+    let defaults = PlanAudio::default();
+    let audio = PlanAudio {
+        sample_rate: sample_rate.or(input.as_ref().map(|(_, config)| config.sample_rate().0)).unwrap_or(defaults.sample_rate),
+        channels: channels.or(input.as_ref().map(|(_, config)| config.channels())).unwrap_or(defaults.channels),
+        bit_depth: format.wav_bit_depth().unwrap_or(BitDepth::default()),
+    };
+    let matrix = PlanMatrix { start_note: start, end_note: end, velocities, round_robins };
+    let plan = SamplingPlan::new(&profile_sampling_config(profile), &matrix, audio)?;
+    print!("{}", plan.summary(list_takes));
+
+    if let Some(naming_pattern) = naming_pattern {
+        // The temp folder already exists, so checking names creates nothing on disk
+        let exporter = SampleExporter::new(ExportConfig {
+            output_directory: std::env::temp_dir(),
+            naming_pattern,
+            naming: naming_context(profile),
+            ..ExportConfig::default()
+        })?;
+        exporter.check_plan(&plan)?;
+        println!("🏷️ Naming pattern gives every take its own file name");
+    }
+    Ok(())
+}
+
+/// Selected profile, with `--clock-bpm`, the watchdog limits and any hook commands applied on
+/// top (to the generic profile if none was chosen)
+fn load_profile(id: Option<&str>, clock_bpm: Option<f32>, watchdog: Option<WatchdogConfig>, hooks: Option<HookConfig>) -> anyhow::Result<Option<SynthProfile>> {
+    if id.is_none() && clock_bpm.is_none() && watchdog.is_none() && hooks.is_none() {
+        return Ok(None);
+    }
+    
+    let library = ProfileLibrary::load_default();
+    let mut profile = library.require(id.unwrap_or("generic"))?.clone();
+    println!("🎛️ Using synth profile: {} ({})", profile.name, profile.id);
+    
+    if let Some(bpm) = clock_bpm {
+        batcherbird_core::midi_clock::MidiClockConfig::new(bpm)?;
+        profile.clock_bpm = Some(bpm);
+    }
+    if let Some(bpm) = profile.clock_bpm {
+        println!("⏱️ MIDI clock: {} BPM", bpm);
+    }
+    if watchdog.is_some() {
+        profile.watchdog = watchdog;
+    }
+    if hooks.is_some() {
+        profile.hooks = hooks;
+    }
+    Ok(Some(profile))
+}
+
+/// Default sampling settings, adjusted by the selected synth profile
+fn profile_sampling_config(profile: Option<&SynthProfile>) -> SamplingConfig {
+    match profile {
+        Some(profile) => profile.apply_to(SamplingConfig::default()),
+        None => SamplingConfig::default(),
+    }
+}
+
+/// Where to look when no MIDI port shows up
+#[cfg(target_os = "macos")]
+const MIDI_SETUP_HINT: &str = "Connect a MIDI device or enable IAC Driver in Audio MIDI Setup.";
+#[cfg(target_os = "windows")]
+const MIDI_SETUP_HINT: &str = "Connect a MIDI device or install a loopback driver such as loopMIDI.";
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const MIDI_SETUP_HINT: &str = "Connect a MIDI device or load the snd-virmidi module for a virtual ALSA port.";
+
+/// Export settings from the global flags, shared by every command that writes samples
+struct ExportOptions {
+    /// `--catalog`
+    catalog: bool,
+    overwrite: OverwritePolicy,
+    dither: Dither,
+    fade_in_curve: FadeCurve,
+    fade_out_curve: FadeCurve,
+    layout: FolderLayout,
+    ui_background: Option<std::path::PathBuf>,
+    velocity_crossfade: Option<f32>,
+    gain_matching: bool,
+}
+
+impl ExportOptions {
+    /// Parse the global export flags, naming the one that is invalid
+    fn from_cli(cli: &Cli) -> Result<Self, String> {
+        let overwrite = OverwritePolicy::parse(&cli.overwrite)
+            .ok_or_else(|| format!("Unknown overwrite policy '{}'. Use overwrite, rename, skip or error.", cli.overwrite))?;
+        let dither = Dither::parse(&cli.dither)
+            .ok_or_else(|| format!("Unknown dither '{}'. Use off, tpdf or shaped.", cli.dither))?;
+        let (Some(fade_in_curve), Some(fade_out_curve)) = (FadeCurve::parse(&cli.fade_in_curve), FadeCurve::parse(&cli.fade_out_curve)) else {
+            return Err(format!("Unknown fade curve '{}' / '{}'. Use linear, exponential or cosine.", cli.fade_in_curve, cli.fade_out_curve));
+        };
+        let layout = FolderLayout::parse(&cli.layout)
+            .ok_or_else(|| format!("Unknown folder layout '{}'. Use flat, velocity or samples.", cli.layout))?;
+        Ok(Self {
+            catalog: cli.catalog,
+            overwrite,
+            dither,
+            fade_in_curve,
+            fade_out_curve,
+            layout,
+            ui_background: cli.ui_background.clone(),
+            velocity_crossfade: cli.velocity_crossfade,
+            gain_matching: cli.gain_match,
+        })
+    }
+}
+
+/// Export config with the global flags applied; commands set the rest and take defaults for
+/// anything they don't care about
+fn base_export_config(options: &ExportOptions) -> ExportConfig {
+    ExportConfig {
+        overwrite: options.overwrite,
+        dither: options.dither,
+        fade_in_curve: options.fade_in_curve,
+        fade_out_curve: options.fade_out_curve,
+        layout: options.layout,
+        ui_background: options.ui_background.clone(),
+        velocity_crossfade: options.velocity_crossfade,
+        gain_matching: options.gain_matching,
+        ..ExportConfig::default()
+    }
+}
+
+/// Catalog recording for an export when `--catalog` is set, with the recording device and the
+/// selected profile's capture settings
+fn catalog_config(options: &ExportOptions, profile: Option<&SynthProfile>) -> Option<CatalogConfig> {
+    options.catalog.then(|| CatalogConfig {
+        path: None,
+        midi_device: None,
+        audio_device: AudioManager::new().ok().and_then(|audio| audio.input_device_name()),
+        sampling: Some(profile_sampling_config(profile)),
+    })
+}
+
+/// {synth} in file names: the selected profile's name
+fn naming_context(profile: Option<&SynthProfile>) -> NamingContext {
+    NamingContext { session: None, synth: profile.map(|profile| profile.name.clone()) }
+}
+
+/// Hook commands from the selected profile, for the exporter (note hooks go through the sampling config)
+fn profile_hooks(profile: Option<&SynthProfile>) -> HookConfig {
+    profile.and_then(|profile| profile.hooks.clone()).unwrap_or_default()
+}
+
+/// Export format by name: sfz, decentsampler, another registered instrument format, or
+/// (when `allow_wav`) plain WAVs. Prints the choices when the name is unknown.
+fn export_format(name: &str, allow_wav: bool) -> Option<batcherbird_core::export::AudioFormat> {
+    use batcherbird_core::{export::AudioFormat, instrument_format};
+
+    let format = AudioFormat::parse(name).filter(|format| allow_wav || format.is_instrument());
+    if format.is_none() {
+        let mut choices = instrument_format::names();
+        if allow_wav {
+            choices.push("wav".to_string());
+        }
+        println!("❌ Unsupported format: {}. Use {}.", name, choices.join(", "));
+    }
+    format
+}
+
+/// Hardware sampler limits by id; prints the available targets when the id is unknown
+fn hardware_target(id: &str) -> Option<batcherbird_core::hardware_target::HardwareTarget> {
+    use batcherbird_core::hardware_target::TargetLibrary;
+
+    match TargetLibrary::load_default().require(id) {
+        Ok(target) => Some(target.clone()),
+        Err(e) => {
+            println!("❌ {}", e);
+            None
+        }
+    }
+}
+
+/// Instrument word size and hardware copy for `--bit-depth`/`--hardware-copy`; None after
+/// reporting an invalid value
+fn export_outputs(bit_depth: &str, hardware_copy: Option<&str>) -> Option<(batcherbird_core::export::BitDepth, Vec<batcherbird_core::export::AudioOutput>)> {
+    use batcherbird_core::export::{AudioOutput, BitDepth};
+
+    let Some(bit_depth) = BitDepth::parse(bit_depth) else {
+        println!("❌ Invalid bit depth: {}. Use 16, 24 or 32f.", bit_depth);
+        return None;
+    };
+    let outputs = match hardware_copy.map(hardware_target) {
+        Some(None) => return None,
+        Some(Some(target)) => vec![AudioOutput { subdirectory: target.id.clone(), bit_depth: BitDepth::Int16, target: Some(target) }],
+        None => Vec::new(),
+    };
+    Some((bit_depth, outputs))
+}
+
+fn profile_detection_config(profile: Option<&SynthProfile>) -> DetectionConfig {
+    profile.map(|p| p.detection_config()).unwrap_or_default()
+}
+
+/// README settings for an exported instrument folder, listing how it was sampled
+fn readme_config(sampling: &SamplingConfig) -> batcherbird_core::readme::ReadmeConfig {
+    use batcherbird_core::readme::ReadmeConfig;
+
+    ReadmeConfig {
+        sampling: Some(sampling.clone()),
+        ..ReadmeConfig::load_default()
+    }
+}
+
+/// GM drum names when the profile plays on channel 10 (drum mode)
+fn profile_drum_map(profile: Option<&SynthProfile>) -> Option<batcherbird_core::drum_map::DrumMap> {
+    use batcherbird_core::drum_map::{DrumMap, GM_DRUM_CHANNEL};
+
+    profile
+        .filter(|p| p.channel_index() == GM_DRUM_CHANNEL)
+        .map(|_| DrumMap::load_default())
+}
+
+fn warn_outside_profile_range(profile: Option<&SynthProfile>, start: u8, end: u8) {
+    if let Some(profile) = profile {
+        if !profile.contains_note(start) || !profile.contains_note(end) {
+            println!("⚠️ Notes {}-{} are outside the {} range ({}-{})",
+                start, end, profile.name, profile.note_range.start, profile.note_range.end);
+        }
+    }
+}
+
+#[cfg(feature = "image")]
+fn render_preview(input: &str, columns: u32) {
+    use batcherbird_core::preview::{write_contact_sheet, ContactSheetConfig};
+
+    let config = ContactSheetConfig { columns, ..ContactSheetConfig::default() };
+    match write_contact_sheet(std::path::Path::new(input), &config) {
+        Ok(path) => println!("✅ Preview written: {}", path.display()),
+        Err(e) => println!("❌ {}", e),
+    }
+}
+
+fn generate_demo(output_dir: String) -> anyhow::Result<()> {
+    use batcherbird_core::demo::generate_demo_project;
+    use std::path::PathBuf;
+
+    let project = generate_demo_project(&PathBuf::from(output_dir))?;
+    
+    println!("\n✅ Demo project created: {}", project.directory.display());
+    println!("   Samples: {}", project.sample_files.len());
+    for instrument in &project.instrument_files {
+        println!("   Instrument: {}", instrument.display());
+    }
+    println!("   Manifest: {}", project.manifest_path.display());
+    println!("   Report: {}", project.report_path.display());
+    Ok(())
+}
+
+/// The sessions merge combines and how it writes the result, from its flags
+struct MergeOptions {
+    /// Exported session folders; a later session wins where two recorded the same note
+    inputs: Vec<String>,
+    format: String,
+    name: String,
+    output_dir: String,
+    dual_mono: bool,
+    target: Option<String>,
+    bit_depth: String,
+    hardware_copy: Option<String>,
+}
+
+fn merge_sessions(merge: MergeOptions, options: &ExportOptions) -> anyhow::Result<()> {
+    use batcherbird_core::{
+        library::{self, LibrarySession},
+        manifest::SessionManifest,
+        export::{SampleExporter, ExportConfig, AudioFormat}
+    };
+    use std::path::{Path, PathBuf};
+
+    let MergeOptions { inputs, format, name, output_dir, dual_mono, target, bit_depth, hardware_copy } = merge;
+    let Some(mut sample_format) = export_format(&format, false) else {
+        return Ok(());
+    };
+    let target = match target.as_deref().map(hardware_target) {
+        Some(None) => return Ok(()),
+        Some(Some(target)) => {
+            sample_format = AudioFormat::Wav16Bit;
+            Some(target)
+        }
+        None => None,
+    };
+    let Some((instrument_bit_depth, additional_outputs)) = export_outputs(&bit_depth, hardware_copy.as_deref()) else {
+        return Ok(());
+    };
+    let output_dir = PathBuf::from(output_dir);
+    if inputs.iter().any(|input| Path::new(input) == output_dir) {
+        println!("❌ Output folder must differ from the input sessions.");
+        return Ok(());
+    }
+
+    let mut sessions = Vec::new();
+    for input in &inputs {
+        let session = LibrarySession::load(Path::new(input))?;
+        for file in &session.unrecognized {
+            println!("   ⚠️ Skipping {}: no note/velocity in the file name", file.display());
+        }
+        sessions.push(session);
+    }
+    
+    let merged = match library::merge_sessions(&sessions) {
+        Ok(merged) => merged,
+        Err(e) => {
+            println!("❌ {}", e);
+            return Ok(());
+        }
+    };
+    for duplicate in &merged.duplicates {
+        println!("   🔁 Note {} vel {}: using {} over {}",
+            duplicate.note, duplicate.velocity, duplicate.kept.display(), duplicate.dropped.display());
+    }
+    
+    // Samples were processed when first exported, so they are written back untouched
+    let export_config = ExportConfig {
+        output_directory: output_dir.clone(),
+        naming_pattern: format!("{}_{{note_name}}_{{note}}_{{velocity}}.wav", name),
+        sample_format,
+        fade_out_ms: 0.0,
+        apply_detection: false,
+        instrument_description: Some(format!("Merged from {} sessions", sessions.len())),
+        dual_mono,
+        instrument_bit_depth,
+        additional_outputs,
+        ..base_export_config(options)
+    };
+    
+    let mut samples: Vec<_> = merged.samples.into_iter().map(|s| s.sample).collect();
+    if let Some(target) = &target {
+        let (fitted, report) = target.fit(&samples);
+        print!("{}", report.summary());
+        samples = fitted;
+    }
+    let exporter = SampleExporter::new(export_config)?;
+    let files = exporter.export_samples(&samples)?;
+    
+    let mut manifest = SessionManifest::new(&name);
+    for (sample, file) in samples.iter().zip(&files) {
+        manifest.add_sample(sample, file);
+    }
+    for file in &files[samples.len()..] {
+        manifest.add_instrument_file(file);
+    }
+    manifest.record_normalization_gains(&exporter.normalization_gains());
+    manifest.save(&output_dir)?;
+    manifest.write_report(&output_dir)?;
+    
+    println!("\n✅ Merged {} sessions: {} samples ({} duplicates replaced, {} Hz, {} ch)",
+        sessions.len(), samples.len(), merged.duplicates.len(), merged.sample_rate, merged.channels);
+    println!("   📁 {}", output_dir.display());
+    Ok(())
+}
+
+fn renormalize_library(inputs: &[String], target_db: Option<f32>, rms: bool, lufs: bool, opcodes: bool, dry_run: bool) -> anyhow::Result<()> {
+    use batcherbird_core::renormalize::{
+        apply_session_gain, plan_renormalization, session_directories,
+        GainMode, LevelMeasure, RenormalizeConfig
+    };
+    use std::path::Path;
+
+    let mut directories = Vec::new();
+    for input in inputs {
+        directories.extend(session_directories(Path::new(input))?);
+    }
+    
+    let measure = if lufs {
+        LevelMeasure::Lufs
+    } else if rms {
+        LevelMeasure::Rms
+    } else {
+        LevelMeasure::Peak
+    };
+    let config = RenormalizeConfig {
+        measure,
+        target_db,
+        mode: if opcodes { GainMode::VolumeOpcodes } else { GainMode::RewriteFiles },
+        ..RenormalizeConfig::default()
+    };
+    let plan = match plan_renormalization(&directories, &config) {
+        Ok(plan) => plan,
+        Err(e) => {
+            println!("❌ {}", e);
+            return Ok(());
+        }
+    };
+    
+    match measure {
+        LevelMeasure::Lufs => println!("🎚️ Target loudness: {:.1} LUFS", plan.target_db),
+        LevelMeasure::Rms => println!("🎚️ Target RMS level: {:.1} dBFS", plan.target_db),
+        LevelMeasure::Peak => println!("🎚️ Target peak level: {:.1} dBFS", plan.target_db),
+    }
+    for session in &plan.sessions {
+        println!("   {:<40} level {:>6.1} dB  peak {:>6.1} dB  gain {:>+5.1} dB{}",
+            session.directory.display(), session.level_db, session.peak_db, session.gain_db,
+            if session.limited { " (limited to avoid clipping)" } else { "" });
+    }
+    
+    if dry_run {
+        println!("\n📝 Dry run: no files changed");
+        return Ok(());
+    }
+    
+    let mut changed = 0;
+    for session in &plan.sessions {
+        changed += apply_session_gain(session, config.mode)?.len();
+    }
+    println!("\n✅ Renormalized {} sessions ({} files changed)", plan.sessions.len(), changed);
+    Ok(())
+}
+
+/// Parse "0-15" / "0,4,9-12" style program lists
+fn edit_sample(file: &str, start: usize, end: Option<usize>, loop_points: Option<(usize, usize)>, output: Option<String>) -> anyhow::Result<()> {
+    use batcherbird_core::edit::{apply_edit, edited_file_path, LoopPoints, SampleEdit};
+    use std::path::{Path, PathBuf};
+
+    let source = Path::new(file);
+    let destination = output.map(PathBuf::from).unwrap_or_else(|| edited_file_path(source));
+    let edit = SampleEdit {
+        trim_start: start,
+        trim_end: end,
+        loop_points: loop_points.map(|(start, end)| LoopPoints { start, end }),
+        ..SampleEdit::default()
+    };
+
+    match apply_edit(source, &destination, &edit) {
+        Ok(()) => println!("✅ Saved edit: {}", destination.display()),
+        Err(e) => println!("❌ {}", e),
+    }
+    Ok(())
+}
+
+fn extract_wavetable(file: &str, frames: usize, frame_size: usize, output: Option<String>) -> anyhow::Result<()> {
+    use batcherbird_core::wavetable::{wavetable_file_path, write_file_wavetable, WavetableConfig};
+    use std::path::{Path, PathBuf};
+
+    let source = Path::new(file);
+    let destination = output.map(PathBuf::from).unwrap_or_else(|| wavetable_file_path(source));
+    match write_file_wavetable(source, &destination, &WavetableConfig { frames, frame_size }) {
+        Ok(table) => println!("✅ Saved {} frame wavetable: {}", table.frames.len(), destination.display()),
+        Err(e) => println!("❌ {}", e),
+    }
+    Ok(())
+}
+
+fn render_edits(input: &str, output: Option<String>) -> anyhow::Result<()> {
+    use batcherbird_core::edit::render_directory;
+    use std::path::{Path, PathBuf};
+
+    let input = Path::new(input);
+    let output = output.map(PathBuf::from).unwrap_or_else(|| input.join("Rendered"));
+    match render_directory(input, &output) {
+        Ok(rendered) => println!("✅ Rendered {} samples into {}", rendered.len(), output.display()),
+        Err(e) => println!("❌ {}", e),
+    }
+    Ok(())
+}
+
+fn list_sessions(query: &CatalogQuery, show: Option<&str>) -> anyhow::Result<()> {
+    let catalog = SessionCatalog::open_default()?;
+    
+    if let Some(id) = show {
+        let Some(session) = catalog.find(id)? else {
+            println!("❌ No single cataloged session matches '{}'", id);
+            return Ok(());
+        };
+        println!("🗂️ {}", session.summary());
+        println!("   📁 {}", session.directory.display());
+        for device in [&session.midi_device, &session.audio_device].into_iter().flatten() {
+            println!("   🎛️ {}", device);
+        }
+        println!("   {}, normalize {}, detection {}", session.settings.format, session.settings.normalize, session.settings.apply_detection);
+        for entry in &session.samples {
+            let issues = session.qa_suspects.iter().find(|suspect| suspect.file == entry.file)
+                .map(|suspect| suspect.issues.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "));
+            println!("  {:<36} {:>4} vel {:>3}  {:>5.2}s  peak {:>6.1} dB{}",
+                entry.file, entry.note_name, entry.velocity, entry.duration_sec, entry.peak_db,
+                issues.map(|issues| format!("  ⚠️ {}", issues)).unwrap_or_default());
+        }
+        return Ok(());
+    }
+    
+    let sessions = catalog.query(query)?;
+    if sessions.is_empty() {
+        println!("🗂️ No cataloged sessions match (record sessions with --catalog)");
+        return Ok(());
+    }
+    println!("🗂️ Sessions ({} of {} in {}):", sessions.len(), catalog.session_count()?, catalog.path().display());
+    for session in sessions {
+        println!("  {}", session.summary());
+    }
+    Ok(())
+}
+
+fn package_instrument(input: &str, format: &str, name: Option<String>, license: Option<String>, output: Option<String>) -> anyhow::Result<()> {
+    use batcherbird_core::package::{self, PackageConfig, PackageFormat};
+    use std::path::{Path, PathBuf};
+
+    let Some(format) = PackageFormat::parse(format) else {
+        println!("❌ Unknown package format: {}. Use zip or dslibrary.", format);
+        return Ok(());
+    };
+    let license = match license {
+        Some(license) if Path::new(&license).is_file() => Some(std::fs::read_to_string(&license)?),
+        license => license,
+    };
+    let config = PackageConfig { format, name, license, output: output.map(PathBuf::from) };
+    let archive = package::package_directory(Path::new(input), &config)?;
+    println!("📦 {}", archive.display());
+    Ok(())
+}
+
+fn qa_check(input: &str, no_pitch: bool) -> anyhow::Result<()> {
+    use batcherbird_core::qa::{QaConfig, QaReport};
+    use std::path::Path;
+
+    let config = QaConfig { check_pitch: !no_pitch, ..QaConfig::default() };
+    match QaReport::check_directory(Path::new(input), &config) {
+        Ok(report) => {
+            print!("{}", report.summary());
+            report.save(Path::new(input))?;
+            if report.suspects.is_empty() {
+                println!("✅ All {} samples passed", report.checked);
+            }
+        }
+        Err(e) => println!("❌ {}", e),
+    }
+    Ok(())
+}
+
+fn rerecord(input: &str, notes: Option<&str>, from_qa: bool, version: bool, format: &str, normalize: bool, profile: Option<&SynthProfile>) -> anyhow::Result<()> {
+    use batcherbird_core::{
+        export::ExportConfig,
+        rerecord::{rerecord_session, RerecordEntry, RerecordMode, TAKES_DIRECTORY},
+        sampler::SamplingEngine,
+    };
+    use std::path::PathBuf;
+
+    let Some(sample_format) = export_format(format, true) else {
+        return Ok(());
+    };
+    let directory = PathBuf::from(input);
+    let mut entries = Vec::new();
+    if let Some(spec) = notes {
+        match RerecordEntry::parse_list(spec) {
+            Ok(parsed) => entries.extend(parsed),
+            Err(e) => {
+                println!("❌ {}", e);
+                return Ok(());
+            }
+        }
+    }
+    if from_qa {
+        match RerecordEntry::from_qa_report(&directory) {
+            Ok(suspects) => entries.extend(suspects),
+            Err(e) => {
+                println!("❌ No QA report to read ({}). Run `batcherbird qa {}` first.", e, input);
+                return Ok(());
+            }
+        }
+    }
+    if entries.is_empty() {
+        println!("❌ Nothing to record: pass --notes and/or --from-qa.");
+        return Ok(());
+    }
+    println!("🔁 Recording {} takes again into {}", entries.len(), directory.display());
+
+    let mut midi_manager = MidiManager::new()?;
+    let output_devices = midi_manager.list_output_devices()?;
+    if output_devices.is_empty() {
+        println!("❌ No MIDI output devices found. {}", MIDI_SETUP_HINT);
+        return Ok(());
+    }
+    let device_index = output_devices.iter()
+        .position(|name| name.contains("MiniFuse"))
+        .unwrap_or(0);
+    println!("🎹 Using MIDI device: {}", output_devices[device_index]);
+    let mut midi_conn = midi_manager.connect_output(device_index)?;
+
+    let engine = SamplingEngine::new(profile_sampling_config(profile))?;
+    let config = ExportConfig {
+        output_directory: directory.clone(),
+        naming_pattern: "{note_name}_{note}_{velocity}.wav".to_string(),
+        sample_format,
+        normalize,
+        detection_config: profile_detection_config(profile),
+        ..ExportConfig::default()
+    };
+    let mode = if version { RerecordMode::Version } else { RerecordMode::Overwrite };
+
+    match rerecord_session(&engine, &mut midi_conn, config, &entries, mode) {
+        Ok(result) => {
+            println!("\n✅ Replaced {} takes, added {}", result.replaced.len(), result.added.len());
+            for file in result.replaced.iter().chain(&result.added) {
+                println!("   📄 {}", file.display());
+            }
+            if !result.archived.is_empty() {
+                println!("   🗄️ Earlier takes kept in {}", directory.join(TAKES_DIRECTORY).display());
+            }
+            for (note, velocity) in &result.skipped {
+                println!("   ⚠️ {} vel {} timed out and was not recorded", sample_note_name(*note), velocity);
+            }
+        }
+        Err(e) => println!("❌ {}", e),
+    }
+    Ok(())
+}
+
+/// Where reprocess reads and writes and which processing it applies, from its flags
+struct ReprocessOptions {
+    input: String,
+    /// None = a Reprocessed folder inside the input
+    output: Option<String>,
+    format: String,
+    name: String,
+    /// None = keep the files' start and end as they are
+    detection: Option<DetectionConfig>,
+    normalize: bool,
+    fade_in_ms: f32,
+    fade_out_ms: f32,
+    loops: bool,
+    classify: bool,
+    align_onsets_ms: Option<f32>,
+    normalize_lufs: Option<f32>,
+    remove_hum: bool,
+    target: Option<String>,
+}
+
+fn reprocess(run: ReprocessOptions, options: &ExportOptions) -> anyhow::Result<()> {
+    use batcherbird_core::{export::{AudioFormat, ExportConfig}, reprocess::reprocess_directory};
+    use std::path::{Path, PathBuf};
+
+    let ReprocessOptions {
+        input, output, format, name, detection, normalize, fade_in_ms, fade_out_ms, loops, classify, align_onsets_ms, normalize_lufs, remove_hum, target,
+    } = run;
+    let Some(mut sample_format) = export_format(&format, true) else {
+        return Ok(());
+    };
+    let target = match target.as_deref().map(hardware_target) {
+        Some(None) => return Ok(()),
+        Some(Some(target)) => {
+            sample_format = AudioFormat::Wav16Bit;
+            Some(target)
+        }
+        None => None,
+    };
+    let input = Path::new(&input);
+    let output_directory = output.map(PathBuf::from).unwrap_or_else(|| input.join("Reprocessed"));
+    let config = ExportConfig {
+        output_directory: output_directory.clone(),
+        naming_pattern: format!("{}_{{note_name}}_{{note}}_{{velocity}}.wav", name),
+        sample_format,
+        normalize: normalize || normalize_lufs.is_some(),
+        fade_in_ms,
+        fade_out_ms,
+        apply_detection: detection.is_some(),
+        detection_config: detection.unwrap_or_default(),
+        apply_loop_detection: loops,
+        classify_samples: classify,
+        align_onsets_ms,
+        loudness_target_lufs: normalize_lufs,
+        remove_hum,
+        ..base_export_config(options)
+    };
+
+    match reprocess_directory(input, config, target.as_ref()) {
+        Ok(result) => {
+            for file in &result.unrecognized {
+                println!("   ⚠️ {}: no note/velocity in the file name, left out of the instrument", file.display());
+            }
+            println!("\n✅ Reprocessed {} samples", result.samples.len() + result.unrecognized.len());
+            for instrument in &result.instrument_files {
+                println!("   Instrument: {}", instrument.display());
+            }
+            println!("   📁 {}", output_directory.display());
+        }
+        Err(e) => println!("❌ {}", e),
+    }
+    Ok(())
+}
+
+fn import_samples(input: &str, output: Option<String>, format: &str, patterns: Vec<String>, velocity: u8, no_pitch: bool, options: &ExportOptions) -> anyhow::Result<()> {
+    use batcherbird_core::{export::ExportConfig, import::{self, ImportConfig}};
+    use std::path::{Path, PathBuf};
+
+    let Some(sample_format) = export_format(format, false) else {
+        return Ok(());
+    };
+    let input = Path::new(input);
+    let output_directory = output.map(PathBuf::from).unwrap_or_else(|| input.join("Imported"));
+    let config = ImportConfig {
+        patterns: patterns.into_iter().chain(ImportConfig::default().patterns).collect(),
+        detect_pitch: !no_pitch,
+        default_velocity: velocity,
+    };
+    // The audio is written back as it is; only names and mapping are Batcherbird's
+    let export = ExportConfig {
+        output_directory: output_directory.clone(),
+        naming_pattern: "{session}_{note_name}_{note}_{velocity}.wav".to_string(),
+        sample_format,
+        fade_out_ms: 0.0,
+        apply_detection: false,
+        ..base_export_config(options)
+    };
+
+    match import::import_library(input, &output_directory, &config, &export) {
+        Ok(presets) if presets.is_empty() => println!("❌ No samples could be mapped under {}", input.display()),
+        Ok(presets) => {
+            for preset in &presets {
+                for file in &preset.unrecognized {
+                    println!("   ⚠️ {}: no note in the name, chunks or pitch, left out", file.display());
+                }
+                let instrument_count = preset.files.iter().filter(|file| file.extension().is_none_or(|ext| ext != "wav")).count();
+                println!("✅ {} -> {} ({} samples)", preset.source.display(), preset.output_directory.display(),
+                    preset.files.len() - instrument_count);
+            }
+            println!("\n✅ Imported {} instruments into {}", presets.len(), output_directory.display());
+        }
+        Err(e) => println!("❌ {}", e),
+    }
+    Ok(())
+}
+
+fn audition_loop(file: &str, start: usize, end: usize, crossfade_ms: f32) -> anyhow::Result<()> {
+    use batcherbird_core::playback::play_loop;
+    use std::io::BufRead;
+    use std::path::Path;
+
+    let (mut loop_start, mut loop_end, mut crossfade) = (start, end, crossfade_ms);
+    let mut handle = match play_loop(Path::new(file), loop_start, loop_end, 0) {
+        Ok(handle) => handle,
+        Err(e) => {
+            println!("❌ {}", e);
+            return Ok(());
+        }
+    };
+    // The crossfade is given in ms; the file's rate is known once it is loaded
+    let sample_rate = handle.sample_rate();
+    let to_frames = |ms: f32| (ms.max(0.0) * sample_rate as f32 / 1000.0) as usize;
+    handle.set_loop_points(loop_start, loop_end, to_frames(crossfade))?;
+
+    println!("🎧 Commands: s <sample> / e <sample> set a point, s+N s-N e+N e-N nudge, x <ms> crossfade, q quit");
+    for line in std::io::stdin().lock().lines() {
+        let line = line?;
+        let command = line.trim();
+        if command == "q" {
+            break;
+        }
+        if command.is_empty() {
+            continue;
+        }
+
+        let (mut new_start, mut new_end) = (loop_start as i64, loop_end as i64);
+        let (point, rest) = command.split_at(command.len().min(1));
+        let rest = rest.trim();
+        let parsed = match point {
+            "s" | "e" => {
+                let target = if point == "s" { &mut new_start } else { &mut new_end };
+                if let Some(delta) = rest.strip_prefix('+') {
+                    delta.parse::<i64>().map(|delta| *target += delta).is_ok()
+                } else if rest.starts_with('-') {
+                    rest.parse::<i64>().map(|delta| *target += delta).is_ok()
+                } else {
+                    rest.parse::<i64>().map(|value| *target = value).is_ok()
+                }
+            }
+            "x" => rest.parse::<f32>().map(|ms| crossfade = ms).is_ok(),
+            _ => false,
+        };
+        if !parsed || new_start < 0 || new_end < 0 {
+            println!("❌ Unrecognized command: {}", command);
+            continue;
+        }
+
+        match handle.set_loop_points(new_start as usize, new_end as usize, to_frames(crossfade)) {
+            Ok(()) => {
+                (loop_start, loop_end) = (new_start as usize, new_end as usize);
+                println!("🔁 Loop {}..{} ({} samples, {:.1}ms crossfade)", loop_start, loop_end, loop_end - loop_start, crossfade);
+            }
+            Err(e) => println!("❌ {}", e),
+        }
+    }
+
+    handle.stop();
+    println!("✅ Final loop points: start {} end {} (length {} samples)", loop_start, loop_end, loop_end - loop_start);
+    Ok(())
+}
+
+fn parse_program_list(spec: &str) -> Option<Vec<u8>> {
+    let mut programs = Vec::new();
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let first: u8 = first.trim().parse().ok()?;
+                let last: u8 = last.trim().parse().ok()?;
+                if first > last || last > 127 {
+                    return None;
+                }
+                programs.extend(first..=last);
+            }
+            None => {
+                let program: u8 = part.parse().ok()?;
+                if program > 127 {
+                    return None;
+                }
+                programs.push(program);
+            }
+        }
+    }
+    if programs.is_empty() { None } else { Some(programs) }
+}
+
 fn sample_note_name(note: u8) -> String {
     let note_names = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
     let octave = (note / 12).saturating_sub(1);
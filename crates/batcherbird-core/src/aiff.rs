@@ -0,0 +1,198 @@
+//! AIFF/AIFF-C reading, for importing libraries made on Macs and hardware samplers. Only
+//! uncompressed audio is supported: big-endian PCM, little-endian PCM ("sowt") and 32-bit float.
+
+use crate::edit::LoopPoints;
+use crate::import::SamplerChunks;
+use crate::{BatcherbirdError, Result};
+use std::path::Path;
+
+/// An IFF chunk: id and body
+pub(crate) struct Chunk<'a> {
+    pub id: &'a [u8],
+    pub body: &'a [u8],
+}
+
+/// Chunks of a FORM AIFF/AIFC file, or None if `bytes` isn't one
+pub(crate) fn chunks(bytes: &[u8]) -> Option<Vec<Chunk<'_>>> {
+    if bytes.len() < 12 || &bytes[0..4] != b"FORM" || !matches!(&bytes[8..12], b"AIFF" | b"AIFC") {
+        return None;
+    }
+    let mut chunks = Vec::new();
+    let mut offset = 12;
+    while let (Some(id), Some(size)) = (bytes.get(offset..offset + 4), read_u32(bytes, offset + 4)) {
+        let body = offset + 8;
+        let end = (body + size as usize).min(bytes.len());
+        chunks.push(Chunk { id, body: &bytes[body..end] });
+        // Chunks are padded to an even length
+        offset = body + size as usize + (size as usize & 1);
+    }
+    Some(chunks)
+}
+
+pub(crate) fn read_u16(bytes: &[u8], at: usize) -> Option<u16> {
+    bytes.get(at..at + 2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+pub(crate) fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    bytes.get(at..at + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// 80-bit IEEE extended float, as COMM stores the sample rate
+fn read_extended(bytes: &[u8]) -> f64 {
+    let exponent = (((bytes[0] & 0x7f) as i32) << 8) | bytes[1] as i32;
+    let mantissa = u64::from_be_bytes([bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7], bytes[8], bytes[9]]);
+    let value = mantissa as f64 * 2f64.powi(exponent - 16383 - 63);
+    if bytes[0] & 0x80 != 0 { -value } else { value }
+}
+
+/// Read an AIFF as interleaved f32 in -1.0..1.0, with its format as a WAV spec
+pub fn read_aiff(path: &Path) -> Result<(Vec<f32>, hound::WavSpec)> {
+    let invalid = |reason: &str| BatcherbirdError::Processing(format!("Failed to read {}: {}", path.display(), reason));
+    let bytes = std::fs::read(path)?;
+    let chunks = chunks(&bytes).ok_or_else(|| invalid("not an AIFF file"))?;
+
+    let comm = chunks.iter().find(|chunk| chunk.id == b"COMM").ok_or_else(|| invalid("no COMM chunk"))?.body;
+    if comm.len() < 18 {
+        return Err(invalid("truncated COMM chunk"));
+    }
+    let channels = read_u16(comm, 0).unwrap_or(0);
+    let frames = read_u32(comm, 2).unwrap_or(0) as usize;
+    let bits = read_u16(comm, 6).unwrap_or(0);
+    let sample_rate = read_extended(&comm[8..18]).round() as u32;
+    let compression = comm.get(18..22).unwrap_or(&b"NONE"[..]);
+    if channels == 0 || sample_rate == 0 {
+        return Err(invalid("no channels or sample rate"));
+    }
+
+    let ssnd = chunks.iter().find(|chunk| chunk.id == b"SSND").ok_or_else(|| invalid("no SSND chunk"))?.body;
+    let data_offset = 8 + read_u32(ssnd, 0).unwrap_or(0) as usize;
+    let data = ssnd.get(data_offset..).unwrap_or_default();
+    let bytes_per_sample = (bits as usize).div_ceil(8);
+    let count = (frames * channels as usize).min(data.len() / bytes_per_sample.max(1));
+
+    let (audio, sample_format): (Vec<f32>, _) = match (compression, bits) {
+        (b"NONE" | b"twos" | b"sowt", 8..=32) => {
+            let little_endian = compression == b"sowt";
+            let scale = (1i64 << (bytes_per_sample * 8 - 1)) as f32;
+            let audio = data.chunks_exact(bytes_per_sample).take(count)
+                .map(|raw| {
+                    // Most significant byte first, then shift down to sign-extend
+                    let mut word = [0u8; 4];
+                    for (i, byte) in word.iter_mut().take(bytes_per_sample).enumerate() {
+                        *byte = if little_endian { raw[bytes_per_sample - 1 - i] } else { raw[i] };
+                    }
+                    (i32::from_be_bytes(word) >> ((4 - bytes_per_sample) * 8)) as f32 / scale
+                })
+                .collect();
+            (audio, hound::SampleFormat::Int)
+        }
+        (b"fl32" | b"FL32", 32) => {
+            let audio = data.chunks_exact(4).take(count)
+                .map(|raw| f32::from_be_bytes([raw[0], raw[1], raw[2], raw[3]]))
+                .collect();
+            (audio, hound::SampleFormat::Float)
+        }
+        _ => return Err(invalid(&format!("unsupported encoding {} ({} bit)", String::from_utf8_lossy(compression), bits))),
+    };
+
+    Ok((audio, hound::WavSpec { channels, sample_rate, bits_per_sample: bits, sample_format }))
+}
+
+/// Mapping from the INST chunk, with its sustain loop's markers looked up in MARK
+pub(crate) fn sampler_chunks(chunks: &[Chunk]) -> Option<SamplerChunks> {
+    let inst = chunks.iter().find(|chunk| chunk.id == b"INST")?.body;
+    if inst.len() < 20 {
+        return None;
+    }
+    // Key range (bytes 2-3) is left to the instrument's own mapping
+    let [base_note, detune, low_velocity, high_velocity] = [0, 1, 4, 5].map(|at| inst[at]);
+
+    // MARK: count, then id (2), position (4) and a padded Pascal-string name per marker
+    let mut markers = Vec::new();
+    if let Some(mark) = chunks.iter().find(|chunk| chunk.id == b"MARK").map(|chunk| chunk.body) {
+        let mut offset = 2;
+        for _ in 0..read_u16(mark, 0).unwrap_or(0) {
+            let (Some(id), Some(position), Some(&name_length)) = (read_u16(mark, offset), read_u32(mark, offset + 2), mark.get(offset + 6)) else {
+                break;
+            };
+            markers.push((id, position as usize));
+            // The count byte and name together are padded to an even length
+            offset += 6 + ((1 + name_length as usize + 1) & !1);
+        }
+    }
+    let marker = |id: u16| markers.iter().find(|(marker, _)| *marker == id).map(|&(_, position)| position);
+    // Sustain loop: play mode (0 = off), begin and end marker ids
+    let sustain_loop = match (read_u16(inst, 8), read_u16(inst, 10), read_u16(inst, 12)) {
+        (Some(mode), Some(begin), Some(end)) if mode != 0 => marker(begin).zip(marker(end))
+            .filter(|(start, end)| start < end)
+            .map(|(start, end)| LoopPoints { start, end }),
+        _ => None,
+    };
+
+    Some(SamplerChunks {
+        root_note: base_note.min(127),
+        // Detune is what playback should add, so the recording sits the opposite way
+        tuning_cents: -(detune as i8 as f32),
+        velocity_range: Some((low_velocity.clamp(1, 127), high_velocity.clamp(1, 127))),
+        loop_points: sustain_loop,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal 16-bit AIFF with the given frames
+    fn aiff_bytes(frames: &[i16], extra_chunks: &[u8]) -> Vec<u8> {
+        let mut comm = Vec::new();
+        comm.extend_from_slice(&1u16.to_be_bytes());
+        comm.extend_from_slice(&(frames.len() as u32).to_be_bytes());
+        comm.extend_from_slice(&16u16.to_be_bytes());
+        // 44100 as an 80-bit extended float
+        comm.extend_from_slice(&[0x40, 0x0e, 0xac, 0x44, 0, 0, 0, 0, 0, 0]);
+
+        let mut ssnd = vec![0u8; 8];
+        for frame in frames {
+            ssnd.extend_from_slice(&frame.to_be_bytes());
+        }
+
+        let mut body = b"AIFF".to_vec();
+        for (id, chunk) in [(b"COMM", &comm), (b"SSND", &ssnd)] {
+            body.extend_from_slice(id);
+            body.extend_from_slice(&(chunk.len() as u32).to_be_bytes());
+            body.extend_from_slice(chunk);
+        }
+        body.extend_from_slice(extra_chunks);
+        let mut bytes = b"FORM".to_vec();
+        bytes.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+
+    #[test]
+    fn test_read_aiff() {
+        let path = std::env::temp_dir().join(format!("batcherbird_aiff_{}.aif", std::process::id()));
+        std::fs::write(&path, aiff_bytes(&[0, 16384, -16384, i16::MIN], &[])).unwrap();
+
+        let (audio, spec) = read_aiff(&path).unwrap();
+        assert_eq!((spec.channels, spec.sample_rate, spec.bits_per_sample), (1, 44100, 16));
+        assert_eq!(audio, vec![0.0, 0.5, -0.5, -1.0]);
+
+        // Root A3, 10 cents flat playback, velocities 64-100, sustain loop between markers 1 and 2
+        let mut extra = b"INST".to_vec();
+        extra.extend_from_slice(&20u32.to_be_bytes());
+        extra.extend_from_slice(&[57, (-10i8) as u8, 0, 127, 64, 100, 0, 0, 0, 1, 0, 1, 0, 2, 0, 0, 0, 0, 0, 0]);
+        extra.extend_from_slice(b"MARK");
+        extra.extend_from_slice(&20u32.to_be_bytes());
+        extra.extend_from_slice(&[0, 2, 0, 1, 0, 0, 0, 1, 1, b'a', 0, 2, 0, 0, 0, 3, 3, b'e', b'n', b'd']);
+        let bytes = aiff_bytes(&[0, 16384, -16384, i16::MIN], &extra);
+        let mapping = sampler_chunks(&chunks(&bytes).unwrap()).unwrap();
+        assert_eq!((mapping.root_note, mapping.tuning_cents), (57, 10.0));
+        assert_eq!(mapping.velocity_range, Some((64, 100)));
+        assert_eq!(mapping.loop_points, Some(LoopPoints { start: 1, end: 3 }));
+
+        std::fs::write(&path, b"RIFF\0\0\0\0WAVE").unwrap();
+        assert!(read_aiff(&path).is_err());
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,190 @@
+use crate::loudness::LoudnessMeasurement;
+use crate::pitch::{self, correlation_peak, estimate_frequency, mixdown};
+use crate::sampler::Sample;
+use serde::{Deserialize, Serialize};
+
+/// Window for tracking pitch over time (seconds), shorter than the one-off estimate's
+const ENVELOPE_WINDOW_SECS: f32 = 0.03;
+/// Spacing of pitch envelope points (ms)
+pub const PITCH_ENVELOPE_HOP_MS: f32 = 20.0;
+/// Drift within one note beyond this suggests a failing or unsettled oscillator (cents)
+pub const DEFAULT_DRIFT_LIMIT_CENTS: f32 = 20.0;
+
+/// Quick measurements of a freshly captured take, computed from the raw audio
+/// before any detection or export processing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TakeAnalysis {
+    pub note: u8,
+    pub velocity: u8,
+    /// Peak level in dBFS
+    pub peak_db: f32,
+    /// RMS level in dBFS
+    pub rms_db: f32,
+    /// BS.1770 integrated loudness (None when the take is silent)
+    #[serde(default)]
+    pub loudness_lufs: Option<f32>,
+    /// 4x oversampled peak in dBTP
+    #[serde(default)]
+    pub true_peak_db: f32,
+    pub duration_secs: f32,
+    /// Estimated fundamental (None when the take is silent or unpitched)
+    pub detected_frequency_hz: Option<f32>,
+    /// Nearest MIDI note to the estimated fundamental
+    pub detected_note: Option<u8>,
+    /// Deviation of the fundamental from the detected note (cents)
+    pub detected_cents: Option<f32>,
+    /// Pitch spread within the note, when its envelope has been tracked
+    pub pitch_drift_cents: Option<f32>,
+    /// Why the take was flagged for a retake, if it was
+    pub retake_reason: Option<String>,
+}
+
+impl TakeAnalysis {
+    pub fn from_sample(sample: &Sample) -> Self {
+        let channels = sample.channels.max(1) as usize;
+        let frames = sample.audio_data.len() / channels;
+
+        let peak = sample.audio_data.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+        let rms = if sample.audio_data.is_empty() {
+            0.0
+        } else {
+            (sample.audio_data.iter().map(|s| s * s).sum::<f32>() / sample.audio_data.len() as f32).sqrt()
+        };
+
+        let estimate = pitch::detect_pitch(&sample.audio_data, sample.channels, sample.sample_rate);
+        let loudness = LoudnessMeasurement::measure(&sample.audio_data, sample.channels, sample.sample_rate);
+
+        Self {
+            note: sample.note,
+            velocity: sample.velocity,
+            peak_db: to_db(peak),
+            rms_db: to_db(rms),
+            loudness_lufs: loudness.integrated_lufs,
+            true_peak_db: loudness.true_peak_db,
+            duration_secs: frames as f32 / sample.sample_rate.max(1) as f32,
+            detected_frequency_hz: estimate.map(|e| e.frequency_hz),
+            detected_note: estimate.map(|e| e.note),
+            detected_cents: estimate.map(|e| e.cents),
+            pitch_drift_cents: sample.metadata.pitch_envelope.as_ref().map(PitchEnvelope::drift_cents),
+            retake_reason: sample.metadata.retake_reason.clone(),
+        }
+    }
+}
+
+/// Pitch of a take over time, for spotting analog oscillators that wander within a note
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PitchEnvelope {
+    /// Spacing between points (ms)
+    pub hop_ms: f32,
+    /// (time in ms, frequency in Hz) for every window with a clear pitch
+    pub points: Vec<(f32, f32)>,
+}
+
+impl PitchEnvelope {
+    /// Track the fundamental every `hop_ms`, searching within half an octave of the take's
+    /// overall pitch so a wobble can't be mistaken for an octave jump
+    pub fn track(audio: &[f32], channels: u16, sample_rate: u32, hop_ms: f32) -> Self {
+        let mono = mixdown(audio, channels);
+        let rate = sample_rate as f32;
+        let mut envelope = Self { hop_ms, points: Vec::new() };
+        let Some(base_hz) = estimate_frequency(&mono, sample_rate) else {
+            return envelope;
+        };
+
+        let min_lag = ((rate / base_hz) / 2f32.sqrt()).floor().max(2.0) as usize;
+        let max_lag = ((rate / base_hz) * 2f32.sqrt()).ceil() as usize;
+        let window = ((rate * ENVELOPE_WINDOW_SECS) as usize).max(max_lag * 2);
+        let hop = ((rate * hop_ms / 1000.0) as usize).max(1);
+        let mut start = 0;
+        while start + window + max_lag <= mono.len() {
+            if let Some(hz) = correlation_peak(&mono[start..start + window + max_lag], window, min_lag, max_lag, rate) {
+                envelope.points.push((start as f32 / rate * 1000.0, hz));
+            }
+            start += hop;
+        }
+        envelope
+    }
+
+    /// Spread between the flattest and sharpest point (cents), ignoring the first and last
+    /// tenth of the tracked points where attack and release transients bend the pitch
+    pub fn drift_cents(&self) -> f32 {
+        let skip = self.points.len() / 10;
+        let settled = &self.points[skip..self.points.len() - skip];
+        let (low, high) = settled.iter().fold((f32::MAX, f32::MIN), |(low, high), &(_, hz)| (low.min(hz), high.max(hz)));
+        if settled.len() < 2 { 0.0 } else { 1200.0 * (high / low).log2() }
+    }
+}
+
+fn to_db(level: f32) -> f32 {
+    if level > 0.0 { (20.0 * level.log10()).max(-96.0) } else { -96.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::SampleMetadata;
+    use std::time::{Duration, SystemTime};
+
+    fn sample(audio_data: Vec<f32>, channels: u16) -> Sample {
+        Sample {
+            note: 69,
+            velocity: 100,
+            audio_data,
+            sample_rate: 44100,
+            channels,
+            recorded_at: SystemTime::now(),
+            midi_timing: Duration::ZERO,
+            audio_timing: Duration::ZERO,
+            metadata: SampleMetadata::default(),
+        }
+    }
+
+    #[test]
+    fn test_take_analysis_levels_and_pitch() {
+        // One second of A4 at half scale, stereo
+        let audio: Vec<f32> = (0..44100)
+            .flat_map(|i| {
+                let s = 0.5 * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / 44100.0).sin();
+                [s, s]
+            })
+            .collect();
+        let analysis = TakeAnalysis::from_sample(&sample(audio, 2));
+
+        assert!((analysis.duration_secs - 1.0).abs() < 1e-3);
+        assert!((analysis.peak_db - -6.02).abs() < 0.1);
+        assert!((analysis.rms_db - -9.03).abs() < 0.1);
+        assert_eq!(analysis.detected_note, Some(69));
+        assert!((analysis.detected_frequency_hz.unwrap() - 440.0).abs() < 2.0);
+        assert!(analysis.detected_cents.unwrap().abs() < 10.0);
+
+        let silent = TakeAnalysis::from_sample(&sample(vec![0.0; 44100], 1));
+        assert_eq!(silent.peak_db, -96.0);
+        assert_eq!(silent.detected_note, None);
+    }
+
+    #[test]
+    fn test_pitch_envelope_measures_drift() {
+        // Two seconds gliding from 440Hz to 452Hz (about 47 cents), built by accumulating phase
+        let rate = 44100;
+        let mut phase = 0.0f32;
+        let gliding: Vec<f32> = (0..rate * 2)
+            .map(|i| {
+                phase += 2.0 * std::f32::consts::PI * (440.0 + 12.0 * i as f32 / (rate * 2) as f32) / rate as f32;
+                0.5 * phase.sin()
+            })
+            .collect();
+        let envelope = PitchEnvelope::track(&gliding, 1, rate as u32, PITCH_ENVELOPE_HOP_MS);
+        assert!(envelope.points.len() > 80);
+        let drift = envelope.drift_cents();
+        assert!(drift > 30.0 && drift < 47.0, "drift {}", drift);
+
+        let mut steady = sample((0..rate * 2).map(|i| 0.5 * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / rate as f32).sin()).collect(), 1);
+        assert!(steady.track_pitch_drift(Some(DEFAULT_DRIFT_LIMIT_CENTS)) < 2.0);
+        assert!(steady.metadata.pitch_envelope.is_some());
+        assert!(steady.metadata.retake_reason.is_none());
+
+        let mut drifting = sample(gliding, 1);
+        drifting.track_pitch_drift(Some(DEFAULT_DRIFT_LIMIT_CENTS));
+        assert!(drifting.metadata.retake_reason.is_some());
+    }
+}
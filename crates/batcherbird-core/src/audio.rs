@@ -1,7 +1,100 @@
 use crate::{Result, BatcherbirdError};
-use cpal::{Host, StreamConfig, SampleFormat, traits::{DeviceTrait, HostTrait, StreamTrait}};
+use crate::multi_device::SecondaryInputConfig;
+use cpal::{Host, HostId, StreamConfig, SampleFormat, traits::{DeviceTrait, HostTrait, StreamTrait}};
+use serde::Serialize;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tracing::{error, info, warn};
+
+/// A device with the stream format negotiated for it
+#[derive(Clone)]
+struct NegotiatedDevice {
+    device: cpal::Device,
+    config: cpal::SupportedStreamConfig,
+}
+
+// Warm-start cache shared by every AudioManager, so back-to-back takes skip device
+// enumeration and format negotiation. Cleared when devices are re-listed or a stream fails.
+static INPUT_CACHE: Mutex<Option<NegotiatedDevice>> = Mutex::new(None);
+static OUTPUT_CACHE: Mutex<Option<NegotiatedDevice>> = Mutex::new(None);
+// Part of the input device name to record from, set by embedders; None picks MiniFuse or the default
+static PREFERRED_INPUT: Mutex<Option<String>> = Mutex::new(None);
+// Same for the playback device; None picks MiniFuse or the default
+static PREFERRED_OUTPUT: Mutex<Option<String>> = Mutex::new(None);
+// Second device paired with the input for stereo takes (see multi_device)
+static SECONDARY_INPUT: Mutex<Option<SecondaryInputConfig>> = Mutex::new(None);
+// Audio host chosen by name (see `list_hosts`); None tries AUTO_HOSTS, then the platform default
+static PREFERRED_HOST: Mutex<Option<String>> = Mutex::new(None);
+// Host settled on by the first AudioManager, so JACK isn't probed for every stream
+static RESOLVED_HOST: Mutex<Option<HostId>> = Mutex::new(None);
+
+/// Hosts used over the platform default when they are compiled in and have an input device:
+/// JACK (the `jack` feature, Linux) shares the interface with a running JACK or PipeWire
+/// graph instead of fighting it for the raw ALSA device
+const AUTO_HOSTS: &[&str] = &["JACK"];
+
+/// Rates offered from a device's supported ranges
+const COMMON_SAMPLE_RATES: &[u32] = &[22050, 32000, 44100, 48000, 88200, 96000, 176400, 192000];
+
+/// ALSA names that open the hardware directly. They can't be opened twice, and the monitor
+/// and capture streams each open the input, so the interface is auto-picked by its shared names.
+const EXCLUSIVE_ALSA_PREFIXES: &[&str] = &["hw:", "plughw:", "front:"];
+
+/// What an input device can record with, for front ends offering only valid settings
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeviceCapabilities {
+    /// Position in `list_input_devices`
+    pub index: usize,
+    pub name: String,
+    /// Common rates inside the device's supported ranges, plus any fixed rate it reports
+    pub sample_rates: Vec<u32>,
+    pub channel_counts: Vec<u16>,
+    /// Sample formats as cpal names them, lowercase ("f32", "i16", ...)
+    pub sample_formats: Vec<String>,
+    /// What recordings use: the device's default configuration
+    pub default_sample_rate: Option<u32>,
+    pub default_channels: Option<u16>,
+    pub default_format: Option<String>,
+}
+
+impl DeviceCapabilities {
+    /// Fold supported ranges of (channels, min rate, max rate, format) into the lists
+    fn from_ranges(index: usize, name: String, ranges: &[(u16, u32, u32, SampleFormat)]) -> Self {
+        let mut sample_rates: Vec<u32> = ranges.iter()
+            .flat_map(|&(_, min, max, _)| if min == max {
+                vec![min]
+            } else {
+                COMMON_SAMPLE_RATES.iter().copied().filter(|rate| (min..=max).contains(rate)).collect()
+            })
+            .collect();
+        sample_rates.sort_unstable();
+        sample_rates.dedup();
+        let mut channel_counts: Vec<u16> = ranges.iter().map(|&(channels, ..)| channels).collect();
+        channel_counts.sort_unstable();
+        channel_counts.dedup();
+        let mut sample_formats: Vec<String> = Vec::new();
+        for &(.., format) in ranges {
+            let format = format_name(format);
+            if !sample_formats.contains(&format) {
+                sample_formats.push(format);
+            }
+        }
+        Self {
+            index,
+            name,
+            sample_rates,
+            channel_counts,
+            sample_formats,
+            default_sample_rate: None,
+            default_channels: None,
+            default_format: None,
+        }
+    }
+}
+
+fn format_name(format: SampleFormat) -> String {
+    format!("{:?}", format).to_lowercase()
+}
 
 pub struct AudioManager {
     host: Host,
@@ -9,10 +102,68 @@ pub struct AudioManager {
 
 impl AudioManager {
     pub fn new() -> Result<Self> {
-        let host = cpal::default_host();
+        let cached = *RESOLVED_HOST.lock().unwrap();
+        if let Some(host) = cached.and_then(|id| cpal::host_from_id(id).ok()) {
+            return Ok(Self { host });
+        }
+        let host = Self::select_host()?;
+        *RESOLVED_HOST.lock().unwrap() = Some(host.id());
         Ok(Self { host })
     }
 
+    fn select_host() -> Result<Host> {
+        if let Some(preferred) = PREFERRED_HOST.lock().unwrap().clone() {
+            let id = Self::host_id_named(&preferred)?;
+            let host = cpal::host_from_id(id)
+                .map_err(|e| BatcherbirdError::Audio(format!("Audio host {} unavailable: {}", id.name(), e)))?;
+            info!("🔊 Audio host: {}", id.name());
+            return Ok(host);
+        }
+        for name in AUTO_HOSTS {
+            let Ok(id) = Self::host_id_named(name) else { continue };
+            match cpal::host_from_id(id) {
+                Ok(host) if host.default_input_device().is_some() => {
+                    info!("🔊 Audio host: {} (running, preferred over the default)", id.name());
+                    return Ok(host);
+                }
+                Ok(_) => info!("🔊 {} has no input ports, using the default host", id.name()),
+                Err(e) => warn!("⚠️ Audio host {} unavailable: {}", id.name(), e),
+            }
+        }
+        Ok(cpal::default_host())
+    }
+
+    fn host_id_named(name: &str) -> Result<HostId> {
+        cpal::available_hosts().into_iter()
+            .find(|id| id.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| BatcherbirdError::Audio(format!(
+                "Unknown audio host '{}' (available: {})", name, Self::list_hosts().join(", ")
+            )))
+    }
+
+    /// Audio hosts compiled in for this platform: CoreAudio on macOS, WASAPI on Windows, ALSA
+    /// (and JACK with the `jack` feature) on Linux
+    pub fn list_hosts() -> Vec<String> {
+        cpal::available_hosts().iter().map(|id| id.name().to_string()).collect()
+    }
+
+    /// Name of the host this manager talks to
+    pub fn host_name(&self) -> &'static str {
+        self.host.id().name()
+    }
+
+    /// Use the audio host called `name` (None restores automatic choice). Errors for hosts
+    /// not compiled into this build.
+    pub fn set_preferred_host(name: Option<String>) -> Result<()> {
+        if let Some(name) = &name {
+            Self::host_id_named(name)?;
+        }
+        *PREFERRED_HOST.lock().unwrap() = name;
+        *RESOLVED_HOST.lock().unwrap() = None;
+        Self::clear_device_cache();
+        Ok(())
+    }
+
     pub fn list_input_devices(&self) -> Result<Vec<String>> {
         let mut devices = Vec::new();
         
@@ -44,33 +195,167 @@ impl AudioManager {
     }
 
     pub fn get_default_input_device(&self) -> Result<cpal::Device> {
-        // Try to find MiniFuse first, then fall back to default
+        // A chosen interface wins; otherwise try to find MiniFuse first, then fall back to default
         let input_devices = self.host.input_devices()
             .map_err(|e| BatcherbirdError::Audio(format!("Failed to enumerate input devices: {}", e)))?;
+        
+        if let Some(preferred) = PREFERRED_INPUT.lock().unwrap().clone() {
+            return input_devices
+                .into_iter()
+                .find(|device| device.name().is_ok_and(|name| name.contains(&preferred)))
+                .ok_or_else(|| BatcherbirdError::Audio(format!("No input device matching '{}'", preferred)));
+        }
             
         for device in input_devices {
             if let Ok(name) = device.name() {
-                if name.contains("MiniFuse") {
-                    println!("🎤 Found MiniFuse: {}", name);
+                if name.contains("MiniFuse") && !is_exclusive_device_name(&name) {
+                    info!("🎤 Found MiniFuse: {}", name);
                     return Ok(device);
                 }
             }
         }
         
-        // Fall back to default device
+        // Fall back to default device (WASAPI: the Windows default recording device, in
+        // shared mode at the rate set in Sound settings; ALSA: usually "default" via PipeWire)
         self.host.default_input_device()
             .ok_or_else(|| BatcherbirdError::Audio("No default input device found".to_string()))
     }
 
+    pub fn get_default_output_device(&self) -> Result<cpal::Device> {
+        // A chosen device wins; otherwise the same interface as the input when available, so
+        // playback is heard on the monitors
+        if let Some(preferred) = PREFERRED_OUTPUT.lock().unwrap().clone() {
+            return self.output_device_named(&preferred);
+        }
+        let output_devices = self.host.output_devices()
+            .map_err(|e| BatcherbirdError::Audio(format!("Failed to enumerate output devices: {}", e)))?;
+            
+        for device in output_devices {
+            if let Ok(name) = device.name() {
+                if name.contains("MiniFuse") && !is_exclusive_device_name(&name) {
+                    return Ok(device);
+                }
+            }
+        }
+        
+        self.host.default_output_device()
+            .ok_or_else(|| BatcherbirdError::Audio("No default output device found".to_string()))
+    }
+
+    /// Sample rates, channel counts and formats of the input device at `index` in
+    /// `list_input_devices`
+    pub fn describe_device(&self, index: usize) -> Result<DeviceCapabilities> {
+        let device = self.host.input_devices()
+            .map_err(|e| BatcherbirdError::Audio(format!("Failed to enumerate input devices: {}", e)))?
+            .filter(|device| device.name().is_ok())
+            .nth(index)
+            .ok_or_else(|| BatcherbirdError::Audio(format!("No input device at index {}", index)))?;
+        let name = device.name().unwrap_or_default();
+        let ranges: Vec<_> = device.supported_input_configs()
+            .map_err(|e| BatcherbirdError::Audio(format!("Failed to query '{}': {}", name, e)))?
+            .map(|range| (range.channels(), range.min_sample_rate().0, range.max_sample_rate().0, range.sample_format()))
+            .collect();
+
+        let mut capabilities = DeviceCapabilities::from_ranges(index, name, &ranges);
+        if let Ok(config) = device.default_input_config() {
+            capabilities.default_sample_rate = Some(config.sample_rate().0);
+            capabilities.default_channels = Some(config.channels());
+            capabilities.default_format = Some(format_name(config.sample_format()));
+        }
+        Ok(capabilities)
+    }
+
+    /// Input device whose name contains `name`
+    pub fn input_device_named(&self, name: &str) -> Result<cpal::Device> {
+        self.host.input_devices()
+            .map_err(|e| BatcherbirdError::Audio(format!("Failed to enumerate input devices: {}", e)))?
+            .find(|device| device.name().is_ok_and(|device_name| device_name.contains(name)))
+            .ok_or_else(|| BatcherbirdError::Audio(format!("No input device matching '{}'", name)))
+    }
+
+    /// Output device whose name contains `name`
+    pub fn output_device_named(&self, name: &str) -> Result<cpal::Device> {
+        self.host.output_devices()
+            .map_err(|e| BatcherbirdError::Audio(format!("Failed to enumerate output devices: {}", e)))?
+            .find(|device| device.name().is_ok_and(|device_name| device_name.contains(name)))
+            .ok_or_else(|| BatcherbirdError::Audio(format!("No output device matching '{}'", name)))
+    }
+
+    /// Recording device and its input format, negotiated on first use and reused afterwards
+    pub fn input_device_and_config(&self) -> Result<(cpal::Device, cpal::SupportedStreamConfig)> {
+        if let Some(cached) = INPUT_CACHE.lock().unwrap().clone() {
+            return Ok((cached.device, cached.config));
+        }
+        
+        let device = self.get_default_input_device()?;
+        let config = device.default_input_config()
+            .map_err(|e| BatcherbirdError::Audio(format!("Failed to get input config: {}", e)))?;
+        info!("🎤 Input negotiated: {} ({} Hz, {} channels, {:?})",
+            device.name().unwrap_or("Unknown".to_string()), config.sample_rate().0, config.channels(), config.sample_format());
+        
+        *INPUT_CACHE.lock().unwrap() = Some(NegotiatedDevice { device: device.clone(), config: config.clone() });
+        Ok((device, config))
+    }
+
+    /// Name of the recording device (the negotiated one, once sampling has started)
+    pub fn input_device_name(&self) -> Option<String> {
+        self.input_device_and_config().ok().and_then(|(device, _)| device.name().ok())
+    }
+
+    /// Playback device and its output format, negotiated on first use and reused afterwards
+    pub fn output_device_and_config(&self) -> Result<(cpal::Device, cpal::SupportedStreamConfig)> {
+        if let Some(cached) = OUTPUT_CACHE.lock().unwrap().clone() {
+            return Ok((cached.device, cached.config));
+        }
+        
+        let device = self.get_default_output_device()?;
+        let config = device.default_output_config()
+            .map_err(|e| BatcherbirdError::Audio(format!("Failed to get output config: {}", e)))?;
+        
+        *OUTPUT_CACHE.lock().unwrap() = Some(NegotiatedDevice { device: device.clone(), config: config.clone() });
+        Ok((device, config))
+    }
+
+    /// Record from the input device whose name contains `name` (None restores the default choice)
+    pub fn set_preferred_input(name: Option<String>) {
+        *PREFERRED_INPUT.lock().unwrap() = name;
+        Self::clear_device_cache();
+    }
+
+    /// Pair the main input with a channel of a second device for stereo takes in batch
+    /// captures (None records the main input alone); see `multi_device`
+    pub fn set_secondary_input(config: Option<SecondaryInputConfig>) {
+        *SECONDARY_INPUT.lock().unwrap() = config;
+    }
+
+    pub fn secondary_input() -> Option<SecondaryInputConfig> {
+        SECONDARY_INPUT.lock().unwrap().clone()
+    }
+
+    /// Play back on the output device whose name contains `name` (None restores the default choice)
+    pub fn set_preferred_output(name: Option<String>) {
+        *PREFERRED_OUTPUT.lock().unwrap() = name;
+        Self::clear_device_cache();
+    }
+
+    /// Forget the negotiated devices (after a device change or a failed stream)
+    pub fn clear_device_cache() {
+        let had_input = INPUT_CACHE.lock().unwrap().take().is_some();
+        let had_output = OUTPUT_CACHE.lock().unwrap().take().is_some();
+        if had_input || had_output {
+            info!("🔄 Audio device cache cleared");
+        }
+    }
+
     pub fn record_test_audio(&self, duration_secs: u64) -> Result<Vec<f32>> {
         let device = self.get_default_input_device()?;
         let config = device.default_input_config()
             .map_err(|e| BatcherbirdError::Audio(format!("Failed to get input config: {}", e)))?;
 
-        println!("🎤 Recording from: {}", device.name().unwrap_or("Unknown".to_string()));
-        println!("   Sample rate: {} Hz", config.sample_rate().0);
-        println!("   Channels: {}", config.channels());
-        println!("   Format: {:?}", config.sample_format());
+        info!("🎤 Recording from: {}", device.name().unwrap_or("Unknown".to_string()));
+        info!("   Sample rate: {} Hz", config.sample_rate().0);
+        info!("   Channels: {}", config.channels());
+        info!("   Format: {:?}", config.sample_format());
 
         let sample_rate = config.sample_rate().0;
         let channels = config.channels() as usize;
@@ -99,7 +384,7 @@ impl AudioManager {
                             samples.extend_from_slice(data);
                         }
                     },
-                    |err| eprintln!("Audio input error: {}", err),
+                    |err| error!("Audio input error: {}", err),
                     None,
                 ).map_err(|e| BatcherbirdError::Audio(format!("Failed to build input stream: {}", e)))?
             }
@@ -122,7 +407,7 @@ impl AudioManager {
                             }
                         }
                     },
-                    |err| eprintln!("Audio input error: {}", err),
+                    |err| error!("Audio input error: {}", err),
                     None,
                 ).map_err(|e| BatcherbirdError::Audio(format!("Failed to build input stream: {}", e)))?
             }
@@ -145,7 +430,30 @@ impl AudioManager {
                             }
                         }
                     },
-                    |err| eprintln!("Audio input error: {}", err),
+                    |err| error!("Audio input error: {}", err),
+                    None,
+                ).map_err(|e| BatcherbirdError::Audio(format!("Failed to build input stream: {}", e)))?
+            }
+            SampleFormat::I32 => {
+                let stream_config = StreamConfig {
+                    channels: config.channels(),
+                    sample_rate: config.sample_rate(),
+                    buffer_size: cpal::BufferSize::Default,
+                };
+
+                device.build_input_stream(
+                    &stream_config,
+                    move |data: &[i32], _: &cpal::InputCallbackInfo| {
+                        let mut samples = samples_clone.lock().unwrap();
+                        let complete = complete_clone.lock().unwrap();
+                        
+                        if !*complete {
+                            for &sample in data {
+                                samples.push(i32_to_f32(sample));
+                            }
+                        }
+                    },
+                    |err| error!("Audio input error: {}", err),
                     None,
                 ).map_err(|e| BatcherbirdError::Audio(format!("Failed to build input stream: {}", e)))?
             }
@@ -157,7 +465,7 @@ impl AudioManager {
         // Start recording
         stream.play().map_err(|e| BatcherbirdError::Audio(format!("Failed to start stream: {}", e)))?;
         
-        println!("🔴 Recording for {} seconds... (make some noise!)", duration_secs);
+        info!("🔴 Recording for {} seconds... (make some noise!)", duration_secs);
         
         // Record for specified duration
         std::thread::sleep(Duration::from_secs(duration_secs));
@@ -171,7 +479,7 @@ impl AudioManager {
         stream.pause().map_err(|e| BatcherbirdError::Audio(format!("Failed to stop stream: {}", e)))?;
         
         let samples = recorded_samples.lock().unwrap().clone();
-        println!("✅ Recording complete! Captured {} samples", samples.len());
+        info!("✅ Recording complete! Captured {} samples", samples.len());
         
         Ok(samples)
     }
@@ -198,4 +506,62 @@ impl AudioManager {
         
         (rms, rms_db, peak_db)
     }
-}
\ No newline at end of file
+}
+
+fn is_exclusive_device_name(name: &str) -> bool {
+    EXCLUSIVE_ALSA_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+}
+
+/// 32-bit integer capture (what many USB interfaces offer on ALSA) as -1.0..1.0
+pub(crate) fn i32_to_f32(sample: i32) -> f32 {
+    (sample as f64 / i32::MAX as f64) as f32
+}
+
+/// -1.0..1.0 as offset-binary 16-bit output, 32768 being silence
+pub(crate) fn f32_to_u16(sample: f32) -> u16 {
+    (sample.clamp(-1.0, 1.0) * 32767.0 + 32768.0) as u16
+}
+
+/// -1.0..1.0 as 32-bit integer output
+pub(crate) fn f32_to_i32(sample: f32) -> i32 {
+    (sample.clamp(-1.0, 1.0) as f64 * i32::MAX as f64) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integer_format_conversions() {
+        assert_eq!(i32_to_f32(i32::MAX), 1.0);
+        assert_eq!(i32_to_f32(0), 0.0);
+        assert!((i32_to_f32(i32::MIN) + 1.0).abs() < 1e-6);
+        assert_eq!(f32_to_u16(0.0), 32768);
+        assert_eq!(f32_to_u16(1.0), u16::MAX);
+        assert_eq!(f32_to_u16(-2.0), 1);
+        assert_eq!(f32_to_i32(1.0), i32::MAX);
+        assert_eq!(f32_to_i32(-1.0), -i32::MAX);
+        assert!((i32_to_f32(f32_to_i32(0.25)) - 0.25).abs() < 1e-6);
+
+        assert!(is_exclusive_device_name("hw:CARD=MiniFuse2,DEV=0"));
+        assert!(!is_exclusive_device_name("sysdefault:CARD=MiniFuse2"));
+        assert!(!is_exclusive_device_name("Microphone (MiniFuse 2)"));
+        assert!(AudioManager::set_preferred_host(Some("no-such-host".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_capabilities_from_supported_ranges() {
+        let ranges = [
+            (2, 44100, 96000, SampleFormat::I32),
+            (2, 44100, 96000, SampleFormat::F32),
+            (1, 48000, 48000, SampleFormat::F32),
+            (4, 47999, 47999, SampleFormat::I16),
+        ];
+        let capabilities = DeviceCapabilities::from_ranges(3, "MiniFuse 2".to_string(), &ranges);
+        assert_eq!(capabilities.index, 3);
+        assert_eq!(capabilities.sample_rates, vec![44100, 47999, 48000, 88200, 96000]);
+        assert_eq!(capabilities.channel_counts, vec![1, 2, 4]);
+        assert_eq!(capabilities.sample_formats, vec!["i32", "f32", "i16"]);
+        assert_eq!(capabilities.default_sample_rate, None);
+    }
+}
@@ -0,0 +1,73 @@
+//! Stopping a recording from outside the capture thread. A front end keeps a clone of the
+//! token the capture runs with; cancelling it cuts the current note short, silences the
+//! synth and ends the batch with an error, leaving takes already handed on untouched.
+
+use crate::{BatcherbirdError, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often an awaiting capture looks at the flag
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Shared cancel flag; clones see the same flag
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Err once cancelled, for checks between notes
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(Self::error());
+        }
+        Ok(())
+    }
+
+    /// Resolves once cancelled; race it against a capture with `tokio::select!`
+    pub async fn cancelled(&self) {
+        while !self.is_cancelled() {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    pub fn error() -> BatcherbirdError {
+        BatcherbirdError::Session("Recording cancelled".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cancel_ends_a_running_capture() {
+        let token = CancelToken::new();
+        assert!(token.check().is_ok());
+
+        let canceller = token.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            canceller.cancel();
+        });
+        let capture = tokio::time::sleep(Duration::from_secs(10));
+        let finished = tokio::select! {
+            _ = capture => true,
+            _ = token.cancelled() => false,
+        };
+        assert!(!finished);
+        assert!(token.is_cancelled());
+        assert!(token.check().is_err());
+    }
+}
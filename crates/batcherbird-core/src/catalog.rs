@@ -0,0 +1,457 @@
+//! Persistent catalog of every exported session: where it went, the devices and settings it
+//! was recorded with, its samples and their QA results. An SQLite database in the user data
+//! folder, so the GUI can browse past sessions and the CLI can list them, independent of where
+//! the sessions themselves were written.
+
+use crate::{Result, BatcherbirdError};
+use crate::manifest::{ManifestEntry, SessionManifest};
+use crate::qa::{QaReport, SampleQa};
+use crate::sampler::SamplingConfig;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, params_from_iter, types::Type, Connection, Row, ToSql};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// File name of the catalog in the user data folder
+pub const CATALOG_FILENAME: &str = "catalog.sqlite";
+
+/// Tables of the catalog; a session's samples and QA issues are deleted with it
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS sessions (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        directory TEXT NOT NULL,
+        recorded_at TEXT NOT NULL,
+        midi_device TEXT,
+        audio_device TEXT,
+        format TEXT NOT NULL,
+        normalize INTEGER NOT NULL,
+        apply_detection INTEGER NOT NULL,
+        loudness_target_lufs REAL,
+        remove_hum INTEGER NOT NULL,
+        note_duration_ms INTEGER,
+        release_time_ms INTEGER,
+        midi_channel INTEGER,
+        hum_hz REAL
+    );
+    CREATE INDEX IF NOT EXISTS sessions_by_date ON sessions (recorded_at);
+    CREATE TABLE IF NOT EXISTS samples (
+        session_id TEXT NOT NULL REFERENCES sessions (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        file TEXT NOT NULL,
+        note INTEGER NOT NULL,
+        note_name TEXT NOT NULL,
+        velocity INTEGER NOT NULL,
+        sample_rate INTEGER NOT NULL,
+        channels INTEGER NOT NULL,
+        duration_sec REAL NOT NULL,
+        peak_db REAL NOT NULL,
+        rms_db REAL NOT NULL,
+        normalization_gain_db REAL,
+        metadata TEXT NOT NULL,
+        silent_gaps TEXT NOT NULL,
+        PRIMARY KEY (session_id, position)
+    );
+    CREATE INDEX IF NOT EXISTS samples_by_note ON samples (note);
+    CREATE TABLE IF NOT EXISTS qa_issues (
+        session_id TEXT NOT NULL REFERENCES sessions (id) ON DELETE CASCADE,
+        file TEXT NOT NULL,
+        note INTEGER NOT NULL,
+        velocity INTEGER NOT NULL,
+        issues TEXT NOT NULL
+    );
+";
+
+const SESSION_COLUMNS: &str = "id, name, directory, recorded_at, midi_device, audio_device, format, normalize, apply_detection, \
+    loudness_target_lufs, remove_hum, note_duration_ms, release_time_ms, midi_channel, hum_hz";
+
+const SAMPLE_COLUMNS: &str = "file, note, note_name, velocity, sample_rate, channels, duration_sec, peak_db, rms_db, \
+    normalization_gain_db, metadata, silent_gaps";
+
+/// Where and with what an export should be recorded in the catalog
+#[derive(Debug, Clone, Default)]
+pub struct CatalogConfig {
+    /// Catalog file (None = the default location in the user data folder)
+    pub path: Option<PathBuf>,
+    pub midi_device: Option<String>,
+    pub audio_device: Option<String>,
+    /// Capture settings the samples were recorded with
+    pub sampling: Option<SamplingConfig>,
+}
+
+impl CatalogConfig {
+    pub fn catalog_path(&self) -> Option<PathBuf> {
+        self.path.clone().or_else(SessionCatalog::default_path)
+    }
+}
+
+/// Capture and export settings of a cataloged session
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CatalogSettings {
+    pub format: String,
+    pub normalize: bool,
+    pub apply_detection: bool,
+    pub loudness_target_lufs: Option<f32>,
+    pub remove_hum: bool,
+    pub note_duration_ms: Option<u64>,
+    pub release_time_ms: Option<u64>,
+    /// MIDI channel as shown on the synth (1-16)
+    pub midi_channel: Option<u8>,
+}
+
+/// One exported session as recorded in the catalog
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogSession {
+    pub id: String,
+    pub name: String,
+    /// Folder the samples were exported to
+    pub directory: PathBuf,
+    pub recorded_at: DateTime<Utc>,
+    #[serde(default)]
+    pub midi_device: Option<String>,
+    #[serde(default)]
+    pub audio_device: Option<String>,
+    #[serde(default)]
+    pub settings: CatalogSettings,
+    pub samples: Vec<ManifestEntry>,
+    /// Takes the QA pass flagged
+    #[serde(default)]
+    pub qa_suspects: Vec<SampleQa>,
+    /// Mains hum frequency found in the noise floor, if any
+    #[serde(default)]
+    pub hum_hz: Option<f32>,
+}
+
+impl CatalogSession {
+    pub fn new(manifest: SessionManifest, directory: &Path, qa: &QaReport, settings: CatalogSettings, config: &CatalogConfig) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: manifest.session_name,
+            directory: directory.to_path_buf(),
+            recorded_at: manifest.created_at,
+            midi_device: config.midi_device.clone(),
+            audio_device: config.audio_device.clone(),
+            settings,
+            samples: manifest.samples,
+            qa_suspects: qa.suspects.clone(),
+            hum_hz: qa.hum.as_ref().map(|hum| hum.mains_hz),
+        }
+    }
+
+    /// Lowest and highest note sampled
+    pub fn note_range(&self) -> Option<(u8, u8)> {
+        let notes = self.samples.iter().map(|entry| entry.note);
+        Some((notes.clone().min()?, notes.max()?))
+    }
+
+    pub fn summary(&self) -> String {
+        let notes = match self.note_range() {
+            Some((low, high)) => format!("notes {}-{}", low, high),
+            None => "no samples".to_string(),
+        };
+        format!("{}  {}  {} samples, {}, {} QA issues  [{}]",
+            self.recorded_at.format("%Y-%m-%d %H:%M"), self.name, self.samples.len(), notes, self.qa_suspects.len(), &self.id[..8])
+    }
+}
+
+/// Filter for catalog queries; unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct CatalogQuery {
+    /// Part of the session name (case-insensitive)
+    pub name: Option<String>,
+    /// Part of the MIDI or audio device name (case-insensitive)
+    pub device: Option<String>,
+    /// Sessions that sampled this note
+    pub note: Option<u8>,
+    pub since: Option<DateTime<Utc>>,
+    /// Only sessions with QA issues
+    pub with_issues: bool,
+    /// At most this many, newest first
+    pub limit: Option<usize>,
+}
+
+impl CatalogQuery {
+    /// WHERE clause over the sessions table, with its parameters in order
+    fn filter(&self) -> (String, Vec<Box<dyn ToSql>>) {
+        let mut clauses = Vec::new();
+        let mut params: Vec<Box<dyn ToSql>> = Vec::new();
+        if let Some(name) = &self.name {
+            clauses.push("name LIKE '%' || ? || '%'");
+            params.push(Box::new(name.clone()));
+        }
+        if let Some(device) = &self.device {
+            clauses.push("(midi_device LIKE '%' || ? || '%' OR audio_device LIKE '%' || ? || '%')");
+            params.push(Box::new(device.clone()));
+            params.push(Box::new(device.clone()));
+        }
+        if let Some(note) = self.note {
+            clauses.push("EXISTS (SELECT 1 FROM samples WHERE samples.session_id = sessions.id AND samples.note = ?)");
+            params.push(Box::new(note));
+        }
+        if let Some(since) = self.since {
+            clauses.push("recorded_at >= ?");
+            params.push(Box::new(since));
+        }
+        if self.with_issues {
+            clauses.push("EXISTS (SELECT 1 FROM qa_issues WHERE qa_issues.session_id = sessions.id)");
+        }
+        let filter = if clauses.is_empty() { String::new() } else { format!("WHERE {}", clauses.join(" AND ")) };
+        (filter, params)
+    }
+}
+
+/// Connection to the catalog database
+#[derive(Debug)]
+pub struct SessionCatalog {
+    path: PathBuf,
+    connection: Connection,
+}
+
+impl SessionCatalog {
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("Batcherbird").join(CATALOG_FILENAME))
+    }
+
+    /// Open the catalog at `path`, creating an empty one if it doesn't exist yet
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(BatcherbirdError::Export)?;
+        }
+        let connection = Connection::open(path)?;
+        connection.execute_batch("PRAGMA foreign_keys = ON;")?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { path: path.to_path_buf(), connection })
+    }
+
+    pub fn open_default() -> Result<Self> {
+        let path = Self::default_path()
+            .ok_or_else(|| BatcherbirdError::Config("No user data folder for the session catalog".to_string()))?;
+        Self::open(&path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of cataloged sessions
+    pub fn session_count(&self) -> Result<usize> {
+        let count: usize = self.connection.query_row("SELECT COUNT(*) FROM sessions", [], |row| row.get(0))?;
+        Ok(count)
+    }
+
+    /// Sessions matching `query`, newest first
+    pub fn query(&self, query: &CatalogQuery) -> Result<Vec<CatalogSession>> {
+        let (filter, mut params) = query.filter();
+        // A negative LIMIT is no limit in SQLite
+        params.push(Box::new(query.limit.map_or(-1, |limit| limit as i64)));
+        let sql = format!("SELECT {} FROM sessions {} ORDER BY recorded_at DESC, rowid DESC LIMIT ?", SESSION_COLUMNS, filter);
+        let sessions = self.connection.prepare(&sql)?
+            .query_map(params_from_iter(params), session_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        sessions.into_iter().map(|session| self.with_samples(session)).collect()
+    }
+
+    /// Every cataloged sample of `note`, with its session, newest first
+    pub fn samples_of_note(&self, note: u8) -> Result<Vec<(CatalogSession, ManifestEntry)>> {
+        let sessions = self.query(&CatalogQuery { note: Some(note), ..CatalogQuery::default() })?;
+        Ok(sessions.into_iter()
+            .flat_map(|session| {
+                session.samples.iter().filter(|entry| entry.note == note)
+                    .map(|entry| (session.clone(), entry.clone())).collect::<Vec<_>>()
+            })
+            .collect())
+    }
+
+    /// Session by id, or by a unique prefix of it as shown in listings
+    pub fn find(&self, id: &str) -> Result<Option<CatalogSession>> {
+        if id.is_empty() {
+            return Ok(None);
+        }
+        let sql = format!("SELECT {} FROM sessions WHERE substr(id, 1, length(?1)) = ?1 LIMIT 2", SESSION_COLUMNS);
+        let mut found = self.connection.prepare(&sql)?
+            .query_map([id], session_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        match (found.pop(), found.is_empty()) {
+            (Some(session), true) => self.with_samples(session).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Add a session with its samples and QA results
+    pub fn record(&mut self, session: CatalogSession) -> Result<()> {
+        let settings = &session.settings;
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            &format!("INSERT INTO sessions ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)", SESSION_COLUMNS),
+            params![
+                session.id, session.name, session.directory.to_string_lossy(), session.recorded_at,
+                session.midi_device, session.audio_device, settings.format, settings.normalize, settings.apply_detection,
+                settings.loudness_target_lufs, settings.remove_hum, settings.note_duration_ms, settings.release_time_ms,
+                settings.midi_channel, session.hum_hz,
+            ],
+        )?;
+        {
+            let mut insert = transaction.prepare(&format!(
+                "INSERT INTO samples (session_id, position, {}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                SAMPLE_COLUMNS,
+            ))?;
+            for (position, entry) in session.samples.iter().enumerate() {
+                insert.execute(params![
+                    session.id, position, entry.file, entry.note, entry.note_name, entry.velocity, entry.sample_rate,
+                    entry.channels, entry.duration_sec, entry.peak_db, entry.rms_db, entry.normalization_gain_db,
+                    to_json(&entry.metadata)?, to_json(&entry.silent_gaps)?,
+                ])?;
+            }
+            let mut insert = transaction.prepare(
+                "INSERT INTO qa_issues (session_id, file, note, velocity, issues) VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for suspect in &session.qa_suspects {
+                insert.execute(params![session.id, suspect.file, suspect.note, suspect.velocity, to_json(&suspect.issues)?])?;
+            }
+        }
+        transaction.commit()?;
+        info!("🗂️ Cataloged session '{}' ({} samples)", session.name, session.samples.len());
+        Ok(())
+    }
+
+    /// Drop a session from the catalog (its files are left alone)
+    pub fn remove(&self, id: &str) -> Result<Option<CatalogSession>> {
+        let Some(session) = self.find(id)? else {
+            return Ok(None);
+        };
+        self.connection.execute("DELETE FROM sessions WHERE id = ?1", [&session.id])?;
+        Ok(Some(session))
+    }
+
+    /// Fill in the samples and QA results of a session read from the sessions table
+    fn with_samples(&self, mut session: CatalogSession) -> Result<CatalogSession> {
+        let sql = format!("SELECT {} FROM samples WHERE session_id = ?1 ORDER BY position", SAMPLE_COLUMNS);
+        session.samples = self.connection.prepare_cached(&sql)?
+            .query_map([&session.id], sample_from_row)?
+            .collect::<rusqlite::Result<_>>()?;
+        session.qa_suspects = self.connection
+            .prepare_cached("SELECT file, note, velocity, issues FROM qa_issues WHERE session_id = ?1 ORDER BY rowid")?
+            .query_map([&session.id], |row| {
+                Ok(SampleQa { file: row.get(0)?, note: row.get(1)?, velocity: row.get(2)?, issues: json_column(row, 3)? })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(session)
+    }
+}
+
+/// Session from a row of SESSION_COLUMNS, without its samples
+fn session_from_row(row: &Row) -> rusqlite::Result<CatalogSession> {
+    Ok(CatalogSession {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        directory: PathBuf::from(row.get::<_, String>(2)?),
+        recorded_at: row.get(3)?,
+        midi_device: row.get(4)?,
+        audio_device: row.get(5)?,
+        settings: CatalogSettings {
+            format: row.get(6)?,
+            normalize: row.get(7)?,
+            apply_detection: row.get(8)?,
+            loudness_target_lufs: row.get(9)?,
+            remove_hum: row.get(10)?,
+            note_duration_ms: row.get(11)?,
+            release_time_ms: row.get(12)?,
+            midi_channel: row.get(13)?,
+        },
+        samples: Vec::new(),
+        qa_suspects: Vec::new(),
+        hum_hz: row.get(14)?,
+    })
+}
+
+/// Sample from a row of SAMPLE_COLUMNS
+fn sample_from_row(row: &Row) -> rusqlite::Result<ManifestEntry> {
+    Ok(ManifestEntry {
+        file: row.get(0)?,
+        note: row.get(1)?,
+        note_name: row.get(2)?,
+        velocity: row.get(3)?,
+        sample_rate: row.get(4)?,
+        channels: row.get(5)?,
+        duration_sec: row.get(6)?,
+        peak_db: row.get(7)?,
+        rms_db: row.get(8)?,
+        normalization_gain_db: row.get(9)?,
+        metadata: json_column(row, 10)?,
+        silent_gaps: json_column(row, 11)?,
+    })
+}
+
+/// Structured fields (sample metadata, dropouts, QA issues) are stored as JSON text
+fn to_json<T: Serialize>(value: &T) -> Result<String> {
+    serde_json::to_string(value).map_err(|e| BatcherbirdError::Session(format!("Failed to serialize catalog entry: {}", e)))
+}
+
+fn json_column<T: DeserializeOwned>(row: &Row, index: usize) -> rusqlite::Result<T> {
+    let json: String = row.get(index)?;
+    serde_json::from_str(&json).map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::Sample;
+
+    fn session(name: &str, notes: &[u8], audio_device: &str) -> CatalogSession {
+        let mut manifest = SessionManifest::new(name);
+        for &note in notes {
+            let sample = Sample {
+                note,
+                velocity: 100,
+                audio_data: vec![0.5; 4800],
+                sample_rate: 48000,
+                channels: 1,
+                recorded_at: std::time::SystemTime::now(),
+                midi_timing: std::time::Duration::ZERO,
+                audio_timing: std::time::Duration::ZERO,
+                metadata: Default::default(),
+            };
+            manifest.add_sample(&sample, Path::new(&format!("{}_{}.wav", name, note)));
+        }
+        let config = CatalogConfig { audio_device: Some(audio_device.to_string()), ..CatalogConfig::default() };
+        let qa = QaReport { checked: notes.len(), suspects: Vec::new(), hum: None };
+        CatalogSession::new(manifest, Path::new("/tmp/out"), &qa, CatalogSettings::default(), &config)
+    }
+
+    #[test]
+    fn test_catalog_persists_and_queries_sessions() {
+        let dir = std::env::temp_dir().join("batcherbird_test_catalog");
+        std::fs::remove_dir_all(&dir).ok();
+        let path = dir.join(CATALOG_FILENAME);
+
+        let mut catalog = SessionCatalog::open(&path).unwrap();
+        assert_eq!(catalog.session_count().unwrap(), 0);
+        catalog.record(session("Bass", &[36, 48], "MiniFuse 2")).unwrap();
+        catalog.record(session("Pad", &[48, 60, 72], "Scarlett 2i2")).unwrap();
+
+        let catalog = SessionCatalog::open(&path).unwrap();
+        assert_eq!(catalog.session_count().unwrap(), 2);
+        let names = |query: &CatalogQuery| catalog.query(query).unwrap().into_iter().map(|s| s.name).collect::<Vec<_>>();
+        assert_eq!(names(&CatalogQuery::default()), ["Pad", "Bass"]);
+        assert_eq!(names(&CatalogQuery { device: Some("minifuse".into()), ..Default::default() }), ["Bass"]);
+        assert_eq!(names(&CatalogQuery { note: Some(72), ..Default::default() }), ["Pad"]);
+        assert_eq!(names(&CatalogQuery { limit: Some(1), ..Default::default() }), ["Pad"]);
+        assert_eq!(names(&CatalogQuery { name: Some("ba".into()), ..Default::default() }), ["Bass"]);
+        assert!(names(&CatalogQuery { with_issues: true, ..Default::default() }).is_empty());
+        assert_eq!(catalog.samples_of_note(48).unwrap().len(), 2);
+
+        let pad = catalog.query(&CatalogQuery::default()).unwrap().remove(0);
+        assert_eq!(pad.note_range(), Some((48, 72)));
+        assert_eq!(pad.samples[2].file, "Pad_72.wav");
+        assert_eq!(pad.audio_device.as_deref(), Some("Scarlett 2i2"));
+
+        let bass = catalog.query(&CatalogQuery::default()).unwrap().remove(1);
+        assert_eq!(catalog.find(&bass.id[..8]).unwrap().unwrap().id, bass.id);
+        assert_eq!(catalog.remove(&bass.id[..8]).unwrap().unwrap().name, "Bass");
+        assert_eq!(SessionCatalog::open(&path).unwrap().session_count().unwrap(), 1);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,640 @@
+use crate::{Result, BatcherbirdError};
+use crate::audio::AudioManager;
+use crate::cancel::CancelToken;
+use crate::identity::SynthIdentity;
+use crate::metering::{KScale, MeterReading};
+use crate::midi::MidiManager;
+use crate::midi_monitor::{MidiCapture, MonitorFilter, MonitoredMessage};
+use crate::multi_device::SecondaryInputConfig;
+use crate::passthrough::{PassthroughConfig, PassthroughHandle};
+use crate::playback::{self, PlaybackHandle};
+use crate::queue::SamplingQueue;
+use crate::sampler::{ArmedCapture, AudioLevels, PeakHoldConfig, Sample, SamplingConfig, SamplingEngine};
+use cpal::traits::StreamTrait;
+use midir::MidiOutputConnection;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// What the engine is doing. Recording and exporting exclude each other; monitoring runs
+/// in the background and is reported when nothing else is going on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EngineState {
+    Idle,
+    Monitoring,
+    Recording,
+    Exporting,
+}
+
+/// Owns the MIDI connection, input monitoring, passthrough, armed capture, file playback and
+/// the sampling queue for a front end, so commands arriving on any thread go through one
+/// place. Every method takes `&self`; keep a single controller in a static or the app's
+/// shared state.
+pub struct EngineController {
+    midi_manager: Mutex<Option<MidiManager>>,
+    midi_connection: Mutex<Option<MidiOutputConnection>>,
+    /// Name of the connected output, for session records
+    midi_device_name: Mutex<Option<String>>,
+    /// Recording or Exporting while one runs
+    activity: Mutex<Option<EngineState>>,
+    monitor: Mutex<Option<Monitor>>,
+    /// Plays the monitored input; stops with monitoring
+    passthrough: Mutex<Option<PassthroughHandle>>,
+    armed: Mutex<Option<ArmedCapture>>,
+    /// Token of the running capture, for cancel_recording
+    cancel: Mutex<Option<CancelToken>>,
+    midi_monitor: Mutex<Option<MidiMonitor>>,
+    /// Messages of the last MIDI monitor run, kept after stopping so they can still be saved
+    last_midi_capture: Mutex<Option<MidiCapture>>,
+    /// File, region or loop playback; starting another stops it
+    playback: Mutex<Option<PlaybackHandle>>,
+    /// Ranges built up in a queue panel, recorded in one run
+    queue: Mutex<SamplingQueue>,
+}
+
+/// Running input monitor: the engine reading levels and the thread owning its stream
+struct Monitor {
+    engine: Arc<SamplingEngine>,
+    running: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+/// Running MIDI monitor: the thread holding the input connection and the messages it kept
+struct MidiMonitor {
+    running: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+    capture: Arc<Mutex<MidiCapture>>,
+}
+
+/// Holds the engine in Recording or Exporting; dropping it returns to the previous state
+pub struct ActivityGuard<'a> {
+    controller: &'a EngineController,
+}
+
+impl Drop for ActivityGuard<'_> {
+    fn drop(&mut self) {
+        *self.controller.activity.lock().unwrap() = None;
+    }
+}
+
+impl Default for EngineController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EngineController {
+    pub const fn new() -> Self {
+        Self {
+            midi_manager: Mutex::new(None),
+            midi_connection: Mutex::new(None),
+            midi_device_name: Mutex::new(None),
+            activity: Mutex::new(None),
+            monitor: Mutex::new(None),
+            passthrough: Mutex::new(None),
+            armed: Mutex::new(None),
+            cancel: Mutex::new(None),
+            midi_monitor: Mutex::new(None),
+            last_midi_capture: Mutex::new(None),
+            playback: Mutex::new(None),
+            queue: Mutex::new(SamplingQueue::new()),
+        }
+    }
+
+    pub fn state(&self) -> EngineState {
+        if let Some(activity) = *self.activity.lock().unwrap() {
+            return activity;
+        }
+        if self.is_monitoring() {
+            EngineState::Monitoring
+        } else {
+            EngineState::Idle
+        }
+    }
+
+    /// Enter Recording or Exporting until the guard is dropped. Fails while another
+    /// recording or export is running.
+    pub fn begin(&self, state: EngineState) -> Result<ActivityGuard<'_>> {
+        self.enter(state)?;
+        Ok(ActivityGuard { controller: self })
+    }
+
+    fn enter(&self, state: EngineState) -> Result<()> {
+        if !matches!(state, EngineState::Recording | EngineState::Exporting) {
+            return Err(BatcherbirdError::Session(format!("{:?} is not an activity", state)));
+        }
+        let mut activity = self.activity.lock().unwrap();
+        if let Some(current) = *activity {
+            return Err(BatcherbirdError::Session(format!("Engine is busy ({:?})", current)));
+        }
+        *activity = Some(state);
+        Ok(())
+    }
+
+    // === MIDI ===
+
+    pub fn list_midi_outputs(&self) -> Result<Vec<String>> {
+        self.with_midi_manager(|manager| manager.list_output_devices())
+    }
+
+    pub fn list_midi_inputs(&self) -> Result<Vec<String>> {
+        self.with_midi_manager(|manager| manager.list_input_devices())
+    }
+
+    /// Connect the MIDI output used for every later command, replacing any earlier one
+    pub fn connect_midi(&self, device_index: usize) -> Result<()> {
+        let (connection, name) = self.with_midi_manager(|manager| {
+            let name = manager.list_output_devices()?.get(device_index).cloned();
+            Ok((manager.connect_output(device_index)?, name))
+        })?;
+        *self.midi_connection.lock().unwrap() = Some(connection);
+        *self.midi_device_name.lock().unwrap() = name;
+        info!("✅ MIDI device {} connected", device_index);
+        Ok(())
+    }
+
+    pub fn is_midi_connected(&self) -> bool {
+        self.midi_connection.lock().unwrap().is_some()
+    }
+
+    pub fn midi_device_name(&self) -> Option<String> {
+        self.midi_device_name.lock().unwrap().clone()
+    }
+
+    /// Play one note on `channel` for `duration` (blocks until note-off)
+    pub fn play_note(&self, channel: u8, note: u8, velocity: u8, duration: Duration) -> Result<()> {
+        self.with_midi_connection(|connection| {
+            MidiManager::send_note_on(connection, channel, note, velocity)?;
+            std::thread::sleep(duration);
+            MidiManager::send_note_off(connection, channel, note, velocity)
+        })
+    }
+
+    /// All notes off / controllers reset on every channel
+    pub fn send_panic(&self) -> Result<()> {
+        self.with_midi_connection(MidiManager::send_midi_panic)
+    }
+
+    /// Ask the connected synth who it is, listening for the reply on `input_device_index`
+    pub fn identify_synth(&self, input_device_index: usize, timeout: Duration) -> Result<SynthIdentity> {
+        let mut connection_guard = self.midi_connection.lock().unwrap();
+        let connection = connection_guard.as_mut().ok_or_else(Self::not_connected)?;
+        self.with_midi_manager(|manager| manager.identify_device(connection, input_device_index, timeout))
+    }
+
+    /// Run a capture with the MIDI connection in Recording state. The capture runs on its
+    /// own thread, clear of any async runtime the caller is on.
+    pub fn record<R, F>(&self, capture: F) -> Result<R>
+    where
+        F: FnOnce(&mut MidiOutputConnection) -> Result<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        self.record_cancellable(|connection, _| capture(connection))
+    }
+
+    /// [`record`](Self::record) with a token that [`cancel_recording`](Self::cancel_recording)
+    /// cancels; hand it to the engine with `SamplingEngine::with_cancel`
+    pub fn record_cancellable<R, F>(&self, capture: F) -> Result<R>
+    where
+        F: FnOnce(&mut MidiOutputConnection, CancelToken) -> Result<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        let _recording = self.begin(EngineState::Recording)?;
+        let mut connection = self.midi_connection.lock().unwrap().take().ok_or_else(Self::not_connected)?;
+        let cancel = CancelToken::new();
+        *self.cancel.lock().unwrap() = Some(cancel.clone());
+
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let result = capture(&mut connection, cancel);
+            let _ = tx.send((result, connection));
+        });
+        let received = rx.recv();
+        self.cancel.lock().unwrap().take();
+        let (result, connection) = received
+            .map_err(|_| BatcherbirdError::Audio("Capture thread exited unexpectedly; reconnect the MIDI device".to_string()))?;
+        *self.midi_connection.lock().unwrap() = Some(connection);
+        result
+    }
+
+    /// Stop the running capture; false if nothing is recording
+    pub fn cancel_recording(&self) -> bool {
+        match self.cancel.lock().unwrap().as_ref() {
+            Some(cancel) => {
+                info!("⏹️ Cancelling the recording");
+                cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn with_midi_manager<R>(&self, f: impl FnOnce(&mut MidiManager) -> Result<R>) -> Result<R> {
+        let mut manager_guard = self.midi_manager.lock().unwrap();
+        if manager_guard.is_none() {
+            *manager_guard = Some(MidiManager::new()?);
+        }
+        f(manager_guard.as_mut().unwrap())
+    }
+
+    fn with_midi_connection<R>(&self, f: impl FnOnce(&mut MidiOutputConnection) -> Result<R>) -> Result<R> {
+        // A capture has the connection on its own thread
+        if *self.activity.lock().unwrap() == Some(EngineState::Recording) {
+            return Err(BatcherbirdError::Session("MIDI is in use by a recording".to_string()));
+        }
+        let mut connection_guard = self.midi_connection.lock().unwrap();
+        f(connection_guard.as_mut().ok_or_else(Self::not_connected)?)
+    }
+
+    fn not_connected() -> BatcherbirdError {
+        BatcherbirdError::Session("No MIDI connection established. Please select a MIDI device first.".to_string())
+    }
+
+    // === MIDI monitor ===
+
+    pub fn is_midi_monitoring(&self) -> bool {
+        self.midi_monitor.lock().unwrap().is_some()
+    }
+
+    /// Watch MIDI input `device_index`, handing messages that pass `filter` to `on_message`
+    /// and keeping them for [`stop_midi_monitor`](Self::stop_midi_monitor). Returns once
+    /// connected; false if a monitor was already running.
+    pub fn start_midi_monitor<F>(&self, device_index: usize, filter: MonitorFilter, on_message: F) -> Result<bool>
+    where
+        F: Fn(&MonitoredMessage) + Send + 'static,
+    {
+        let mut midi_monitor = self.midi_monitor.lock().unwrap();
+        if midi_monitor.is_some() {
+            return Ok(false);
+        }
+
+        let device = self.list_midi_inputs()?.get(device_index).cloned();
+        let capture = Arc::new(Mutex::new(MidiCapture::new(device)));
+        let running = Arc::new(AtomicBool::new(true));
+        let (ready_tx, ready_rx) = mpsc::channel::<Result<()>>();
+        let (thread_capture, thread_running) = (Arc::clone(&capture), Arc::clone(&running));
+        // The input connection lives and dies with this thread, like the monitoring stream
+        let thread = std::thread::spawn(move || {
+            let connected = MidiManager::new()
+                .and_then(|mut manager| manager.connect_input_monitor(device_index, filter));
+            let (_connection, messages) = match connected {
+                Ok(connected) => connected,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+            let _ = ready_tx.send(Ok(()));
+
+            while thread_running.load(Ordering::Relaxed) {
+                match messages.recv_timeout(Duration::from_millis(100)) {
+                    Ok(message) => {
+                        on_message(&message);
+                        thread_capture.lock().unwrap().push(message);
+                    }
+                    Err(mpsc::RecvTimeoutError::Timeout) => {}
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                }
+            }
+        });
+
+        match ready_rx.recv() {
+            Ok(Ok(())) => {
+                *midi_monitor = Some(MidiMonitor { running, thread, capture });
+                info!("✅ MIDI monitor started on input {}", device_index);
+                Ok(true)
+            }
+            Ok(Err(e)) => {
+                let _ = thread.join();
+                Err(e)
+            }
+            Err(_) => Err(BatcherbirdError::Session("MIDI monitor thread exited unexpectedly".to_string())),
+        }
+    }
+
+    /// Messages the running monitor has kept so far, or after stopping those of its last run
+    pub fn midi_monitor_capture(&self) -> Option<MidiCapture> {
+        match self.midi_monitor.lock().unwrap().as_ref() {
+            Some(midi_monitor) => Some(midi_monitor.capture.lock().unwrap().clone()),
+            None => self.last_midi_capture.lock().unwrap().clone(),
+        }
+    }
+
+    /// Stop the MIDI monitor and return what it kept, which stays available from
+    /// [`midi_monitor_capture`](Self::midi_monitor_capture); None if it wasn't running
+    pub fn stop_midi_monitor(&self) -> Option<MidiCapture> {
+        let midi_monitor = self.midi_monitor.lock().unwrap().take()?;
+        midi_monitor.running.store(false, Ordering::Relaxed);
+        if midi_monitor.thread.join().is_err() {
+            warn!("⚠️ Warning: MIDI monitor thread did not exit cleanly");
+        }
+        info!("✅ MIDI monitor stopped");
+        let capture = std::mem::take(&mut *midi_monitor.capture.lock().unwrap());
+        *self.last_midi_capture.lock().unwrap() = Some(capture.clone());
+        Some(capture)
+    }
+
+    // === Audio devices ===
+
+    /// Record from the input device called `name` (None = MiniFuse or the system default).
+    /// Monitoring and an armed stream hold the old device, so both are stopped; returns
+    /// whether monitoring was running, for the caller to start it again.
+    pub fn set_audio_input(&self, name: Option<String>) -> Result<bool> {
+        if self.state() == EngineState::Recording {
+            return Err(BatcherbirdError::Session("Can't change the audio input while recording".to_string()));
+        }
+        if let Some(name) = &name {
+            if !AudioManager::new()?.list_input_devices()?.contains(name) {
+                return Err(BatcherbirdError::Audio(format!("No audio input device called '{}'", name)));
+            }
+        }
+        let was_monitoring = self.is_monitoring();
+        self.stop_monitoring();
+        self.disarm_capture();
+        info!("🎤 Audio input: {}", name.as_deref().unwrap_or("default"));
+        AudioManager::set_preferred_input(name);
+        Ok(was_monitoring)
+    }
+
+    /// Play back on the output device called `name` (None = MiniFuse or the system default).
+    /// A running passthrough keeps its output until it is restarted.
+    pub fn set_audio_output(&self, name: Option<String>) -> Result<()> {
+        if let Some(name) = &name {
+            if !AudioManager::new()?.list_output_devices()?.contains(name) {
+                return Err(BatcherbirdError::Audio(format!("No audio output device called '{}'", name)));
+            }
+        }
+        info!("🔊 Audio output: {}", name.as_deref().unwrap_or("default"));
+        AudioManager::set_preferred_output(name);
+        Ok(())
+    }
+
+    /// Pair a channel of a second device with the input for stereo batch takes (None records
+    /// the input alone). Takes effect from the next batch.
+    pub fn set_secondary_input(&self, config: Option<SecondaryInputConfig>) -> Result<()> {
+        if self.state() == EngineState::Recording {
+            return Err(BatcherbirdError::Session("Can't change the second input while recording".to_string()));
+        }
+        if let Some(config) = &config {
+            AudioManager::new()?.input_device_named(&config.device)?;
+        }
+        info!("🔀 Second input: {}", config.as_ref().map_or("none".to_string(), |config| format!("{} channel {}", config.device, config.channel + 1)));
+        AudioManager::set_secondary_input(config);
+        Ok(())
+    }
+
+    // === Monitoring ===
+
+    pub fn is_monitoring(&self) -> bool {
+        self.monitor.lock().unwrap().is_some()
+    }
+
+    /// Open the input for metering and push readings to `on_levels` at `meter_rate_hz`.
+    /// Returns once the stream is live; false if monitoring was already running.
+    pub fn start_monitoring<F>(&self, meter_rate_hz: f32, k_scale: KScale, on_levels: F) -> Result<bool>
+    where
+        F: Fn(MeterReading) + Send + 'static,
+    {
+        let mut monitor = self.monitor.lock().unwrap();
+        if monitor.is_some() {
+            return Ok(false);
+        }
+
+        let engine = Arc::new(SamplingEngine::new(SamplingConfig::default())?);
+        let running = Arc::new(AtomicBool::new(true));
+        let (ready_tx, ready_rx) = mpsc::channel::<Result<()>>();
+        let (thread_engine, thread_running) = (Arc::clone(&engine), Arc::clone(&running));
+        // cpal streams are not Send, so the stream is created and owned by this thread
+        let thread = std::thread::spawn(move || {
+            let stream = match thread_engine.start_monitoring_stream().and_then(|stream| {
+                stream.play().map_err(|e| BatcherbirdError::Audio(format!("Failed to start monitoring stream: {}", e)))?;
+                Ok(stream)
+            }) {
+                Ok(stream) => stream,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+            let _ = ready_tx.send(Ok(()));
+
+            // Stops pushing when dropped at the end of the thread
+            let _publisher = thread_engine.publish_levels(meter_rate_hz, k_scale, on_levels);
+            while thread_running.load(Ordering::Relaxed) {
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            if let Err(e) = stream.pause() {
+                warn!("⚠️ Warning: Failed to pause monitoring stream: {}", e);
+            }
+        });
+
+        match ready_rx.recv() {
+            Ok(Ok(())) => {
+                *monitor = Some(Monitor { engine, running, thread });
+                info!("✅ Audio input monitoring started");
+                Ok(true)
+            }
+            Ok(Err(e)) => {
+                let _ = thread.join();
+                Err(e)
+            }
+            Err(_) => Err(BatcherbirdError::Audio("Monitoring thread exited unexpectedly".to_string())),
+        }
+    }
+
+    /// Stop monitoring (and the passthrough reading from it)
+    pub fn stop_monitoring(&self) {
+        self.stop_passthrough();
+        let Some(monitor) = self.monitor.lock().unwrap().take() else {
+            return;
+        };
+        monitor.running.store(false, Ordering::Relaxed);
+        if monitor.thread.join().is_err() {
+            warn!("⚠️ Warning: Monitoring thread did not exit cleanly");
+        }
+        info!("✅ Audio input monitoring stopped");
+    }
+
+    /// Current input levels (silent when not monitoring)
+    pub fn audio_levels(&self) -> AudioLevels {
+        self.monitor.lock().unwrap().as_ref()
+            .map(|monitor| monitor.engine.get_audio_levels())
+            .unwrap_or_default()
+    }
+
+    pub fn set_peak_hold(&self, config: PeakHoldConfig) -> Result<()> {
+        self.monitoring_engine()?.set_peak_hold(config);
+        Ok(())
+    }
+
+    /// Play the monitored input on an output, replacing any passthrough already running
+    pub fn start_passthrough(&self, config: &PassthroughConfig) -> Result<()> {
+        let engine = self.monitoring_engine()?;
+        self.stop_passthrough();
+        let handle = engine.start_passthrough(config)?;
+        *self.passthrough.lock().unwrap() = Some(handle);
+        Ok(())
+    }
+
+    pub fn set_passthrough_gain(&self, gain_db: f32) -> Result<()> {
+        let passthrough = self.passthrough.lock().unwrap();
+        let handle = passthrough.as_ref()
+            .ok_or_else(|| BatcherbirdError::Session("Passthrough is not running".to_string()))?;
+        handle.set_gain_db(gain_db);
+        Ok(())
+    }
+
+    pub fn stop_passthrough(&self) {
+        self.passthrough.lock().unwrap().take();
+    }
+
+    fn monitoring_engine(&self) -> Result<Arc<SamplingEngine>> {
+        self.monitor.lock().unwrap().as_ref()
+            .map(|monitor| Arc::clone(&monitor.engine))
+            .ok_or_else(|| BatcherbirdError::Session("Start input monitoring first".to_string()))
+    }
+
+    // === Armed capture ===
+
+    /// Open an input stream for instant-start takes; false if one is already armed
+    pub fn arm_capture(&self) -> Result<bool> {
+        let mut armed = self.armed.lock().unwrap();
+        if armed.is_some() {
+            return Ok(false);
+        }
+        let engine = SamplingEngine::new(SamplingConfig::default())?;
+        *armed = Some(engine.arm_capture()?);
+        Ok(true)
+    }
+
+    pub fn disarm_capture(&self) {
+        if let Some(capture) = self.armed.lock().unwrap().take() {
+            if capture.is_recording() {
+                *self.activity.lock().unwrap() = None;
+            }
+            capture.disarm();
+        }
+    }
+
+    /// Sample rate and channel count of the armed stream
+    pub fn armed_format(&self) -> Option<(u32, u16)> {
+        self.armed.lock().unwrap().as_ref().map(|capture| (capture.sample_rate(), capture.channels()))
+    }
+
+    /// Start a take on the armed stream; the engine stays in Recording until it stops
+    pub fn start_armed_take(&self) -> Result<()> {
+        let mut armed = self.armed.lock().unwrap();
+        let capture = armed.as_mut()
+            .ok_or_else(|| BatcherbirdError::Session("Capture is not armed".to_string()))?;
+        self.enter(EngineState::Recording)?;
+        capture.start_take();
+        Ok(())
+    }
+
+    pub fn stop_armed_take(&self, note: u8, velocity: u8) -> Result<Sample> {
+        let mut armed = self.armed.lock().unwrap();
+        let capture = armed.as_mut()
+            .ok_or_else(|| BatcherbirdError::Session("Capture is not armed".to_string()))?;
+        let sample = capture.stop_take(note, velocity)?;
+        *self.activity.lock().unwrap() = None;
+        Ok(sample)
+    }
+
+    // === Playback ===
+
+    /// Play frames start_frame..end_frame of a WAV (None = to the end), stopping whatever was playing
+    pub fn play_region(&self, path: &Path, start_frame: usize, end_frame: Option<usize>) -> Result<()> {
+        let mut playback = self.playback.lock().unwrap();
+        playback.take();
+        *playback = Some(playback::play_region(path, start_frame, end_frame)?);
+        Ok(())
+    }
+
+    /// Loop loop_start..loop_end of a WAV with a crossfade, stopping whatever was playing
+    pub fn play_loop(&self, path: &Path, loop_start: usize, loop_end: usize, crossfade: Duration) -> Result<()> {
+        let mut playback = self.playback.lock().unwrap();
+        playback.take();
+        let sample_rate = hound::WavReader::open(path)
+            .map_err(|e| BatcherbirdError::Processing(format!("Failed to open {}: {}", path.display(), e)))?
+            .spec()
+            .sample_rate;
+        let crossfade_frames = (crossfade.as_secs_f32() * sample_rate as f32) as usize;
+        *playback = Some(playback::play_loop(path, loop_start, loop_end, crossfade_frames)?);
+        Ok(())
+    }
+
+    /// Move the loop points of the running loop audition without restarting it
+    pub fn set_loop_points(&self, loop_start: usize, loop_end: usize, crossfade: Duration) -> Result<()> {
+        let playback = self.playback.lock().unwrap();
+        let handle = playback.as_ref()
+            .filter(|handle| handle.is_playing())
+            .ok_or_else(|| BatcherbirdError::Session("No loop audition running".to_string()))?;
+        let crossfade_frames = (crossfade.as_secs_f32() * handle.sample_rate() as f32) as usize;
+        handle.set_loop_points(loop_start, loop_end, crossfade_frames)
+    }
+
+    pub fn stop_playback(&self) {
+        self.playback.lock().unwrap().take();
+    }
+
+    /// Current playback frame, or None when nothing is playing
+    pub fn playback_position(&self) -> Option<usize> {
+        self.playback.lock().unwrap().as_ref()
+            .filter(|handle| handle.is_playing())
+            .map(|handle| handle.position_frames())
+    }
+
+    // === Sampling queue ===
+
+    /// A copy of the queue, e.g. to record it
+    pub fn sampling_queue(&self) -> SamplingQueue {
+        self.queue.lock().unwrap().clone()
+    }
+
+    /// Change the queue; `edit` runs with the queue locked
+    pub fn edit_queue<R>(&self, edit: impl FnOnce(&mut SamplingQueue) -> R) -> R {
+        edit(&mut self.queue.lock().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_activities_exclude_each_other() {
+        let controller = EngineController::new();
+        assert_eq!(controller.state(), EngineState::Idle);
+
+        let recording = controller.begin(EngineState::Recording).unwrap();
+        assert_eq!(controller.state(), EngineState::Recording);
+        assert!(controller.begin(EngineState::Exporting).is_err());
+        // MIDI commands wait for the capture to give the connection back
+        assert!(controller.send_panic().is_err());
+        drop(recording);
+
+        let exporting = controller.begin(EngineState::Exporting).unwrap();
+        assert_eq!(controller.state(), EngineState::Exporting);
+        drop(exporting);
+        assert_eq!(controller.state(), EngineState::Idle);
+        assert!(controller.begin(EngineState::Monitoring).is_err());
+
+        // Nothing connected or armed yet
+        assert!(controller.record(|_| Ok(())).is_err());
+        assert!(!controller.cancel_recording());
+        assert!(controller.set_audio_input(Some("No Such Interface 9000".to_string())).is_err());
+        assert!(controller.set_audio_output(Some("No Such Interface 9000".to_string())).is_err());
+        assert!(controller.set_secondary_input(SecondaryInputConfig::parse("No Such Interface 9000:2")).is_err());
+        assert_eq!(controller.state(), EngineState::Idle);
+        assert!(controller.start_armed_take().is_err());
+        assert!(controller.set_passthrough_gain(-6.0).is_err());
+        assert_eq!(controller.audio_levels().clip_count, 0);
+        assert!(controller.set_loop_points(0, 100, Duration::ZERO).is_err());
+        assert_eq!(controller.playback_position(), None);
+        assert!(controller.stop_midi_monitor().is_none() && controller.midi_monitor_capture().is_none());
+    }
+}
@@ -0,0 +1,159 @@
+use crate::Result;
+use crate::export::{AudioFormat, ExportConfig, SampleExporter};
+use crate::manifest::SessionManifest;
+use crate::sampler::{Sample, SampleMetadata};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::info;
+
+/// Notes in the demo session: one C major arpeggio across two octaves
+const DEMO_NOTES: [u8; 4] = [48, 52, 55, 60];
+/// Two velocity layers so the instrument files show layering
+const DEMO_VELOCITIES: [u8; 2] = [64, 127];
+const DEMO_SAMPLE_RATE: u32 = 44100;
+
+/// Files produced by the demo project generator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DemoProject {
+    pub directory: PathBuf,
+    pub sample_files: Vec<PathBuf>,
+    pub instrument_files: Vec<PathBuf>,
+    pub manifest_path: PathBuf,
+    pub report_path: PathBuf,
+}
+
+/// Generate a small demo session in `directory` without any hardware:
+/// simulated captures, SFZ and Decent Sampler instruments, manifest and report.
+pub fn generate_demo_project(directory: &Path) -> Result<DemoProject> {
+    info!("🧪 Generating demo project in: {}", directory.display());
+
+    let samples: Vec<Sample> = DEMO_VELOCITIES.iter()
+        .flat_map(|&velocity| DEMO_NOTES.iter().map(move |&note| simulated_sample(note, velocity)))
+        .collect();
+
+    let export_config = ExportConfig {
+        output_directory: directory.to_path_buf(),
+        naming_pattern: "Demo_{note_name}_{note}_{velocity}.wav".to_string(),
+        sample_format: AudioFormat::SFZ,
+        normalize: false,
+        fade_in_ms: 0.0,
+        fade_out_ms: 10.0,
+        apply_detection: true,
+        detection_config: Default::default(),
+        creator_name: Some("Batcherbird".to_string()),
+        instrument_description: Some("Demo instrument generated from simulated captures".to_string()),
+        drum_map: None,
+        readme: None,
+        sidecar_edits: false,
+        dual_mono: false,
+        instrument_bit_depth: Default::default(),
+        additional_outputs: Vec::new(),
+        apply_loop_detection: false,
+        loop_detection_config: Default::default(),
+        classify_samples: false,
+        align_onsets_ms: None,
+        loudness_target_lufs: None,
+        remove_hum: false,
+        hooks: Default::default(),
+        catalog: None,
+        overwrite: Default::default(),
+        naming: Default::default(),
+        ui_background: None,
+        velocity_crossfade: None,
+        gain_matching: false,
+        dither: Default::default(),
+        fade_in_curve: Default::default(),
+        fade_out_curve: Default::default(),
+        layout: Default::default(),
+    };
+
+    // SFZ export writes the WAVs plus the .sfz; the .dspreset reuses the same WAVs
+    let sfz_exporter = SampleExporter::new(export_config.clone())?;
+    let mut exported = sfz_exporter.export_samples(&samples)?;
+    let sfz_path = exported.pop().expect("SFZ export always ends with the .sfz file");
+    let sample_files = exported;
+
+    let dspreset_exporter = SampleExporter::new(ExportConfig {
+        sample_format: AudioFormat::DecentSampler,
+        ..export_config
+    })?;
+    let dspreset_path = dspreset_exporter.generate_dspreset_file(&samples, &sample_files)?;
+
+    let mut manifest = SessionManifest::new("Batcherbird Demo");
+    for (sample, file) in samples.iter().zip(&sample_files) {
+        manifest.add_sample(sample, file);
+    }
+    manifest.add_instrument_file(&sfz_path);
+    manifest.add_instrument_file(&dspreset_path);
+
+    let manifest_path = manifest.save(directory)?;
+    let report_path = manifest.write_report(directory)?;
+
+    info!("🎉 Demo project ready: {} samples, 2 instruments", sample_files.len());
+
+    Ok(DemoProject {
+        directory: directory.to_path_buf(),
+        sample_files,
+        instrument_files: vec![sfz_path, dspreset_path],
+        manifest_path,
+        report_path,
+    })
+}
+
+/// Synthesize a mono capture that looks like a real recording: leading silence
+/// (MIDI/audio latency), a plucked saw-ish tone, release tail and noise floor.
+fn simulated_sample(note: u8, velocity: u8) -> Sample {
+    let sample_rate = DEMO_SAMPLE_RATE as f32;
+    let frequency = 440.0 * 2.0f32.powf((note as f32 - 69.0) / 12.0);
+    let amplitude = 0.7 * velocity as f32 / 127.0;
+
+    let latency_frames = (0.03 * sample_rate) as usize;
+    let hold_frames = (0.8 * sample_rate) as usize;
+    let release_frames = (0.4 * sample_rate) as usize;
+    let tail_frames = (0.2 * sample_rate) as usize;
+    let total_frames = latency_frames + hold_frames + release_frames + tail_frames;
+
+    // Cheap deterministic noise so every run produces identical files
+    let mut noise_state: u32 = 0x1234_5678 ^ ((note as u32) << 8) ^ velocity as u32;
+    let mut noise = move || {
+        noise_state ^= noise_state << 13;
+        noise_state ^= noise_state >> 17;
+        noise_state ^= noise_state << 5;
+        (noise_state as f32 / u32::MAX as f32 - 0.5) * 0.0004
+    };
+
+    let mut audio_data = Vec::with_capacity(total_frames);
+    for frame in 0..total_frames {
+        let tone = if frame < latency_frames || frame >= latency_frames + hold_frames + release_frames {
+            0.0
+        } else {
+            let t = (frame - latency_frames) as f32 / sample_rate;
+            let attack = (t / 0.005).min(1.0);
+            let decay = 0.6 + 0.4 * (-t * 4.0).exp();
+            let release = if frame >= latency_frames + hold_frames {
+                1.0 - (frame - latency_frames - hold_frames) as f32 / release_frames as f32
+            } else {
+                1.0
+            };
+
+            let phase = 2.0 * std::f32::consts::PI * frequency * t;
+            let wave = phase.sin() + 0.5 * (2.0 * phase).sin() + 0.25 * (3.0 * phase).sin();
+            amplitude * attack * decay * release * wave / 1.75
+        };
+
+        audio_data.push(tone + noise());
+    }
+
+    Sample {
+        note,
+        velocity,
+        audio_data,
+        sample_rate: DEMO_SAMPLE_RATE,
+        channels: 1,
+        recorded_at: std::time::SystemTime::now(),
+        midi_timing: Duration::ZERO,
+        audio_timing: Duration::from_secs_f32(latency_frames as f32 / sample_rate),
+        metadata: SampleMetadata::default(),
+    }
+}
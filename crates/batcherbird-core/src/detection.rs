@@ -1,4 +1,7 @@
 use crate::{Result, BatcherbirdError};
+use crate::loop_detection::fft;
+use serde::Serialize;
+use tracing::{info, warn};
 
 /// Sample detection configuration for automatic trimming
 #[derive(Debug, Clone)]
@@ -20,6 +23,20 @@ pub struct DetectionConfig {
     
     /// Number of consecutive windows required to confirm start/end
     pub confirmation_windows: usize,
+    
+    /// How the boundaries are found
+    pub algorithm: DetectionAlgorithm,
+}
+
+/// Boundary detection algorithm, chosen per kind of material
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DetectionAlgorithm {
+    /// RMS windows against the silence threshold ([`SampleDetector`])
+    #[default]
+    Rms,
+    /// Spectral-flux onset and backward-integrated energy tail ([`SpectralFluxDetector`]);
+    /// finds attacks under a noise floor and ends tails by how much energy is left
+    SpectralFlux,
 }
 
 impl Default for DetectionConfig {
@@ -31,6 +48,7 @@ impl Default for DetectionConfig {
             pre_trigger_ms: 20.0,       // 20ms pre-trigger
             post_trigger_ms: 200.0,     // 200ms for reverb tails
             confirmation_windows: 3,    // 3 consecutive windows for stability
+            algorithm: DetectionAlgorithm::Rms,
         }
     }
 }
@@ -45,6 +63,7 @@ impl DetectionConfig {
             pre_trigger_ms: 10.0,
             post_trigger_ms: 50.0,
             confirmation_windows: 2,
+            algorithm: DetectionAlgorithm::Rms,
         }
     }
     
@@ -57,10 +76,12 @@ impl DetectionConfig {
             pre_trigger_ms: 50.0,
             post_trigger_ms: 500.0,
             confirmation_windows: 4,
+            algorithm: DetectionAlgorithm::Rms,
         }
     }
     
-    /// Preset for vintage synthesizers (more noise-tolerant)
+    /// Preset for vintage synthesizers (more noise-tolerant; hiss and hum often sit above
+    /// any usable threshold, so onsets are found by spectral flux)
     pub fn vintage_synth() -> Self {
         Self {
             threshold_db: -35.0,
@@ -69,12 +90,21 @@ impl DetectionConfig {
             pre_trigger_ms: 30.0,
             post_trigger_ms: 300.0,
             confirmation_windows: 3,
+            algorithm: DetectionAlgorithm::SpectralFlux,
+        }
+    }
+    
+    /// Detector running this configuration's algorithm
+    pub fn detector(&self) -> Box<dyn BoundaryDetector> {
+        match self.algorithm {
+            DetectionAlgorithm::Rms => Box::new(SampleDetector::new(self.clone())),
+            DetectionAlgorithm::SpectralFlux => Box::new(SpectralFluxDetector::new(self.clone())),
         }
     }
 }
 
 /// Result of sample detection analysis
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct DetectionResult {
     /// Start sample index (after applying pre-trigger)
     pub start_sample: usize,
@@ -91,6 +121,13 @@ pub struct DetectionResult {
     /// RMS energy values for each window (for debugging/visualization)
     pub rms_values: Vec<f32>,
     
+    /// Start time of each RMS window in milliseconds, parallel to `rms_values`
+    pub window_times_ms: Vec<f32>,
+    
+    /// Silence threshold the windows were compared against (dB and linear RMS)
+    pub threshold_db: f32,
+    pub threshold_linear: f32,
+    
     /// Whether detection was successful
     pub success: bool,
     
@@ -98,36 +135,60 @@ pub struct DetectionResult {
     pub failure_reason: Option<String>,
 }
 
+/// Finds where the sound in a take starts and ends. Indices are into the audio as given
+/// (interleaved audio is treated as one stream).
+pub trait BoundaryDetector: Send + Sync {
+    /// Analyze audio and detect sample boundaries
+    fn detect_boundaries(&self, audio_data: &[f32], sample_rate: u32) -> Result<DetectionResult>;
+    
+    /// Trim audio data based on detection result
+    fn trim_audio(&self, audio_data: &[f32], detection: &DetectionResult) -> Vec<f32> {
+        if !detection.success {
+            warn!("⚠️  Detection failed, returning original audio");
+            return audio_data.to_vec();
+        }
+        
+        let start = detection.start_sample.min(audio_data.len());
+        let end = detection.end_sample.min(audio_data.len());
+        
+        if start >= end {
+            warn!("⚠️  Invalid detection boundaries, returning original audio");
+            return audio_data.to_vec();
+        }
+        
+        info!("✂️  Trimming audio: {} -> {} samples ({:.1}% reduction)",
+            audio_data.len(),
+            end - start,
+            ((audio_data.len() - (end - start)) as f32 / audio_data.len() as f32) * 100.0);
+        
+        audio_data[start..end].to_vec()
+    }
+}
+
 /// Professional sample detection engine using RMS window analysis
 pub struct SampleDetector {
     config: DetectionConfig,
 }
 
+impl Default for SampleDetector {
+    /// Detector with default settings
+    fn default() -> Self {
+        Self::new(DetectionConfig::default())
+    }
+}
+
 impl SampleDetector {
     pub fn new(config: DetectionConfig) -> Self {
         Self { config }
     }
     
-    /// Create detector with default settings
-    pub fn default() -> Self {
-        Self::new(DetectionConfig::default())
-    }
-    
     /// Analyze audio and detect sample boundaries
     pub fn detect_boundaries(&self, audio_data: &[f32], sample_rate: u32) -> Result<DetectionResult> {
         if audio_data.is_empty() {
-            return Ok(DetectionResult {
-                start_sample: 0,
-                end_sample: 0,
-                detected_start: 0,
-                detected_end: 0,
-                rms_values: vec![],
-                success: false,
-                failure_reason: Some("Empty audio data".to_string()),
-            });
+            return Ok(empty_result(&self.config));
         }
         
-        println!("🔍 Starting sample detection on {} samples at {}Hz", audio_data.len(), sample_rate);
+        info!("🔍 Starting sample detection on {} samples at {}Hz", audio_data.len(), sample_rate);
         
         // Calculate window size in samples
         let window_size_samples = ((self.config.window_size_ms / 1000.0) * sample_rate as f32) as usize;
@@ -137,13 +198,17 @@ impl SampleDetector {
         
         // Calculate RMS values for each window
         let rms_values = self.calculate_rms_windows(audio_data, window_size_samples);
+        let window_hop = (window_size_samples / 2).max(1);
+        let window_times_ms: Vec<f32> = (0..rms_values.len())
+            .map(|i| (i * window_hop) as f32 / sample_rate as f32 * 1000.0)
+            .collect();
         
         // Convert threshold from dB to linear
-        let threshold_linear = self.db_to_linear(self.config.threshold_db);
+        let threshold_linear = db_to_linear(self.config.threshold_db);
         
-        println!("   Threshold: {}dB ({:.6} linear)", self.config.threshold_db, threshold_linear);
-        println!("   Window size: {}ms ({} samples)", self.config.window_size_ms, window_size_samples);
-        println!("   Calculated {} RMS windows", rms_values.len());
+        info!("   Threshold: {}dB ({:.6} linear)", self.config.threshold_db, threshold_linear);
+        info!("   Window size: {}ms ({} samples)", self.config.window_size_ms, window_size_samples);
+        info!("   Calculated {} RMS windows", rms_values.len());
         
         // Find start and end points using RMS analysis
         let (detected_start_window, detected_end_window) = self.find_signal_boundaries(&rms_values, threshold_linear)?;
@@ -152,52 +217,7 @@ impl SampleDetector {
         let detected_start_sample = detected_start_window * window_size_samples;
         let detected_end_sample = ((detected_end_window + 1) * window_size_samples).min(audio_data.len());
         
-        // Apply pre/post trigger adjustments
-        let pre_trigger_samples = ((self.config.pre_trigger_ms / 1000.0) * sample_rate as f32) as usize;
-        let post_trigger_samples = ((self.config.post_trigger_ms / 1000.0) * sample_rate as f32) as usize;
-        
-        let final_start = detected_start_sample.saturating_sub(pre_trigger_samples);
-        let final_end = (detected_end_sample + post_trigger_samples).min(audio_data.len());
-        
-        // Validate minimum length
-        let final_length_samples = final_end - final_start;
-        let min_length_samples = ((self.config.min_sample_length_ms / 1000.0) * sample_rate as f32) as usize;
-        
-        if final_length_samples < min_length_samples {
-            println!("⚠️  Detected sample too short: {}ms < {}ms minimum", 
-                (final_length_samples as f32 / sample_rate as f32) * 1000.0,
-                self.config.min_sample_length_ms);
-            
-            return Ok(DetectionResult {
-                start_sample: 0,
-                end_sample: audio_data.len(),
-                detected_start: detected_start_sample,
-                detected_end: detected_end_sample,
-                rms_values,
-                success: false,
-                failure_reason: Some("Sample too short after detection".to_string()),
-            });
-        }
-        
-        println!("✅ Detection successful:");
-        println!("   Raw detection: samples {}-{} ({:.1}ms-{:.1}ms)", 
-            detected_start_sample, detected_end_sample,
-            (detected_start_sample as f32 / sample_rate as f32) * 1000.0,
-            (detected_end_sample as f32 / sample_rate as f32) * 1000.0);
-        println!("   With triggers: samples {}-{} ({:.1}ms-{:.1}ms)",
-            final_start, final_end,
-            (final_start as f32 / sample_rate as f32) * 1000.0,
-            (final_end as f32 / sample_rate as f32) * 1000.0);
-        
-        Ok(DetectionResult {
-            start_sample: final_start,
-            end_sample: final_end,
-            detected_start: detected_start_sample,
-            detected_end: detected_end_sample,
-            rms_values,
-            success: true,
-            failure_reason: None,
-        })
+        Ok(apply_triggers(&self.config, audio_data.len(), sample_rate, (detected_start_sample, detected_end_sample), rms_values, window_times_ms))
     }
     
     /// Calculate RMS energy for each window
@@ -230,7 +250,7 @@ impl SampleDetector {
         // Find end: last position where we have enough consecutive windows above threshold  
         let end_window = self.find_end_boundary(rms_values, threshold, start_window)?;
         
-        println!("   Signal boundaries: windows {}-{} of {}", start_window, end_window, rms_values.len());
+        info!("   Signal boundaries: windows {}-{} of {}", start_window, end_window, rms_values.len());
         
         Ok((start_window, end_window))
     }
@@ -240,8 +260,8 @@ impl SampleDetector {
         for i in 0..rms_values.len() {
             // Check if we have enough consecutive windows above threshold
             let mut consecutive_count = 0;
-            for j in i..rms_values.len().min(i + self.config.confirmation_windows) {
-                if rms_values[j] > threshold {
+            for &rms in &rms_values[i..rms_values.len().min(i + self.config.confirmation_windows)] {
+                if rms > threshold {
                     consecutive_count += 1;
                 } else {
                     break;
@@ -293,32 +313,265 @@ impl SampleDetector {
         // Fallback: use end of audio
         Ok(rms_values.len().saturating_sub(1))
     }
+}
+
+impl BoundaryDetector for SampleDetector {
+    fn detect_boundaries(&self, audio_data: &[f32], sample_rate: u32) -> Result<DetectionResult> {
+        SampleDetector::detect_boundaries(self, audio_data, sample_rate)
+    }
+}
+
+/// Analysis frame for spectral flux (a power of two, for the FFT)
+const FLUX_FRAME: usize = 1024;
+/// Onset: the first frame whose flux reaches this fraction of the take's strongest flux
+const FLUX_ONSET_RATIO: f32 = 0.2;
+/// Quiet frames count as a noise floor only this far (20 dB) under the loudest frame
+const NOISE_FLOOR_RATIO: f64 = 0.01;
+
+/// Detector for takes recorded over audible noise or hum, where the level never drops under
+/// the silence threshold. The start is the first spectral-flux peak (a sudden change in
+/// spectrum) above the threshold; the end is where the energy left in the take, integrated
+/// backwards from its end, falls `threshold_db` below the total, so a decaying tail is kept
+/// whole and the noise after it is cut.
+pub struct SpectralFluxDetector {
+    config: DetectionConfig,
+}
+
+impl SpectralFluxDetector {
+    pub fn new(config: DetectionConfig) -> Self {
+        Self { config }
+    }
     
-    /// Convert decibels to linear amplitude
-    fn db_to_linear(&self, db: f32) -> f32 {
-        10.0_f32.powf(db / 20.0)
+    /// Positive magnitude change between consecutive Hann-windowed frames, with each frame's RMS
+    fn flux_frames(audio_data: &[f32], hop: usize) -> (Vec<f32>, Vec<f32>) {
+        let mut previous = vec![0.0f32; FLUX_FRAME / 2];
+        let mut flux = Vec::new();
+        let mut rms = Vec::new();
+        for start in (0..audio_data.len()).step_by(hop) {
+            let frame = &audio_data[start..(start + FLUX_FRAME).min(audio_data.len())];
+            let mut re = vec![0.0f32; FLUX_FRAME];
+            let mut im = vec![0.0f32; FLUX_FRAME];
+            for (i, &sample) in frame.iter().enumerate() {
+                let hann = 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / FLUX_FRAME as f32).cos();
+                re[i] = sample * hann;
+            }
+            fft(&mut re, &mut im);
+            let magnitudes: Vec<f32> = re.iter().zip(&im).take(FLUX_FRAME / 2).map(|(r, i)| (r * r + i * i).sqrt()).collect();
+            flux.push(magnitudes.iter().zip(&previous).map(|(now, before)| (now - before).max(0.0)).sum());
+            rms.push((frame.iter().map(|&x| x * x).sum::<f32>() / frame.len() as f32).sqrt());
+            previous = magnitudes;
+        }
+        (flux, rms)
     }
     
-    /// Trim audio data based on detection result
-    pub fn trim_audio(&self, audio_data: &[f32], detection: &DetectionResult) -> Vec<f32> {
-        if !detection.success {
-            println!("⚠️  Detection failed, returning original audio");
-            return audio_data.to_vec();
+    /// End of the last frame (of `hop` samples, from `from`) before the energy left in the
+    /// take falls `floor_db` below the total. The noise floor, taken from the quietest frames,
+    /// is subtracted first so the noise after a tail doesn't count as more tail.
+    fn energy_tail_end(audio_data: &[f32], from: usize, hop: usize, floor_db: f32) -> usize {
+        let frames: Vec<f64> = audio_data[from..].chunks(hop)
+            .map(|frame| frame.iter().map(|&x| (x as f64) * (x as f64)).sum())
+            .collect();
+        let mut sorted = frames.clone();
+        sorted.sort_by(f64::total_cmp);
+        let loudest = sorted.last().copied().unwrap_or(0.0);
+        // A take that never gets quiet (sustained to the end) has no noise floor to measure
+        let noise = Some(sorted[sorted.len() / 10]).filter(|&quiet| quiet < loudest * NOISE_FLOOR_RATIO).unwrap_or(0.0);
+        let signal: Vec<f64> = frames.iter().map(|&energy| (energy - 2.0 * noise).max(0.0)).collect();
+        
+        let total: f64 = signal.iter().sum();
+        if total <= 0.0 {
+            return audio_data.len();
+        }
+        let floor = total * 10f64.powf(floor_db as f64 / 10.0);
+        let mut remaining = 0.0f64;
+        for (frame, &energy) in signal.iter().enumerate().rev() {
+            remaining += energy;
+            if remaining > floor {
+                return (from + (frame + 1) * hop).min(audio_data.len());
+            }
+        }
+        audio_data.len()
+    }
+}
+
+impl BoundaryDetector for SpectralFluxDetector {
+    fn detect_boundaries(&self, audio_data: &[f32], sample_rate: u32) -> Result<DetectionResult> {
+        if audio_data.is_empty() {
+            return Ok(empty_result(&self.config));
         }
         
-        let start = detection.start_sample.min(audio_data.len());
-        let end = detection.end_sample.min(audio_data.len());
+        info!("🔍 Starting spectral flux detection on {} samples at {}Hz", audio_data.len(), sample_rate);
         
-        if start >= end {
-            println!("⚠️  Invalid detection boundaries, returning original audio");
-            return audio_data.to_vec();
-        }
+        let hop = FLUX_FRAME / 2;
+        let (flux, rms_values) = Self::flux_frames(audio_data, hop);
+        let window_times_ms: Vec<f32> = (0..rms_values.len())
+            .map(|i| (i * hop) as f32 / sample_rate as f32 * 1000.0)
+            .collect();
+        let threshold_linear = db_to_linear(self.config.threshold_db);
         
-        println!("✂️  Trimming audio: {} -> {} samples ({:.1}% reduction)",
-            audio_data.len(),
-            end - start,
-            ((audio_data.len() - (end - start)) as f32 / audio_data.len() as f32) * 100.0);
+        // Flux of frames that are only noise would still peak somewhere; onsets must be audible
+        let peak_flux = flux.iter().zip(&rms_values)
+            .filter(|(_, &rms)| rms > threshold_linear)
+            .fold(0.0f32, |max, (&value, _)| max.max(value));
+        let Some(onset_frame) = flux.iter().zip(&rms_values)
+            .position(|(&value, &rms)| peak_flux > 0.0 && value >= peak_flux * FLUX_ONSET_RATIO && rms > threshold_linear)
+        else {
+            warn!("⚠️  No onset above {}dB", self.config.threshold_db);
+            return Ok(DetectionResult {
+                start_sample: 0,
+                end_sample: audio_data.len(),
+                detected_start: 0,
+                detected_end: audio_data.len(),
+                rms_values,
+                window_times_ms,
+                threshold_db: self.config.threshold_db,
+                threshold_linear,
+                success: false,
+                failure_reason: Some("No onset above the threshold".to_string()),
+            });
+        };
         
-        audio_data[start..end].to_vec()
+        // A frame's flux rises as the attack enters it; the attack is in its second half
+        let detected_start = (onset_frame * hop).saturating_sub(hop).min(audio_data.len());
+        let detected_end = Self::energy_tail_end(audio_data, detected_start, hop, self.config.threshold_db).max(detected_start + 1);
+        info!("   Onset frame {} of {}, tail ends at sample {}", onset_frame, flux.len(), detected_end);
+        
+        Ok(apply_triggers(&self.config, audio_data.len(), sample_rate, (detected_start, detected_end), rms_values, window_times_ms))
     }
-}
\ No newline at end of file
+}
+
+/// Result for no audio at all
+fn empty_result(config: &DetectionConfig) -> DetectionResult {
+    DetectionResult {
+        start_sample: 0,
+        end_sample: 0,
+        detected_start: 0,
+        detected_end: 0,
+        rms_values: vec![],
+        window_times_ms: vec![],
+        threshold_db: config.threshold_db,
+        threshold_linear: db_to_linear(config.threshold_db),
+        success: false,
+        failure_reason: Some("Empty audio data".to_string()),
+    }
+}
+
+/// Widen detected boundaries by the pre/post triggers and check the minimum length
+fn apply_triggers(
+    config: &DetectionConfig,
+    audio_len: usize,
+    sample_rate: u32,
+    (detected_start_sample, detected_end_sample): (usize, usize),
+    rms_values: Vec<f32>,
+    window_times_ms: Vec<f32>,
+) -> DetectionResult {
+    let threshold_linear = db_to_linear(config.threshold_db);
+    
+    // Apply pre/post trigger adjustments
+    let pre_trigger_samples = ((config.pre_trigger_ms / 1000.0) * sample_rate as f32) as usize;
+    let post_trigger_samples = ((config.post_trigger_ms / 1000.0) * sample_rate as f32) as usize;
+    
+    let final_start = detected_start_sample.saturating_sub(pre_trigger_samples);
+    let final_end = (detected_end_sample + post_trigger_samples).min(audio_len);
+    
+    // Validate minimum length
+    let final_length_samples = final_end - final_start;
+    let min_length_samples = ((config.min_sample_length_ms / 1000.0) * sample_rate as f32) as usize;
+    
+    if final_length_samples < min_length_samples {
+        warn!("⚠️  Detected sample too short: {}ms < {}ms minimum", 
+            (final_length_samples as f32 / sample_rate as f32) * 1000.0,
+            config.min_sample_length_ms);
+        
+        return DetectionResult {
+            start_sample: 0,
+            end_sample: audio_len,
+            detected_start: detected_start_sample,
+            detected_end: detected_end_sample,
+            rms_values,
+            window_times_ms,
+            threshold_db: config.threshold_db,
+            threshold_linear,
+            success: false,
+            failure_reason: Some("Sample too short after detection".to_string()),
+        };
+    }
+    
+    info!("✅ Detection successful:");
+    info!("   Raw detection: samples {}-{} ({:.1}ms-{:.1}ms)", 
+        detected_start_sample, detected_end_sample,
+        (detected_start_sample as f32 / sample_rate as f32) * 1000.0,
+        (detected_end_sample as f32 / sample_rate as f32) * 1000.0);
+    info!("   With triggers: samples {}-{} ({:.1}ms-{:.1}ms)",
+        final_start, final_end,
+        (final_start as f32 / sample_rate as f32) * 1000.0,
+        (final_end as f32 / sample_rate as f32) * 1000.0);
+    
+    DetectionResult {
+        start_sample: final_start,
+        end_sample: final_end,
+        detected_start: detected_start_sample,
+        detected_end: detected_end_sample,
+        rms_values,
+        window_times_ms,
+        threshold_db: config.threshold_db,
+        threshold_linear,
+        success: true,
+        failure_reason: None,
+    }
+}
+
+/// Convert decibels to linear amplitude
+fn db_to_linear(db: f32) -> f32 {
+    10.0_f32.powf(db / 20.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detection_result_carries_window_timeline() {
+        // 100ms silence, 300ms tone, 100ms silence at 10kHz
+        let audio: Vec<f32> = (0..5000)
+            .map(|i| if (1000..4000).contains(&i) { 0.5 * (i as f32 * 0.3).sin() } else { 0.0 })
+            .collect();
+        let result = SampleDetector::default().detect_boundaries(&audio, 10000).unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.window_times_ms.len(), result.rms_values.len());
+        // 10ms windows with 50% overlap start every 5ms
+        assert_eq!(result.window_times_ms[0], 0.0);
+        assert!((result.window_times_ms[1] - 5.0).abs() < 1e-4);
+        assert_eq!(result.threshold_db, -40.0);
+        assert!((result.threshold_linear - 0.01).abs() < 1e-6);
+        assert!(result.rms_values.iter().any(|&rms| rms > result.threshold_linear));
+    }
+
+    #[test]
+    fn test_spectral_flux_finds_onset_and_tail_over_noise() {
+        // 0.3s of noise at -53 dBFS, a decaying tone, then noise again to 1.5s
+        let rate = 44100;
+        let mut seed = 1u32;
+        let audio: Vec<f32> = (0..rate * 3 / 2).map(|i| {
+            seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+            let noise = 0.004 * ((seed >> 8) as f32 / (1u32 << 23) as f32 - 1.0);
+            let t = i as f32 / rate as f32 - 0.3;
+            let tone = if t >= 0.0 { 0.5 * (-t / 0.1).exp() * (2.0 * std::f32::consts::PI * 440.0 * t).sin() } else { 0.0 };
+            noise + tone
+        }).collect();
+        let config = DetectionConfig { threshold_db: -60.0, algorithm: DetectionAlgorithm::SpectralFlux, ..DetectionConfig::default() };
+
+        let result = config.detector().detect_boundaries(&audio, rate as u32).unwrap();
+        assert!(result.success, "{:?}", result.failure_reason);
+        let onset = (0.3 * rate as f32) as usize;
+        assert!(result.detected_start <= onset && result.detected_start + 1500 > onset, "{}", result.detected_start);
+        // The tail sinks into the noise about 0.45s after the attack
+        let end_s = result.detected_end as f32 / rate as f32;
+        assert!(end_s > 0.6 && end_s < 1.0, "{}", end_s);
+
+        // The noise is above the threshold, so RMS windows start at the beginning
+        let rms = DetectionConfig { algorithm: DetectionAlgorithm::Rms, ..config }.detector().detect_boundaries(&audio, rate as u32).unwrap();
+        assert_eq!(rms.detected_start, 0);
+    }
+}
@@ -0,0 +1,99 @@
+//! Dither for writing the f32 capture as 16 or 24-bit integers. TPDF noise of one step either
+//! side decorrelates the rounding error from the signal, so quiet tails and fades sink into a
+//! steady noise floor instead of turning into distortion. Noise shaping feeds the error back
+//! to push that floor towards high frequencies, where it is harder to hear.
+
+/// How float audio is rounded to integer samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dither {
+    /// Round to the nearest step
+    Off,
+    /// Triangular (TPDF) dither
+    #[default]
+    Tpdf,
+    /// TPDF dither with first-order noise shaping
+    Shaped,
+}
+
+impl Dither {
+    /// "off", "tpdf" or "shaped"
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "off" | "none" => Some(Dither::Off),
+            "tpdf" => Some(Dither::Tpdf),
+            "shaped" => Some(Dither::Shaped),
+            _ => None,
+        }
+    }
+
+    /// Interleaved audio in -1.0..1.0 as `bits`-bit integers, clamped to full scale. Each
+    /// channel carries its own shaping error; the noise is seeded the same for every call, so
+    /// exporting a take twice writes the same file.
+    pub fn quantize(self, audio: &[f32], channels: u16, bits: u16) -> Vec<i32> {
+        let scale = ((1i64 << (bits - 1)) - 1) as f32;
+        let channels = channels.max(1) as usize;
+        let mut noise = Tpdf(0x9E37_79B9);
+        let mut errors = vec![0.0f32; channels];
+
+        audio.iter().enumerate()
+            .map(|(i, &sample)| {
+                let error = &mut errors[i % channels];
+                let target = sample * scale - if self == Dither::Shaped { *error } else { 0.0 };
+                let dithered = if self == Dither::Off { target } else { target + noise.next() };
+                let quantized = dithered.round().clamp(-scale - 1.0, scale);
+                // Clipped samples would otherwise feed back a huge error
+                *error = (quantized - target).clamp(-2.0, 2.0);
+                quantized as i32
+            })
+            .collect()
+    }
+}
+
+/// Triangular noise in -1.0..1.0 steps: the sum of two uniform values from a xorshift generator
+struct Tpdf(u32);
+
+impl Tpdf {
+    fn uniform(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as f32 / u32::MAX as f32 - 0.5
+    }
+
+    fn next(&mut self) -> f32 {
+        self.uniform() + self.uniform()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dither_keeps_signal_below_one_step() {
+        // A quarter of a 16-bit step: rounding loses it, dither keeps it on average
+        let step = 1.0 / 32767.0;
+        let audio = vec![0.25 * step; 20000];
+        assert!(Dither::Off.quantize(&audio, 1, 16).iter().all(|&s| s == 0));
+        for dither in [Dither::Tpdf, Dither::Shaped] {
+            let quantized = dither.quantize(&audio, 1, 16);
+            let mean = quantized.iter().sum::<i32>() as f32 / quantized.len() as f32;
+            assert!((mean - 0.25).abs() < 0.05, "{:?}: {}", dither, mean);
+            assert!(quantized.iter().all(|s| s.abs() <= 2));
+        }
+        assert_eq!(Dither::Tpdf.quantize(&audio, 1, 16), Dither::Tpdf.quantize(&audio, 1, 16));
+
+        // Shaping moves the error up in frequency: neighbouring errors pull in opposite directions
+        let correlation = |dither: Dither| {
+            let errors: Vec<f32> = dither.quantize(&audio, 1, 16).iter().map(|&s| s as f32 - 0.25).collect();
+            errors.windows(2).map(|pair| pair[0] * pair[1]).sum::<f32>() / errors.len() as f32
+        };
+        assert!(correlation(Dither::Shaped) < correlation(Dither::Tpdf) - 0.1);
+
+        // Full scale stays in range
+        let full_scale = Dither::Tpdf.quantize(&[1.0, -1.0, 1.0, -1.0], 1, 16);
+        assert!(full_scale.iter().all(|&s| (i16::MIN as i32..=i16::MAX as i32).contains(&s)), "{:?}", full_scale);
+        assert_eq!(Dither::parse("shaped"), Some(Dither::Shaped));
+        assert_eq!(Dither::parse("triangular"), None);
+    }
+}
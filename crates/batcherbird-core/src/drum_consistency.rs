@@ -0,0 +1,327 @@
+use crate::sampler::Sample;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Thresholds for flagging inconsistent round robin hits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsistencyConfig {
+    /// Allowed RMS loudness deviation from the note's median take (dB)
+    pub loudness_tolerance_db: f32,
+    /// Allowed peak (transient) level deviation from the note's median take (dB)
+    pub transient_tolerance_db: f32,
+    /// Allowed attack time deviation from the note's median take (ms)
+    pub attack_tolerance_ms: f32,
+    /// Hits quieter than this peak level are treated as missed triggers (dBFS)
+    pub silence_threshold_db: f32,
+    /// A second onset must reach this level relative to the hit's peak to count as a double trigger (dB)
+    pub retrigger_threshold_db: f32,
+}
+
+impl Default for ConsistencyConfig {
+    fn default() -> Self {
+        Self {
+            loudness_tolerance_db: 3.0,
+            transient_tolerance_db: 3.0,
+            attack_tolerance_ms: 5.0,
+            silence_threshold_db: -60.0,
+            retrigger_threshold_db: -24.0,
+        }
+    }
+}
+
+/// Loudness and transient measurements for one hit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HitMetrics {
+    pub note: u8,
+    pub velocity: u8,
+    /// 1-based take number (1 when the sample has no round robin tag)
+    pub round_robin: u8,
+    /// Peak level in dBFS
+    pub peak_db: f32,
+    /// Loudest 50ms RMS window in dBFS
+    pub rms_db: f32,
+    /// Time from onset (10% of peak) to 90% of peak
+    pub attack_ms: f32,
+    /// Onset times (ms from start of capture); more than one means the pad fired twice
+    pub onsets_ms: Vec<f32>,
+}
+
+/// Why a hit should be retaken
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum HitIssue {
+    /// Nothing (or almost nothing) was recorded
+    Missed,
+    /// A second strike was detected this long after the first
+    DoubleTrigger { gap_ms: f32 },
+    /// RMS loudness differs from the median take
+    Loudness { deviation_db: f32 },
+    /// Transient peak differs from the median take
+    Transient { deviation_db: f32 },
+    /// Attack is slower or faster than the median take
+    Attack { deviation_ms: f32 },
+}
+
+impl HitIssue {
+    pub fn describe(&self) -> String {
+        match self {
+            HitIssue::Missed => "no hit recorded".to_string(),
+            HitIssue::DoubleTrigger { gap_ms } => format!("double trigger {:.0}ms after onset", gap_ms),
+            HitIssue::Loudness { deviation_db } => format!("loudness {:+.1} dB from median", deviation_db),
+            HitIssue::Transient { deviation_db } => format!("transient peak {:+.1} dB from median", deviation_db),
+            HitIssue::Attack { deviation_ms } => format!("attack {:+.1}ms from median", deviation_ms),
+        }
+    }
+}
+
+/// A hit that should be re-recorded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlaggedHit {
+    pub note: u8,
+    pub velocity: u8,
+    pub round_robin: u8,
+    pub issues: Vec<HitIssue>,
+}
+
+/// Metrics for every hit plus the outliers that should be retaken
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsistencyReport {
+    pub hits: Vec<HitMetrics>,
+    pub flagged: Vec<FlaggedHit>,
+}
+
+impl ConsistencyReport {
+    pub fn is_consistent(&self) -> bool {
+        self.flagged.is_empty()
+    }
+
+    /// (note, round robin) pairs to pass back to the sampler for a retake
+    pub fn retake_list(&self) -> Vec<(u8, u8)> {
+        self.flagged.iter().map(|hit| (hit.note, hit.round_robin)).collect()
+    }
+}
+
+/// Measure every hit and compare takes of the same note/velocity against their median.
+///
+/// Missed and double-triggered hits are flagged on their own; loudness, transient and
+/// attack outliers need at least three takes so there is a meaningful median to compare to.
+pub fn analyze_round_robins(samples: &[Sample], config: &ConsistencyConfig) -> ConsistencyReport {
+    let hits: Vec<HitMetrics> = samples.iter().map(|sample| measure_hit(sample, config)).collect();
+
+    let mut groups: BTreeMap<(u8, u8), Vec<&HitMetrics>> = BTreeMap::new();
+    for hit in &hits {
+        groups.entry((hit.note, hit.velocity)).or_default().push(hit);
+    }
+
+    let mut flagged = Vec::new();
+    for takes in groups.values() {
+        let audible: Vec<&&HitMetrics> = takes.iter()
+            .filter(|hit| hit.peak_db > config.silence_threshold_db)
+            .collect();
+        let medians = if audible.len() >= 3 {
+            Some((
+                median(audible.iter().map(|hit| hit.rms_db)),
+                median(audible.iter().map(|hit| hit.peak_db)),
+                median(audible.iter().map(|hit| hit.attack_ms)),
+            ))
+        } else {
+            None
+        };
+
+        for hit in takes {
+            let mut issues = Vec::new();
+
+            if hit.peak_db <= config.silence_threshold_db {
+                issues.push(HitIssue::Missed);
+            } else {
+                if let [first, second, ..] = hit.onsets_ms.as_slice() {
+                    issues.push(HitIssue::DoubleTrigger { gap_ms: second - first });
+                }
+
+                if let Some((rms, peak, attack)) = medians {
+                    let loudness = hit.rms_db - rms;
+                    if loudness.abs() > config.loudness_tolerance_db {
+                        issues.push(HitIssue::Loudness { deviation_db: loudness });
+                    }
+                    let transient = hit.peak_db - peak;
+                    if transient.abs() > config.transient_tolerance_db {
+                        issues.push(HitIssue::Transient { deviation_db: transient });
+                    }
+                    let attack = hit.attack_ms - attack;
+                    if attack.abs() > config.attack_tolerance_ms {
+                        issues.push(HitIssue::Attack { deviation_ms: attack });
+                    }
+                }
+            }
+
+            if !issues.is_empty() {
+                flagged.push(FlaggedHit {
+                    note: hit.note,
+                    velocity: hit.velocity,
+                    round_robin: hit.round_robin,
+                    issues,
+                });
+            }
+        }
+    }
+
+    ConsistencyReport { hits, flagged }
+}
+
+/// Measure loudness, attack and onsets for a single hit
+pub fn measure_hit(sample: &Sample, config: &ConsistencyConfig) -> HitMetrics {
+    let channels = sample.channels.max(1) as usize;
+    let sample_rate = sample.sample_rate.max(1) as f32;
+
+    // Per-frame absolute level across channels
+    let frames: Vec<f32> = sample.audio_data
+        .chunks(channels)
+        .map(|frame| frame.iter().fold(0.0f32, |max, s| max.max(s.abs())))
+        .collect();
+
+    let peak = frames.iter().copied().fold(0.0f32, f32::max);
+
+    // Loudest 50ms RMS window, same measure as the velocity response
+    let window = ((sample_rate as usize) / 20).max(1);
+    let rms = frames.chunks(window)
+        .map(|chunk| (chunk.iter().map(|s| s * s).sum::<f32>() / chunk.len() as f32).sqrt())
+        .fold(0.0f32, f32::max);
+
+    let envelope = envelope_ms(&frames, sample_rate);
+
+    let attack_ms = if peak > 0.0 {
+        let start = envelope.iter().position(|&level| level >= peak * 0.1).unwrap_or(0);
+        let end = envelope.iter().position(|&level| level >= peak * 0.9).unwrap_or(start);
+        end.saturating_sub(start) as f32
+    } else {
+        0.0
+    };
+
+    HitMetrics {
+        note: sample.note,
+        velocity: sample.velocity,
+        round_robin: sample.metadata.round_robin.unwrap_or(1),
+        peak_db: to_db(peak),
+        rms_db: to_db(rms),
+        attack_ms,
+        onsets_ms: detect_onsets(&envelope, peak * db_to_linear(config.retrigger_threshold_db)),
+    }
+}
+
+/// Peak-hold envelope (instant attack, ~30ms release) sampled once per millisecond.
+/// The release keeps low drums from looking like a string of new onsets every cycle.
+fn envelope_ms(frames: &[f32], sample_rate: f32) -> Vec<f32> {
+    let release = (-1.0 / (0.030 * sample_rate)).exp();
+    let step = ((sample_rate / 1000.0) as usize).max(1);
+
+    let mut level = 0.0f32;
+    let mut envelope = Vec::with_capacity(frames.len() / step + 1);
+    for (i, &value) in frames.iter().enumerate() {
+        level = value.max(level * release);
+        if i % step == step - 1 {
+            envelope.push(level);
+        }
+    }
+    envelope
+}
+
+/// Onsets are sharp rises (9 dB within 5ms) above `threshold`, at least 20ms apart
+fn detect_onsets(envelope: &[f32], threshold: f32) -> Vec<f32> {
+    const RISE_WINDOW_MS: usize = 5;
+    const REFRACTORY_MS: usize = 20;
+    const RISE_RATIO: f32 = 2.8; // ~9 dB
+
+    let mut onsets = Vec::new();
+    let mut last_onset: Option<usize> = None;
+
+    for i in 0..envelope.len() {
+        if envelope[i] < threshold || threshold <= 0.0 {
+            continue;
+        }
+        if last_onset.is_some_and(|last| i - last < REFRACTORY_MS) {
+            continue;
+        }
+        let floor = envelope[i.saturating_sub(RISE_WINDOW_MS)..i]
+            .iter()
+            .copied()
+            .fold(f32::MAX, f32::min);
+        // The very first frame above threshold counts when the capture starts mid-hit
+        if i == 0 || envelope[i] >= floor * RISE_RATIO {
+            onsets.push(i as f32);
+            last_onset = Some(i);
+        }
+    }
+    onsets
+}
+
+fn median(values: impl Iterator<Item = f32>) -> f32 {
+    let mut values: Vec<f32> = values.collect();
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    values[values.len() / 2]
+}
+
+fn to_db(level: f32) -> f32 {
+    if level > 0.0 { 20.0 * level.log10() } else { -96.0 }
+}
+
+fn db_to_linear(db: f32) -> f32 {
+    10.0f32.powf(db / 20.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::SampleMetadata;
+    use std::time::{Duration, SystemTime};
+
+    /// Decaying 120Hz "drum" hit, optionally struck again after `retrigger_ms`
+    fn hit(take: u8, gain: f32, retrigger_ms: Option<usize>) -> Sample {
+        let sample_rate = 44100;
+        let strike = |t: usize| {
+            let seconds = t as f32 / sample_rate as f32;
+            (-seconds * 12.0).exp() * (2.0 * std::f32::consts::PI * 120.0 * seconds).sin()
+        };
+        let audio_data = (0..sample_rate / 2)
+            .map(|i| {
+                let second = retrigger_ms
+                    .map(|ms| ms * sample_rate / 1000)
+                    .filter(|&start| i >= start)
+                    .map(|start| strike(i - start))
+                    .unwrap_or(0.0);
+                gain * (strike(i) + second) * 0.5
+            })
+            .collect();
+
+        Sample {
+            note: 38,
+            velocity: 100,
+            audio_data,
+            sample_rate: sample_rate as u32,
+            channels: 1,
+            recorded_at: SystemTime::now(),
+            midi_timing: Duration::from_millis(100),
+            audio_timing: Duration::from_millis(500),
+            metadata: SampleMetadata { round_robin: Some(take), ..Default::default() },
+        }
+    }
+
+    #[test]
+    fn test_flags_quiet_and_double_triggered_hits() {
+        let samples = vec![
+            hit(1, 1.0, None),
+            hit(2, 0.95, None),
+            hit(3, 0.3, None),       // Mistriggered: ~10 dB quieter
+            hit(4, 1.0, Some(150)),  // Pad fired twice
+            hit(5, 1.0, None),
+        ];
+
+        let report = analyze_round_robins(&samples, &ConsistencyConfig::default());
+
+        assert_eq!(report.hits[0].onsets_ms.len(), 1);
+        assert_eq!(report.retake_list(), vec![(38, 3), (38, 4)]);
+        assert!(report.flagged[0].issues.iter().any(|issue| matches!(issue, HitIssue::Loudness { deviation_db } if *deviation_db < -6.0)));
+        assert!(report.flagged[1].issues.iter().any(|issue| matches!(issue, HitIssue::DoubleTrigger { .. })));
+    }
+}
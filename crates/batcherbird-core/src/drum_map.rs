@@ -0,0 +1,180 @@
+use crate::{Result, BatcherbirdError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// 0-indexed MIDI channel 10, the General MIDI percussion channel
+pub const GM_DRUM_CHANNEL: u8 = 9;
+
+/// General MIDI Level 1 percussion key map (notes 35-81)
+const GM_DRUM_NAMES: [(u8, &str); 47] = [
+    (35, "Acoustic Kick"),
+    (36, "Kick"),
+    (37, "Side Stick"),
+    (38, "Snare"),
+    (39, "Hand Clap"),
+    (40, "Electric Snare"),
+    (41, "Low Floor Tom"),
+    (42, "Closed HH"),
+    (43, "High Floor Tom"),
+    (44, "Pedal HH"),
+    (45, "Low Tom"),
+    (46, "Open HH"),
+    (47, "Low-Mid Tom"),
+    (48, "Hi-Mid Tom"),
+    (49, "Crash 1"),
+    (50, "High Tom"),
+    (51, "Ride 1"),
+    (52, "China"),
+    (53, "Ride Bell"),
+    (54, "Tambourine"),
+    (55, "Splash"),
+    (56, "Cowbell"),
+    (57, "Crash 2"),
+    (58, "Vibraslap"),
+    (59, "Ride 2"),
+    (60, "Hi Bongo"),
+    (61, "Low Bongo"),
+    (62, "Mute Hi Conga"),
+    (63, "Open Hi Conga"),
+    (64, "Low Conga"),
+    (65, "High Timbale"),
+    (66, "Low Timbale"),
+    (67, "High Agogo"),
+    (68, "Low Agogo"),
+    (69, "Cabasa"),
+    (70, "Maracas"),
+    (71, "Short Whistle"),
+    (72, "Long Whistle"),
+    (73, "Short Guiro"),
+    (74, "Long Guiro"),
+    (75, "Claves"),
+    (76, "Hi Wood Block"),
+    (77, "Low Wood Block"),
+    (78, "Mute Cuica"),
+    (79, "Open Cuica"),
+    (80, "Mute Triangle"),
+    (81, "Open Triangle"),
+];
+
+/// Note -> drum name table used for file names and instrument labels in drum mode
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DrumMap {
+    names: BTreeMap<u8, String>,
+}
+
+/// TOML layout for user overrides: `[names]` with `36 = "Big Kick"` entries
+#[derive(Debug, Default, Deserialize)]
+struct DrumMapFile {
+    #[serde(default)]
+    names: BTreeMap<String, String>,
+}
+
+impl DrumMap {
+    /// The standard General MIDI percussion names
+    pub fn general_midi() -> Self {
+        Self {
+            names: GM_DRUM_NAMES.iter().map(|&(note, name)| (note, name.to_string())).collect(),
+        }
+    }
+
+    /// General MIDI names with the user's override file applied, if there is one.
+    /// A broken override file is reported and ignored.
+    pub fn load_default() -> Self {
+        let mut map = Self::general_midi();
+        if let Some(path) = Self::user_map_path().filter(|path| path.exists()) {
+            if let Err(e) = map.load_file(&path) {
+                warn!("⚠️ Ignoring drum map {}: {}", path.display(), e);
+            }
+        }
+        map
+    }
+
+    /// Where the user override table lives (e.g. ~/.config/Batcherbird/drum_map.toml on Linux)
+    pub fn user_map_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("Batcherbird").join("drum_map.toml"))
+    }
+
+    /// Apply overrides from a TOML file. Returns how many names were set.
+    pub fn load_file(&mut self, path: &Path) -> Result<usize> {
+        let content = std::fs::read_to_string(path)?;
+        let file: DrumMapFile = toml::from_str(&content)?;
+
+        let mut overrides = Vec::with_capacity(file.names.len());
+        for (note, name) in file.names {
+            overrides.push((Self::parse_note(&note)?, name));
+        }
+        Ok(self.apply_overrides(overrides))
+    }
+
+    /// Apply inline overrides: "36=Big Kick,38=Rim Snare"
+    pub fn apply_override_str(&mut self, spec: &str) -> Result<usize> {
+        let mut overrides = Vec::new();
+        for entry in spec.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (note, name) = entry.split_once('=').ok_or_else(|| {
+                BatcherbirdError::Config(format!("Invalid drum name '{}', expected note=name", entry))
+            })?;
+            overrides.push((Self::parse_note(note)?, name.trim().to_string()));
+        }
+        Ok(self.apply_overrides(overrides))
+    }
+
+    /// Set names; an empty name removes the note from the map
+    fn apply_overrides(&mut self, overrides: Vec<(u8, String)>) -> usize {
+        let count = overrides.len();
+        for (note, name) in overrides {
+            if name.trim().is_empty() {
+                self.names.remove(&note);
+            } else {
+                self.names.insert(note, name.trim().to_string());
+            }
+        }
+        count
+    }
+
+    /// Mapped notes in ascending order (the notes a kit is sampled on)
+    pub fn notes(&self) -> Vec<u8> {
+        self.names.keys().copied().collect()
+    }
+
+    pub fn name(&self, note: u8) -> Option<&str> {
+        self.names.get(&note).map(String::as_str)
+    }
+
+    /// Filesystem-safe version of the name, e.g. "Closed_HH"
+    pub fn file_token(&self, note: u8) -> Option<String> {
+        self.name(note).map(|name| {
+            name.chars()
+                .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+                .collect()
+        })
+    }
+
+    fn parse_note(note: &str) -> Result<u8> {
+        note.trim().parse::<u8>().ok()
+            .filter(|&n| n <= 127)
+            .ok_or_else(|| BatcherbirdError::Config(format!("Invalid drum note '{}', expected 0-127", note)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gm_names_with_overrides() {
+        let mut map = DrumMap::general_midi();
+        assert_eq!(map.name(38), Some("Snare"));
+        assert_eq!(map.file_token(42).as_deref(), Some("Closed_HH"));
+        assert_eq!(map.name(20), None);
+
+        map.apply_override_str("36=808 Kick, 37=").unwrap();
+        assert_eq!(map.file_token(36).as_deref(), Some("808_Kick"));
+        assert_eq!(map.name(37), None);
+        assert!(!map.notes().contains(&37));
+
+        assert!(map.apply_override_str("200=Too High").is_err());
+        assert!(map.apply_override_str("Kick").is_err());
+    }
+}
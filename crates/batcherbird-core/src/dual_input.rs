@@ -0,0 +1,122 @@
+use crate::{Result, BatcherbirdError};
+use crate::sampler::{Sample, SampleMetadata};
+
+/// One source on the audio interface: a name used as the filename suffix token, and the
+/// 0-indexed input channels it arrives on
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputSource {
+    pub name: String,
+    pub channels: Vec<u16>,
+}
+
+impl InputSource {
+    pub fn new(name: &str, channels: &[u16]) -> Self {
+        Self { name: name.to_string(), channels: channels.to_vec() }
+    }
+}
+
+/// Dual-input capture: the synth's direct signal and the same notes through an amp or
+/// pedal chain, recorded in one pass on one interface (so both share a clock)
+#[derive(Debug, Clone, PartialEq)]
+pub struct DualInputConfig {
+    pub direct: InputSource,
+    pub processed: InputSource,
+}
+
+impl Default for DualInputConfig {
+    fn default() -> Self {
+        Self {
+            direct: InputSource::new("di", &[0]),
+            processed: InputSource::new("amp", &[1]),
+        }
+    }
+}
+
+impl DualInputConfig {
+    pub fn sources(&self) -> [&InputSource; 2] {
+        [&self.direct, &self.processed]
+    }
+
+    /// Check both sources fit in a capture with `channels` input channels and don't overlap
+    pub fn validate(&self, channels: u16) -> Result<()> {
+        if self.direct.name == self.processed.name {
+            return Err(BatcherbirdError::Config(format!("Both inputs are named '{}'; their files would collide", self.direct.name)));
+        }
+        for source in self.sources() {
+            if source.channels.is_empty() {
+                return Err(BatcherbirdError::Config(format!("Input '{}' has no channels", source.name)));
+            }
+            if let Some(&channel) = source.channels.iter().find(|&&channel| channel >= channels) {
+                return Err(BatcherbirdError::Config(format!(
+                    "Input '{}' uses channel {} but the interface records {} channels",
+                    source.name, channel + 1, channels
+                )));
+            }
+        }
+        if self.direct.channels.iter().any(|channel| self.processed.channels.contains(channel)) {
+            return Err(BatcherbirdError::Config("Direct and processed inputs share a channel".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Split one multichannel capture into the direct and processed takes, each tagged with
+    /// its source name. Both keep the capture's timing, so their trims and loops line up.
+    pub fn split(&self, sample: &Sample) -> Result<[Sample; 2]> {
+        self.validate(sample.channels)?;
+        Ok(self.sources().map(|source| extract_source(sample, source)))
+    }
+}
+
+fn extract_source(sample: &Sample, source: &InputSource) -> Sample {
+    let channels = sample.channels.max(1) as usize;
+    let audio_data = sample.audio_data.chunks_exact(channels)
+        .flat_map(|frame| source.channels.iter().map(move |&channel| frame[channel as usize]))
+        .collect();
+    Sample {
+        audio_data,
+        channels: source.channels.len() as u16,
+        metadata: SampleMetadata {
+            input_source: Some(source.name.clone()),
+            ..sample.metadata.clone()
+        },
+        ..sample.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_pairs_direct_and_processed_takes() {
+        // Four-channel capture: DI on 1, amp in stereo on 3+4, channel 2 unused
+        let capture = Sample {
+            note: 60,
+            velocity: 100,
+            audio_data: (0..3).flat_map(|frame| [frame as f32, 9.0, 10.0 + frame as f32, 20.0 + frame as f32]).collect(),
+            sample_rate: 48000,
+            channels: 4,
+            recorded_at: std::time::SystemTime::now(),
+            midi_timing: std::time::Duration::ZERO,
+            audio_timing: std::time::Duration::ZERO,
+            metadata: SampleMetadata { round_robin: Some(2), ..Default::default() },
+        };
+        let config = DualInputConfig {
+            direct: InputSource::new("di", &[0]),
+            processed: InputSource::new("amp", &[2, 3]),
+        };
+
+        let [direct, processed] = config.split(&capture).unwrap();
+        assert_eq!((direct.channels, direct.audio_data.clone()), (1, vec![0.0, 1.0, 2.0]));
+        assert_eq!(processed.channels, 2);
+        assert_eq!(processed.audio_data, vec![10.0, 20.0, 11.0, 21.0, 12.0, 22.0]);
+        assert_eq!(direct.metadata.input_source.as_deref(), Some("di"));
+        assert_eq!(processed.metadata.input_source.as_deref(), Some("amp"));
+        assert_eq!(processed.metadata.round_robin, Some(2));
+
+        // Channels past the interface's count, overlaps and duplicate names are rejected
+        assert!(DualInputConfig { processed: InputSource::new("amp", &[4]), ..config.clone() }.validate(4).is_err());
+        assert!(DualInputConfig { processed: InputSource::new("amp", &[0, 1]), ..config.clone() }.validate(4).is_err());
+        assert!(DualInputConfig { processed: InputSource::new("di", &[1]), ..config }.validate(4).is_err());
+    }
+}
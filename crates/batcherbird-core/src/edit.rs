@@ -0,0 +1,318 @@
+use crate::{library, BatcherbirdError, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Forward loop, in frames of the file it is stored in. `end` is exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoopPoints {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Gain curve of a fade
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FadeCurve {
+    #[default]
+    Linear,
+    /// Straight in dB over a 60 dB range, so a fade out follows a natural decay instead of
+    /// cutting a reverb tail short
+    Exponential,
+    /// Quarter sine (equal power)
+    Cosine,
+}
+
+impl FadeCurve {
+    /// "linear", "exponential"/"exp" or "cosine"/"equal-power"
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "linear" => Some(FadeCurve::Linear),
+            "exponential" | "exp" => Some(FadeCurve::Exponential),
+            "cosine" | "equal-power" => Some(FadeCurve::Cosine),
+            _ => None,
+        }
+    }
+
+    /// Gain at `position` (0.0 silent end .. 1.0 full level) through the fade
+    pub fn gain(self, position: f32) -> f32 {
+        let position = position.clamp(0.0, 1.0);
+        match self {
+            FadeCurve::Linear => position,
+            // -60 dB at the silent end, offset so it still reaches zero there
+            FadeCurve::Exponential => (1000f32.powf(position - 1.0) - 0.001) / 0.999,
+            FadeCurve::Cosine => (position * std::f32::consts::FRAC_PI_2).sin(),
+        }
+    }
+}
+
+/// Sidecar files sit next to the raw WAV: "Pad_C4_60_vel127.wav" -> "Pad_C4_60_vel127.edit.json"
+pub const SIDECAR_EXTENSION: &str = "edit.json";
+
+/// Trim, loop, gain and fade decisions for one WAV, in frames of the source file.
+/// Kept in a sidecar so the raw capture stays untouched until it is rendered.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SampleEdit {
+    pub trim_start: usize,
+    /// Exclusive end frame (None keeps everything after `trim_start`)
+    pub trim_end: Option<usize>,
+    pub loop_points: Option<LoopPoints>,
+    /// Gain applied after trimming (normalization)
+    #[serde(default)]
+    pub gain_db: f32,
+    #[serde(default)]
+    pub fade_in_ms: f32,
+    #[serde(default)]
+    pub fade_out_ms: f32,
+    #[serde(default)]
+    pub fade_in_curve: FadeCurve,
+    #[serde(default)]
+    pub fade_out_curve: FadeCurve,
+}
+
+impl SampleEdit {
+    pub fn sidecar_path(wav: &Path) -> PathBuf {
+        wav.with_extension(SIDECAR_EXTENSION)
+    }
+
+    pub fn save_sidecar(&self, wav: &Path) -> Result<PathBuf> {
+        let path = Self::sidecar_path(wav);
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| BatcherbirdError::Session(format!("Failed to serialize edit: {}", e)))?;
+        fs::write(&path, json)?;
+        Ok(path)
+    }
+
+    /// The edit saved for `wav`, if it has a sidecar
+    pub fn load_sidecar(wav: &Path) -> Result<Option<Self>> {
+        let path = Self::sidecar_path(wav);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = fs::read_to_string(&path)?;
+        serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| BatcherbirdError::Session(format!("Invalid edit file {}: {}", path.display(), e)))
+    }
+}
+
+/// Where an edit of `source` is written when no destination is given: "Pad_C4_60_vel127_edit.wav"
+pub fn edited_file_path(source: &Path) -> PathBuf {
+    let stem = source.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    source.with_file_name(format!("{}_edit.wav", stem))
+}
+
+/// Write `source` with `edit` applied to `destination`, leaving the source untouched.
+/// Loop points are stored in a standard `smpl` chunk, shifted to the trimmed timeline.
+pub fn apply_edit(source: &Path, destination: &Path, edit: &SampleEdit) -> Result<()> {
+    if source == destination {
+        return Err(BatcherbirdError::Config("Edits must be written to a new file".to_string()));
+    }
+
+    let (audio, spec) = library::read_wav(source)?;
+    let channels = spec.channels.max(1) as usize;
+    let frames = audio.len() / channels;
+    let trim_end = edit.trim_end.unwrap_or(frames).min(frames);
+    if edit.trim_start >= trim_end {
+        return Err(BatcherbirdError::Config(format!(
+            "Trim start {} must be before trim end {} ({} frames in file)", edit.trim_start, trim_end, frames
+        )));
+    }
+
+    let loop_points = edit.loop_points
+        .map(|points| {
+            if points.start < edit.trim_start || points.end > trim_end || points.start >= points.end {
+                return Err(BatcherbirdError::Config(format!(
+                    "Loop {}-{} must lie inside the trimmed region {}-{}",
+                    points.start, points.end, edit.trim_start, trim_end
+                )));
+            }
+            Ok(LoopPoints { start: points.start - edit.trim_start, end: points.end - edit.trim_start })
+        })
+        .transpose()?;
+
+    let mut rendered = audio[edit.trim_start * channels..trim_end * channels].to_vec();
+    let gain = 10.0_f32.powf(edit.gain_db / 20.0);
+    let fade_in = (edit.fade_in_ms.max(0.0) / 1000.0 * spec.sample_rate as f32) as usize;
+    let fade_out = (edit.fade_out_ms.max(0.0) / 1000.0 * spec.sample_rate as f32) as usize;
+    let rendered_frames = rendered.len() / channels;
+    for (frame, samples) in rendered.chunks_mut(channels).enumerate() {
+        let mut factor = gain;
+        if frame < fade_in {
+            factor *= edit.fade_in_curve.gain(frame as f32 / fade_in as f32);
+        }
+        if rendered_frames - frame <= fade_out {
+            factor *= edit.fade_out_curve.gain((rendered_frames - frame) as f32 / fade_out as f32);
+        }
+        samples.iter_mut().for_each(|sample| *sample *= factor);
+    }
+
+    library::write_wav(destination, &rendered, spec)?;
+    if let Some(points) = loop_points {
+        let unity_note = source.file_name()
+            .and_then(|name| library::parse_sample_filename(&name.to_string_lossy()))
+            .map_or(60, |(note, _, _)| note);
+        append_smpl_chunk(destination, points, spec.sample_rate, unity_note)?;
+    }
+
+    info!("✂️ Wrote edit of {} -> {} (frames {}-{}{})",
+        source.display(), destination.display(), edit.trim_start, trim_end,
+        loop_points.map(|p| format!(", loop {}-{}", p.start, p.end)).unwrap_or_default());
+    Ok(())
+}
+
+/// Render every WAV in `source_dir` that has a sidecar into `output_dir` under the same name.
+/// Other files (instrument files, README, WAVs without edits) are copied as they are, so the
+/// output folder is a complete library. Returns the rendered WAVs.
+pub fn render_directory(source_dir: &Path, output_dir: &Path) -> Result<Vec<PathBuf>> {
+    if source_dir == output_dir {
+        return Err(BatcherbirdError::Config("Render output folder must differ from the source folder".to_string()));
+    }
+    fs::create_dir_all(output_dir)?;
+
+    let mut rendered = Vec::new();
+    let mut entries: Vec<PathBuf> = fs::read_dir(source_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    entries.sort();
+    for path in entries {
+        let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        if name.ends_with(&format!(".{}", SIDECAR_EXTENSION)) {
+            continue;
+        }
+        let destination = output_dir.join(&name);
+        let is_wav = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("wav"));
+        match SampleEdit::load_sidecar(&path)? {
+            Some(edit) if is_wav => {
+                apply_edit(&path, &destination, &edit)?;
+                rendered.push(destination);
+            }
+            _ => {
+                fs::copy(&path, &destination)?;
+            }
+        }
+    }
+
+    info!("🎚️ Rendered {} edited samples into {}", rendered.len(), output_dir.display());
+    Ok(rendered)
+}
+
+/// Append a single forward loop `smpl` chunk and fix up the RIFF size
+pub(crate) fn append_smpl_chunk(path: &Path, points: LoopPoints, sample_rate: u32, unity_note: u8) -> Result<()> {
+    let mut chunk = Vec::with_capacity(68);
+    chunk.extend_from_slice(b"smpl");
+    chunk.extend_from_slice(&60u32.to_le_bytes());
+    let header = [
+        0,                                       // manufacturer
+        0,                                       // product
+        1_000_000_000 / sample_rate.max(1),      // sample period (ns)
+        unity_note as u32,                       // MIDI unity note
+        0, 0, 0,                                 // pitch fraction, SMPTE format, SMPTE offset
+        1,                                       // number of loops
+        0,                                       // sampler data bytes
+    ];
+    // Loop: cue id, forward type, start, inclusive end, fraction, play forever
+    let sample_loop = [0, 0, points.start as u32, points.end.saturating_sub(1) as u32, 0, 0];
+    for value in header.iter().chain(sample_loop.iter()) {
+        chunk.extend_from_slice(&value.to_le_bytes());
+    }
+
+    let mut file = OpenOptions::new().append(true).open(path)?;
+    file.write_all(&chunk)?;
+    drop(file);
+
+    let mut bytes = fs::read(path)?;
+    let riff_size = (bytes.len() - 8) as u32;
+    bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    fs::write(path, bytes)?;
+    Ok(())
+}
+
+/// First loop stored in a WAV's `smpl` chunk, if it has one
+pub fn read_loop_points(path: &Path) -> Result<Option<LoopPoints>> {
+    let bytes = fs::read(path)?;
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(BatcherbirdError::Processing(format!("{} is not a WAV file", path.display())));
+    }
+    let read_u32 = |at: usize| bytes.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+
+    let mut offset = 12;
+    while let (Some(id), Some(size)) = (bytes.get(offset..offset + 4), read_u32(offset + 4)) {
+        let body = offset + 8;
+        if id == b"smpl" {
+            let loop_count = read_u32(body + 28).unwrap_or(0);
+            if loop_count == 0 {
+                return Ok(None);
+            }
+            return match (read_u32(body + 36 + 8), read_u32(body + 36 + 12)) {
+                (Some(start), Some(end)) => Ok(Some(LoopPoints { start: start as usize, end: end as usize + 1 })),
+                _ => Err(BatcherbirdError::Processing(format!("Truncated smpl chunk in {}", path.display()))),
+            };
+        }
+        // Chunks are padded to an even length
+        offset = body + size as usize + (size as usize & 1);
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_edit_trims_and_stores_loop() {
+        let dir = std::env::temp_dir().join(format!("batcherbird_edit_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("Pad_A4_69_vel100.wav");
+        let spec = hound::WavSpec { channels: 2, sample_rate: 48000, bits_per_sample: 24, sample_format: hound::SampleFormat::Int };
+        let audio: Vec<f32> = (0..4800).flat_map(|i| [i as f32 / 8000.0, -(i as f32) / 8000.0]).collect();
+        library::write_wav(&source, &audio, spec).unwrap();
+
+        let destination = edited_file_path(&source);
+        assert_eq!(destination, dir.join("Pad_A4_69_vel100_edit.wav"));
+        let edit = SampleEdit {
+            trim_start: 100,
+            trim_end: Some(4000),
+            loop_points: Some(LoopPoints { start: 1100, end: 3100 }),
+            ..SampleEdit::default()
+        };
+        apply_edit(&source, &destination, &edit).unwrap();
+
+        // Source is untouched, the edit is trimmed and still a readable WAV
+        assert_eq!(hound::WavReader::open(&source).unwrap().duration(), 4800);
+        let (edited, edited_spec) = library::read_wav(&destination).unwrap();
+        assert_eq!(edited_spec.channels, 2);
+        assert_eq!(edited.len(), 3900 * 2);
+        assert!((edited[0] - 100.0 / 8000.0).abs() < 1e-4);
+
+        assert_eq!(read_loop_points(&destination).unwrap(), Some(LoopPoints { start: 1000, end: 3000 }));
+        assert_eq!(read_loop_points(&source).unwrap(), None);
+
+        // Loops outside the kept region and in-place edits are rejected
+        let outside = SampleEdit { loop_points: Some(LoopPoints { start: 50, end: 200 }), ..edit.clone() };
+        assert!(apply_edit(&source, &destination, &outside).is_err());
+        assert!(apply_edit(&source, &source, &edit).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_fade_curves() {
+        for curve in [FadeCurve::Linear, FadeCurve::Exponential, FadeCurve::Cosine] {
+            assert!(curve.gain(0.0).abs() < 1e-6 && (curve.gain(1.0) - 1.0).abs() < 1e-6, "{:?}", curve);
+        }
+        // Halfway through: equal power holds more level than linear, exponential far less
+        assert!((FadeCurve::Cosine.gain(0.5) - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-3);
+        assert!((FadeCurve::Exponential.gain(0.5) - 0.0307).abs() < 1e-3);
+        assert_eq!(FadeCurve::parse("equal-power"), Some(FadeCurve::Cosine));
+
+        // Sidecars written before curves existed still load, as linear
+        let edit: SampleEdit = serde_json::from_str(r#"{"trim_start": 0, "trim_end": null, "loop_points": null, "fade_out_ms": 10.0}"#).unwrap();
+        assert_eq!(edit.fade_out_curve, FadeCurve::Linear);
+        let json = serde_json::to_string(&SampleEdit { fade_out_curve: FadeCurve::Exponential, ..edit }).unwrap();
+        assert!(json.contains(r#""fade_out_curve":"exponential""#), "{}", json);
+    }
+}
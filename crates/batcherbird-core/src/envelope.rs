@@ -0,0 +1,167 @@
+use crate::detection::DetectionConfig;
+use crate::pitch::mixdown;
+use serde::{Deserialize, Serialize};
+
+/// RMS window used to follow the amplitude (ms)
+const WINDOW_MS: f32 = 10.0;
+/// Span over which the level must hold to count as sustained (windows)
+const PLATEAU_WINDOWS: usize = 5;
+/// Largest drop across a plateau span (dB)
+const PLATEAU_TOLERANCE_DB: f32 = 1.5;
+/// Level below the peak treated as silence (dB)
+const SILENCE_DB: f32 = 60.0;
+/// Level below the peak where the attack is taken to start (dB)
+const ONSET_DB: f32 = 40.0;
+/// Lowest sustain level (fraction of the peak) that still counts as a held sound
+const SUSTAINED_LEVEL: f32 = 0.1;
+
+/// Attack/decay/sustain/release fitted to a take's amplitude, in the units sampler
+/// envelopes use (seconds, sustain as a fraction of the peak)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AdsrEnvelope {
+    pub attack_secs: f32,
+    pub decay_secs: f32,
+    /// 0-1 of the peak level; 0 for sounds that die away while held (plucks, drums)
+    pub sustain: f32,
+    pub release_secs: f32,
+}
+
+impl AdsrEnvelope {
+    /// Fit an envelope to interleaved audio. None for silent or too-short takes.
+    pub fn estimate(audio: &[f32], channels: u16, sample_rate: u32) -> Option<Self> {
+        let levels = level_curve(&mixdown(audio, channels), sample_rate);
+        let secs = |windows: usize| windows as f32 * WINDOW_MS / 1000.0;
+
+        let (peak, peak_db) = levels.iter().copied().enumerate().max_by(|a, b| a.1.total_cmp(&b.1))?;
+        if peak_db <= -90.0 || levels.len() < PLATEAU_WINDOWS * 2 {
+            return None;
+        }
+        let onset = levels.iter().position(|&db| db > peak_db - ONSET_DB).unwrap_or(0);
+        let attack_end = (onset..=peak).find(|&i| levels[i] >= peak_db - 1.0).unwrap_or(peak);
+        let end = levels.iter().rposition(|&db| db > peak_db - SILENCE_DB).unwrap_or(peak);
+
+        // The last span after the peak that holds its level is the sustain, as long as it
+        // sits clearly above silence
+        let plateau = (peak..end.saturating_sub(PLATEAU_WINDOWS)).rev().find(|&i| {
+            let span = &levels[i..=i + PLATEAU_WINDOWS];
+            let high = span.iter().copied().fold(f32::MIN, f32::max);
+            let low = span.iter().copied().fold(f32::MAX, f32::min);
+            high - low < PLATEAU_TOLERANCE_DB && low > peak_db - SILENCE_DB + 12.0
+        });
+
+        let Some(plateau) = plateau else {
+            // Nothing is held: the sound decays to silence on its own
+            let decay = secs(end - peak);
+            return Some(Self { attack_secs: secs(attack_end - onset), decay_secs: decay, sustain: 0.0, release_secs: decay });
+        };
+
+        let span = &levels[plateau..=plateau + PLATEAU_WINDOWS];
+        let sustain_db = span.iter().sum::<f32>() / span.len() as f32;
+        let release_start = (plateau..=end).rev().find(|&i| levels[i] >= sustain_db - 0.5).unwrap_or(plateau);
+        let decay_end = (peak..=release_start).find(|&i| levels[i] <= sustain_db + 1.0).unwrap_or(peak);
+
+        Some(Self {
+            attack_secs: secs(attack_end - onset),
+            decay_secs: secs(decay_end - peak),
+            sustain: 10f32.powf((sustain_db - peak_db) / 20.0).min(1.0),
+            release_secs: secs(end - release_start),
+        })
+    }
+}
+
+/// Whether a sound holds while its key is down (pads, organs) or dies away on its own
+/// (plucks, drums), which decides how it is trimmed and whether it is looped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SoundClass {
+    Sustained,
+    Percussive,
+}
+
+impl SoundClass {
+    /// Classify interleaved audio by its envelope. None for silent or too-short takes.
+    pub fn classify(audio: &[f32], channels: u16, sample_rate: u32) -> Option<Self> {
+        AdsrEnvelope::estimate(audio, channels, sample_rate).map(|envelope| envelope.sound_class())
+    }
+
+    /// Detection preset for this kind of sound
+    pub fn detection_config(self) -> DetectionConfig {
+        match self {
+            SoundClass::Sustained => DetectionConfig::sustained(),
+            SoundClass::Percussive => DetectionConfig::percussive(),
+        }
+    }
+
+    /// Sustained sounds get a loop; percussive ones play once to the end
+    pub fn loops(self) -> bool {
+        self == SoundClass::Sustained
+    }
+}
+
+impl AdsrEnvelope {
+    pub fn sound_class(&self) -> SoundClass {
+        if self.sustain >= SUSTAINED_LEVEL { SoundClass::Sustained } else { SoundClass::Percussive }
+    }
+}
+
+/// Smoothed RMS level of each window, in dBFS
+fn level_curve(mono: &[f32], sample_rate: u32) -> Vec<f32> {
+    let window = ((WINDOW_MS / 1000.0) * sample_rate as f32).max(1.0) as usize;
+    let rms: Vec<f32> = mono.chunks_exact(window)
+        .map(|chunk| (chunk.iter().map(|s| s * s).sum::<f32>() / chunk.len() as f32).sqrt())
+        .collect();
+    (0..rms.len())
+        .map(|i| {
+            let around = &rms[i.saturating_sub(1)..(i + 2).min(rms.len())];
+            let level = around.iter().sum::<f32>() / around.len() as f32;
+            if level > 0.0 { 20.0 * level.log10() } else { -120.0 }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_adsr_from_synthetic_notes() {
+        let rate = 44100;
+        let secs = |s: f32| (s * rate as f32) as usize;
+        let tone = |gain: &dyn Fn(usize) -> f32, frames: usize| -> Vec<f32> {
+            (0..frames).map(|i| gain(i) * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / rate as f32).sin()).collect()
+        };
+
+        // 50ms attack, 200ms decay to half level, held for 1s, 300ms release
+        let (a, d, s, r) = (secs(0.05), secs(0.2), secs(1.0), secs(0.3));
+        let pad_gain = |i: usize| -> f32 {
+            if i < a {
+                i as f32 / a as f32
+            } else if i < a + d {
+                1.0 - 0.5 * (i - a) as f32 / d as f32
+            } else if i < a + d + s {
+                0.5
+            } else {
+                (0.5 * (1.0 - (i - a - d - s) as f32 / r as f32)).max(0.0)
+            }
+        };
+        let mut pad = tone(&pad_gain, a + d + s + r);
+        pad.extend(vec![0.0; secs(0.2)]);
+        let envelope = AdsrEnvelope::estimate(&pad, 1, rate).unwrap();
+        assert!((envelope.attack_secs - 0.05).abs() < 0.03, "{:?}", envelope);
+        assert!((envelope.decay_secs - 0.2).abs() < 0.06, "{:?}", envelope);
+        assert!((envelope.sustain - 0.5).abs() < 0.05, "{:?}", envelope);
+        assert!((envelope.release_secs - 0.3).abs() < 0.06, "{:?}", envelope);
+
+        // Stereo gives the same fit
+        let stereo: Vec<f32> = pad.iter().flat_map(|&v| [v, v]).collect();
+        assert_eq!(AdsrEnvelope::estimate(&stereo, 2, rate), Some(envelope));
+
+        // A pluck never holds: no sustain
+        let pluck = tone(&|i: usize| (-(i as f32) / (0.15 * rate as f32)).exp(), secs(1.5));
+        let envelope = AdsrEnvelope::estimate(&pluck, 1, rate).unwrap();
+        assert_eq!(envelope.sustain, 0.0);
+        assert!(envelope.attack_secs < 0.02 && envelope.decay_secs > 0.5, "{:?}", envelope);
+
+        assert_eq!(AdsrEnvelope::estimate(&vec![0.0; rate as usize], 1, rate), None);
+    }
+}
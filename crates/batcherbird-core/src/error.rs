@@ -7,8 +7,9 @@ pub enum BatcherbirdError {
     #[error("MIDI error: {0}")]
     Midi(#[from] midir::InitError),
     
+    /// Kept as text: midir's connect error holds the port, which isn't `Sync` on ALSA
     #[error("MIDI connection error: {0}")]
-    MidiConnection(#[from] midir::ConnectError<midir::MidiInput>),
+    MidiConnection(String),
     
     #[error("Audio error: {0}")]
     Audio(String),
@@ -30,4 +31,12 @@ pub enum BatcherbirdError {
     
     #[error("Session error: {0}")]
     Session(String),
-}
\ No newline at end of file
+    
+    #[error("Catalog error: {0}")]
+    Catalog(#[from] rusqlite::Error),
+}
+impl From<midir::ConnectError<midir::MidiInput>> for BatcherbirdError {
+    fn from(error: midir::ConnectError<midir::MidiInput>) -> Self {
+        BatcherbirdError::MidiConnection(error.to_string())
+    }
+}
@@ -1,9 +1,37 @@
 use crate::{Result, BatcherbirdError};
-use crate::sampler::Sample;
+use crate::sampler::{Sample, SampleMetadata, CcLayer};
 use crate::detection::DetectionConfig;
+use crate::edit::{self, FadeCurve, LoopPoints, SampleEdit};
+use crate::drum_map::DrumMap;
+use crate::readme::{ReadmeConfig, ReadmeContext};
+use crate::qa::{QaConfig, QaReport};
+use crate::manifest::{NormalizationSummary, SessionManifest};
+use crate::pitch;
+use crate::alignment;
+use crate::envelope::{AdsrEnvelope, SoundClass};
+use crate::loop_detection::{LoopCandidate, LoopDetectionConfig, LoopDetector};
+use crate::hardware_target::HardwareTarget;
+use crate::loudness::{self, LoudnessMeasurement};
+use crate::library;
+use crate::hum::{self, HumMeasurement};
+use crate::instrument_format::{self, InstrumentExporter};
+use crate::hooks::{HookConfig, HookContext, HookPoint};
+use crate::catalog::{CatalogConfig, CatalogSession, CatalogSettings, SessionCatalog};
+use crate::naming::{self, NamingContext, NamingPattern};
+use crate::plan::SamplingPlan;
+use crate::dither::Dither;
 use hound::{WavWriter, WavSpec, SampleFormat};
+use rayon::prelude::*;
 use std::path::{Path, PathBuf};
 use std::fs;
+use std::sync::{Arc, Mutex};
+use tracing::{error, info, warn};
+
+/// Highest true peak LUFS normalization may raise a sample to (dBTP)
+const LOUDNESS_CEILING_DBTP: f32 = -1.0;
+
+/// Most gain matching moves a region's volume (dB), so a near-silent take isn't boosted into noise
+const MAX_GAIN_MATCH_DB: f32 = 12.0;
 
 #[derive(Debug, Clone)]
 pub struct ExportConfig {
@@ -13,11 +41,178 @@ pub struct ExportConfig {
     pub normalize: bool,
     pub fade_in_ms: f32,
     pub fade_out_ms: f32,
+    pub fade_in_curve: FadeCurve,
+    /// Exponential or cosine fade outs keep reverb tails from being clamped
+    pub fade_out_curve: FadeCurve,
     pub apply_detection: bool,
     pub detection_config: DetectionConfig,
     // Decent Sampler metadata
     pub creator_name: Option<String>,
     pub instrument_description: Option<String>,
+    /// Drum mode: name files and instrument regions after drums (Kick, Snare...) instead of pitches,
+    /// and export one-shot regions without pitch tracking
+    pub drum_map: Option<DrumMap>,
+    /// Write a README from a template into the output folder after a batch export
+    pub readme: Option<ReadmeConfig>,
+    /// Keep the raw capture and save detection/normalization/fade decisions to a sidecar
+    /// edit file instead of applying them (see `edit::render_directory`)
+    pub sidecar_edits: bool,
+    /// Write stereo samples as separate _L/_R mono files, paired as hard-panned regions in
+    /// instrument files, for hardware samplers that can't load stereo WAVs
+    pub dual_mono: bool,
+    /// Word size of the WAVs behind SFZ/Decent Sampler instruments (the WAV formats carry their own)
+    pub instrument_bit_depth: BitDepth,
+    /// Extra copies of every sample written in the same `export_samples` pass
+    pub additional_outputs: Vec<AudioOutput>,
+    /// Find loop points in every sustained sample, store them in the WAV's smpl chunk and
+    /// map them as loops in SFZ/Decent Sampler instruments
+    pub apply_loop_detection: bool,
+    pub loop_detection_config: LoopDetectionConfig,
+    /// Decide per sample from its envelope whether it is sustained or percussive, and trim
+    /// (and loop, or play one-shot) it with the matching preset instead of `detection_config`
+    pub classify_samples: bool,
+    /// Trim (or pad) every sample to exactly this much audio before its attack, so notes
+    /// played back to back in the sampler respond with the same timing
+    pub align_onsets_ms: Option<f32>,
+    /// Normalize to this BS.1770 integrated loudness instead of the peak, keeping true peaks
+    /// at or under -1 dBTP
+    pub loudness_target_lufs: Option<f32>,
+    /// Notch out 50/60 Hz mains hum and its harmonics, at the frequency measured in the
+    /// batch's noise floor
+    pub remove_hum: bool,
+    /// Commands run after each sample file is written
+    pub hooks: HookConfig,
+    /// Record the session in the persistent session catalog (None = don't)
+    pub catalog: Option<CatalogConfig>,
+    /// What to do when a sample or instrument file about to be written already exists
+    pub overwrite: OverwritePolicy,
+    /// Background image for instrument formats with a UI (Decent Sampler), copied next to the preset
+    pub ui_background: Option<PathBuf>,
+    /// Crossfade neighbouring velocity layers over this share (percent) of the narrower layer
+    /// instead of switching hard at the split (None = hard splits)
+    pub velocity_crossfade: Option<f32>,
+    /// Even out loudness across notes with per-region volume in the instrument file, leaving
+    /// the audio as recorded
+    pub gain_matching: bool,
+    /// How 16 and 24-bit files are rounded from the float capture
+    pub dither: Dither,
+    /// Values of the {session} and {synth} naming tokens
+    pub naming: NamingContext,
+    /// Where sample files go relative to the instrument file
+    pub layout: FolderLayout,
+}
+
+/// Export word size, independent of the 32-bit float capture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BitDepth {
+    Int16,
+    #[default]
+    Int24,
+    Float32,
+}
+
+impl BitDepth {
+    pub fn wav_format(self) -> AudioFormat {
+        match self {
+            BitDepth::Int16 => AudioFormat::Wav16Bit,
+            BitDepth::Int24 => AudioFormat::Wav24Bit,
+            BitDepth::Float32 => AudioFormat::Wav32BitFloat,
+        }
+    }
+
+    pub fn bits(self) -> u16 {
+        match self {
+            BitDepth::Int16 => 16,
+            BitDepth::Int24 => 24,
+            BitDepth::Float32 => 32,
+        }
+    }
+
+    /// "16", "24" or "32f"
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "16" => Some(BitDepth::Int16),
+            "24" => Some(BitDepth::Int24),
+            "32f" | "32" => Some(BitDepth::Float32),
+            _ => None,
+        }
+    }
+}
+
+/// What an export does with a file that already exists at the path it is about to write
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverwritePolicy {
+    /// Stop the export with an error
+    Error,
+    /// Replace the existing file
+    #[default]
+    Overwrite,
+    /// Write beside it under the first free index: C4_60_vel127_2.wav
+    AutoRenameWithIndex,
+    /// Keep the existing file and write nothing
+    Skip,
+}
+
+impl OverwritePolicy {
+    /// "error", "overwrite", "rename" or "skip"
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "error" => Some(OverwritePolicy::Error),
+            "overwrite" => Some(OverwritePolicy::Overwrite),
+            "rename" | "auto-rename" => Some(OverwritePolicy::AutoRenameWithIndex),
+            "skip" => Some(OverwritePolicy::Skip),
+            _ => None,
+        }
+    }
+}
+
+/// Where sample files go relative to the instrument file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FolderLayout {
+    /// Next to the instrument file
+    #[default]
+    Flat,
+    /// One folder per velocity layer: vel064/, vel127/
+    PerVelocity,
+    /// A samples/ folder, with the instrument file at the root as SFZ libraries expect
+    SamplesFolder,
+}
+
+impl FolderLayout {
+    /// "flat", "velocity" or "samples"
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "flat" => Some(FolderLayout::Flat),
+            "velocity" | "per-velocity" => Some(FolderLayout::PerVelocity),
+            "samples" => Some(FolderLayout::SamplesFolder),
+            _ => None,
+        }
+    }
+
+    /// Folder `sample`'s file goes in, relative to the output directory (None = the directory itself)
+    pub fn subfolder(self, sample: &Sample) -> Option<String> {
+        match self {
+            FolderLayout::Flat => None,
+            FolderLayout::PerVelocity => Some(format!("vel{:03}", sample.velocity)),
+            FolderLayout::SamplesFolder => Some("samples".to_string()),
+        }
+    }
+
+    /// Whether a folder named `name` inside a session holds samples under one of the layouts
+    pub fn is_sample_folder(name: &str) -> bool {
+        name == "samples" || name.strip_prefix("vel").is_some_and(|velocity| velocity.len() == 3 && velocity.bytes().all(|b| b.is_ascii_digit()))
+    }
+}
+
+/// A second set of WAVs from the same takes, e.g. 16-bit files for a hardware sampler
+/// next to the 24-bit files of a software instrument
+#[derive(Debug, Clone)]
+pub struct AudioOutput {
+    /// Folder for the copies, relative to the output directory
+    pub subdirectory: String,
+    pub bit_depth: BitDepth,
+    /// Fit the copies to a sampler's rate, channel, length and memory limits first
+    pub target: Option<HardwareTarget>,
 }
 
 #[derive(Debug, Clone)]
@@ -27,6 +222,61 @@ pub enum AudioFormat {
     Wav32BitFloat,
     DecentSampler, // Generates .dspreset XML file with WAV samples
     SFZ, // Generates .sfz file with WAV samples
+    /// Instrument format registered with `instrument_format::register`, by name
+    Custom(String),
+}
+
+impl AudioFormat {
+    /// "wav" (24-bit), "sfz", "decentsampler" or the name of a registered instrument format
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "wav" => Some(AudioFormat::Wav24Bit),
+            "sfz" => Some(AudioFormat::SFZ),
+            "decentsampler" => Some(AudioFormat::DecentSampler),
+            other => instrument_format::find(other).map(|exporter| AudioFormat::Custom(exporter.name().to_string())),
+        }
+    }
+
+    /// Whether an instrument file is written next to the WAVs
+    pub fn is_instrument(&self) -> bool {
+        matches!(self, AudioFormat::DecentSampler | AudioFormat::SFZ | AudioFormat::Custom(_))
+    }
+
+    /// Writer of the instrument file, None for plain WAV exports
+    pub fn instrument_exporter(&self) -> Result<Option<Arc<dyn InstrumentExporter>>> {
+        let name = match self {
+            AudioFormat::SFZ => "sfz",
+            AudioFormat::DecentSampler => "decentsampler",
+            AudioFormat::Custom(name) => name,
+            _ => return Ok(None),
+        };
+        instrument_format::find(name)
+            .map(Some)
+            .ok_or_else(|| BatcherbirdError::Config(format!("No instrument format registered as '{}'", name)))
+    }
+
+    /// Word size of the WAVs this format writes itself (None for instrument formats, which
+    /// use `instrument_bit_depth`)
+    pub fn wav_bit_depth(&self) -> Option<BitDepth> {
+        match self {
+            AudioFormat::Wav16Bit => Some(BitDepth::Int16),
+            AudioFormat::Wav24Bit => Some(BitDepth::Int24),
+            AudioFormat::Wav32BitFloat => Some(BitDepth::Float32),
+            _ => None,
+        }
+    }
+
+    pub fn display_name(&self) -> String {
+        match self {
+            AudioFormat::Wav16Bit => "WAV (16-bit)".to_string(),
+            AudioFormat::Wav24Bit => "WAV (24-bit)".to_string(),
+            AudioFormat::Wav32BitFloat => "WAV (32-bit float)".to_string(),
+            _ => match self.instrument_exporter() {
+                Ok(Some(exporter)) => exporter.display_name().to_string(),
+                _ => format!("{:?}", self),
+            },
+        }
+    }
 }
 
 impl Default for ExportConfig {
@@ -42,12 +292,87 @@ impl Default for ExportConfig {
             detection_config: DetectionConfig::default(),
             creator_name: None,
             instrument_description: None,
+            drum_map: None,
+            readme: None,
+            sidecar_edits: false,
+            dual_mono: false,
+            instrument_bit_depth: BitDepth::default(),
+            additional_outputs: Vec::new(),
+            apply_loop_detection: false,
+            loop_detection_config: LoopDetectionConfig::default(),
+            classify_samples: false,
+            align_onsets_ms: None,
+            loudness_target_lufs: None,
+            remove_hum: false,
+            hooks: HookConfig::default(),
+            catalog: None,
+            overwrite: OverwritePolicy::default(),
+            ui_background: None,
+            velocity_crossfade: None,
+            gain_matching: false,
+            dither: Dither::default(),
+            fade_in_curve: FadeCurve::default(),
+            fade_out_curve: FadeCurve::default(),
+            naming: NamingContext::default(),
+            layout: FolderLayout::default(),
         }
     }
 }
 
+/// Velocity range a layer plays over, with the ranges it fades in and out across
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct VelocityZone {
+    lo: u8,
+    hi: u8,
+    fade_in: Option<(u8, u8)>,
+    fade_out: Option<(u8, u8)>,
+}
+
+impl VelocityZone {
+    /// Most velocity steps a fade is split into where the format has no crossfade opcodes
+    const FADE_STEPS: u8 = 8;
+
+    /// The zone as fixed-gain velocity ranges: full level between the fades, then each fade
+    /// in steps on an equal-power curve (gain in dB, None = full level)
+    fn slices(&self) -> Vec<(u8, u8, Option<f32>)> {
+        let fade_slices = |(lo, hi): (u8, u8), fading_in: bool| {
+            let width = hi - lo + 1;
+            let step = width.div_ceil(Self::FADE_STEPS);
+            (lo..=hi).step_by(step as usize)
+                .map(|start| {
+                    let end = start.saturating_add(step - 1).min(hi);
+                    // Position of the step's middle through the fade, 0..1
+                    let t = ((start as f32 + end as f32) / 2.0 - lo as f32 + 0.5) / width as f32;
+                    let angle = t * std::f32::consts::FRAC_PI_2;
+                    let gain = if fading_in { angle.sin() } else { angle.cos() };
+                    (start, end, Some(20.0 * gain.log10()))
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut slices = Vec::new();
+        if let Some(fade) = self.fade_in {
+            slices.extend(fade_slices(fade, true));
+        }
+        let full_lo = self.fade_in.map_or(self.lo, |(_, hi)| hi + 1);
+        let full_hi = self.fade_out.map_or(Some(self.hi), |(lo, _)| lo.checked_sub(1));
+        if let Some(full_hi) = full_hi.filter(|&full_hi| full_lo <= full_hi) {
+            slices.push((full_lo, full_hi, None));
+        }
+        if let Some(fade) = self.fade_out {
+            slices.extend(fade_slices(fade, false));
+        }
+        slices
+    }
+}
+
 pub struct SampleExporter {
     config: ExportConfig,
+    /// `config.naming_pattern`, parsed
+    pattern: NamingPattern,
+    /// Normalization gain (dB) applied or planned per written file, for the session report
+    normalization_gains: Mutex<Vec<(PathBuf, f32)>>,
+    /// Mains frequency measured across the batch by `prepare_hum_removal`
+    session_hum_hz: Mutex<Option<f32>>,
 }
 
 impl SampleExporter {
@@ -55,126 +380,655 @@ impl SampleExporter {
         // Create output directory if it doesn't exist
         if !config.output_directory.exists() {
             fs::create_dir_all(&config.output_directory)
-                .map_err(|e| BatcherbirdError::Export(e))?;
+                .map_err(BatcherbirdError::Export)?;
         }
         
-        Ok(Self { config })
+        let pattern = NamingPattern::parse(&config.naming_pattern)?;
+        if let Some(overlap) = config.velocity_crossfade.filter(|overlap| !(0.0..=100.0).contains(overlap)) {
+            return Err(BatcherbirdError::Config(format!("Velocity crossfade must be 0-100%, got {}", overlap)));
+        }
+        Ok(Self { config, pattern, normalization_gains: Mutex::new(Vec::new()), session_hum_hz: Mutex::new(None) })
+    }
+
+    /// Settings this exporter writes with, for instrument formats laying out their files
+    pub fn config(&self) -> &ExportConfig {
+        &self.config
     }
 
     pub fn export_sample(&self, sample: &Sample) -> Result<PathBuf> {
-        let filename = self.generate_filename(sample);
-        let filepath = self.config.output_directory.join(&filename);
+        self.export_sample_as(sample, &self.generate_filename(sample))
+    }
+
+    /// Process and write `sample` under an explicit file name instead of the naming pattern
+    pub fn export_sample_as(&self, sample: &Sample, filename: &str) -> Result<PathBuf> {
+        let directory = match self.config.layout.subfolder(sample) {
+            Some(subfolder) => self.config.output_directory.join(subfolder),
+            None => self.config.output_directory.clone(),
+        };
+        fs::create_dir_all(&directory)?;
+        let filepath = directory.join(filename);
+        // A dual mono pair is taken when either side exists
+        let dual_mono = self.config.dual_mono && sample.channels == 2;
+        let Some(filepath) = self.resolve_path_with(&filepath, |path| if dual_mono {
+            Self::dual_mono_path(path, "L").exists() || Self::dual_mono_path(path, "R").exists()
+        } else {
+            path.exists()
+        })? else {
+            info!("⏭️ Keeping existing sample: {}", filename);
+            return Ok(if dual_mono { Self::dual_mono_path(&filepath, "L") } else { filepath });
+        };
+        let filename = filepath.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        
+        info!("💾 Exporting sample: {}", filename);
         
-        println!("💾 Exporting sample: {}", filename);
+        // Sidecar mode writes the raw capture and records the processing decisions next to it
+        let sidecar_edit = self.config.sidecar_edits.then(|| self.plan_edit(sample));
         
         // Clone sample for processing (detection may modify audio data)
         let mut sample_copy = sample.clone();
         
+        // Before detection, so hum left in the release tail doesn't hold the trim open
+        if self.config.remove_hum && sidecar_edit.is_none() {
+            let session_hum_hz = *self.session_hum_hz.lock().unwrap();
+            let mains_hz = session_hum_hz.or_else(|| HumMeasurement::measure(std::slice::from_ref(sample)).map(|hum| hum.mains_hz));
+            if let Some(mains_hz) = mains_hz {
+                hum::remove_hum(&mut sample_copy.audio_data, sample.channels, sample.sample_rate, mains_hz);
+                info!("   ⚡ Removed {:.0} Hz hum", mains_hz);
+            }
+        }
+        
         // Apply sample detection if enabled
-        if self.config.apply_detection {
-            println!("🔍 Applying sample detection...");
-            match sample_copy.apply_detection(self.config.detection_config.clone()) {
+        if self.config.apply_detection && sidecar_edit.is_none() {
+            info!("🔍 Applying sample detection...");
+            match sample_copy.apply_detection(self.detection_config(sample)) {
                 Ok(detection_result) => {
                     if detection_result.success {
-                        println!("   ✅ Detection successful, sample trimmed");
+                        info!("   ✅ Detection successful, sample trimmed");
                     } else {
-                        println!("   ⚠️ Detection failed: {}", 
+                        warn!("   ⚠️ Detection failed: {}", 
                             detection_result.failure_reason.as_deref().unwrap_or("Unknown"));
-                        println!("   📝 Exporting original sample without trimming");
+                        info!("   📝 Exporting original sample without trimming");
                     }
                 },
                 Err(e) => {
-                    println!("   ❌ Detection error: {}", e);
-                    println!("   📝 Exporting original sample without trimming");
+                    error!("   ❌ Detection error: {}", e);
+                    info!("   📝 Exporting original sample without trimming");
                 }
             }
         }
         
+        // Same pre-attack for every sample, measured in the region detection kept
+        if let (Some(pre_attack_ms), None) = (self.config.align_onsets_ms, &sidecar_edit) {
+            if let Some(shift) = alignment::align_sample(&mut sample_copy, pre_attack_ms) {
+                info!("   📐 Onset aligned to {:.1}ms pre-attack ({:+} frames)", pre_attack_ms, -shift);
+            }
+        }
+        
         // Process audio data
         let mut audio_data = sample_copy.audio_data.clone();
         
         // Apply fades if configured
-        if self.config.fade_in_ms > 0.0 || self.config.fade_out_ms > 0.0 {
+        if sidecar_edit.is_none() && (self.config.fade_in_ms > 0.0 || self.config.fade_out_ms > 0.0) {
             self.apply_fades(&mut audio_data, sample.sample_rate)?;
         }
         
         // Normalize if configured
-        if self.config.normalize {
-            self.normalize_audio(&mut audio_data)?;
+        let mut gain_db = sidecar_edit.as_ref().map(|edit| edit.gain_db).filter(|_| self.config.normalize);
+        if self.config.normalize && sidecar_edit.is_none() {
+            gain_db = self.normalize_audio(&mut audio_data, sample)?;
         }
         
-        // Handle different export formats
+        // Loop last, so the crossfade is baked into the final audio
+        let loop_points = if sidecar_edit.is_none() { self.apply_loop(&mut audio_data, sample) } else { None };
+        
+        // Vintage samplers that only load mono get one file per side, paired in the instrument file
+        let written = if dual_mono {
+            let mono = Sample { channels: 1, ..sample.clone() };
+            let mut sides = Vec::new();
+            for (side, channel) in [("L", 0), ("R", 1)] {
+                let side_audio: Vec<f32> = audio_data.iter().skip(channel).step_by(2).copied().collect();
+                let side_path = Self::dual_mono_path(&filepath, side);
+                self.write_sample_audio(&side_path, &side_audio, &mono)?;
+                sides.push(side_path);
+            }
+            sides
+        } else {
+            self.write_sample_audio(&filepath, &audio_data, sample)?;
+            vec![filepath]
+        };
+        
+        for path in &written {
+            if let Some(gain_db) = gain_db {
+                self.normalization_gains.lock().unwrap().push((path.clone(), gain_db));
+            }
+            if let Some(points) = loop_points {
+                edit::append_smpl_chunk(path, points, sample.sample_rate, sample.note)?;
+            }
+            if let Some(ref edit) = sidecar_edit {
+                let sidecar = edit.save_sidecar(path)?;
+                info!("   📝 Edit decisions: {}", sidecar.display());
+            }
+            info!("   ✅ Saved: {}", path.display());
+            self.config.hooks.run(HookPoint::PostExport, &HookContext::file(sample.note, sample.velocity, path))?;
+        }
+        Ok(written[0].clone())
+    }
+
+    /// Write processed audio as WAV in the configured format (instrument formats use `instrument_bit_depth`)
+    fn write_sample_audio(&self, filepath: &Path, audio_data: &[f32], sample: &Sample) -> Result<()> {
         match self.config.sample_format {
-            AudioFormat::DecentSampler => {
-                // For DecentSampler, we only write WAV files here
-                // The .dspreset XML will be generated separately via export_samples()
+            ref format if format.is_instrument() => {
+                // For instrument formats, we only write WAV files here
+                // The instrument file will be generated separately via export_samples()
                 let wav_config = ExportConfig {
-                    sample_format: AudioFormat::Wav24Bit, // Use 24-bit for DecentSampler compatibility
+                    sample_format: self.config.instrument_bit_depth.wav_format(),
                     ..self.config.clone()
                 };
-                let temp_exporter = SampleExporter { config: wav_config };
-                temp_exporter.write_wav_file(&filepath, &audio_data, sample)?;
-            },
-            AudioFormat::SFZ => {
-                // For SFZ, we only write WAV files here
-                // The .sfz file will be generated separately via export_samples()
-                let wav_config = ExportConfig {
-                    sample_format: AudioFormat::Wav24Bit, // Use 24-bit for good compatibility
-                    ..self.config.clone()
-                };
-                let temp_exporter = SampleExporter { config: wav_config };
-                temp_exporter.write_wav_file(&filepath, &audio_data, sample)?;
+                let temp_exporter = SampleExporter { config: wav_config, pattern: self.pattern.clone(), normalization_gains: Mutex::new(Vec::new()), session_hum_hz: Mutex::new(None) };
+                temp_exporter.write_wav_file(filepath, audio_data, sample)?;
             },
             _ => {
                 // Standard WAV export
-                self.write_wav_file(&filepath, &audio_data, sample)?;
+                self.write_wav_file(filepath, audio_data, sample)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Where to write `path` under the overwrite policy: `path` itself, the first free indexed
+    /// name beside it, or None to keep the existing file. Instrument formats writing their own
+    /// files should go through this too.
+    pub fn resolve_output_path(&self, path: &Path) -> Result<Option<PathBuf>> {
+        self.resolve_path_with(path, Path::exists)
+    }
+
+    fn resolve_path_with(&self, path: &Path, taken: impl Fn(&Path) -> bool) -> Result<Option<PathBuf>> {
+        if !taken(path) {
+            return Ok(Some(path.to_path_buf()));
+        }
+        match self.config.overwrite {
+            OverwritePolicy::Overwrite => Ok(Some(path.to_path_buf())),
+            OverwritePolicy::Skip => Ok(None),
+            OverwritePolicy::Error => Err(BatcherbirdError::Export(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("{} already exists (choose another overwrite policy to replace or rename it)", path.display()),
+            ))),
+            OverwritePolicy::AutoRenameWithIndex => {
+                let stem = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+                let extension = path.extension().map(|ext| format!(".{}", ext.to_string_lossy())).unwrap_or_default();
+                let renamed = (2..).map(|index| path.with_file_name(format!("{}_{}{}", stem, index, extension)))
+                    .find(|candidate| !taken(candidate))
+                    .expect("unbounded index range");
+                info!("   🔢 {} exists, writing {}", path.display(), renamed.display());
+                Ok(Some(renamed))
+            }
+        }
+    }
+
+    /// "Pad_C4_60_vel127.wav" -> "Pad_C4_60_vel127_L.wav"
+    fn dual_mono_path(filepath: &Path, side: &str) -> PathBuf {
+        let stem = filepath.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        filepath.with_file_name(format!("{}_{}.wav", stem, side))
+    }
+
+    /// Files an instrument region plays for an exported sample, with the pan for dual mono pairs.
+    /// `wav_file` is the path `export_sample` returned (the left file of a pair); files are
+    /// referenced relative to the instrument file, with forward slashes.
+    fn region_files(&self, sample: &Sample, wav_file: &Path) -> Vec<(String, Option<i32>)> {
+        let filename = match wav_file.strip_prefix(&self.config.output_directory) {
+            Ok(relative) => relative.components()
+                .map(|part| part.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            Err(_) => wav_file.file_name().and_then(|name| name.to_str()).unwrap_or("sample.wav").to_string(),
+        };
+        if !(self.config.dual_mono && sample.channels == 2) {
+            return vec![(filename, None)];
+        }
+        let right = filename.strip_suffix("_L.wav")
+            .map(|stem| format!("{}_R.wav", stem))
+            .unwrap_or_else(|| filename.clone());
+        vec![(filename, Some(-100)), (right, Some(100))]
+    }
+
+    /// Fine tuning (cents) that brings a pitched sample onto its key. Uses the tuning measured
+    /// at capture, or measures it now; drum kits and takes of the wrong note aren't retuned.
+    fn tune_correction(&self, sample: &Sample) -> Option<i32> {
+        if self.config.drum_map.is_some() {
+            return None;
+        }
+        let cents = sample.metadata.tuning_cents.or_else(|| {
+            pitch::detect_pitch(&sample.audio_data, sample.channels, sample.sample_rate)
+                .filter(|estimate| estimate.note == sample.note)
+                .map(|estimate| estimate.cents)
+        })?;
+        let correction = -cents.round() as i32;
+        (correction != 0).then_some(correction)
+    }
+
+    /// Envelope to play a sample back with: the one fitted at capture, or fitted now. Drum
+    /// hits, release tails and legato transitions play as recorded.
+    fn playback_envelope(&self, sample: &Sample) -> Option<AdsrEnvelope> {
+        if self.plays_as_recorded(sample) {
+            return None;
+        }
+        sample.metadata.envelope
+            .or_else(|| AdsrEnvelope::estimate(&sample.audio_data, sample.channels, sample.sample_rate))
+    }
+
+    /// Drum hits, release tails and legato transitions keep the envelope they were recorded with
+    fn plays_as_recorded(&self, sample: &Sample) -> bool {
+        self.config.drum_map.is_some() || sample.metadata.release || sample.metadata.legato_from.is_some()
+    }
+
+    /// Sustained or percussive, when per-sample classification is on. Drum kits are always
+    /// one-shot and release/legato samples play as recorded, so they aren't classified.
+    fn sound_class(&self, sample: &Sample) -> Option<SoundClass> {
+        if !self.config.classify_samples {
+            return None;
+        }
+        self.playback_envelope(sample).map(|envelope| envelope.sound_class())
+    }
+
+    /// Detection settings for `sample`: its class preset when classifying, else the configured ones
+    fn detection_config(&self, sample: &Sample) -> DetectionConfig {
+        self.sound_class(sample)
+            .map(SoundClass::detection_config)
+            .unwrap_or_else(|| self.config.detection_config.clone())
+    }
+
+    /// Best loop in mono `audio` of a sustained sample, kept clear of the fade out. None when
+    /// loop detection is off, the sample doesn't sustain, or no loop is good enough.
+    fn find_loop(&self, audio: &[f32], sample: &Sample) -> Option<LoopCandidate> {
+        let wants_loop = match self.sound_class(sample) {
+            Some(class) => class.loops(),
+            None => self.config.apply_loop_detection && self.playback_envelope(sample).is_some_and(|env| env.sustain > 0.0),
+        };
+        if !wants_loop {
+            return None;
+        }
+        let fade_out = (self.config.fade_out_ms.max(0.0) / 1000.0 * sample.sample_rate as f32) as usize;
+        let usable = &audio[..audio.len().saturating_sub(fade_out)];
+        let result = LoopDetector::new(self.config.loop_detection_config.clone())
+            .detect_loop_points(usable, sample.sample_rate);
+        match result.best_candidate {
+            Some(candidate) if result.success => Some(candidate),
+            _ => {
+                warn!("   ⚠️ No loop found: {}", result.failure_reason.as_deref().unwrap_or("Unknown"));
+                None
             }
         }
+    }
+
+    /// Detect a loop in processed interleaved audio and crossfade it in place, per channel.
+    /// Returns the loop in frames.
+    fn apply_loop(&self, audio_data: &mut [f32], sample: &Sample) -> Option<LoopPoints> {
+        let candidate = self.find_loop(&pitch::mixdown(audio_data, sample.channels), sample)?;
+        let detector = LoopDetector::new(self.config.loop_detection_config.clone());
+        let channels = sample.channels.max(1) as usize;
+        for channel in 0..channels {
+            let mut side: Vec<f32> = audio_data.iter().skip(channel).step_by(channels).copied().collect();
+            if let Err(e) = detector.apply_loop_with_crossfade(&mut side, &candidate, sample.sample_rate) {
+                warn!("   ⚠️ Failed to apply crossfade: {}", e);
+                continue;
+            }
+            for (frame, value) in side.into_iter().enumerate() {
+                audio_data[frame * channels + channel] = value;
+            }
+        }
+        info!("   🔁 Loop {}-{} ({:.2}s, quality {:.3})", candidate.start_sample, candidate.end_sample,
+            candidate.length_samples as f32 / sample.sample_rate as f32, candidate.quality_score);
+        Some(LoopPoints { start: candidate.start_sample, end: candidate.end_sample })
+    }
+
+    /// Loop stored in an exported WAV's smpl chunk; drum hits always play one-shot
+    fn region_loop(&self, wav_file: &Path) -> Option<LoopPoints> {
+        if self.config.drum_map.is_some() {
+            return None;
+        }
+        edit::read_loop_points(wav_file).ok().flatten()
+    }
+
+    /// Trim, normalization and fade decisions this exporter would apply to `sample`, in frames
+    fn plan_edit(&self, sample: &Sample) -> SampleEdit {
+        let channels = sample.channels.max(1) as usize;
+        let frames = sample.audio_data.len() / channels;
+        let (mut trim_start, mut trim_end) = (0, frames);
+        
+        if self.config.apply_detection {
+            let detector = self.detection_config(sample).detector();
+            match detector.detect_boundaries(&sample.audio_data, sample.sample_rate) {
+                Ok(detection) if detection.success => {
+                    // Detection works on interleaved indices; round down to whole frames
+                    trim_start = detection.start_sample / channels;
+                    trim_end = (detection.end_sample / channels).clamp(trim_start + 1, frames.max(1));
+                }
+                Ok(detection) => warn!("   ⚠️ Detection failed: {}",
+                    detection.failure_reason.as_deref().unwrap_or("Unknown")),
+                Err(e) => error!("   ❌ Detection error: {}", e),
+            }
+        }
+        
+        // A sidecar can't pad, so onsets closer to the start than the pre-attack keep what there is
+        if let Some(pre_attack_ms) = self.config.align_onsets_ms {
+            let kept = &sample.audio_data[trim_start * channels..trim_end * channels];
+            if let Some(onset) = alignment::onset_frame(kept, sample.channels, sample.sample_rate) {
+                let pre_attack = (pre_attack_ms.max(0.0) / 1000.0 * sample.sample_rate as f32).round() as usize;
+                trim_start = (trim_start + onset).saturating_sub(pre_attack);
+            }
+        }
+        
+        // Same rule as normalize_audio, measured on the kept region
+        let gain_db = if self.config.normalize {
+            self.normalization_gain_db(&sample.audio_data[trim_start * channels..trim_end * channels], sample)
+                .unwrap_or(0.0)
+        } else {
+            0.0
+        };
+        
+        // Loop points are found in the kept region but stored in frames of the raw file
+        let mono = pitch::mixdown(&sample.audio_data[trim_start * channels..trim_end * channels], sample.channels);
+        let loop_points = self.find_loop(&mono, sample)
+            .map(|candidate| LoopPoints { start: trim_start + candidate.start_sample, end: trim_start + candidate.end_sample });
         
-        println!("   ✅ Saved: {}", filepath.display());
-        Ok(filepath)
+        SampleEdit {
+            trim_start,
+            trim_end: Some(trim_end),
+            loop_points,
+            gain_db,
+            fade_in_ms: self.config.fade_in_ms,
+            fade_out_ms: self.config.fade_out_ms,
+            fade_in_curve: self.config.fade_in_curve,
+            fade_out_curve: self.config.fade_out_curve,
+        }
     }
 
+    /// Export a batch: trimming, fades, normalization and WAV writing run for several samples
+    /// at once on rayon's thread pool (so post-export hooks may overlap), then the instrument
+    /// file and reports are written. Files are returned in the order of `samples`.
     pub fn export_samples(&self, samples: &[Sample]) -> Result<Vec<PathBuf>> {
-        let mut exported_files = Vec::new();
+        self.check_names(samples)?;
+        info!("💾 Exporting {} samples to: {} ({} threads)",
+            samples.len(), self.config.output_directory.display(), rayon::current_num_threads());
+        self.prepare_hum_removal(samples);
         
-        println!("💾 Exporting {} samples to: {}", samples.len(), self.config.output_directory.display());
+        let exported_files = samples.par_iter()
+            .enumerate()
+            .map(|(i, sample)| {
+                info!("   Exporting sample {} of {}...", i + 1, samples.len());
+                self.export_sample(sample)
+            })
+            .collect::<Result<Vec<PathBuf>>>()?;
+        self.export_additional_outputs(samples, &exported_files)?;
         
-        for (i, sample) in samples.iter().enumerate() {
-            println!("   Exporting sample {} of {}...", i + 1, samples.len());
-            let filepath = self.export_sample(sample)?;
-            exported_files.push(filepath);
+        self.finish_batch(samples, exported_files)
+    }
+
+    /// Write the `additional_outputs` copies of samples this exporter already wrote to `files`,
+    /// under the same file names
+    pub fn export_additional_outputs(&self, samples: &[Sample], files: &[PathBuf]) -> Result<()> {
+        for (exporter, copies) in self.prepare_additional_outputs(samples)? {
+            copies.par_iter().zip(files).try_for_each(|(copy, file)| {
+                let filename = file.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+                exporter.export_sample_as(copy, &filename).map(drop)
+            })?;
+            info!("📦 Also wrote {} samples to {}", samples.len(), exporter.config.output_directory.display());
+        }
+        Ok(())
+    }
+
+    /// Everything a batch gets after its sample files: QA report, instrument file, README and
+    /// catalog entry. `exported_files` are the samples' WAVs, in the same order as `samples`;
+    /// the returned list has the instrument and README appended.
+    pub fn finish_batch(&self, samples: &[Sample], mut exported_files: Vec<PathBuf>) -> Result<Vec<PathBuf>> {
+        // Wrong notes, dropouts, clipping etc. so only the suspect takes get re-recorded
+        let qa_config = QaConfig { check_pitch: self.config.drum_map.is_none(), ..QaConfig::default() };
+        let qa_report = QaReport::run(samples, &exported_files, &qa_config);
+        if !qa_report.suspects.is_empty() || qa_report.hum.is_some() {
+            info!("{}", qa_report.summary().trim_end());
+        }
+        qa_report.save(&self.config.output_directory)?;
+        
+        // .sfz, .dspreset or a registered format's instrument file
+        if let Some(instrument) = self.config.sample_format.instrument_exporter()? {
+            info!("🎼 Generating {} instrument file...", instrument.display_name());
+            let instrument_path = instrument.write_samples(self, samples, &exported_files)?;
+            exported_files.push(instrument_path);
         }
         
-        // Generate .dspreset XML file for DecentSampler format
-        if matches!(self.config.sample_format, AudioFormat::DecentSampler) {
-            println!("🎹 Generating Decent Sampler .dspreset file...");
-            let dspreset_path = self.generate_dspreset_file(samples, &exported_files)?;
-            exported_files.push(dspreset_path);
+        // Self-documenting library folder
+        if let Some(ref readme) = self.config.readme {
+            let context = ReadmeContext::new(
+                &self.instrument_name(),
+                samples,
+                &self.config.sample_format,
+                self.config.instrument_description.as_deref(),
+                self.config.creator_name.as_deref(),
+                readme,
+            );
+            let readme_path = crate::readme::write_readme(&self.config.output_directory, readme, &context)?;
+            exported_files.push(readme_path);
         }
         
-        // Generate .sfz file for SFZ format
-        if matches!(self.config.sample_format, AudioFormat::SFZ) {
-            println!("🎼 Generating SFZ .sfz file...");
-            let sfz_path = self.generate_sfz_file(samples, &exported_files)?;
-            exported_files.push(sfz_path);
+        let gains: Vec<f32> = self.normalization_gains().iter().map(|(_, gain)| *gain).collect();
+        if let Some(summary) = NormalizationSummary::from_gains(&gains) {
+            info!("🔊 {}", summary);
         }
         
-        println!("✅ Exported {} samples successfully!", samples.len());
+        // The export itself succeeded, so a catalog that can't be written only warns
+        if let Some(ref catalog) = self.config.catalog {
+            if let Err(e) = self.record_in_catalog(catalog, samples, &exported_files[..samples.len()], &qa_report) {
+                warn!("⚠️ Session not cataloged: {}", e);
+            }
+        }
+        
+        info!("✅ Exported {} samples successfully!", samples.len());
         Ok(exported_files)
     }
 
+    fn record_in_catalog(&self, catalog: &CatalogConfig, samples: &[Sample], files: &[PathBuf], qa_report: &QaReport) -> Result<()> {
+        let path = catalog.catalog_path()
+            .ok_or_else(|| BatcherbirdError::Config("No user data folder for the session catalog".to_string()))?;
+        let mut manifest = SessionManifest::new(self.instrument_name());
+        for (sample, file) in samples.iter().zip(files) {
+            manifest.add_sample(sample, file);
+        }
+        manifest.record_normalization_gains(&self.normalization_gains());
+        let settings = CatalogSettings {
+            format: self.config.sample_format.display_name(),
+            normalize: self.config.normalize,
+            apply_detection: self.config.apply_detection,
+            loudness_target_lufs: self.config.loudness_target_lufs,
+            remove_hum: self.config.remove_hum,
+            note_duration_ms: catalog.sampling.as_ref().map(|sampling| sampling.note_duration_ms),
+            release_time_ms: catalog.sampling.as_ref().map(|sampling| sampling.release_time_ms),
+            midi_channel: catalog.sampling.as_ref().map(|sampling| sampling.midi_channel + 1),
+        };
+        let session = CatalogSession::new(manifest, &self.config.output_directory, qa_report, settings, catalog);
+        SessionCatalog::open(&path)?.record(session)
+    }
+
+    /// Measure mains hum across the whole batch, so samples without a quiet tail of their own
+    /// are notched at the same frequency (no-op unless `remove_hum` is set)
+    pub fn prepare_hum_removal(&self, samples: &[Sample]) {
+        if !self.config.remove_hum {
+            return;
+        }
+        let hum = HumMeasurement::measure(samples);
+        if let Some(hum) = &hum {
+            info!("⚡ {}", hum);
+        }
+        *self.session_hum_hz.lock().unwrap() = hum.map(|hum| hum.mains_hz);
+    }
+
+    /// Exporters and (fitted) samples for each additional output, all WAVs without extras
+    fn prepare_additional_outputs(&self, samples: &[Sample]) -> Result<Vec<(SampleExporter, Vec<Sample>)>> {
+        self.config.additional_outputs.iter().map(|output| {
+            let copies = match &output.target {
+                Some(target) => {
+                    let (fitted, report) = target.fit(samples);
+                    info!("{}", report.summary().trim_end());
+                    fitted
+                }
+                None => samples.to_vec(),
+            };
+            let exporter = SampleExporter::new(ExportConfig {
+                output_directory: self.config.output_directory.join(&output.subdirectory),
+                sample_format: output.bit_depth.wav_format(),
+                readme: None,
+                sidecar_edits: false,
+                additional_outputs: Vec::new(),
+                ..self.config.clone()
+            })?;
+            Ok((exporter, copies))
+        }).collect()
+    }
+
     fn generate_filename(&self, sample: &Sample) -> String {
-        let note_name = Self::note_to_name(sample.note);
-        let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
+        self.filename_at(sample, chrono::Local::now())
+    }
+
+    /// File name for `sample` as if exported at `now`
+    fn filename_at(&self, sample: &Sample, now: chrono::DateTime<chrono::Local>) -> String {
+        let cc_token = sample.metadata.cc_layer
+            .map(|layer| format!("cc{}_{:03}", layer.controller, layer.value)) // cc74_064
+            .unwrap_or_default();
+        let rr_token = sample.metadata.round_robin
+            .map(|take| format!("rr{}", take)) // rr2
+            .unwrap_or_default();
+        let legato_token = sample.metadata.legato_from
+            .map(|source| format!("from_{}", self.note_label(source))) // from_C4
+            .unwrap_or_default();
+        let release_token = if sample.metadata.release { "rel" } else { "" };
+        let take_token = sample.metadata.take
+            .map(|take| format!("take{}", take.index)) // take2
+            .unwrap_or_default();
+        let input_token = sample.metadata.input_source.clone().unwrap_or_default(); // di, amp
         
         // Consistent "vel" prefix naming for all samples: C4_60_vel127.wav
-        self.config.naming_pattern
-            .replace("{note}", &sample.note.to_string())
-            .replace("{note_name}", &note_name)
-            .replace("{velocity}", &format!("vel{:03}", sample.velocity)) // vel064, vel127
-            .replace("{timestamp}", &timestamp.to_string())
-            .replace("{sample_rate}", &sample.sample_rate.to_string())
+        let mut filename = self.pattern.render(|token| match token {
+            "note" => sample.note.to_string(),
+            "note_name" => self.note_label(sample.note),
+            "velocity" => format!("vel{:03}", sample.velocity), // vel064, vel127
+            "timestamp" => now.with_timezone(&chrono::Utc).format("%Y%m%d_%H%M%S").to_string(),
+            "sample_rate" => sample.sample_rate.to_string(),
+            "cc" => cc_token.clone(),
+            "rr" => rr_token.clone(),
+            "legato" => legato_token.clone(),
+            "release" => release_token.to_string(),
+            "take" => take_token.clone(),
+            "input" => input_token.clone(),
+            "channel" => sample.metadata.midi_channel.map(|channel| format!("ch{:02}", channel + 1)).unwrap_or_default(), // ch01
+            "patch" => sample.metadata.program.map(|program| format!("prog{:03}", program)).unwrap_or_default(), // prog012
+            "articulation" => Self::articulation(sample).to_string(),
+            session_token => self.session_token(session_token, now),
+        });
+        
+        // Sweep layers and round robins share note/velocity, so keep them apart even without a token
+        if sample.metadata.cc_layer.is_some() && !self.config.naming_pattern.contains("{cc}") {
+            filename = Self::append_token(&filename, &cc_token);
+        }
+        if sample.metadata.round_robin.is_some() && !self.config.naming_pattern.contains("{rr}") {
+            filename = Self::append_token(&filename, &rr_token);
+        }
+        // {articulation} already tells legato and release takes from the sustained one
+        let articulation = self.pattern.contains("articulation");
+        if sample.metadata.legato_from.is_some() && !self.config.naming_pattern.contains("{legato}") && !articulation {
+            filename = Self::append_token(&filename, &legato_token);
+        }
+        if sample.metadata.release && !self.config.naming_pattern.contains("{release}") && !articulation {
+            filename = Self::append_token(&filename, release_token);
+        }
+        // The picked take keeps the plain name; the alternates kept beside it are told apart
+        if sample.metadata.take.is_some_and(|take| !take.best) && !self.config.naming_pattern.contains("{take}") {
+            filename = Self::append_token(&filename, &take_token);
+        }
+        // Paired direct/processed takes of the same note
+        if sample.metadata.input_source.is_some() && !self.config.naming_pattern.contains("{input}") {
+            filename = Self::append_token(&filename, &input_token);
+        }
+        filename
+    }
+
+    /// {session}, {synth} and {date}: the same for every file of the export
+    fn session_token(&self, token: &str, now: chrono::DateTime<chrono::Local>) -> String {
+        match token {
+            "session" => self.config.naming.session.clone().unwrap_or_else(|| {
+                self.config.output_directory.file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            }),
+            "synth" => self.config.naming.synth.clone().unwrap_or_default(),
+            "date" => now.format("%Y-%m-%d").to_string(),
+            _ => String::new(),
+        }
+    }
+
+    fn articulation(sample: &Sample) -> &'static str {
+        if sample.metadata.release {
+            "release"
+        } else if sample.metadata.legato_from.is_some() {
+            "legato"
+        } else {
+            "sustain"
+        }
+    }
+
+    /// Fail when two of `samples` would be written to the same file name (compared
+    /// case-insensitively), naming the colliding takes
+    pub fn check_names(&self, samples: &[Sample]) -> Result<()> {
+        let now = chrono::Local::now();
+        let names: Vec<String> = samples.iter().map(|sample| self.filename_at(sample, now)).collect();
+        let collisions = naming::find_collisions(&names);
+        if collisions.is_empty() {
+            return Ok(());
+        }
+        let described: Vec<String> = collisions.iter().take(5)
+            .map(|(name, indices)| {
+                let takes: Vec<String> = indices.iter()
+                    .map(|&i| format!("{} vel {}", Self::note_to_name(samples[i].note), samples[i].velocity))
+                    .collect();
+                format!("{} ({})", name, takes.join(", "))
+            })
+            .collect();
+        Err(BatcherbirdError::Config(format!(
+            "Naming pattern '{}' gives {} file names to more than one take: {}{}",
+            self.config.naming_pattern,
+            collisions.len(),
+            described.join("; "),
+            if collisions.len() > 5 { "; ..." } else { "" },
+        )))
+    }
+
+    /// `check_names` for the takes of a dry-run plan, before anything is recorded
+    pub fn check_plan(&self, plan: &SamplingPlan) -> Result<()> {
+        let placeholders: Vec<Sample> = plan.takes.iter()
+            .map(|take| Sample {
+                note: take.note,
+                velocity: take.velocity,
+                audio_data: Vec::new(),
+                sample_rate: plan.sample_rate,
+                channels: plan.channels,
+                recorded_at: std::time::SystemTime::now(),
+                midi_timing: std::time::Duration::ZERO,
+                audio_timing: std::time::Duration::ZERO,
+                metadata: SampleMetadata { round_robin: take.round_robin, ..Default::default() },
+            })
+            .collect();
+        self.check_names(&placeholders)
+    }
+
+    /// Alternate takes are exported next to the picked one but left out of instrument files
+    fn is_mapped_take(sample: &Sample) -> bool {
+        sample.metadata.take.is_none_or(|take| take.best)
+    }
+
+    /// Insert `_token` before the .wav extension
+    fn append_token(filename: &str, token: &str) -> String {
+        match filename.strip_suffix(".wav") {
+            Some(stem) => format!("{}_{}.wav", stem, token),
+            None => format!("{}_{}", filename, token),
+        }
     }
 
     fn apply_fades(&self, audio_data: &mut [f32], sample_rate: u32) -> Result<()> {
@@ -185,43 +1039,58 @@ impl SampleExporter {
         
         // Apply fade in
         if fade_in_samples > 0 && fade_in_samples < len {
-            for i in 0..fade_in_samples.min(len) {
-                let fade_factor = i as f32 / fade_in_samples as f32;
-                audio_data[i] *= fade_factor;
+            for (i, value) in audio_data.iter_mut().enumerate().take(fade_in_samples) {
+                let fade_factor = self.config.fade_in_curve.gain(i as f32 / fade_in_samples as f32);
+                *value *= fade_factor;
             }
         }
         
         // Apply fade out
         if fade_out_samples > 0 && fade_out_samples < len {
             let fade_start = len.saturating_sub(fade_out_samples);
-            for i in fade_start..len {
-                let fade_factor = (len - i) as f32 / fade_out_samples as f32;
-                audio_data[i] *= fade_factor;
+            for (i, value) in audio_data.iter_mut().enumerate().skip(fade_start) {
+                let fade_factor = self.config.fade_out_curve.gain((len - i) as f32 / fade_out_samples as f32);
+                *value *= fade_factor;
             }
         }
         
         Ok(())
     }
 
-    fn normalize_audio(&self, audio_data: &mut [f32]) -> Result<()> {
-        // Find peak amplitude
+    /// Bring the peak to 95% (or the loudness to the LUFS target), returning the gain applied (dB)
+    fn normalize_audio(&self, audio_data: &mut [f32], sample: &Sample) -> Result<Option<f32>> {
+        let Some(gain_db) = self.normalization_gain_db(audio_data, sample) else {
+            return Ok(None);
+        };
+        let gain = 10f32.powf(gain_db / 20.0);
+        for value in audio_data.iter_mut() {
+            *value *= gain;
+        }
+        info!(note = sample.note, gain_db = (gain_db * 10.0).round() / 10.0, "   🔊 Normalized");
+        Ok(Some(gain_db))
+    }
+
+    /// Gain normalization would apply to `audio_data` (dB), None when it is silent or
+    /// (peak mode) already at full scale
+    fn normalization_gain_db(&self, audio_data: &[f32], sample: &Sample) -> Option<f32> {
+        if let Some(target_lufs) = self.config.loudness_target_lufs {
+            return LoudnessMeasurement::measure(audio_data, sample.channels, sample.sample_rate)
+                .gain_to_target(target_lufs, LOUDNESS_CEILING_DBTP);
+        }
         let peak = audio_data.iter()
             .map(|&sample| sample.abs())
             .fold(0.0f32, f32::max);
-        
-        if peak > 0.0 && peak < 1.0 {
-            let gain = 0.95 / peak; // Normalize to 95% to avoid clipping
-            for sample in audio_data.iter_mut() {
-                *sample *= gain;
-            }
-            println!("   🔊 Normalized: +{:.1} dB gain", 20.0 * gain.log10());
-        }
-        
-        Ok(())
+        // Normalize to 95% to avoid clipping
+        (peak > 0.0 && peak < 1.0).then(|| 20.0 * (0.95 / peak).log10())
+    }
+
+    /// Normalization gain per file written so far (planned gain for sidecar exports)
+    pub fn normalization_gains(&self) -> Vec<(PathBuf, f32)> {
+        self.normalization_gains.lock().unwrap().clone()
     }
 
     fn write_wav_file(&self, filepath: &Path, audio_data: &[f32], sample: &Sample) -> Result<()> {
-        println!("🔍 Writing WAV file: {} ({} samples)", filepath.display(), audio_data.len());
+        info!("🔍 Writing WAV file: {} ({} samples)", filepath.display(), audio_data.len());
         
         // Validate audio data first
         if audio_data.is_empty() {
@@ -250,31 +1119,25 @@ impl SampleExporter {
                 bits_per_sample: 32,
                 sample_format: SampleFormat::Float,
             },
-            AudioFormat::DecentSampler => {
+            AudioFormat::DecentSampler | AudioFormat::SFZ | AudioFormat::Custom(_) => {
                 return Err(BatcherbirdError::Export(std::io::Error::new(
                     std::io::ErrorKind::InvalidInput,
-                    "DecentSampler format should be handled separately, not in WAV writing"
-                )));
-            },
-            AudioFormat::SFZ => {
-                return Err(BatcherbirdError::Export(std::io::Error::new(
-                    std::io::ErrorKind::InvalidInput,
-                    "SFZ format should not reach write_wav_file - this is a logic error"
+                    "Instrument formats should be handled separately, not in WAV writing"
                 )));
             }
         };
 
-        println!("🔍 WAV spec: {}Hz, {} channels, {} bits", spec.sample_rate, spec.channels, spec.bits_per_sample);
+        info!("🔍 WAV spec: {}Hz, {} channels, {} bits", spec.sample_rate, spec.channels, spec.bits_per_sample);
 
         // Create writer with explicit error handling
         let mut writer = match WavWriter::create(filepath, spec) {
             Ok(w) => {
-                println!("✅ WAV writer created successfully");
+                info!("✅ WAV writer created successfully");
                 w
             },
             Err(e) => {
-                println!("❌ Failed to create WAV writer: {}", e);
-                return Err(BatcherbirdError::Export(std::io::Error::new(std::io::ErrorKind::Other, e)));
+                error!("❌ Failed to create WAV writer: {}", e);
+                return Err(BatcherbirdError::Export(std::io::Error::other(e)));
             }
         };
 
@@ -282,55 +1145,47 @@ impl SampleExporter {
         let total_samples = audio_data.len();
         match self.config.sample_format {
             AudioFormat::Wav16Bit => {
-                for (i, &sample) in audio_data.iter().enumerate() {
-                    let sample_i16 = (sample * i16::MAX as f32) as i16;
-                    if let Err(e) = writer.write_sample(sample_i16) {
-                        println!("❌ Failed to write sample {} of {}: {}", i, total_samples, e);
-                        return Err(BatcherbirdError::Export(std::io::Error::new(std::io::ErrorKind::Other, e)));
+                for (i, value) in self.config.dither.quantize(audio_data, sample.channels, 16).into_iter().enumerate() {
+                    if let Err(e) = writer.write_sample(value as i16) {
+                        error!("❌ Failed to write sample {} of {}: {}", i, total_samples, e);
+                        return Err(BatcherbirdError::Export(std::io::Error::other(e)));
                     }
                 }
             }
             AudioFormat::Wav24Bit => {
-                for (i, &sample) in audio_data.iter().enumerate() {
-                    let sample_i32 = (sample * 8_388_607.0) as i32; // 24-bit max value
-                    if let Err(e) = writer.write_sample(sample_i32) {
-                        println!("❌ Failed to write sample {} of {}: {}", i, total_samples, e);
-                        return Err(BatcherbirdError::Export(std::io::Error::new(std::io::ErrorKind::Other, e)));
+                for (i, value) in self.config.dither.quantize(audio_data, sample.channels, 24).into_iter().enumerate() {
+                    if let Err(e) = writer.write_sample(value) {
+                        error!("❌ Failed to write sample {} of {}: {}", i, total_samples, e);
+                        return Err(BatcherbirdError::Export(std::io::Error::other(e)));
                     }
                 }
             }
             AudioFormat::Wav32BitFloat => {
                 for (i, &sample) in audio_data.iter().enumerate() {
                     if let Err(e) = writer.write_sample(sample) {
-                        println!("❌ Failed to write sample {} of {}: {}", i, total_samples, e);
-                        return Err(BatcherbirdError::Export(std::io::Error::new(std::io::ErrorKind::Other, e)));
+                        error!("❌ Failed to write sample {} of {}: {}", i, total_samples, e);
+                        return Err(BatcherbirdError::Export(std::io::Error::other(e)));
                     }
                 }
             }
-            AudioFormat::DecentSampler => {
+            AudioFormat::DecentSampler | AudioFormat::SFZ | AudioFormat::Custom(_) => {
                 return Err(BatcherbirdError::Export(std::io::Error::new(
                     std::io::ErrorKind::InvalidInput,
-                    "DecentSampler format should not reach write_wav_file - this is a logic error"
-                )));
-            },
-            AudioFormat::SFZ => {
-                return Err(BatcherbirdError::Export(std::io::Error::new(
-                    std::io::ErrorKind::InvalidInput,
-                    "SFZ format should not reach write_wav_file - this is a logic error"
+                    "Instrument formats should not reach write_wav_file - this is a logic error"
                 )));
             }
         }
 
-        println!("✅ All {} samples written, finalizing...", total_samples);
+        info!("✅ All {} samples written, finalizing...", total_samples);
 
         // Finalize with explicit error handling
         match writer.finalize() {
             Ok(_) => {
-                println!("✅ WAV file finalized successfully");
+                info!("✅ WAV file finalized successfully");
             },
             Err(e) => {
-                println!("❌ Failed to finalize WAV file: {}", e);
-                return Err(BatcherbirdError::Export(std::io::Error::new(std::io::ErrorKind::Other, e)));
+                error!("❌ Failed to finalize WAV file: {}", e);
+                return Err(BatcherbirdError::Export(std::io::Error::other(e)));
             }
         }
 
@@ -338,13 +1193,13 @@ impl SampleExporter {
         match std::fs::File::open(filepath) {
             Ok(file) => {
                 if let Err(e) = file.sync_all() {
-                    println!("⚠️ Warning: Failed to sync file to disk: {}", e);
+                    warn!("⚠️ Warning: Failed to sync file to disk: {}", e);
                 } else {
-                    println!("✅ File synced to disk successfully");
+                    info!("✅ File synced to disk successfully");
                 }
             },
             Err(e) => {
-                println!("⚠️ Warning: Could not reopen file for sync: {}", e);
+                warn!("⚠️ Warning: Could not reopen file for sync: {}", e);
             }
         }
 
@@ -352,15 +1207,15 @@ impl SampleExporter {
         match std::fs::metadata(filepath) {
             Ok(metadata) => {
                 let file_size = metadata.len();
-                println!("✅ File created: {} bytes", file_size);
+                info!("✅ File created: {} bytes", file_size);
                 
                 // Basic sanity check - WAV header is 44 bytes, so file should be larger
                 if file_size < 100 {
-                    println!("⚠️ Warning: File size suspiciously small: {} bytes", file_size);
+                    warn!("⚠️ Warning: File size suspiciously small: {} bytes", file_size);
                 }
             },
             Err(e) => {
-                println!("❌ Failed to verify file creation: {}", e);
+                error!("❌ Failed to verify file creation: {}", e);
                 return Err(BatcherbirdError::Export(e));
             }
         }
@@ -368,6 +1223,13 @@ impl SampleExporter {
         Ok(())
     }
 
+    /// Drum name in drum mode (falling back to the pitch for unmapped notes), otherwise the pitch
+    fn note_label(&self, note: u8) -> String {
+        self.config.drum_map.as_ref()
+            .and_then(|map| map.file_token(note))
+            .unwrap_or_else(|| Self::note_to_name(note))
+    }
+
     fn note_to_name(note: u8) -> String {
         let note_names = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
         let octave = (note / 12).saturating_sub(1);
@@ -375,33 +1237,42 @@ impl SampleExporter {
         format!("{}{}", note_name, octave)
     }
 
-    /// Generate a Decent Sampler .dspreset XML file
-    pub fn generate_dspreset_file(&self, samples: &[Sample], wav_files: &[PathBuf]) -> Result<PathBuf> {
-        use std::io::Write;
-        
-        // Create the .dspreset filename (use the sample name from config or default)
-        let preset_name = self.config.naming_pattern
-            .replace("{note}", "")
-            .replace("{note_name}", "")  
-            .replace("{velocity}", "")
+    /// Instrument name derived from the naming pattern's fixed text, e.g. "MySynth" for
+    /// "MySynth_{note_name}_{note}_{velocity}.wav"
+    pub fn instrument_name(&self) -> String {
+        let now = chrono::Local::now();
+        let name = self.pattern
+            .render(|token| if naming::SESSION_TOKENS.contains(&token) { self.session_token(token, now) } else { String::new() })
             .replace("_", "")
             .replace(".wav", "")
             .trim_matches('_')
             .to_string();
-            
-        let preset_name = if preset_name.is_empty() { 
-            "Batcherbird_Instrument".to_string() 
-        } else { 
-            preset_name 
-        };
         
-        let dspreset_filename = format!("{}.dspreset", preset_name);
-        let dspreset_path = self.config.output_directory.join(&dspreset_filename);
+        if name.is_empty() {
+            "Batcherbird_Instrument".to_string()
+        } else {
+            name
+        }
+    }
+
+    /// Generate a Decent Sampler .dspreset XML file
+    pub fn generate_dspreset_file(&self, samples: &[Sample], wav_files: &[PathBuf]) -> Result<PathBuf> {
+        use std::io::Write;
+        
+        // Instrument file is named after the sample name from config (or a default)
+        let preset_name = self.instrument_name();
+        
+        let dspreset_path = self.config.output_directory.join(format!("{}.dspreset", preset_name));
+        let Some(dspreset_path) = self.resolve_output_path(&dspreset_path)? else {
+            info!("   ⏭️ Keeping existing Decent Sampler preset: {}", dspreset_path.display());
+            return Ok(dspreset_path);
+        };
+        let dspreset_filename = dspreset_path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
         
         // Group samples by velocity for layering
         let mut velocity_groups = std::collections::HashMap::new();
         for (i, sample) in samples.iter().enumerate() {
-            if i < wav_files.len() {
+            if i < wav_files.len() && Self::is_mapped_take(sample) {
                 velocity_groups.entry(sample.velocity)
                     .or_insert_with(Vec::new)
                     .push((sample, &wav_files[i]));
@@ -413,12 +1284,12 @@ impl SampleExporter {
         
         // Write XML file
         let mut file = std::fs::File::create(&dspreset_path)
-            .map_err(|e| BatcherbirdError::Export(e))?;
+            .map_err(BatcherbirdError::Export)?;
         
         file.write_all(xml_content.as_bytes())
-            .map_err(|e| BatcherbirdError::Export(e))?;
+            .map_err(BatcherbirdError::Export)?;
             
-        println!("   ✅ Generated Decent Sampler preset: {}", dspreset_filename);
+        info!("   ✅ Generated Decent Sampler preset: {}", dspreset_filename);
         Ok(dspreset_path)
     }
     
@@ -440,35 +1311,100 @@ impl SampleExporter {
         
         xml.push_str("<DecentSampler>\n");
         
+        // CC sweep layers become one group each, switched from a menu
+        let cc_layers = Self::collect_cc_layers(velocity_groups);
+        // Sorted so the file is the same from run to run
+        let mut velocities: Vec<u8> = velocity_groups.keys().copied().collect();
+        velocities.sort();
+        let velocity_zones: Vec<(u8, VelocityZone)> = {
+            let ranges = Self::velocity_layer_ranges(&velocities);
+            let zones = self.velocity_zones(&ranges.iter().map(|&(_, lo, hi)| (lo, hi)).collect::<Vec<_>>());
+            ranges.iter().map(|&(velocity, _, _)| velocity).zip(zones).collect()
+        };
+        let mapped: Vec<(&Sample, &PathBuf)> = velocities.iter()
+            .flat_map(|velocity| velocity_groups[velocity].iter().copied())
+            .collect();
+        
+        // One envelope for the whole instrument, so the ADSR knobs can drive it: the median of
+        // the envelopes fitted per sample
+        let envelope = Self::median_envelope(mapped.iter().filter_map(|(sample, _)| self.playback_envelope(sample)).collect());
+        let gains = self.region_gains(velocity_groups);
+        let (attack, decay, sustain, release) = envelope
+            .map(|env| (env.attack_secs, env.decay_secs, env.sustain, env.release_secs))
+            .unwrap_or((0.0, 0.0, 1.0, 0.1));
+        
         // UI Section following official template structure
-        xml.push_str("  <ui width=\"812\" height=\"375\">\n");
+        let background = self.copy_ui_background()?
+            .map(|image| format!(" bgImage=\"{}\"", image))
+            .unwrap_or_default();
+        xml.push_str(&format!("  <ui width=\"812\" height=\"375\"{}>\n", background));
         xml.push_str("    <tab name=\"main\">\n");
         xml.push_str("      <labeled-knob x=\"50\" y=\"50\" label=\"Volume\" type=\"float\" minValue=\"0\" maxValue=\"1\" value=\"0.7\">\n");
         xml.push_str("        <binding type=\"amp\" level=\"instrument\" parameter=\"VOLUME\" />\n");
         xml.push_str("      </labeled-knob>\n");
+        let knobs = [
+            ("Attack", "ENV_ATTACK", 10.0, attack),
+            ("Decay", "ENV_DECAY", 10.0, decay),
+            ("Sustain", "ENV_SUSTAIN", 1.0, sustain),
+            ("Release", "ENV_RELEASE", 20.0, release),
+        ];
+        for (index, (label, parameter, max, value)) in knobs.iter().enumerate() {
+            xml.push_str(&format!(
+                "      <labeled-knob x=\"{}\" y=\"50\" label=\"{}\" type=\"float\" minValue=\"0\" maxValue=\"{}\" value=\"{:.3}\">\n",
+                150 + index * 100, label, max, value
+            ));
+            xml.push_str(&format!("        <binding type=\"amp\" level=\"instrument\" position=\"0\" parameter=\"{}\" />\n", parameter));
+            xml.push_str("      </labeled-knob>\n");
+        }
+        if !cc_layers.is_empty() {
+            xml.push_str("      <label x=\"600\" y=\"40\" width=\"160\" height=\"20\" text=\"Layer\" />\n");
+            xml.push_str("      <menu x=\"600\" y=\"65\" width=\"160\" height=\"30\" value=\"1\">\n");
+            for (selected, layer) in cc_layers.iter().enumerate() {
+                xml.push_str(&format!("        <option name=\"{}\">\n", layer.label()));
+                for position in 0..cc_layers.len() {
+                    xml.push_str(&format!(
+                        "          <binding type=\"general\" level=\"group\" position=\"{}\" parameter=\"ENABLED\" translation=\"fixed_value\" translationValue=\"{}\" />\n",
+                        position, position == selected
+                    ));
+                }
+                xml.push_str("        </option>\n");
+            }
+            xml.push_str("      </menu>\n");
+        }
         xml.push_str("    </tab>\n");
         xml.push_str("  </ui>\n");
         
-        // Groups Section following official template
-        xml.push_str("  <groups>\n");
-        xml.push_str("    <group>\n");
+        // Groups Section following official template; the envelope lives here for the knobs
+        let envelope_attributes = envelope
+            .map(|env| format!(" attack=\"{:.3}\" decay=\"{:.3}\" sustain=\"{:.3}\" release=\"{:.3}\"",
+                env.attack_secs, env.decay_secs, env.sustain, env.release_secs))
+            .unwrap_or_default();
+        xml.push_str(&format!("  <groups{}>\n", envelope_attributes));
         
-        // Add all samples following the working example format
-        for samples in velocity_groups.values() {
+        let group_attributes = |samples: &[&(&Sample, &PathBuf)]| {
+            let round_robins = samples.iter().filter_map(|(sample, _)| sample.metadata.round_robin).max();
+            round_robins
+                .map(|length| format!(" seqMode=\"round_robin\" seqLength=\"{}\"", length))
+                .unwrap_or_default()
+        };
+        if cc_layers.is_empty() {
+            let samples: Vec<_> = mapped.iter().collect();
+            xml.push_str(&format!("    <group{}>\n", group_attributes(&samples)));
             for (sample, wav_file) in samples {
-                let filename = wav_file.file_name()
-                    .and_then(|name| name.to_str())
-                    .unwrap_or("sample.wav");
-                
-                // Use official template sample format
-                xml.push_str(&format!(
-                    "      <sample path=\"{}\" loNote=\"{}\" hiNote=\"{}\" rootNote=\"{}\" />\n",
-                    filename, sample.note, sample.note, sample.note
-                ));
+                self.push_dspreset_sample(&mut xml, sample, wav_file, &velocity_zones, envelope.is_some(), gains.get(*wav_file).copied());
+            }
+            xml.push_str("    </group>\n");
+        } else {
+            for (index, layer) in cc_layers.iter().enumerate() {
+                let samples: Vec<_> = mapped.iter().filter(|(s, _)| s.metadata.cc_layer == Some(*layer)).collect();
+                xml.push_str(&format!("    <group name=\"{}\" enabled=\"{}\"{}>\n", layer.label(), index == 0, group_attributes(&samples)));
+                for (sample, wav_file) in samples {
+                    self.push_dspreset_sample(&mut xml, sample, wav_file, &velocity_zones, envelope.is_some(), gains.get(*wav_file).copied());
+                }
+                xml.push_str("    </group>\n");
             }
         }
         
-        xml.push_str("    </group>\n");
         xml.push_str("  </groups>\n");
         
         // Close root element
@@ -477,33 +1413,210 @@ impl SampleExporter {
         Ok(xml)
     }
     
+    /// Write one <sample> element using the official template format. With an instrument
+    /// envelope, samples that should play as recorded override it.
+    fn push_dspreset_sample(&self, xml: &mut String, sample: &Sample, wav_file: &Path, velocity_zones: &[(u8, VelocityZone)], instrument_envelope: bool, gain_db: Option<f32>) {
+        let seq_position = sample.metadata.round_robin
+            .map(|take| format!(" seqPosition=\"{}\"", take))
+            .unwrap_or_default();
+        // Crossfading layers repeat the sample for each step of their fades, on top of any
+        // gain matching
+        let volume = |fade_db: Option<f32>| match (fade_db, gain_db) {
+            (None, None) => String::new(),
+            (fade_db, gain_db) => format!(" volume=\"{:.2}dB\"", fade_db.unwrap_or(0.0) + gain_db.unwrap_or(0.0)),
+        };
+        let velocity_slices: Vec<String> = match velocity_zones.iter().find(|(velocity, _)| *velocity == sample.velocity) {
+            Some((_, zone)) => zone.slices().into_iter()
+                .map(|(lo, hi, fade_db)| format!(" loVel=\"{}\" hiVel=\"{}\"{}", lo, hi, volume(fade_db)))
+                .collect(),
+            None => vec![volume(None)],
+        };
+        let trigger = if sample.metadata.release { " trigger=\"release\"" } else { "" };
+        let drum = if self.config.drum_map.is_some() { " loopEnabled=\"false\" pitchKeyTrack=\"0\"" } else { "" };
+        
+        if let Some(name) = self.config.drum_map.as_ref().and_then(|map| map.name(sample.note)) {
+            xml.push_str(&format!("      <!-- {} -->\n", name));
+        }
+        // Decent Sampler tunes in semitones
+        let tuning = self.tune_correction(sample)
+            .map(|cents| format!(" tuning=\"{:.2}\"", cents as f32 / 100.0))
+            .unwrap_or_default();
+        // Only samples that play as recorded by design override it; one whose envelope couldn't
+        // be fitted (e.g. near silence) follows the instrument like every other region
+        let as_recorded = instrument_envelope && self.plays_as_recorded(sample);
+        let envelope = if as_recorded { " attack=\"0.000\" decay=\"0.000\" sustain=\"1.000\"" } else { "" };
+        // Decent Sampler's loopEnd is the last looped frame
+        let looping = match (self.region_loop(wav_file), self.sound_class(sample)) {
+            (Some(points), _) => format!(" loopStart=\"{}\" loopEnd=\"{}\" loopEnabled=\"true\"", points.start, points.end.saturating_sub(1)),
+            (None, Some(SoundClass::Percussive)) => " loopEnabled=\"false\"".to_string(),
+            (None, _) => String::new(),
+        };
+        for (filename, pan) in self.region_files(sample, wav_file) {
+            let pan = pan.map(|pan| format!(" pan=\"{}\"", pan)).unwrap_or_default();
+            for velocity in &velocity_slices {
+                xml.push_str(&format!(
+                    "      <sample path=\"{}\" loNote=\"{}\" hiNote=\"{}\" rootNote=\"{}\"{}{}{}{}{}{}{}{} />\n",
+                    filename, sample.note, sample.note, sample.note, velocity, seq_position, trigger, drum, pan, tuning, envelope, looping
+                ));
+            }
+        }
+    }
+    
+    /// Velocity range each recorded velocity plays over: up to and including itself, from just
+    /// above the next softer one. The loudest layer reaches 127.
+    fn velocity_layer_ranges(velocities: &[u8]) -> Vec<(u8, u8, u8)> {
+        velocities.iter().enumerate()
+            .map(|(i, &velocity)| {
+                let lo = if i == 0 { 1 } else { velocities[i - 1].saturating_add(1) };
+                let hi = if i + 1 == velocities.len() { 127 } else { velocity };
+                (velocity, lo, hi)
+            })
+            .collect()
+    }
+    
+    /// Per-region volume (dB) evening out loudness across notes, when gain matching is on. Each
+    /// sustain sample moves to the median loudness of its velocity (and CC) layer, so layers
+    /// keep their dynamics; release and legato samples follow their note. Measured on the
+    /// written files, whose audio is left as it is.
+    fn region_gains(&self, velocity_groups: &std::collections::HashMap<u8, Vec<(&Sample, &PathBuf)>>) -> std::collections::HashMap<PathBuf, f32> {
+        let mut gains = std::collections::HashMap::new();
+        // Drum hits are meant to differ in level
+        if !self.config.gain_matching || self.config.drum_map.is_some() {
+            return gains;
+        }
+        let follows_note = |sample: &Sample| sample.metadata.release || sample.metadata.legato_from.is_some();
+        let layer = |sample: &Sample| (sample.velocity, sample.metadata.cc_layer);
+        
+        let mut layers: std::collections::HashMap<_, Vec<(&Sample, &PathBuf, f32)>> = std::collections::HashMap::new();
+        for &(sample, wav_file) in velocity_groups.values().flatten().filter(|&&(sample, _)| !follows_note(sample)) {
+            let lufs = library::read_wav(wav_file).ok()
+                .and_then(|(audio, spec)| loudness::integrated_loudness(&audio, spec.channels, spec.sample_rate));
+            if let Some(lufs) = lufs {
+                layers.entry(layer(sample)).or_default().push((sample, wav_file, lufs));
+            }
+        }
+        
+        let mut note_gains = std::collections::HashMap::new();
+        for measured in layers.values() {
+            let mut levels: Vec<f32> = measured.iter().map(|&(_, _, lufs)| lufs).collect();
+            levels.sort_by(|a, b| a.total_cmp(b));
+            let target = levels[levels.len() / 2];
+            for &(sample, wav_file, lufs) in measured {
+                let gain = (target - lufs).clamp(-MAX_GAIN_MATCH_DB, MAX_GAIN_MATCH_DB);
+                note_gains.insert((layer(sample), sample.note), gain);
+                gains.insert(wav_file.clone(), gain);
+            }
+        }
+        for &(sample, wav_file) in velocity_groups.values().flatten().filter(|&&(sample, _)| follows_note(sample)) {
+            if let Some(&gain) = note_gains.get(&(layer(sample), sample.note)) {
+                gains.insert(wav_file.clone(), gain);
+            }
+        }
+        // Changes too small to hear aren't written
+        gains.retain(|_, gain| gain.abs() >= 0.05);
+        gains
+    }
+    
+    /// Velocity layers as played: the given ranges, widened across each split when crossfading
+    fn velocity_zones(&self, ranges: &[(u8, u8)]) -> Vec<VelocityZone> {
+        let mut zones: Vec<VelocityZone> = ranges.iter()
+            .map(|&(lo, hi)| VelocityZone { lo, hi, fade_in: None, fade_out: None })
+            .collect();
+        let Some(overlap) = self.config.velocity_crossfade else {
+            return zones;
+        };
+        for i in 1..zones.len() {
+            // Split between ranges[i - 1] and ranges[i]; fades run equally either side of it
+            let (lower, upper) = (ranges[i - 1], ranges[i]);
+            let width = (lower.1 - lower.0 + 1).min(upper.1 - upper.0 + 1);
+            let half = (width as f32 * overlap / 100.0 / 2.0).round() as u8;
+            if half == 0 {
+                continue;
+            }
+            let fade = ((lower.1 + 1).saturating_sub(half).max(lower.0), lower.1.saturating_add(half).min(upper.1));
+            zones[i - 1].hi = fade.1;
+            zones[i - 1].fade_out = Some(fade);
+            zones[i].lo = fade.0;
+            zones[i].fade_in = Some(fade);
+        }
+        zones
+    }
+    
+    /// Per-stage median of several envelopes
+    fn median_envelope(envelopes: Vec<AdsrEnvelope>) -> Option<AdsrEnvelope> {
+        if envelopes.is_empty() {
+            return None;
+        }
+        let median = |stage: fn(&AdsrEnvelope) -> f32| {
+            let mut values: Vec<f32> = envelopes.iter().map(stage).collect();
+            values.sort_by(|a, b| a.total_cmp(b));
+            values[values.len() / 2]
+        };
+        Some(AdsrEnvelope {
+            attack_secs: median(|env| env.attack_secs),
+            decay_secs: median(|env| env.decay_secs),
+            sustain: median(|env| env.sustain),
+            release_secs: median(|env| env.release_secs),
+        })
+    }
+    
+    /// Copy `ui_background` next to the instrument file, returning the name to reference it by
+    fn copy_ui_background(&self) -> Result<Option<String>> {
+        let Some(image) = &self.config.ui_background else {
+            return Ok(None);
+        };
+        let name = image.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| BatcherbirdError::Config(format!("Not an image file: {}", image.display())))?;
+        let destination = self.config.output_directory.join(&name);
+        if *image != destination {
+            std::fs::copy(image, &destination).map_err(BatcherbirdError::Export)?;
+        }
+        Ok(Some(name))
+    }
+    
+    /// Distinct CC sweep layers present in a sample set, sorted by controller then value
+    fn collect_cc_layers(velocity_groups: &std::collections::HashMap<u8, Vec<(&Sample, &PathBuf)>>) -> Vec<CcLayer> {
+        let mut layers: Vec<CcLayer> = velocity_groups.values()
+            .flatten()
+            .filter_map(|(sample, _)| sample.metadata.cc_layer)
+            .collect();
+        layers.sort();
+        layers.dedup();
+        layers
+    }
+    
+    /// Split the 0-127 controller range between sweep layers (midpoint between neighbouring values)
+    fn cc_layer_ranges(layers: &[CcLayer]) -> Vec<(CcLayer, u8, u8)> {
+        let mut ranges = Vec::with_capacity(layers.len());
+        for (i, layer) in layers.iter().enumerate() {
+            let prev = if i > 0 { layers.get(i - 1).filter(|l| l.controller == layer.controller) } else { None };
+            let next = layers.get(i + 1).filter(|l| l.controller == layer.controller);
+            let lo = prev.map(|p| ((p.value as u16 + layer.value as u16) / 2 + 1) as u8).unwrap_or(0);
+            let hi = next.map(|n| ((layer.value as u16 + n.value as u16) / 2) as u8).unwrap_or(127);
+            ranges.push((*layer, lo, hi));
+        }
+        ranges
+    }
+    
     /// Generate an SFZ .sfz file
     pub fn generate_sfz_file(&self, samples: &[Sample], wav_files: &[PathBuf]) -> Result<PathBuf> {
         use std::io::Write;
         
-        // Create the .sfz filename (use the sample name from config or default)
-        let preset_name = self.config.naming_pattern
-            .replace("{note}", "")
-            .replace("{note_name}", "")
-            .replace("{velocity}", "")
-            .replace("_", "")
-            .replace(".wav", "")
-            .trim_matches('_')
-            .to_string();
-            
-        let preset_name = if preset_name.is_empty() {
-            "Batcherbird_Instrument".to_string()
-        } else {
-            preset_name
-        };
+        // Instrument file is named after the sample name from config (or a default)
+        let preset_name = self.instrument_name();
         
-        let sfz_filename = format!("{}.sfz", preset_name);
-        let sfz_path = self.config.output_directory.join(&sfz_filename);
+        let sfz_path = self.config.output_directory.join(format!("{}.sfz", preset_name));
+        let Some(sfz_path) = self.resolve_output_path(&sfz_path)? else {
+            info!("   ⏭️ Keeping existing SFZ instrument: {}", sfz_path.display());
+            return Ok(sfz_path);
+        };
+        let sfz_filename = sfz_path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
         
         // Group samples by velocity for layering
         let mut velocity_groups = std::collections::HashMap::new();
         for (i, sample) in samples.iter().enumerate() {
-            if i < wav_files.len() {
+            if i < wav_files.len() && Self::is_mapped_take(sample) {
                 velocity_groups.entry(sample.velocity)
                     .or_insert_with(Vec::new)
                     .push((sample, &wav_files[i]));
@@ -515,12 +1628,12 @@ impl SampleExporter {
         
         // Write SFZ file
         let mut file = std::fs::File::create(&sfz_path)
-            .map_err(|e| BatcherbirdError::Export(e))?;
+            .map_err(BatcherbirdError::Export)?;
         
         file.write_all(sfz_content.as_bytes())
-            .map_err(|e| BatcherbirdError::Export(e))?;
+            .map_err(BatcherbirdError::Export)?;
             
-        println!("   ✅ Generated SFZ instrument: {}", sfz_filename);
+        info!("   ✅ Generated SFZ instrument: {}", sfz_filename);
         Ok(sfz_path)
     }
     
@@ -539,67 +1652,212 @@ impl SampleExporter {
             sfz.push_str(&format!("// Description: {}\n", description));
         }
         
-        sfz.push_str("\n");
+        sfz.push('\n');
         
-        // Control section - path settings
-        sfz.push_str("<control>\n");
-        sfz.push_str("default_path=samples/\n");
-        sfz.push_str("\n");
+        // Control section - path settings, for samples kept in their own folder
+        if self.config.layout == FolderLayout::SamplesFolder {
+            sfz.push_str("<control>\n");
+            sfz.push_str("default_path=samples/\n");
+            sfz.push('\n');
+        }
         
         // Global section - overall settings
         sfz.push_str("<global>\n");
         sfz.push_str("ampeg_release=0.5\n");
-        sfz.push_str("\n");
+        sfz.push_str(&format!("amp_veltrack={}\n", Self::amp_veltrack(velocity_groups.len())));
+        if self.config.drum_map.is_some() {
+            // Hits play to the end regardless of note length and never transpose
+            sfz.push_str("loop_mode=one_shot\n");
+            sfz.push_str("pitch_keytrack=0\n");
+        }
+        sfz.push('\n');
+        
+        let gains = self.region_gains(velocity_groups);
+        let cc_layers = Self::collect_cc_layers(velocity_groups);
+        if cc_layers.is_empty() {
+            self.push_sfz_velocity_groups(&mut sfz, velocity_groups, None, &gains);
+        } else {
+            // One set of velocity groups per sweep layer, selected by locc/hicc
+            for (layer, lo_cc, hi_cc) in Self::cc_layer_ranges(&cc_layers) {
+                let mut layer_groups = std::collections::HashMap::new();
+                for (velocity, samples) in velocity_groups {
+                    let layer_samples: Vec<(&Sample, &PathBuf)> = samples.iter()
+                        .filter(|(s, _)| s.metadata.cc_layer == Some(layer))
+                        .cloned()
+                        .collect();
+                    if !layer_samples.is_empty() {
+                        layer_groups.insert(*velocity, layer_samples);
+                    }
+                }
+                
+                sfz.push_str(&format!("// Layer {}\n", layer.label()));
+                self.push_sfz_velocity_groups(&mut sfz, &layer_groups, Some((layer.controller, lo_cc, hi_cc)), &gains);
+            }
+        }
         
+        Ok(sfz)
+    }
+    
+    /// Emit <group>/<region> blocks for each velocity layer, optionally restricted to a CC range
+    fn push_sfz_velocity_groups(
+        &self,
+        sfz: &mut String,
+        velocity_groups: &std::collections::HashMap<u8, Vec<(&Sample, &PathBuf)>>,
+        cc_range: Option<(u8, u8, u8)>,
+        gains: &std::collections::HashMap<PathBuf, f32>,
+    ) {
         // Sort velocity groups for consistent output
         let mut sorted_velocities: Vec<_> = velocity_groups.keys().collect();
         sorted_velocities.sort();
+        let has_legato = velocity_groups.values()
+            .flatten()
+            .any(|(sample, _)| sample.metadata.legato_from.is_some());
+        
+        // Velocity range for each layer: a single velocity covers the full range, several
+        // share it evenly
+        let layer_width = 127.0 / sorted_velocities.len() as f32;
+        let ranges: Vec<(u8, u8)> = (0..sorted_velocities.len())
+            .map(|i| (((i as f32 * layer_width) as u8).max(1), (((i + 1) as f32 * layer_width) as u8).min(127)))
+            .collect();
+        let zones = self.velocity_zones(&ranges);
         
         // Generate regions for each velocity layer
         for (group_index, &velocity) in sorted_velocities.iter().enumerate() {
             if let Some(samples) = velocity_groups.get(velocity) {
-                // Group header for this velocity layer
-                if sorted_velocities.len() > 1 {
+                // Group header for this velocity layer (always needed to carry a CC range)
+                if sorted_velocities.len() > 1 || cc_range.is_some() {
                     sfz.push_str("<group>\n");
                     
-                    // Calculate velocity range for this layer
-                    let (lo_vel, hi_vel) = if sorted_velocities.len() == 1 {
-                        (1, 127) // Single velocity covers full range
-                    } else {
-                        // Distribute velocity ranges among layers
-                        let vel_range = 127.0 / sorted_velocities.len() as f32;
-                        let lo = ((group_index as f32 * vel_range) as u8).max(1);
-                        let hi = (((group_index + 1) as f32 * vel_range) as u8).min(127);
-                        (lo, hi)
-                    };
+                    if let Some((controller, lo_cc, hi_cc)) = cc_range {
+                        sfz.push_str(&format!("locc{}={}\n", controller, lo_cc));
+                        sfz.push_str(&format!("hicc{}={}\n", controller, hi_cc));
+                    }
                     
-                    sfz.push_str(&format!("lovel={}\n", lo_vel));
-                    sfz.push_str(&format!("hivel={}\n", hi_vel));
-                    sfz.push_str("\n");
+                    let zone = zones[group_index];
+                    sfz.push_str(&format!("lovel={}\n", zone.lo));
+                    sfz.push_str(&format!("hivel={}\n", zone.hi));
+                    if let Some((lo, hi)) = zone.fade_in {
+                        sfz.push_str(&format!("xfin_lovel={}\n", lo));
+                        sfz.push_str(&format!("xfin_hivel={}\n", hi));
+                    }
+                    if let Some((lo, hi)) = zone.fade_out {
+                        sfz.push_str(&format!("xfout_lovel={}\n", lo));
+                        sfz.push_str(&format!("xfout_hivel={}\n", hi));
+                    }
+                    sfz.push('\n');
                 }
                 
+                // Notes skipped between samples play the nearest one, except in drum kits
+                let mut notes: Vec<u8> = samples.iter()
+                    .filter(|(s, _)| s.metadata.legato_from.is_none())
+                    .map(|(s, _)| s.note)
+                    .collect();
+                notes.sort();
+                notes.dedup();
+                let spans = if self.config.drum_map.is_some() { std::collections::HashMap::new() } else { Self::key_spans(&notes) };
+                
                 // Add regions (samples) for this velocity group
                 for (sample, wav_file) in samples {
-                    let filename = wav_file.file_name()
-                        .and_then(|name| name.to_str())
-                        .unwrap_or("sample.wav");
+                    if let Some(name) = self.config.drum_map.as_ref().and_then(|map| map.name(sample.note)) {
+                        sfz.push_str(&format!("// {}\n", name));
+                    }
+                    let tune = self.tune_correction(sample);
+                    let envelope = self.playback_envelope(sample);
+                    let looping = self.region_loop(wav_file);
+                    let one_shot = looping.is_none() && self.sound_class(sample) == Some(SoundClass::Percussive);
+                    for (filename, pan) in self.region_files(sample, wav_file) {
+                        // default_path already points into samples/
+                        let filename = filename.strip_prefix("samples/").filter(|_| self.config.layout == FolderLayout::SamplesFolder).unwrap_or(&filename);
+                        sfz.push_str("<region>\n");
+                        sfz.push_str(&format!("sample={}\n", filename));
+                        match spans.get(&sample.note).filter(|(lo, hi)| lo != hi && sample.metadata.legato_from.is_none()) {
+                            Some((lo, hi)) => {
+                                sfz.push_str(&format!("pitch_keycenter={}\n", sample.note));
+                                sfz.push_str(&format!("lokey={}\n", lo));
+                                sfz.push_str(&format!("hikey={}\n", hi));
+                            }
+                            None => sfz.push_str(&format!("key={}\n", sample.note)),
+                        }
+                        if let Some(pan) = pan {
+                            sfz.push_str(&format!("pan={}\n", pan));
+                        }
+                        if let Some(tune) = tune {
+                            sfz.push_str(&format!("tune={}\n", tune));
+                        }
+                        if let Some(gain) = gains.get(*wav_file) {
+                            sfz.push_str(&format!("volume={:.2}\n", gain));
+                        }
+                        if let Some(env) = envelope {
+                            sfz.push_str(&format!("ampeg_attack={:.3}\n", env.attack_secs));
+                            sfz.push_str(&format!("ampeg_decay={:.3}\n", env.decay_secs));
+                            sfz.push_str(&format!("ampeg_sustain={:.1}\n", env.sustain * 100.0));
+                            sfz.push_str(&format!("ampeg_release={:.3}\n", env.release_secs));
+                        }
+                        if let Some(points) = looping {
+                            // SFZ loop_end is the last looped frame
+                            sfz.push_str("loop_mode=loop_continuous\n");
+                            sfz.push_str(&format!("loop_start={}\n", points.start));
+                            sfz.push_str(&format!("loop_end={}\n", points.end.saturating_sub(1)));
+                        } else if one_shot {
+                            sfz.push_str("loop_mode=one_shot\n");
+                        }
                     
-                    sfz.push_str("<region>\n");
-                    sfz.push_str(&format!("sample={}\n", filename));
-                    sfz.push_str(&format!("key={}\n", sample.note));
+                        // Legato transitions only play when connected from their source note
+                        if let Some(source) = sample.metadata.legato_from {
+                            sfz.push_str("trigger=legato\n");
+                            sfz.push_str(&format!("sw_previous={}\n", source));
+                        } else if sample.metadata.release {
+                            sfz.push_str("trigger=release\n");
+                        } else if has_legato {
+                            sfz.push_str("trigger=first\n");
+                        }
                     
-                    // Add velocity range for single-layer instruments
-                    if sorted_velocities.len() == 1 {
-                        sfz.push_str("lovel=1\n");
-                        sfz.push_str("hivel=127\n");
-                    }
+                        // Round robin takes of the same key cycle in order
+                        if let Some(take) = sample.metadata.round_robin {
+                            let takes = samples.iter()
+                                .filter(|(s, _)| s.note == sample.note)
+                                .filter_map(|(s, _)| s.metadata.round_robin)
+                                .max()
+                                .unwrap_or(take);
+                            sfz.push_str(&format!("seq_length={}\n", takes));
+                            sfz.push_str(&format!("seq_position={}\n", take));
+                        }
+                    
+                        // Add velocity range for single-layer instruments
+                        if sorted_velocities.len() == 1 {
+                            sfz.push_str("lovel=1\n");
+                            sfz.push_str("hivel=127\n");
+                        }
                     
-                    sfz.push_str("\n");
+                        sfz.push('\n');
+                    }
                 }
             }
         }
-        
-        Ok(sfz)
+    }
+
+    /// Key span each sampled note plays over: split halfway to each neighbour (the lower note
+    /// taking the odd key), and at the ends reaching as far out as the inner side reaches in
+    fn key_spans(notes: &[u8]) -> std::collections::HashMap<u8, (u8, u8)> {
+        notes.iter().enumerate()
+            .map(|(i, &note)| {
+                let lo = i.checked_sub(1).map(|prev| notes[prev] + (note - notes[prev] - 1) / 2 + 1);
+                let hi = notes.get(i + 1).map(|&next| note + (next - note - 1) / 2);
+                let span = match (lo, hi) {
+                    (Some(lo), Some(hi)) => (lo, hi),
+                    (None, Some(hi)) => (note.saturating_sub(hi - note), hi),
+                    (Some(lo), None) => (lo, note.saturating_add(note - lo).min(127)),
+                    (None, None) => (note, note),
+                };
+                (note, span)
+            })
+            .collect()
+    }
+    
+    /// How much velocity changes level (percent). A single layer has to cover the whole dynamic
+    /// range; with several, each only has to bridge the step to the next layer.
+    fn amp_veltrack(velocity_layers: usize) -> u32 {
+        100 / velocity_layers.max(1) as u32
     }
 
     pub fn get_export_info(&self) -> String {
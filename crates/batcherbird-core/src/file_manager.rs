@@ -0,0 +1,77 @@
+//! Showing a folder or file in the platform's file manager: Finder on macOS, Explorer on
+//! Windows and whatever `xdg-open` hands folders to on Linux and the BSDs.
+
+use crate::{BatcherbirdError, Result};
+use std::ffi::OsString;
+use std::path::Path;
+use std::process::Command;
+use tracing::info;
+
+/// What the file manager is called on this platform, for messages
+pub fn file_manager_name() -> &'static str {
+    match std::env::consts::OS {
+        "macos" => "Finder",
+        "windows" => "Explorer",
+        _ => "file manager",
+    }
+}
+
+/// Open a folder, or the folder of a file with the file selected where the file manager
+/// supports it
+pub fn reveal(path: &Path) -> Result<()> {
+    if !path.exists() {
+        return Err(BatcherbirdError::Session(format!("Nothing to show at {}", path.display())));
+    }
+    let (program, args) = reveal_command(path, path.is_file(), std::env::consts::OS);
+    let status = Command::new(program).args(&args).status()
+        .map_err(|e| BatcherbirdError::Session(format!("Failed to start {}: {}", program, e)))?;
+    // Explorer exits with 1 even when it opened the window
+    if !status.success() && program != "explorer" {
+        return Err(BatcherbirdError::Session(format!("{} exited with {}", program, status)));
+    }
+    info!("📁 Showed {} in the {}", path.display(), file_manager_name());
+    Ok(())
+}
+
+/// Program and arguments that show `path` on `os` (as in `std::env::consts::OS`)
+fn reveal_command(path: &Path, is_file: bool, os: &str) -> (&'static str, Vec<OsString>) {
+    match os {
+        "macos" if is_file => ("open", vec!["-R".into(), path.into()]),
+        "macos" => ("open", vec![path.into()]),
+        "windows" if is_file => {
+            let mut select = OsString::from("/select,");
+            select.push(path);
+            ("explorer", vec![select])
+        }
+        "windows" => ("explorer", vec![path.into()]),
+        // xdg-open would open a file in its default app, so show its folder instead
+        _ => {
+            let folder = if is_file { path.parent().unwrap_or(path) } else { path };
+            ("xdg-open", vec![folder.into()])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reveal_command_per_platform() {
+        let folder = Path::new("/samples/Juno");
+        let file = Path::new("/samples/Juno/C4_60_vel127.wav");
+        let args = |args: Vec<OsString>| args.into_iter().map(|arg| arg.to_string_lossy().to_string()).collect::<Vec<_>>();
+
+        let (program, macos) = reveal_command(file, true, "macos");
+        assert_eq!((program, args(macos)), ("open", vec!["-R".to_string(), file.display().to_string()]));
+        let (program, windows) = reveal_command(file, true, "windows");
+        assert_eq!((program, args(windows)), ("explorer", vec![format!("/select,{}", file.display())]));
+        let (program, windows) = reveal_command(folder, false, "windows");
+        assert_eq!((program, args(windows)), ("explorer", vec![folder.display().to_string()]));
+        for os in ["linux", "freebsd"] {
+            let (program, linux) = reveal_command(file, true, os);
+            assert_eq!((program, args(linux)), ("xdg-open", vec![folder.display().to_string()]));
+        }
+        assert!(reveal(Path::new("/no/such/batcherbird/folder")).is_err());
+    }
+}
@@ -0,0 +1,354 @@
+use crate::sampler::Sample;
+use crate::{BatcherbirdError, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Stock configurations; expanded machines can be described in the user targets file
+const BUILTIN_TARGETS: &str = r#"
+[[target]]
+id = "mpc2000"
+name = "Akai MPC2000"
+max_sample_rate = 44100
+bit_depth = 16
+max_channels = 2
+memory_bytes = 2097152
+
+[[target]]
+id = "mpc2000xl"
+name = "Akai MPC2000XL"
+max_sample_rate = 44100
+bit_depth = 16
+max_channels = 2
+memory_bytes = 2097152
+
+[[target]]
+id = "sp404"
+name = "Roland SP-404"
+max_sample_rate = 44100
+bit_depth = 16
+max_channels = 2
+
+[[target]]
+id = "s900"
+name = "Akai S900"
+max_sample_rate = 40000
+bit_depth = 12
+max_channels = 1
+memory_bytes = 768000
+
+[[target]]
+id = "s950"
+name = "Akai S950"
+max_sample_rate = 48000
+bit_depth = 12
+max_channels = 1
+memory_bytes = 768000
+
+[[target]]
+id = "s1000"
+name = "Akai S1000"
+max_sample_rate = 44100
+bit_depth = 16
+max_channels = 2
+memory_bytes = 2097152
+
+[[target]]
+id = "mirage"
+name = "Ensoniq Mirage"
+max_sample_rate = 33000
+bit_depth = 8
+max_channels = 1
+max_sample_secs = 2.0
+memory_bytes = 131072
+"#;
+
+/// Fade applied where a sample is cut short so it doesn't end with a click (ms)
+const CUT_FADE_MS: f32 = 10.0;
+
+/// Limits of a hardware sampler a bank is being built for
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HardwareTarget {
+    pub id: String,
+    pub name: String,
+    pub max_sample_rate: u32,
+    /// Resolution the machine stores samples at (written as 16-bit WAV)
+    pub bit_depth: u8,
+    /// 1 for mono-only samplers
+    pub max_channels: u16,
+    /// Longest single sample the machine accepts
+    #[serde(default)]
+    pub max_sample_secs: Option<f32>,
+    /// Sample memory available for the whole bank
+    #[serde(default)]
+    pub memory_bytes: Option<u64>,
+}
+
+/// What fitting changed about one sample
+#[derive(Debug, Clone, PartialEq)]
+pub struct FitChange {
+    pub note: u8,
+    pub velocity: u8,
+    pub changes: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FitReport {
+    pub target: String,
+    pub samples: Vec<FitChange>,
+    /// Memory the fitted bank takes on the target
+    pub bank_bytes: u64,
+}
+
+impl FitReport {
+    pub fn summary(&self) -> String {
+        let mut report = format!("🎛️ Fitted for {}: {:.1} KB of sample memory\n", self.target, self.bank_bytes as f64 / 1024.0);
+        if self.samples.is_empty() {
+            report.push_str("   No changes needed\n");
+        }
+        for sample in &self.samples {
+            report.push_str(&format!("   Note {} vel {}: {}\n", sample.note, sample.velocity, sample.changes.join(", ")));
+        }
+        report
+    }
+}
+
+impl HardwareTarget {
+    /// Bytes the target needs to hold `frames` frames of `channels` channels
+    pub fn sample_bytes(&self, frames: usize, channels: u16) -> u64 {
+        (frames as u64 * channels as u64 * self.bit_depth as u64).div_ceil(8)
+    }
+
+    /// Convert a bank to the target's format: mix to mono, downsample, reduce resolution and
+    /// shorten samples to the length limit. When the bank still exceeds sample memory, the
+    /// longest samples are shortened to a common length until it fits.
+    pub fn fit(&self, samples: &[Sample]) -> (Vec<Sample>, FitReport) {
+        let mut fitted = Vec::with_capacity(samples.len());
+        let mut changes: Vec<Vec<String>> = Vec::with_capacity(samples.len());
+
+        for sample in samples {
+            let mut sample = sample.clone();
+            let mut changed = Vec::new();
+            if sample.channels > self.max_channels {
+                sample.audio_data = crate::pitch::mixdown(&sample.audio_data, sample.channels);
+                changed.push(format!("{} channels mixed to mono", sample.channels));
+                sample.channels = 1;
+            }
+            if sample.sample_rate > self.max_sample_rate {
+                sample.audio_data = resample(&sample.audio_data, sample.channels, sample.sample_rate, self.max_sample_rate);
+                changed.push(format!("resampled {} -> {} Hz", sample.sample_rate, self.max_sample_rate));
+                sample.sample_rate = self.max_sample_rate;
+            }
+            if let Some(max_secs) = self.max_sample_secs {
+                let max_frames = (max_secs * sample.sample_rate as f32) as usize;
+                if let Some(change) = shorten(&mut sample, max_frames) {
+                    changed.push(format!("{} (limit {:.1}s)", change, max_secs));
+                }
+            }
+            fitted.push(sample);
+            changes.push(changed);
+        }
+
+        let frames = |sample: &Sample| sample.audio_data.len() / sample.channels.max(1) as usize;
+        let bank_bytes = |bank: &[Sample], cap: usize| -> u64 {
+            bank.iter().map(|s| self.sample_bytes(frames(s).min(cap), s.channels)).sum()
+        };
+        if let Some(memory) = self.memory_bytes.filter(|&memory| bank_bytes(&fitted, usize::MAX) > memory) {
+            // Largest common length cap that fits, found by bisection
+            let (mut low, mut high) = (0, fitted.iter().map(frames).max().unwrap_or(0));
+            while low < high {
+                let mid = (low + high).div_ceil(2);
+                if bank_bytes(&fitted, mid) <= memory { low = mid } else { high = mid - 1 }
+            }
+            for (sample, changed) in fitted.iter_mut().zip(changes.iter_mut()) {
+                if let Some(change) = shorten(sample, low) {
+                    changed.push(format!("{} to fit {} KB memory", change, memory / 1024));
+                }
+            }
+        }
+
+        // Last, so the fades above are quantized too
+        if self.bit_depth < 16 {
+            let steps = (1i32 << (self.bit_depth - 1)) as f32;
+            for (sample, changed) in fitted.iter_mut().zip(changes.iter_mut()) {
+                sample.audio_data.iter_mut().for_each(|s| *s = (*s * steps).round().clamp(-steps, steps - 1.0) / steps);
+                changed.push(format!("reduced to {}-bit", self.bit_depth));
+            }
+        }
+
+        let report = FitReport {
+            target: self.name.clone(),
+            samples: fitted.iter().zip(changes)
+                .filter(|(_, changed)| !changed.is_empty())
+                .map(|(sample, changes)| FitChange { note: sample.note, velocity: sample.velocity, changes })
+                .collect(),
+            bank_bytes: bank_bytes(&fitted, usize::MAX),
+        };
+        (fitted, report)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.id.trim().is_empty() || self.max_sample_rate == 0 || self.max_channels == 0 || !(8..=16).contains(&self.bit_depth) {
+            return Err(BatcherbirdError::Config(format!(
+                "Target '{}' needs an id, a sample rate, at least one channel and an 8-16 bit depth", self.id
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Cut a sample to `max_frames` with a short fade; describes the cut if there was one
+fn shorten(sample: &mut Sample, max_frames: usize) -> Option<String> {
+    let channels = sample.channels.max(1) as usize;
+    let frames = sample.audio_data.len() / channels;
+    if frames <= max_frames {
+        return None;
+    }
+    sample.audio_data.truncate(max_frames * channels);
+    let fade = ((CUT_FADE_MS / 1000.0 * sample.sample_rate as f32) as usize).min(max_frames);
+    for (i, frame) in sample.audio_data.chunks_mut(channels).rev().take(fade).enumerate() {
+        frame.iter_mut().for_each(|s| *s *= i as f32 / fade as f32);
+    }
+    let rate = sample.sample_rate.max(1) as f32;
+    Some(format!("trimmed {:.2}s -> {:.2}s", frames as f32 / rate, max_frames as f32 / rate))
+}
+
+/// Linear interpolation resampling of interleaved audio
+fn resample(audio: &[f32], channels: u16, from_rate: u32, to_rate: u32) -> Vec<f32> {
+    let channels = channels.max(1) as usize;
+    let frames = audio.len() / channels;
+    let out_frames = (frames as u64 * to_rate as u64 / from_rate as u64) as usize;
+    let step = from_rate as f64 / to_rate as f64;
+    let mut out = Vec::with_capacity(out_frames * channels);
+    for frame in 0..out_frames {
+        let position = frame as f64 * step;
+        let index = position as usize;
+        let fraction = (position - index as f64) as f32;
+        let next = (index + 1).min(frames - 1);
+        for channel in 0..channels {
+            let a = audio[index * channels + channel];
+            let b = audio[next * channels + channel];
+            out.push(a + (b - a) * fraction);
+        }
+    }
+    out
+}
+
+/// TOML file layout: one `[[target]]` table per sampler
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TargetFile {
+    #[serde(default, rename = "target")]
+    targets: Vec<HardwareTarget>,
+}
+
+/// Built-in hardware targets plus user-defined ones
+#[derive(Debug, Clone)]
+pub struct TargetLibrary {
+    targets: Vec<HardwareTarget>,
+}
+
+impl TargetLibrary {
+    pub fn builtin() -> Self {
+        let file: TargetFile = toml::from_str(BUILTIN_TARGETS)
+            .expect("built-in hardware targets must parse");
+        Self { targets: file.targets }
+    }
+
+    /// Built-in targets plus the user targets file, if there is one
+    pub fn load_default() -> Self {
+        let mut library = Self::builtin();
+        if let Some(path) = Self::user_targets_path().filter(|path| path.exists()) {
+            if let Err(e) = library.load_file(&path) {
+                warn!("⚠️ Skipping hardware targets file {}: {}", path.display(), e);
+            }
+        }
+        library
+    }
+
+    /// Where user targets live (e.g. ~/.config/Batcherbird/targets.toml on Linux)
+    pub fn user_targets_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("Batcherbird").join("targets.toml"))
+    }
+
+    /// Add or replace targets from a TOML file. Returns how many were loaded.
+    pub fn load_file(&mut self, path: &Path) -> Result<usize> {
+        let content = std::fs::read_to_string(path)?;
+        self.load_str(&content)
+    }
+
+    pub fn load_str(&mut self, content: &str) -> Result<usize> {
+        let file: TargetFile = toml::from_str(content)?;
+        for target in &file.targets {
+            target.validate()?;
+        }
+
+        let count = file.targets.len();
+        for target in file.targets {
+            self.targets.retain(|existing| existing.id != target.id);
+            self.targets.push(target);
+        }
+        Ok(count)
+    }
+
+    pub fn targets(&self) -> &[HardwareTarget] {
+        &self.targets
+    }
+
+    /// Target by id, with an error listing the available ids
+    pub fn require(&self, id: &str) -> Result<&HardwareTarget> {
+        self.targets.iter().find(|target| target.id.eq_ignore_ascii_case(id)).ok_or_else(|| {
+            let ids: Vec<&str> = self.targets.iter().map(|t| t.id.as_str()).collect();
+            BatcherbirdError::Config(format!("Unknown hardware target '{}'. Available: {}", id, ids.join(", ")))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn sample(note: u8, secs: f32) -> Sample {
+        let frames = (secs * 48000.0) as usize;
+        Sample {
+            note,
+            velocity: 127,
+            audio_data: (0..frames).flat_map(|i| { let s = 0.5 * (i as f32 * 0.01).sin(); [s, s] }).collect(),
+            sample_rate: 48000,
+            channels: 2,
+            recorded_at: SystemTime::now(),
+            midi_timing: Duration::ZERO,
+            audio_timing: Duration::ZERO,
+            metadata: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_fit_bank_to_s900() {
+        let library = TargetLibrary::builtin();
+        let s900 = library.require("S900").unwrap();
+        assert!(library.require("mpc60").is_err());
+
+        // 4 x 6s stereo at 48k is far beyond 750 KB of 12-bit mono at 40k
+        let bank: Vec<Sample> = (60..64).map(|note| sample(note, 6.0)).collect();
+        let (fitted, report) = s900.fit(&bank);
+
+        assert!(fitted.iter().all(|s| s.channels == 1 && s.sample_rate == 40000));
+        assert!(report.bank_bytes <= s900.memory_bytes.unwrap());
+        assert!(report.bank_bytes > s900.memory_bytes.unwrap() - 1024);
+        // Equal samples are cut to the same length
+        assert_eq!(fitted[0].audio_data.len(), fitted[3].audio_data.len());
+        assert_eq!(report.samples.len(), 4);
+        assert!(report.samples[0].changes.iter().any(|c| c.contains("mixed to mono")));
+        assert!(report.samples[0].changes.iter().any(|c| c.contains("memory")));
+        // 12-bit steps only
+        assert!(fitted[0].audio_data.iter().all(|s| (s * 2048.0 - (s * 2048.0).round()).abs() < 1e-3));
+
+        // A bank that already fits the SP-404 is left alone
+        let sp404 = library.require("sp404").unwrap();
+        let small = vec![Sample { sample_rate: 44100, ..sample(60, 1.0) }];
+        let (unchanged, report) = sp404.fit(&small);
+        assert_eq!(unchanged[0].audio_data, small[0].audio_data);
+        assert!(report.samples.is_empty());
+    }
+}
@@ -0,0 +1,286 @@
+//! User commands run around the sampling pipeline: before and after each note is captured
+//! and after each sample file is written. Commands go through the shell with the note,
+//! velocity and file in environment variables, e.g. to switch external gear before a note,
+//! or tag and upload a finished file:
+//!
+//! ```text
+//! BATCHERBIRD_HOOK       pre_note, post_note or post_export
+//! BATCHERBIRD_NOTE       MIDI note (60)
+//! BATCHERBIRD_NOTE_NAME  note name (C4)
+//! BATCHERBIRD_VELOCITY   velocity (100)
+//! BATCHERBIRD_FILE       written file (post_export only)
+//! ```
+//!
+//! A hook naming a `.rhai` file runs that script in an embedded Rhai engine instead. It sees
+//! the same values as the constants `hook`, `note`, `note_name`, `velocity` and `file` (`()`
+//! where unset), can run commands with `shell(command)`, which returns whether the command
+//! succeeded, and fails the hook by throwing:
+//!
+//! ```text
+//! if velocity < 64 { return; }
+//! if !shell(`rclone copy "${file}" remote:samples`) { throw "upload failed"; }
+//! ```
+
+use crate::{BatcherbirdError, Result};
+use rhai::{Dynamic, Engine, EvalAltResult, Scope};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// How long a command may run when no timeout is configured
+const DEFAULT_TIMEOUT_SECS: f32 = 30.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookPoint {
+    PreNote,
+    PostNote,
+    PostExport,
+}
+
+impl HookPoint {
+    pub fn name(self) -> &'static str {
+        match self {
+            HookPoint::PreNote => "pre_note",
+            HookPoint::PostNote => "post_note",
+            HookPoint::PostExport => "post_export",
+        }
+    }
+}
+
+/// Shell commands or Rhai script files for each hook point, run in order
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HookConfig {
+    /// Before each note-on, after the synth has been silenced
+    pub pre_note: Vec<String>,
+    /// After each note is captured
+    pub post_note: Vec<String>,
+    /// After each sample file is written (dual mono pairs run once per file)
+    pub post_export: Vec<String>,
+    /// Seconds a command or script may run before it is stopped (None = 30s)
+    pub timeout_secs: Option<f32>,
+    /// Stop the batch when a hook fails instead of warning and carrying on
+    pub abort_on_failure: bool,
+}
+
+/// The note, and for exports the file, a hook runs for
+#[derive(Debug, Clone, Default)]
+pub struct HookContext {
+    pub note: Option<u8>,
+    pub velocity: Option<u8>,
+    pub file: Option<PathBuf>,
+}
+
+impl HookContext {
+    pub fn note(note: u8, velocity: u8) -> Self {
+        Self { note: Some(note), velocity: Some(velocity), file: None }
+    }
+
+    pub fn file(note: u8, velocity: u8, file: &Path) -> Self {
+        Self { file: Some(file.to_path_buf()), ..Self::note(note, velocity) }
+    }
+
+    /// Script constants matching the environment variables
+    fn scope(&self, point: HookPoint) -> Scope<'static> {
+        let optional = |value: Option<Dynamic>| value.unwrap_or(Dynamic::UNIT);
+        let mut scope = Scope::new();
+        scope.push_constant("hook", point.name());
+        scope.push_constant_dynamic("note", optional(self.note.map(|note| Dynamic::from(note as i64))));
+        scope.push_constant_dynamic("note_name", optional(self.note.map(|note| Dynamic::from(note_to_name(note)))));
+        scope.push_constant_dynamic("velocity", optional(self.velocity.map(|velocity| Dynamic::from(velocity as i64))));
+        scope.push_constant_dynamic("file", optional(self.file.as_ref().map(|file| Dynamic::from(file.display().to_string()))));
+        scope
+    }
+
+    fn env(&self, point: HookPoint) -> Vec<(&'static str, String)> {
+        let mut env = vec![("BATCHERBIRD_HOOK", point.name().to_string())];
+        if let Some(note) = self.note {
+            env.push(("BATCHERBIRD_NOTE", note.to_string()));
+            env.push(("BATCHERBIRD_NOTE_NAME", note_to_name(note)));
+        }
+        if let Some(velocity) = self.velocity {
+            env.push(("BATCHERBIRD_VELOCITY", velocity.to_string()));
+        }
+        if let Some(file) = &self.file {
+            env.push(("BATCHERBIRD_FILE", file.display().to_string()));
+        }
+        env
+    }
+}
+
+impl HookConfig {
+    pub fn is_empty(&self) -> bool {
+        self.pre_note.is_empty() && self.post_note.is_empty() && self.post_export.is_empty()
+    }
+
+    fn commands(&self, point: HookPoint) -> &[String] {
+        match point {
+            HookPoint::PreNote => &self.pre_note,
+            HookPoint::PostNote => &self.post_note,
+            HookPoint::PostExport => &self.post_export,
+        }
+    }
+
+    /// Run the commands and scripts for `point`. A failing hook is reported and skipped, or
+    /// ends the batch with an error when `abort_on_failure` is set.
+    pub fn run(&self, point: HookPoint, context: &HookContext) -> Result<()> {
+        for command in self.commands(point) {
+            info!("🪝 {} hook: {}", point.name(), command);
+            let result = if is_script(command) {
+                self.run_script(Path::new(command.trim()), point, context)
+            } else {
+                run_shell(command, context.env(point), self.timeout())
+            };
+            if let Err(reason) = result {
+                let message = format!("{} hook '{}' failed: {}", point.name(), command, reason);
+                if self.abort_on_failure {
+                    return Err(BatcherbirdError::Processing(message));
+                }
+                warn!("⚠️ {}", message);
+            }
+        }
+        Ok(())
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs_f32(self.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS).max(0.0))
+    }
+
+    fn run_script(&self, script: &Path, point: HookPoint, context: &HookContext) -> std::result::Result<(), String> {
+        let timeout = self.timeout();
+        let started = Instant::now();
+        let mut engine = Engine::new();
+        engine.on_progress(move |_| (started.elapsed() >= timeout).then_some(Dynamic::UNIT));
+        engine.on_print(|text| info!("🪝 {}", text));
+        let env = context.env(point);
+        engine.register_fn("shell", move |command: &str| {
+            run_shell(command, env.clone(), timeout.saturating_sub(started.elapsed())).is_ok()
+        });
+        engine.run_file_with_scope(&mut context.scope(point), script.to_path_buf()).map_err(|e| match *e {
+            EvalAltResult::ErrorTerminated(..) => format!("still running after {:.1}s, stopped", timeout.as_secs_f32()),
+            e => e.to_string(),
+        })
+    }
+}
+
+/// Hooks naming a `.rhai` file are scripts; anything else is a shell command
+fn is_script(command: &str) -> bool {
+    Path::new(command.trim()).extension().is_some_and(|extension| extension.eq_ignore_ascii_case("rhai"))
+}
+
+/// Run `command` in the shell, killing it once it outlives `timeout`
+fn run_shell(command: &str, env: Vec<(&'static str, String)>, timeout: Duration) -> std::result::Result<(), String> {
+    let mut child = shell(command)
+        .envs(env)
+        .stdin(Stdio::null())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    let started = Instant::now();
+    loop {
+        match child.try_wait().map_err(|e| e.to_string())? {
+            Some(status) if status.success() => return Ok(()),
+            Some(status) => return Err(format!("exited with {}", status)),
+            None if started.elapsed() >= timeout => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("still running after {:.1}s, killed", timeout.as_secs_f32()));
+            }
+            None => std::thread::sleep(Duration::from_millis(10)),
+        }
+    }
+}
+
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    }
+}
+
+fn note_to_name(note: u8) -> String {
+    let note_names = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+    let octave = (note / 12).saturating_sub(1);
+    format!("{}{}", note_names[(note % 12) as usize], octave)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hooks_see_note_and_file_and_report_failures() {
+        let dir = std::env::temp_dir().join("batcherbird_test_hooks");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("log.txt");
+        let file = dir.join("Pad_C4_60_vel100.wav");
+
+        let hooks = HookConfig {
+            post_export: vec![format!("echo \"$BATCHERBIRD_HOOK $BATCHERBIRD_NOTE_NAME $BATCHERBIRD_VELOCITY $BATCHERBIRD_FILE\" >> {}", log.display())],
+            pre_note: vec!["exit 3".to_string()],
+            post_note: vec!["sleep 5".to_string()],
+            timeout_secs: Some(0.2),
+            ..HookConfig::default()
+        };
+        hooks.run(HookPoint::PostExport, &HookContext::file(60, 100, &file)).unwrap();
+        let logged = std::fs::read_to_string(&log).unwrap();
+        assert_eq!(logged.trim(), format!("post_export C4 100 {}", file.display()));
+
+        // Failures only warn unless the batch should stop; hung commands are killed
+        hooks.run(HookPoint::PreNote, &HookContext::note(60, 100)).unwrap();
+        let strict = HookConfig { abort_on_failure: true, ..hooks };
+        assert!(strict.run(HookPoint::PreNote, &HookContext::note(60, 100)).unwrap_err().to_string().contains("exited with"));
+        let started = Instant::now();
+        assert!(strict.run(HookPoint::PostNote, &HookContext::note(60, 100)).is_err());
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_rhai_hooks_see_note_and_run_commands() {
+        let dir = std::env::temp_dir().join("batcherbird_test_rhai_hooks");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("log.txt");
+        let script = dir.join("tag.rhai");
+        std::fs::write(&script, format!(
+            "if velocity < 64 {{ return; }}\nif !shell(`echo \"${{hook}} ${{note_name}} ${{file}}\" >> {}`) {{ throw \"echo failed\"; }}",
+            log.display(),
+        )).unwrap();
+        let file = dir.join("Pad_C4_60_vel100.wav");
+
+        let hooks = HookConfig { post_export: vec![script.display().to_string()], abort_on_failure: true, ..HookConfig::default() };
+        hooks.run(HookPoint::PostExport, &HookContext::file(60, 100, &file)).unwrap();
+        hooks.run(HookPoint::PostExport, &HookContext::file(60, 40, &file)).unwrap();
+        let logged = std::fs::read_to_string(&log).unwrap();
+        assert_eq!(logged.trim(), format!("post_export C4 {}", file.display()));
+
+        // Throwing fails the hook, and runaway scripts are stopped
+        let failing = dir.join("fail.rhai");
+        std::fs::write(&failing, "throw \"no gear\";").unwrap();
+        let looping = dir.join("loop.rhai");
+        std::fs::write(&looping, "loop {}").unwrap();
+        let hooks = HookConfig {
+            pre_note: vec![failing.display().to_string()],
+            post_note: vec![looping.display().to_string()],
+            timeout_secs: Some(0.2),
+            abort_on_failure: true,
+            ..HookConfig::default()
+        };
+        assert!(hooks.run(HookPoint::PreNote, &HookContext::note(60, 100)).unwrap_err().to_string().contains("no gear"));
+        let started = Instant::now();
+        assert!(hooks.run(HookPoint::PostNote, &HookContext::note(60, 100)).unwrap_err().to_string().contains("stopped"));
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
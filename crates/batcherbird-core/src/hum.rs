@@ -0,0 +1,192 @@
+use crate::loudness::Biquad;
+use crate::sampler::Sample;
+use serde::{Deserialize, Serialize};
+
+/// Mains frequencies checked for hum (Hz)
+const MAINS_FREQUENCIES: [f32; 2] = [50.0, 60.0];
+/// Fundamental plus this many harmonics are measured and notched
+pub const HUM_HARMONICS: usize = 4;
+/// Analysis window (ms); long enough to tell 50 Hz from 60 Hz
+const WINDOW_MS: f32 = 200.0;
+/// Windows this far below a sample's loudest window are taken as its noise floor (dB)
+const NOISE_FLOOR_BELOW_PEAK_DB: f32 = 30.0;
+/// Share of the noise floor's power the hum lines must carry to count as hum
+const MIN_HUM_SHARE: f32 = 0.3;
+/// Hum quieter than this is inaudible in practice (dBFS)
+const MIN_HUM_DB: f32 = -90.0;
+/// Notch width: Q of 30 removes under 2 Hz around 50 Hz
+const NOTCH_Q: f64 = 30.0;
+
+/// Mains hum found in the noise floor of a session's takes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HumMeasurement {
+    /// 50 or 60 Hz
+    pub mains_hz: f32,
+    /// RMS level of the hum lines together (dBFS)
+    pub level_db: f32,
+    /// Level of the fundamental and each harmonic (dBFS)
+    pub harmonics_db: Vec<f32>,
+    /// RMS level of the noise floor the hum was measured in (dBFS)
+    pub noise_floor_db: f32,
+}
+
+impl HumMeasurement {
+    /// Look for hum in the quiet parts (lead-in, release tail, gaps) of every take. None when
+    /// there is no usable noise floor or nothing hum-like in it.
+    pub fn measure(samples: &[Sample]) -> Option<Self> {
+        // Noise floor power, and power per mains frequency and harmonic, summed over windows
+        let mut floor_power = 0.0f64;
+        let mut line_power = [[0.0f64; HUM_HARMONICS]; MAINS_FREQUENCIES.len()];
+        let mut windows = 0usize;
+
+        for sample in samples {
+            let channels = sample.channels.max(1) as usize;
+            let window = ((WINDOW_MS / 1000.0) * sample.sample_rate as f32) as usize;
+            if window == 0 {
+                continue;
+            }
+            let mono: Vec<f32> = sample.audio_data.chunks_exact(channels)
+                .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+                .collect();
+            let powers: Vec<f64> = mono.chunks_exact(window)
+                .map(|chunk| chunk.iter().map(|s| (*s as f64).powi(2)).sum::<f64>() / window as f64)
+                .collect();
+            let loudest = powers.iter().copied().fold(0.0f64, f64::max);
+            let floor_limit = loudest * 10f64.powf(-NOISE_FLOOR_BELOW_PEAK_DB as f64 / 10.0);
+
+            for (chunk, &power) in mono.chunks_exact(window).zip(&powers) {
+                if power <= 0.0 || power > floor_limit {
+                    continue;
+                }
+                floor_power += power;
+                windows += 1;
+                for (lines, mains_hz) in line_power.iter_mut().zip(MAINS_FREQUENCIES) {
+                    for (harmonic, line) in lines.iter_mut().enumerate() {
+                        let frequency = mains_hz * (harmonic + 1) as f32;
+                        if frequency < sample.sample_rate as f32 / 2.0 {
+                            *line += tone_power(chunk, frequency, sample.sample_rate);
+                        }
+                    }
+                }
+            }
+        }
+        if windows == 0 {
+            return None;
+        }
+
+        let (mains, lines) = line_power.iter().enumerate()
+            .max_by(|(_, a), (_, b)| a.iter().sum::<f64>().total_cmp(&b.iter().sum::<f64>()))?;
+        let hum_power = lines.iter().sum::<f64>();
+        if hum_power < floor_power * MIN_HUM_SHARE as f64 || to_db(hum_power / windows as f64) < MIN_HUM_DB {
+            return None;
+        }
+        Some(Self {
+            mains_hz: MAINS_FREQUENCIES[mains],
+            level_db: to_db(hum_power / windows as f64),
+            harmonics_db: lines.iter().map(|power| to_db(power / windows as f64)).collect(),
+            noise_floor_db: to_db(floor_power / windows as f64),
+        })
+    }
+}
+
+impl std::fmt::Display for HumMeasurement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.0} Hz mains hum at {:.1} dBFS in a {:.1} dBFS noise floor; check grounding and cables",
+            self.mains_hz, self.level_db, self.noise_floor_db)
+    }
+}
+
+/// Notch out `mains_hz` and its harmonics below Nyquist from interleaved audio, in place
+pub fn remove_hum(audio: &mut [f32], channels: u16, sample_rate: u32, mains_hz: f32) {
+    let channels = channels.max(1) as usize;
+    for channel in 0..channels {
+        let mut notches: Vec<Biquad> = (1..=HUM_HARMONICS)
+            .map(|harmonic| mains_hz as f64 * harmonic as f64)
+            .filter(|&frequency| frequency < sample_rate as f64 / 2.0)
+            .map(|frequency| notch(frequency, sample_rate))
+            .collect();
+        for value in audio.iter_mut().skip(channel).step_by(channels) {
+            *value = notches.iter_mut().fold(*value as f64, |value, notch| notch.process(value)) as f32;
+        }
+    }
+}
+
+/// RBJ cookbook notch
+fn notch(frequency: f64, sample_rate: u32) -> Biquad {
+    let w0 = 2.0 * std::f64::consts::PI * frequency / sample_rate as f64;
+    let alpha = w0.sin() / (2.0 * NOTCH_Q);
+    let a0 = 1.0 + alpha;
+    let cos = w0.cos();
+    Biquad::new([1.0 / a0, -2.0 * cos / a0, 1.0 / a0], [-2.0 * cos / a0, (1.0 - alpha) / a0])
+}
+
+/// Power of the sine at `frequency` in a Hann-windowed chunk (Goertzel)
+fn tone_power(chunk: &[f32], frequency: f32, sample_rate: u32) -> f64 {
+    let n = chunk.len() as f64;
+    let coefficient = 2.0 * (2.0 * std::f64::consts::PI * frequency as f64 / sample_rate as f64).cos();
+    let (mut previous, mut before) = (0.0f64, 0.0f64);
+    for (i, &value) in chunk.iter().enumerate() {
+        let window = 0.5 - 0.5 * (2.0 * std::f64::consts::PI * i as f64 / n).cos();
+        let current = value as f64 * window + coefficient * previous - before;
+        before = previous;
+        previous = current;
+    }
+    let magnitude_squared = previous * previous + before * before - coefficient * previous * before;
+    // A sine of amplitude A gives |X| = A*N/4 through a Hann window; its power is A²/2
+    let amplitude = 4.0 * magnitude_squared.max(0.0).sqrt() / n;
+    amplitude * amplitude / 2.0
+}
+
+fn to_db(power: f64) -> f32 {
+    if power > 0.0 { (10.0 * power.log10()).max(-120.0) as f32 } else { -120.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_and_removes_mains_hum() {
+        let rate = 48000;
+        let hum = |i: usize| {
+            let t = i as f32 / rate as f32;
+            0.003 * (2.0 * std::f32::consts::PI * 60.0 * t).sin() + 0.001 * (2.0 * std::f32::consts::PI * 180.0 * t).sin()
+        };
+        // A second of 440 Hz note, then a second of tail with only the hum under it
+        let audio_data: Vec<f32> = (0..rate as usize * 2)
+            .map(|i| {
+                let note = if i < rate as usize { 0.5 * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / rate as f32).sin() } else { 0.0 };
+                note + hum(i)
+            })
+            .collect();
+        let take = |audio_data: Vec<f32>| Sample {
+            note: 69,
+            velocity: 100,
+            audio_data,
+            sample_rate: rate,
+            channels: 1,
+            recorded_at: std::time::SystemTime::now(),
+            midi_timing: std::time::Duration::ZERO,
+            audio_timing: std::time::Duration::ZERO,
+            metadata: Default::default(),
+        };
+
+        let measurement = HumMeasurement::measure(&[take(audio_data.clone())]).unwrap();
+        assert_eq!(measurement.mains_hz, 60.0);
+        // 0.003 amplitude sine: 4.5e-6 power, -53.5 dBFS
+        assert!((measurement.harmonics_db[0] + 53.5).abs() < 1.0, "{:?}", measurement);
+        assert!(measurement.harmonics_db[2] > measurement.harmonics_db[1] + 20.0, "{:?}", measurement);
+
+        let mut cleaned = audio_data;
+        remove_hum(&mut cleaned, 1, rate, measurement.mains_hz);
+        // Once the notches settle the tail is close to silent, and the note keeps its level
+        let tail_peak = cleaned[rate as usize * 3 / 2..].iter().fold(0.0f32, |max, s| max.max(s.abs()));
+        assert!(tail_peak < 0.0003, "{}", tail_peak);
+        let note_peak = cleaned[rate as usize / 2..rate as usize].iter().fold(0.0f32, |max, s| max.max(s.abs()));
+        assert!((note_peak - 0.5).abs() < 0.01, "{}", note_peak);
+        assert_eq!(HumMeasurement::measure(&[take(cleaned)]), None);
+
+        // Clean silence has no noise floor to judge
+        assert_eq!(HumMeasurement::measure(&[take(vec![0.0; rate as usize])]), None);
+    }
+}
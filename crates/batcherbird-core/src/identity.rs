@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+
+/// Device ID 0x7F addresses every device on the port ("all call")
+pub const ALL_CALL_DEVICE_ID: u8 = 0x7F;
+
+/// Synth identity from a Universal SysEx Identity Reply (F0 7E <dev> 06 02 ...)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SynthIdentity {
+    /// Device ID the synth answered with
+    pub device_id: u8,
+    /// Manufacturer SysEx ID: one byte, or three bytes starting with 0x00
+    pub manufacturer_id: Vec<u8>,
+    /// Device family code (14-bit, LSB first on the wire)
+    pub family: u16,
+    /// Device family member / model code
+    pub model: u16,
+    /// Software revision bytes as sent by the device
+    pub version: [u8; 4],
+}
+
+impl SynthIdentity {
+    /// Build the Identity Request message for the given device ID (use ALL_CALL_DEVICE_ID when unknown)
+    pub fn request(device_id: u8) -> Vec<u8> {
+        vec![0xF0, 0x7E, device_id & 0x7F, 0x06, 0x01, 0xF7]
+    }
+
+    /// True if `message` looks like an Identity Reply
+    pub fn is_reply(message: &[u8]) -> bool {
+        message.len() >= 5 && message[0] == 0xF0 && message[1] == 0x7E && message[3] == 0x06 && message[4] == 0x02
+    }
+
+    /// Parse an Identity Reply. Returns None for anything else or a truncated reply.
+    pub fn parse_reply(message: &[u8]) -> Option<Self> {
+        if !Self::is_reply(message) {
+            return None;
+        }
+
+        let body = &message[5..];
+        let (manufacturer_id, rest) = match body.first()? {
+            0x00 => (body.get(..3)?.to_vec(), &body[3..]),
+            &id => (vec![id], &body[1..]),
+        };
+
+        // family (2) + model (2) + version (4)
+        let fields = rest.get(..8)?;
+        Some(Self {
+            device_id: message[2],
+            manufacturer_id,
+            family: fields[0] as u16 | (fields[1] as u16) << 7,
+            model: fields[2] as u16 | (fields[3] as u16) << 7,
+            version: [fields[4], fields[5], fields[6], fields[7]],
+        })
+    }
+
+    /// Manufacturer name, or the hex ID when it isn't in our table
+    pub fn manufacturer_name(&self) -> String {
+        let name = match self.manufacturer_id.as_slice() {
+            [0x01] => "Sequential",
+            [0x04] => "Moog",
+            [0x0F] => "Ensoniq",
+            [0x10] => "Oberheim",
+            [0x18] => "E-mu",
+            [0x33] => "Clavia",
+            [0x3E] => "Waldorf",
+            [0x40] => "Kawai",
+            [0x41] => "Roland",
+            [0x42] => "Korg",
+            [0x43] => "Yamaha",
+            [0x44] => "Casio",
+            [0x47] => "Akai",
+            [0x00, 0x00, 0x0E] => "Alesis",
+            [0x00, 0x20, 0x29] => "Novation",
+            [0x00, 0x20, 0x32] => "Behringer",
+            [0x00, 0x20, 0x33] => "Access",
+            [0x00, 0x20, 0x3C] => "Elektron",
+            [0x00, 0x20, 0x6B] => "Arturia",
+            [0x00, 0x20, 0x76] => "Teenage Engineering",
+            _ => "",
+        };
+
+        if name.is_empty() {
+            let hex: Vec<String> = self.manufacturer_id.iter().map(|b| format!("{:02X}", b)).collect();
+            format!("Manufacturer {}", hex.join(""))
+        } else {
+            name.to_string()
+        }
+    }
+
+    /// Human-readable name, e.g. "Korg 0013:0000 (v1.0.0.0)"
+    pub fn display_name(&self) -> String {
+        format!("{} {:04X}:{:04X} (v{}.{}.{}.{})",
+            self.manufacturer_name(), self.family, self.model,
+            self.version[0], self.version[1], self.version[2], self.version[3])
+    }
+
+    /// Session name derived from the identity, e.g. "Korg 0013-0000"
+    pub fn session_name(&self) -> String {
+        format!("{} {:04X}-{:04X}", self.manufacturer_name(), self.family, self.model)
+    }
+
+    /// Filesystem-safe folder name, e.g. "Korg_0013-0000"
+    pub fn folder_name(&self) -> String {
+        self.session_name()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_identity_reply() {
+        // Korg, one-byte manufacturer ID
+        let korg = [0xF0, 0x7E, 0x00, 0x06, 0x02, 0x42, 0x13, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0xF7];
+        let identity = SynthIdentity::parse_reply(&korg).unwrap();
+        assert_eq!(identity.manufacturer_name(), "Korg");
+        assert_eq!(identity.family, 0x13);
+        assert_eq!(identity.folder_name(), "Korg_0013-0000");
+
+        // Three-byte manufacturer ID (Arturia)
+        let arturia = [0xF0, 0x7E, 0x7F, 0x06, 0x02, 0x00, 0x20, 0x6B, 0x02, 0x00, 0x04, 0x00, 0x01, 0x02, 0x03, 0x04, 0xF7];
+        let identity = SynthIdentity::parse_reply(&arturia).unwrap();
+        assert_eq!(identity.manufacturer_name(), "Arturia");
+        assert_eq!(identity.model, 0x04);
+        assert_eq!(identity.version, [1, 2, 3, 4]);
+
+        // Truncated replies and other messages are rejected
+        assert!(SynthIdentity::parse_reply(&korg[..9]).is_none());
+        assert!(SynthIdentity::parse_reply(&SynthIdentity::request(ALL_CALL_DEVICE_ID)).is_none());
+    }
+}
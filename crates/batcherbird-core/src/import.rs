@@ -0,0 +1,516 @@
+//! Map folders of existing WAV/AIFF files to notes and velocities, so instrument files can be
+//! generated for libraries Batcherbird didn't record. Files are mapped from a session manifest,
+//! their names (read with naming patterns using the tokens of
+//! [`ExportConfig::naming_pattern`](crate::export::ExportConfig::naming_pattern)), their
+//! smpl/inst chunks, or, failing all of those, the pitch detector.
+
+use crate::aiff;
+use crate::edit::{self, LoopPoints};
+use crate::export::{ExportConfig, SampleExporter};
+use crate::library::{self, note_from_name};
+use crate::manifest::{SessionManifest, MANIFEST_FILENAME};
+use crate::naming::{NamingContext, NamingPattern};
+use crate::pitch;
+use crate::sampler::{CcLayer, Sample, SampleMetadata};
+use crate::{BatcherbirdError, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Audio files an import picks up
+pub const AUDIO_EXTENSIONS: &[&str] = &["wav", "aif", "aiff"];
+
+/// Patterns tried when none are configured, besides Batcherbird's own names
+pub const DEFAULT_PATTERNS: &[&str] = &[
+    "{session}_{note_name}_{note}_{velocity}.wav", // Roland-EM1014_C4_60_vel127.wav
+    "{session}_{note_name}_v{velocity}_rk{note}.wav", // Batcherbird_F4_v127_rk65.wav
+];
+
+#[derive(Debug, Clone)]
+pub struct ImportConfig {
+    /// Naming patterns to read file names with, tried in order. Each needs {note} or {note_name}.
+    pub patterns: Vec<String>,
+    /// Find the root note of files no pattern fits by listening to them
+    pub detect_pitch: bool,
+    /// Velocity of files whose name doesn't give one
+    pub default_velocity: u8,
+}
+
+impl Default for ImportConfig {
+    fn default() -> Self {
+        Self {
+            patterns: DEFAULT_PATTERNS.iter().map(|pattern| pattern.to_string()).collect(),
+            detect_pitch: true,
+            default_velocity: 127,
+        }
+    }
+}
+
+/// Where an imported file's note came from
+#[derive(Debug, Clone, PartialEq)]
+pub enum NoteSource {
+    /// The session manifest next to the files
+    Manifest,
+    /// The file name, read with the pattern at this index of `ImportConfig::patterns`
+    Pattern(usize),
+    /// The file's smpl/inst (WAV) or INST (AIFF) chunk
+    Chunk,
+    /// A Batcherbird file name with appended tokens, e.g. C4_60_vel127_rr2.wav
+    BatcherbirdName,
+    /// The pitch detector, at this fundamental
+    Pitch { frequency_hz: f32 },
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedSample {
+    pub path: PathBuf,
+    pub sample: Sample,
+    pub source: NoteSource,
+    /// Sustain loop stored in the file, in frames
+    pub loop_points: Option<LoopPoints>,
+}
+
+/// Audio files of a folder, mapped where possible
+#[derive(Debug, Clone)]
+pub struct ImportResult {
+    pub directory: PathBuf,
+    /// Mapped samples, sorted by file name
+    pub samples: Vec<ImportedSample>,
+    /// Files nothing could map: no manifest entry, name, chunk or (with pitch detection on) clear pitch
+    pub unrecognized: Vec<PathBuf>,
+}
+
+/// Mapping a sampler or editor stored in a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct SamplerChunks {
+    pub root_note: u8,
+    /// How far the recording sits from the root note (cents, positive is sharp)
+    pub tuning_cents: f32,
+    pub velocity_range: Option<(u8, u8)>,
+    pub loop_points: Option<LoopPoints>,
+}
+
+/// Presets written for one folder of an imported tree
+#[derive(Debug, Clone)]
+pub struct ImportedPreset {
+    /// Folder the samples were read from
+    pub source: PathBuf,
+    pub output_directory: PathBuf,
+    /// Sample WAVs, then the instrument file (and README when configured)
+    pub files: Vec<PathBuf>,
+    pub unrecognized: Vec<PathBuf>,
+}
+
+/// File name reader for a set of patterns
+#[derive(Debug, Clone)]
+pub struct FileNameParser {
+    patterns: Vec<NamingPattern>,
+    default_velocity: u8,
+}
+
+impl FileNameParser {
+    pub fn new(config: &ImportConfig) -> Result<Self> {
+        let patterns = config.patterns.iter()
+            .map(|pattern| {
+                let parsed = NamingPattern::parse(pattern)?;
+                if !parsed.contains("note") && !parsed.contains("note_name") {
+                    return Err(BatcherbirdError::Config(format!("Import pattern '{}' needs {{note}} or {{note_name}}", pattern)));
+                }
+                Ok(parsed)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { patterns, default_velocity: config.default_velocity.clamp(1, 127) })
+    }
+
+    /// Note, velocity and layer info from a file name, with the index of the pattern that fit.
+    /// AIFF names are also tried as .wav, as naming patterns are written for exports.
+    pub fn parse(&self, file_name: &str) -> Option<(usize, u8, u8, SampleMetadata)> {
+        let as_wav = is_aiff(Path::new(file_name))
+            .then(|| Path::new(file_name).with_extension("wav").to_string_lossy().to_string());
+        self.patterns.iter().enumerate().find_map(|(index, pattern)| {
+            let values = pattern.parse_name(file_name)
+                .or_else(|| pattern.parse_name(as_wav.as_deref()?))?;
+            let (note, velocity, metadata) = self.sample_fields(&values)?;
+            Some((index, note, velocity, metadata))
+        })
+    }
+
+    fn sample_fields(&self, values: &HashMap<&'static str, String>) -> Option<(u8, u8, SampleMetadata)> {
+        let number = |token: &str, prefix: &str| -> Option<Option<u8>> {
+            match values.get(token).filter(|value| !value.is_empty()) {
+                Some(value) => value.strip_prefix(prefix).unwrap_or(value).parse().ok().map(Some),
+                None => Some(None),
+            }
+        };
+
+        // The note number wins over the name, whose octave numbering varies between vendors
+        let note = match number("note", "")? {
+            Some(note) => note,
+            None => note_from_name(values.get("note_name")?)?,
+        };
+        if note > 127 {
+            return None;
+        }
+        let velocity = number("velocity", "vel")?.unwrap_or(self.default_velocity);
+        if !(1..=127).contains(&velocity) {
+            return None;
+        }
+
+        let articulation = values.get("articulation").map(String::as_str);
+        let metadata = SampleMetadata {
+            round_robin: number("rr", "rr")?,
+            program: number("patch", "prog")?,
+            midi_channel: number("channel", "ch")?.map(|channel| channel.saturating_sub(1)),
+            cc_layer: values.get("cc")
+                .and_then(|cc| cc.strip_prefix("cc")?.split_once('_'))
+                .and_then(|(controller, value)| Some(CcLayer { controller: controller.parse().ok()?, value: value.parse().ok()? })),
+            legato_from: values.get("legato").and_then(|legato| note_from_name(legato.strip_prefix("from_")?)),
+            release: values.get("release").is_some_and(|release| !release.is_empty()) || articulation == Some("release"),
+            input_source: values.get("input").filter(|input| !input.is_empty()).cloned(),
+            ..Default::default()
+        };
+        Some((note, velocity, metadata))
+    }
+}
+
+/// Map every WAV/AIFF in `directory`: from the session manifest when there is one, otherwise
+/// from the file name, the file's sampler chunks, or (with `detect_pitch`) the pitch detector.
+/// Subfolders are left out; see [`import_tree`].
+pub fn import_directory(directory: &Path, config: &ImportConfig) -> Result<ImportResult> {
+    if !directory.is_dir() {
+        return Err(BatcherbirdError::Session(format!("Not a folder: {}", directory.display())));
+    }
+    let parser = FileNameParser::new(config)?;
+
+    let manifest_path = directory.join(MANIFEST_FILENAME);
+    let manifest_entries: HashMap<String, (u8, u8, SampleMetadata)> = if manifest_path.exists() {
+        SessionManifest::load(&manifest_path)?.samples.into_iter()
+            .map(|entry| (entry.file, (entry.note, entry.velocity, entry.metadata)))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+
+    let mut samples = Vec::new();
+    let mut unrecognized = Vec::new();
+    for path in list_audio_files(directory)? {
+        let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let chunks = read_sampler_chunks(&path).unwrap_or_else(|e| {
+            warn!("   ⚠️ Ignoring sampler chunks of {}: {}", file_name, e);
+            None
+        });
+        // Names before chunks: plenty of editors write a unity note of 60 whatever the pitch
+        let mapped = manifest_entries.get(&file_name).cloned().map(|info| (NoteSource::Manifest, info))
+            .or_else(|| parser.parse(&file_name).map(|(index, note, velocity, metadata)| (NoteSource::Pattern(index), (note, velocity, metadata))))
+            .or_else(|| chunks.as_ref().map(|chunks| (NoteSource::Chunk, chunk_fields(chunks, parser.default_velocity))))
+            .or_else(|| library::parse_sample_filename(&file_name).map(|info| (NoteSource::BatcherbirdName, info)));
+        if mapped.is_none() && !config.detect_pitch {
+            unrecognized.push(path);
+            continue;
+        }
+
+        let (audio_data, spec) = read_audio(&path)?;
+        let (source, (note, velocity, metadata)) = match mapped {
+            Some(mapped) => mapped,
+            None => match pitch::detect_pitch(&audio_data, spec.channels, spec.sample_rate) {
+                Some(estimate) => {
+                    info!("🎵 Inferred root note {} ({:.1} Hz) for: {}", estimate.note, estimate.frequency_hz, file_name);
+                    let metadata = SampleMetadata { tuning_cents: Some(estimate.cents), ..Default::default() };
+                    (NoteSource::Pitch { frequency_hz: estimate.frequency_hz }, (estimate.note, parser.default_velocity, metadata))
+                }
+                None => {
+                    unrecognized.push(path);
+                    continue;
+                }
+            },
+        };
+
+        let frames = audio_data.len() / spec.channels.max(1) as usize;
+        let recorded_at = std::fs::metadata(&path)
+            .and_then(|meta| meta.modified())
+            .unwrap_or_else(|_| std::time::SystemTime::now());
+        samples.push(ImportedSample {
+            path,
+            sample: Sample {
+                note,
+                velocity,
+                audio_data,
+                sample_rate: spec.sample_rate,
+                channels: spec.channels,
+                recorded_at,
+                midi_timing: Duration::ZERO,
+                audio_timing: Duration::from_secs_f64(frames as f64 / spec.sample_rate.max(1) as f64),
+                metadata,
+            },
+            source,
+            loop_points: chunks.and_then(|chunks| chunks.loop_points).filter(|points| points.end <= frames),
+        });
+    }
+
+    info!("📂 {}: mapped {} samples ({} unrecognized files)", directory.display(), samples.len(), unrecognized.len());
+    Ok(ImportResult { directory: directory.to_path_buf(), samples, unrecognized })
+}
+
+/// [`import_directory`] for `root` and every folder below it holding audio files, in path
+/// order. Hidden folders are skipped, as are folders under `exclude` (e.g. the output folder).
+pub fn import_tree(root: &Path, config: &ImportConfig, exclude: Option<&Path>) -> Result<Vec<ImportResult>> {
+    let mut results = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(directory) = pending.pop() {
+        if exclude.is_some_and(|exclude| directory != root && directory.starts_with(exclude)) {
+            continue;
+        }
+        let mut subfolders: Vec<PathBuf> = std::fs::read_dir(&directory)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_dir() && !path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.')))
+            .collect();
+        // Reversed, so folders pop off the stack in name order
+        subfolders.sort_by(|a, b| b.cmp(a));
+        pending.extend(subfolders);
+
+        if !list_audio_files(&directory)?.is_empty() {
+            results.push(import_directory(&directory, config)?);
+        }
+    }
+    Ok(results)
+}
+
+/// Import every folder under `root` and write each as a preset into the matching folder under
+/// `output_dir`, with `export`'s format and processing. Samples are written as WAVs named by
+/// their mapping, keeping loops stored in the source files; takes sharing a note and velocity
+/// become round robins. Folders where nothing could be mapped are skipped.
+pub fn import_library(root: &Path, output_dir: &Path, config: &ImportConfig, export: &ExportConfig) -> Result<Vec<ImportedPreset>> {
+    let mut presets = Vec::new();
+    for mut result in import_tree(root, config, Some(output_dir))? {
+        if result.samples.is_empty() {
+            continue;
+        }
+        let relative = result.directory.strip_prefix(root).unwrap_or(Path::new("")).to_path_buf();
+        let folder_name = result.directory.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "Imported".to_string());
+        let output_directory = output_dir.join(relative);
+        let files = export_imported(&mut result.samples, ExportConfig {
+            output_directory: output_directory.clone(),
+            naming: NamingContext { session: Some(folder_name), ..export.naming.clone() },
+            ..export.clone()
+        })?;
+        presets.push(ImportedPreset { source: result.directory, output_directory, files, unrecognized: result.unrecognized });
+    }
+    Ok(presets)
+}
+
+/// Write imported samples with `config` and generate the instrument file, manifest and report
+/// for them. Returns the sample files followed by the instrument file.
+pub fn export_imported(samples: &mut [ImportedSample], config: ExportConfig) -> Result<Vec<PathBuf>> {
+    number_round_robins(samples);
+    let exporter = SampleExporter::new(config)?;
+    let batch: Vec<Sample> = samples.iter().map(|imported| imported.sample.clone()).collect();
+    exporter.check_names(&batch)?;
+
+    let mut files = Vec::new();
+    for imported in samples.iter() {
+        let file = exporter.export_sample(&imported.sample)?;
+        // Loop detection may have stored its own loop already
+        if let (Some(points), Ok(None)) = (imported.loop_points, edit::read_loop_points(&file)) {
+            edit::append_smpl_chunk(&file, points, imported.sample.sample_rate, imported.sample.note)?;
+        }
+        files.push(file);
+    }
+    exporter.export_additional_outputs(&batch, &files)?;
+    let files = exporter.finish_batch(&batch, files)?;
+
+    let directory = &exporter.config().output_directory;
+    let mut manifest = SessionManifest::new(exporter.instrument_name());
+    for (sample, file) in batch.iter().zip(&files) {
+        manifest.add_sample(sample, file);
+    }
+    let readme_count = exporter.config().readme.is_some() as usize;
+    for file in &files[batch.len()..files.len() - readme_count] {
+        manifest.add_instrument_file(file);
+    }
+    manifest.save(directory)?;
+    manifest.write_report(directory)?;
+    Ok(files)
+}
+
+/// Number takes that landed in the same slot (e.g. several files mapped by pitch) as round
+/// robins, in file order, so they neither collide nor replace each other
+fn number_round_robins(samples: &mut [ImportedSample]) {
+    let slot = |sample: &Sample| {
+        let metadata = &sample.metadata;
+        (sample.note, sample.velocity, metadata.cc_layer, metadata.program, metadata.legato_from,
+            metadata.release, metadata.midi_channel, metadata.input_source.clone())
+    };
+    let mut counts: HashMap<_, u8> = HashMap::new();
+    for imported in samples.iter().filter(|imported| imported.sample.metadata.round_robin.is_none()) {
+        *counts.entry(slot(&imported.sample)).or_default() += 1;
+    }
+    let mut taken: HashMap<_, u8> = HashMap::new();
+    for imported in samples.iter_mut().filter(|imported| imported.sample.metadata.round_robin.is_none()) {
+        let key = slot(&imported.sample);
+        if counts.get(&key).is_some_and(|&count| count > 1) {
+            let round_robin = taken.entry(key).or_default();
+            *round_robin += 1;
+            imported.sample.metadata.round_robin = Some(*round_robin);
+        }
+    }
+}
+
+fn chunk_fields(chunks: &SamplerChunks, default_velocity: u8) -> (u8, u8, SampleMetadata) {
+    let velocity = chunks.velocity_range.map_or(default_velocity, |(_, high)| high);
+    let metadata = SampleMetadata {
+        tuning_cents: (chunks.tuning_cents != 0.0).then_some(chunks.tuning_cents),
+        ..Default::default()
+    };
+    (chunks.root_note, velocity, metadata)
+}
+
+/// Audio files directly inside `directory`, sorted by name
+pub fn list_audio_files(directory: &Path) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| {
+            AUDIO_EXTENSIONS.iter().any(|audio| ext.eq_ignore_ascii_case(audio))
+        }))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn is_aiff(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("aif") || ext.eq_ignore_ascii_case("aiff"))
+}
+
+fn read_audio(path: &Path) -> Result<(Vec<f32>, hound::WavSpec)> {
+    if is_aiff(path) {
+        aiff::read_aiff(path)
+    } else {
+        library::read_wav(path)
+    }
+}
+
+/// Root note, tuning, velocity range and sustain loop stored in a WAV's smpl/inst chunks or
+/// an AIFF's INST chunk, if it has any
+pub fn read_sampler_chunks(path: &Path) -> Result<Option<SamplerChunks>> {
+    let bytes = std::fs::read(path)?;
+    if let Some(chunks) = aiff::chunks(&bytes) {
+        return Ok(aiff::sampler_chunks(&chunks));
+    }
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(BatcherbirdError::Processing(format!("{} is not a WAV or AIFF file", path.display())));
+    }
+    let read_u32 = |body: &[u8], at: usize| body.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+
+    let (mut smpl, mut inst) = (None, None);
+    let mut offset = 12;
+    while let (Some(id), Some(size)) = (bytes.get(offset..offset + 4), read_u32(&bytes, offset + 4)) {
+        let body = offset + 8;
+        let chunk = &bytes[body.min(bytes.len())..(body + size as usize).min(bytes.len())];
+        match id {
+            b"smpl" => smpl = Some(chunk),
+            b"inst" => inst = Some(chunk),
+            _ => {}
+        }
+        // Chunks are padded to an even length
+        offset = body + size as usize + (size as usize & 1);
+    }
+
+    // inst: unshifted note, fine tune (cents to add on playback), gain, key range, velocity range
+    if let Some(inst) = inst.filter(|inst| inst.len() >= 7) {
+        let loop_points = smpl.and_then(smpl_loop);
+        return Ok(Some(SamplerChunks {
+            root_note: inst[0].min(127),
+            tuning_cents: -(inst[1] as i8 as f32),
+            velocity_range: Some((inst[5].clamp(1, 127), inst[6].clamp(1, 127))),
+            loop_points,
+        }));
+    }
+    // smpl: unity note at 12, then the pitch fraction above it as a fraction of 2^32
+    Ok(smpl.and_then(|smpl| {
+        let unity_note = read_u32(smpl, 12)?;
+        let fraction = read_u32(smpl, 16)?;
+        Some(SamplerChunks {
+            root_note: unity_note.min(127) as u8,
+            tuning_cents: (fraction as f64 / 4_294_967_296.0 * 100.0) as f32,
+            velocity_range: None,
+            loop_points: smpl_loop(smpl),
+        })
+    }))
+}
+
+/// First loop of a smpl chunk; its end is stored inclusive
+fn smpl_loop(smpl: &[u8]) -> Option<LoopPoints> {
+    let read_u32 = |at: usize| smpl.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+    if read_u32(28)? == 0 {
+        return None;
+    }
+    let (start, end) = (read_u32(44)? as usize, read_u32(48)? as usize + 1);
+    (start < end).then_some(LoopPoints { start, end })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_file_names() {
+        let parser = FileNameParser::new(&ImportConfig::default()).unwrap();
+        let (index, note, velocity, _) = parser.parse("Roland-EM1014_C4_60_vel127.wav").unwrap();
+        assert_eq!((index, note, velocity), (0, 60, 127));
+        let (index, note, velocity, _) = parser.parse("Batcherbird_F4_v100_rk65.wav").unwrap();
+        assert_eq!((index, note, velocity), (1, 65, 100));
+        assert!(parser.parse("kick.wav").is_none());
+        assert_eq!(parser.parse("Roland-EM1014_A2_45_vel064.aif").unwrap().1, 45);
+
+        let config = ImportConfig {
+            patterns: vec!["{synth} {note_name} {articulation} {rr}.wav".to_string()],
+            default_velocity: 90,
+            ..ImportConfig::default()
+        };
+        let parser = FileNameParser::new(&config).unwrap();
+        let (_, note, velocity, metadata) = parser.parse("Old Strings Eb3 release rr2.wav").unwrap();
+        assert_eq!((note, velocity), (51, 90));
+        assert!(metadata.release);
+        assert_eq!(metadata.round_robin, Some(2));
+
+        let no_note = ImportConfig { patterns: vec!["{synth}_{velocity}.wav".to_string()], ..ImportConfig::default() };
+        assert!(FileNameParser::new(&no_note).is_err());
+    }
+
+    #[test]
+    fn test_wav_sampler_chunks_and_round_robins() {
+        let path = std::env::temp_dir().join(format!("batcherbird_import_{}.wav", std::process::id()));
+        let spec = hound::WavSpec { channels: 1, sample_rate: 48000, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+        library::write_wav(&path, &[0.0; 480], spec).unwrap();
+        assert_eq!(read_sampler_chunks(&path).unwrap(), None);
+
+        edit::append_smpl_chunk(&path, LoopPoints { start: 100, end: 400 }, 48000, 50).unwrap();
+        let chunks = read_sampler_chunks(&path).unwrap().unwrap();
+        assert_eq!((chunks.root_note, chunks.velocity_range), (50, None));
+        assert_eq!(chunks.loop_points, Some(LoopPoints { start: 100, end: 400 }));
+        std::fs::remove_file(&path).ok();
+
+        let imported = |note| ImportedSample {
+            path: PathBuf::new(),
+            sample: Sample {
+                note,
+                velocity: 127,
+                audio_data: Vec::new(),
+                sample_rate: 48000,
+                channels: 1,
+                recorded_at: std::time::SystemTime::now(),
+                midi_timing: Duration::ZERO,
+                audio_timing: Duration::ZERO,
+                metadata: SampleMetadata::default(),
+            },
+            source: NoteSource::Chunk,
+            loop_points: None,
+        };
+        let mut samples = vec![imported(60), imported(62), imported(60)];
+        number_round_robins(&mut samples);
+        let round_robins: Vec<Option<u8>> = samples.iter().map(|s| s.sample.metadata.round_robin).collect();
+        assert_eq!(round_robins, vec![Some(1), None, Some(2)]);
+    }
+}
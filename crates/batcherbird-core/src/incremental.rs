@@ -0,0 +1,129 @@
+//! Export each take while the rest of a range is still recording, so a crash or a pulled
+//! cable mid-batch leaves every take captured so far on disk. A worker thread writes the
+//! WAVs and rewrites the manifest after each one; the instrument file, QA report and README
+//! follow once recording is done.
+
+use crate::export::{ExportConfig, SampleExporter};
+use crate::manifest::SessionManifest;
+use crate::sampler::Sample;
+use crate::{BatcherbirdError, Result};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use tracing::{error, info};
+
+/// What an incremental export wrote
+#[derive(Debug, Clone)]
+pub struct IncrementalOutput {
+    /// Takes in capture order
+    pub samples: Vec<Sample>,
+    /// Sample WAVs in the same order, followed by the instrument and README files
+    pub files: Vec<PathBuf>,
+}
+
+/// Takes exported by the worker so far, with the files they went to
+type Written = Vec<(Sample, PathBuf)>;
+
+/// An export running alongside a recording. Queue takes with [`push`](Self::push) and call
+/// [`finish`](Self::finish) when recording is done; dropping it instead still writes every
+/// queued take and the manifest, but no instrument file.
+pub struct IncrementalExport {
+    exporter: Arc<SampleExporter>,
+    sender: Option<mpsc::Sender<Sample>>,
+    worker: Option<JoinHandle<Result<Written>>>,
+}
+
+impl IncrementalExport {
+    /// Create the output folder, write an empty manifest and start the export worker
+    pub fn start(config: ExportConfig) -> Result<Self> {
+        Self::start_observed(config, |_, _| {})
+    }
+
+    /// [`start`](Self::start), calling `on_exported` on the worker thread after each take
+    /// is written
+    pub fn start_observed<F>(config: ExportConfig, on_exported: F) -> Result<Self>
+    where
+        F: Fn(&Sample, &Path) + Send + 'static,
+    {
+        let exporter = Arc::new(SampleExporter::new(config)?);
+        let directory = exporter.config().output_directory.clone();
+        let mut manifest = SessionManifest::new(exporter.instrument_name());
+        manifest.save(&directory)?;
+
+        let (sender, receiver) = mpsc::channel::<Sample>();
+        let worker_exporter = Arc::clone(&exporter);
+        let worker = std::thread::spawn(move || {
+            let mut written = Vec::new();
+            for sample in receiver {
+                let file = worker_exporter.export_sample(&sample)?;
+                manifest.add_sample(&sample, &file);
+                manifest.record_normalization_gains(&worker_exporter.normalization_gains());
+                manifest.save(&directory)?;
+                on_exported(&sample, &file);
+                written.push((sample, file));
+            }
+            Ok(written)
+        });
+        info!("💾 Exporting takes as they are recorded to: {}", exporter.config().output_directory.display());
+
+        Ok(Self { exporter, sender: Some(sender), worker: Some(worker) })
+    }
+
+    /// Queue a captured take. Fails once the worker has stopped on an export error, so the
+    /// recording can stop instead of capturing takes that can't be written.
+    pub fn push(&self, sample: Sample) -> Result<()> {
+        self.sender.as_ref()
+            .and_then(|sender| sender.send(sample).ok())
+            .ok_or_else(|| BatcherbirdError::Session("Export worker stopped after an export error".to_string()))
+    }
+
+    /// Wait for the queued takes, then write the batch's QA report, instrument file and README
+    pub fn finish(mut self) -> Result<IncrementalOutput> {
+        let written = self.join()?;
+        let (samples, files): (Vec<Sample>, Vec<PathBuf>) = written.into_iter().unzip();
+        if samples.is_empty() {
+            return Ok(IncrementalOutput { samples, files });
+        }
+
+        self.exporter.export_additional_outputs(&samples, &files)?;
+        let files = self.exporter.finish_batch(&samples, files)?;
+
+        let directory = &self.exporter.config().output_directory;
+        let mut manifest = SessionManifest::new(self.exporter.instrument_name());
+        for (sample, file) in samples.iter().zip(&files) {
+            manifest.add_sample(sample, file);
+        }
+        // finish_batch appends the instrument file, then the README when one is configured
+        let readme_count = self.exporter.config().readme.is_some() as usize;
+        for file in &files[samples.len()..files.len() - readme_count] {
+            manifest.add_instrument_file(file);
+        }
+        manifest.record_normalization_gains(&self.exporter.normalization_gains());
+        manifest.save(directory)?;
+        manifest.write_report(directory)?;
+
+        Ok(IncrementalOutput { samples, files })
+    }
+
+    fn join(&mut self) -> Result<Written> {
+        // Closing the channel ends the worker once the queue is drained
+        self.sender.take();
+        match self.worker.take() {
+            Some(worker) => worker.join()
+                .map_err(|_| BatcherbirdError::Session("Export worker panicked".to_string()))?,
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+impl Drop for IncrementalExport {
+    fn drop(&mut self) {
+        match self.join() {
+            Ok(written) if !written.is_empty() => {
+                info!("💾 Recording stopped early; {} exported takes are listed in the manifest", written.len());
+            }
+            Ok(_) => {}
+            Err(e) => error!("❌ Incremental export failed: {}", e),
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! Instrument file formats written next to the exported WAVs. SFZ and Decent Sampler are
+//! built in; other formats implement [`InstrumentExporter`] in their own module (or crate)
+//! and are made available to `AudioFormat::Custom` by name with [`register`].
+
+use crate::{BatcherbirdError, Result};
+use crate::export::SampleExporter;
+use crate::sampler::Sample;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock, RwLock};
+
+pub trait InstrumentExporter: Send + Sync {
+    /// Registry key, also the format name the CLI and GUI accept (e.g. "sfz")
+    fn name(&self) -> &str;
+
+    /// Name shown in READMEs and logs
+    fn display_name(&self) -> &str {
+        self.name()
+    }
+
+    /// Extensions of the files this format writes, the instrument file's first
+    fn extensions(&self) -> &[&str];
+
+    /// Write the instrument file mapping `samples`, whose WAVs `exporter` wrote to `wav_files`
+    /// (same order). Returns the instrument file's path. Paths should go through
+    /// `exporter.resolve_output_path` so the export's overwrite policy applies.
+    fn write_samples(&self, exporter: &SampleExporter, samples: &[Sample], wav_files: &[PathBuf]) -> Result<PathBuf>;
+
+    /// README paragraph on loading the instrument
+    fn install_instructions(&self, instrument_name: &str) -> String {
+        format!(
+            "Keep this folder together and load {}.{} in a sampler that reads {} files.",
+            instrument_name,
+            self.extensions().first().copied().unwrap_or("*"),
+            self.display_name()
+        )
+    }
+}
+
+pub struct SfzFormat;
+
+impl InstrumentExporter for SfzFormat {
+    fn name(&self) -> &str {
+        "sfz"
+    }
+
+    fn display_name(&self) -> &str {
+        "SFZ"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sfz"]
+    }
+
+    fn write_samples(&self, exporter: &SampleExporter, samples: &[Sample], wav_files: &[PathBuf]) -> Result<PathBuf> {
+        exporter.generate_sfz_file(samples, wav_files)
+    }
+
+    fn install_instructions(&self, instrument_name: &str) -> String {
+        format!(
+            "Keep this folder together and load {}.sfz in any SFZ player (e.g. sforzando, Sfizz or TAL-Sampler).",
+            instrument_name
+        )
+    }
+}
+
+pub struct DecentSamplerFormat;
+
+impl InstrumentExporter for DecentSamplerFormat {
+    fn name(&self) -> &str {
+        "decentsampler"
+    }
+
+    fn display_name(&self) -> &str {
+        "Decent Sampler"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["dspreset"]
+    }
+
+    fn write_samples(&self, exporter: &SampleExporter, samples: &[Sample], wav_files: &[PathBuf]) -> Result<PathBuf> {
+        exporter.generate_dspreset_file(samples, wav_files)
+    }
+
+    fn install_instructions(&self, instrument_name: &str) -> String {
+        format!(
+            "Keep this folder together and open {}.dspreset in Decent Sampler (File > Load Sample Library).",
+            instrument_name
+        )
+    }
+}
+
+fn registry() -> &'static RwLock<Vec<Arc<dyn InstrumentExporter>>> {
+    static REGISTRY: OnceLock<RwLock<Vec<Arc<dyn InstrumentExporter>>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| RwLock::new(vec![Arc::new(SfzFormat), Arc::new(DecentSamplerFormat)]))
+}
+
+/// Make a format available by its name, replacing any registered under the same name
+pub fn register(exporter: Arc<dyn InstrumentExporter>) -> Result<()> {
+    let name = exporter.name().to_lowercase();
+    if name.is_empty() || name == "wav" {
+        return Err(BatcherbirdError::Config(format!("'{}' can't be used as an instrument format name", exporter.name())));
+    }
+    let mut exporters = registry().write().unwrap();
+    match exporters.iter_mut().find(|existing| existing.name().to_lowercase() == name) {
+        Some(slot) => *slot = exporter,
+        None => exporters.push(exporter),
+    }
+    Ok(())
+}
+
+/// Registered format by name (case-insensitive)
+pub fn find(name: &str) -> Option<Arc<dyn InstrumentExporter>> {
+    registry().read().unwrap().iter()
+        .find(|exporter| exporter.name().eq_ignore_ascii_case(name))
+        .cloned()
+}
+
+/// Names of every registered format, built-ins first
+pub fn names() -> Vec<String> {
+    registry().read().unwrap().iter().map(|exporter| exporter.name().to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct ListFormat;
+
+    impl InstrumentExporter for ListFormat {
+        fn name(&self) -> &str {
+            "list"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["txt"]
+        }
+
+        fn write_samples(&self, _exporter: &SampleExporter, _samples: &[Sample], wav_files: &[PathBuf]) -> Result<PathBuf> {
+            Ok(wav_files[0].with_extension("txt"))
+        }
+    }
+
+    #[test]
+    fn test_registry_resolves_builtin_and_custom_formats() {
+        assert_eq!(find("SFZ").unwrap().extensions(), &["sfz"]);
+        assert_eq!(find("decentsampler").unwrap().display_name(), "Decent Sampler");
+        assert!(find("list").is_none());
+
+        register(Arc::new(ListFormat)).unwrap();
+        let list = find("list").unwrap();
+        assert_eq!(list.install_instructions("Pad"), "Keep this folder together and load Pad.txt in a sampler that reads list files.");
+        assert!(names().starts_with(&["sfz".to_string(), "decentsampler".to_string()]));
+        assert!(names().contains(&"list".to_string()));
+    }
+}
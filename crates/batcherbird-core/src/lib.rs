@@ -1,6 +1,7 @@
 //! Batcherbird Core Library
 //! 
 //! Core library for hardware synthesizer sampling automation.
+//! To embed the whole capture-and-export pipeline, start with [`BatcherbirdSession`].
 
 pub mod error;
 pub mod midi;
@@ -10,11 +11,67 @@ pub mod session;
 pub mod config;
 pub mod sampler;
 pub mod export;
+pub mod instrument_format;
 pub mod detection;
 pub mod loop_detection;
+pub mod output_location;
+pub mod manifest;
+pub mod demo;
+pub mod velocity_curve;
+pub mod identity;
+pub mod profiles;
+pub mod drum_consistency;
+pub mod midi_file;
+pub mod drum_map;
+pub mod readme;
+pub mod midi_clock;
+pub mod library;
+pub mod renormalize;
+pub mod playback;
+pub mod trigger;
+pub mod analysis;
+pub mod pitch;
+pub mod waveform;
+pub mod qa;
+pub mod edit;
+pub mod reprocess;
+pub mod sds;
+pub mod hardware_target;
+pub mod watchdog;
+pub mod rerecord;
+pub mod takes;
+pub mod envelope;
+pub mod pipeline;
+pub mod wavetable;
+pub mod slicing;
+pub mod alignment;
+pub mod loudness;
+pub mod hum;
+pub mod metering;
+pub mod passthrough;
+pub mod dual_input;
+pub mod controller;
+pub mod hooks;
+pub mod catalog;
+pub mod package;
+pub mod plan;
+pub mod preflight;
+pub mod incremental;
+pub mod naming;
+pub mod import;
+pub mod aiff;
+pub mod dither;
+pub mod cancel;
+pub mod queue;
+pub mod file_manager;
+pub mod multi_device;
+pub mod midi_monitor;
+#[cfg(feature = "image")]
+pub mod preview;
 
 pub use error::{BatcherbirdError, Result};
-pub use sampler::{AudioLevels, LevelMeterState};
+pub use sampler::{AudioLevels, LevelMeterState, PeakHoldConfig};
+pub use pipeline::BatcherbirdSession;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,330 @@
+use crate::{Result, BatcherbirdError};
+use crate::manifest::{SessionManifest, MANIFEST_FILENAME};
+use crate::export::FolderLayout;
+use crate::sampler::{CcLayer, Sample, SampleMetadata};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::info;
+
+/// A sample read back from an exported session folder
+#[derive(Debug, Clone)]
+pub struct LibrarySample {
+    pub path: PathBuf,
+    pub sample: Sample,
+}
+
+/// Samples found in one exported session folder
+#[derive(Debug, Clone)]
+pub struct LibrarySession {
+    pub directory: PathBuf,
+    pub samples: Vec<LibrarySample>,
+    /// WAV files whose note/velocity could not be determined
+    pub unrecognized: Vec<PathBuf>,
+}
+
+/// A sample dropped because a later session recorded the same slot
+#[derive(Debug, Clone)]
+pub struct MergeDuplicate {
+    pub note: u8,
+    pub velocity: u8,
+    pub kept: PathBuf,
+    pub dropped: PathBuf,
+}
+
+/// Result of combining several sessions into one instrument
+#[derive(Debug, Clone)]
+pub struct MergeResult {
+    pub samples: Vec<LibrarySample>,
+    pub duplicates: Vec<MergeDuplicate>,
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+/// Identity of a sample slot in an instrument; two samples with the same key would collide
+type SlotKey = (u8, u8, Option<CcLayer>, Option<u8>, Option<u8>, Option<u8>, bool);
+
+impl LibrarySession {
+    /// Read every WAV in `directory`. Note/velocity/layer info comes from the session
+    /// manifest when there is one, otherwise from Batcherbird file names (C4_60_vel127.wav).
+    pub fn load(directory: &Path) -> Result<Self> {
+        if !directory.is_dir() {
+            return Err(BatcherbirdError::Session(format!("Not a session folder: {}", directory.display())));
+        }
+
+        let manifest_path = directory.join(MANIFEST_FILENAME);
+        let manifest_entries: HashMap<String, (u8, u8, SampleMetadata)> = if manifest_path.exists() {
+            SessionManifest::load(&manifest_path)?.samples.into_iter()
+                .map(|entry| (entry.file, (entry.note, entry.velocity, entry.metadata)))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        let mut samples = Vec::new();
+        let mut unrecognized = Vec::new();
+        for path in list_wav_files(directory)? {
+            let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+            let info = manifest_entries.get(&file_name).cloned()
+                .or_else(|| parse_sample_filename(&file_name));
+
+            match info {
+                Some((note, velocity, metadata)) => {
+                    let (audio_data, spec) = read_wav(&path)?;
+                    let (sample_rate, channels) = (spec.sample_rate, spec.channels);
+                    let frames = audio_data.len() / channels.max(1) as usize;
+                    let recorded_at = std::fs::metadata(&path)
+                        .and_then(|meta| meta.modified())
+                        .unwrap_or_else(|_| std::time::SystemTime::now());
+                    samples.push(LibrarySample {
+                        path,
+                        sample: Sample {
+                            note,
+                            velocity,
+                            audio_data,
+                            sample_rate,
+                            channels,
+                            recorded_at,
+                            midi_timing: Duration::ZERO,
+                            audio_timing: Duration::from_secs_f64(frames as f64 / sample_rate.max(1) as f64),
+                            metadata,
+                        },
+                    });
+                }
+                None => unrecognized.push(path),
+            }
+        }
+
+        info!("📂 {}: {} samples ({} unrecognized files)", directory.display(), samples.len(), unrecognized.len());
+        Ok(Self {
+            directory: directory.to_path_buf(),
+            samples,
+            unrecognized,
+        })
+    }
+}
+
+/// Combine sessions in order. Sample rate and channel count must match across all of them;
+/// when two sessions hold the same note/velocity/layer slot, the later session wins.
+pub fn merge_sessions(sessions: &[LibrarySession]) -> Result<MergeResult> {
+    let first = sessions.iter()
+        .flat_map(|session| session.samples.first())
+        .next()
+        .ok_or_else(|| BatcherbirdError::Session("No samples found in the sessions to merge".to_string()))?;
+    let (sample_rate, channels) = (first.sample.sample_rate, first.sample.channels);
+
+    let mismatched: Vec<String> = sessions.iter()
+        .flat_map(|session| &session.samples)
+        .filter(|s| s.sample.sample_rate != sample_rate || s.sample.channels != channels)
+        .map(|s| format!("{} ({} Hz, {} ch)", s.path.display(), s.sample.sample_rate, s.sample.channels))
+        .collect();
+    if !mismatched.is_empty() {
+        return Err(BatcherbirdError::Session(format!(
+            "Sessions must share one format ({} Hz, {} ch); mismatched: {}",
+            sample_rate, channels, mismatched.join(", ")
+        )));
+    }
+
+    let mut slots: HashMap<SlotKey, usize> = HashMap::new();
+    let mut samples: Vec<LibrarySample> = Vec::new();
+    let mut duplicates = Vec::new();
+    for sample in sessions.iter().flat_map(|session| &session.samples) {
+        let metadata = &sample.sample.metadata;
+        let key = (
+            sample.sample.note,
+            sample.sample.velocity,
+            metadata.cc_layer,
+            metadata.program,
+            metadata.round_robin,
+            metadata.legato_from,
+            metadata.release,
+        );
+        match slots.get(&key) {
+            Some(&index) => {
+                duplicates.push(MergeDuplicate {
+                    note: sample.sample.note,
+                    velocity: sample.sample.velocity,
+                    kept: sample.path.clone(),
+                    dropped: samples[index].path.clone(),
+                });
+                samples[index] = sample.clone();
+            }
+            None => {
+                slots.insert(key, samples.len());
+                samples.push(sample.clone());
+            }
+        }
+    }
+
+    samples.sort_by_key(|s| (s.sample.note, s.sample.velocity));
+    Ok(MergeResult {
+        samples,
+        duplicates,
+        sample_rate,
+        channels,
+    })
+}
+
+/// WAV files of a session folder, sorted by path: directly inside `directory`, plus those in
+/// the samples/ or per-velocity folders an export layout puts them in
+pub fn list_wav_files(directory: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in std::fs::read_dir(directory)?.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+        let sample_folder = path.file_name().is_some_and(|name| FolderLayout::is_sample_folder(&name.to_string_lossy()));
+        if path.is_dir() && sample_folder {
+            files.extend(std::fs::read_dir(&path)?.filter_map(|entry| entry.ok().map(|entry| entry.path())).filter(|path| is_wav(path)));
+        } else if is_wav(&path) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn is_wav(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("wav"))
+}
+
+/// Read a WAV as interleaved f32 in -1.0..1.0 together with its format
+pub fn read_wav(path: &Path) -> Result<(Vec<f32>, hound::WavSpec)> {
+    let mut reader = hound::WavReader::open(path)
+        .map_err(|e| BatcherbirdError::Processing(format!("Failed to read {}: {}", path.display(), e)))?;
+    let spec = reader.spec();
+
+    let audio: std::result::Result<Vec<f32>, hound::Error> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect(),
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader.samples::<i32>().map(|s| s.map(|s| s as f32 / scale)).collect()
+        }
+    };
+    let audio = audio
+        .map_err(|e| BatcherbirdError::Processing(format!("Failed to decode {}: {}", path.display(), e)))?;
+
+    Ok((audio, spec))
+}
+
+/// Write interleaved f32 audio in the given format (e.g. the spec it was read with),
+/// clamping to full scale for integer formats
+pub fn write_wav(path: &Path, audio: &[f32], spec: hound::WavSpec) -> Result<()> {
+    let to_error = |e: hound::Error| BatcherbirdError::Processing(format!("Failed to write {}: {}", path.display(), e));
+    let mut writer = hound::WavWriter::create(path, spec).map_err(to_error)?;
+
+    match spec.sample_format {
+        hound::SampleFormat::Float => {
+            for &sample in audio {
+                writer.write_sample(sample).map_err(to_error)?;
+            }
+        }
+        hound::SampleFormat::Int => {
+            let max = ((1i64 << (spec.bits_per_sample - 1)) - 1) as f32;
+            for &sample in audio {
+                writer.write_sample((sample.clamp(-1.0, 1.0) * max) as i32).map_err(to_error)?;
+            }
+        }
+    }
+    writer.finalize().map_err(to_error)
+}
+
+/// Recover note, velocity and layer tokens from an exported file name, e.g.
+/// "Pad_C4_60_vel127_cc74_064_rr2.wav". The note number directly before the
+/// `velNNN` token is authoritative; note names are only used for legato sources.
+pub fn parse_sample_filename(file_name: &str) -> Option<(u8, u8, SampleMetadata)> {
+    let stem = file_name.rsplit_once('.').map_or(file_name, |(stem, _)| stem);
+    let tokens: Vec<&str> = stem.split('_').collect();
+
+    let vel_index = tokens.iter().position(|token| {
+        token.strip_prefix("vel").is_some_and(|v| v.parse::<u8>().is_ok())
+    })?;
+    let note: u8 = tokens.get(vel_index.checked_sub(1)?)?.parse().ok().filter(|&n| n <= 127)?;
+    let velocity: u8 = tokens[vel_index][3..].parse().ok().filter(|&v| (1..=127).contains(&v))?;
+
+    let mut metadata = SampleMetadata::default();
+    let mut rest = tokens[vel_index + 1..].iter().peekable();
+    while let Some(token) = rest.next() {
+        if let Some(take) = token.strip_prefix("rr").and_then(|t| t.parse().ok()) {
+            metadata.round_robin = Some(take);
+        } else if let Some(controller) = token.strip_prefix("cc").and_then(|t| t.parse().ok()) {
+            if let Some(value) = rest.peek().and_then(|t| t.parse().ok()) {
+                rest.next();
+                metadata.cc_layer = Some(CcLayer { controller, value });
+            }
+        } else if *token == "rel" {
+            metadata.release = true;
+        } else if *token == "from" {
+            metadata.legato_from = rest.next().and_then(|name| note_from_name(name));
+        }
+    }
+
+    Some((note, velocity, metadata))
+}
+
+/// "C4" / "F#2" / "Eb3" -> MIDI note number (C4 = 60)
+pub(crate) fn note_from_name(name: &str) -> Option<u8> {
+    let note_names = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+    let split = name.find(|c: char| c.is_ascii_digit() || c == '-')?;
+    let (pitch, octave) = name.split_at(split);
+    let (pitch, flat) = match pitch.strip_suffix('b') {
+        Some(natural) => (natural, 1),
+        None => (pitch, 0),
+    };
+    let pitch = note_names.iter().position(|&n| n == pitch)? as i32 - flat;
+    let octave: i32 = octave.parse().ok()?;
+    u8::try_from((octave + 1) * 12 + pitch).ok().filter(|&n| n <= 127)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn library_sample(path: &str, note: u8, velocity: u8, sample_rate: u32) -> LibrarySample {
+        LibrarySample {
+            path: PathBuf::from(path),
+            sample: Sample {
+                note,
+                velocity,
+                audio_data: vec![0.1; 8],
+                sample_rate,
+                channels: 1,
+                recorded_at: std::time::SystemTime::now(),
+                midi_timing: Duration::ZERO,
+                audio_timing: Duration::ZERO,
+                metadata: SampleMetadata::default(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_merge_sessions_deduplicates_and_validates() {
+        let (note, velocity, metadata) = parse_sample_filename("Pad_D4_62_vel100_cc74_064_rr2_from_C4.wav").unwrap();
+        assert_eq!((note, velocity), (62, 100));
+        assert_eq!(metadata.cc_layer, Some(CcLayer { controller: 74, value: 64 }));
+        assert_eq!(metadata.round_robin, Some(2));
+        assert_eq!(metadata.legato_from, Some(60));
+        assert!(parse_sample_filename("C2_36_vel064_rel.wav").unwrap().2.release);
+        assert!(parse_sample_filename("notes.wav").is_none());
+
+        let day_one = LibrarySession {
+            directory: PathBuf::from("day1"),
+            samples: vec![library_sample("day1/a.wav", 60, 64, 48000), library_sample("day1/b.wav", 62, 64, 48000)],
+            unrecognized: Vec::new(),
+        };
+        let day_two = LibrarySession {
+            directory: PathBuf::from("day2"),
+            samples: vec![library_sample("day2/a.wav", 60, 64, 48000), library_sample("day2/c.wav", 60, 127, 48000)],
+            unrecognized: Vec::new(),
+        };
+
+        let merged = merge_sessions(&[day_one.clone(), day_two]).unwrap();
+        assert_eq!(merged.samples.len(), 3);
+        assert_eq!(merged.duplicates.len(), 1);
+        assert_eq!(merged.duplicates[0].kept, PathBuf::from("day2/a.wav"));
+
+        let other_rate = LibrarySession {
+            directory: PathBuf::from("day3"),
+            samples: vec![library_sample("day3/a.wav", 64, 64, 44100)],
+            unrecognized: Vec::new(),
+        };
+        assert!(merge_sessions(&[day_one, other_rate]).is_err());
+    }
+}
@@ -1,4 +1,9 @@
 use crate::Result;
+use crate::pitch;
+use tracing::{info, warn};
+
+/// FFT size for comparing the timbre at both ends of a loop
+const SPECTRUM_SIZE: usize = 2048;
 
 /// Loop detection configuration
 #[derive(Debug, Clone)]
@@ -11,8 +16,14 @@ pub struct LoopDetectionConfig {
     pub max_candidates: usize,
     /// Correlation threshold for matching waveforms (0.0-1.0)
     pub correlation_threshold: f32,
-    /// Crossfade length in milliseconds
+    /// Crossfade length in milliseconds; long (multi-second) fades suit evolving pads and
+    /// are shortened to the material available before the loop start
     pub crossfade_ms: f32,
+    /// Gain curve of the crossfade
+    pub crossfade_shape: CrossfadeShape,
+    /// Use the detected fundamental to make loops whole cycles long, starting and ending
+    /// on the same cycle phase (falls back to plain zero crossings for unpitched audio)
+    pub pitch_aware: bool,
 }
 
 impl Default for LoopDetectionConfig {
@@ -23,6 +34,38 @@ impl Default for LoopDetectionConfig {
             max_candidates: 20,         // Test up to 20 candidates
             correlation_threshold: 0.8,  // 80% correlation required
             crossfade_ms: 10.0,         // 10ms crossfade
+            crossfade_shape: CrossfadeShape::Linear,
+            pitch_aware: true,
+        }
+    }
+}
+
+/// Gain curve used to blend the loop tail into the material before the loop start
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CrossfadeShape {
+    /// Gains sum to 1: right for well-correlated material
+    #[default]
+    Linear,
+    /// Powers sum to 1 (sine/cosine): keeps the level steady when the two sides differ
+    EqualPower,
+    /// Half-cosine S-curve: linear sum, but with gentle starts and ends
+    RaisedCosine,
+}
+
+impl CrossfadeShape {
+    /// (fade out, fade in) gains at `position` 0.0-1.0 through the crossfade
+    pub fn gains(self, position: f32) -> (f32, f32) {
+        let position = position.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => (1.0 - position, position),
+            Self::EqualPower => {
+                let angle = position * std::f32::consts::FRAC_PI_2;
+                (angle.cos(), angle.sin())
+            }
+            Self::RaisedCosine => {
+                let fade_in = 0.5 - 0.5 * (position * std::f32::consts::PI).cos();
+                (1.0 - fade_in, fade_in)
+            }
         }
     }
 }
@@ -42,6 +85,10 @@ pub struct LoopCandidate {
     pub zero_crossing_aligned: bool,
     /// Correlation between start and end regions
     pub correlation: f32,
+    /// Whole pitch cycles spanned by the loop (pitch-aware candidates only)
+    pub cycles: Option<u32>,
+    /// Match between the magnitude spectra at the loop start and end (0.0-1.0)
+    pub spectral_similarity: f32,
 }
 
 /// Result of loop detection process
@@ -70,8 +117,8 @@ impl LoopDetector {
 
     /// Detect loop points in the given audio sample
     pub fn detect_loop_points(&self, audio_data: &[f32], sample_rate: u32) -> LoopDetectionResult {
-        println!("🔄 Starting loop detection...");
-        println!("   Audio length: {} samples ({:.2}s)", 
+        info!("🔄 Starting loop detection...");
+        info!("   Audio length: {} samples ({:.2}s)", 
                 audio_data.len(), 
                 audio_data.len() as f32 / sample_rate as f32);
 
@@ -86,10 +133,20 @@ impl LoopDetector {
             };
         }
 
-        println!("   Found {} zero crossings", zero_crossings.len());
+        info!("   Found {} zero crossings", zero_crossings.len());
 
-        // Step 2: Generate loop candidates
-        let candidates = self.generate_loop_candidates(&zero_crossings, audio_data, sample_rate);
+        // Step 2: Generate loop candidates, whole cycles long when the pitch is known
+        let period = self.config.pitch_aware
+            .then(|| pitch::estimate_frequency(audio_data, sample_rate))
+            .flatten()
+            .map(|frequency| sample_rate as f32 / frequency);
+        let candidates = match period {
+            Some(period) => {
+                info!("   Fundamental period: {:.1} samples", period);
+                self.generate_period_candidates(audio_data, sample_rate, period)
+            }
+            None => self.generate_loop_candidates(&zero_crossings, audio_data, sample_rate),
+        };
         if candidates.is_empty() {
             return LoopDetectionResult {
                 success: false,
@@ -99,13 +156,13 @@ impl LoopDetector {
             };
         }
 
-        println!("   Generated {} loop candidates", candidates.len());
+        info!("   Generated {} loop candidates", candidates.len());
 
         // Step 3: Evaluate and rank candidates
         let mut evaluated_candidates = self.evaluate_candidates(&candidates, audio_data);
         evaluated_candidates.sort_by(|a, b| b.quality_score.partial_cmp(&a.quality_score).unwrap());
 
-        println!("   Best candidate quality: {:.3}", 
+        info!("   Best candidate quality: {:.3}", 
                 evaluated_candidates.first().map(|c| c.quality_score).unwrap_or(0.0));
 
         // Step 4: Return results
@@ -167,6 +224,8 @@ impl LoopDetector {
                         quality_score: 0.0, // Will be calculated later
                         zero_crossing_aligned: true, // By definition
                         correlation: 0.0, // Will be calculated later
+                        cycles: None,
+                        spectral_similarity: 0.0, // Will be calculated later
                     });
                 }
                 
@@ -184,6 +243,57 @@ impl LoopDetector {
         candidates
     }
 
+    /// Generate candidates that start on a rising zero crossing and end on the rising crossing
+    /// nearest a whole number of pitch periods later, so both ends sit at the same cycle phase
+    fn generate_period_candidates(&self, audio_data: &[f32], sample_rate: u32, period: f32) -> Vec<LoopCandidate> {
+        let rising: Vec<usize> = (1..audio_data.len())
+            .filter(|&i| audio_data[i - 1] <= 0.0 && audio_data[i] > 0.0)
+            .collect();
+        let min_samples = self.config.min_loop_length_sec * sample_rate as f32;
+        let max_samples = (self.config.max_loop_length_sec * sample_rate as f32).min(audio_data.len() as f32);
+        let min_cycles = (min_samples / period).ceil().max(1.0) as u32;
+        let max_cycles = (max_samples / period).floor() as u32;
+        if rising.is_empty() || max_cycles < min_cycles {
+            return Vec::new();
+        }
+
+        // Starts spread over the body of the sound, past the attack
+        let body: Vec<usize> = rising.iter().copied()
+            .filter(|&i| i >= audio_data.len() / 5 && i <= audio_data.len() * 3 / 5)
+            .collect();
+        let starts: Vec<usize> = if body.is_empty() { vec![rising[0]] } else {
+            let count = body.len().min(4);
+            (0..count).map(|n| body[n * body.len() / count]).collect()
+        };
+        let per_start = (self.config.max_candidates / starts.len()).max(1) as u32;
+        let cycle_step = ((max_cycles - min_cycles) / per_start).max(1);
+
+        let mut candidates = Vec::new();
+        for &start in &starts {
+            for cycles in (min_cycles..=max_cycles).step_by(cycle_step as usize).take(per_start as usize) {
+                let target = start as f32 + cycles as f32 * period;
+                // Nearest rising crossing, if it is within a quarter cycle of the target
+                let Some(&end) = rising.iter()
+                    .min_by(|&&a, &&b| (a as f32 - target).abs().total_cmp(&(b as f32 - target).abs()))
+                    .filter(|&&end| (end as f32 - target).abs() <= period / 4.0 && end > start)
+                else {
+                    continue;
+                };
+                candidates.push(LoopCandidate {
+                    start_sample: start,
+                    end_sample: end,
+                    length_samples: end - start,
+                    quality_score: 0.0, // Will be calculated later
+                    zero_crossing_aligned: true,
+                    correlation: 0.0, // Will be calculated later
+                    cycles: Some(cycles),
+                    spectral_similarity: 0.0, // Will be calculated later
+                });
+            }
+        }
+        candidates
+    }
+
     /// Evaluate the quality of loop candidates
     fn evaluate_candidates(
         &self,
@@ -200,6 +310,13 @@ impl LoopDetector {
                 candidate.end_sample
             );
             
+            // Compare timbre too, so evolving sounds loop where they sound the same
+            evaluated.spectral_similarity = self.calculate_spectral_similarity(
+                audio_data,
+                candidate.start_sample,
+                candidate.end_sample
+            );
+            
             // Calculate overall quality score
             evaluated.quality_score = self.calculate_quality_score(&evaluated);
             
@@ -234,6 +351,31 @@ impl LoopDetector {
         self.normalized_cross_correlation(start_window, end_window)
     }
 
+    /// Compare the magnitude spectra of the audio just after the loop start and just before
+    /// the loop end: 1.0 for identical timbre, falling towards 0.0 as they differ
+    fn calculate_spectral_similarity(
+        &self,
+        audio_data: &[f32],
+        start_sample: usize,
+        end_sample: usize
+    ) -> f32 {
+        let after_start = &audio_data[start_sample.min(audio_data.len())..(start_sample + SPECTRUM_SIZE).min(audio_data.len())];
+        let before_end = &audio_data[end_sample.saturating_sub(SPECTRUM_SIZE)..end_sample.min(audio_data.len())];
+        if after_start.len() < 64 || before_end.len() < 64 {
+            return 0.0;
+        }
+        
+        let start_spectrum = magnitude_spectrum(after_start);
+        let end_spectrum = magnitude_spectrum(before_end);
+        let difference: f32 = start_spectrum.iter().zip(&end_spectrum).map(|(a, b)| (a - b).abs()).sum();
+        let total: f32 = start_spectrum.iter().chain(&end_spectrum).sum();
+        if total > 0.0 {
+            (1.0 - difference / total).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
     /// Calculate normalized cross-correlation between two audio windows
     fn normalized_cross_correlation(&self, window1: &[f32], window2: &[f32]) -> f32 {
         let len = window1.len().min(window2.len());
@@ -271,8 +413,11 @@ impl LoopDetector {
     fn calculate_quality_score(&self, candidate: &LoopCandidate) -> f32 {
         let mut score = 0.0;
         
-        // Correlation contributes 70% of score
-        score += candidate.correlation * 0.7;
+        // Waveform correlation contributes 50% of score
+        score += candidate.correlation * 0.5;
+        
+        // Spectral (timbre) match contributes 20% of score
+        score += candidate.spectral_similarity * 0.2;
         
         // Zero crossing alignment contributes 20% of score
         if candidate.zero_crossing_aligned {
@@ -288,36 +433,88 @@ impl LoopDetector {
         score.clamp(0.0, 1.0)
     }
 
-    /// Apply the detected loop to audio data with crossfading
+    /// Apply the detected loop to audio data with crossfading. The end of the loop is faded
+    /// into the audio leading up to the loop start, so jumping from the loop end back to the
+    /// start continues exactly where that lead-in would have gone.
     pub fn apply_loop_with_crossfade(
         &self,
         audio_data: &mut [f32],
         loop_candidate: &LoopCandidate,
         sample_rate: u32
     ) -> Result<()> {
-        let crossfade_samples = (self.config.crossfade_ms * sample_rate as f32 / 1000.0) as usize;
+        let start = loop_candidate.start_sample;
+        let end = loop_candidate.end_sample.min(audio_data.len());
+        let requested = (self.config.crossfade_ms.max(0.0) * sample_rate as f32 / 1000.0) as usize;
         
-        if crossfade_samples == 0 || crossfade_samples >= loop_candidate.length_samples / 2 {
+        // Limited by the pre-loop material and by the loop itself
+        let crossfade_samples = requested.min(start).min(end.saturating_sub(start) / 2);
+        if crossfade_samples == 0 || end <= start {
             return Ok(()); // Skip crossfade if not applicable
         }
+        if crossfade_samples < requested {
+            warn!("   ⚠️ Crossfade shortened to {:.1}ms (only {:.1}ms before the loop start)",
+                crossfade_samples as f32 * 1000.0 / sample_rate as f32, start as f32 * 1000.0 / sample_rate as f32);
+        }
         
-        let start = loop_candidate.start_sample;
-        let end = loop_candidate.end_sample;
-        
-        // Apply linear crossfade
+        let tail = end - crossfade_samples;
+        let lead_in = start - crossfade_samples;
         for i in 0..crossfade_samples {
-            if start + i < audio_data.len() && end - crossfade_samples + i < audio_data.len() {
-                let fade_ratio = i as f32 / crossfade_samples as f32;
-                let start_value = audio_data[start + i] * (1.0 - fade_ratio);
-                let end_value = audio_data[end - crossfade_samples + i] * fade_ratio;
-                audio_data[start + i] = start_value + end_value;
-            }
+            let (fade_out, fade_in) = self.config.crossfade_shape.gains(i as f32 / crossfade_samples as f32);
+            audio_data[tail + i] = audio_data[tail + i] * fade_out + audio_data[lead_in + i] * fade_in;
         }
         
         Ok(())
     }
 }
 
+/// Hann-windowed magnitude spectrum of up to SPECTRUM_SIZE samples (zero padded)
+fn magnitude_spectrum(window: &[f32]) -> Vec<f32> {
+    let len = window.len().min(SPECTRUM_SIZE);
+    let mut re = vec![0.0f32; SPECTRUM_SIZE];
+    let mut im = vec![0.0f32; SPECTRUM_SIZE];
+    for (i, &sample) in window[..len].iter().enumerate() {
+        let hann = 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / len as f32).cos();
+        re[i] = sample * hann;
+    }
+    fft(&mut re, &mut im);
+    re.iter().zip(&im).take(SPECTRUM_SIZE / 2).map(|(r, i)| (r * r + i * i).sqrt()).collect()
+}
+
+/// In-place iterative radix-2 FFT; the length must be a power of two
+pub(crate) fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    
+    let mut size = 2;
+    while size <= n {
+        let angle = -2.0 * std::f32::consts::PI / size as f32;
+        for start in (0..n).step_by(size) {
+            for k in 0..size / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + size / 2);
+                let (t_re, t_im) = (re[b] * cos - im[b] * sin, re[b] * sin + im[b] * cos);
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        size <<= 1;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -332,6 +529,91 @@ mod tests {
         assert!(!crossings.is_empty());
     }
 
+    #[test]
+    fn test_pitch_aware_loops_span_whole_cycles() {
+        // 55 Hz with a harmonic: the period (801.8 samples) isn't a whole number of samples
+        let rate = 44100;
+        let frequency = 55.0;
+        let audio: Vec<f32> = (0..rate * 3)
+            .map(|i| {
+                let phase = 2.0 * std::f32::consts::PI * frequency * i as f32 / rate as f32;
+                0.6 * phase.sin() + 0.3 * (2.0 * phase + 0.5).sin()
+            })
+            .collect();
+        let detector = LoopDetector::new(LoopDetectionConfig::default());
+        let result = detector.detect_loop_points(&audio, rate);
+        let best = result.best_candidate.unwrap();
+
+        let period = rate as f32 / frequency;
+        let cycles = best.length_samples as f32 / period;
+        assert!(result.success);
+        assert_eq!(best.cycles, Some(cycles.round() as u32));
+        assert!((cycles - cycles.round()).abs() < 0.01, "{} cycles", cycles);
+        assert!(audio[best.start_sample - 1] <= 0.0 && audio[best.start_sample] > 0.0);
+        assert!(audio[best.end_sample - 1] <= 0.0 && audio[best.end_sample] > 0.0);
+        assert!(best.correlation > 0.99);
+    }
+
+    #[test]
+    fn test_spectral_similarity_tracks_timbre() {
+        // A pad whose second harmonic fades in over four seconds
+        let rate = 44100;
+        let audio: Vec<f32> = (0..rate * 4)
+            .map(|i| {
+                let t = i as f32 / rate as f32;
+                let phase = 2.0 * std::f32::consts::PI * 220.0 * t;
+                0.5 * phase.sin() + 0.4 * (t / 4.0) * (2.0 * phase).sin()
+            })
+            .collect();
+        let detector = LoopDetector::new(LoopDetectionConfig::default());
+
+        let spectrum = magnitude_spectrum(&audio[..SPECTRUM_SIZE]);
+        let peak_bin = (0..spectrum.len()).max_by(|&a, &b| spectrum[a].total_cmp(&spectrum[b])).unwrap();
+        assert_eq!(peak_bin, (220.0 * SPECTRUM_SIZE as f32 / rate as f32).round() as usize);
+
+        let close = detector.calculate_spectral_similarity(&audio, 88200, 92610);
+        let far = detector.calculate_spectral_similarity(&audio, 4410, 167580);
+        assert!(close > 0.95, "{}", close);
+        assert!(far < close - 0.1, "{} vs {}", far, close);
+    }
+
+    #[test]
+    fn test_crossfade_blends_tail_into_lead_in() {
+        // Equal power keeps the summed power at 1; the S-curve and linear keep the gain sum at 1
+        for position in [0.0, 0.25, 0.5, 0.9] {
+            let (out, into) = CrossfadeShape::EqualPower.gains(position);
+            assert!((out * out + into * into - 1.0).abs() < 1e-5);
+            for shape in [CrossfadeShape::Linear, CrossfadeShape::RaisedCosine] {
+                let (out, into) = shape.gains(position);
+                assert!((out + into - 1.0).abs() < 1e-5);
+            }
+        }
+
+        // Lead-in is 1.0, loop is 0.0: the tail should rise to the lead-in level
+        let rate = 1000;
+        let mut audio = vec![1.0f32; 500];
+        audio.extend(vec![0.0f32; 1500]);
+        let candidate = LoopCandidate {
+            start_sample: 500,
+            end_sample: 2000,
+            length_samples: 1500,
+            quality_score: 1.0,
+            zero_crossing_aligned: true,
+            correlation: 1.0,
+            cycles: None,
+            spectral_similarity: 1.0,
+        };
+        let config = LoopDetectionConfig { crossfade_ms: 2000.0, crossfade_shape: CrossfadeShape::RaisedCosine, ..Default::default() };
+        LoopDetector::new(config).apply_loop_with_crossfade(&mut audio, &candidate, rate).unwrap();
+
+        // 2s requested, but only 500 samples precede the loop
+        assert_eq!(audio[1499], 0.0);
+        assert!(audio[1510] < 0.01);
+        assert!((audio[1750] - 0.5).abs() < 0.01);
+        assert!(audio[1999] > 0.99);
+        assert!(audio[..1500].iter().enumerate().all(|(i, &s)| s == if i < 500 { 1.0 } else { 0.0 }));
+    }
+
     #[test]
     fn test_correlation_calculation() {
         let detector = LoopDetector::new(LoopDetectionConfig::default());
@@ -0,0 +1,216 @@
+//! ITU-R BS.1770 loudness (K-weighted, gated integrated LUFS) and 4x oversampled true peak.
+
+use serde::{Deserialize, Serialize};
+
+/// Gating block length and hop (seconds): 400ms blocks with 75% overlap
+const BLOCK_SECS: f32 = 0.4;
+const HOP_SECS: f32 = 0.1;
+/// Blocks quieter than this never count (LUFS)
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+/// Blocks this far below the ungated loudness are dropped (LU)
+const RELATIVE_GATE_LU: f64 = 10.0;
+/// Oversampling factor and interpolation half-length (input samples) for true peak
+const OVERSAMPLING: usize = 4;
+const TRUE_PEAK_TAPS: i32 = 12;
+
+/// Loudness figures for one take
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LoudnessMeasurement {
+    /// Gated integrated loudness (None for silence)
+    pub integrated_lufs: Option<f32>,
+    /// Highest inter-sample peak (dBTP)
+    pub true_peak_db: f32,
+    /// Highest sample value (dBFS)
+    pub sample_peak_db: f32,
+}
+
+impl LoudnessMeasurement {
+    pub fn measure(audio: &[f32], channels: u16, sample_rate: u32) -> Self {
+        let sample_peak = audio.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+        Self {
+            integrated_lufs: integrated_loudness(audio, channels, sample_rate),
+            true_peak_db: to_db(true_peak(audio, channels)),
+            sample_peak_db: to_db(sample_peak),
+        }
+    }
+
+    /// Gain that brings the take to `target_lufs`, reduced if needed so the true peak stays
+    /// at or under `ceiling_db`. None for silence.
+    pub fn gain_to_target(&self, target_lufs: f32, ceiling_db: f32) -> Option<f32> {
+        let gain = target_lufs - self.integrated_lufs?;
+        Some(gain.min(ceiling_db - self.true_peak_db))
+    }
+}
+
+/// Integrated loudness of interleaved audio in LUFS. Takes shorter than one gating block
+/// are measured as a single block. None when everything falls under the absolute gate.
+pub fn integrated_loudness(audio: &[f32], channels: u16, sample_rate: u32) -> Option<f32> {
+    let channels = channels.max(1) as usize;
+    let frames = audio.len() / channels;
+    if frames == 0 || sample_rate == 0 {
+        return None;
+    }
+
+    // K-weighted power of every frame, summed over channels (all weighted 1.0; mono and
+    // stereo takes have no surround channels to weight up)
+    let mut power = vec![0.0f64; frames];
+    for channel in 0..channels {
+        let mut filter = KWeighting::new(sample_rate);
+        for (frame, value) in audio.iter().skip(channel).step_by(channels).enumerate() {
+            let weighted = filter.process(*value as f64);
+            power[frame] += weighted * weighted;
+        }
+    }
+
+    let block = ((BLOCK_SECS * sample_rate as f32) as usize).clamp(1, frames);
+    let hop = ((HOP_SECS * sample_rate as f32) as usize).max(1);
+    let blocks: Vec<f64> = (0..=frames - block)
+        .step_by(hop)
+        .map(|start| power[start..start + block].iter().sum::<f64>() / block as f64)
+        .collect();
+    let loudness = |mean_square: f64| -0.691 + 10.0 * mean_square.log10();
+    let gated_mean = |threshold: f64| -> Option<f64> {
+        let kept: Vec<f64> = blocks.iter().copied().filter(|&z| z > 0.0 && loudness(z) > threshold).collect();
+        (!kept.is_empty()).then(|| kept.iter().sum::<f64>() / kept.len() as f64)
+    };
+
+    let ungated = gated_mean(ABSOLUTE_GATE_LUFS)?;
+    let integrated = gated_mean(loudness(ungated) - RELATIVE_GATE_LU)?;
+    Some(loudness(integrated) as f32)
+}
+
+/// Highest absolute value of interleaved audio after 4x windowed-sinc oversampling (linear)
+pub fn true_peak(audio: &[f32], channels: u16) -> f32 {
+    let channels = channels.max(1) as usize;
+    // Interpolation weights per phase, for input offsets -(TAPS-1)..=TAPS around each sample
+    let phases: Vec<Vec<f32>> = (0..OVERSAMPLING)
+        .map(|phase| {
+            (1 - TRUE_PEAK_TAPS..=TRUE_PEAK_TAPS)
+                .map(|offset| {
+                    let x = phase as f32 / OVERSAMPLING as f32 - offset as f32;
+                    let window = 0.5 * (1.0 + (std::f32::consts::PI * x / TRUE_PEAK_TAPS as f32).cos());
+                    sinc(x) * window
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut peak = 0.0f32;
+    for channel in 0..channels {
+        let samples: Vec<f32> = audio.iter().skip(channel).step_by(channels).copied().collect();
+        for index in 0..samples.len() {
+            peak = peak.max(samples[index].abs());
+            for weights in &phases[1..] {
+                let value: f32 = weights.iter().zip(1 - TRUE_PEAK_TAPS..)
+                    .filter_map(|(weight, offset)| {
+                        let at = index as i64 + offset as i64;
+                        (at >= 0).then(|| samples.get(at as usize).map(|s| s * weight)).flatten()
+                    })
+                    .sum();
+                peak = peak.max(value.abs());
+            }
+        }
+    }
+    peak
+}
+
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-6 {
+        1.0
+    } else {
+        let pi_x = std::f32::consts::PI * x;
+        pi_x.sin() / pi_x
+    }
+}
+
+fn to_db(level: f32) -> f32 {
+    if level > 0.0 { 20.0 * level.log10() } else { -96.0 }
+}
+
+/// BS.1770 K-weighting: a high shelf for the head's acoustic effect, then the RLB high-pass.
+/// Coefficients are derived for any sample rate (they match the published 48 kHz values).
+struct KWeighting {
+    stages: [Biquad; 2],
+}
+
+impl KWeighting {
+    fn new(sample_rate: u32) -> Self {
+        let rate = sample_rate as f64;
+
+        let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+        let k = (std::f64::consts::PI * f0 / rate).tan();
+        let vh = 10f64.powf(gain_db / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Biquad::new(
+            [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        );
+
+        let (f0, q) = (38.13547087602444, 0.5003270373238773);
+        let k = (std::f64::consts::PI * f0 / rate).tan();
+        let a0 = 1.0 + k / q + k * k;
+        let high_pass = Biquad::new([1.0, -2.0, 1.0], [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0]);
+
+        Self { stages: [shelf, high_pass] }
+    }
+
+    fn process(&mut self, input: f64) -> f64 {
+        self.stages.iter_mut().fold(input, |value, stage| stage.process(value))
+    }
+}
+
+/// Direct form I biquad with a0 normalized to 1
+pub(crate) struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    pub(crate) fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Self { b, a, x: [0.0; 2], y: [0.0; 2] }
+    }
+
+    pub(crate) fn process(&mut self, input: f64) -> f64 {
+        let output = self.b[0] * input + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0] - self.a[1] * self.y[1];
+        self.x = [input, self.x[0]];
+        self.y = [output, self.y[0]];
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loudness_and_true_peak_reference_signals() {
+        // BS.1770: a 1 kHz sine at -20 dBFS reads about -23 LUFS per channel (stereo sums to -20)
+        let rate = 48000;
+        let sine: Vec<f32> = (0..rate as usize * 3)
+            .map(|i| 0.1 * (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / rate as f32).sin())
+            .collect();
+        let mono = integrated_loudness(&sine, 1, rate).unwrap();
+        assert!((mono + 23.0).abs() < 0.1, "{}", mono);
+        let stereo: Vec<f32> = sine.iter().flat_map(|&s| [s, s]).collect();
+        let stereo = integrated_loudness(&stereo, 2, rate).unwrap();
+        assert!((stereo + 20.0).abs() < 0.1, "{}", stereo);
+        assert_eq!(integrated_loudness(&vec![0.0; 48000], 1, rate), None);
+
+        // A quarter-rate sine sampled 45 degrees off its peaks: samples read -3 dB, true peak 0 dB
+        let offset: Vec<f32> = (0..4800)
+            .map(|i| (std::f32::consts::FRAC_PI_2 * i as f32 + std::f32::consts::FRAC_PI_4).sin())
+            .collect();
+        let measurement = LoudnessMeasurement::measure(&offset, 1, rate);
+        assert!((measurement.sample_peak_db + 3.01).abs() < 0.05, "{:?}", measurement);
+        assert!(measurement.true_peak_db.abs() < 0.3, "{:?}", measurement);
+
+        // Gain to -14 LUFS is limited by a -1 dBTP ceiling
+        let loud = LoudnessMeasurement { integrated_lufs: Some(-20.0), true_peak_db: -3.0, sample_peak_db: -3.0 };
+        assert_eq!(loud.gain_to_target(-14.0, -1.0), Some(2.0));
+        assert_eq!(loud.gain_to_target(-22.0, -1.0), Some(-2.0));
+    }
+}
@@ -0,0 +1,317 @@
+use crate::{Result, BatcherbirdError};
+use crate::sampler::{Sample, SampleMetadata};
+use crate::qa::{self, SilentGap, SilentGapConfig};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// File name of the manifest written next to the exported samples
+pub const MANIFEST_FILENAME: &str = "batcherbird_manifest.json";
+
+/// File name of the human-readable session report
+pub const REPORT_FILENAME: &str = "batcherbird_report.txt";
+
+/// One exported sample as recorded in the manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// WAV file name, relative to the manifest directory
+    pub file: String,
+    pub note: u8,
+    pub note_name: String,
+    pub velocity: u8,
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Captured length in seconds (before export trimming)
+    pub duration_sec: f32,
+    /// Captured peak level in dBFS
+    pub peak_db: f32,
+    /// Captured RMS level in dBFS
+    pub rms_db: f32,
+    #[serde(default)]
+    pub metadata: SampleMetadata,
+    /// Dropouts found inside the captured audio
+    #[serde(default)]
+    pub silent_gaps: Vec<SilentGap>,
+    /// Gain applied by normalization (dB), when it was enabled
+    #[serde(default)]
+    pub normalization_gain_db: Option<f32>,
+}
+
+/// Spread of the normalization gains applied across a session
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NormalizationSummary {
+    pub count: usize,
+    pub min_db: f32,
+    pub max_db: f32,
+    pub median_db: f32,
+}
+
+impl NormalizationSummary {
+    /// None when no gain was applied
+    pub fn from_gains(gains: &[f32]) -> Option<Self> {
+        if gains.is_empty() {
+            return None;
+        }
+        let mut sorted = gains.to_vec();
+        sorted.sort_by(f32::total_cmp);
+        let middle = sorted.len() / 2;
+        let median_db = if sorted.len().is_multiple_of(2) {
+            (sorted[middle - 1] + sorted[middle]) / 2.0
+        } else {
+            sorted[middle]
+        };
+        Some(Self { count: sorted.len(), min_db: sorted[0], max_db: sorted[sorted.len() - 1], median_db })
+    }
+}
+
+impl std::fmt::Display for NormalizationSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Normalization gain over {} samples: min {:+.1} dB, max {:+.1} dB, median {:+.1} dB",
+            self.count, self.min_db, self.max_db, self.median_db)
+    }
+}
+
+/// Standard deviation of note-on-to-onset latency above which the rig's timing is flagged (ms)
+const JITTER_WARN_STDDEV_MS: f32 = 2.0;
+/// Spread (max - min) of the latencies above which the rig's timing is flagged (ms)
+const JITTER_WARN_SPREAD_MS: f32 = 5.0;
+
+/// Distribution of the note-on-to-onset latencies across a session
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimingSummary {
+    pub count: usize,
+    pub min_ms: f32,
+    pub median_ms: f32,
+    pub max_ms: f32,
+    pub stddev_ms: f32,
+}
+
+impl TimingSummary {
+    /// None when no latency was measured
+    pub fn from_latencies(latencies: &[f32]) -> Option<Self> {
+        if latencies.is_empty() {
+            return None;
+        }
+        let mut sorted = latencies.to_vec();
+        sorted.sort_by(f32::total_cmp);
+        let middle = sorted.len() / 2;
+        let median_ms = if sorted.len().is_multiple_of(2) {
+            (sorted[middle - 1] + sorted[middle]) / 2.0
+        } else {
+            sorted[middle]
+        };
+        let mean = sorted.iter().sum::<f32>() / sorted.len() as f32;
+        let variance = sorted.iter().map(|ms| (ms - mean).powi(2)).sum::<f32>() / sorted.len() as f32;
+        Some(Self {
+            count: sorted.len(),
+            min_ms: sorted[0],
+            median_ms,
+            max_ms: sorted[sorted.len() - 1],
+            stddev_ms: variance.sqrt(),
+        })
+    }
+
+    /// Whether the latencies vary enough to smear attacks between samples
+    pub fn is_jittery(&self) -> bool {
+        self.stddev_ms > JITTER_WARN_STDDEV_MS || self.max_ms - self.min_ms > JITTER_WARN_SPREAD_MS
+    }
+}
+
+impl std::fmt::Display for TimingSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Note-on latency over {} samples: min {:.1} ms, median {:.1} ms, max {:.1} ms, stddev {:.1} ms",
+            self.count, self.min_ms, self.median_ms, self.max_ms, self.stddev_ms)
+    }
+}
+
+/// Machine-readable description of a sampling session's outputs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionManifest {
+    pub session_name: String,
+    pub created_at: DateTime<Utc>,
+    /// Application and version that wrote the manifest
+    pub generator: String,
+    /// Instrument files (.sfz/.dspreset), relative to the manifest directory
+    pub instrument_files: Vec<String>,
+    pub samples: Vec<ManifestEntry>,
+}
+
+impl SessionManifest {
+    pub fn new(session_name: impl Into<String>) -> Self {
+        Self {
+            session_name: session_name.into(),
+            created_at: Utc::now(),
+            generator: format!("Batcherbird {}", env!("CARGO_PKG_VERSION")),
+            instrument_files: Vec::new(),
+            samples: Vec::new(),
+        }
+    }
+
+    /// Record a sample together with the file it was exported to
+    pub fn add_sample(&mut self, sample: &Sample, file: &Path) {
+        let peak = sample.audio_data.iter().map(|s| s.abs()).fold(0.0f32, f32::max);
+        let rms = if sample.audio_data.is_empty() {
+            0.0
+        } else {
+            (sample.audio_data.iter().map(|s| s * s).sum::<f32>() / sample.audio_data.len() as f32).sqrt()
+        };
+        let frames = sample.audio_data.len() / sample.channels.max(1) as usize;
+
+        self.samples.push(ManifestEntry {
+            file: Self::file_name(file),
+            note: sample.note,
+            note_name: Self::note_to_name(sample.note),
+            velocity: sample.velocity,
+            sample_rate: sample.sample_rate,
+            channels: sample.channels,
+            duration_sec: frames as f32 / sample.sample_rate.max(1) as f32,
+            peak_db: Self::to_db(peak),
+            rms_db: Self::to_db(rms),
+            metadata: sample.metadata.clone(),
+            silent_gaps: qa::find_silent_gaps(&sample.audio_data, sample.channels, sample.sample_rate, &SilentGapConfig::default()),
+            normalization_gain_db: None,
+        });
+    }
+
+    /// Attach the gains reported by `SampleExporter::normalization_gains` to their entries
+    pub fn record_normalization_gains(&mut self, gains: &[(PathBuf, f32)]) {
+        for (file, gain_db) in gains {
+            let name = Self::file_name(file);
+            if let Some(entry) = self.samples.iter_mut().find(|entry| entry.file == name) {
+                entry.normalization_gain_db = Some(*gain_db);
+            }
+        }
+    }
+
+    pub fn normalization_summary(&self) -> Option<NormalizationSummary> {
+        let gains: Vec<f32> = self.samples.iter().filter_map(|entry| entry.normalization_gain_db).collect();
+        NormalizationSummary::from_gains(&gains)
+    }
+
+    /// Latency statistics over the samples that recorded one (see `SampleMetadata::onset_latency_ms`)
+    pub fn timing_summary(&self) -> Option<TimingSummary> {
+        let latencies: Vec<f32> = self.samples.iter().filter_map(|entry| entry.metadata.onset_latency_ms).collect();
+        TimingSummary::from_latencies(&latencies)
+    }
+
+    /// Record an instrument file (.sfz/.dspreset) produced for this session
+    pub fn add_instrument_file(&mut self, file: &Path) {
+        self.instrument_files.push(Self::file_name(file));
+    }
+
+    /// Write the manifest as pretty JSON into `directory`. The file is replaced in one rename,
+    /// so a crash mid-write leaves the previous manifest intact.
+    pub fn save(&self, directory: &Path) -> Result<PathBuf> {
+        let path = directory.join(MANIFEST_FILENAME);
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| BatcherbirdError::Session(format!("Failed to serialize manifest: {}", e)))?;
+        let partial = path.with_extension("json.partial");
+        std::fs::write(&partial, json).map_err(BatcherbirdError::Export)?;
+        std::fs::rename(&partial, &path).map_err(BatcherbirdError::Export)?;
+
+        info!("📋 Wrote manifest: {}", path.display());
+        Ok(path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path).map_err(BatcherbirdError::Export)?;
+        serde_json::from_str(&json)
+            .map_err(|e| BatcherbirdError::Session(format!("Invalid manifest {}: {}", path.display(), e)))
+    }
+
+    /// Human-readable session summary
+    pub fn summary(&self) -> String {
+        let mut report = String::new();
+        report.push_str(&format!("Batcherbird Session Report: {}\n", self.session_name));
+        report.push_str(&format!("Created: {}\n", self.created_at.format("%Y-%m-%d %H:%M:%S UTC")));
+        report.push_str(&format!("Generator: {}\n\n", self.generator));
+
+        if self.samples.is_empty() {
+            report.push_str("No samples recorded.\n");
+            return report;
+        }
+
+        let lowest = self.samples.iter().min_by_key(|s| s.note).unwrap();
+        let highest = self.samples.iter().max_by_key(|s| s.note).unwrap();
+        let mut velocities: Vec<u8> = self.samples.iter().map(|s| s.velocity).collect();
+        velocities.sort_unstable();
+        velocities.dedup();
+        let total_duration: f32 = self.samples.iter().map(|s| s.duration_sec).sum();
+
+        report.push_str(&format!("Samples: {}\n", self.samples.len()));
+        report.push_str(&format!("Note range: {} ({}) - {} ({})\n",
+            lowest.note_name, lowest.note, highest.note_name, highest.note));
+        report.push_str(&format!("Velocities: {:?}\n", velocities));
+        report.push_str(&format!("Total audio: {:.1}s\n", total_duration));
+        if let Some(normalization) = self.normalization_summary() {
+            report.push_str(&format!("{}\n", normalization));
+        }
+        let timing = self.timing_summary();
+        if let Some(timing) = timing {
+            report.push_str(&format!("{}\n", timing));
+        }
+
+        if !self.instrument_files.is_empty() {
+            report.push_str("\nInstrument files:\n");
+            for file in &self.instrument_files {
+                report.push_str(&format!("  {}\n", file));
+            }
+        }
+
+        let with_gaps: Vec<&ManifestEntry> = self.samples.iter().filter(|s| !s.silent_gaps.is_empty()).collect();
+        report.push_str("\nQuality checks:\n");
+        if with_gaps.is_empty() {
+            report.push_str("  No silent gaps found inside samples\n");
+        }
+        for entry in with_gaps {
+            let gaps: Vec<String> = entry.silent_gaps.iter()
+                .map(|gap| format!("{:.0}ms at {:.2}s", gap.duration_ms, gap.start_ms / 1000.0))
+                .collect();
+            report.push_str(&format!("  ⚠️ {}: silent gap {}\n", entry.file, gaps.join(", ")));
+        }
+        for entry in self.samples.iter().filter(|s| s.metadata.retake_reason.is_some()) {
+            report.push_str(&format!("  🔁 {}: retake ({})\n", entry.file, entry.metadata.retake_reason.as_deref().unwrap_or_default()));
+        }
+        if let Some(timing) = timing.filter(TimingSummary::is_jittery) {
+            report.push_str(&format!("  ⚠️ Note-on latency varies by {:.1} ms (stddev {:.1} ms); check USB MIDI hubs and interfaces\n",
+                timing.max_ms - timing.min_ms, timing.stddev_ms));
+        }
+
+        report.push_str("\nSamples:\n");
+        for entry in &self.samples {
+            let gain = entry.normalization_gain_db.map(|db| format!("  gain {:+.1} dB", db)).unwrap_or_default();
+            let latency = entry.metadata.onset_latency_ms.map(|ms| format!("  lat {:.1} ms", ms)).unwrap_or_default();
+            report.push_str(&format!("  {:<32} {:>4} vel {:>3}  {:>5.2}s  peak {:>6.1} dB  rms {:>6.1} dB{}{}\n",
+                entry.file, entry.note_name, entry.velocity, entry.duration_sec, entry.peak_db, entry.rms_db, gain, latency));
+        }
+
+        report
+    }
+
+    /// Write the readable summary into `directory`
+    pub fn write_report(&self, directory: &Path) -> Result<PathBuf> {
+        let path = directory.join(REPORT_FILENAME);
+        std::fs::write(&path, self.summary()).map_err(BatcherbirdError::Export)?;
+
+        info!("📝 Wrote session report: {}", path.display());
+        Ok(path)
+    }
+
+    fn file_name(file: &Path) -> String {
+        file.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| file.to_string_lossy().to_string())
+    }
+
+    fn to_db(level: f32) -> f32 {
+        if level > 0.0 { 20.0 * level.log10() } else { -96.0 }
+    }
+
+    fn note_to_name(note: u8) -> String {
+        let note_names = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+        let octave = (note / 12).saturating_sub(1);
+        let note_name = note_names[(note % 12) as usize];
+        format!("{}{}", note_name, octave)
+    }
+}
@@ -0,0 +1,136 @@
+use crate::sampler::{AudioLevels, LevelMeterState};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::warn;
+
+/// Default meter event rate (Hz), one per display frame
+pub const DEFAULT_METER_RATE_HZ: f32 = 60.0;
+
+/// Bob Katz's K-System scales: 0 on the meter sits this many dB below full scale
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum KScale {
+    /// Heavily compressed material
+    K12,
+    K14,
+    /// Wide dynamic range (film, classical, raw synth captures)
+    #[default]
+    K20,
+}
+
+impl KScale {
+    pub fn headroom_db(self) -> f32 {
+        match self {
+            KScale::K12 => 12.0,
+            KScale::K14 => 14.0,
+            KScale::K20 => 20.0,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "k12" | "k-12" => Some(KScale::K12),
+            "k14" | "k-14" => Some(KScale::K14),
+            "k20" | "k-20" => Some(KScale::K20),
+            _ => None,
+        }
+    }
+}
+
+/// One pushed meter update: peak, RMS and K-System readings plus held peak and clips
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MeterReading {
+    pub peak_db: f32,
+    pub rms_db: f32,
+    /// RMS on the K scale (0 = the scale's reference level, positive = into the headroom)
+    pub k_db: f32,
+    pub k_scale: KScale,
+    pub peak_hold_db: f32,
+    pub clip_count: u64,
+}
+
+impl MeterReading {
+    pub fn from_levels(levels: &AudioLevels, k_scale: KScale) -> Self {
+        Self {
+            peak_db: levels.peak_db,
+            rms_db: levels.rms_db,
+            // K-System meters read sine RMS per AES-17, 3 dB above plain RMS
+            k_db: levels.rms_db + 3.01 + k_scale.headroom_db(),
+            k_scale,
+            peak_hold_db: levels.peak_hold_db,
+            clip_count: levels.clip_count,
+        }
+    }
+}
+
+/// Background thread pushing meter readings to a callback at a fixed rate, so the UI
+/// doesn't have to poll. Stops when dropped.
+pub struct LevelPublisher {
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl LevelPublisher {
+    pub fn start<F>(state: Arc<LevelMeterState>, rate_hz: f32, k_scale: KScale, callback: F) -> Self
+    where
+        F: Fn(MeterReading) + Send + 'static,
+    {
+        let running = Arc::new(AtomicBool::new(true));
+        let interval = Duration::from_secs_f32(1.0 / rate_hz.clamp(1.0, 240.0));
+        let thread_running = Arc::clone(&running);
+        let thread = std::thread::spawn(move || {
+            while thread_running.load(Ordering::Relaxed) {
+                callback(MeterReading::from_levels(&state.get_levels(), k_scale));
+                std::thread::sleep(interval);
+            }
+        });
+        Self { running, thread: Some(thread) }
+    }
+
+    pub fn stop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                warn!("⚠️ Level publisher thread panicked");
+            }
+        }
+    }
+}
+
+impl Drop for LevelPublisher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_publisher_pushes_k_readings_until_stopped() {
+        let state = Arc::new(LevelMeterState::new());
+        state.update_levels(AudioLevels { rms_db: -23.01, peak_db: -17.0, ..AudioLevels::default() });
+
+        let readings = Arc::new(Mutex::new(Vec::new()));
+        let received = Arc::clone(&readings);
+        let mut publisher = LevelPublisher::start(Arc::clone(&state), 100.0, KScale::K20, move |reading| {
+            received.lock().unwrap().push(reading);
+        });
+        std::thread::sleep(Duration::from_millis(100));
+        publisher.stop();
+
+        let count = readings.lock().unwrap().len();
+        assert!((3..=15).contains(&count), "{}", count);
+        // -20 dBFS sine (RMS -23 dB) is the K-20 reference level
+        let reading = readings.lock().unwrap()[0].clone();
+        assert!(reading.k_db.abs() < 0.01 && reading.peak_db == -17.0, "{:?}", reading);
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(readings.lock().unwrap().len(), count);
+        assert_eq!(KScale::from_name("K-14"), Some(KScale::K14));
+    }
+}
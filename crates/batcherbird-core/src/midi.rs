@@ -1,6 +1,54 @@
 use crate::{Result, BatcherbirdError};
-use midir::{MidiInput, MidiInputConnection, MidiOutput, MidiOutputConnection};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::identity::{SynthIdentity, ALL_CALL_DEVICE_ID};
+use crate::midi_monitor::{describe_message, MonitorFilter, MonitoredMessage};
+use crate::trigger::{TriggerAction, TriggerMap};
+use serde::{Deserialize, Serialize};
+use midir::{Ignore, MidiInput, MidiInputConnection, MidiOutput, MidiOutputConnection};
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// SysEx start / end status bytes
+const SYSEX_START: u8 = 0xF0;
+const SYSEX_END: u8 = 0xF7;
+
+/// How stuck notes are cleared around a recording batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PanicStyle {
+    /// Enhanced all-channel panic before/after a batch, channel panic before each note
+    #[default]
+    Full,
+    /// Only All Notes Off + Reset Controllers on the sampling channel
+    Channel,
+    /// No panic messages - for synths that misinterpret CC 121/123 (rely on note-offs)
+    None,
+}
+
+/// A note message received on a MIDI input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoteEvent {
+    /// 0-indexed MIDI channel
+    pub channel: u8,
+    pub note: u8,
+    pub velocity: u8,
+    /// false for note-off (including note-on with velocity 0)
+    pub on: bool,
+}
+
+impl NoteEvent {
+    pub fn parse(message: &[u8]) -> Option<Self> {
+        let (&status, data) = message.split_first()?;
+        let (&note, &velocity) = (data.first()?, data.get(1)?);
+        let channel = status & 0x0F;
+        match status & 0xF0 {
+            0x90 => Some(Self { channel, note, velocity, on: velocity > 0 }),
+            0x80 => Some(Self { channel, note, velocity, on: false }),
+            _ => None,
+        }
+    }
+}
 
 pub struct MidiManager {
     input: Option<MidiInput>,
@@ -30,6 +78,14 @@ impl MidiManager {
         Ok(devices)
     }
 
+    /// Index of the MIDI input called `name`, or else the first whose name contains it
+    pub fn input_index_named(&mut self, name: &str) -> Result<usize> {
+        let devices = self.list_input_devices()?;
+        devices.iter().position(|device| device == name)
+            .or_else(|| devices.iter().position(|device| device.contains(name)))
+            .ok_or_else(|| BatcherbirdError::Session(format!("No MIDI input matching '{}'", name)))
+    }
+
     pub fn list_output_devices(&mut self) -> Result<Vec<String>> {
         let midi_out = MidiOutput::new("batcherbird-output")?;
         let ports = midi_out.ports();
@@ -87,6 +143,199 @@ impl MidiManager {
         Ok(())
     }
 
+    pub fn send_control_change(conn: &mut MidiOutputConnection, channel: u8, controller: u8, value: u8) -> Result<()> {
+        let msg = [0xB0 | (channel & 0x0F), controller & 0x7F, value & 0x7F];
+        conn.send(&msg)
+            .map_err(|e| BatcherbirdError::Session(format!("Failed to send control change: {:?}", e)))?;
+        Ok(())
+    }
+
+    pub fn send_program_change(conn: &mut MidiOutputConnection, channel: u8, program: u8) -> Result<()> {
+        let msg = [0xC0 | (channel & 0x0F), program & 0x7F];
+        conn.send(&msg)
+            .map_err(|e| BatcherbirdError::Session(format!("Failed to send program change: {:?}", e)))?;
+        Ok(())
+    }
+
+    /// Send a complete raw MIDI message (e.g. an event read from a MIDI file)
+    pub fn send_message(conn: &mut MidiOutputConnection, message: &[u8]) -> Result<()> {
+        conn.send(message)
+            .map_err(|e| BatcherbirdError::Session(format!("Failed to send MIDI message: {:?}", e)))?;
+        Ok(())
+    }
+
+    /// Send a System Exclusive message. `data` may be a complete F0 ... F7 frame
+    /// or just the payload, in which case the framing bytes are added.
+    pub fn send_sysex(conn: &mut MidiOutputConnection, data: &[u8]) -> Result<()> {
+        let msg = Self::frame_sysex(data)?;
+        conn.send(&msg)
+            .map_err(|e| BatcherbirdError::Session(format!("Failed to send SysEx: {:?}", e)))?;
+        Ok(())
+    }
+
+    /// Send every SysEx message in a .syx dump (e.g. a patch or setup dump) before sampling.
+    /// Vintage synths drop data when messages arrive back-to-back, so `gap` is waited between messages.
+    pub fn send_sysex_file(conn: &mut MidiOutputConnection, path: &Path, gap: Duration) -> Result<usize> {
+        let data = std::fs::read(path).map_err(BatcherbirdError::Export)?;
+        let messages = Self::split_sysex_messages(&data);
+        if messages.is_empty() {
+            return Err(BatcherbirdError::Session(format!("No SysEx messages found in {}", path.display())));
+        }
+        
+        info!("📤 Sending {} SysEx message(s) from {}", messages.len(), path.display());
+        for (i, message) in messages.iter().enumerate() {
+            Self::send_sysex(conn, message)?;
+            if i + 1 < messages.len() {
+                std::thread::sleep(gap);
+            }
+        }
+        
+        Ok(messages.len())
+    }
+
+    /// Send a SysEx request and wait for the first SysEx reply starting with `response_prefix`
+    /// (e.g. `[0xF0, 0x42]` for Korg). Replies are read from the given MIDI input device.
+    pub fn sysex_request(
+        &mut self,
+        conn: &mut MidiOutputConnection,
+        input_device_index: usize,
+        request: &[u8],
+        response_prefix: &[u8],
+        timeout: Duration,
+    ) -> Result<Vec<u8>> {
+        self.sysex_request_matching(conn, input_device_index, request, |reply| reply.starts_with(response_prefix), timeout)
+    }
+
+    /// Ask the synth who it is (Universal SysEx Identity Request) and parse the reply
+    pub fn identify_device(
+        &mut self,
+        conn: &mut MidiOutputConnection,
+        input_device_index: usize,
+        timeout: Duration,
+    ) -> Result<SynthIdentity> {
+        info!("🔎 Sending MIDI Device Inquiry...");
+        let request = SynthIdentity::request(ALL_CALL_DEVICE_ID);
+        let reply = self.sysex_request_matching(conn, input_device_index, &request, SynthIdentity::is_reply, timeout)?;
+        
+        let identity = SynthIdentity::parse_reply(&reply)
+            .ok_or_else(|| BatcherbirdError::Session("Malformed Identity Reply".to_string()))?;
+        info!("✅ Synth identified: {}", identity.display_name());
+        Ok(identity)
+    }
+
+    /// Send a SysEx request and wait for the first complete SysEx reply accepted by `matches`
+    fn sysex_request_matching<F>(
+        &mut self,
+        conn: &mut MidiOutputConnection,
+        input_device_index: usize,
+        request: &[u8],
+        matches: F,
+        timeout: Duration,
+    ) -> Result<Vec<u8>>
+    where
+        F: Fn(&[u8]) -> bool,
+    {
+        let (_conn_in, rx) = self.connect_input_sysex(input_device_index)?;
+        Self::send_sysex(conn, request)?;
+        
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match rx.recv_timeout(remaining) {
+                Ok(reply) if matches(&reply) => return Ok(reply),
+                Ok(_) => continue, // Unrelated SysEx traffic
+                Err(_) => {
+                    return Err(BatcherbirdError::Session(format!(
+                        "No SysEx reply within {}ms", timeout.as_millis()
+                    )));
+                }
+            }
+        }
+    }
+
+    /// Listen for complete SysEx messages on a MIDI input; they arrive on the returned channel
+    /// while the connection is held
+    pub fn connect_input_sysex(&mut self, device_index: usize) -> Result<(MidiInputConnection<()>, mpsc::Receiver<Vec<u8>>)> {
+        let mut midi_in = match self.input.take() {
+            Some(input) => input,
+            None => MidiInput::new("batcherbird-input")?,
+        };
+        // midir drops SysEx by default
+        midi_in.ignore(Ignore::None);
+        
+        let ports = midi_in.ports();
+        if device_index >= ports.len() {
+            return Err(BatcherbirdError::Session(format!(
+                "MIDI input device index {} out of range (0-{})",
+                device_index,
+                ports.len().saturating_sub(1)
+            )));
+        }
+        
+        let (tx, rx) = mpsc::channel::<Vec<u8>>();
+        let mut pending: Vec<u8> = Vec::new();
+        
+        let conn_in = midi_in.connect(&ports[device_index], "batcherbird-sysex-in", move |_, message, _| {
+            // Some backends deliver long dumps in chunks - reassemble up to F7
+            if message.first() == Some(&SYSEX_START) {
+                pending.clear();
+            } else if pending.is_empty() {
+                return;
+            }
+            pending.extend_from_slice(message);
+            if pending.last() == Some(&SYSEX_END) {
+                let _ = tx.send(std::mem::take(&mut pending));
+            }
+        }, ())
+            .map_err(|e| BatcherbirdError::Session(format!("Failed to connect to MIDI input: {:?}", e)))?;
+        
+        Ok((conn_in, rx))
+    }
+
+    /// Split raw bytes (e.g. a .syx file) into individual F0 ... F7 messages
+    pub fn split_sysex_messages(data: &[u8]) -> Vec<Vec<u8>> {
+        let mut messages = Vec::new();
+        let mut current: Option<Vec<u8>> = None;
+        
+        for &byte in data {
+            match byte {
+                SYSEX_START => current = Some(vec![byte]),
+                SYSEX_END => {
+                    if let Some(mut message) = current.take() {
+                        message.push(byte);
+                        messages.push(message);
+                    }
+                }
+                _ => {
+                    if let Some(message) = current.as_mut() {
+                        message.push(byte);
+                    }
+                }
+            }
+        }
+        
+        messages
+    }
+
+    /// Add missing F0/F7 framing and reject payload bytes with the high bit set
+    fn frame_sysex(data: &[u8]) -> Result<Vec<u8>> {
+        let payload = data.strip_prefix(&[SYSEX_START]).unwrap_or(data);
+        let payload = payload.strip_suffix(&[SYSEX_END]).unwrap_or(payload);
+        
+        if payload.is_empty() {
+            return Err(BatcherbirdError::Session("SysEx message is empty".to_string()));
+        }
+        if let Some(byte) = payload.iter().find(|&&b| b & 0x80 != 0) {
+            return Err(BatcherbirdError::Session(format!("Invalid SysEx data byte: {:02X}", byte)));
+        }
+        
+        let mut msg = Vec::with_capacity(payload.len() + 2);
+        msg.push(SYSEX_START);
+        msg.extend_from_slice(payload);
+        msg.push(SYSEX_END);
+        Ok(msg)
+    }
+
     pub async fn send_test_note(conn: &mut MidiOutputConnection, channel: u8, note: u8, velocity: u8, duration: Duration) -> Result<()> {
         // Send note on
         Self::send_note_on(conn, channel, note, velocity)?;
@@ -100,10 +349,13 @@ impl MidiManager {
         Ok(())
     }
 
-    pub fn connect_input(&mut self, device_index: usize) -> Result<MidiInputConnection<()>> {
-        let midi_in = self.input.take().unwrap_or_else(|| {
+    /// Log the messages arriving on a MIDI input that pass `filter` while the connection is held
+    pub fn connect_input(&mut self, device_index: usize, filter: MonitorFilter) -> Result<MidiInputConnection<()>> {
+        let mut midi_in = self.input.take().unwrap_or_else(|| {
             MidiInput::new("batcherbird-input").expect("Failed to create MIDI input")
         });
+        // Let the filter decide about SysEx and the timing bytes midir would drop
+        midi_in.ignore(Ignore::None);
         
         let ports = midi_in.ports();
         if device_index >= ports.len() {
@@ -120,6 +372,9 @@ impl MidiManager {
             
         let conn_in = midi_in.connect(port, &format!("batcherbird-in-{}", device_name), 
             move |timestamp, message, _| {
+                if !MonitoredMessage::new(0.0, message).is_some_and(|monitored| filter.accepts(&monitored)) {
+                    return;
+                }
                 let now = SystemTime::now()
                     .duration_since(UNIX_EPOCH)
                     .unwrap()
@@ -132,6 +387,117 @@ impl MidiManager {
         Ok(conn_in)
     }
 
+    /// Listen for notes on a MIDI input; events arrive on the returned channel while the connection is held
+    pub fn connect_input_notes(&mut self, device_index: usize) -> Result<(MidiInputConnection<()>, mpsc::Receiver<NoteEvent>)> {
+        let midi_in = match self.input.take() {
+            Some(input) => input,
+            None => MidiInput::new("batcherbird-input")?,
+        };
+        
+        let ports = midi_in.ports();
+        if device_index >= ports.len() {
+            return Err(BatcherbirdError::Session(format!(
+                "MIDI input device index {} out of range (0-{})",
+                device_index,
+                ports.len().saturating_sub(1)
+            )));
+        }
+        
+        let (tx, rx) = mpsc::channel::<NoteEvent>();
+        let conn_in = midi_in.connect(&ports[device_index], "batcherbird-notes-in", move |_, message, _| {
+            if let Some(event) = NoteEvent::parse(message) {
+                let _ = tx.send(event);
+            }
+        }, ())
+            .map_err(|e| BatcherbirdError::Session(format!("Failed to connect to MIDI input: {:?}", e)))?;
+        
+        Ok((conn_in, rx))
+    }
+
+    /// Watch a MIDI input: messages passing `filter` arrive on the returned channel, stamped
+    /// with the time since connecting, while the connection is held. SysEx split over
+    /// several packets arrives as one message.
+    pub fn connect_input_monitor(
+        &mut self,
+        device_index: usize,
+        filter: MonitorFilter,
+    ) -> Result<(MidiInputConnection<()>, mpsc::Receiver<MonitoredMessage>)> {
+        let mut midi_in = match self.input.take() {
+            Some(input) => input,
+            None => MidiInput::new("batcherbird-input")?,
+        };
+        // midir drops SysEx, clock and active sensing by default; the filter decides instead
+        midi_in.ignore(Ignore::None);
+        
+        let ports = midi_in.ports();
+        if device_index >= ports.len() {
+            return Err(BatcherbirdError::Session(format!(
+                "MIDI input device index {} out of range (0-{})",
+                device_index,
+                ports.len().saturating_sub(1)
+            )));
+        }
+        
+        let (tx, rx) = mpsc::channel::<MonitoredMessage>();
+        let started = Instant::now();
+        let mut pending: Vec<u8> = Vec::new();
+        let conn_in = midi_in.connect(&ports[device_index], "batcherbird-monitor-in", move |_, message, _| {
+            // Real-time bytes may arrive in the middle of a SysEx dump; they pass straight through
+            let continues_sysex = !pending.is_empty() && message.first().is_some_and(|&status| status < 0xF8);
+            let message = if message.first() == Some(&SYSEX_START) || continues_sysex {
+                if message.first() == Some(&SYSEX_START) {
+                    pending.clear();
+                }
+                pending.extend_from_slice(message);
+                if pending.last() != Some(&SYSEX_END) {
+                    return;
+                }
+                std::mem::take(&mut pending)
+            } else {
+                message.to_vec()
+            };
+            let time_ms = started.elapsed().as_secs_f64() * 1000.0;
+            if let Some(monitored) = MonitoredMessage::new(time_ms, &message) {
+                if filter.accepts(&monitored) {
+                    let _ = tx.send(monitored);
+                }
+            }
+        }, ())
+            .map_err(|e| BatcherbirdError::Session(format!("Failed to connect to MIDI input: {:?}", e)))?;
+        
+        Ok((conn_in, rx))
+    }
+
+    /// Listen for footswitch / controller triggers on a MIDI input and send their actions
+    /// to `actions` while the connection is held
+    pub fn connect_input_triggers(
+        &mut self,
+        device_index: usize,
+        mut triggers: TriggerMap,
+        actions: mpsc::Sender<TriggerAction>,
+    ) -> Result<MidiInputConnection<()>> {
+        let midi_in = match self.input.take() {
+            Some(input) => input,
+            None => MidiInput::new("batcherbird-input")?,
+        };
+        
+        let ports = midi_in.ports();
+        if device_index >= ports.len() {
+            return Err(BatcherbirdError::Session(format!(
+                "MIDI input device index {} out of range (0-{})",
+                device_index,
+                ports.len().saturating_sub(1)
+            )));
+        }
+        
+        midi_in.connect(&ports[device_index], "batcherbird-trigger-in", move |_, message, _| {
+            if let Some(action) = triggers.action_for(message) {
+                let _ = actions.send(action);
+            }
+        }, ())
+            .map_err(|e| BatcherbirdError::Session(format!("Failed to connect to MIDI input: {:?}", e)))
+    }
+
     fn print_midi_message(timestamp_ms: u128, _midi_timestamp: u64, message: &[u8]) {
         if message.is_empty() {
             return;
@@ -143,56 +509,14 @@ impl MidiManager {
             (timestamp_ms / 1000) % 60,
             timestamp_ms % 1000
         );
-
-        let status = message[0];
-        let msg_type = status & 0xF0;
-        let channel = (status & 0x0F) + 1;
-
-        match msg_type {
-            0x90 if message.len() >= 3 && message[2] > 0 => {
-                let note = message[1];
-                let velocity = message[2];
-                let note_name = Self::note_to_name(note);
-                println!("[{}] Note On  Ch:{} Note:{}({}) Vel:{}", 
-                    time_str, channel, note, note_name, velocity);
-            }
-            0x80 | 0x90 if message.len() >= 3 => { // Note off or note on with vel 0
-                let note = message[1];
-                let velocity = message[2];
-                let note_name = Self::note_to_name(note);
-                println!("[{}] Note Off Ch:{} Note:{}({}) Vel:{}", 
-                    time_str, channel, note, note_name, velocity);
-            }
-            0xB0 if message.len() >= 3 => {
-                let controller = message[1];
-                let value = message[2];
-                println!("[{}] CC       Ch:{} CC:{} Val:{}", 
-                    time_str, channel, controller, value);
-            }
-            0xC0 if message.len() >= 2 => {
-                let program = message[1];
-                println!("[{}] Program  Ch:{} Prog:{}", 
-                    time_str, channel, program);
-            }
-            _ => {
-                let hex_msg: Vec<String> = message.iter().map(|b| format!("{:02X}", b)).collect();
-                println!("[{}] Raw      {}", time_str, hex_msg.join(" "));
-            }
-        }
-    }
-
-    fn note_to_name(note: u8) -> String {
-        let note_names = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
-        let octave = (note / 12).saturating_sub(1);
-        let note_name = note_names[(note % 12) as usize];
-        format!("{}{}", note_name, octave)
+        info!("[{}] {}", time_str, describe_message(message));
     }
 
     /// Send MIDI Panic - All Notes Off on all channels
     /// Professional standard for handling stuck notes (like Logic/Ableton's panic button)
     /// Enhanced for vintage synths like DW6000 that need specific timing
     pub fn send_midi_panic(conn: &mut MidiOutputConnection) -> Result<()> {
-        println!("🚨 MIDI Panic: Enhanced panic for vintage synths...");
+        warn!("🚨 MIDI Panic: Enhanced panic for vintage synths...");
         
         let mut notes_sent = 0;
         
@@ -236,14 +560,23 @@ impl MidiManager {
             let _ = conn.send(&all_notes_off);
         }
         
-        println!("✅ Enhanced MIDI Panic complete: {} individual note-offs + CC messages", notes_sent);
+        info!("✅ Enhanced MIDI Panic complete: {} individual note-offs + CC messages", notes_sent);
         Ok(())
     }
 
+    /// Batch-level panic according to the synth's required style
+    pub fn send_panic(conn: &mut MidiOutputConnection, style: PanicStyle, channel: u8) -> Result<()> {
+        match style {
+            PanicStyle::Full => Self::send_midi_panic(conn),
+            PanicStyle::Channel => Self::send_channel_panic(conn, channel),
+            PanicStyle::None => Ok(()),
+        }
+    }
+
     /// Send a quick MIDI panic for a specific channel
     pub fn send_channel_panic(conn: &mut MidiOutputConnection, channel: u8) -> Result<()> {
         let channel = channel & 0x0F;
-        println!("🚨 Channel {} Panic: Sending All Notes Off...", channel + 1);
+        warn!("🚨 Channel {} Panic: Sending All Notes Off...", channel + 1);
         
         // Send All Notes Off CC
         let all_notes_off = [0xB0 | channel, 123, 0];
@@ -255,7 +588,33 @@ impl MidiManager {
         conn.send(&reset_controllers)
             .map_err(|e| BatcherbirdError::Session(format!("Failed to send Reset Controllers: {:?}", e)))?;
         
-        println!("✅ Channel {} panic complete", channel + 1);
+        info!("✅ Channel {} panic complete", channel + 1);
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sysex_framing_and_splitting() {
+        // Payload only gets framed; full frames pass through unchanged
+        assert_eq!(MidiManager::frame_sysex(&[0x7E, 0x7F, 0x06, 0x01]).unwrap(), vec![0xF0, 0x7E, 0x7F, 0x06, 0x01, 0xF7]);
+        assert_eq!(MidiManager::frame_sysex(&[0xF0, 0x42, 0x30, 0xF7]).unwrap(), vec![0xF0, 0x42, 0x30, 0xF7]);
+        assert!(MidiManager::frame_sysex(&[0x42, 0x90]).is_err());
+
+        let dump = [0xF0, 0x42, 0x01, 0xF7, 0x00, 0xF0, 0x43, 0x02, 0xF7];
+        let messages = MidiManager::split_sysex_messages(&dump);
+        assert_eq!(messages, vec![vec![0xF0, 0x42, 0x01, 0xF7], vec![0xF0, 0x43, 0x02, 0xF7]]);
+    }
+
+    #[test]
+    fn test_note_event_parsing() {
+        assert_eq!(NoteEvent::parse(&[0x92, 60, 100]), Some(NoteEvent { channel: 2, note: 60, velocity: 100, on: true }));
+        assert_eq!(NoteEvent::parse(&[0x90, 60, 0]).map(|event| event.on), Some(false));
+        assert_eq!(NoteEvent::parse(&[0x8F, 61, 64]), Some(NoteEvent { channel: 15, note: 61, velocity: 64, on: false }));
+        assert_eq!(NoteEvent::parse(&[0xB0, 7, 100]), None);
+        assert_eq!(NoteEvent::parse(&[0x90, 60]), None);
+    }
+}
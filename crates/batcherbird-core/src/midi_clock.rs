@@ -0,0 +1,141 @@
+use crate::{Result, BatcherbirdError};
+use crate::midi::MidiManager;
+use midir::MidiOutputConnection;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// MIDI beat clock resolution
+pub const CLOCKS_PER_QUARTER: u32 = 24;
+
+const CLOCK_TICK: u8 = 0xF8;
+const CLOCK_START: u8 = 0xFA;
+const CLOCK_STOP: u8 = 0xFC;
+
+/// Tempo range accepted for clock output
+const MIN_BPM: f32 = 20.0;
+const MAX_BPM: f32 = 300.0;
+
+/// MIDI clock output settings for tempo-synced patches (arpeggiators, synced LFOs/delays)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MidiClockConfig {
+    pub bpm: f32,
+    /// Send Stop + Start before every note so arps and synced LFOs begin at the same phase
+    pub restart_per_note: bool,
+    /// Beats of clock before the first note so the synth can lock to the tempo
+    pub lead_in_beats: u32,
+}
+
+impl MidiClockConfig {
+    pub fn new(bpm: f32) -> Result<Self> {
+        if !(MIN_BPM..=MAX_BPM).contains(&bpm) {
+            return Err(BatcherbirdError::Config(format!(
+                "Clock tempo must be {}-{} BPM, got {}", MIN_BPM, MAX_BPM, bpm
+            )));
+        }
+        Ok(Self {
+            bpm,
+            restart_per_note: true,
+            lead_in_beats: 4,
+        })
+    }
+
+    /// Time between clock ticks (24 per quarter note)
+    pub fn tick_interval(&self) -> Duration {
+        Duration::from_secs_f64(60.0 / (self.bpm as f64 * CLOCKS_PER_QUARTER as f64))
+    }
+}
+
+/// Running clock generator. Ticks are sent from `wait`, so the sampler keeps the
+/// clock going simply by waiting through it instead of sleeping.
+#[derive(Debug)]
+pub struct MidiClock {
+    config: MidiClockConfig,
+    next_tick: Instant,
+    running: bool,
+}
+
+impl MidiClock {
+    pub fn new(config: MidiClockConfig) -> Self {
+        Self {
+            config,
+            next_tick: Instant::now(),
+            running: false,
+        }
+    }
+
+    pub fn config(&self) -> &MidiClockConfig {
+        &self.config
+    }
+
+    /// Send Start; the next tick is the first beat
+    pub fn start(&mut self, conn: &mut MidiOutputConnection) -> Result<()> {
+        MidiManager::send_message(conn, &[CLOCK_START])?;
+        self.running = true;
+        self.next_tick = Instant::now();
+        Ok(())
+    }
+
+    pub fn stop(&mut self, conn: &mut MidiOutputConnection) -> Result<()> {
+        if self.running {
+            MidiManager::send_message(conn, &[CLOCK_STOP])?;
+            self.running = false;
+        }
+        Ok(())
+    }
+
+    /// Stop and start again so the synth's sequencer resets to beat one
+    pub fn restart(&mut self, conn: &mut MidiOutputConnection) -> Result<()> {
+        self.stop(conn)?;
+        self.start(conn)
+    }
+
+    /// Wait for `duration`, sending clock ticks on schedule while running
+    pub async fn wait(&mut self, conn: &mut MidiOutputConnection, duration: Duration) -> Result<()> {
+        let deadline = Instant::now() + duration;
+        if !self.running {
+            tokio::time::sleep_until(deadline).await;
+            return Ok(());
+        }
+
+        let interval = self.config.tick_interval();
+        // After a long stall (e.g. a blocking call) resync instead of bursting catch-up ticks
+        if Instant::now().saturating_duration_since(self.next_tick) > interval * 4 {
+            self.next_tick = Instant::now();
+        }
+
+        while self.next_tick <= deadline {
+            tokio::time::sleep_until(self.next_tick).await;
+            MidiManager::send_message(conn, &[CLOCK_TICK])?;
+            self.next_tick += interval;
+        }
+        tokio::time::sleep_until(deadline).await;
+        Ok(())
+    }
+
+    /// Wait with the clock if there is one, otherwise just sleep
+    pub async fn wait_optional(clock: Option<&mut MidiClock>, conn: &mut MidiOutputConnection, duration: Duration) -> Result<()> {
+        match clock {
+            Some(clock) => clock.wait(conn, duration).await,
+            None => {
+                tokio::time::sleep(duration).await;
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_tick_interval() {
+        // 120 BPM = 2 beats/s = 48 ticks/s
+        let config = MidiClockConfig::new(120.0).unwrap();
+        assert!((config.tick_interval().as_secs_f64() - 1.0 / 48.0).abs() < 1e-9);
+
+        assert!(MidiClockConfig::new(0.0).is_err());
+        assert!(MidiClockConfig::new(1000.0).is_err());
+    }
+}
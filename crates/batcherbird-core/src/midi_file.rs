@@ -0,0 +1,337 @@
+use crate::{Result, BatcherbirdError};
+use std::path::Path;
+use std::time::Duration;
+
+/// Default tempo when a file has no Set Tempo event (120 BPM)
+const DEFAULT_MICROS_PER_QUARTER: u32 = 500_000;
+
+/// One MIDI message to send at a fixed offset from the start of playback
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedMidiEvent {
+    pub time: Duration,
+    /// Raw bytes ready for the wire (channel message or complete SysEx)
+    pub message: Vec<u8>,
+}
+
+/// A Standard MIDI File flattened into one time-ordered event list.
+/// Tempo changes are already applied, so playback only needs to follow `time`.
+#[derive(Debug, Clone)]
+pub struct MidiFile {
+    /// SMF format (0 = single track, 1 = parallel tracks)
+    pub format: u16,
+    pub track_count: usize,
+    pub events: Vec<TimedMidiEvent>,
+    /// Length including trailing time up to the last End of Track
+    pub duration: Duration,
+}
+
+/// Raw event before tempo conversion
+enum TrackEvent {
+    Message(Vec<u8>),
+    Tempo(u32),
+    EndOfTrack,
+}
+
+/// Tick timing from the header division field
+enum Division {
+    TicksPerQuarter(u16),
+    /// SMPTE: fixed seconds per tick, independent of tempo
+    Smpte(f64),
+}
+
+impl MidiFile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path)?;
+        Self::parse(&data)
+    }
+
+    /// Parse SMF format 0 or 1 data
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(data);
+
+        if reader.take(4)? != b"MThd" {
+            return Err(Self::invalid("missing MThd header"));
+        }
+        let header_len = reader.u32()? as usize;
+        if header_len < 6 {
+            return Err(Self::invalid("header too short"));
+        }
+        let format = reader.u16()?;
+        let declared_tracks = reader.u16()? as usize;
+        let division = match reader.u16()? {
+            raw if raw & 0x8000 != 0 => {
+                let fps = -((raw >> 8) as u8 as i8) as f64;
+                let ticks_per_frame = (raw & 0xFF) as f64;
+                // 29 means 29.97 drop-frame
+                let fps = if fps == 29.0 { 29.97 } else { fps };
+                if fps <= 0.0 || ticks_per_frame <= 0.0 {
+                    return Err(Self::invalid("bad SMPTE division"));
+                }
+                Division::Smpte(1.0 / (fps * ticks_per_frame))
+            }
+            0 => return Err(Self::invalid("division of zero ticks per quarter note")),
+            ticks => Division::TicksPerQuarter(ticks),
+        };
+        if format > 1 {
+            return Err(BatcherbirdError::Processing(format!(
+                "MIDI file format {} is not supported (only 0 and 1)", format
+            )));
+        }
+        reader.take(header_len - 6)?;
+
+        // (tick, track, index within track, event) so merged order is stable
+        let mut merged: Vec<(u64, usize, usize, TrackEvent)> = Vec::new();
+        let mut track_count = 0;
+        while track_count < declared_tracks && reader.remaining() > 0 {
+            let chunk_id = reader.take(4)?;
+            let chunk_len = reader.u32()? as usize;
+            let chunk = reader.take(chunk_len)?;
+            if chunk_id != b"MTrk" {
+                continue; // Unknown chunks are skipped per spec
+            }
+            for (index, (tick, event)) in Self::parse_track(chunk)?.into_iter().enumerate() {
+                merged.push((tick, track_count, index, event));
+            }
+            track_count += 1;
+        }
+        merged.sort_by_key(|&(tick, track, index, _)| (tick, track, index));
+
+        // Walk the merged list once, converting ticks to time with the running tempo
+        let mut events = Vec::new();
+        let mut micros_per_quarter = DEFAULT_MICROS_PER_QUARTER;
+        let mut last_tick = 0u64;
+        let mut seconds = 0.0f64;
+        let mut duration = 0.0f64;
+        for (tick, _, _, event) in merged {
+            seconds += (tick - last_tick) as f64 * match division {
+                Division::TicksPerQuarter(tpq) => micros_per_quarter as f64 / 1_000_000.0 / tpq as f64,
+                Division::Smpte(seconds_per_tick) => seconds_per_tick,
+            };
+            last_tick = tick;
+            duration = duration.max(seconds);
+
+            match event {
+                TrackEvent::Message(message) => events.push(TimedMidiEvent {
+                    time: Duration::from_secs_f64(seconds),
+                    message,
+                }),
+                TrackEvent::Tempo(tempo) => micros_per_quarter = tempo,
+                TrackEvent::EndOfTrack => {}
+            }
+        }
+
+        Ok(Self {
+            format,
+            track_count,
+            events,
+            duration: Duration::from_secs_f64(duration),
+        })
+    }
+
+    /// Number of note-on events (velocity > 0)
+    pub fn note_count(&self) -> usize {
+        self.events.iter()
+            .filter(|event| matches!(event.message.as_slice(), [status, _, velocity] if status & 0xF0 == 0x90 && *velocity > 0))
+            .count()
+    }
+
+    /// 0-indexed MIDI channels used by channel messages, sorted
+    pub fn channels(&self) -> Vec<u8> {
+        let mut channels: Vec<u8> = self.events.iter()
+            .filter_map(|event| event.message.first())
+            .filter(|&&status| (0x80..0xF0).contains(&status))
+            .map(|status| status & 0x0F)
+            .collect();
+        channels.sort();
+        channels.dedup();
+        channels
+    }
+
+    /// Route every channel message to one channel (for synths listening on a single channel)
+    pub fn remap_channel(&mut self, channel: u8) {
+        for event in &mut self.events {
+            if let Some(status) = event.message.first_mut() {
+                if (0x80..0xF0).contains(status) {
+                    *status = (*status & 0xF0) | (channel & 0x0F);
+                }
+            }
+        }
+    }
+
+    /// Decode one MTrk chunk into (absolute tick, event) pairs
+    fn parse_track(chunk: &[u8]) -> Result<Vec<(u64, TrackEvent)>> {
+        let mut reader = Reader::new(chunk);
+        let mut events = Vec::new();
+        let mut tick = 0u64;
+        let mut running_status: Option<u8> = None;
+
+        while reader.remaining() > 0 {
+            tick += reader.vlq()? as u64;
+            let first = reader.u8()?;
+
+            match first {
+                0xFF => {
+                    let meta_type = reader.u8()?;
+                    let len = reader.vlq()? as usize;
+                    let data = reader.take(len)?;
+                    match meta_type {
+                        0x51 if len == 3 => {
+                            let tempo = (data[0] as u32) << 16 | (data[1] as u32) << 8 | data[2] as u32;
+                            events.push((tick, TrackEvent::Tempo(tempo)));
+                        }
+                        0x2F => {
+                            events.push((tick, TrackEvent::EndOfTrack));
+                            break;
+                        }
+                        _ => {} // Text, markers, key/time signature: nothing to send
+                    }
+                }
+                0xF0 => {
+                    let len = reader.vlq()? as usize;
+                    let mut message = vec![0xF0];
+                    message.extend_from_slice(reader.take(len)?);
+                    if message.last() != Some(&0xF7) {
+                        message.push(0xF7); // Continuation packets are not supported; close the frame
+                    }
+                    events.push((tick, TrackEvent::Message(message)));
+                    running_status = None;
+                }
+                0xF7 => {
+                    // Escape sequence: bytes are sent as-is
+                    let len = reader.vlq()? as usize;
+                    events.push((tick, TrackEvent::Message(reader.take(len)?.to_vec())));
+                    running_status = None;
+                }
+                _ => {
+                    let (status, first_data) = if first & 0x80 != 0 {
+                        running_status = Some(first);
+                        (first, None)
+                    } else {
+                        let status = running_status
+                            .ok_or_else(|| Self::invalid("data byte without running status"))?;
+                        (status, Some(first))
+                    };
+
+                    let data_len = match status & 0xF0 {
+                        0xC0 | 0xD0 => 1,
+                        _ => 2,
+                    };
+                    let mut message = vec![status];
+                    if let Some(byte) = first_data {
+                        message.push(byte);
+                    }
+                    while message.len() < data_len + 1 {
+                        message.push(reader.u8()?);
+                    }
+                    events.push((tick, TrackEvent::Message(message)));
+                }
+            }
+        }
+
+        Ok(events)
+    }
+
+    fn invalid(reason: &str) -> BatcherbirdError {
+        BatcherbirdError::Processing(format!("Invalid MIDI file: {}", reason))
+    }
+}
+
+/// Big-endian cursor over SMF bytes with truncation errors instead of panics
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.remaining() {
+            return Err(MidiFile::invalid("unexpected end of data"));
+        }
+        let slice = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Variable-length quantity (at most 4 bytes)
+    fn vlq(&mut self) -> Result<u32> {
+        let mut value = 0u32;
+        for _ in 0..4 {
+            let byte = self.u8()?;
+            value = (value << 7) | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(MidiFile::invalid("variable-length value longer than 4 bytes"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_format1_with_tempo_change() {
+        let mut data = Vec::new();
+        // Header: format 1, 2 tracks, 96 ticks per quarter
+        data.extend_from_slice(b"MThd");
+        data.extend_from_slice(&[0, 0, 0, 6, 0, 1, 0, 2, 0, 96]);
+
+        // Tempo track: 120 BPM, then 60 BPM at tick 96
+        let tempo_track = [
+            0x00, 0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20,
+            0x60, 0xFF, 0x51, 0x03, 0x0F, 0x42, 0x40,
+            0x00, 0xFF, 0x2F, 0x00,
+        ];
+        // Note track: note on at 0, running-status note off (vel 0) at 96, note on at 192
+        let note_track = [
+            0x00, 0x91, 0x3C, 0x64,
+            0x60, 0x3C, 0x00,
+            0x60, 0x91, 0x40, 0x50,
+            0x60, 0xFF, 0x2F, 0x00,
+        ];
+        for track in [&tempo_track[..], &note_track[..]] {
+            data.extend_from_slice(b"MTrk");
+            data.extend_from_slice(&(track.len() as u32).to_be_bytes());
+            data.extend_from_slice(track);
+        }
+
+        let mut file = MidiFile::parse(&data).unwrap();
+        assert_eq!(file.track_count, 2);
+        assert_eq!(file.note_count(), 2);
+        assert_eq!(file.channels(), vec![1]);
+
+        // First quarter at 120 BPM (0.5s), second at 60 BPM (1s)
+        let times: Vec<f64> = file.events.iter().map(|e| e.time.as_secs_f64()).collect();
+        assert!((times[1] - 0.5).abs() < 1e-6);
+        assert!((times[2] - 1.5).abs() < 1e-6);
+        assert!((file.duration.as_secs_f64() - 2.5).abs() < 1e-6);
+
+        file.remap_channel(9);
+        assert_eq!(file.events[0].message, vec![0x99, 0x3C, 0x64]);
+
+        assert!(MidiFile::parse(&data[..20]).is_err());
+    }
+}
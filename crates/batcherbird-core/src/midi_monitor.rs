@@ -0,0 +1,307 @@
+//! Watching a MIDI input: incoming messages are classified, filtered, timestamped from the
+//! start of the watch and handed over on a channel (see `MidiManager::connect_input_monitor`),
+//! and can be kept in a [`MidiCapture`] to save as JSON or a Standard MIDI File.
+
+use crate::{BatcherbirdError, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Ticks per quarter note of saved MIDI files; at the 120 BPM written into the file one
+/// tick is about half a millisecond
+const SMF_TICKS_PER_QUARTER: u16 = 960;
+const SMF_MICROS_PER_QUARTER: u32 = 500_000;
+
+/// Timing Clock and Active Sensing: sent continuously (24 per beat / every 300 ms) by
+/// gear that is just sitting there
+const TIMING_FLOOD_BYTES: [u8; 2] = [0xF8, 0xFE];
+
+/// Kind of a MIDI message, for filtering
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageKind {
+    /// Note on and note off
+    Note,
+    ControlChange,
+    ProgramChange,
+    PitchBend,
+    /// Polyphonic and channel pressure
+    Aftertouch,
+    SysEx,
+    /// Clock, start/stop, active sensing, song position and the other system messages
+    System,
+}
+
+impl MessageKind {
+    /// "notes", "cc", "program", "pitchbend", "aftertouch", "sysex" or "system"
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "note" | "notes" => Some(MessageKind::Note),
+            "cc" => Some(MessageKind::ControlChange),
+            "program" => Some(MessageKind::ProgramChange),
+            "pitchbend" => Some(MessageKind::PitchBend),
+            "aftertouch" => Some(MessageKind::Aftertouch),
+            "sysex" => Some(MessageKind::SysEx),
+            "system" => Some(MessageKind::System),
+            _ => None,
+        }
+    }
+
+    /// Kind and 0-indexed channel (None for system messages) of a raw message
+    pub fn classify(message: &[u8]) -> Option<(Self, Option<u8>)> {
+        let status = *message.first()?;
+        let channel = Some(status & 0x0F);
+        match status & 0xF0 {
+            0x80 | 0x90 => Some((MessageKind::Note, channel)),
+            0xA0 | 0xD0 => Some((MessageKind::Aftertouch, channel)),
+            0xB0 => Some((MessageKind::ControlChange, channel)),
+            0xC0 => Some((MessageKind::ProgramChange, channel)),
+            0xE0 => Some((MessageKind::PitchBend, channel)),
+            0xF0 if status == 0xF0 => Some((MessageKind::SysEx, None)),
+            0xF0 => Some((MessageKind::System, None)),
+            _ => None,
+        }
+    }
+}
+
+/// Which messages a monitor passes on
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MonitorFilter {
+    /// Kinds to keep (empty = all)
+    #[serde(default)]
+    pub kinds: Vec<MessageKind>,
+    /// 0-indexed channel to keep (system messages and SysEx always pass)
+    #[serde(default)]
+    pub channel: Option<u8>,
+    /// Keep Timing Clock and Active Sensing, which otherwise bury everything else
+    #[serde(default)]
+    pub pass_timing: bool,
+}
+
+impl MonitorFilter {
+    /// Comma-separated kinds as in [`MessageKind::parse`], e.g. "notes,cc"
+    pub fn parse_kinds(list: &str) -> Result<Vec<MessageKind>> {
+        list.split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| MessageKind::parse(name)
+                .ok_or_else(|| BatcherbirdError::Config(format!(
+                    "Unknown MIDI message kind '{}' (use notes, cc, program, pitchbend, aftertouch, sysex or system)", name
+                ))))
+            .collect()
+    }
+
+    pub fn accepts(&self, message: &MonitoredMessage) -> bool {
+        if !self.pass_timing && message.bytes.first().is_some_and(|status| TIMING_FLOOD_BYTES.contains(status)) {
+            return false;
+        }
+        let kind_ok = self.kinds.is_empty() || self.kinds.contains(&message.kind);
+        let channel_ok = match (self.channel, message.channel) {
+            (Some(wanted), Some(channel)) => wanted == channel,
+            _ => true,
+        };
+        kind_ok && channel_ok
+    }
+}
+
+/// One message seen by a monitor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonitoredMessage {
+    /// Milliseconds since the monitor was connected
+    pub time_ms: f64,
+    pub kind: MessageKind,
+    /// 0-indexed channel, None for system messages and SysEx
+    pub channel: Option<u8>,
+    pub bytes: Vec<u8>,
+    /// Readable form, e.g. "Note On  Ch:1 Note:60(C4) Vel:100"
+    pub description: String,
+}
+
+impl MonitoredMessage {
+    /// None for empty or unclassifiable (stray data byte) messages
+    pub fn new(time_ms: f64, bytes: &[u8]) -> Option<Self> {
+        let (kind, channel) = MessageKind::classify(bytes)?;
+        Some(Self { time_ms, kind, channel, bytes: bytes.to_vec(), description: describe_message(bytes) })
+    }
+}
+
+/// Messages kept from a monitor run, in arrival order
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MidiCapture {
+    /// Input the messages came from
+    #[serde(default)]
+    pub device: Option<String>,
+    pub messages: Vec<MonitoredMessage>,
+}
+
+impl MidiCapture {
+    pub fn new(device: Option<String>) -> Self {
+        Self { device, messages: Vec::new() }
+    }
+
+    pub fn push(&mut self, message: MonitoredMessage) {
+        self.messages.push(message);
+    }
+
+    /// Write as JSON (.json) or a Standard MIDI File (.mid/.midi), by extension
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default().to_lowercase();
+        let data = match extension.as_str() {
+            "json" => serde_json::to_vec_pretty(self)
+                .map_err(|e| BatcherbirdError::Session(format!("Failed to serialize MIDI capture: {}", e)))?,
+            "mid" | "midi" | "smf" => self.to_smf(),
+            _ => return Err(BatcherbirdError::Config(format!(
+                "Can't tell the capture format from {} (use .json or .mid)", path.display()
+            ))),
+        };
+        std::fs::write(path, data).map_err(BatcherbirdError::Export)
+    }
+
+    /// Format 0 Standard MIDI File at 120 BPM. System messages other than SysEx are stored
+    /// as escape events, so they survive the round trip but most sequencers ignore them.
+    pub fn to_smf(&self) -> Vec<u8> {
+        let ticks_per_ms = SMF_TICKS_PER_QUARTER as f64 * 1000.0 / SMF_MICROS_PER_QUARTER as f64;
+        let mut track = Vec::new();
+        // Set Tempo first so readers don't depend on the 120 BPM default
+        track.extend_from_slice(&[0x00, 0xFF, 0x51, 0x03]);
+        track.extend_from_slice(&SMF_MICROS_PER_QUARTER.to_be_bytes()[1..]);
+
+        let mut last_tick = 0u64;
+        for message in &self.messages {
+            let tick = ((message.time_ms.max(0.0) * ticks_per_ms).round() as u64).max(last_tick);
+            write_vlq(&mut track, (tick - last_tick) as u32);
+            last_tick = tick;
+            match message.kind {
+                MessageKind::SysEx => {
+                    track.push(0xF0);
+                    write_vlq(&mut track, (message.bytes.len() - 1) as u32);
+                    track.extend_from_slice(&message.bytes[1..]);
+                }
+                MessageKind::System => {
+                    track.push(0xF7);
+                    write_vlq(&mut track, message.bytes.len() as u32);
+                    track.extend_from_slice(&message.bytes);
+                }
+                _ => track.extend_from_slice(&message.bytes),
+            }
+        }
+        track.extend_from_slice(&[0x00, 0xFF, 0x2F, 0x00]);
+
+        let mut data = Vec::with_capacity(track.len() + 22);
+        data.extend_from_slice(b"MThd");
+        data.extend_from_slice(&6u32.to_be_bytes());
+        data.extend_from_slice(&0u16.to_be_bytes());
+        data.extend_from_slice(&1u16.to_be_bytes());
+        data.extend_from_slice(&SMF_TICKS_PER_QUARTER.to_be_bytes());
+        data.extend_from_slice(b"MTrk");
+        data.extend_from_slice(&(track.len() as u32).to_be_bytes());
+        data.extend_from_slice(&track);
+        data
+    }
+}
+
+/// Readable one-line form of a raw MIDI message
+pub fn describe_message(message: &[u8]) -> String {
+    let Some(&status) = message.first() else {
+        return String::new();
+    };
+    let channel = (status & 0x0F) + 1;
+    match (status & 0xF0, message) {
+        (0x90, [_, note, velocity, ..]) if *velocity > 0 => {
+            format!("Note On  Ch:{} Note:{}({}) Vel:{}", channel, note, note_to_name(*note), velocity)
+        }
+        // Note off or note on with vel 0
+        (0x80 | 0x90, [_, note, velocity, ..]) => {
+            format!("Note Off Ch:{} Note:{}({}) Vel:{}", channel, note, note_to_name(*note), velocity)
+        }
+        (0xB0, [_, controller, value, ..]) => format!("CC       Ch:{} CC:{} Val:{}", channel, controller, value),
+        (0xC0, [_, program, ..]) => format!("Program  Ch:{} Prog:{}", channel, program),
+        (0xE0, [_, lsb, msb, ..]) => {
+            let bend = ((*msb as i32) << 7 | *lsb as i32) - 8192;
+            format!("Bend     Ch:{} Val:{:+}", channel, bend)
+        }
+        (0xF0, _) if status == 0xF0 => format!("SysEx    {} bytes", message.len()),
+        _ => {
+            let hex_msg: Vec<String> = message.iter().map(|b| format!("{:02X}", b)).collect();
+            format!("Raw      {}", hex_msg.join(" "))
+        }
+    }
+}
+
+fn write_vlq(out: &mut Vec<u8>, mut value: u32) {
+    let mut bytes = vec![(value & 0x7F) as u8];
+    value >>= 7;
+    while value > 0 {
+        bytes.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    out.extend(bytes.iter().rev());
+}
+
+fn note_to_name(note: u8) -> String {
+    let note_names = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+    let octave = (note / 12).saturating_sub(1);
+    let note_name = note_names[(note % 12) as usize];
+    format!("{}{}", note_name, octave)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::midi_file::MidiFile;
+
+    #[test]
+    fn test_filter_and_save_as_midi_file() {
+        let incoming: [(f64, &[u8]); 7] = [
+            (0.0, &[0x90, 60, 100]),
+            (2.0, &[0xF8]),
+            (3.0, &[0xFA]),
+            (250.0, &[0xB1, 74, 64]),
+            (500.0, &[0x80, 60, 0]),
+            (750.0, &[0xF0, 0x7E, 0x7F, 0x06, 0x01, 0xF7]),
+            (1000.0, &[0xE0, 0x00, 0x40]),
+        ];
+        let keep = |filter: &MonitorFilter| -> Vec<MonitoredMessage> {
+            incoming.iter()
+                .filter_map(|&(time_ms, bytes)| MonitoredMessage::new(time_ms, bytes))
+                .filter(|message| filter.accepts(message))
+                .collect()
+        };
+
+        // The default drops only the clock byte; Start still gets through
+        let all = keep(&MonitorFilter::default());
+        assert_eq!(all.len(), 6);
+        assert_eq!(all[0].description, "Note On  Ch:1 Note:60(C4) Vel:100");
+        assert_eq!(all[1].bytes, vec![0xFA]);
+        assert_eq!(all[5].description, "Bend     Ch:1 Val:+0");
+        let notes_on_1 = MonitorFilter { kinds: MonitorFilter::parse_kinds("notes, cc").unwrap(), channel: Some(0), ..Default::default() };
+        assert_eq!(keep(&notes_on_1).len(), 2);
+        let system = MonitorFilter { kinds: vec![MessageKind::System, MessageKind::SysEx], channel: Some(5), ..Default::default() };
+        assert_eq!(keep(&system).len(), 2);
+        let system = MonitorFilter { pass_timing: true, ..system };
+        assert_eq!(keep(&system).len(), 3);
+        assert!(MonitorFilter::parse_kinds("notes,clock").is_err());
+        assert_eq!(MonitoredMessage::new(0.0, &[0x40, 0x00]), None);
+
+        let mut capture = MidiCapture::new(Some("Keystep".to_string()));
+        let clock = keep(&system)[0].clone();
+        for message in all.iter().chain([&clock]) {
+            capture.push(message.clone());
+        }
+        capture.messages.sort_by(|a, b| a.time_ms.total_cmp(&b.time_ms));
+        let file = MidiFile::parse(&capture.to_smf()).unwrap();
+        let events: Vec<(u64, Vec<u8>)> = file.events.iter()
+            .map(|event| ((event.time.as_secs_f64() * 1000.0).round() as u64, event.message.clone()))
+            .collect();
+        let expected: Vec<(u64, Vec<u8>)> = capture.messages.iter()
+            .map(|message| (message.time_ms.round() as u64, message.bytes.clone()))
+            .collect();
+        assert_eq!(events, expected);
+        assert_eq!(file.note_count(), 1);
+
+        let json = serde_json::to_string(&capture).unwrap();
+        let back: MidiCapture = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.messages, capture.messages);
+        assert!(json.contains("\"kind\":\"control_change\""));
+        assert!(capture.save(Path::new("capture.txt")).is_err());
+    }
+}
@@ -0,0 +1,341 @@
+//! Stereo pairs recorded across two audio devices, for setups where one interface has the
+//! mic inputs and another the line input. Each device runs on its own clock, so the second
+//! device's channel is resampled onto the main input's timeline: the rate ratio is measured
+//! from how many frames both deliver while recording (kept within the drift a real clock can
+//! have), and the start offset left by the two streams' buffering is found by
+//! cross-correlation when both sides carry the same synth.
+//!
+//! A CoreAudio aggregate device needs none of this: with drift correction ticked in Audio
+//! MIDI Setup it shows up as one input carrying the channels of both devices, and is picked
+//! like any other input (see `AudioManager::set_preferred_input`).
+
+use crate::{BatcherbirdError, Result};
+use crate::audio::AudioManager;
+use cpal::traits::{DeviceTrait, StreamTrait};
+use cpal::{FromSample, Sample, SizedSample};
+use std::sync::{Arc, Mutex};
+use tracing::{error, info};
+
+/// Largest clock difference between the devices believed; estimates beyond it are noise from
+/// too little audio, and are clamped
+const MAX_DRIFT_PPM: f64 = 300.0;
+/// Recorded frames (at the main rate) the drift estimate needs before it is used
+const MIN_DRIFT_FRAMES: f64 = 48000.0;
+/// Widest start offset looked for between the two sides
+const MAX_LAG_MS: u32 = 25;
+/// Audio compared when looking for the offset, from the first audible frame
+const ALIGN_WINDOW_MS: u32 = 250;
+/// Correlation below this means the sides carry different sounds; they are then left unshifted
+const MIN_CORRELATION: f32 = 0.5;
+
+/// The device and channels making up the pair: the main input's channel is the left side,
+/// the second device's the right
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecondaryInputConfig {
+    /// Part of the second device's name
+    pub device: String,
+    /// 0-indexed channel of the second device
+    pub channel: u16,
+    /// 0-indexed channel of the main input
+    pub primary_channel: u16,
+}
+
+impl SecondaryInputConfig {
+    /// "DEVICE" or "DEVICE:INPUT" (1-based input on the second device); the left side is the
+    /// main input's first channel
+    pub fn parse(value: &str) -> Option<Self> {
+        let (device, channel) = match value.rsplit_once(':') {
+            Some((device, input)) => (device, input.trim().parse::<u16>().ok().filter(|&input| input >= 1)? - 1),
+            None => (value, 0),
+        };
+        let device = device.trim();
+        (!device.is_empty()).then(|| Self { device: device.to_string(), channel, primary_channel: 0 })
+    }
+}
+
+/// Frames each side delivered while recording, summed over the takes of a session
+#[derive(Debug, Default)]
+struct DriftTracker {
+    /// (main frames, second frames) at the first and latest callback of the current take
+    take: Option<((usize, usize), (usize, usize))>,
+    primary_total: f64,
+    secondary_total: f64,
+}
+
+impl DriftTracker {
+    fn observe(&mut self, primary: usize, secondary: usize) {
+        self.take = Some(match self.take {
+            Some((first, _)) => (first, (primary, secondary)),
+            None => ((primary, secondary), (primary, secondary)),
+        });
+    }
+
+    /// Fold the take into the totals; returns where the take's first observation put the
+    /// main frame count against the second's
+    fn finish_take(&mut self) -> Option<(usize, usize)> {
+        let (first, last) = self.take.take()?;
+        self.primary_total += last.0.saturating_sub(first.0) as f64;
+        self.secondary_total += last.1.saturating_sub(first.1) as f64;
+        Some(first)
+    }
+
+    /// Second-device frames per main frame
+    fn ratio(&self, nominal: f64) -> f64 {
+        if self.primary_total < MIN_DRIFT_FRAMES || self.secondary_total <= 0.0 {
+            return nominal;
+        }
+        let drift = (self.secondary_total / self.primary_total / nominal - 1.0)
+            .clamp(-MAX_DRIFT_PPM * 1e-6, MAX_DRIFT_PPM * 1e-6);
+        nominal * (1.0 + drift)
+    }
+}
+
+/// The second device's stream within a capture session. Its channel is collected while the
+/// session records, and merged with the main take into a stereo pair.
+pub(crate) struct SecondaryCapture {
+    stream: cpal::Stream,
+    buffer: Arc<Mutex<Vec<f32>>>,
+    drift: Arc<Mutex<DriftTracker>>,
+    config: SecondaryInputConfig,
+    primary_rate: u32,
+    /// Nominal second-device frames per main frame
+    nominal_ratio: f64,
+}
+
+impl SecondaryCapture {
+    /// Start the second device alongside a session's main stream, recording whenever
+    /// `recording` is set. `primary` is the main take buffer, read to relate the two clocks.
+    pub(crate) fn open(
+        audio_manager: &AudioManager,
+        config: SecondaryInputConfig,
+        primary: Arc<Mutex<Vec<f32>>>,
+        primary_channels: u16,
+        primary_rate: u32,
+        recording: Arc<Mutex<bool>>,
+    ) -> Result<Self> {
+        if config.primary_channel >= primary_channels {
+            return Err(BatcherbirdError::Config(format!(
+                "Main input channel {} doesn't exist; the input records {} channels", config.primary_channel + 1, primary_channels
+            )));
+        }
+        let device = audio_manager.input_device_named(&config.device)?;
+        let device_config = device.default_input_config()
+            .map_err(|e| BatcherbirdError::Audio(format!("Failed to get input config of '{}': {}", config.device, e)))?;
+        let channels = device_config.channels();
+        if config.channel >= channels {
+            return Err(BatcherbirdError::Config(format!(
+                "'{}' has {} input channels, not {}", config.device, channels, config.channel + 1
+            )));
+        }
+
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let drift = Arc::new(Mutex::new(DriftTracker::default()));
+        let side = SideInput {
+            buffer: Arc::clone(&buffer),
+            drift: Arc::clone(&drift),
+            primary,
+            primary_channels: primary_channels.max(1) as usize,
+            recording,
+            channels: channels as usize,
+            channel: config.channel as usize,
+        };
+        let stream_config: cpal::StreamConfig = device_config.clone().into();
+        let stream = match device_config.sample_format() {
+            cpal::SampleFormat::F32 => side.build::<f32>(&device, &stream_config),
+            cpal::SampleFormat::I16 => side.build::<i16>(&device, &stream_config),
+            cpal::SampleFormat::U16 => side.build::<u16>(&device, &stream_config),
+            cpal::SampleFormat::I32 => side.build::<i32>(&device, &stream_config),
+            format => return Err(BatcherbirdError::Audio(format!("Unsupported sample format: {:?}", format))),
+        }?;
+        stream.play()
+            .map_err(|e| BatcherbirdError::Audio(format!("Failed to start second input: {}", e)))?;
+
+        let rate = device_config.sample_rate().0;
+        info!("🔀 Second input: {} channel {} ({} Hz) paired with main channel {} ({} Hz)",
+            device.name().unwrap_or(config.device.clone()), config.channel + 1, rate, config.primary_channel + 1, primary_rate);
+        Ok(Self { stream, buffer, drift, config, primary_rate, nominal_ratio: rate as f64 / primary_rate as f64 })
+    }
+
+    /// Empty the buffer before a take
+    pub(crate) fn clear(&self) {
+        self.buffer.lock().unwrap().clear();
+        self.drift.lock().unwrap().take = None;
+    }
+
+    /// The main take (interleaved, `channels` wide) as a stereo pair: its paired channel on
+    /// the left, the second device's channel on the right, resampled onto the same frames
+    pub(crate) fn merge(&self, primary: &[f32], channels: u16) -> Vec<f32> {
+        let channels = channels.max(1) as usize;
+        let left: Vec<f32> = primary.chunks_exact(channels).map(|frame| frame[self.config.primary_channel as usize]).collect();
+        let secondary = self.buffer.lock().unwrap().clone();
+
+        let (ratio, anchor) = {
+            let mut drift = self.drift.lock().unwrap();
+            let anchor = drift.finish_take();
+            (drift.ratio(self.nominal_ratio), anchor)
+        };
+        // Where main frame 0 falls in the second buffer
+        let mut start = anchor.map_or(0.0, |(primary_frame, secondary_frame)| secondary_frame as f64 - primary_frame as f64 * ratio);
+        let right = resample(&secondary, start, ratio, left.len());
+        let frames_in = |ms: u32| (ms as u64 * self.primary_rate as u64 / 1000) as usize;
+        if let Some(lag) = best_lag(&left, &right, frames_in(MAX_LAG_MS), frames_in(ALIGN_WINDOW_MS)) {
+            start += lag as f64 * ratio;
+        }
+        let right = resample(&secondary, start, ratio, left.len());
+        info!("   🔀 Paired {} frames with the second input ({:+.0} ppm)", left.len(), (ratio / self.nominal_ratio - 1.0) * 1e6);
+
+        left.iter().zip(&right).flat_map(|(&l, &r)| [l, r]).collect()
+    }
+
+    pub(crate) fn pause(&self) {
+        let _ = self.stream.pause();
+    }
+}
+
+/// What the second device's callback needs
+struct SideInput {
+    buffer: Arc<Mutex<Vec<f32>>>,
+    drift: Arc<Mutex<DriftTracker>>,
+    primary: Arc<Mutex<Vec<f32>>>,
+    primary_channels: usize,
+    recording: Arc<Mutex<bool>>,
+    channels: usize,
+    channel: usize,
+}
+
+impl SideInput {
+    fn build<T>(self, device: &cpal::Device, config: &cpal::StreamConfig) -> Result<cpal::Stream>
+    where
+        T: SizedSample,
+        f32: FromSample<T>,
+    {
+        device.build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                if !*self.recording.lock().unwrap() {
+                    return;
+                }
+                let mut buffer = self.buffer.lock().unwrap();
+                buffer.extend(data.chunks_exact(self.channels).map(|frame| f32::from_sample(frame[self.channel])));
+                let primary_frames = self.primary.lock().unwrap().len() / self.primary_channels;
+                self.drift.lock().unwrap().observe(primary_frames, buffer.len());
+            },
+            |err| error!("Second input error: {}", err),
+            None,
+        ).map_err(|e| BatcherbirdError::Audio(format!("Failed to build second input stream: {}", e)))
+    }
+}
+
+/// `frames` values read from `input` at `start`, `start + step`, ... by linear interpolation
+/// (silence outside it)
+fn resample(input: &[f32], start: f64, step: f64, frames: usize) -> Vec<f32> {
+    (0..frames)
+        .map(|frame| {
+            let position = start + frame as f64 * step;
+            if position < 0.0 {
+                return 0.0;
+            }
+            let index = position as usize;
+            let fraction = (position - index as f64) as f32;
+            match (input.get(index), input.get(index + 1)) {
+                (Some(&a), Some(&b)) => a + (b - a) * fraction,
+                (Some(&a), None) => a,
+                _ => 0.0,
+            }
+        })
+        .collect()
+}
+
+/// Frames `other` trails `reference` by (positive: `other` should be read that much later),
+/// comparing `window` frames from the first audible part of `reference`; None when the two
+/// don't look alike
+fn best_lag(reference: &[f32], other: &[f32], max_lag: usize, window: usize) -> Option<isize> {
+    let onset = reference.iter().position(|sample| sample.abs() > 0.01)?;
+    let window = window.min(reference.len() - onset);
+    let reference = &reference[onset..onset + window];
+    let energy = |samples: &[f32]| samples.iter().map(|s| s * s).sum::<f32>().sqrt();
+    let reference_energy = energy(reference);
+
+    let max_lag = max_lag as isize;
+    (-max_lag..=max_lag)
+        .filter_map(|lag| {
+            let from = onset as isize - lag;
+            if from < 0 || from as usize + window > other.len() {
+                return None;
+            }
+            let candidate = &other[from as usize..from as usize + window];
+            let norm = reference_energy * energy(candidate);
+            (norm > 0.0).then(|| (lag, reference.iter().zip(candidate).map(|(a, b)| a * b).sum::<f32>() / norm))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .filter(|&(_, correlation)| correlation >= MIN_CORRELATION)
+        .map(|(lag, _)| -lag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(frames: usize, start: f64, step: f64) -> Vec<f32> {
+        (0..frames).map(|i| {
+            let t = start + i as f64 * step;
+            // A decaying pluck with some harmonics, silent before t = 0
+            if t < 0.0 { 0.0 } else { ((t * 0.0523).sin() * 0.5 + (t * 0.131).sin() * 0.3) as f32 * (-t / 40000.0).exp() as f32 }
+        }).collect()
+    }
+
+    #[test]
+    fn test_drift_is_measured_and_bounded() {
+        let mut drift = DriftTracker::default();
+        // Too little audio yet: nominal
+        drift.observe(0, 0);
+        drift.observe(1000, 1001);
+        drift.finish_take();
+        assert_eq!(drift.ratio(1.0), 1.0);
+
+        // 100 ppm fast over three takes
+        for _ in 0..3 {
+            drift.observe(5000, 7000);
+            drift.observe(5000 + 100_000, 7000 + 100_010);
+            assert_eq!(drift.finish_take(), Some((5000, 7000)));
+        }
+        assert!((drift.ratio(1.0) - 1.0001).abs() < 5e-6, "{}", drift.ratio(1.0));
+
+        // Wild estimates are clamped
+        let mut wild = DriftTracker::default();
+        wild.observe(0, 0);
+        wild.observe(100_000, 101_000);
+        wild.finish_take();
+        assert!((wild.ratio(1.0) - (1.0 + MAX_DRIFT_PPM * 1e-6)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_second_side_is_resampled_and_aligned() {
+        // The second device runs 200 ppm fast and its take started 300 frames early
+        let left = tone(48000, -2000.0, 1.0);
+        let ratio = 1.0002;
+        let secondary: Vec<f32> = tone(60000, -2000.0 - 300.0 / ratio, 1.0 / ratio);
+
+        let resampled = resample(&secondary, 0.0, ratio, left.len());
+        let lag = best_lag(&left, &resampled, 1200, 12000).unwrap();
+        assert!((lag - 300).abs() <= 1, "{}", lag);
+
+        let aligned = resample(&secondary, lag as f64 * ratio, ratio, left.len());
+        let error = left.iter().zip(&aligned).map(|(a, b)| (a - b).abs()).fold(0.0f32, f32::max);
+        assert!(error < 0.02, "{}", error);
+
+        // Unrelated sides aren't shifted
+        let noise: Vec<f32> = (0..48000).map(|i| ((i * 7919 % 1000) as f32 / 500.0 - 1.0) * 0.3).collect();
+        assert_eq!(best_lag(&left, &noise, 1200, 12000), None);
+        assert_eq!(resample(&[1.0, 3.0], -1.0, 0.5, 5), vec![0.0, 0.0, 1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_parse_second_input() {
+        assert_eq!(SecondaryInputConfig::parse("USB Audio CODEC:2"),
+            Some(SecondaryInputConfig { device: "USB Audio CODEC".to_string(), channel: 1, primary_channel: 0 }));
+        assert_eq!(SecondaryInputConfig::parse("Line In").map(|config| config.channel), Some(0));
+        assert_eq!(SecondaryInputConfig::parse("Line In:0"), None);
+        assert_eq!(SecondaryInputConfig::parse(":2"), None);
+    }
+}
@@ -0,0 +1,244 @@
+//! File name templates such as `{synth}_{note_name}_{velocity}_{rr}.wav`. A pattern is parsed
+//! once, so unknown tokens and stray braces are reported before recording, and a batch's names
+//! can be checked for collisions before any file is written. Patterns also read names back,
+//! for mapping existing libraries.
+
+use crate::{BatcherbirdError, Result};
+use std::collections::HashMap;
+
+/// Tokens a naming pattern may use
+pub const TOKENS: &[&str] = &[
+    "note",         // 60
+    "note_name",    // C4 (or the drum name in drum mode)
+    "velocity",     // vel127
+    "timestamp",    // 20240131_142501
+    "date",         // 2024-01-31
+    "sample_rate",  // 48000
+    "cc",           // cc74_064
+    "rr",           // rr2
+    "legato",       // from_C4
+    "release",      // rel
+    "take",         // take2
+    "input",        // di, amp
+    "channel",      // ch01
+    "patch",        // prog012
+    "articulation", // sustain, release, legato
+    "session",      // session name, or the output folder's name
+    "synth",        // synth name, e.g. the profile's
+];
+
+/// Tokens with the same value for every file of a session
+pub const SESSION_TOKENS: &[&str] = &["session", "synth", "date"];
+
+/// Values of the session-wide tokens
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NamingContext {
+    /// `{session}` (None = the output folder's name)
+    pub session: Option<String>,
+    /// `{synth}` (None = empty)
+    pub synth: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Text(String),
+    Token(&'static str),
+}
+
+/// A parsed naming pattern
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamingPattern {
+    segments: Vec<Segment>,
+}
+
+impl NamingPattern {
+    pub fn parse(pattern: &str) -> Result<Self> {
+        let invalid = |reason: String| BatcherbirdError::Config(format!("Invalid naming pattern '{}': {}", pattern, reason));
+        if pattern.contains(['/', '\\']) {
+            return Err(invalid("must be a file name, not a path".to_string()));
+        }
+
+        let mut segments = Vec::new();
+        let mut rest = pattern;
+        while let Some(open) = rest.find(['{', '}']) {
+            if rest[open..].starts_with('}') {
+                return Err(invalid("'}' without a matching '{'".to_string()));
+            }
+            if open > 0 {
+                segments.push(Segment::Text(rest[..open].to_string()));
+            }
+            let close = rest[open..].find('}')
+                .map(|close| open + close)
+                .ok_or_else(|| invalid("'{' is never closed".to_string()))?;
+            let name = &rest[open + 1..close];
+            let token = TOKENS.iter().find(|&&token| token == name)
+                .ok_or_else(|| invalid(format!("unknown token {{{}}} (available: {})", name, TOKENS.join(", "))))?;
+            segments.push(Segment::Token(token));
+            rest = &rest[close + 1..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Text(rest.to_string()));
+        }
+        if segments.is_empty() {
+            return Err(invalid("empty".to_string()));
+        }
+        Ok(Self { segments })
+    }
+
+    pub fn contains(&self, token: &str) -> bool {
+        self.segments.iter().any(|segment| matches!(segment, Segment::Token(name) if *name == token))
+    }
+
+    /// Substitute every token with `value(token)`
+    pub fn render(&self, mut value: impl FnMut(&str) -> String) -> String {
+        self.segments.iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text.clone(),
+                Segment::Token(token) => value(token),
+            })
+            .collect()
+    }
+
+    /// Token values of a name this pattern could have rendered, e.g. `note_name` = "C4" for
+    /// "Pad_C4.wav" under "{synth}_{note_name}.wav". A token's value is taken as short as the
+    /// rest of the name allows; a token used twice must have the same value both times.
+    pub fn parse_name(&self, name: &str) -> Option<HashMap<&'static str, String>> {
+        let mut values = Vec::new();
+        match_segments(&self.segments, name, &mut values)
+            .then(|| values.into_iter().map(|(token, value)| (token, value.to_string())).collect())
+    }
+}
+
+fn match_segments<'a>(segments: &[Segment], rest: &'a str, values: &mut Vec<(&'static str, &'a str)>) -> bool {
+    match segments.split_first() {
+        None => rest.is_empty(),
+        Some((Segment::Text(text), tail)) => rest.strip_prefix(text.as_str())
+            .is_some_and(|rest| match_segments(tail, rest, values)),
+        Some((Segment::Token(token), tail)) => {
+            let earlier = values.iter().find(|(name, _)| name == token).map(|&(_, value)| value);
+            for end in (0..=rest.len()).filter(|&end| rest.is_char_boundary(end)) {
+                let value = &rest[..end];
+                if !token_accepts(token, value) || earlier.is_some_and(|earlier| earlier != value) {
+                    continue;
+                }
+                values.push((token, value));
+                if match_segments(tail, &rest[end..], values) {
+                    return true;
+                }
+                values.pop();
+            }
+            false
+        }
+    }
+}
+
+/// Whether `value` could be what `token` renders to. Tokens for things a take may not have
+/// (round robin, CC layer, ...) render empty when it doesn't.
+fn token_accepts(token: &str, value: &str) -> bool {
+    let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let prefixed = |prefix: &str| value.strip_prefix(prefix).is_some_and(digits);
+    let note_name = |s: &str| {
+        let mut chars = s.chars();
+        let octave = chars.next().is_some_and(|c| ('A'..='G').contains(&c))
+            .then(|| chars.as_str().trim_start_matches(['#', 'b']).trim_start_matches('-'));
+        octave.is_some_and(|octave| digits(octave) && s.len() - octave.len() <= 3)
+    };
+    let optional = value.is_empty();
+
+    match token {
+        "note" | "sample_rate" => digits(value),
+        "velocity" => digits(value) || prefixed("vel"),
+        "note_name" => note_name(value),
+        "timestamp" => value.split_once('_').is_some_and(|(date, time)| date.len() == 8 && digits(date) && time.len() == 6 && digits(time)),
+        "date" => value.len() == 10 && value.split('-').map(str::len).eq([4, 2, 2]) && digits(&value.replace('-', "")),
+        "cc" => optional || value.strip_prefix("cc").and_then(|cc| cc.split_once('_')).is_some_and(|(controller, level)| digits(controller) && digits(level)),
+        "rr" => optional || prefixed("rr"),
+        "take" => optional || prefixed("take"),
+        "channel" => optional || prefixed("ch"),
+        "patch" => optional || prefixed("prog"),
+        "legato" => optional || value.strip_prefix("from_").is_some_and(note_name),
+        "release" => optional || value == "rel",
+        "articulation" => matches!(value, "sustain" | "release" | "legato"),
+        // input, session, synth: free text, but never the extension
+        _ => !value.contains('.'),
+    }
+}
+
+/// Names used by more than one entry, each with the indices using it. Names are compared
+/// case-insensitively, as macOS and Windows file systems do.
+pub fn find_collisions(names: &[String]) -> Vec<(String, Vec<usize>)> {
+    let mut by_name: HashMap<String, Vec<usize>> = HashMap::new();
+    for (index, name) in names.iter().enumerate() {
+        by_name.entry(name.to_lowercase()).or_default().push(index);
+    }
+    let mut collisions: Vec<(String, Vec<usize>)> = by_name.into_values()
+        .filter(|indices| indices.len() > 1)
+        .map(|indices| (names[indices[0]].clone(), indices))
+        .collect();
+    collisions.sort_by_key(|(_, indices)| indices[0]);
+    collisions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_render() {
+        let pattern = NamingPattern::parse("{synth}_{note_name}_{velocity}_{rr}.wav").unwrap();
+        assert!(pattern.contains("rr") && pattern.contains("synth") && !pattern.contains("cc"));
+        let name = pattern.render(|token| match token {
+            "synth" => "Juno".to_string(),
+            "note_name" => "C4".to_string(),
+            "velocity" => "vel127".to_string(),
+            _ => "rr2".to_string(),
+        });
+        assert_eq!(name, "Juno_C4_vel127_rr2.wav");
+
+        // Substituted values are not scanned for tokens again
+        let pattern = NamingPattern::parse("{session}.wav").unwrap();
+        assert_eq!(pattern.render(|_| "{note}".to_string()), "{note}.wav");
+        assert_eq!(NamingPattern::parse("plain.wav").unwrap().render(|_| unreachable!()), "plain.wav");
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_patterns() {
+        for bad in ["{nope}.wav", "{note.wav", "note}.wav", "takes/{note}.wav", ""] {
+            assert!(NamingPattern::parse(bad).is_err(), "{}", bad);
+        }
+        let error = NamingPattern::parse("{patchname}.wav").unwrap_err().to_string();
+        assert!(error.contains("{patchname}") && error.contains("articulation"), "{}", error);
+    }
+
+    #[test]
+    fn test_find_collisions() {
+        let names: Vec<String> = ["C4_vel127.wav", "D4_vel127.wav", "c4_VEL127.wav", "E4.wav", "E4.wav"]
+            .iter().map(|name| name.to_string()).collect();
+        assert_eq!(find_collisions(&names), vec![
+            ("C4_vel127.wav".to_string(), vec![0, 2]),
+            ("E4.wav".to_string(), vec![3, 4]),
+        ]);
+        assert!(find_collisions(&names[..2]).is_empty());
+    }
+
+    #[test]
+    fn test_parse_name() {
+        let pattern = NamingPattern::parse("{synth}_{note_name}_{note}_{velocity}_{rr}.wav").unwrap();
+        let values = pattern.parse_name("Roland_EM-1014_C#4_61_vel100_rr2.wav").unwrap();
+        assert_eq!(values["synth"], "Roland_EM-1014");
+        assert_eq!(values["note_name"], "C#4");
+        assert_eq!((values["note"].as_str(), values["velocity"].as_str(), values["rr"].as_str()), ("61", "vel100", "rr2"));
+        // Tokens a take doesn't have render empty
+        assert_eq!(pattern.parse_name("Juno_Eb2_39_vel064_.wav").unwrap()["rr"], "");
+        assert!(pattern.parse_name("Juno_Eb2_39_vel064.wav").is_none());
+        assert!(pattern.parse_name("Juno_H2_39_vel064_.wav").is_none());
+
+        let pattern = NamingPattern::parse("{note_name}_v{velocity}_rk{note}.wav").unwrap();
+        let values = pattern.parse_name("F4_v127_rk65.wav").unwrap();
+        assert_eq!((values["velocity"].as_str(), values["note"].as_str()), ("127", "65"));
+
+        let pattern = NamingPattern::parse("{note}-{note}.wav").unwrap();
+        assert!(pattern.parse_name("60-60.wav").is_some());
+        assert!(pattern.parse_name("60-62.wav").is_none());
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{Result, BatcherbirdError};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Folder created inside the chosen base directory when no explicit output is given
+pub const DEFAULT_FOLDER_NAME: &str = "Batcherbird Samples";
+
+/// Output directory chosen for an export, and whether we had to fall back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedOutput {
+    /// Directory that was verified writable and should be used
+    pub path: PathBuf,
+    /// Directory the user asked for (if any)
+    pub requested: Option<PathBuf>,
+    /// True when the requested/default location was not writable (e.g. sandbox denied Desktop)
+    pub used_fallback: bool,
+}
+
+/// Candidate base locations in order of preference.
+/// Desktop comes first (historical default); the rest stay inside locations a
+/// sandboxed app can normally write without user-granted access.
+pub fn fallback_output_directories() -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+
+    if let Some(desktop) = dirs::desktop_dir() {
+        candidates.push(desktop.join(DEFAULT_FOLDER_NAME));
+    }
+    if let Some(music) = dirs::audio_dir() {
+        candidates.push(music.join(DEFAULT_FOLDER_NAME));
+    }
+    if let Some(documents) = dirs::document_dir() {
+        candidates.push(documents.join(DEFAULT_FOLDER_NAME));
+    }
+    // Inside a macOS sandbox this resolves to the app container, which is always writable
+    if let Some(data) = dirs::data_dir() {
+        candidates.push(data.join("Batcherbird").join("Samples"));
+    }
+    candidates.push(std::env::temp_dir().join(DEFAULT_FOLDER_NAME));
+
+    candidates
+}
+
+/// Default output directory (first candidate, without checking writability)
+pub fn default_output_directory() -> PathBuf {
+    fallback_output_directories()
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| PathBuf::from("samples"))
+}
+
+/// Create the directory if needed and prove we can write into it.
+/// A sandbox denial often only shows up on the first file write, so we probe with a real file.
+pub fn is_writable(dir: &Path) -> bool {
+    if std::fs::create_dir_all(dir).is_err() {
+        return false;
+    }
+
+    let probe = dir.join(".batcherbird_write_test");
+    match std::fs::write(&probe, b"ok") {
+        Ok(_) => {
+            let _ = std::fs::remove_file(&probe);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Resolve where exports should go: the requested directory when writable,
+/// otherwise the first writable fallback location.
+pub fn resolve_output_directory(requested: Option<&str>) -> Result<ResolvedOutput> {
+    let requested = requested
+        .map(str::trim)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from);
+
+    if let Some(ref dir) = requested {
+        if is_writable(dir) {
+            return Ok(ResolvedOutput {
+                path: dir.clone(),
+                requested: requested.clone(),
+                used_fallback: false,
+            });
+        }
+        warn!("⚠️ Output directory not writable (sandbox or permissions): {}", dir.display());
+    }
+
+    for (index, candidate) in fallback_output_directories().into_iter().enumerate() {
+        if is_writable(&candidate) {
+            // Falling back from the default Desktop location counts as a fallback too
+            let used_fallback = requested.is_some() || index > 0;
+            if used_fallback {
+                info!("📁 Using fallback output directory: {}", candidate.display());
+            }
+            return Ok(ResolvedOutput {
+                path: candidate,
+                requested,
+                used_fallback,
+            });
+        }
+    }
+
+    Err(BatcherbirdError::Export(std::io::Error::new(
+        std::io::ErrorKind::PermissionDenied,
+        "No writable output directory available",
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_writable_requested_directory_is_used() {
+        let dir = std::env::temp_dir().join("batcherbird_test_output_location");
+        let resolved = resolve_output_directory(Some(dir.to_str().unwrap())).unwrap();
+
+        assert_eq!(resolved.path, dir);
+        assert!(!resolved.used_fallback);
+        // The write probe must not be left behind
+        assert!(!dir.join(".batcherbird_write_test").exists());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
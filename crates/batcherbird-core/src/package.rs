@@ -0,0 +1,271 @@
+//! Distributable archives of an exported instrument folder: the WAVs, instrument files and
+//! README (generated if the folder has none) plus an optional license, in a plain .zip or a
+//! Decent Sampler .dslibrary.
+
+use crate::{Result, BatcherbirdError};
+use crate::export::AudioFormat;
+use crate::instrument_format;
+use crate::library::LibrarySession;
+use crate::readme::{ReadmeConfig, ReadmeContext, DEFAULT_README_TEMPLATE};
+use chrono::{Datelike, Local, Timelike};
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PackageFormat {
+    #[default]
+    Zip,
+    /// Decent Sampler library: a zip it installs from File > Install Library
+    DsLibrary,
+}
+
+impl PackageFormat {
+    /// "zip" or "dslibrary"
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "zip" => Some(PackageFormat::Zip),
+            "dslibrary" | "decentsampler" => Some(PackageFormat::DsLibrary),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            PackageFormat::Zip => "zip",
+            PackageFormat::DsLibrary => "dslibrary",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PackageConfig {
+    pub format: PackageFormat,
+    /// Library name, also the folder inside the archive (default: the instrument folder's name)
+    pub name: Option<String>,
+    /// Written into the archive as LICENSE.txt and quoted in a generated README
+    pub license: Option<String>,
+    /// Archive path (default: <name>.<ext> next to the instrument folder)
+    pub output: Option<PathBuf>,
+}
+
+/// Pack `directory` into an archive. Returns the archive's path.
+pub fn package_directory(directory: &Path, config: &PackageConfig) -> Result<PathBuf> {
+    if !directory.is_dir() {
+        return Err(BatcherbirdError::Session(format!("Not an instrument folder: {}", directory.display())));
+    }
+    let name = config.name.clone()
+        .or_else(|| directory.file_name().map(|name| name.to_string_lossy().to_string()))
+        .unwrap_or_else(|| "Instrument".to_string());
+    let files = distributable_files(directory)?;
+    let format = instrument_format_in(&files);
+
+    if config.format == PackageFormat::DsLibrary && !files.iter().any(|file| has_extension(file, "dspreset")) {
+        return Err(BatcherbirdError::Config("A .dslibrary needs a .dspreset; export as decentsampler first".to_string()));
+    }
+
+    let archive_path = config.output.clone().unwrap_or_else(|| {
+        directory.parent().unwrap_or(directory).join(format!("{}.{}", name, config.format.extension()))
+    });
+    info!("📦 Packaging {} files into {}", files.len(), archive_path.display());
+
+    let mut zip = ZipWriter::new(std::fs::File::create(&archive_path)?);
+    for file in &files {
+        let relative = file.strip_prefix(directory).unwrap_or(file).to_string_lossy().replace('\\', "/");
+        zip.add_file(&format!("{}/{}", name, relative), &std::fs::read(file)?)?;
+    }
+
+    if !files.iter().any(|file| is_readme(file)) {
+        let readme_config = ReadmeConfig { license: config.license.clone(), ..ReadmeConfig::load_default() };
+        let samples: Vec<_> = LibrarySession::load(directory)?.samples.into_iter().map(|s| s.sample).collect();
+        let context = ReadmeContext::new(&name, &samples, &format, None, None, &readme_config);
+        let template = match &readme_config.template {
+            Some(path) => std::fs::read_to_string(path)?,
+            None => DEFAULT_README_TEMPLATE.to_string(),
+        };
+        zip.add_file(&format!("{}/{}", name, readme_config.file_name()), context.render(&template).as_bytes())?;
+    }
+    if let Some(license) = &config.license {
+        zip.add_file(&format!("{}/LICENSE.txt", name), license.as_bytes())?;
+    }
+    if config.format == PackageFormat::DsLibrary {
+        let info = format!("<DSLibraryInfo name=\"{}\" productId=\"{}\" version=\"1.0.0\" />\n",
+            xml_escape(&name), xml_escape(&name.to_lowercase().replace(' ', "-")));
+        zip.add_file(&format!("{}/DSLibraryInfo.xml", name), info.as_bytes())?;
+    }
+
+    let entries = zip.finish()?;
+    info!("   ✅ Packaged {} entries: {}", entries, archive_path.display());
+    Ok(archive_path)
+}
+
+/// WAVs, instrument files, READMEs and licenses under `directory`; session bookkeeping
+/// (manifest, QA reports, edit sidecars) stays out
+fn distributable_files(directory: &Path) -> Result<Vec<PathBuf>> {
+    let mut extensions = vec!["wav".to_string()];
+    for name in instrument_format::names() {
+        if let Some(exporter) = instrument_format::find(&name) {
+            extensions.extend(exporter.extensions().iter().map(|ext| ext.to_lowercase()));
+        }
+    }
+
+    let mut files = Vec::new();
+    let mut pending = vec![directory.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.')) {
+                continue;
+            }
+            if path.is_dir() {
+                pending.push(path);
+            } else if is_readme(&path) || is_license(&path) || extensions.iter().any(|ext| has_extension(&path, ext)) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Instrument format of the first instrument file found, for the README
+fn instrument_format_in(files: &[PathBuf]) -> AudioFormat {
+    instrument_format::names().into_iter()
+        .find(|name| {
+            instrument_format::find(name)
+                .and_then(|exporter| exporter.extensions().first().map(|ext| ext.to_string()))
+                .is_some_and(|ext| files.iter().any(|file| has_extension(file, &ext)))
+        })
+        .and_then(|name| AudioFormat::parse(&name))
+        .unwrap_or(AudioFormat::Wav24Bit)
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+fn file_stem_is(path: &Path, stem: &str) -> bool {
+    path.file_stem().is_some_and(|name| name.to_string_lossy().eq_ignore_ascii_case(stem))
+}
+
+fn is_readme(path: &Path) -> bool {
+    file_stem_is(path, "readme")
+}
+
+fn is_license(path: &Path) -> bool {
+    file_stem_is(path, "license") || file_stem_is(path, "licence")
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Central directory record of a written entry
+struct ZipEntry {
+    name: String,
+    method: u16,
+    crc: u32,
+    compressed_size: u32,
+    size: u32,
+    offset: u32,
+}
+
+/// Minimal zip writer: deflated (or stored, when deflate doesn't help) entries with UTF-8
+/// names, no zip64, so archives and entries stay under 4 GB
+struct ZipWriter<W: Write> {
+    out: W,
+    offset: u64,
+    entries: Vec<ZipEntry>,
+    time: u16,
+    date: u16,
+}
+
+const ZIP_VERSION: u16 = 20;
+const ZIP_UTF8_FLAG: u16 = 0x0800;
+
+impl<W: Write> ZipWriter<W> {
+    fn new(out: W) -> Self {
+        let now = Local::now();
+        let time = ((now.hour() << 11) | (now.minute() << 5) | (now.second() / 2)) as u16;
+        let date = (((now.year().clamp(1980, 2107) - 1980) as u32) << 9 | (now.month() << 5) | now.day()) as u16;
+        Self { out, offset: 0, entries: Vec::new(), time, date }
+    }
+
+    fn add_file(&mut self, name: &str, data: &[u8]) -> Result<()> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data)?;
+        let deflated = encoder.finish()?;
+        let (method, body) = if deflated.len() < data.len() { (8, deflated.as_slice()) } else { (0, data) };
+
+        let too_large = || BatcherbirdError::Export(std::io::Error::other(format!("{} is too large for a zip archive", name)));
+        let entry = ZipEntry {
+            name: name.to_string(),
+            method,
+            crc: crc32fast::hash(data),
+            compressed_size: u32::try_from(body.len()).map_err(|_| too_large())?,
+            size: u32::try_from(data.len()).map_err(|_| too_large())?,
+            offset: u32::try_from(self.offset).map_err(|_| too_large())?,
+        };
+
+        let mut header = Vec::with_capacity(30 + name.len());
+        header.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        header.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+        header.extend_from_slice(&ZIP_UTF8_FLAG.to_le_bytes());
+        header.extend_from_slice(&entry.method.to_le_bytes());
+        header.extend_from_slice(&self.time.to_le_bytes());
+        header.extend_from_slice(&self.date.to_le_bytes());
+        header.extend_from_slice(&entry.crc.to_le_bytes());
+        header.extend_from_slice(&entry.compressed_size.to_le_bytes());
+        header.extend_from_slice(&entry.size.to_le_bytes());
+        header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(name.as_bytes());
+        self.out.write_all(&header)?;
+        self.out.write_all(body)?;
+        self.offset += (header.len() + body.len()) as u64;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Write the central directory. Returns the number of entries.
+    fn finish(mut self) -> Result<usize> {
+        let directory_offset = self.offset;
+        let mut directory = Vec::new();
+        for entry in &self.entries {
+            directory.extend_from_slice(&0x02014b50u32.to_le_bytes());
+            directory.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+            directory.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+            directory.extend_from_slice(&ZIP_UTF8_FLAG.to_le_bytes());
+            directory.extend_from_slice(&entry.method.to_le_bytes());
+            directory.extend_from_slice(&self.time.to_le_bytes());
+            directory.extend_from_slice(&self.date.to_le_bytes());
+            directory.extend_from_slice(&entry.crc.to_le_bytes());
+            directory.extend_from_slice(&entry.compressed_size.to_le_bytes());
+            directory.extend_from_slice(&entry.size.to_le_bytes());
+            directory.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+            // Extra field, comment, disk number, internal and external attributes
+            directory.extend_from_slice(&[0; 12]);
+            directory.extend_from_slice(&entry.offset.to_le_bytes());
+            directory.extend_from_slice(entry.name.as_bytes());
+        }
+        let count = u16::try_from(self.entries.len())
+            .map_err(|_| BatcherbirdError::Export(std::io::Error::other("Too many files for a zip archive")))?;
+        let directory_offset = u32::try_from(directory_offset)
+            .map_err(|_| BatcherbirdError::Export(std::io::Error::other("Archive too large for zip")))?;
+
+        self.out.write_all(&directory)?;
+        let mut end = Vec::with_capacity(22);
+        end.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        end.extend_from_slice(&[0; 4]);
+        end.extend_from_slice(&count.to_le_bytes());
+        end.extend_from_slice(&count.to_le_bytes());
+        end.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        end.extend_from_slice(&directory_offset.to_le_bytes());
+        end.extend_from_slice(&0u16.to_le_bytes());
+        self.out.write_all(&end)?;
+        self.out.flush()?;
+        Ok(self.entries.len())
+    }
+}
@@ -0,0 +1,299 @@
+use crate::{Result, BatcherbirdError};
+use crate::audio::{AudioManager, f32_to_i32, f32_to_u16};
+use cpal::traits::{DeviceTrait, StreamTrait};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::{error, info};
+
+/// How to route the monitored input to an output
+#[derive(Debug, Clone)]
+pub struct PassthroughConfig {
+    /// Part of the output device name (None = the interface's outputs, or the default)
+    pub output_device: Option<String>,
+    pub gain_db: f32,
+    /// Audio queued beyond this is dropped so the delay can't build up (ms)
+    pub max_latency_ms: f32,
+}
+
+impl Default for PassthroughConfig {
+    fn default() -> Self {
+        Self { output_device: None, gain_db: 0.0, max_latency_ms: 20.0 }
+    }
+}
+
+/// Input audio handed from the monitoring stream to the passthrough output. Idle (and
+/// free for the input callback) until a passthrough is started.
+#[derive(Debug, Default)]
+pub struct MonitorTap {
+    active: AtomicBool,
+    buffer: Mutex<VecDeque<f32>>,
+    channels: AtomicU32,
+    sample_rate: AtomicU32,
+    /// Most interleaved samples kept queued
+    max_buffered: AtomicUsize,
+}
+
+impl MonitorTap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Input format, set when the monitoring stream starts
+    pub(crate) fn set_format(&self, channels: u16, sample_rate: u32) {
+        self.channels.store(channels as u32, Ordering::Relaxed);
+        self.sample_rate.store(sample_rate, Ordering::Relaxed);
+    }
+
+    /// Queue input audio (audio thread). Drops audio rather than waiting on the lock.
+    pub(crate) fn push(&self, data: &[f32]) {
+        if !self.active.load(Ordering::Relaxed) {
+            return;
+        }
+        if let Ok(mut buffer) = self.buffer.try_lock() {
+            buffer.extend(data);
+            let max = self.max_buffered.load(Ordering::Relaxed);
+            let channels = (self.channels.load(Ordering::Relaxed) as usize).max(1);
+            // Drop whole frames from the front so channels stay aligned
+            let excess = buffer.len().saturating_sub(max) / channels * channels;
+            buffer.drain(..excess);
+        }
+    }
+
+    fn start(&self, max_latency_ms: f32) {
+        let channels = self.channels.load(Ordering::Relaxed) as usize;
+        let rate = self.sample_rate.load(Ordering::Relaxed) as f32;
+        let frames = ((max_latency_ms.max(1.0) / 1000.0) * rate) as usize;
+        self.max_buffered.store(frames.max(64) * channels, Ordering::Relaxed);
+        self.buffer.lock().unwrap().clear();
+        self.active.store(true, Ordering::Relaxed);
+    }
+
+    fn stop(&self) {
+        self.active.store(false, Ordering::Relaxed);
+        self.buffer.lock().unwrap().clear();
+    }
+}
+
+/// Running passthrough. Dropping the handle stops it.
+pub struct PassthroughHandle {
+    stop: Arc<AtomicBool>,
+    gain: Arc<AtomicU32>,
+    tap: Arc<MonitorTap>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl PassthroughHandle {
+    /// Change the monitoring level while it runs
+    pub fn set_gain_db(&self, gain_db: f32) {
+        self.gain.store(f32::to_bits(db_to_gain(gain_db)), Ordering::Relaxed);
+    }
+
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+            self.tap.stop();
+            info!("🎧 Input passthrough stopped");
+        }
+    }
+}
+
+impl Drop for PassthroughHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Play the monitored input on an output device. The monitoring stream must be running
+/// (it sets the tap's format) and the output must run at the input's sample rate.
+pub fn start_passthrough(tap: Arc<MonitorTap>, config: &PassthroughConfig) -> Result<PassthroughHandle> {
+    if tap.channels.load(Ordering::Relaxed) == 0 {
+        return Err(BatcherbirdError::Audio("Start input monitoring before passthrough".to_string()));
+    }
+    let stop = Arc::new(AtomicBool::new(false));
+    let gain = Arc::new(AtomicU32::new(f32::to_bits(db_to_gain(config.gain_db))));
+    let (ready_tx, ready_rx) = mpsc::channel::<Result<()>>();
+
+    tap.start(config.max_latency_ms);
+    let (thread_stop, thread_gain, thread_tap) = (stop.clone(), gain.clone(), tap.clone());
+    let output_device = config.output_device.clone();
+    // cpal streams are not Send, so the output lives on its own thread
+    let thread = std::thread::spawn(move || {
+        let stream = match build_passthrough_stream(thread_tap, output_device.as_deref(), thread_gain) {
+            Ok(stream) => stream,
+            Err(e) => {
+                let _ = ready_tx.send(Err(e));
+                return;
+            }
+        };
+        let _ = ready_tx.send(Ok(()));
+        while !thread_stop.load(Ordering::Relaxed) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let _ = stream.pause();
+    });
+
+    match ready_rx.recv() {
+        Ok(Ok(())) => Ok(PassthroughHandle { stop, gain, tap, thread: Some(thread) }),
+        Ok(Err(e)) => {
+            let _ = thread.join();
+            tap.stop();
+            Err(e)
+        }
+        Err(_) => {
+            tap.stop();
+            Err(BatcherbirdError::Audio("Passthrough thread exited unexpectedly".to_string()))
+        }
+    }
+}
+
+fn build_passthrough_stream(tap: Arc<MonitorTap>, output_device: Option<&str>, gain: Arc<AtomicU32>) -> Result<cpal::Stream> {
+    let manager = AudioManager::new()?;
+    let (device, config) = match output_device {
+        Some(name) => {
+            let device = manager.output_device_named(name)?;
+            let config = device.default_output_config()
+                .map_err(|e| BatcherbirdError::Audio(format!("Failed to get output config: {}", e)))?;
+            (device, config)
+        }
+        None => manager.output_device_and_config()?,
+    };
+    let input_rate = tap.sample_rate.load(Ordering::Relaxed);
+    if config.sample_rate().0 != input_rate {
+        return Err(BatcherbirdError::Audio(format!(
+            "Output runs at {} Hz but the input at {} Hz; pick an output on the same clock as the input",
+            config.sample_rate().0, input_rate
+        )));
+    }
+    let stream_config: cpal::StreamConfig = config.clone().into();
+    let output_channels = stream_config.channels.max(1) as usize;
+    let input_channels = (tap.channels.load(Ordering::Relaxed) as usize).max(1);
+    info!("🎧 Input passthrough to {} ({} Hz, {} in → {} out)",
+        device.name().unwrap_or("Unknown".to_string()), input_rate, input_channels, output_channels);
+
+    let mut scratch = Vec::new();
+    let mut render = move |data: &mut dyn FnMut(usize, f32), len: usize| {
+        scratch.resize(len, 0.0);
+        let level = f32::from_bits(gain.load(Ordering::Relaxed));
+        match tap.buffer.try_lock() {
+            Ok(mut buffer) => fill_output(&mut buffer, input_channels, &mut scratch, output_channels, level),
+            Err(_) => scratch.fill(0.0),
+        }
+        for (index, &sample) in scratch.iter().enumerate() {
+            data(index, sample);
+        }
+    };
+
+    let error_callback = |err| error!("Passthrough output error: {}", err);
+    let stream = match config.sample_format() {
+        cpal::SampleFormat::F32 => device.build_output_stream(
+            &stream_config,
+            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                let len = data.len();
+                render(&mut |i, s| data[i] = s, len);
+            },
+            error_callback,
+            None,
+        ),
+        cpal::SampleFormat::I16 => device.build_output_stream(
+            &stream_config,
+            move |data: &mut [i16], _: &cpal::OutputCallbackInfo| {
+                let len = data.len();
+                render(&mut |i, s| data[i] = (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16, len);
+            },
+            error_callback,
+            None,
+        ),
+        cpal::SampleFormat::U16 => device.build_output_stream(
+            &stream_config,
+            move |data: &mut [u16], _: &cpal::OutputCallbackInfo| {
+                let len = data.len();
+                render(&mut |i, s| data[i] = f32_to_u16(s), len);
+            },
+            error_callback,
+            None,
+        ),
+        cpal::SampleFormat::I32 => device.build_output_stream(
+            &stream_config,
+            move |data: &mut [i32], _: &cpal::OutputCallbackInfo| {
+                let len = data.len();
+                render(&mut |i, s| data[i] = f32_to_i32(s), len);
+            },
+            error_callback,
+            None,
+        ),
+        format => return Err(BatcherbirdError::Audio(format!("Unsupported output sample format: {:?}", format))),
+    }.map_err(|e| BatcherbirdError::Audio(format!("Failed to build passthrough stream: {}", e)))?;
+
+    stream.play()
+        .map_err(|e| BatcherbirdError::Audio(format!("Failed to start passthrough: {}", e)))?;
+    Ok(stream)
+}
+
+/// Fill interleaved output frames from queued input frames: mono input goes to every
+/// output, multichannel input is mixed down for a mono output, extra outputs stay silent.
+/// Outputs silence once the queue runs dry.
+fn fill_output(buffer: &mut VecDeque<f32>, input_channels: usize, out: &mut [f32], output_channels: usize, gain: f32) {
+    let mut frame = vec![0.0f32; input_channels];
+    for out_frame in out.chunks_mut(output_channels) {
+        if buffer.len() < input_channels {
+            out_frame.fill(0.0);
+            continue;
+        }
+        for (slot, sample) in frame.iter_mut().zip(buffer.drain(..input_channels)) {
+            *slot = sample;
+        }
+        for (channel, sample) in out_frame.iter_mut().enumerate() {
+            *sample = gain * if input_channels == 1 {
+                frame[0]
+            } else if output_channels == 1 {
+                frame.iter().sum::<f32>() / input_channels as f32
+            } else {
+                frame.get(channel).copied().unwrap_or(0.0)
+            };
+        }
+    }
+}
+
+fn db_to_gain(gain_db: f32) -> f32 {
+    10f32.powf(gain_db / 20.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tap_caps_latency_and_maps_channels() {
+        let tap = MonitorTap::new();
+        tap.set_format(2, 1000);
+        // Idle taps ignore input
+        tap.push(&[1.0, 1.0]);
+        assert!(tap.buffer.lock().unwrap().is_empty());
+
+        // 1ms cap at 1 kHz is below the 64 frame floor: 128 samples kept, oldest dropped
+        tap.start(1.0);
+        let input: Vec<f32> = (0..200).flat_map(|f| [f as f32, -(f as f32)]).collect();
+        tap.push(&input);
+        let mut buffer = tap.buffer.lock().unwrap();
+        assert_eq!(buffer.len(), 128);
+        assert_eq!(buffer[0], 136.0);
+
+        // Stereo in, stereo out at -6 dB, then silence once the queue is empty
+        let mut out = vec![0.0f32; 4];
+        fill_output(&mut buffer, 2, &mut out, 2, db_to_gain(-6.0206));
+        assert!((out[0] - 68.0).abs() < 0.01 && (out[1] + 68.0).abs() < 0.01 && (out[2] - 68.5).abs() < 0.01, "{:?}", out);
+        buffer.truncate(2);
+        let mut mono = vec![1.0f32; 2];
+        fill_output(&mut buffer, 2, &mut mono, 1, 1.0);
+        assert_eq!(mono, vec![0.0, 0.0]);
+        drop(buffer);
+
+        tap.stop();
+        tap.push(&input);
+        assert!(tap.buffer.lock().unwrap().is_empty());
+    }
+}
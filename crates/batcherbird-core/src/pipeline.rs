@@ -0,0 +1,275 @@
+//! High-level sampling pipeline for embedding Batcherbird in other tools.
+//!
+//! One call connects the MIDI output and audio input, plays every note/velocity pair,
+//! and exports the takes as an instrument, the way the CLI and GUI do:
+//!
+//! ```no_run
+//! use batcherbird_core::pipeline::{sfz, BatcherbirdSession};
+//!
+//! let output = BatcherbirdSession::builder()
+//!     .midi("MiniFuse")
+//!     .audio("MiniFuse")
+//!     .notes(36..=84)
+//!     .velocities(&[64, 127])
+//!     .export(sfz())
+//!     .output("./Juno Pads")
+//!     .name("JunoPad")
+//!     .run()?;
+//! println!("{} files, {} notes skipped", output.files.len(), output.failed.len());
+//! # Ok::<(), batcherbird_core::BatcherbirdError>(())
+//! ```
+
+use crate::audio::AudioManager;
+use crate::detection::DetectionConfig;
+use crate::export::{AudioFormat, ExportConfig, SampleExporter};
+use crate::midi::MidiManager;
+use crate::profiles::SynthProfile;
+use crate::readme::ReadmeConfig;
+use crate::sampler::{Sample, SamplingConfig, SamplingEngine};
+use crate::watchdog::FailedNote;
+use crate::{BatcherbirdError, Result};
+use std::path::PathBuf;
+use tracing::info;
+
+/// SFZ instrument with 24-bit WAVs
+pub fn sfz() -> AudioFormat {
+    AudioFormat::SFZ
+}
+
+/// Decent Sampler .dspreset with 24-bit WAVs
+pub fn decent_sampler() -> AudioFormat {
+    AudioFormat::DecentSampler
+}
+
+/// Plain 24-bit WAVs, no instrument file
+pub fn wav() -> AudioFormat {
+    AudioFormat::Wav24Bit
+}
+
+/// What a finished run produced
+#[derive(Debug, Clone)]
+pub struct SessionOutput {
+    /// Captured takes, in note then velocity order
+    pub samples: Vec<Sample>,
+    /// Exported WAVs followed by the instrument and session files
+    pub files: Vec<PathBuf>,
+    /// Notes the watchdog gave up on
+    pub failed: Vec<FailedNote>,
+}
+
+/// A configured sampling run. Create one with [`BatcherbirdSession::builder`].
+#[derive(Debug, Clone)]
+pub struct BatcherbirdSession {
+    midi_device: Option<String>,
+    audio_device: Option<String>,
+    notes: Vec<u8>,
+    velocities: Vec<u8>,
+    sampling: SamplingConfig,
+    export: ExportConfig,
+}
+
+impl BatcherbirdSession {
+    pub fn builder() -> BatcherbirdSessionBuilder {
+        BatcherbirdSessionBuilder::default()
+    }
+
+    /// Every (note, velocity) pair the run will play, in order
+    pub fn entries(&self) -> Vec<(u8, u8)> {
+        self.notes.iter()
+            .flat_map(|&note| self.velocities.iter().map(move |&velocity| (note, velocity)))
+            .collect()
+    }
+
+    /// Capture every note/velocity pair and export the takes. Blocks until done.
+    pub fn run(&self) -> Result<SessionOutput> {
+        if let Some(name) = &self.audio_device {
+            AudioManager::set_preferred_input(Some(name.clone()));
+        }
+
+        let mut midi_manager = MidiManager::new()?;
+        let output_devices = midi_manager.list_output_devices()?;
+        let device_index = match &self.midi_device {
+            Some(name) => output_devices.iter()
+                .position(|device| device.contains(name.as_str()))
+                .ok_or_else(|| BatcherbirdError::Config(format!("No MIDI output matching '{}' (found: {})", name, output_devices.join(", "))))?,
+            None if output_devices.is_empty() => {
+                return Err(BatcherbirdError::Config("No MIDI output devices found".to_string()));
+            }
+            // Same default as the CLI: MiniFuse if available, otherwise the first device
+            None => output_devices.iter().position(|device| device.contains("MiniFuse")).unwrap_or(0),
+        };
+        info!("🎹 Using MIDI device: {}", output_devices[device_index]);
+        let mut midi_conn = midi_manager.connect_output(device_index)?;
+
+        let engine = SamplingEngine::new(self.sampling.clone())?;
+        let mut samples: Vec<Sample> = engine.sample_entries_blocking(&mut midi_conn, &self.entries())?
+            .into_iter()
+            .flatten()
+            .collect();
+        for sample in &mut samples {
+            sample.verify_played_note();
+        }
+
+        let files = SampleExporter::new(self.export.clone())?.export_samples(&samples)?;
+        Ok(SessionOutput { samples, files, failed: engine.failed_notes() })
+    }
+}
+
+/// Builder for [`BatcherbirdSession`]. Only the notes are required.
+#[derive(Debug, Clone)]
+pub struct BatcherbirdSessionBuilder {
+    midi_device: Option<String>,
+    audio_device: Option<String>,
+    notes: Vec<u8>,
+    velocities: Vec<u8>,
+    format: AudioFormat,
+    output: PathBuf,
+    name: String,
+    sampling: SamplingConfig,
+    detection: Option<DetectionConfig>,
+    normalize: bool,
+}
+
+impl Default for BatcherbirdSessionBuilder {
+    fn default() -> Self {
+        Self {
+            midi_device: None,
+            audio_device: None,
+            notes: Vec::new(),
+            velocities: vec![127],
+            format: AudioFormat::SFZ,
+            output: PathBuf::from("./samples"),
+            name: "Batcherbird".to_string(),
+            sampling: SamplingConfig::default(),
+            detection: Some(DetectionConfig::default()),
+            normalize: false,
+        }
+    }
+}
+
+impl BatcherbirdSessionBuilder {
+    /// MIDI output whose name contains `name` (default: MiniFuse, else the first output)
+    pub fn midi(mut self, name: impl Into<String>) -> Self {
+        self.midi_device = Some(name.into());
+        self
+    }
+
+    /// Audio input whose name contains `name` (default: MiniFuse, else the system input)
+    pub fn audio(mut self, name: impl Into<String>) -> Self {
+        self.audio_device = Some(name.into());
+        self
+    }
+
+    /// Notes to sample, e.g. `36..=84` or `[36, 38, 42]`
+    pub fn notes(mut self, notes: impl IntoIterator<Item = u8>) -> Self {
+        self.notes = notes.into_iter().collect();
+        self
+    }
+
+    /// Velocity layers played for every note (default: 127)
+    pub fn velocities(mut self, velocities: &[u8]) -> Self {
+        self.velocities = velocities.to_vec();
+        self
+    }
+
+    /// Instrument format, see [`sfz`], [`decent_sampler`] and [`wav`]
+    pub fn export(mut self, format: AudioFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Folder the samples and instrument are written to (default: ./samples)
+    pub fn output(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.output = directory.into();
+        self
+    }
+
+    /// Instrument name, used for the instrument file and as the sample file prefix
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Timing and MIDI settings (note length, pre-roll, channel, watchdog...)
+    pub fn sampling(mut self, config: SamplingConfig) -> Self {
+        self.sampling = config;
+        self
+    }
+
+    /// Take channel, timing and detection settings from a synth profile
+    pub fn profile(mut self, profile: &SynthProfile) -> Self {
+        self.sampling = profile.apply_to(self.sampling);
+        self.detection = Some(profile.detection_config());
+        self
+    }
+
+    /// Trim settings; None keeps every take at full length
+    pub fn detection(mut self, config: Option<DetectionConfig>) -> Self {
+        self.detection = config;
+        self
+    }
+
+    pub fn normalize(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    pub fn build(self) -> Result<BatcherbirdSession> {
+        if self.notes.is_empty() {
+            return Err(BatcherbirdError::Config("No notes to sample".to_string()));
+        }
+        if let Some(note) = self.notes.iter().find(|&&note| note > 127) {
+            return Err(BatcherbirdError::Config(format!("Invalid note {}: use 0-127", note)));
+        }
+        if self.velocities.is_empty() || self.velocities.iter().any(|v| !(1..=127).contains(v)) {
+            return Err(BatcherbirdError::Config(format!("Invalid velocities {:?}: use 1-127", self.velocities)));
+        }
+
+        let export = ExportConfig {
+            output_directory: self.output,
+            naming_pattern: format!("{}_{{note_name}}_{{note}}_{{velocity}}.wav", self.name),
+            sample_format: self.format,
+            normalize: self.normalize,
+            apply_detection: self.detection.is_some(),
+            detection_config: self.detection.unwrap_or_default(),
+            readme: Some(ReadmeConfig { sampling: Some(self.sampling.clone()), ..ReadmeConfig::load_default() }),
+            ..ExportConfig::default()
+        };
+        Ok(BatcherbirdSession {
+            midi_device: self.midi_device,
+            audio_device: self.audio_device,
+            notes: self.notes,
+            velocities: self.velocities,
+            sampling: self.sampling,
+            export,
+        })
+    }
+
+    /// Build and run in one go
+    pub fn run(self) -> Result<SessionOutput> {
+        self.build()?.run()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_validates_and_orders_entries() {
+        let session = BatcherbirdSession::builder()
+            .notes(60..=61)
+            .velocities(&[64, 127])
+            .export(decent_sampler())
+            .name("Pad")
+            .build()
+            .unwrap();
+        assert_eq!(session.entries(), vec![(60, 64), (60, 127), (61, 64), (61, 127)]);
+        assert_eq!(session.export.naming_pattern, "Pad_{note_name}_{note}_{velocity}.wav");
+        assert!(matches!(session.export.sample_format, AudioFormat::DecentSampler));
+
+        assert!(BatcherbirdSession::builder().build().is_err());
+        assert!(BatcherbirdSession::builder().notes([60, 128]).build().is_err());
+        assert!(BatcherbirdSession::builder().notes([60]).velocities(&[0]).build().is_err());
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{library, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Lowest and highest fundamentals considered when guessing the played note (Hz)
+const MIN_FREQUENCY_HZ: f32 = 27.5;
+const MAX_FREQUENCY_HZ: f32 = 4186.0;
+/// Length of the window the pitch estimate is taken from (seconds)
+const PITCH_WINDOW_SECS: f32 = 0.1;
+/// Normalized correlation below this is treated as unpitched (noise, percussion)
+const MIN_CORRELATION: f32 = 0.8;
+
+/// Estimated fundamental of a sample and the note it is closest to
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PitchEstimate {
+    pub frequency_hz: f32,
+    /// Nearest MIDI note
+    pub note: u8,
+    /// Deviation from that note (cents, -50 to +50)
+    pub cents: f32,
+}
+
+impl PitchEstimate {
+    pub fn from_frequency(frequency_hz: f32) -> Self {
+        let midi = 69.0 + 12.0 * (frequency_hz / 440.0).log2();
+        let nearest = midi.round().clamp(0.0, 127.0);
+        Self { frequency_hz, note: nearest as u8, cents: (midi - nearest) * 100.0 }
+    }
+}
+
+/// Fundamental of interleaved audio (None when silent or unpitched)
+pub fn detect_pitch(audio: &[f32], channels: u16, sample_rate: u32) -> Option<PitchEstimate> {
+    estimate_frequency(&mixdown(audio, channels), sample_rate).map(PitchEstimate::from_frequency)
+}
+
+/// Fundamental of a WAV file, e.g. to find the root note of a file whose name doesn't say
+pub fn detect_file_pitch(path: &Path) -> Result<Option<PitchEstimate>> {
+    let (audio, spec) = library::read_wav(path)?;
+    Ok(detect_pitch(&audio, spec.channels, spec.sample_rate))
+}
+
+pub(crate) fn mixdown(audio: &[f32], channels: u16) -> Vec<f32> {
+    audio
+        .chunks(channels.max(1) as usize)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect()
+}
+
+/// Autocorrelation estimate of the fundamental, taken from the loudest window of the take
+pub(crate) fn estimate_frequency(mono: &[f32], sample_rate: u32) -> Option<f32> {
+    let rate = sample_rate as f32;
+    let min_lag = (rate / MAX_FREQUENCY_HZ).floor().max(2.0) as usize;
+    let max_lag = (rate / MIN_FREQUENCY_HZ).ceil() as usize;
+    let window = ((rate * PITCH_WINDOW_SECS) as usize).max(max_lag * 2);
+    if mono.len() < window + max_lag {
+        return None;
+    }
+
+    // Step through in half-window hops and keep the window with the most energy
+    let hop = window / 2;
+    let start = (0..=mono.len() - window - max_lag)
+        .step_by(hop)
+        .max_by(|&a, &b| {
+            let energy = |at: usize| mono[at..at + window].iter().map(|s| s * s).sum::<f32>();
+            energy(a).total_cmp(&energy(b))
+        })?;
+    correlation_peak(&mono[start..start + window + max_lag], window, min_lag, max_lag, rate)
+}
+
+/// Frequency of the strongest normalized autocorrelation peak between `min_lag` and `max_lag`.
+/// `frame` holds `window + max_lag` samples.
+pub(crate) fn correlation_peak(frame: &[f32], window: usize, min_lag: usize, max_lag: usize, rate: f32) -> Option<f32> {
+    let base = &frame[..window];
+    let base_energy: f32 = base.iter().map(|s| s * s).sum();
+    if (base_energy / window as f32).sqrt() < 0.001 {
+        return None;
+    }
+
+    let correlation = |lag: usize| -> f32 {
+        let shifted = &frame[lag..lag + window];
+        let dot: f32 = base.iter().zip(shifted).map(|(a, b)| a * b).sum();
+        let shifted_energy: f32 = shifted.iter().map(|s| s * s).sum();
+        let denom = (base_energy * shifted_energy).sqrt();
+        if denom > 0.0 { dot / denom } else { 0.0 }
+    };
+    let scores: Vec<f32> = (min_lag..=max_lag).map(correlation).collect();
+    let best = scores.iter().cloned().fold(f32::MIN, f32::max);
+    if best < MIN_CORRELATION {
+        return None;
+    }
+
+    // The first local peak close to the best score avoids picking an octave below
+    let index = (1..scores.len().saturating_sub(1)).find(|&i| {
+        scores[i] >= best * 0.95 && scores[i] >= scores[i - 1] && scores[i] >= scores[i + 1]
+    })?;
+
+    // Parabolic interpolation around the peak for sub-sample lag accuracy
+    let (left, center, right) = (scores[index - 1], scores[index], scores[index + 1]);
+    let curvature = left - 2.0 * center + right;
+    let offset = if curvature.abs() > f32::EPSILON { 0.5 * (left - right) / curvature } else { 0.0 };
+    let lag = (min_lag + index) as f32 + offset;
+    Some(rate / lag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_fundamental_and_nearest_note() {
+        // A3 with a strong second harmonic, which must not be mistaken for A4
+        let rate = 48000;
+        let tone: Vec<f32> = (0..rate)
+            .map(|i| {
+                let t = i as f32 / rate as f32;
+                0.3 * (2.0 * std::f32::consts::PI * 220.0 * t).sin() + 0.3 * (2.0 * std::f32::consts::PI * 440.0 * t).sin()
+            })
+            .collect();
+        let estimate = detect_pitch(&tone, 1, rate as u32).unwrap();
+        assert_eq!(estimate.note, 57);
+        assert!(estimate.cents.abs() < 10.0);
+
+        // A take recorded for C4 that sounded A3 is flagged
+        let mut take = crate::sampler::Sample {
+            note: 60,
+            velocity: 100,
+            audio_data: tone.clone(),
+            sample_rate: rate as u32,
+            channels: 1,
+            recorded_at: std::time::SystemTime::now(),
+            midi_timing: std::time::Duration::ZERO,
+            audio_timing: std::time::Duration::ZERO,
+            metadata: Default::default(),
+        };
+        take.verify_played_note();
+        assert_eq!(take.metadata.retake_reason.as_deref(), Some("played A3 instead of C4"));
+        take.note = 57;
+        take.metadata.retake_reason = None;
+        take.verify_played_note();
+        assert_eq!(take.metadata.retake_reason, None);
+
+        // 25 cents sharp of C4
+        let sharp = PitchEstimate::from_frequency(261.63 * 2f32.powf(25.0 / 1200.0));
+        assert_eq!(sharp.note, 60);
+        assert!((sharp.cents - 25.0).abs() < 0.5);
+
+        let mut state = 0x2545F491u32;
+        let noise: Vec<f32> = (0..rate)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as f32 / u32::MAX as f32 - 0.5
+            })
+            .collect();
+        assert_eq!(detect_pitch(&noise, 1, rate as u32), None);
+        assert_eq!(detect_pitch(&vec![0.0; rate], 1, rate as u32), None);
+    }
+}
@@ -0,0 +1,173 @@
+//! Dry run of a batch: every take that would be recorded, how long the session will take and
+//! how much disk the WAVs will use, worked out before any MIDI is sent.
+
+use crate::{Result, BatcherbirdError};
+use crate::export::BitDepth;
+use crate::sampler::{SamplingConfig, NOTE_GAP_MS};
+use serde::Serialize;
+use std::time::Duration;
+
+/// WAV header plus the smpl/cue chunks an export may add
+const WAV_OVERHEAD_BYTES: u64 = 256;
+
+/// The note / velocity / round robin matrix of a batch
+#[derive(Debug, Clone)]
+pub struct PlanMatrix {
+    pub start_note: u8,
+    pub end_note: u8,
+    /// Velocity layers, in recording order (empty = the sampling config's velocity)
+    pub velocities: Vec<u8>,
+    /// Takes of each note/velocity (1 = no round robins)
+    pub round_robins: u8,
+}
+
+/// Format the takes will be written in
+#[derive(Debug, Clone, Copy)]
+pub struct PlanAudio {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bit_depth: BitDepth,
+}
+
+impl Default for PlanAudio {
+    fn default() -> Self {
+        Self { sample_rate: 48000, channels: 2, bit_depth: BitDepth::default() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedTake {
+    pub note: u8,
+    pub note_name: String,
+    pub velocity: u8,
+    /// 1-based round robin, when there is more than one
+    pub round_robin: Option<u8>,
+    /// When the take starts, from the beginning of the batch
+    pub starts_at: Duration,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SamplingPlan {
+    pub takes: Vec<PlannedTake>,
+    /// Pre-delay to post-delay of one take
+    pub take_duration: Duration,
+    pub total_duration: Duration,
+    /// Untrimmed WAV size of one take
+    pub bytes_per_take: u64,
+    pub total_bytes: u64,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits: u16,
+}
+
+impl SamplingPlan {
+    /// Expand `matrix` with the timings of `config`. Notes are the outer loop, then velocity
+    /// layers, then round robins, as the samplers record them.
+    pub fn new(config: &SamplingConfig, matrix: &PlanMatrix, audio: PlanAudio) -> Result<Self> {
+        if matrix.start_note > matrix.end_note || matrix.end_note > 127 {
+            return Err(BatcherbirdError::Config(format!("Invalid note range {}-{}", matrix.start_note, matrix.end_note)));
+        }
+        if matrix.round_robins == 0 {
+            return Err(BatcherbirdError::Config("Round robins must be at least 1".to_string()));
+        }
+        let velocities = if matrix.velocities.is_empty() { vec![config.velocity] } else { matrix.velocities.clone() };
+        if let Some(velocity) = velocities.iter().find(|&&velocity| velocity == 0 || velocity > 127) {
+            return Err(BatcherbirdError::Config(format!("Invalid velocity {}", velocity)));
+        }
+
+        let take_duration = Duration::from_millis(config.pre_delay_ms + config.note_duration_ms + config.release_time_ms + config.post_delay_ms);
+        let gap = Duration::from_millis(NOTE_GAP_MS);
+        let mut takes = Vec::new();
+        let mut starts_at = Duration::ZERO;
+        for note in matrix.start_note..=matrix.end_note {
+            for &velocity in &velocities {
+                for round_robin in 1..=matrix.round_robins {
+                    takes.push(PlannedTake {
+                        note,
+                        note_name: note_to_name(note),
+                        velocity,
+                        round_robin: (matrix.round_robins > 1).then_some(round_robin),
+                        starts_at,
+                    });
+                    starts_at += take_duration + gap;
+                }
+            }
+        }
+        let total_duration = starts_at.saturating_sub(gap);
+
+        let frames = (take_duration.as_secs_f64() * audio.sample_rate as f64).ceil() as u64;
+        let bytes_per_take = frames * audio.channels as u64 * (audio.bit_depth.bits() as u64 / 8) + WAV_OVERHEAD_BYTES;
+        Ok(Self {
+            total_bytes: bytes_per_take * takes.len() as u64,
+            takes,
+            take_duration,
+            total_duration,
+            bytes_per_take,
+            sample_rate: audio.sample_rate,
+            channels: audio.channels,
+            bits: audio.bit_depth.bits(),
+        })
+    }
+
+    /// Totals, then one line per take when `list_takes` is set
+    pub fn summary(&self, list_takes: bool) -> String {
+        let mut report = format!(
+            "📋 Plan: {} takes of {:.1}s ({} Hz, {} ch, {}-bit)\n   ⏱️ Recording time: {}\n   💾 Disk: {} ({} per take, before trimming)\n",
+            self.takes.len(), self.take_duration.as_secs_f32(), self.sample_rate, self.channels, self.bits,
+            format_duration(self.total_duration), format_bytes(self.total_bytes), format_bytes(self.bytes_per_take));
+        if list_takes {
+            for take in &self.takes {
+                let round_robin = take.round_robin.map(|rr| format!(" rr{}", rr)).unwrap_or_default();
+                report.push_str(&format!("   {:>8}  {:<4} ({:>3}) vel {:>3}{}\n",
+                    format_duration(take.starts_at), take.note_name, take.note, take.velocity, round_robin));
+            }
+        }
+        report
+    }
+}
+
+/// h:mm:ss
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1 << 30 => format!("{:.2} GB", b as f64 / (1u64 << 30) as f64),
+        b if b >= 1 << 20 => format!("{:.1} MB", b as f64 / (1u64 << 20) as f64),
+        b => format!("{:.0} KB", b as f64 / 1024.0),
+    }
+}
+
+fn note_to_name(note: u8) -> String {
+    let note_names = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+    let octave = (note / 12).saturating_sub(1);
+    format!("{}{}", note_names[(note % 12) as usize], octave)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_expands_matrix_and_estimates_time_and_disk() {
+        let config = SamplingConfig { pre_delay_ms: 100, note_duration_ms: 2000, release_time_ms: 800, post_delay_ms: 100, ..SamplingConfig::default() };
+        let matrix = PlanMatrix { start_note: 60, end_note: 62, velocities: vec![64, 127], round_robins: 2 };
+        let audio = PlanAudio { sample_rate: 48000, channels: 2, bit_depth: BitDepth::Int24 };
+        let plan = SamplingPlan::new(&config, &matrix, audio).unwrap();
+
+        assert_eq!(plan.takes.len(), 12);
+        let order: Vec<(u8, u8, Option<u8>)> = plan.takes.iter().take(5).map(|t| (t.note, t.velocity, t.round_robin)).collect();
+        assert_eq!(order, [(60, 64, Some(1)), (60, 64, Some(2)), (60, 127, Some(1)), (60, 127, Some(2)), (61, 64, Some(1))]);
+        assert_eq!(plan.takes[1].starts_at, Duration::from_millis(3300));
+        // 12 takes of 3s with 11 gaps between them
+        assert_eq!(plan.total_duration, Duration::from_millis(12 * 3000 + 11 * 300));
+        assert_eq!(plan.bytes_per_take, 144_000 * 2 * 3 + WAV_OVERHEAD_BYTES);
+        assert_eq!(plan.total_bytes, plan.bytes_per_take * 12);
+        assert!(plan.summary(true).contains("0:00:39"));
+
+        assert!(SamplingPlan::new(&config, &PlanMatrix { round_robins: 0, ..matrix.clone() }, audio).is_err());
+        assert!(SamplingPlan::new(&config, &PlanMatrix { start_note: 70, ..matrix }, audio).is_err());
+    }
+}
@@ -0,0 +1,412 @@
+use crate::{Result, BatcherbirdError};
+use crate::audio::{AudioManager, f32_to_i32, f32_to_u16};
+use crate::library::read_wav;
+use cpal::traits::{DeviceTrait, StreamTrait};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::{error, info};
+
+/// A WAV file held in memory for playback
+#[derive(Debug, Clone)]
+pub struct PlaybackBuffer {
+    /// Interleaved samples
+    pub audio: Arc<Vec<f32>>,
+    pub channels: u16,
+    pub sample_rate: u32,
+}
+
+impl PlaybackBuffer {
+    pub fn load(path: &Path) -> Result<Self> {
+        let (audio, spec) = read_wav(path)?;
+        Ok(Self {
+            audio: Arc::new(audio),
+            channels: spec.channels,
+            sample_rate: spec.sample_rate,
+        })
+    }
+
+    pub fn frames(&self) -> usize {
+        self.audio.len() / self.channels.max(1) as usize
+    }
+
+    /// One channel of a frame as heard on an output with `output_channels` channels:
+    /// mono is sent to every output, multichannel is mixed down for a mono output,
+    /// and outputs beyond the source channels stay silent.
+    fn output_sample(&self, frame: usize, output_channel: usize, output_channels: usize) -> f32 {
+        let channels = self.channels.max(1) as usize;
+        let base = frame * channels;
+        if channels == 1 {
+            self.audio[base]
+        } else if output_channels == 1 {
+            self.audio[base..base + channels].iter().sum::<f32>() / channels as f32
+        } else if output_channel < channels {
+            self.audio[base + output_channel]
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Loop points of a running audition, shared with the audio thread so they can be moved while it plays
+#[derive(Debug)]
+struct LoopControl {
+    start: AtomicUsize,
+    end: AtomicUsize,
+    crossfade: AtomicUsize,
+}
+
+impl LoopControl {
+    fn new(start: usize, end: usize, crossfade: usize) -> Self {
+        Self {
+            start: AtomicUsize::new(start),
+            end: AtomicUsize::new(end),
+            crossfade: AtomicUsize::new(crossfade),
+        }
+    }
+
+    fn set(&self, start: usize, end: usize, crossfade: usize) {
+        self.start.store(start, Ordering::Relaxed);
+        self.end.store(end, Ordering::Relaxed);
+        self.crossfade.store(crossfade, Ordering::Relaxed);
+    }
+
+    /// (start, end, crossfade) with the crossfade clamped to the material available before the loop start
+    fn points(&self) -> (usize, usize, usize) {
+        let start = self.start.load(Ordering::Relaxed);
+        let end = self.end.load(Ordering::Relaxed);
+        let crossfade = self.crossfade.load(Ordering::Relaxed)
+            .min(start)
+            .min(end.saturating_sub(start) / 2);
+        (start, end, crossfade)
+    }
+}
+
+/// Reads a frame range of a buffer at the output device's rate and channel count
+#[derive(Debug, Clone)]
+struct RegionCursor {
+    buffer: PlaybackBuffer,
+    end: usize,
+    /// Fractional frame position in the source
+    position: f64,
+    /// Source frames per output frame (sample rate conversion)
+    step: f64,
+    /// Wrap from the loop end back to the loop start instead of stopping
+    looping: Option<Arc<LoopControl>>,
+}
+
+impl RegionCursor {
+    fn new(buffer: PlaybackBuffer, start: usize, end: usize, output_rate: u32) -> Self {
+        let step = buffer.sample_rate as f64 / output_rate.max(1) as f64;
+        Self { buffer, end, position: start as f64, step, looping: None }
+    }
+
+    fn frame_index(&self) -> usize {
+        self.position as usize
+    }
+
+    /// Write the next output frame; false once the region has been played
+    fn next_frame(&mut self, out: &mut [f32]) -> bool {
+        let output_channels = out.len();
+        let looping = self.looping.as_ref().map(|control| control.points());
+
+        if let Some((loop_start, loop_end, _)) = looping {
+            if self.position >= loop_end as f64 {
+                // Wrap by the loop length; jump straight to the start if the end was moved behind us
+                let length = (loop_end - loop_start) as f64;
+                let overshoot = (self.position - loop_end as f64) % length;
+                self.position = loop_start as f64 + overshoot;
+            }
+        }
+
+        let frame = self.frame_index();
+        let limit = looping.map_or(self.end, |(_, loop_end, _)| loop_end);
+        if frame >= limit {
+            out.fill(0.0);
+            return false;
+        }
+
+        // Linear interpolation between neighbouring source frames
+        let next = (frame + 1).min(limit - 1);
+        let fraction = (self.position - frame as f64) as f32;
+        for (channel, sample) in out.iter_mut().enumerate() {
+            let current = self.buffer.output_sample(frame, channel, output_channels);
+            let following = self.buffer.output_sample(next, channel, output_channels);
+            *sample = current + (following - current) * fraction;
+        }
+
+        // Temporary crossfade: fade in the audio leading up to the loop start over the loop tail
+        if let Some((loop_start, loop_end, crossfade)) = looping {
+            if crossfade > 0 && frame >= loop_end - crossfade {
+                let fade_ratio = (frame + crossfade - loop_end) as f32 / crossfade as f32;
+                let source = frame - (loop_end - loop_start);
+                for (channel, sample) in out.iter_mut().enumerate() {
+                    let lead_in = self.buffer.output_sample(source, channel, output_channels);
+                    *sample = *sample * (1.0 - fade_ratio) + lead_in * fade_ratio;
+                }
+            }
+        }
+
+        self.position += self.step;
+        true
+    }
+}
+
+/// Running playback. Dropping the handle stops it.
+pub struct PlaybackHandle {
+    stop: Arc<AtomicBool>,
+    finished: Arc<AtomicBool>,
+    position: Arc<AtomicUsize>,
+    looping: Option<Arc<LoopControl>>,
+    frames: usize,
+    sample_rate: u32,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl PlaybackHandle {
+    pub fn is_playing(&self) -> bool {
+        !self.finished.load(Ordering::Relaxed)
+    }
+
+    /// Current source frame, for drawing a playhead
+    pub fn position_frames(&self) -> usize {
+        self.position.load(Ordering::Relaxed)
+    }
+
+    /// Sample rate of the file being played, for converting times to frames
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Move the loop points of a running loop audition; takes effect on the next pass
+    pub fn set_loop_points(&self, loop_start: usize, loop_end: usize, crossfade_frames: usize) -> Result<()> {
+        let Some(looping) = &self.looping else {
+            return Err(BatcherbirdError::Processing("Playback is not a loop audition".to_string()));
+        };
+        validate_loop(loop_start, loop_end, self.frames)?;
+        looping.set(loop_start, loop_end, crossfade_frames);
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for PlaybackHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Play frames `start_frame..end_frame` of a WAV (end None = to the end of the file)
+/// on the default output device. Returns once the stream is running.
+pub fn play_region(path: &Path, start_frame: usize, end_frame: Option<usize>) -> Result<PlaybackHandle> {
+    let buffer = PlaybackBuffer::load(path)?;
+    let frames = buffer.frames();
+    let end_frame = end_frame.unwrap_or(frames).min(frames);
+    if start_frame >= end_frame {
+        return Err(BatcherbirdError::Processing(format!(
+            "Empty playback region {}..{} ({} frames in file)", start_frame, end_frame, frames
+        )));
+    }
+
+    info!("▶️ Playing {} frames {}..{} ({:.2}s)", path.display(), start_frame, end_frame,
+        (end_frame - start_frame) as f32 / buffer.sample_rate.max(1) as f32);
+    start_playback(buffer, start_frame, end_frame, None)
+}
+
+/// Loop `loop_start..loop_end` of a WAV until stopped, crossfading the loop end into the
+/// audio before the loop start. The crossfade is only applied to what is heard; the file is
+/// not touched. Use `PlaybackHandle::set_loop_points` to fine-tune while it plays.
+pub fn play_loop(path: &Path, loop_start: usize, loop_end: usize, crossfade_frames: usize) -> Result<PlaybackHandle> {
+    let buffer = PlaybackBuffer::load(path)?;
+    validate_loop(loop_start, loop_end, buffer.frames())?;
+
+    info!("🔁 Looping {} frames {}..{} ({:.3}s, {} frame crossfade)", path.display(), loop_start, loop_end,
+        (loop_end - loop_start) as f32 / buffer.sample_rate.max(1) as f32, crossfade_frames);
+    let frames = buffer.frames();
+    let looping = Arc::new(LoopControl::new(loop_start, loop_end, crossfade_frames));
+    start_playback(buffer, loop_start, frames, Some(looping))
+}
+
+fn validate_loop(loop_start: usize, loop_end: usize, frames: usize) -> Result<()> {
+    if loop_end > frames || loop_start + 2 > loop_end {
+        return Err(BatcherbirdError::Processing(format!(
+            "Invalid loop {}..{} ({} frames in file)", loop_start, loop_end, frames
+        )));
+    }
+    Ok(())
+}
+
+/// Stream a cursor on its own thread (cpal streams are not Send), reporting startup errors back
+fn start_playback(buffer: PlaybackBuffer, start: usize, end: usize, looping: Option<Arc<LoopControl>>) -> Result<PlaybackHandle> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let finished = Arc::new(AtomicBool::new(false));
+    let position = Arc::new(AtomicUsize::new(start));
+    let (frames, sample_rate) = (buffer.frames(), buffer.sample_rate);
+    let (ready_tx, ready_rx) = mpsc::channel::<Result<()>>();
+
+    let (thread_stop, thread_finished, thread_position) = (stop.clone(), finished.clone(), position.clone());
+    let thread_looping = looping.clone();
+    let thread = std::thread::spawn(move || {
+        let stream = match build_output_stream(buffer, start, end, thread_looping, thread_finished.clone(), thread_position) {
+            Ok(stream) => stream,
+            Err(e) => {
+                thread_finished.store(true, Ordering::Relaxed);
+                let _ = ready_tx.send(Err(e));
+                return;
+            }
+        };
+        let _ = ready_tx.send(Ok(()));
+
+        while !thread_stop.load(Ordering::Relaxed) && !thread_finished.load(Ordering::Relaxed) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let _ = stream.pause();
+        thread_finished.store(true, Ordering::Relaxed);
+    });
+
+    match ready_rx.recv() {
+        Ok(Ok(())) => Ok(PlaybackHandle { stop, finished, position, looping, frames, sample_rate, thread: Some(thread) }),
+        Ok(Err(e)) => {
+            let _ = thread.join();
+            Err(e)
+        }
+        Err(_) => Err(BatcherbirdError::Audio("Playback thread exited unexpectedly".to_string())),
+    }
+}
+
+fn build_output_stream(
+    buffer: PlaybackBuffer,
+    start: usize,
+    end: usize,
+    looping: Option<Arc<LoopControl>>,
+    finished: Arc<AtomicBool>,
+    position: Arc<AtomicUsize>,
+) -> Result<cpal::Stream> {
+    let (device, config) = AudioManager::new()?.output_device_and_config()?;
+    let stream_config: cpal::StreamConfig = config.clone().into();
+    let output_channels = stream_config.channels.max(1) as usize;
+    let mut cursor = RegionCursor::new(buffer, start, end, stream_config.sample_rate.0);
+    cursor.looping = looping;
+
+    // Render into f32 frames, then convert to the device format
+    let mut frame = vec![0.0f32; output_channels];
+    let mut render = move |data: &mut dyn FnMut(usize, f32), len: usize| {
+        for index in 0..len / output_channels {
+            if !cursor.next_frame(&mut frame) {
+                finished.store(true, Ordering::Relaxed);
+            }
+            for (channel, &sample) in frame.iter().enumerate() {
+                data(index * output_channels + channel, sample);
+            }
+        }
+        position.store(cursor.frame_index(), Ordering::Relaxed);
+    };
+
+    let error_callback = |err| error!("Audio output error: {}", err);
+    let stream = match config.sample_format() {
+        cpal::SampleFormat::F32 => device.build_output_stream(
+            &stream_config,
+            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                let len = data.len();
+                render(&mut |i, s| data[i] = s, len);
+            },
+            error_callback,
+            None,
+        ),
+        cpal::SampleFormat::I16 => device.build_output_stream(
+            &stream_config,
+            move |data: &mut [i16], _: &cpal::OutputCallbackInfo| {
+                let len = data.len();
+                render(&mut |i, s| data[i] = (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16, len);
+            },
+            error_callback,
+            None,
+        ),
+        cpal::SampleFormat::U16 => device.build_output_stream(
+            &stream_config,
+            move |data: &mut [u16], _: &cpal::OutputCallbackInfo| {
+                let len = data.len();
+                render(&mut |i, s| data[i] = f32_to_u16(s), len);
+            },
+            error_callback,
+            None,
+        ),
+        cpal::SampleFormat::I32 => device.build_output_stream(
+            &stream_config,
+            move |data: &mut [i32], _: &cpal::OutputCallbackInfo| {
+                let len = data.len();
+                render(&mut |i, s| data[i] = f32_to_i32(s), len);
+            },
+            error_callback,
+            None,
+        ),
+        format => return Err(BatcherbirdError::Audio(format!("Unsupported output sample format: {:?}", format))),
+    }.map_err(|e| BatcherbirdError::Audio(format!("Failed to build output stream: {}", e)))
+        .inspect_err(|_| AudioManager::clear_device_cache())?;
+
+    stream.play()
+        .map_err(|e| BatcherbirdError::Audio(format!("Failed to start playback: {}", e)))
+        .inspect_err(|_| AudioManager::clear_device_cache())?;
+    Ok(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_region_cursor_resamples_and_maps_channels() {
+        // Stereo ramp: left = frame, right = -frame
+        let audio: Vec<f32> = (0..8).flat_map(|f| [f as f32, -(f as f32)]).collect();
+        let buffer = PlaybackBuffer { audio: Arc::new(audio), channels: 2, sample_rate: 44100 };
+
+        // Half-rate output steps two source frames per output frame
+        let mut cursor = RegionCursor::new(buffer.clone(), 2, 6, 22050);
+        let mut frame = [0.0f32; 2];
+        let mut played = Vec::new();
+        while cursor.next_frame(&mut frame) {
+            played.push(frame);
+        }
+        assert_eq!(played, vec![[2.0, -2.0], [4.0, -4.0]]);
+
+        // Mono output mixes both channels; double-rate output interpolates between frames
+        let mut cursor = RegionCursor::new(buffer, 0, 8, 88200);
+        let mut mono = [0.0f32; 1];
+        assert!(cursor.next_frame(&mut mono));
+        assert_eq!(mono[0], 0.0);
+        let mut stereo = [0.0f32; 2];
+        assert!(cursor.next_frame(&mut stereo));
+        assert_eq!(stereo, [0.5, -0.5]);
+    }
+
+    #[test]
+    fn test_loop_cursor_wraps_with_crossfade() {
+        let audio: Vec<f32> = (0..16).map(|f| f as f32).collect();
+        let buffer = PlaybackBuffer { audio: Arc::new(audio), channels: 1, sample_rate: 44100 };
+        let control = Arc::new(LoopControl::new(8, 12, 2));
+
+        let mut cursor = RegionCursor::new(buffer, 8, 16, 44100);
+        cursor.looping = Some(control.clone());
+        let mut frame = [0.0f32; 1];
+        let mut played = Vec::new();
+        for _ in 0..6 {
+            assert!(cursor.next_frame(&mut frame));
+            played.push(frame[0]);
+        }
+        // Frames 10 and 11 fade towards 6 and 7, then playback wraps to 8
+        assert_eq!(played, vec![8.0, 9.0, 10.0, 9.0, 8.0, 9.0]);
+
+        // Moving the end behind the playhead jumps back into the new loop
+        control.set(2, 5, 0);
+        assert!(cursor.next_frame(&mut frame));
+        assert!((2.0..5.0).contains(&frame[0]));
+    }
+}
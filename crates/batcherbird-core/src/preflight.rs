@@ -0,0 +1,289 @@
+//! Checks run before a batch so a setup problem stops it up front with one report, instead of
+//! surfacing as a failed write or a folder of silent takes halfway through: the output folder
+//! is writable with room to spare, the input delivers audio, and the synth answers a test note.
+
+use crate::{Result, BatcherbirdError};
+use crate::audio::AudioManager;
+use crate::sampler::{block_on, Sample, SamplingConfig, SamplingEngine};
+use midir::MidiOutputConnection;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Free space required when the batch size isn't known
+pub const DEFAULT_MIN_FREE_BYTES: u64 = 500 * 1024 * 1024;
+
+/// Pre-roll before the test note, measured as the input's noise floor
+const TEST_PRE_ROLL_MS: u64 = 200;
+/// How far the test note must rise above the noise floor
+const MIN_RISE_DB: f32 = 10.0;
+/// Peaks this close to full scale will clip at louder velocities
+const HEADROOM_WARNING_DB: f32 = -0.5;
+
+#[derive(Debug, Clone)]
+pub struct PreflightConfig {
+    pub output_directory: PathBuf,
+    /// Disk space the batch needs, e.g. a `SamplingPlan`'s total (None = DEFAULT_MIN_FREE_BYTES)
+    pub required_bytes: Option<u64>,
+    /// Note played to check the synth responds (None = check the input only)
+    pub test_note: Option<u8>,
+    pub test_velocity: u8,
+    /// Quietest test note peak that counts as the synth responding (dBFS)
+    pub min_signal_db: f32,
+}
+
+impl Default for PreflightConfig {
+    fn default() -> Self {
+        Self {
+            output_directory: PathBuf::from("./samples"),
+            required_bytes: None,
+            test_note: Some(60),
+            test_velocity: 100,
+            min_signal_db: -50.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PreflightCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl PreflightCheck {
+    fn new(name: &'static str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self { name, status, detail: detail.into() }
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct PreflightReport {
+    pub checks: Vec<PreflightCheck>,
+}
+
+impl PreflightReport {
+    /// No check failed (warnings allowed)
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.status != CheckStatus::Fail)
+    }
+
+    pub fn summary(&self) -> String {
+        let mut report = format!("🛫 Pre-flight: {}\n", if self.passed() { "ready to record" } else { "not ready, batch not started" });
+        for check in &self.checks {
+            let icon = match check.status {
+                CheckStatus::Pass => "✅",
+                CheckStatus::Warn => "⚠️",
+                CheckStatus::Fail => "❌",
+            };
+            report.push_str(&format!("  {} {}: {}\n", icon, check.name, check.detail));
+        }
+        report
+    }
+
+    /// The report as an error when any check failed
+    pub fn into_result(self) -> Result<Self> {
+        if self.passed() {
+            Ok(self)
+        } else {
+            Err(BatcherbirdError::Config(self.summary().trim_end().to_string()))
+        }
+    }
+}
+
+/// Run every check. With a MIDI connection and a test note, a short note is recorded with
+/// `sampling`'s channel and velocity curve; without, the input is checked on its own.
+pub async fn run_async(sampling: &SamplingConfig, midi_conn: Option<&mut MidiOutputConnection>, config: &PreflightConfig) -> PreflightReport {
+    let mut report = PreflightReport::default();
+    report.checks.push(check_output_directory(&config.output_directory));
+    report.checks.push(check_free_space(&config.output_directory, config.required_bytes.unwrap_or(DEFAULT_MIN_FREE_BYTES)));
+
+    match (midi_conn, config.test_note) {
+        (Some(midi_conn), Some(note)) => {
+            let test_config = SamplingConfig {
+                note_duration_ms: 400,
+                release_time_ms: 200,
+                pre_delay_ms: TEST_PRE_ROLL_MS,
+                post_delay_ms: 50,
+                velocity: config.test_velocity,
+                hooks: Default::default(),
+                ..sampling.clone()
+            };
+            let take = match SamplingEngine::new(test_config) {
+                Ok(engine) => engine.sample_single_note_async(midi_conn, note).await,
+                Err(e) => Err(e),
+            };
+            match take {
+                Ok(sample) => report.checks.extend(check_test_note(&sample, TEST_PRE_ROLL_MS, config.min_signal_db)),
+                Err(e) => report.checks.push(PreflightCheck::new("Audio input", CheckStatus::Fail, format!("test note could not be recorded: {}", e))),
+            }
+        }
+        (None, Some(_)) => {
+            report.checks.push(PreflightCheck::new("Synth response", CheckStatus::Fail, "no MIDI output to send the test note to"));
+            report.checks.push(check_input_only());
+        }
+        (_, None) => report.checks.push(check_input_only()),
+    }
+
+    for check in &report.checks {
+        match check.status {
+            CheckStatus::Fail => warn!("❌ Pre-flight {}: {}", check.name, check.detail),
+            CheckStatus::Warn => warn!("⚠️ Pre-flight {}: {}", check.name, check.detail),
+            CheckStatus::Pass => info!("✅ Pre-flight {}: {}", check.name, check.detail),
+        }
+    }
+    report
+}
+
+/// Blocking interface for the GUI's capture threads
+pub fn run_blocking(sampling: &SamplingConfig, midi_conn: Option<&mut MidiOutputConnection>, config: &PreflightConfig) -> PreflightReport {
+    block_on(async { Ok(run_async(sampling, midi_conn, config).await) }).unwrap_or_else(|e| PreflightReport {
+        checks: vec![PreflightCheck::new("Audio input", CheckStatus::Fail, e.to_string())],
+    })
+}
+
+fn check_output_directory(directory: &Path) -> PreflightCheck {
+    const NAME: &str = "Output folder";
+    if let Err(e) = std::fs::create_dir_all(directory) {
+        return PreflightCheck::new(NAME, CheckStatus::Fail, format!("can't create {}: {}", directory.display(), e));
+    }
+    let probe = directory.join(".batcherbird_preflight");
+    match std::fs::write(&probe, b"preflight") {
+        Ok(()) => {
+            std::fs::remove_file(&probe).ok();
+            PreflightCheck::new(NAME, CheckStatus::Pass, format!("{} is writable", directory.display()))
+        }
+        Err(e) => PreflightCheck::new(NAME, CheckStatus::Fail, format!("can't write to {}: {}", directory.display(), e)),
+    }
+}
+
+fn check_free_space(directory: &Path, required_bytes: u64) -> PreflightCheck {
+    const NAME: &str = "Disk space";
+    let mb = |bytes: u64| bytes as f64 / (1024.0 * 1024.0);
+    match available_bytes(directory) {
+        Some(free) if free >= required_bytes => {
+            PreflightCheck::new(NAME, CheckStatus::Pass, format!("{:.0} MB free, {:.0} MB needed", mb(free), mb(required_bytes)))
+        }
+        Some(free) => PreflightCheck::new(NAME, CheckStatus::Fail, format!("only {:.0} MB free, {:.0} MB needed", mb(free), mb(required_bytes))),
+        None => PreflightCheck::new(NAME, CheckStatus::Warn, "free space unknown on this platform"),
+    }
+}
+
+/// Noise floor from the pre-roll, then the note's peak against it and against full scale
+fn check_test_note(sample: &Sample, pre_roll_ms: u64, min_signal_db: f32) -> Vec<PreflightCheck> {
+    if sample.audio_data.is_empty() {
+        return vec![PreflightCheck::new("Audio input", CheckStatus::Fail, "no audio arrived from the input device")];
+    }
+    let pre_roll = (sample.sample_rate as u64 * pre_roll_ms / 1000) as usize * sample.channels.max(1) as usize;
+    let (floor, note) = sample.audio_data.split_at(pre_roll.min(sample.audio_data.len()));
+    let peak_db = |audio: &[f32]| to_db(audio.iter().fold(0.0f32, |peak, s| peak.max(s.abs())));
+    let (floor_db, note_db) = (peak_db(floor), peak_db(note));
+
+    let mut checks = vec![if floor.iter().all(|&s| s == 0.0) {
+        PreflightCheck::new("Audio input", CheckStatus::Warn, "input is digital silence; check the selected input and its gain")
+    } else {
+        PreflightCheck::new("Audio input", CheckStatus::Pass, format!("receiving audio, noise floor {:.1} dBFS", floor_db))
+    }];
+
+    checks.push(if note_db >= min_signal_db && note_db >= floor_db + MIN_RISE_DB {
+        PreflightCheck::new("Synth response", CheckStatus::Pass, format!("test note {} peaked at {:.1} dBFS", sample.note, note_db))
+    } else {
+        PreflightCheck::new("Synth response", CheckStatus::Fail, format!(
+            "no signal from test note {} (peak {:.1} dBFS); check MIDI routing, the synth's channel and volume, and the input cable",
+            sample.note, note_db))
+    });
+    if note_db > HEADROOM_WARNING_DB {
+        checks.push(PreflightCheck::new("Headroom", CheckStatus::Warn, format!("test note peaked at {:.1} dBFS; lower the input gain to avoid clipping", note_db)));
+    }
+    checks
+}
+
+/// Without MIDI: a second of input, to see the device delivers audio at all
+fn check_input_only() -> PreflightCheck {
+    match AudioManager::new().and_then(|audio| audio.record_test_audio(1)) {
+        Ok(audio) if audio.is_empty() => PreflightCheck::new("Audio input", CheckStatus::Fail, "no audio arrived from the input device"),
+        Ok(audio) if audio.iter().all(|&s| s == 0.0) => {
+            PreflightCheck::new("Audio input", CheckStatus::Warn, "input is digital silence; check the selected input and its gain")
+        }
+        Ok(audio) => {
+            let peak = audio.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+            PreflightCheck::new("Audio input", CheckStatus::Pass, format!("receiving audio, peak {:.1} dBFS", to_db(peak)))
+        }
+        Err(e) => PreflightCheck::new("Audio input", CheckStatus::Fail, e.to_string()),
+    }
+}
+
+fn to_db(level: f32) -> f32 {
+    if level > 0.0 { 20.0 * level.log10() } else { -120.0 }
+}
+
+#[cfg(unix)]
+#[allow(clippy::unnecessary_cast)] // statvfs field widths differ between platforms
+fn available_bytes(directory: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+    let path = std::ffi::CString::new(directory.as_os_str().as_bytes()).ok()?;
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is NUL-terminated and `stats` is a valid statvfs to fill in
+    let status = unsafe { libc::statvfs(path.as_ptr(), &mut stats) };
+    (status == 0).then(|| stats.f_bavail as u64 * stats.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+fn available_bytes(_directory: &Path) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn take(audio_data: Vec<f32>) -> Sample {
+        Sample {
+            note: 60,
+            velocity: 100,
+            audio_data,
+            sample_rate: 1000,
+            channels: 1,
+            recorded_at: SystemTime::now(),
+            midi_timing: Duration::ZERO,
+            audio_timing: Duration::ZERO,
+            metadata: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_preflight_checks_folder_space_and_test_note() {
+        let dir = std::env::temp_dir().join("batcherbird_test_preflight");
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(check_output_directory(&dir).status, CheckStatus::Pass);
+        assert!(dir.is_dir() && std::fs::read_dir(&dir).unwrap().next().is_none());
+        if cfg!(unix) {
+            assert_eq!(check_free_space(&dir, 1).status, CheckStatus::Pass);
+            assert_eq!(check_free_space(&dir, u64::MAX).status, CheckStatus::Fail);
+        }
+
+        // 200ms of noise floor at 1 kHz, then the note
+        let mut audio = vec![0.001; 200];
+        audio.extend(std::iter::repeat_n(0.5, 400));
+        let statuses = |checks: Vec<PreflightCheck>| checks.iter().map(|c| (c.name, c.status)).collect::<Vec<_>>();
+        assert_eq!(statuses(check_test_note(&take(audio), 200, -50.0)),
+            [("Audio input", CheckStatus::Pass), ("Synth response", CheckStatus::Pass)]);
+        assert_eq!(statuses(check_test_note(&take(vec![0.001; 600]), 200, -50.0)),
+            [("Audio input", CheckStatus::Pass), ("Synth response", CheckStatus::Fail)]);
+        assert_eq!(statuses(check_test_note(&take(vec![0.0; 200].into_iter().chain(vec![1.0; 400]).collect()), 200, -50.0)),
+            [("Audio input", CheckStatus::Warn), ("Synth response", CheckStatus::Pass), ("Headroom", CheckStatus::Warn)]);
+
+        let report = PreflightReport { checks: check_test_note(&take(vec![0.001; 600]), 200, -50.0) };
+        let error = report.into_result().unwrap_err().to_string();
+        assert!(error.contains("not ready") && error.contains("Synth response"), "{}", error);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,212 @@
+use crate::library::LibrarySession;
+use crate::waveform::WaveformPeaks;
+use crate::{BatcherbirdError, Result};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// File name of the contact sheet written into the session folder
+pub const CONTACT_SHEET_FILENAME: &str = "batcherbird_preview.png";
+
+const BACKGROUND: [u8; 3] = [24, 24, 28];
+const CELL: [u8; 3] = [38, 38, 46];
+const CENTER_LINE: [u8; 3] = [70, 70, 80];
+const WAVE: [u8; 3] = [110, 200, 255];
+const LABEL: [u8; 3] = [225, 225, 225];
+
+/// Pixel size of one glyph pixel in the labels
+const GLYPH_SCALE: u32 = 2;
+const LABEL_HEIGHT: u32 = 5 * GLYPH_SCALE + 4;
+
+/// Layout of the waveform grid
+#[derive(Debug, Clone)]
+pub struct ContactSheetConfig {
+    pub thumb_width: u32,
+    pub thumb_height: u32,
+    pub columns: u32,
+    /// Gap between thumbnails and around the sheet
+    pub padding: u32,
+}
+
+impl Default for ContactSheetConfig {
+    fn default() -> Self {
+        Self { thumb_width: 160, thumb_height: 64, columns: 8, padding: 6 }
+    }
+}
+
+/// One thumbnail: a label ("C#4 v127") over the waveform
+pub struct Thumbnail {
+    pub label: String,
+    pub peaks: WaveformPeaks,
+}
+
+/// 8-bit RGB image
+#[derive(Debug, Clone)]
+pub struct ContactSheet {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl ContactSheet {
+    /// Lay the thumbnails out in rows, in the order given. Waveforms share one scale, so
+    /// level differences between velocity layers stay visible.
+    pub fn render(thumbnails: &[Thumbnail], config: &ContactSheetConfig) -> Self {
+        let columns = config.columns.max(1);
+        let rows = (thumbnails.len() as u32).div_ceil(columns).max(1);
+        let cell_height = LABEL_HEIGHT + config.thumb_height;
+        let width = config.padding + columns * (config.thumb_width + config.padding);
+        let height = config.padding + rows * (cell_height + config.padding);
+        let mut sheet = Self { width, height, pixels: BACKGROUND.repeat((width * height) as usize) };
+
+        for (index, thumbnail) in thumbnails.iter().enumerate() {
+            let x = config.padding + (index as u32 % columns) * (config.thumb_width + config.padding);
+            let y = config.padding + (index as u32 / columns) * (cell_height + config.padding);
+            sheet.fill(x, y, config.thumb_width, cell_height, CELL);
+            sheet.draw_text(x + 3, y + 2, &thumbnail.label);
+            sheet.draw_waveform(x, y + LABEL_HEIGHT, config.thumb_width, config.thumb_height, &thumbnail.peaks);
+        }
+        sheet
+    }
+
+    /// Write the sheet as a PNG file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let file = std::fs::File::create(path).map_err(BatcherbirdError::Export)?;
+        let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.pixels))
+            .map_err(|e| BatcherbirdError::Processing(format!("Failed to write {}: {}", path.display(), e)))
+    }
+
+    fn draw_waveform(&mut self, x: u32, y: u32, width: u32, height: u32, peaks: &WaveformPeaks) {
+        let middle = y + height / 2;
+        self.fill(x, middle, width, 1, CENTER_LINE);
+
+        let level = peaks.level_for_width(width as usize);
+        if level.is_empty() {
+            return;
+        }
+        let half = (height / 2).saturating_sub(1) as f32;
+        for column in 0..width {
+            // Peaks under this pixel column, merged across channels
+            let first = column as usize * level.len() / width as usize;
+            let last = ((column as usize + 1) * level.len() / width as usize).max(first + 1).min(level.len());
+            let high = level.max.iter().flat_map(|channel| &channel[first..last]).copied().fold(f32::MIN, f32::max);
+            let low = level.min.iter().flat_map(|channel| &channel[first..last]).copied().fold(f32::MAX, f32::min);
+
+            let top = middle as f32 - high.clamp(-1.0, 1.0) * half;
+            let bottom = middle as f32 - low.clamp(-1.0, 1.0) * half;
+            let top = top.round() as u32;
+            let bottom = (bottom.round() as u32).max(top);
+            self.fill(x + column, top, 1, bottom - top + 1, WAVE);
+        }
+    }
+
+    fn draw_text(&mut self, x: u32, y: u32, text: &str) {
+        for (index, character) in text.chars().enumerate() {
+            let origin = x + index as u32 * 4 * GLYPH_SCALE;
+            for (row, bits) in glyph(character).iter().enumerate() {
+                for column in 0..3 {
+                    if bits & (0b100 >> column) != 0 {
+                        self.fill(origin + column * GLYPH_SCALE, y + row as u32 * GLYPH_SCALE, GLYPH_SCALE, GLYPH_SCALE, LABEL);
+                    }
+                }
+            }
+        }
+    }
+
+    fn fill(&mut self, x: u32, y: u32, width: u32, height: u32, color: [u8; 3]) {
+        for row in y..(y + height).min(self.height) {
+            for column in x..(x + width).min(self.width) {
+                let offset = ((row * self.width + column) * 3) as usize;
+                self.pixels[offset..offset + 3].copy_from_slice(&color);
+            }
+        }
+    }
+}
+
+/// Render a contact sheet of every sample in an exported session folder (by note, then
+/// velocity) and write it into the folder
+pub fn write_contact_sheet(directory: &Path, config: &ContactSheetConfig) -> Result<PathBuf> {
+    let mut session = LibrarySession::load(directory)?;
+    if session.samples.is_empty() {
+        return Err(BatcherbirdError::Session(format!("No samples to preview in {}", directory.display())));
+    }
+    session.samples.sort_by_key(|s| (s.sample.note, s.sample.velocity));
+
+    let thumbnails: Vec<Thumbnail> = session.samples.iter()
+        .map(|s| Thumbnail {
+            label: format!("{} v{}", note_to_name(s.sample.note), s.sample.velocity),
+            peaks: WaveformPeaks::from_sample(&s.sample),
+        })
+        .collect();
+    let path = directory.join(CONTACT_SHEET_FILENAME);
+    ContactSheet::render(&thumbnails, config).save(&path)?;
+
+    info!("🖼️ Wrote preview of {} samples: {}", thumbnails.len(), path.display());
+    Ok(path)
+}
+
+fn note_to_name(note: u8) -> String {
+    let note_names = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+    let octave = (note / 12) as i32 - 1;
+    format!("{}{}", note_names[(note % 12) as usize], octave)
+}
+
+/// 3x5 pixel glyphs for note names and velocities, one row per entry
+fn glyph(character: char) -> [u8; 5] {
+    match character {
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b111, 0b100, 0b100, 0b100, 0b111],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b111, 0b100, 0b101, 0b101, 0b111],
+        '#' => [0b101, 0b111, 0b101, 0b111, 0b101],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        'v' => [0b000, 0b000, 0b101, 0b101, 0b010],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        _ => [0; 5],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contact_sheet_layout_and_png() {
+        let sine: Vec<f32> = (0..44100).map(|i| 0.8 * (i as f32 * 0.01).sin()).collect();
+        let thumbnails: Vec<Thumbnail> = (0..10)
+            .map(|i| Thumbnail { label: format!("C{} v127", i), peaks: WaveformPeaks::from_interleaved(&sine, 1, 44100) })
+            .collect();
+        let config = ContactSheetConfig { thumb_width: 100, thumb_height: 40, columns: 4, padding: 5 };
+        let sheet = ContactSheet::render(&thumbnails, &config);
+
+        assert_eq!(sheet.width, 5 + 4 * 105);
+        assert_eq!(sheet.height, 5 + 3 * (LABEL_HEIGHT + 40 + 5));
+        assert_eq!(sheet.pixels.len(), (sheet.width * sheet.height * 3) as usize);
+        // Waveform reaches near the top of the first thumbnail; the unused last cells stay background
+        let pixel = |x: u32, y: u32| &sheet.pixels[((y * sheet.width + x) * 3) as usize..][..3];
+        let wave_top = 5 + LABEL_HEIGHT + 20 - 15;
+        assert!((5..105).any(|x| pixel(x, wave_top) == WAVE));
+        assert_eq!(pixel(sheet.width - 10, sheet.height - 10), BACKGROUND);
+
+        let path = std::env::temp_dir().join(format!("batcherbird_preview_{}.png", std::process::id()));
+        sheet.save(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[1..4], b"PNG");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
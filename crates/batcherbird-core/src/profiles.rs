@@ -0,0 +1,338 @@
+use crate::{Result, BatcherbirdError};
+use crate::config::NoteRange;
+use crate::detection::DetectionConfig;
+use crate::hooks::HookConfig;
+use crate::identity::SynthIdentity;
+use crate::midi::PanicStyle;
+use crate::midi_clock::MidiClockConfig;
+use crate::sampler::SamplingConfig;
+use crate::watchdog::WatchdogConfig;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Profiles shipped with Batcherbird. User profiles with the same id replace these.
+const BUILTIN_PROFILES: &str = r#"
+[[profile]]
+id = "generic"
+name = "Generic MIDI Synth"
+midi_channel = 1
+note_range = { start = 36, end = 84 }
+program_change_settle_ms = 200
+panic_style = "full"
+detection_preset = "default"
+
+[[profile]]
+id = "vintage-analog"
+name = "Vintage Analog Poly"
+midi_channel = 1
+note_range = { start = 36, end = 96 }
+program_change_settle_ms = 500
+panic_style = "full"
+detection_preset = "vintage_synth"
+release_time_ms = 1500
+
+[[profile]]
+id = "korg-dw6000"
+name = "Korg DW-6000"
+midi_channel = 1
+note_range = { start = 36, end = 96 }
+program_change_settle_ms = 600
+panic_style = "full"
+detection_preset = "vintage_synth"
+
+[[profile]]
+id = "modern-poly"
+name = "Modern Polysynth"
+midi_channel = 1
+note_range = { start = 24, end = 108 }
+program_change_settle_ms = 100
+panic_style = "channel"
+detection_preset = "default"
+
+[[profile]]
+id = "drum-machine"
+name = "Drum Machine (GM channel 10)"
+midi_channel = 10
+note_range = { start = 35, end = 81 }
+program_change_settle_ms = 200
+panic_style = "none"
+detection_preset = "percussive"
+note_duration_ms = 500
+release_time_ms = 1000
+"#;
+
+/// Named detection settings a profile can recommend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DetectionPreset {
+    #[default]
+    Default,
+    Percussive,
+    Sustained,
+    VintageSynth,
+}
+
+impl DetectionPreset {
+    pub fn config(&self) -> DetectionConfig {
+        match self {
+            DetectionPreset::Default => DetectionConfig::default(),
+            DetectionPreset::Percussive => DetectionConfig::percussive(),
+            DetectionPreset::Sustained => DetectionConfig::sustained(),
+            DetectionPreset::VintageSynth => DetectionConfig::vintage_synth(),
+        }
+    }
+}
+
+/// Per-synth sampling defaults so timing doesn't have to be re-tuned for every device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SynthProfile {
+    /// Stable identifier used on the command line and in saved settings
+    pub id: String,
+    pub name: String,
+    /// Manufacturer name as reported by Device Inquiry (see SynthIdentity::manufacturer_name)
+    #[serde(default)]
+    pub manufacturer: Option<String>,
+    /// Device family code from Device Inquiry
+    #[serde(default)]
+    pub family: Option<u16>,
+    /// Device model code from Device Inquiry
+    #[serde(default)]
+    pub model: Option<u16>,
+    /// MIDI channel as shown on the synth (1-16)
+    pub midi_channel: u8,
+    pub note_range: NoteRange,
+    pub program_change_settle_ms: u64,
+    #[serde(default)]
+    pub panic_style: PanicStyle,
+    #[serde(default)]
+    pub detection_preset: DetectionPreset,
+    #[serde(default)]
+    pub note_duration_ms: Option<u64>,
+    #[serde(default)]
+    pub release_time_ms: Option<u64>,
+    /// Send MIDI clock at this tempo during capture (arpeggiated / tempo-synced patches)
+    #[serde(default)]
+    pub clock_bpm: Option<f32>,
+    /// Note and batch time limits, for synths or interfaces known to hang
+    #[serde(default)]
+    pub watchdog: Option<WatchdogConfig>,
+    /// Commands or Rhai scripts run before and after each note and after each export (e.g. to switch gear)
+    #[serde(default)]
+    pub hooks: Option<HookConfig>,
+}
+
+impl SynthProfile {
+    /// 0-indexed MIDI channel for the wire
+    pub fn channel_index(&self) -> u8 {
+        self.midi_channel.clamp(1, 16) - 1
+    }
+
+    /// Apply this profile on top of an existing sampling configuration
+    pub fn apply_to(&self, config: SamplingConfig) -> SamplingConfig {
+        SamplingConfig {
+            midi_channel: self.channel_index(),
+            panic_style: self.panic_style,
+            note_duration_ms: self.note_duration_ms.unwrap_or(config.note_duration_ms),
+            release_time_ms: self.release_time_ms.unwrap_or(config.release_time_ms),
+            clock: self.clock().or(config.clock),
+            watchdog: self.watchdog.clone().unwrap_or(config.watchdog),
+            hooks: self.hooks.clone().unwrap_or(config.hooks),
+            ..config
+        }
+    }
+
+    /// Clock settings for this profile's tempo, if it has one
+    pub fn clock(&self) -> Option<MidiClockConfig> {
+        self.clock_bpm.and_then(|bpm| MidiClockConfig::new(bpm).ok())
+    }
+
+    pub fn detection_config(&self) -> DetectionConfig {
+        self.detection_preset.config()
+    }
+
+    pub fn contains_note(&self, note: u8) -> bool {
+        note >= self.note_range.start && note <= self.note_range.end
+    }
+
+    /// How specifically this profile matches an identity (0 = no match)
+    fn match_score(&self, identity: &SynthIdentity) -> u8 {
+        let Some(manufacturer) = &self.manufacturer else {
+            return 0;
+        };
+        if !manufacturer.eq_ignore_ascii_case(&identity.manufacturer_name()) {
+            return 0;
+        }
+
+        let family_ok = self.family.is_none_or(|family| family == identity.family);
+        let model_ok = self.model.is_none_or(|model| model == identity.model);
+        if !family_ok || !model_ok {
+            return 0;
+        }
+
+        1 + self.family.is_some() as u8 + self.model.is_some() as u8
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.id.trim().is_empty() {
+            return Err(BatcherbirdError::Config("Synth profile is missing an id".to_string()));
+        }
+        if !(1..=16).contains(&self.midi_channel) {
+            return Err(BatcherbirdError::Config(format!(
+                "Profile '{}': midi_channel must be 1-16, got {}", self.id, self.midi_channel
+            )));
+        }
+        if self.note_range.start > self.note_range.end || self.note_range.end > 127 {
+            return Err(BatcherbirdError::Config(format!(
+                "Profile '{}': invalid note range {}-{}", self.id, self.note_range.start, self.note_range.end
+            )));
+        }
+        if let Some(bpm) = self.clock_bpm {
+            MidiClockConfig::new(bpm).map_err(|e| BatcherbirdError::Config(format!("Profile '{}': {}", self.id, e)))?;
+        }
+        Ok(())
+    }
+}
+
+/// TOML file layout: one `[[profile]]` table per synth
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProfileFile {
+    #[serde(default, rename = "profile")]
+    profiles: Vec<SynthProfile>,
+}
+
+/// Built-in profiles plus any user-defined ones
+#[derive(Debug, Clone)]
+pub struct ProfileLibrary {
+    profiles: Vec<SynthProfile>,
+}
+
+impl ProfileLibrary {
+    pub fn builtin() -> Self {
+        let file: ProfileFile = toml::from_str(BUILTIN_PROFILES)
+            .expect("built-in synth profiles must parse");
+        Self { profiles: file.profiles }
+    }
+
+    /// Built-in profiles plus everything in the user profile directory.
+    /// Broken user files are reported and skipped so one typo doesn't hide every profile.
+    pub fn load_default() -> Self {
+        let mut library = Self::builtin();
+
+        if let Some(dir) = Self::user_profile_dir() {
+            if let Ok(entries) = std::fs::read_dir(&dir) {
+                let mut files: Vec<PathBuf> = entries
+                    .filter_map(|entry| entry.ok().map(|e| e.path()))
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+                    .collect();
+                files.sort();
+
+                for file in files {
+                    if let Err(e) = library.load_file(&file) {
+                        warn!("⚠️ Skipping synth profile file {}: {}", file.display(), e);
+                    }
+                }
+            }
+        }
+
+        library
+    }
+
+    /// Where user profiles live (e.g. ~/.config/Batcherbird/profiles on Linux)
+    pub fn user_profile_dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("Batcherbird").join("profiles"))
+    }
+
+    /// Add or replace profiles from a TOML file. Returns how many were loaded.
+    pub fn load_file(&mut self, path: &Path) -> Result<usize> {
+        let content = std::fs::read_to_string(path)?;
+        self.load_str(&content)
+    }
+
+    pub fn load_str(&mut self, content: &str) -> Result<usize> {
+        let file: ProfileFile = toml::from_str(content)?;
+        for profile in &file.profiles {
+            profile.validate()?;
+        }
+
+        let count = file.profiles.len();
+        for profile in file.profiles {
+            self.profiles.retain(|existing| existing.id != profile.id);
+            self.profiles.push(profile);
+        }
+        Ok(count)
+    }
+
+    pub fn profiles(&self) -> &[SynthProfile] {
+        &self.profiles
+    }
+
+    pub fn get(&self, id: &str) -> Option<&SynthProfile> {
+        self.profiles.iter().find(|profile| profile.id.eq_ignore_ascii_case(id))
+    }
+
+    /// Like `get`, but with an error listing the available ids
+    pub fn require(&self, id: &str) -> Result<&SynthProfile> {
+        self.get(id).ok_or_else(|| {
+            let ids: Vec<&str> = self.profiles.iter().map(|p| p.id.as_str()).collect();
+            BatcherbirdError::Config(format!("Unknown synth profile '{}'. Available: {}", id, ids.join(", ")))
+        })
+    }
+
+    /// Most specific profile matching a Device Inquiry reply (manufacturer + family + model wins)
+    pub fn find_for_identity(&self, identity: &SynthIdentity) -> Option<&SynthProfile> {
+        self.profiles.iter()
+            .map(|profile| (profile.match_score(identity), profile))
+            .filter(|(score, _)| *score > 0)
+            .max_by_key(|(score, _)| *score)
+            .map(|(_, profile)| profile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_and_user_profiles() {
+        let mut library = ProfileLibrary::builtin();
+        assert!(library.get("generic").is_some());
+        assert_eq!(library.require("drum-machine").unwrap().channel_index(), 9);
+
+        library.load_str(r#"
+            [[profile]]
+            id = "korg-any"
+            name = "Any Korg"
+            manufacturer = "Korg"
+            midi_channel = 1
+            note_range = { start = 36, end = 96 }
+            program_change_settle_ms = 300
+
+            [[profile]]
+            id = "korg-minilogue"
+            name = "Korg minilogue"
+            manufacturer = "Korg"
+            family = 0x2C
+            midi_channel = 2
+            note_range = { start = 24, end = 96 }
+            program_change_settle_ms = 150
+            panic_style = "channel"
+        "#).unwrap();
+
+        let identity = SynthIdentity {
+            device_id: 0,
+            manufacturer_id: vec![0x42],
+            family: 0x2C,
+            model: 0,
+            version: [0; 4],
+        };
+        // Family match beats the manufacturer-only profile
+        assert_eq!(library.find_for_identity(&identity).unwrap().id, "korg-minilogue");
+
+        let config = library.get("korg-minilogue").unwrap().apply_to(SamplingConfig::default());
+        assert_eq!(config.midi_channel, 1);
+        assert_eq!(config.panic_style, PanicStyle::Channel);
+
+        assert!(library.load_str("[[profile]]\nid = \"bad\"\nname = \"Bad\"\nmidi_channel = 17\nnote_range = { start = 0, end = 1 }\nprogram_change_settle_ms = 0\n").is_err());
+    }
+}
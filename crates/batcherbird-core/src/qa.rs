@@ -0,0 +1,473 @@
+use crate::hum::HumMeasurement;
+use crate::library::LibrarySession;
+use crate::loudness;
+use crate::pitch;
+use crate::sampler::Sample;
+use crate::{BatcherbirdError, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// File names of the QA report written next to the exported samples
+pub const QA_JSON_FILENAME: &str = "batcherbird_qa.json";
+pub const QA_REPORT_FILENAME: &str = "batcherbird_qa.txt";
+
+/// Settings for finding dropouts inside a sample
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SilentGapConfig {
+    /// Windows quieter than this count as silent (dBFS RMS)
+    pub threshold_db: f32,
+    /// Shortest silence reported as a gap (ms)
+    pub min_gap_ms: f32,
+    /// RMS analysis window (ms)
+    pub window_ms: f32,
+}
+
+impl Default for SilentGapConfig {
+    fn default() -> Self {
+        Self {
+            threshold_db: -60.0,
+            min_gap_ms: 30.0,
+            window_ms: 5.0,
+        }
+    }
+}
+
+/// Silence between two audible parts of a sample, e.g. a voice dropout or a retriggered note
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SilentGap {
+    /// Where the silence begins (ms from the start of the sample)
+    pub start_ms: f32,
+    pub duration_ms: f32,
+}
+
+/// Find silent stretches surrounded by sound. Leading silence and the fade into the
+/// release tail are expected and never reported.
+pub fn find_silent_gaps(audio: &[f32], channels: u16, sample_rate: u32, config: &SilentGapConfig) -> Vec<SilentGap> {
+    let channel_count = channels.max(1) as usize;
+    let window_frames = ((config.window_ms / 1000.0) * sample_rate as f32).max(1.0) as usize;
+    let threshold = 10.0_f32.powf(config.threshold_db / 20.0);
+
+    let loud: Vec<bool> = audio
+        .chunks(window_frames * channel_count)
+        .map(|window| (window.iter().map(|s| s * s).sum::<f32>() / window.len() as f32).sqrt() > threshold)
+        .collect();
+    let window_ms = window_frames as f32 / sample_rate.max(1) as f32 * 1000.0;
+
+    let mut gaps = Vec::new();
+    let mut quiet_since = None;
+    let mut heard_sound = false;
+    for (index, &is_loud) in loud.iter().enumerate() {
+        if !is_loud {
+            if heard_sound && quiet_since.is_none() {
+                quiet_since = Some(index);
+            }
+            continue;
+        }
+        // Sound again after a quiet run: that run was internal
+        if let Some(start) = quiet_since.take() {
+            let duration_ms = (index - start) as f32 * window_ms;
+            if duration_ms >= config.min_gap_ms {
+                gaps.push(SilentGap { start_ms: start as f32 * window_ms, duration_ms });
+            }
+        }
+        heard_sound = true;
+    }
+    gaps
+}
+
+/// How the two sides of a stereo take relate
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StereoImage {
+    /// Pearson correlation between left and right (1 = mono, 0 = unrelated, -1 = polarity
+    /// flipped); None when a side is silent
+    pub correlation: Option<f32>,
+    /// Left RMS minus right RMS (dB)
+    pub imbalance_db: f32,
+}
+
+impl StereoImage {
+    /// None unless `audio` is two-channel and audible
+    pub fn measure(audio: &[f32], channels: u16) -> Option<Self> {
+        if channels != 2 {
+            return None;
+        }
+        let (mut left_power, mut right_power, mut cross) = (0.0f64, 0.0f64, 0.0f64);
+        for frame in audio.chunks_exact(2) {
+            let (left, right) = (frame[0] as f64, frame[1] as f64);
+            left_power += left * left;
+            right_power += right * right;
+            cross += left * right;
+        }
+        if left_power + right_power <= 0.0 {
+            return None;
+        }
+        let to_db = |power: f64| if power > 0.0 { (10.0 * power.log10()) as f32 } else { -96.0 };
+        let correlation = (left_power > 0.0 && right_power > 0.0)
+            .then(|| (cross / (left_power * right_power).sqrt()) as f32);
+        // Same frame count on both sides, so the power ratio is the RMS ratio
+        let imbalance_db = (to_db(left_power) - to_db(right_power)).clamp(-96.0, 96.0);
+        Some(Self { correlation, imbalance_db })
+    }
+}
+
+/// Limits for the post-batch QA pass
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QaConfig {
+    /// Compare the detected pitch with the requested note (off for drum kits)
+    pub check_pitch: bool,
+    /// Takes peaking below this are suspiciously quiet (dBFS)
+    pub min_peak_db: f32,
+    /// Runs of at least this many consecutive full-scale samples count as clipping
+    pub clip_run: usize,
+    /// Shortest audible length expected of a take (seconds)
+    pub min_audible_secs: f32,
+    /// Takes audible for less than this fraction of the batch median are suspect
+    pub min_duration_ratio: f32,
+    /// Largest acceptable mean (DC) offset
+    pub max_dc_offset: f32,
+    /// Inter-sample peaks above this will clip on playback or conversion (dBTP)
+    pub max_true_peak_db: f32,
+    /// Stereo takes correlating below this partly cancel when summed to mono
+    pub min_stereo_correlation: f32,
+    /// Larger level differences between left and right suggest a dead cable or wrong input (dB)
+    pub max_channel_imbalance_db: f32,
+    pub silent_gaps: SilentGapConfig,
+}
+
+impl Default for QaConfig {
+    fn default() -> Self {
+        Self {
+            check_pitch: true,
+            min_peak_db: -40.0,
+            clip_run: 3,
+            min_audible_secs: 0.05,
+            min_duration_ratio: 0.25,
+            max_dc_offset: 0.01,
+            max_true_peak_db: 0.0,
+            min_stereo_correlation: -0.2,
+            max_channel_imbalance_db: 10.0,
+            silent_gaps: SilentGapConfig::default(),
+        }
+    }
+}
+
+/// Something the QA pass found wrong with a take
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum QaIssue {
+    WrongNote { expected: u8, heard: u8, frequency_hz: f32 },
+    Quiet { peak_db: f32 },
+    Clipped { runs: usize },
+    TruePeakOver { true_peak_db: f32 },
+    MonoIncompatible { correlation: f32 },
+    ChannelImbalance { imbalance_db: f32 },
+    Short { audible_secs: f32, median_secs: f32 },
+    DcOffset { offset: f32 },
+    SilentGap { gap: SilentGap },
+}
+
+impl std::fmt::Display for QaIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QaIssue::WrongNote { expected, heard, frequency_hz } => {
+                write!(f, "heard note {} ({:.0} Hz) instead of {}", heard, frequency_hz, expected)
+            }
+            QaIssue::Quiet { peak_db } => write!(f, "quiet, peak {:.1} dBFS", peak_db),
+            QaIssue::Clipped { runs } => write!(f, "clipped in {} places", runs),
+            QaIssue::TruePeakOver { true_peak_db } => write!(f, "true peak {:+.1} dBTP", true_peak_db),
+            QaIssue::MonoIncompatible { correlation } => {
+                write!(f, "stereo correlation {:+.2}, cancels when summed to mono", correlation)
+            }
+            QaIssue::ChannelImbalance { imbalance_db } => write!(f, "{} channel {:.1} dB quieter",
+                if *imbalance_db > 0.0 { "right" } else { "left" }, imbalance_db.abs()),
+            QaIssue::Short { audible_secs, median_secs } => {
+                write!(f, "audible for {:.2}s (batch median {:.2}s)", audible_secs, median_secs)
+            }
+            QaIssue::DcOffset { offset } => write!(f, "DC offset {:+.3}", offset),
+            QaIssue::SilentGap { gap } => write!(f, "silent gap of {:.0}ms at {:.2}s", gap.duration_ms, gap.start_ms / 1000.0),
+        }
+    }
+}
+
+/// QA findings for one take
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SampleQa {
+    pub file: String,
+    pub note: u8,
+    pub velocity: u8,
+    pub issues: Vec<QaIssue>,
+}
+
+/// Result of the QA pass over a batch; only takes with issues are listed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QaReport {
+    pub checked: usize,
+    pub suspects: Vec<SampleQa>,
+    /// Mains hum in the batch's noise floor, reported for the whole session
+    #[serde(default)]
+    pub hum: Option<HumMeasurement>,
+}
+
+impl QaReport {
+    /// Check each take against `config`; `files` are where the takes were exported
+    pub fn run(samples: &[Sample], files: &[PathBuf], config: &QaConfig) -> Self {
+        let audible: Vec<f32> = samples.iter().map(|s| audible_secs(s, config.silent_gaps.threshold_db)).collect();
+        let mut sorted = audible.clone();
+        sorted.sort_by(f32::total_cmp);
+        let median_secs = sorted.get(sorted.len() / 2).copied().unwrap_or(0.0);
+
+        let suspects = samples.iter().zip(files).zip(&audible)
+            .filter_map(|((sample, file), &audible_secs)| {
+                let issues = check_sample(sample, audible_secs, median_secs, config);
+                (!issues.is_empty()).then(|| SampleQa {
+                    file: file.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default(),
+                    note: sample.note,
+                    velocity: sample.velocity,
+                    issues,
+                })
+            })
+            .collect();
+        Self { checked: samples.len(), suspects, hum: HumMeasurement::measure(samples) }
+    }
+
+    /// QA an exported session folder
+    pub fn check_directory(directory: &Path, config: &QaConfig) -> Result<Self> {
+        let session = LibrarySession::load(directory)?;
+        let samples: Vec<Sample> = session.samples.iter().map(|s| s.sample.clone()).collect();
+        let files: Vec<PathBuf> = session.samples.iter().map(|s| s.path.clone()).collect();
+        Ok(Self::run(&samples, &files, config))
+    }
+
+    /// Readable list of suspect takes to re-record
+    pub fn summary(&self) -> String {
+        let mut report = format!("QA: {} of {} samples need attention\n", self.suspects.len(), self.checked);
+        for suspect in &self.suspects {
+            let issues: Vec<String> = suspect.issues.iter().map(ToString::to_string).collect();
+            report.push_str(&format!("  ⚠️ {} (note {} vel {}): {}\n", suspect.file, suspect.note, suspect.velocity, issues.join("; ")));
+        }
+        if let Some(hum) = &self.hum {
+            report.push_str(&format!("  ⚡ {}\n", hum));
+        }
+        report
+    }
+
+    /// Write the JSON and readable reports into `directory`
+    pub fn save(&self, directory: &Path) -> Result<(PathBuf, PathBuf)> {
+        let json_path = directory.join(QA_JSON_FILENAME);
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| BatcherbirdError::Session(format!("Failed to serialize QA report: {}", e)))?;
+        std::fs::write(&json_path, json).map_err(BatcherbirdError::Export)?;
+        let report_path = directory.join(QA_REPORT_FILENAME);
+        std::fs::write(&report_path, self.summary()).map_err(BatcherbirdError::Export)?;
+
+        info!("🩺 Wrote QA report: {}", report_path.display());
+        Ok((json_path, report_path))
+    }
+}
+
+fn check_sample(sample: &Sample, audible_secs: f32, median_secs: f32, config: &QaConfig) -> Vec<QaIssue> {
+    let audio = &sample.audio_data;
+    let mut issues = Vec::new();
+
+    if config.check_pitch {
+        if let Some(estimate) = pitch::detect_pitch(audio, sample.channels, sample.sample_rate) {
+            if estimate.note != sample.note {
+                issues.push(QaIssue::WrongNote { expected: sample.note, heard: estimate.note, frequency_hz: estimate.frequency_hz });
+            }
+        }
+    }
+
+    let peak = audio.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+    let peak_db = if peak > 0.0 { 20.0 * peak.log10() } else { -96.0 };
+    if peak_db < config.min_peak_db {
+        issues.push(QaIssue::Quiet { peak_db });
+    }
+
+    let runs = clip_runs(audio, sample.channels, config.clip_run);
+    if runs > 0 {
+        issues.push(QaIssue::Clipped { runs });
+    }
+
+    let true_peak_db = 20.0 * loudness::true_peak(audio, sample.channels).max(1e-6).log10();
+    if true_peak_db > config.max_true_peak_db {
+        issues.push(QaIssue::TruePeakOver { true_peak_db });
+    }
+
+    if audible_secs < config.min_audible_secs || audible_secs < median_secs * config.min_duration_ratio {
+        issues.push(QaIssue::Short { audible_secs, median_secs });
+    }
+
+    if let Some(image) = StereoImage::measure(audio, sample.channels) {
+        if let Some(correlation) = image.correlation.filter(|&c| c < config.min_stereo_correlation) {
+            issues.push(QaIssue::MonoIncompatible { correlation });
+        }
+        if image.imbalance_db.abs() > config.max_channel_imbalance_db {
+            issues.push(QaIssue::ChannelImbalance { imbalance_db: image.imbalance_db });
+        }
+    }
+
+    let channels = sample.channels.max(1) as usize;
+    for channel in 0..channels {
+        let values = audio.iter().skip(channel).step_by(channels);
+        let count = values.clone().count().max(1);
+        let offset = values.sum::<f32>() / count as f32;
+        if offset.abs() > config.max_dc_offset {
+            issues.push(QaIssue::DcOffset { offset });
+            break;
+        }
+    }
+
+    issues.extend(find_silent_gaps(audio, sample.channels, sample.sample_rate, &config.silent_gaps)
+        .into_iter()
+        .map(|gap| QaIssue::SilentGap { gap }));
+    issues
+}
+
+/// Time from the first to the last frame above `threshold_db`
+fn audible_secs(sample: &Sample, threshold_db: f32) -> f32 {
+    let threshold = 10.0_f32.powf(threshold_db / 20.0);
+    let channels = sample.channels.max(1) as usize;
+    let loud = |frame: &[f32]| frame.iter().any(|s| s.abs() > threshold);
+    let frames = sample.audio_data.chunks_exact(channels);
+    let (Some(first), Some(from_end)) = (frames.clone().position(loud), frames.clone().rev().position(loud)) else {
+        return 0.0;
+    };
+    (frames.len() - from_end - first) as f32 / sample.sample_rate.max(1) as f32
+}
+
+/// Runs of `min_run` or more consecutive full-scale samples in any channel
+pub(crate) fn clip_runs(audio: &[f32], channels: u16, min_run: usize) -> usize {
+    let channels = channels.max(1) as usize;
+    (0..channels)
+        .map(|channel| {
+            let mut runs = 0;
+            let mut run = 0;
+            for sample in audio.iter().skip(channel).step_by(channels) {
+                if sample.abs() >= 0.999 {
+                    run += 1;
+                    if run == min_run.max(1) {
+                        runs += 1;
+                    }
+                } else {
+                    run = 0;
+                }
+            }
+            runs
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finds_only_internal_silent_gaps() {
+        let rate = 10000;
+        let tone = |frames: usize| (0..frames).map(|i| 0.5 * (i as f32 * 0.2).sin()).collect::<Vec<f32>>();
+        let silence = |frames: usize| vec![0.0f32; frames];
+
+        // 50ms lead-in, 200ms tone, 100ms dropout, 200ms tone, 10ms blip of silence, tone, 300ms tail
+        let audio = [silence(500), tone(2000), silence(1000), tone(2000), silence(100), tone(500), silence(3000)].concat();
+        let gaps = find_silent_gaps(&audio, 1, rate, &SilentGapConfig::default());
+
+        assert_eq!(gaps.len(), 1);
+        assert!((gaps[0].start_ms - 250.0).abs() <= 5.0);
+        assert!((gaps[0].duration_ms - 100.0).abs() <= 5.0);
+
+        // Stereo interleaving gives the same timeline
+        let stereo: Vec<f32> = audio.iter().flat_map(|&s| [s, s]).collect();
+        assert_eq!(find_silent_gaps(&stereo, 2, rate, &SilentGapConfig::default()), gaps);
+
+        assert!(find_silent_gaps(&silence(5000), 1, rate, &SilentGapConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn test_qa_flags_suspect_takes() {
+        let rate = 44100;
+        let take = |note: u8, audio_data: Vec<f32>| Sample {
+            note,
+            velocity: 100,
+            audio_data,
+            sample_rate: rate,
+            channels: 1,
+            recorded_at: std::time::SystemTime::now(),
+            midi_timing: std::time::Duration::ZERO,
+            audio_timing: std::time::Duration::ZERO,
+            metadata: Default::default(),
+        };
+        let tone = |frequency: f32, level: f32, secs: f32| -> Vec<f32> {
+            (0..(rate as f32 * secs) as usize)
+                .map(|i| level * (2.0 * std::f32::consts::PI * frequency * i as f32 / rate as f32).sin())
+                .collect()
+        };
+
+        let samples = vec![
+            take(69, tone(440.0, 0.5, 1.0)),
+            take(69, tone(440.0, 0.5, 1.0)),
+            // Octave switch left up
+            take(69, tone(880.0, 0.5, 1.0)),
+            // Hard clipped and offset
+            take(69, tone(440.0, 2.0, 1.0).iter().map(|s| s.clamp(-1.0, 1.0) + 0.05).collect()),
+            // Barely there, and cut short
+            take(69, [tone(440.0, 0.005, 0.1), vec![0.0; rate as usize]].concat()),
+        ];
+        let files: Vec<PathBuf> = (0..samples.len()).map(|i| PathBuf::from(format!("/tmp/take{}.wav", i))).collect();
+        let report = QaReport::run(&samples, &files, &QaConfig::default());
+
+        assert_eq!(report.checked, 5);
+        let issues = |file: &str| report.suspects.iter().find(|s| s.file == file).map(|s| s.issues.clone()).unwrap_or_default();
+        assert!(issues("take0.wav").is_empty() && issues("take1.wav").is_empty());
+        assert!(matches!(issues("take2.wav")[..], [QaIssue::WrongNote { expected: 69, heard: 81, .. }]));
+        let clipped = issues("take3.wav");
+        assert!(clipped.iter().any(|issue| matches!(issue, QaIssue::Clipped { runs } if *runs > 100)));
+        assert!(clipped.iter().any(|issue| matches!(issue, QaIssue::DcOffset { .. })));
+        assert!(clipped.iter().any(|issue| matches!(issue, QaIssue::TruePeakOver { .. })));
+        let quiet = issues("take4.wav");
+        assert!(quiet.iter().any(|issue| matches!(issue, QaIssue::Quiet { .. })));
+        assert!(quiet.iter().any(|issue| matches!(issue, QaIssue::Short { .. })));
+        assert!(report.summary().starts_with("QA: 3 of 5 samples need attention"));
+
+        // The JSON report round-trips for tools that re-record from it
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains("\"kind\":\"wrong_note\""));
+        let parsed: QaReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.suspects.len(), 3);
+    }
+
+    #[test]
+    fn test_stereo_checks_flag_flipped_and_one_sided_takes() {
+        let rate = 44100;
+        let wave: Vec<f32> = (0..rate as usize / 2)
+            .map(|i| 0.5 * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / rate as f32).sin())
+            .collect();
+        let stereo = |right_gain: f32| -> Vec<f32> { wave.iter().flat_map(|&s| [s, s * right_gain]).collect() };
+        let take = |audio_data: Vec<f32>| Sample {
+            note: 69,
+            velocity: 100,
+            audio_data,
+            sample_rate: rate,
+            channels: 2,
+            recorded_at: std::time::SystemTime::now(),
+            midi_timing: std::time::Duration::ZERO,
+            audio_timing: std::time::Duration::ZERO,
+            metadata: Default::default(),
+        };
+
+        let centred = StereoImage::measure(&stereo(1.0), 2).unwrap();
+        assert!((centred.correlation.unwrap() - 1.0).abs() < 1e-4 && centred.imbalance_db.abs() < 1e-3);
+        let dead_right = StereoImage::measure(&stereo(0.0), 2).unwrap();
+        assert_eq!((dead_right.correlation, dead_right.imbalance_db), (None, 96.0));
+        assert_eq!(StereoImage::measure(&wave, 1), None);
+
+        let samples = vec![take(stereo(0.8)), take(stereo(-1.0)), take(stereo(0.0))];
+        let files: Vec<PathBuf> = (0..3).map(|i| PathBuf::from(format!("take{}.wav", i))).collect();
+        let report = QaReport::run(&samples, &files, &QaConfig::default());
+        let issues: Vec<Vec<QaIssue>> = report.suspects.iter().map(|s| s.issues.clone()).collect();
+        assert_eq!(report.suspects.iter().map(|s| s.file.as_str()).collect::<Vec<_>>(), vec!["take1.wav", "take2.wav"]);
+        assert!(matches!(issues[0][..], [QaIssue::MonoIncompatible { correlation }] if correlation < -0.99));
+        assert!(matches!(issues[1][..], [QaIssue::ChannelImbalance { imbalance_db }] if imbalance_db > 90.0));
+        assert!(report.summary().contains("right channel 96.0 dB quieter"));
+    }
+}
@@ -0,0 +1,245 @@
+//! A sampling queue: several note ranges, each with its own velocities, articulation and
+//! patch, built up and reordered in a front end, then recorded as one supervised run over
+//! a single capture stream (see `SamplingEngine::sample_queue_async`).
+
+use crate::{BatcherbirdError, Result};
+use crate::sampler::{LegatoConfig, ReleaseCaptureConfig};
+use serde::{Deserialize, Serialize};
+
+/// How the notes of a queue entry are played and kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Articulation {
+    /// Plain note-on/note-off takes
+    #[default]
+    Sustain,
+    /// The audio from note-off on, for trigger=release regions (see `QueueEntry::release`)
+    Release,
+    /// Transitions between neighbouring notes of the range (see `QueueEntry::legato`)
+    Legato,
+}
+
+impl Articulation {
+    /// "sustain", "release" or "legato"
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "sustain" => Some(Articulation::Sustain),
+            "release" => Some(Articulation::Release),
+            "legato" => Some(Articulation::Legato),
+            _ => None,
+        }
+    }
+}
+
+/// One range of a queue
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueueEntry {
+    /// Assigned by the queue on push; stays the same when entries are moved
+    #[serde(default)]
+    pub id: u64,
+    pub start_note: u8,
+    pub end_note: u8,
+    /// Velocity layers, in recording order (empty = the sampling config's velocity)
+    #[serde(default)]
+    pub velocities: Vec<u8>,
+    #[serde(default)]
+    pub articulation: Articulation,
+    /// Program Change sent before the entry (None = leave the patch alone)
+    #[serde(default)]
+    pub program: Option<u8>,
+    /// Intervals and timing of the transitions, for legato entries
+    #[serde(default)]
+    pub legato: LegatoConfig,
+    /// Pre-buffer and sustain keeping, for release entries
+    #[serde(default)]
+    pub release: ReleaseCaptureConfig,
+}
+
+impl QueueEntry {
+    pub fn new(start_note: u8, end_note: u8) -> Self {
+        Self {
+            id: 0,
+            start_note,
+            end_note,
+            velocities: Vec::new(),
+            articulation: Articulation::default(),
+            program: None,
+            legato: LegatoConfig::default(),
+            release: ReleaseCaptureConfig::default(),
+        }
+    }
+
+    /// The entry's velocities, or `default` when it has none
+    pub fn velocities_or(&self, default: u8) -> Vec<u8> {
+        if self.velocities.is_empty() {
+            vec![default]
+        } else {
+            self.velocities.clone()
+        }
+    }
+
+    /// Takes the entry records
+    pub fn take_count(&self) -> usize {
+        let layers = self.velocities.len().max(1);
+        let notes = (self.end_note - self.start_note + 1) as usize;
+        match self.articulation {
+            Articulation::Sustain => notes * layers,
+            Articulation::Release if self.release.keep_sustain => notes * 2 * layers,
+            Articulation::Release => notes * layers,
+            Articulation::Legato => self.legato.transitions(self.start_note, self.end_note).len() * layers,
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        let invalid = |reason: &str| Err(BatcherbirdError::Config(format!("Invalid queue entry: {}", reason)));
+        if self.start_note > self.end_note || self.end_note > 127 {
+            return invalid("notes must be 0-127 with start <= end");
+        }
+        if self.velocities.iter().any(|&velocity| velocity == 0 || velocity > 127) {
+            return invalid("velocities must be 1-127");
+        }
+        if self.program.is_some_and(|program| program > 127) {
+            return invalid("program must be 0-127");
+        }
+        if self.take_count() == 0 {
+            return invalid("no legato transitions fit in the range");
+        }
+        Ok(())
+    }
+}
+
+/// Entries in recording order
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SamplingQueue {
+    entries: Vec<QueueEntry>,
+    #[serde(default)]
+    next_id: u64,
+}
+
+impl SamplingQueue {
+    pub const fn new() -> Self {
+        Self { entries: Vec::new(), next_id: 0 }
+    }
+
+    pub fn entries(&self) -> &[QueueEntry] {
+        &self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Append an entry; returns the id it was given
+    pub fn push(&mut self, mut entry: QueueEntry) -> Result<u64> {
+        entry.validate()?;
+        self.next_id += 1;
+        entry.id = self.next_id;
+        self.entries.push(entry);
+        Ok(self.next_id)
+    }
+
+    pub fn remove(&mut self, id: u64) -> Result<QueueEntry> {
+        let index = self.index_of(id)?;
+        Ok(self.entries.remove(index))
+    }
+
+    /// Move an entry to `index` (clamped to the end), shifting the others
+    pub fn move_entry(&mut self, id: u64, index: usize) -> Result<()> {
+        let entry = self.remove(id)?;
+        let index = index.min(self.entries.len());
+        self.entries.insert(index, entry);
+        Ok(())
+    }
+
+    /// Remove every entry; ids start over from 1
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.next_id = 0;
+    }
+
+    /// Takes the whole queue records
+    pub fn take_count(&self) -> usize {
+        self.entries.iter().map(QueueEntry::take_count).sum()
+    }
+
+    /// Whether any entry changes the patch (takes then need {patch} to stay apart)
+    pub fn has_programs(&self) -> bool {
+        self.entries.iter().any(|entry| entry.program.is_some())
+    }
+
+    fn index_of(&self, id: u64) -> Result<usize> {
+        self.entries.iter().position(|entry| entry.id == id)
+            .ok_or_else(|| BatcherbirdError::Session(format!("No queue entry with id {}", id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_and_reorder_queue() {
+        let mut queue = SamplingQueue::new();
+        let low = queue.push(QueueEntry { velocities: vec![64, 127], ..QueueEntry::new(36, 47) }).unwrap();
+        let release = queue.push(QueueEntry { articulation: Articulation::Release, ..QueueEntry::new(48, 59) }).unwrap();
+        let patch = queue.push(QueueEntry { program: Some(5), ..QueueEntry::new(60, 60) }).unwrap();
+        assert_eq!(queue.take_count(), 24 + 12 + 1);
+        assert!(queue.has_programs());
+
+        queue.move_entry(patch, 0).unwrap();
+        let order: Vec<u64> = queue.entries().iter().map(|entry| entry.id).collect();
+        assert_eq!(order, vec![patch, low, release]);
+        queue.move_entry(patch, 99).unwrap();
+        assert_eq!(queue.entries().last().unwrap().id, patch);
+
+        assert_eq!(queue.remove(low).unwrap().velocities, vec![64, 127]);
+        assert!(queue.remove(low).is_err());
+        assert!(queue.move_entry(low, 0).is_err());
+
+        // Ids keep counting after removals
+        let again = queue.push(QueueEntry::new(40, 41)).unwrap();
+        assert!(again > patch);
+
+        for bad in [
+            QueueEntry::new(60, 48),
+            QueueEntry { velocities: vec![0], ..QueueEntry::new(60, 61) },
+            QueueEntry { program: Some(128), ..QueueEntry::new(60, 61) },
+            QueueEntry { articulation: Articulation::Legato, ..QueueEntry::new(60, 60) },
+        ] {
+            assert!(queue.push(bad.clone()).is_err(), "{:?}", bad);
+        }
+        assert_eq!(queue.entries().len(), 3);
+
+        queue.clear();
+        assert!(queue.is_empty());
+        assert_eq!(queue.push(QueueEntry::new(60, 60)).unwrap(), 1);
+        assert_eq!(Articulation::parse("legato"), Some(Articulation::Legato));
+    }
+
+    #[test]
+    fn test_entry_carries_its_settings() {
+        let legato = QueueEntry {
+            articulation: Articulation::Legato,
+            legato: LegatoConfig { intervals: vec![1], ..LegatoConfig::default() },
+            ..QueueEntry::new(60, 64)
+        };
+        assert_eq!(legato.take_count(), 4);
+        // An octave jump doesn't fit the range, so the entry is refused
+        let octave = QueueEntry { legato: LegatoConfig { intervals: vec![12], ..LegatoConfig::default() }, ..legato.clone() };
+        assert!(SamplingQueue::new().push(octave).is_err());
+
+        let release = QueueEntry {
+            articulation: Articulation::Release,
+            release: ReleaseCaptureConfig { keep_sustain: true, ..ReleaseCaptureConfig::default() },
+            ..QueueEntry::new(48, 59)
+        };
+        assert_eq!(release.take_count(), 24);
+
+        // Entries saved before the settings existed load with the defaults
+        let old: QueueEntry = serde_json::from_str(r#"{"start_note": 60, "end_note": 62, "articulation": "legato"}"#).unwrap();
+        assert_eq!(old.legato, LegatoConfig::default());
+        assert_eq!(old.release, ReleaseCaptureConfig::default());
+        let round_trip: QueueEntry = serde_json::from_str(&serde_json::to_string(&release).unwrap()).unwrap();
+        assert_eq!(round_trip, release);
+    }
+}
@@ -0,0 +1,223 @@
+use crate::Result;
+use crate::export::AudioFormat;
+use crate::sampler::{Sample, SamplingConfig};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Template used when no custom template is configured.
+/// Placeholders are `{{name}}`; unknown placeholders are left untouched.
+pub const DEFAULT_README_TEMPLATE: &str = "# {{instrument_name}}
+
+{{description}}
+
+Created by {{creator}} on {{date}} with {{generator}}.
+
+## Contents
+
+- Samples: {{sample_count}}
+- Notes: {{note_range}}
+- Velocity layers: {{velocities}}
+- Audio: {{sample_rate}} Hz, {{channels}}
+- Instrument format: {{format}}
+
+## Sampling settings
+
+{{sampling_settings}}
+
+## License
+
+{{license}}
+
+## Installation
+
+{{install_instructions}}
+";
+
+/// Settings for the README written next to an exported instrument
+#[derive(Debug, Clone, Default)]
+pub struct ReadmeConfig {
+    /// Template file (.md or .txt); None uses DEFAULT_README_TEMPLATE
+    pub template: Option<PathBuf>,
+    /// License text for the library
+    pub license: Option<String>,
+    /// Sampling settings the instrument was captured with, listed in the README
+    pub sampling: Option<SamplingConfig>,
+}
+
+impl ReadmeConfig {
+    /// Default configuration, picking up a user template from
+    /// <config dir>/Batcherbird/readme_template.md (or .txt) if one exists
+    pub fn load_default() -> Self {
+        let template = dirs::config_dir()
+            .map(|dir| dir.join("Batcherbird"))
+            .into_iter()
+            .flat_map(|dir| [dir.join("readme_template.md"), dir.join("readme_template.txt")])
+            .find(|path| path.exists());
+
+        Self { template, ..Self::default() }
+    }
+
+    /// README.txt for .txt templates, README.md otherwise
+    pub fn file_name(&self) -> &'static str {
+        match &self.template {
+            Some(path) if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("txt")) => "README.txt",
+            _ => "README.md",
+        }
+    }
+}
+
+/// Values substituted into the README template
+#[derive(Debug, Clone)]
+pub struct ReadmeContext {
+    pub instrument_name: String,
+    pub description: String,
+    pub creator: String,
+    pub format: String,
+    pub install_instructions: String,
+    pub license: String,
+    pub sampling_settings: String,
+    pub sample_count: usize,
+    pub note_range: String,
+    pub velocities: String,
+    pub sample_rate: String,
+    pub channels: String,
+}
+
+impl ReadmeContext {
+    pub fn new(
+        instrument_name: &str,
+        samples: &[Sample],
+        format: &AudioFormat,
+        description: Option<&str>,
+        creator: Option<&str>,
+        config: &ReadmeConfig,
+    ) -> Self {
+        let low = samples.iter().map(|s| s.note).min();
+        let high = samples.iter().map(|s| s.note).max();
+        let note_range = match (low, high) {
+            (Some(low), Some(high)) if low == high => Self::note_to_name(low),
+            (Some(low), Some(high)) => format!("{} - {}", Self::note_to_name(low), Self::note_to_name(high)),
+            _ => "none".to_string(),
+        };
+
+        let mut velocities: Vec<u8> = samples.iter().map(|s| s.velocity).collect();
+        velocities.sort();
+        velocities.dedup();
+        let velocities = if velocities.is_empty() {
+            "none".to_string()
+        } else {
+            let list: Vec<String> = velocities.iter().map(|v| v.to_string()).collect();
+            format!("{} ({})", velocities.len(), list.join(", "))
+        };
+
+        let sample_rate = samples.first().map(|s| s.sample_rate.to_string()).unwrap_or_else(|| "unknown".to_string());
+        let channels = match samples.first().map(|s| s.channels) {
+            Some(1) => "mono".to_string(),
+            Some(2) => "stereo".to_string(),
+            Some(n) => format!("{} channels", n),
+            None => "unknown".to_string(),
+        };
+
+        Self {
+            instrument_name: instrument_name.to_string(),
+            description: description.unwrap_or("Sampled from hardware with Batcherbird.").to_string(),
+            creator: creator.unwrap_or("Unknown").to_string(),
+            format: format.display_name(),
+            install_instructions: Self::install_instructions(format, instrument_name),
+            license: config.license.clone().unwrap_or_else(|| "No license specified. Contact the creator before redistributing.".to_string()),
+            sampling_settings: config.sampling.as_ref()
+                .map(Self::describe_sampling)
+                .unwrap_or_else(|| "Not recorded.".to_string()),
+            sample_count: samples.len(),
+            note_range,
+            velocities,
+            sample_rate,
+            channels,
+        }
+    }
+
+    /// Substitute `{{placeholder}}` values into a template
+    pub fn render(&self, template: &str) -> String {
+        let values = [
+            ("instrument_name", self.instrument_name.clone()),
+            ("description", self.description.clone()),
+            ("creator", self.creator.clone()),
+            ("date", chrono::Local::now().format("%Y-%m-%d").to_string()),
+            ("generator", format!("Batcherbird {}", env!("CARGO_PKG_VERSION"))),
+            ("format", self.format.clone()),
+            ("install_instructions", self.install_instructions.clone()),
+            ("license", self.license.clone()),
+            ("sampling_settings", self.sampling_settings.clone()),
+            ("sample_count", self.sample_count.to_string()),
+            ("note_range", self.note_range.clone()),
+            ("velocities", self.velocities.clone()),
+            ("sample_rate", self.sample_rate.clone()),
+            ("channels", self.channels.clone()),
+        ];
+
+        values.iter().fold(template.to_string(), |text, (key, value)| {
+            text.replace(&format!("{{{{{}}}}}", key), value)
+        })
+    }
+
+    fn install_instructions(format: &AudioFormat, instrument_name: &str) -> String {
+        match format.instrument_exporter() {
+            Ok(Some(exporter)) => exporter.install_instructions(instrument_name),
+            _ => "Drag the WAV files into your sampler or DAW. File names contain the note name, MIDI note and velocity.".to_string(),
+        }
+    }
+
+    fn describe_sampling(config: &SamplingConfig) -> String {
+        let mut settings = format!(
+            "- MIDI channel: {}\n- Note length: {} ms\n- Release capture: {} ms\n- Pre-roll: {} ms\n- Velocity curve: {:?}",
+            config.midi_channel + 1,
+            config.note_duration_ms,
+            config.release_time_ms,
+            config.pre_delay_ms,
+            config.velocity_curve,
+        );
+        if let Some(clock) = config.clock {
+            settings.push_str(&format!("\n- MIDI clock: {} BPM", clock.bpm));
+        }
+        settings
+    }
+
+    fn note_to_name(note: u8) -> String {
+        let note_names = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+        let octave = (note / 12).saturating_sub(1);
+        let note_name = note_names[(note % 12) as usize];
+        format!("{}{}", note_name, octave)
+    }
+}
+
+/// Render the configured template into the instrument folder. Returns the README path.
+pub fn write_readme(directory: &Path, config: &ReadmeConfig, context: &ReadmeContext) -> Result<PathBuf> {
+    let template = match &config.template {
+        Some(path) => std::fs::read_to_string(path)?,
+        None => DEFAULT_README_TEMPLATE.to_string(),
+    };
+
+    let path = directory.join(config.file_name());
+    std::fs::write(&path, context.render(&template))?;
+    info!("   ✅ Generated README: {}", path.display());
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_template() {
+        let config = ReadmeConfig {
+            license: Some("CC0".to_string()),
+            ..Default::default()
+        };
+        let context = ReadmeContext::new("Pad", &[], &AudioFormat::SFZ, None, Some("Jo"), &config);
+        let text = context.render("{{instrument_name}} by {{creator}} ({{license}}) {{unknown}}\n{{install_instructions}}");
+
+        assert!(text.starts_with("Pad by Jo (CC0) {{unknown}}\n"));
+        assert!(text.contains("Pad.sfz"));
+        assert!(!context.render(DEFAULT_README_TEMPLATE).contains("{{"));
+    }
+}
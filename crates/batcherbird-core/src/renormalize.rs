@@ -0,0 +1,272 @@
+use crate::{Result, BatcherbirdError};
+use crate::library::{list_wav_files, read_wav, write_wav};
+use crate::loudness::LoudnessMeasurement;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Marker comment in front of the gain line written into instrument files
+const GAIN_MARKER: &str = "// Batcherbird library gain";
+
+/// Which level is matched between sessions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelMeasure {
+    /// Sample peaks; keeps headroom predictable
+    Peak,
+    /// Average loudness; better match for sustained sounds
+    Rms,
+    /// BS.1770 integrated loudness (LUFS), with true peaks checked against the ceiling
+    Lufs,
+}
+
+/// How the gain is applied to each session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GainMode {
+    /// Scale and rewrite the WAV files in place
+    RewriteFiles,
+    /// Leave audio untouched and set volume in the .sfz/.dspreset files
+    VolumeOpcodes,
+}
+
+#[derive(Debug, Clone)]
+pub struct RenormalizeConfig {
+    pub measure: LevelMeasure,
+    /// Level every session is matched to; None = median of the session levels
+    pub target_db: Option<f32>,
+    /// Highest peak allowed after gain when rewriting files
+    pub ceiling_db: f32,
+    pub mode: GainMode,
+}
+
+impl Default for RenormalizeConfig {
+    fn default() -> Self {
+        Self {
+            measure: LevelMeasure::Peak,
+            target_db: None,
+            ceiling_db: -0.3,
+            mode: GainMode::RewriteFiles,
+        }
+    }
+}
+
+/// Level and planned gain for one session folder
+#[derive(Debug, Clone)]
+pub struct SessionLevel {
+    pub directory: PathBuf,
+    pub files: Vec<PathBuf>,
+    /// Median of the per-file levels
+    pub level_db: f32,
+    /// Loudest peak in the session
+    pub peak_db: f32,
+    pub gain_db: f32,
+    /// Gain was reduced to stay under the ceiling
+    pub limited: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct RenormalizePlan {
+    pub target_db: f32,
+    pub sessions: Vec<SessionLevel>,
+}
+
+/// Measure every session and work out the gain that brings each to a common level
+pub fn plan_renormalization(directories: &[PathBuf], config: &RenormalizeConfig) -> Result<RenormalizePlan> {
+    let mut sessions = Vec::new();
+    for directory in directories {
+        let files = list_wav_files(directory)?;
+        if files.is_empty() {
+            warn!("⚠️ No WAV files in {}, skipping", directory.display());
+            continue;
+        }
+
+        let mut levels = Vec::with_capacity(files.len());
+        let mut peak_db = f32::NEG_INFINITY;
+        for file in &files {
+            let (audio, spec) = read_wav(file)?;
+            let (peak, rms) = measure_levels(&audio);
+            if config.measure == LevelMeasure::Lufs {
+                let loudness = LoudnessMeasurement::measure(&audio, spec.channels, spec.sample_rate);
+                peak_db = peak_db.max(loudness.true_peak_db);
+                levels.push(loudness.integrated_lufs.unwrap_or(-96.0));
+                continue;
+            }
+            peak_db = peak_db.max(peak);
+            levels.push(match config.measure {
+                LevelMeasure::Rms => rms,
+                _ => peak,
+            });
+        }
+
+        sessions.push(SessionLevel {
+            directory: directory.clone(),
+            files,
+            level_db: median(&mut levels),
+            peak_db,
+            gain_db: 0.0,
+            limited: false,
+        });
+    }
+
+    if sessions.is_empty() {
+        return Err(BatcherbirdError::Session("No WAV files found to renormalize".to_string()));
+    }
+
+    let mut session_levels: Vec<f32> = sessions.iter().map(|s| s.level_db).collect();
+    let target_db = config.target_db.unwrap_or_else(|| median(&mut session_levels));
+    for session in &mut sessions {
+        session.gain_db = target_db - session.level_db;
+        // Rewritten files must not clip; opcode gain is left to the player's headroom
+        if config.mode == GainMode::RewriteFiles && session.peak_db + session.gain_db > config.ceiling_db {
+            session.gain_db = config.ceiling_db - session.peak_db;
+            session.limited = true;
+        }
+    }
+
+    Ok(RenormalizePlan { target_db, sessions })
+}
+
+/// Apply the planned gain to one session. Returns the files that were changed.
+pub fn apply_session_gain(session: &SessionLevel, mode: GainMode) -> Result<Vec<PathBuf>> {
+    if session.gain_db.abs() < 0.01 {
+        return Ok(Vec::new());
+    }
+
+    match mode {
+        GainMode::RewriteFiles => {
+            let gain = 10f32.powf(session.gain_db / 20.0);
+            for file in &session.files {
+                let (mut audio, spec) = read_wav(file)?;
+                audio.iter_mut().for_each(|sample| *sample *= gain);
+                write_wav(file, &audio, spec)?;
+            }
+            info!("   🔊 {}: {:+.1} dB applied to {} files", session.directory.display(), session.gain_db, session.files.len());
+            Ok(session.files.clone())
+        }
+        GainMode::VolumeOpcodes => {
+            let mut changed = Vec::new();
+            for entry in std::fs::read_dir(&session.directory)? {
+                let path = entry?.path();
+                let extension = path.extension().map(|ext| ext.to_string_lossy().to_lowercase());
+                let updated = match extension.as_deref() {
+                    Some("sfz") => set_sfz_volume(&std::fs::read_to_string(&path)?, session.gain_db),
+                    Some("dspreset") => set_dspreset_volume(&std::fs::read_to_string(&path)?, session.gain_db),
+                    _ => continue,
+                };
+                std::fs::write(&path, updated)?;
+                changed.push(path);
+            }
+            if changed.is_empty() {
+                warn!("   ⚠️ {}: no .sfz/.dspreset file to carry the gain", session.directory.display());
+            } else {
+                info!("   🔊 {}: volume {:+.1} dB set in {} instrument files", session.directory.display(), session.gain_db, changed.len());
+            }
+            Ok(changed)
+        }
+    }
+}
+
+/// Put `volume=` in the <global> header, replacing a gain written by an earlier run
+pub fn set_sfz_volume(sfz: &str, gain_db: f32) -> String {
+    let gain_line = format!("{}\nvolume={:.2}\n", GAIN_MARKER, gain_db);
+    let mut lines: Vec<&str> = sfz.lines().collect();
+    if let Some(index) = lines.iter().position(|line| line.trim() == GAIN_MARKER) {
+        let end = (index + 2).min(lines.len());
+        lines.drain(index..end);
+    }
+    let text = lines.join("\n") + "\n";
+
+    match text.find("<global>\n") {
+        Some(index) => {
+            let insert_at = index + "<global>\n".len();
+            format!("{}{}{}", &text[..insert_at], gain_line, &text[insert_at..])
+        }
+        None => {
+            // No global header: add one in front of the first group/region
+            let insert_at = ["<group>", "<region>"].iter()
+                .filter_map(|header| text.find(header))
+                .min()
+                .unwrap_or(text.len());
+            format!("{}<global>\n{}\n{}", &text[..insert_at], gain_line, &text[insert_at..])
+        }
+    }
+}
+
+/// Set the volume attribute of the <groups> element
+pub fn set_dspreset_volume(xml: &str, gain_db: f32) -> String {
+    let Some(start) = xml.find("<groups") else {
+        return xml.to_string();
+    };
+    let Some(end) = xml[start..].find('>').map(|offset| start + offset) else {
+        return xml.to_string();
+    };
+
+    let tag = &xml[start..end];
+    let tag = match tag.find(" volume=\"") {
+        Some(attr) => {
+            let value_start = attr + " volume=\"".len();
+            let value_end = tag[value_start..].find('"').map_or(tag.len(), |offset| value_start + offset + 1);
+            format!("{}{}", &tag[..attr], &tag[value_end..])
+        }
+        None => tag.to_string(),
+    };
+    format!("{} volume=\"{:.2}dB\"{}", tag, gain_db, &xml[end..])
+}
+
+/// (peak dBFS, RMS dBFS) of interleaved audio
+fn measure_levels(audio: &[f32]) -> (f32, f32) {
+    if audio.is_empty() {
+        return (-96.0, -96.0);
+    }
+    let peak = audio.iter().map(|s| s.abs()).fold(0.0f32, f32::max);
+    let rms = (audio.iter().map(|s| s * s).sum::<f32>() / audio.len() as f32).sqrt();
+    (to_db(peak), to_db(rms))
+}
+
+fn to_db(level: f32) -> f32 {
+    if level > 0.0 { (20.0 * level.log10()).max(-96.0) } else { -96.0 }
+}
+
+fn median(values: &mut [f32]) -> f32 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    match values.len() {
+        0 => -96.0,
+        n if n % 2 == 1 => values[n / 2],
+        n => (values[n / 2 - 1] + values[n / 2]) / 2.0,
+    }
+}
+
+/// Renormalize a path: a folder of WAVs is one session, a folder of folders is a library
+pub fn session_directories(path: &Path) -> Result<Vec<PathBuf>> {
+    if !list_wav_files(path)?.is_empty() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut directories: Vec<PathBuf> = std::fs::read_dir(path)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    directories.sort();
+    Ok(directories)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_volume_opcodes_replace_previous_gain() {
+        let sfz = "<control>\ndefault_path=samples/\n\n<global>\nampeg_release=0.5\n\n<region>\nsample=a.wav\n";
+        let once = set_sfz_volume(sfz, -3.0);
+        assert!(once.contains("<global>\n// Batcherbird library gain\nvolume=-3.00\nampeg_release=0.5"));
+
+        let twice = set_sfz_volume(&once, 1.5);
+        assert_eq!(twice.matches("volume=").count(), 1);
+        assert!(twice.contains("volume=1.50"));
+
+        let headerless = set_sfz_volume("<region>\nsample=a.wav\n", 2.0);
+        assert!(headerless.starts_with("<global>\n// Batcherbird library gain\nvolume=2.00\n"));
+
+        let xml = "<DecentSampler>\n  <groups seqMode=\"round_robin\" volume=\"0.00dB\">\n  </groups>\n</DecentSampler>";
+        let updated = set_dspreset_volume(xml, -6.0);
+        assert!(updated.contains("<groups seqMode=\"round_robin\" volume=\"-6.00dB\">"));
+        assert_eq!(updated.matches("volume=").count(), 1);
+    }
+}
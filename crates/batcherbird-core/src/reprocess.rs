@@ -0,0 +1,144 @@
+use crate::export::{ExportConfig, SampleExporter};
+use crate::hardware_target::HardwareTarget;
+use crate::library::{self, LibrarySession};
+use crate::manifest::SessionManifest;
+use crate::qa::{QaConfig, QaReport};
+use crate::sampler::Sample;
+use crate::{BatcherbirdError, Result};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::info;
+
+/// Files written by a reprocessing run
+#[derive(Debug, Clone)]
+pub struct ReprocessResult {
+    /// Reprocessed WAVs, under their original file names
+    pub samples: Vec<PathBuf>,
+    /// Instrument files regenerated for the recognized samples
+    pub instrument_files: Vec<PathBuf>,
+    /// WAVs processed without a note/velocity, so they are left out of instrument files
+    pub unrecognized: Vec<PathBuf>,
+}
+
+/// Re-run detection, trimming, normalization and fades over every WAV in `source_dir` with
+/// the settings in `config`, writing the results to `config.output_directory`. File names are
+/// kept; instrument files, manifest and report are regenerated for the new audio.
+/// With a `target` the recognized samples are first fitted to that sampler's limits.
+pub fn reprocess_directory(source_dir: &Path, config: ExportConfig, target: Option<&HardwareTarget>) -> Result<ReprocessResult> {
+    if source_dir == config.output_directory {
+        return Err(BatcherbirdError::Config("Reprocess output folder must differ from the source folder".to_string()));
+    }
+    let mut session = LibrarySession::load(source_dir)?;
+    if let Some(target) = target {
+        let loaded: Vec<Sample> = session.samples.iter().map(|s| s.sample.clone()).collect();
+        let (fitted, report) = target.fit(&loaded);
+        info!("{}", report.summary().trim_end());
+        for (library_sample, sample) in session.samples.iter_mut().zip(fitted) {
+            library_sample.sample = sample;
+        }
+    }
+    let exporter = SampleExporter::new(config.clone())?;
+    let loaded: Vec<Sample> = session.samples.iter().map(|s| s.sample.clone()).collect();
+    exporter.prepare_hum_removal(&loaded);
+    let mut manifest = SessionManifest::new(exporter.instrument_name());
+
+    let mut samples = Vec::new();
+    let mut files = Vec::new();
+    for library_sample in &session.samples {
+        let file = reprocess_file(&exporter, &library_sample.sample, &library_sample.path)?;
+        manifest.add_sample(&read_back(&library_sample.sample, &file)?, &file);
+        samples.push(library_sample.sample.clone());
+        files.push(file);
+    }
+
+    let mut unrecognized = Vec::new();
+    for path in &session.unrecognized {
+        let (audio_data, spec) = library::read_wav(path)?;
+        let placeholder = Sample {
+            note: 60,
+            velocity: 127,
+            audio_data,
+            sample_rate: spec.sample_rate,
+            channels: spec.channels,
+            recorded_at: SystemTime::now(),
+            midi_timing: Duration::ZERO,
+            audio_timing: Duration::ZERO,
+            metadata: Default::default(),
+        };
+        unrecognized.push(reprocess_file(&exporter, &placeholder, path)?);
+    }
+
+    let mut instrument_files = Vec::new();
+    if !samples.is_empty() {
+        if let Some(instrument) = config.sample_format.instrument_exporter()? {
+            instrument_files.push(instrument.write_samples(&exporter, &samples, &files)?);
+        }
+    }
+    for file in &instrument_files {
+        manifest.add_instrument_file(file);
+    }
+    manifest.record_normalization_gains(&exporter.normalization_gains());
+    manifest.save(&config.output_directory)?;
+    manifest.write_report(&config.output_directory)?;
+    QaReport::run(&samples, &files, &QaConfig::default()).save(&config.output_directory)?;
+
+    info!("🔁 Reprocessed {} samples from {} into {}",
+        files.len() + unrecognized.len(), source_dir.display(), config.output_directory.display());
+    Ok(ReprocessResult { samples: files, instrument_files, unrecognized })
+}
+
+fn reprocess_file(exporter: &SampleExporter, sample: &Sample, source: &Path) -> Result<PathBuf> {
+    let file_name = source.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| BatcherbirdError::Session(format!("Not a file: {}", source.display())))?;
+    exporter.export_sample_as(sample, &file_name)
+}
+
+/// The manifest describes the audio as written, not the audio that was loaded
+fn read_back(sample: &Sample, file: &Path) -> Result<Sample> {
+    let (audio_data, spec) = library::read_wav(file)?;
+    Ok(Sample { audio_data, channels: spec.channels, ..sample.clone() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detection::DetectionConfig;
+    use crate::export::AudioFormat;
+
+    #[test]
+    fn test_reprocess_applies_new_settings() {
+        let dir = std::env::temp_dir().join(format!("batcherbird_reprocess_{}", std::process::id()));
+        let source = dir.join("Raw");
+        std::fs::create_dir_all(&source).unwrap();
+        let spec = hound::WavSpec { channels: 1, sample_rate: 44100, bits_per_sample: 24, sample_format: hound::SampleFormat::Int };
+        let tone: Vec<f32> = (0..4410).map(|i| 0.25 * (i as f32 * 0.05).sin()).collect();
+        library::write_wav(&source.join("Pad_C4_60_vel127.wav"), &tone, spec).unwrap();
+        library::write_wav(&source.join("noise.wav"), &tone, spec).unwrap();
+
+        let config = ExportConfig {
+            output_directory: dir.join("Processed"),
+            naming_pattern: "Pad_{note_name}_{note}_{velocity}.wav".to_string(),
+            sample_format: AudioFormat::SFZ,
+            normalize: true,
+            fade_in_ms: 0.0,
+            fade_out_ms: 0.0,
+            apply_detection: false,
+            detection_config: DetectionConfig::default(),
+            ..ExportConfig::default()
+        };
+        let result = reprocess_directory(&source, config.clone(), None).unwrap();
+
+        assert_eq!(result.samples, vec![dir.join("Processed").join("Pad_C4_60_vel127.wav")]);
+        assert_eq!(result.unrecognized, vec![dir.join("Processed").join("noise.wav")]);
+        assert_eq!(result.instrument_files.len(), 1);
+        let (audio, _) = library::read_wav(&result.samples[0]).unwrap();
+        let peak = audio.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+        assert!((peak - 0.95).abs() < 0.01);
+
+        // Writing over the source is refused
+        assert!(reprocess_directory(&source, ExportConfig { output_directory: source.clone(), ..config }, None).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
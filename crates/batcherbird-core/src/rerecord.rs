@@ -0,0 +1,255 @@
+use crate::edit::SampleEdit;
+use crate::export::{ExportConfig, SampleExporter};
+use crate::library::LibrarySession;
+use crate::manifest::{SessionManifest, MANIFEST_FILENAME};
+use crate::qa::{QaReport, QA_JSON_FILENAME};
+use crate::sampler::{Sample, SampleMetadata, SamplingEngine};
+use crate::{BatcherbirdError, Result};
+use midir::MidiOutputConnection;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Folder inside the session that keeps replaced takes in versioning mode
+pub const TAKES_DIRECTORY: &str = "Takes";
+
+/// One take to record again
+#[derive(Debug, Clone, PartialEq)]
+pub struct RerecordEntry {
+    pub note: u8,
+    pub velocity: u8,
+    /// File the new take replaces (None = the next file holding this note/velocity, or a new file)
+    pub file: Option<String>,
+}
+
+impl RerecordEntry {
+    /// "60:127,62:100" as note:velocity pairs
+    pub fn parse_list(spec: &str) -> Result<Vec<Self>> {
+        spec.split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                let parsed = part.split_once(':')
+                    .and_then(|(note, velocity)| Some((note.trim().parse::<u8>().ok()?, velocity.trim().parse::<u8>().ok()?)));
+                match parsed {
+                    Some((note, velocity)) if note <= 127 && (1..=127).contains(&velocity) => {
+                        Ok(Self { note, velocity, file: None })
+                    }
+                    _ => Err(BatcherbirdError::Config(format!("Invalid take '{}': use note:velocity, e.g. 60:127", part))),
+                }
+            })
+            .collect()
+    }
+
+    /// The suspect takes listed in a session's QA report
+    pub fn from_qa_report(directory: &Path) -> Result<Vec<Self>> {
+        let path = directory.join(QA_JSON_FILENAME);
+        let json = std::fs::read_to_string(&path).map_err(BatcherbirdError::Export)?;
+        let report: QaReport = serde_json::from_str(&json)
+            .map_err(|e| BatcherbirdError::Session(format!("Invalid QA report {}: {}", path.display(), e)))?;
+        Ok(report.suspects.into_iter()
+            .map(|suspect| Self { note: suspect.note, velocity: suspect.velocity, file: Some(suspect.file) })
+            .collect())
+    }
+}
+
+/// What happens to the take being replaced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RerecordMode {
+    Overwrite,
+    /// Move the earlier take to Takes/<name>_v<N>.wav first
+    Version,
+}
+
+/// Files touched by a re-record
+#[derive(Debug, Clone, Default)]
+pub struct RerecordResult {
+    /// Session files that now hold a new take
+    pub replaced: Vec<PathBuf>,
+    /// New files for entries the session didn't have yet
+    pub added: Vec<PathBuf>,
+    /// Where the earlier takes were moved (versioning mode)
+    pub archived: Vec<PathBuf>,
+    /// Entries that couldn't be recorded (watchdog timeouts)
+    pub skipped: Vec<(u8, u8)>,
+}
+
+/// Record `entries` again into the session at `config.output_directory`, processed with
+/// `config`. The synth should be set up as it was for the original session.
+pub fn rerecord_session(
+    engine: &SamplingEngine,
+    midi_conn: &mut MidiOutputConnection,
+    config: ExportConfig,
+    entries: &[RerecordEntry],
+    mode: RerecordMode,
+) -> Result<RerecordResult> {
+    if !config.output_directory.is_dir() {
+        return Err(BatcherbirdError::Session(format!("Not a session folder: {}", config.output_directory.display())));
+    }
+    let pairs: Vec<(u8, u8)> = entries.iter().map(|entry| (entry.note, entry.velocity)).collect();
+    let takes = engine.sample_entries_blocking(midi_conn, &pairs)?;
+    store_takes(config, entries, takes, mode)
+}
+
+/// Write recorded `takes` (lined up with `entries`, None = not recorded) into the session,
+/// then update its manifest, report and, when slots were added, its instrument file
+pub fn store_takes(
+    config: ExportConfig,
+    entries: &[RerecordEntry],
+    takes: Vec<Option<Sample>>,
+    mode: RerecordMode,
+) -> Result<RerecordResult> {
+    let directory = config.output_directory.clone();
+    let session = LibrarySession::load(&directory)?;
+    let exporter = SampleExporter::new(config.clone())?;
+    let manifest_path = directory.join(MANIFEST_FILENAME);
+    let mut manifest = if manifest_path.exists() { Some(SessionManifest::load(&manifest_path)?) } else { None };
+
+    let mut result = RerecordResult::default();
+    let mut used = HashSet::new();
+    for (entry, take) in entries.iter().zip(takes) {
+        let Some(mut take) = take else {
+            result.skipped.push((entry.note, entry.velocity));
+            continue;
+        };
+        let existing = session.samples.iter().find(|existing| {
+            let name = file_name(&existing.path);
+            !used.contains(&existing.path) && match &entry.file {
+                Some(file) => &name == file,
+                None => existing.sample.note == entry.note && existing.sample.velocity == entry.velocity,
+            }
+        });
+
+        let path = match existing {
+            Some(existing) => {
+                used.insert(existing.path.clone());
+                // Keep the slot's layer/round robin info; checks are redone for the new take
+                take.metadata = SampleMetadata {
+                    tempo_bpm: take.metadata.tempo_bpm,
+                    retake_reason: None,
+                    tuning_cents: None,
+                    pitch_envelope: None,
+                    ..existing.sample.metadata.clone()
+                };
+                take.verify_played_note();
+                if mode == RerecordMode::Version {
+                    result.archived.push(archive_take(&existing.path)?);
+                }
+                let name = file_name(&existing.path);
+                if let Some(manifest) = manifest.as_mut() {
+                    manifest.samples.retain(|sample| sample.file != name);
+                }
+                let path = exporter.export_sample_as(&take, &name)?;
+                result.replaced.push(path.clone());
+                path
+            }
+            None => {
+                take.verify_played_note();
+                let path = exporter.export_sample(&take)?;
+                result.added.push(path.clone());
+                path
+            }
+        };
+        if let Some(manifest) = manifest.as_mut() {
+            manifest.add_sample(&take, &path);
+        }
+    }
+
+    if let Some(manifest) = &manifest {
+        manifest.save(&directory)?;
+        manifest.write_report(&directory)?;
+    }
+
+    // New slots only play once the instrument file maps them
+    if !result.added.is_empty() {
+        let session = LibrarySession::load(&directory)?;
+        let samples: Vec<Sample> = session.samples.iter().map(|s| s.sample.clone()).collect();
+        let files: Vec<PathBuf> = session.samples.iter().map(|s| s.path.clone()).collect();
+        if let Some(instrument) = config.sample_format.instrument_exporter()? {
+            instrument.write_samples(&exporter, &samples, &files)?;
+        }
+    }
+
+    info!("🔁 Re-recorded {} takes ({} replaced, {} added, {} skipped)",
+        result.replaced.len() + result.added.len(), result.replaced.len(), result.added.len(), result.skipped.len());
+    Ok(result)
+}
+
+/// Move a take (and its edit file) to Takes/<name>_v<N>.wav, using the first free N
+fn archive_take(path: &Path) -> Result<PathBuf> {
+    let takes = path.parent().unwrap_or(Path::new(".")).join(TAKES_DIRECTORY);
+    std::fs::create_dir_all(&takes).map_err(BatcherbirdError::Export)?;
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    let archived = (1..)
+        .map(|version| takes.join(format!("{}_v{}.wav", stem, version)))
+        .find(|candidate| !candidate.exists())
+        .expect("unbounded version numbers");
+
+    std::fs::rename(path, &archived).map_err(BatcherbirdError::Export)?;
+    let sidecar = SampleEdit::sidecar_path(path);
+    if sidecar.exists() {
+        std::fs::rename(&sidecar, SampleEdit::sidecar_path(&archived)).map_err(BatcherbirdError::Export)?;
+    }
+    info!("   🗄️ Kept earlier take as {}", archived.display());
+    Ok(archived)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::AudioFormat;
+    use crate::library;
+
+    #[test]
+    fn test_store_takes_replaces_versions_and_adds() {
+        let dir = std::env::temp_dir().join(format!("batcherbird_rerecord_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let spec = hound::WavSpec { channels: 1, sample_rate: 44100, bits_per_sample: 24, sample_format: hound::SampleFormat::Int };
+        let quiet: Vec<f32> = (0..4410).map(|i| 0.01 * (i as f32 * 0.05).sin()).collect();
+        library::write_wav(&dir.join("Pad_C4_60_vel127.wav"), &quiet, spec).unwrap();
+        library::write_wav(&dir.join("Pad_D4_62_vel127.wav"), &quiet, spec).unwrap();
+
+        let take = |note: u8| Sample {
+            note,
+            velocity: 127,
+            audio_data: (0..4410).map(|i| 0.5 * (i as f32 * 0.05).sin()).collect(),
+            sample_rate: 44100,
+            channels: 1,
+            recorded_at: std::time::SystemTime::now(),
+            midi_timing: std::time::Duration::ZERO,
+            audio_timing: std::time::Duration::ZERO,
+            metadata: Default::default(),
+        };
+        let config = ExportConfig {
+            output_directory: dir.clone(),
+            naming_pattern: "Pad_{note_name}_{note}_{velocity}.wav".to_string(),
+            sample_format: AudioFormat::SFZ,
+            fade_out_ms: 0.0,
+            apply_detection: false,
+            ..ExportConfig::default()
+        };
+        let mut entries = RerecordEntry::parse_list("60:127, 64:127, 62:127").unwrap();
+        entries[0].file = Some("Pad_C4_60_vel127.wav".to_string());
+        let result = store_takes(config, &entries, vec![Some(take(60)), Some(take(64)), None], RerecordMode::Version).unwrap();
+
+        assert_eq!(result.replaced, vec![dir.join("Pad_C4_60_vel127.wav")]);
+        assert_eq!(result.added, vec![dir.join("Pad_E4_64_vel127.wav")]);
+        assert_eq!(result.archived, vec![dir.join(TAKES_DIRECTORY).join("Pad_C4_60_vel127_v1.wav")]);
+        assert_eq!(result.skipped, vec![(62, 127)]);
+
+        let (audio, _) = library::read_wav(&result.replaced[0]).unwrap();
+        assert!(audio.iter().fold(0.0f32, |max, s| max.max(s.abs())) > 0.4);
+        let (old, _) = library::read_wav(&result.archived[0]).unwrap();
+        assert!(old.iter().fold(0.0f32, |max, s| max.max(s.abs())) < 0.02);
+        let sfz = std::fs::read_to_string(dir.join("Pad.sfz")).unwrap();
+        assert!(sfz.contains("sample=Pad_E4_64_vel127.wav"));
+
+        assert!(RerecordEntry::parse_list("60").is_err());
+        assert!(RerecordEntry::parse_list("60:0").is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,14 +1,35 @@
 use crate::{Result, BatcherbirdError};
-use crate::midi::MidiManager;
-use crate::audio::AudioManager;
-use crate::detection::{SampleDetector, DetectionConfig, DetectionResult};
+use crate::midi::{MidiManager, NoteEvent, PanicStyle};
+use crate::audio::{AudioManager, i32_to_f32};
+use crate::detection::{DetectionConfig, DetectionResult};
 use crate::loop_detection::{LoopDetector, LoopDetectionConfig, LoopDetectionResult};
+use crate::velocity_curve::{VelocityCurve, VelocityResponse};
+use crate::midi_file::MidiFile;
+use crate::midi_clock::{MidiClock, MidiClockConfig};
+use crate::trigger::TriggerAction;
+use crate::analysis::{PitchEnvelope, PITCH_ENVELOPE_HOP_MS};
+use crate::metering::{KScale, LevelPublisher, MeterReading};
+use crate::passthrough::{self, MonitorTap, PassthroughConfig, PassthroughHandle};
+use crate::dual_input::DualInputConfig;
+use crate::multi_device::SecondaryCapture;
+use crate::pitch::{self, PitchEstimate};
+use crate::watchdog::{BatchWatchdog, FailedNote, WatchdogConfig};
+use crate::hooks::{HookConfig, HookContext, HookPoint};
+use crate::takes::TakeInfo;
+use crate::envelope::AdsrEnvelope;
+use crate::cancel::CancelToken;
+use crate::queue::{Articulation, SamplingQueue};
+use crate::slicing::{slice_long_take, NoteMark, SliceConfig};
 use midir::MidiOutputConnection;
 use std::time::Duration;
 use std::sync::{Arc, Mutex};
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use tokio::time::Instant;
 use cpal::traits::{DeviceTrait, StreamTrait};
+use tracing::{error, info, warn};
+
+/// Pause between consecutive takes in a batch (hardware stability)
+pub const NOTE_GAP_MS: u64 = 300;
 
 #[derive(Debug, Clone)]
 pub struct SamplingConfig {
@@ -18,6 +39,16 @@ pub struct SamplingConfig {
     pub post_delay_ms: u64,
     pub midi_channel: u8,
     pub velocity: u8,
+    /// Remapping applied to velocities right before note-on is sent
+    pub velocity_curve: VelocityCurve,
+    /// How stuck notes are cleared before/after batches and notes
+    pub panic_style: PanicStyle,
+    /// MIDI clock sent during capture for tempo-synced patches (None = no clock)
+    pub clock: Option<MidiClockConfig>,
+    /// Per-note and per-batch time limits for hung streams or stuck waits
+    pub watchdog: WatchdogConfig,
+    /// Commands run before and after each note
+    pub hooks: HookConfig,
 }
 
 impl Default for SamplingConfig {
@@ -29,8 +60,70 @@ impl Default for SamplingConfig {
             post_delay_ms: 100,       // 100ms post delay
             midi_channel: 0,          // Channel 1 (0-indexed)
             velocity: 100,            // Default velocity
+            velocity_curve: VelocityCurve::Linear,
+            panic_style: PanicStyle::Full,
+            clock: None,
+            watchdog: WatchdogConfig::default(),
+            hooks: HookConfig::default(),
+        }
+    }
+}
+
+/// Samples at or above this are counted as clipped
+const CLIP_LEVEL: f32 = 0.999;
+/// Clipped samples closer together than this belong to the same clip event (covers
+/// interleaved channels and the cycles of one overdriven note)
+const CLIP_EVENT_GAP_SAMPLES: usize = 4800;
+
+/// Milliseconds from the note-on (sent at `note_on_frame` of the capture) to the sound's
+/// attack, None when nothing was heard after it
+fn onset_latency_ms(audio: &[f32], channels: u16, sample_rate: u32, note_on_frame: usize) -> Option<f32> {
+    let after_note_on = audio.get(note_on_frame * channels.max(1) as usize..)?;
+    let onset = crate::alignment::onset_frame(after_note_on, channels, sample_rate)?;
+    Some(onset as f32 * 1000.0 / sample_rate.max(1) as f32)
+}
+
+/// Wall time of the part of a take kept from `from_frame`: the whole take's time less what
+/// the skipped frames cover, so clock drift tracking sets the kept audio against its own span
+fn kept_take_timing(take: Duration, from_frame: usize, sample_rate: u32) -> Duration {
+    take.saturating_sub(Duration::from_secs_f64(from_frame as f64 / sample_rate.max(1) as f64))
+}
+
+/// Run an async capture to completion for the `_blocking` entry points. Inside a
+/// multi-threaded tokio runtime (the CLI's) the future runs on that runtime instead of a
+/// nested one; elsewhere (GUI capture threads) one runtime is shared by every call.
+/// Async callers should await the `_async` methods directly.
+pub(crate) fn block_on<T>(future: impl std::future::Future<Output = Result<T>>) -> Result<T> {
+    if let Ok(handle) = tokio::runtime::Handle::try_current() {
+        if handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread {
+            return tokio::task::block_in_place(|| handle.block_on(future));
         }
+        return Err(BatcherbirdError::Audio(
+            "Blocking capture called from a single-threaded runtime; await the _async method instead".to_string()
+        ));
+    }
+    shared_runtime()?.block_on(future)
+}
+
+fn shared_runtime() -> Result<&'static tokio::runtime::Runtime> {
+    static RUNTIME: std::sync::OnceLock<tokio::runtime::Runtime> = std::sync::OnceLock::new();
+    if let Some(runtime) = RUNTIME.get() {
+        return Ok(runtime);
     }
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("batcherbird-capture")
+        .enable_all()
+        .build()
+        .map_err(|e| BatcherbirdError::Audio(format!("Failed to create runtime: {}", e)))?;
+    Ok(RUNTIME.get_or_init(|| runtime))
+}
+
+/// Report a finished capture with the fields a log consumer filters on
+fn log_capture(note: u8, audio_data: &[f32], duration: Duration) {
+    let peak = audio_data.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+    let peak_db = if peak > 0.0 { (200.0 * peak.log10()).round() / 10.0 } else { f32::NEG_INFINITY };
+    info!(note, samples = audio_data.len(), peak_db, duration_ms = duration.as_millis() as u64, "   ✅ Captured");
 }
 
 /// Professional audio level detector for real-time metering
@@ -42,6 +135,9 @@ pub struct AudioLevelDetector {
     rms_window_size: usize,
     #[allow(dead_code)] // Reserved for future advanced RMS windowing
     rms_window_samples: f32,
+    /// Clip events seen by this detector
+    clip_count: u64,
+    samples_since_clip: usize,
 }
 
 impl AudioLevelDetector {
@@ -54,18 +150,30 @@ impl AudioLevelDetector {
             rms_sample_count: 0,
             rms_window_size,
             rms_window_samples: 0.0,
+            clip_count: 0,
+            samples_since_clip: usize::MAX,
         }
     }
     
     /// Process audio samples and update levels (called from audio thread)
     pub fn process_samples(&mut self, samples: &[f32]) -> AudioLevels {
-        // Calculate peak level (instantaneous maximum)
+        // Peak of this block only; holding it is LevelMeterState's job
+        self.reset_peak();
         for &sample in samples {
             let abs_sample = sample.abs();
             if abs_sample > self.peak_level {
                 self.peak_level = abs_sample;
             }
             
+            if abs_sample >= CLIP_LEVEL {
+                if self.samples_since_clip > CLIP_EVENT_GAP_SAMPLES {
+                    self.clip_count += 1;
+                }
+                self.samples_since_clip = 0;
+            } else {
+                self.samples_since_clip = self.samples_since_clip.saturating_add(1);
+            }
+            
             // Accumulate for RMS calculation
             self.rms_accumulator += sample * sample;
             self.rms_sample_count += 1;
@@ -87,8 +195,12 @@ impl AudioLevelDetector {
         AudioLevels {
             peak: self.peak_level,
             rms: rms_level,
-            peak_db: if self.peak_level > 0.0 { 20.0 * self.peak_level.log10() } else { -60.0 },
-            rms_db: if rms_level > 0.0 { 20.0 * rms_level.log10() } else { -60.0 },
+            peak_db: level_to_db(self.peak_level),
+            rms_db: level_to_db(rms_level),
+            // Filled in by LevelMeterState, which holds peaks across blocks
+            peak_hold: self.peak_level,
+            peak_hold_db: level_to_db(self.peak_level),
+            clip_count: self.clip_count,
         }
     }
     
@@ -98,6 +210,11 @@ impl AudioLevelDetector {
     }
 }
 
+/// Meter display floor (dBFS)
+fn level_to_db(level: f32) -> f32 {
+    if level > 0.0 { 20.0 * level.log10() } else { -60.0 }
+}
+
 /// Real-time audio levels (thread-safe)
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct AudioLevels {
@@ -105,6 +222,9 @@ pub struct AudioLevels {
     pub rms: f32,       // RMS level (0.0 to 1.0)
     pub peak_db: f32,   // Peak in dBFS
     pub rms_db: f32,    // RMS in dBFS
+    pub peak_hold: f32,     // Held peak, decaying per PeakHoldConfig
+    pub peak_hold_db: f32,  // Held peak in dBFS
+    pub clip_count: u64,    // Clip events since monitoring started (never decreases)
 }
 
 impl Default for AudioLevels {
@@ -114,10 +234,27 @@ impl Default for AudioLevels {
             rms: 0.0,
             peak_db: -60.0,
             rms_db: -60.0,
+            peak_hold: 0.0,
+            peak_hold_db: -60.0,
+            clip_count: 0,
         }
     }
 }
 
+/// Peak-hold ballistics: how long a peak is held, then how fast it falls
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PeakHoldConfig {
+    pub hold_ms: f32,
+    pub decay_db_per_sec: f32,
+}
+
+impl Default for PeakHoldConfig {
+    fn default() -> Self {
+        // Typical interface meter: 1.5s hold, then IEC-style 20 dB/s fall
+        Self { hold_ms: 1500.0, decay_db_per_sec: 20.0 }
+    }
+}
+
 /// Thread-safe level meter state using atomic operations
 #[derive(Debug)]
 pub struct LevelMeterState {
@@ -125,38 +262,90 @@ pub struct LevelMeterState {
     input_rms: AtomicU32,
     input_peak_db: AtomicU32,
     input_rms_db: AtomicU32,
+    peak_hold: AtomicU32,
+    peak_hold_at_us: AtomicU64, // When the held peak was set, from `started`
+    hold_ms: AtomicU32,
+    decay_db_per_sec: AtomicU32,
+    clip_count: AtomicU64,
+    detector_clips: AtomicU64,  // Last clip count reported by the current detector
+    started: std::time::Instant,
     #[allow(dead_code)] // Reserved for future rate limiting features
     last_update: std::time::Instant,
 }
 
 impl LevelMeterState {
     pub fn new() -> Self {
+        let ballistics = PeakHoldConfig::default();
         Self {
             input_peak: AtomicU32::new(0),
             input_rms: AtomicU32::new(0),
             input_peak_db: AtomicU32::new(f32::to_bits(-60.0)),
             input_rms_db: AtomicU32::new(f32::to_bits(-60.0)),
+            peak_hold: AtomicU32::new(0),
+            peak_hold_at_us: AtomicU64::new(0),
+            hold_ms: AtomicU32::new(f32::to_bits(ballistics.hold_ms)),
+            decay_db_per_sec: AtomicU32::new(f32::to_bits(ballistics.decay_db_per_sec)),
+            clip_count: AtomicU64::new(0),
+            detector_clips: AtomicU64::new(0),
+            started: std::time::Instant::now(),
             last_update: std::time::Instant::now(),
         }
     }
     
+    /// Change the peak-hold ballistics (takes effect on the next read)
+    pub fn set_peak_hold(&self, config: PeakHoldConfig) {
+        self.hold_ms.store(f32::to_bits(config.hold_ms.max(0.0)), Ordering::Relaxed);
+        self.decay_db_per_sec.store(f32::to_bits(config.decay_db_per_sec.max(0.0)), Ordering::Relaxed);
+    }
+    
+    pub fn peak_hold_config(&self) -> PeakHoldConfig {
+        PeakHoldConfig {
+            hold_ms: f32::from_bits(self.hold_ms.load(Ordering::Relaxed)),
+            decay_db_per_sec: f32::from_bits(self.decay_db_per_sec.load(Ordering::Relaxed)),
+        }
+    }
+    
     /// Update levels from audio thread (atomic write)
     pub fn update_levels(&self, levels: AudioLevels) {
         self.input_peak.store(f32::to_bits(levels.peak), Ordering::Relaxed);
         self.input_rms.store(f32::to_bits(levels.rms), Ordering::Relaxed);
         self.input_peak_db.store(f32::to_bits(levels.peak_db), Ordering::Relaxed);
         self.input_rms_db.store(f32::to_bits(levels.rms_db), Ordering::Relaxed);
+        
+        let now_us = self.started.elapsed().as_micros() as u64;
+        if levels.peak >= self.held_peak(now_us) {
+            self.peak_hold.store(f32::to_bits(levels.peak), Ordering::Relaxed);
+            self.peak_hold_at_us.store(now_us, Ordering::Relaxed);
+        }
+        
+        // A new stream brings a new detector counting from zero; keep the total rising
+        let seen = self.detector_clips.swap(levels.clip_count, Ordering::Relaxed);
+        let new_clips = if levels.clip_count >= seen { levels.clip_count - seen } else { levels.clip_count };
+        self.clip_count.fetch_add(new_clips, Ordering::Relaxed);
     }
     
     /// Get current levels for UI (atomic read)
     pub fn get_levels(&self) -> AudioLevels {
+        let peak_hold = self.held_peak(self.started.elapsed().as_micros() as u64);
         AudioLevels {
             peak: f32::from_bits(self.input_peak.load(Ordering::Relaxed)),
             rms: f32::from_bits(self.input_rms.load(Ordering::Relaxed)),
             peak_db: f32::from_bits(self.input_peak_db.load(Ordering::Relaxed)),
             rms_db: f32::from_bits(self.input_rms_db.load(Ordering::Relaxed)),
+            peak_hold,
+            peak_hold_db: level_to_db(peak_hold),
+            clip_count: self.clip_count.load(Ordering::Relaxed),
         }
     }
+    
+    /// Held peak at `now_us`: flat for the hold time, then falling at the decay rate
+    fn held_peak(&self, now_us: u64) -> f32 {
+        let held = f32::from_bits(self.peak_hold.load(Ordering::Relaxed));
+        let since_ms = now_us.saturating_sub(self.peak_hold_at_us.load(Ordering::Relaxed)) as f32 / 1000.0;
+        let ballistics = self.peak_hold_config();
+        let falling_secs = (since_ms - ballistics.hold_ms).max(0.0) / 1000.0;
+        held * 10f32.powf(-ballistics.decay_db_per_sec * falling_secs / 20.0)
+    }
 }
 
 impl Default for LevelMeterState {
@@ -175,12 +364,363 @@ pub struct Sample {
     pub recorded_at: std::time::SystemTime,
     pub midi_timing: Duration,
     pub audio_timing: Duration,
+    pub metadata: SampleMetadata,
+}
+
+/// Extra capture context carried alongside the audio (used for naming and instrument mapping)
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SampleMetadata {
+    /// CC value the synth was set to when this sample was captured (sweep layers)
+    pub cc_layer: Option<CcLayer>,
+    /// Program (patch) number selected when this sample was captured (patch iteration)
+    pub program: Option<u8>,
+    /// 1-based round robin take for repeated hits of the same note/velocity
+    pub round_robin: Option<u8>,
+    /// Tempo of the MIDI clock running during capture (tempo-synced patches)
+    pub tempo_bpm: Option<f32>,
+    /// Source note of a legato transition; the audio starts at the target note-on
+    pub legato_from: Option<u8>,
+    /// Audio from just before note-off only, for trigger=release regions
+    #[serde(default)]
+    pub release: bool,
+    /// 0-indexed MIDI channel of the part played (multi-channel sampling)
+    pub midi_channel: Option<u8>,
+    /// Pitch over the course of the note (analog drift tracking)
+    #[serde(default)]
+    pub pitch_envelope: Option<PitchEnvelope>,
+    /// Why this take should be recorded again, if a check flagged it
+    #[serde(default)]
+    pub retake_reason: Option<String>,
+    /// How far the played note sat from the requested one (cents, positive is sharp)
+    #[serde(default)]
+    pub tuning_cents: Option<f32>,
+    /// Which of several takes of the same note this is (multi-take recording)
+    #[serde(default)]
+    pub take: Option<TakeInfo>,
+    /// Amplitude envelope fitted to the raw capture
+    #[serde(default)]
+    pub envelope: Option<AdsrEnvelope>,
+    /// Which source of a dual-input capture this is (e.g. "di" or "amp")
+    #[serde(default)]
+    pub input_source: Option<String>,
+    /// Time from the note-on being sent to the sound starting (ms); includes MIDI transport,
+    /// the synth's response and the audio buffer the note-on landed in
+    #[serde(default)]
+    pub onset_latency_ms: Option<f32>,
+}
+
+/// A single controller setting used as a sample layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+pub struct CcLayer {
+    pub controller: u8,
+    pub value: u8,
+}
+
+impl CcLayer {
+    /// Human-readable layer label, e.g. "CC74 064"
+    pub fn label(&self) -> String {
+        format!("CC{} {:03}", self.controller, self.value)
+    }
+}
+
+/// CC parameter sweep: every note is captured once per controller value
+#[derive(Debug, Clone)]
+pub struct CcSweepConfig {
+    /// Controller number to sweep (e.g. 74 for filter cutoff)
+    pub controller: u8,
+    /// Controller values to capture, one layer per value
+    pub values: Vec<u8>,
+    /// Time to let the synth settle after each CC change
+    pub settle_ms: u64,
+}
+
+impl Default for CcSweepConfig {
+    fn default() -> Self {
+        Self {
+            controller: 74,                    // Filter cutoff (sound controller 5)
+            values: vec![0, 32, 64, 96, 127],
+            settle_ms: 200,
+        }
+    }
+}
+
+/// Patch iteration: run the same note/velocity batch for every program number
+#[derive(Debug, Clone)]
+pub struct PatchIterationConfig {
+    /// Program numbers (0-127) to send as Program Change, in order
+    pub programs: Vec<u8>,
+    /// Velocities to capture per note (empty = SamplingConfig::velocity)
+    pub velocities: Vec<u8>,
+    /// Time to let the synth load the patch after Program Change
+    pub settle_ms: u64,
+}
+
+impl Default for PatchIterationConfig {
+    fn default() -> Self {
+        Self {
+            programs: (0..8).collect(),
+            velocities: Vec::new(),
+            settle_ms: 500,   // Vintage synths can take a while to load a patch
+        }
+    }
+}
+
+/// Legato transitions: note pairs played with overlapping note-ons
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct LegatoConfig {
+    /// Semitone steps from source to target note (negative = downward)
+    pub intervals: Vec<i8>,
+    /// How long the source note sounds before the target note-on
+    pub source_hold_ms: u64,
+    /// Time both notes are held before the source note-off
+    pub overlap_ms: u64,
+}
+
+impl Default for LegatoConfig {
+    fn default() -> Self {
+        Self {
+            intervals: vec![-2, -1, 1, 2],  // Steps used by most melodic lines
+            source_hold_ms: 800,
+            overlap_ms: 60,
+        }
+    }
+}
+
+impl LegatoConfig {
+    /// (source, target) pairs where both notes lie within the range
+    pub fn transitions(&self, start_note: u8, end_note: u8) -> Vec<(u8, u8)> {
+        (start_note..=end_note)
+            .flat_map(|source| self.intervals.iter().filter_map(move |&interval| {
+                let target = source as i16 + interval as i16;
+                (interval != 0 && target >= start_note as i16 && target <= end_note as i16)
+                    .then_some((source, target as u8))
+            }))
+            .collect()
+    }
+}
+
+/// Multi-channel sampling: capture each part of a multitimbral module in one run
+#[derive(Debug, Clone)]
+pub struct MultiChannelConfig {
+    /// 0-indexed MIDI channels to play, in order
+    pub channels: Vec<u8>,
+    /// Velocities to capture per note (empty = SamplingConfig::velocity)
+    pub velocities: Vec<u8>,
+    /// Pause before each channel so the previous part's tails die away
+    pub settle_ms: u64,
+}
+
+impl Default for MultiChannelConfig {
+    fn default() -> Self {
+        Self {
+            channels: (0..16).collect(),
+            velocities: Vec::new(),
+            settle_ms: 500,
+        }
+    }
+}
+
+/// Release sample capture: keep the audio after note-off as its own sample
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ReleaseCaptureConfig {
+    /// Audio kept from before the note-off so the release starts from the sustained sound
+    pub pre_buffer_ms: u64,
+    /// Also keep the full take as the normal (attack) sample
+    pub keep_sustain: bool,
+}
+
+impl Default for ReleaseCaptureConfig {
+    fn default() -> Self {
+        Self {
+            pre_buffer_ms: 20,
+            keep_sustain: false,
+        }
+    }
+}
+
+/// Record whatever is played on a MIDI keyboard (passive, performance-triggered sampling)
+#[derive(Debug, Clone)]
+pub struct PassiveCaptureConfig {
+    /// Audio kept from before each note-on (taken from the rolling input buffer)
+    pub pre_roll_ms: u64,
+    /// How long to keep recording after the note-off
+    pub tail_ms: u64,
+    /// Cut a take that is held longer than this
+    pub max_note_ms: u64,
+    /// Only react to this 0-indexed MIDI channel (None = any channel)
+    pub input_channel: Option<u8>,
+    /// Stop after this many takes (None = until stopped)
+    pub max_takes: Option<usize>,
+}
+
+impl Default for PassiveCaptureConfig {
+    fn default() -> Self {
+        Self {
+            pre_roll_ms: 200,
+            tail_ms: 1500,
+            max_note_ms: 30000,
+            input_channel: None,
+            max_takes: None,
+        }
+    }
+}
+
+/// A passive take in progress; positions are absolute sample indices in the input stream
+struct PassiveTake {
+    note: u8,
+    velocity: u8,
+    channel: u8,
+    start: usize,
+    started_at: Instant,
+    released_at: Option<Instant>,
+}
+
+/// Input stream kept running between takes, so a take starts the moment it is asked for
+/// instead of waiting for the audio device to open. The stream lives on its own thread;
+/// dropping the handle disarms it.
+pub struct ArmedCapture {
+    audio_samples: Arc<Mutex<Vec<f32>>>,
+    recording_active: Arc<Mutex<bool>>,
+    sample_rate: u32,
+    channels: u16,
+    take_started: Option<Instant>,
+    disarm: Arc<AtomicBool>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl ArmedCapture {
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    pub fn is_recording(&self) -> bool {
+        *self.recording_active.lock().unwrap()
+    }
+
+    /// Start collecting audio (discarding anything from a previous take)
+    pub fn start_take(&mut self) {
+        self.audio_samples.lock().unwrap().clear();
+        *self.recording_active.lock().unwrap() = true;
+        self.take_started = Some(Instant::now());
+        info!("   🔴 Recording started");
+    }
+
+    /// Stop collecting audio and return the take as a sample of `note` at `velocity`
+    pub fn stop_take(&mut self, note: u8, velocity: u8) -> Result<Sample> {
+        let started = self.take_started.take()
+            .ok_or_else(|| BatcherbirdError::Session("No take in progress".to_string()))?;
+        *self.recording_active.lock().unwrap() = false;
+        let audio_data = std::mem::take(&mut *self.audio_samples.lock().unwrap());
+        let audio_timing = started.elapsed();
+        info!("   ⏹️ Recording stopped: {} samples in {:.1}ms", audio_data.len(), audio_timing.as_millis());
+
+        Ok(Sample {
+            note,
+            velocity,
+            audio_data,
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            recorded_at: std::time::SystemTime::now(),
+            midi_timing: audio_timing,
+            audio_timing,
+            metadata: SampleMetadata::default(),
+        })
+    }
+
+    /// Stop the input stream
+    pub fn disarm(mut self) {
+        self.shut_down();
+    }
+
+    fn shut_down(&mut self) {
+        self.disarm.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+            info!("🔓 Capture disarmed");
+        }
+    }
+}
+
+impl Drop for ArmedCapture {
+    fn drop(&mut self) {
+        self.shut_down();
+    }
 }
 
+/// Persistent input stream plus the shared buffers it writes into
+struct CaptureSession {
+    stream: cpal::Stream,
+    audio_samples: Arc<Mutex<Vec<f32>>>,
+    recording_active: Arc<Mutex<bool>>,
+    sample_rate: u32,
+    channels: u16,
+    panic_style: PanicStyle,
+    midi_channel: u8,
+    clock: Option<MidiClock>,
+    /// Frame of the current take at the most recent note-off
+    note_off_frame: usize,
+    /// Second device paired with the input, when one is set
+    secondary: Option<SecondaryCapture>,
+}
+
+impl CaptureSession {
+    /// Empty the take buffers and start recording
+    fn start_take(&self) {
+        self.audio_samples.lock().unwrap().clear();
+        if let Some(secondary) = &self.secondary {
+            secondary.clear();
+        }
+        *self.recording_active.lock().unwrap() = true;
+    }
+
+    /// Frames recorded so far in the current take
+    fn frames(&self) -> usize {
+        self.audio_samples.lock().unwrap().len() / self.channels.max(1) as usize
+    }
+
+    /// The take from `from_frame` on and its channel count: the input as recorded, or the
+    /// stereo pair when a second device is paired with it
+    fn take_audio(&self, from_frame: usize) -> (Vec<f32>, u16) {
+        let recorded = self.audio_samples.lock().unwrap().clone();
+        let (audio, channels) = match &self.secondary {
+            Some(secondary) => (secondary.merge(&recorded, self.channels), 2),
+            None => (recorded, self.channels),
+        };
+        let from = (from_frame * channels.max(1) as usize).min(audio.len());
+        (audio[from..].to_vec(), channels)
+    }
+}
+
+/// Per-note progress of a batch, for front ends listing notes as they are recorded. Takes
+/// that made it are handed over as samples; this covers what happens around them.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum CaptureProgress {
+    /// The note is about to be played
+    Started { note: u8, velocity: u8 },
+    /// The watchdog skipped the note, or its capture failed or was cancelled
+    Failed { note: u8, velocity: u8, reason: String },
+}
+
+type ProgressCallback = Box<dyn Fn(CaptureProgress) + Send + Sync>;
+
 pub struct SamplingEngine {
     audio_manager: AudioManager,
     config: SamplingConfig,
     level_meter_state: Arc<LevelMeterState>,
+    /// Monitoring input shared with the passthrough output
+    monitor_tap: Arc<MonitorTap>,
+    /// Notes the watchdog skipped in the most recent batch
+    failed_notes: Mutex<Vec<FailedNote>>,
+    /// Stops a capture from another thread
+    cancel: CancelToken,
+    progress: Option<ProgressCallback>,
 }
 
 impl SamplingEngine {
@@ -191,24 +731,68 @@ impl SamplingEngine {
             audio_manager,
             config,
             level_meter_state: Arc::new(LevelMeterState::new()),
+            monitor_tap: Arc::new(MonitorTap::new()),
+            failed_notes: Mutex::new(Vec::new()),
+            cancel: CancelToken::new(),
+            progress: None,
         })
     }
     
+    /// Capture with `cancel`: cancelling it cuts the current note short and ends the batch
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+    
+    /// Call `on_progress` as each note of a batch starts or fails
+    pub fn with_progress(mut self, on_progress: impl Fn(CaptureProgress) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Box::new(on_progress));
+        self
+    }
+    
+    fn report(&self, progress: CaptureProgress) {
+        if let Some(on_progress) = &self.progress {
+            on_progress(progress);
+        }
+    }
+    
+    /// Notes the watchdog gave up on during the last batch (to re-record later)
+    pub fn failed_notes(&self) -> Vec<FailedNote> {
+        self.failed_notes.lock().unwrap().clone()
+    }
+    
     /// Get current audio levels for UI (thread-safe)
     pub fn get_audio_levels(&self) -> AudioLevels {
         self.level_meter_state.get_levels()
     }
     
+    /// Set how long the meter holds peaks and how fast they fall afterwards
+    pub fn set_peak_hold(&self, config: PeakHoldConfig) {
+        self.level_meter_state.set_peak_hold(config);
+    }
+    
+    /// Hear the monitored input on an output device (needs the monitoring stream running)
+    pub fn start_passthrough(&self, config: &PassthroughConfig) -> Result<PassthroughHandle> {
+        passthrough::start_passthrough(Arc::clone(&self.monitor_tap), config)
+    }
+    
+    /// Push meter readings to `callback` at `rate_hz` until the publisher is dropped
+    pub fn publish_levels<F>(&self, rate_hz: f32, k_scale: KScale, callback: F) -> LevelPublisher
+    where
+        F: Fn(MeterReading) + Send + 'static,
+    {
+        LevelPublisher::start(Arc::clone(&self.level_meter_state), rate_hz, k_scale, callback)
+    }
+    
     /// Start persistent audio monitoring stream (separate from recording)
     pub fn start_monitoring_stream(&self) -> Result<cpal::Stream> {
-        println!("🎛️ Starting persistent audio monitoring stream");
+        info!("🎛️ Starting persistent audio monitoring stream");
         
-        let device = self.audio_manager.get_default_input_device()?;
-        let config = device.default_input_config()
-            .map_err(|e| BatcherbirdError::Audio(format!("Failed to get input config: {}", e)))?;
+        let (device, config) = self.audio_manager.input_device_and_config()?;
 
         let sample_rate = config.sample_rate().0;
         let level_state = Arc::clone(&self.level_meter_state);
+        self.monitor_tap.set_format(config.channels(), sample_rate);
         
         use cpal::{SampleFormat, StreamConfig};
 
@@ -221,6 +805,7 @@ impl SamplingEngine {
         let stream = match config.sample_format() {
             SampleFormat::F32 => {
                 let level_state_clone = Arc::clone(&level_state);
+                let tap = Arc::clone(&self.monitor_tap);
                 let mut level_detector = AudioLevelDetector::new(sample_rate);
                 
                 device.build_input_stream(
@@ -229,13 +814,15 @@ impl SamplingEngine {
                         // Continuous level detection for monitoring
                         let levels = level_detector.process_samples(data);
                         level_state_clone.update_levels(levels);
+                        tap.push(data);
                     },
-                    |err| eprintln!("Audio monitoring error: {}", err),
+                    |err| error!("Audio monitoring error: {}", err),
                     None,
                 ).map_err(|e| BatcherbirdError::Audio(format!("Failed to build monitoring stream: {}", e)))?
             }
             SampleFormat::I16 => {
                 let level_state_clone = Arc::clone(&level_state);
+                let tap = Arc::clone(&self.monitor_tap);
                 let mut level_detector = AudioLevelDetector::new(sample_rate);
                 
                 device.build_input_stream(
@@ -248,13 +835,15 @@ impl SamplingEngine {
                         
                         let levels = level_detector.process_samples(&f32_samples);
                         level_state_clone.update_levels(levels);
+                        tap.push(&f32_samples);
                     },
-                    |err| eprintln!("Audio monitoring error: {}", err),
+                    |err| error!("Audio monitoring error: {}", err),
                     None,
                 ).map_err(|e| BatcherbirdError::Audio(format!("Failed to build monitoring stream: {}", e)))?
             }
             SampleFormat::U16 => {
                 let level_state_clone = Arc::clone(&level_state);
+                let tap = Arc::clone(&self.monitor_tap);
                 let mut level_detector = AudioLevelDetector::new(sample_rate);
                 
                 device.build_input_stream(
@@ -267,8 +856,30 @@ impl SamplingEngine {
                         
                         let levels = level_detector.process_samples(&f32_samples);
                         level_state_clone.update_levels(levels);
+                        tap.push(&f32_samples);
+                    },
+                    |err| error!("Audio monitoring error: {}", err),
+                    None,
+                ).map_err(|e| BatcherbirdError::Audio(format!("Failed to build monitoring stream: {}", e)))?
+            }
+            SampleFormat::I32 => {
+                let level_state_clone = Arc::clone(&level_state);
+                let tap = Arc::clone(&self.monitor_tap);
+                let mut level_detector = AudioLevelDetector::new(sample_rate);
+                
+                device.build_input_stream(
+                    &stream_config,
+                    move |data: &[i32], _: &cpal::InputCallbackInfo| {
+                        // Convert to f32 for level detection
+                        let f32_samples: Vec<f32> = data.iter()
+                            .map(|&sample| i32_to_f32(sample))
+                            .collect();
+                        
+                        let levels = level_detector.process_samples(&f32_samples);
+                        level_state_clone.update_levels(levels);
+                        tap.push(&f32_samples);
                     },
-                    |err| eprintln!("Audio monitoring error: {}", err),
+                    |err| error!("Audio monitoring error: {}", err),
                     None,
                 ).map_err(|e| BatcherbirdError::Audio(format!("Failed to build monitoring stream: {}", e)))?
             }
@@ -277,39 +888,107 @@ impl SamplingEngine {
             }
         };
 
-        println!("✅ Persistent audio monitoring stream created");
+        info!("✅ Persistent audio monitoring stream created");
         Ok(stream)
     }
     
 
+    /// Open the input stream and keep it running without recording, ready for
+    /// `ArmedCapture::start_take`. Returns once the stream is live.
+    pub fn arm_capture(&self) -> Result<ArmedCapture> {
+        let audio_samples = Arc::new(Mutex::new(Vec::new()));
+        let recording_active = Arc::new(Mutex::new(false));
+        let disarm = Arc::new(AtomicBool::new(false));
+        let (ready_tx, ready_rx) = std::sync::mpsc::channel::<Result<(u32, u16)>>();
+
+        let level_state = Arc::clone(&self.level_meter_state);
+        let (thread_samples, thread_recording, thread_disarm) = (audio_samples.clone(), recording_active.clone(), disarm.clone());
+        // cpal streams are not Send, so the stream is created and owned by this thread
+        let thread = std::thread::spawn(move || {
+            let opened = AudioManager::new().and_then(|audio_manager| {
+                let (device, config) = audio_manager.input_device_and_config()?;
+                let stream = Self::build_persistent_recording_stream(level_state, &device, &config, thread_samples, thread_recording)?;
+                stream.play().map_err(|e| BatcherbirdError::Audio(format!("Failed to start persistent stream: {}", e)))?;
+                Ok((stream, config.sample_rate().0, config.channels()))
+            }).inspect_err(|_| AudioManager::clear_device_cache());
+
+            match opened {
+                Ok((stream, sample_rate, channels)) => {
+                    let _ = ready_tx.send(Ok((sample_rate, channels)));
+                    while !thread_disarm.load(Ordering::Relaxed) {
+                        std::thread::sleep(Duration::from_millis(10));
+                    }
+                    let _ = stream.pause();
+                }
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                }
+            }
+        });
+
+        match ready_rx.recv() {
+            Ok(Ok((sample_rate, channels))) => {
+                info!("🔒 Capture armed ({} Hz, {} channels)", sample_rate, channels);
+                Ok(ArmedCapture {
+                    audio_samples,
+                    recording_active,
+                    sample_rate,
+                    channels,
+                    take_started: None,
+                    disarm,
+                    thread: Some(thread),
+                })
+            }
+            Ok(Err(e)) => {
+                let _ = thread.join();
+                Err(e)
+            }
+            Err(_) => Err(BatcherbirdError::Audio("Capture thread exited unexpectedly".to_string())),
+        }
+    }
+
     /// Blocking interface for Tauri GUI layer (follows TAURI_AUDIO_ARCHITECTURE.md)
     pub fn sample_single_note_blocking(
         &self,
         midi_conn: &mut MidiOutputConnection,
         note: u8,
     ) -> Result<Sample> {
-        // Create dedicated runtime for this blocking operation
-        let rt = tokio::runtime::Runtime::new()
-            .map_err(|e| BatcherbirdError::Audio(format!("Failed to create runtime: {}", e)))?;
-        
-        // Execute the async operation in blocking context
-        rt.block_on(self.sample_single_note_async(midi_conn, note))
+        block_on(self.sample_single_note_async(midi_conn, note))
+    }
+
+    /// Async implementation (Core Audio Engine); await it directly from a tokio runtime
+    #[tracing::instrument(name = "sample_note", skip_all, fields(note))]
+    pub async fn sample_single_note_async(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        note: u8,
+    ) -> Result<Sample> {
+        self.cancel.check()?;
+        let outcome = tokio::select! {
+            result = self.record_single_note(midi_conn, note) => Some(result),
+            _ = self.cancel.cancelled() => None,
+        };
+        outcome.unwrap_or_else(|| {
+            warn!("⏹️ Recording cancelled, silencing the synth");
+            MidiManager::send_panic(midi_conn, self.config.panic_style, self.config.midi_channel)?;
+            Err(CancelToken::error())
+        })
     }
 
-    /// Internal async implementation (Core Audio Engine)
-    async fn sample_single_note_async(
+    async fn record_single_note(
         &self,
         midi_conn: &mut MidiOutputConnection,
         note: u8,
     ) -> Result<Sample> {
-        println!("🎵 Sampling note {} ({})", note, Self::note_to_name(note));
+        info!(note, velocity = self.config.velocity, "🎵 Sampling {}", Self::note_to_name(note));
+        self.config.hooks.run(HookPoint::PreNote, &HookContext::note(note, self.config.velocity))?;
         
         let _total_duration = self.config.pre_delay_ms 
             + self.config.note_duration_ms 
             + self.config.release_time_ms 
             + self.config.post_delay_ms;
 
-        println!("   Pre-delay: {}ms, Note: {}ms, Release: {}ms, Post: {}ms", 
+        info!("   Pre-delay: {}ms, Note: {}ms, Release: {}ms, Post: {}ms", 
             self.config.pre_delay_ms,
             self.config.note_duration_ms,
             self.config.release_time_ms,
@@ -322,51 +1001,71 @@ impl SamplingEngine {
         let samples_clone = audio_samples.clone();
         let complete_clone = recording_complete.clone();
 
-        let device = self.audio_manager.get_default_input_device()?;
-        let config = device.default_input_config()
-            .map_err(|e| BatcherbirdError::Audio(format!("Failed to get input config: {}", e)))?;
+        let (device, config) = self.audio_manager.input_device_and_config()?;
 
         let sample_rate = config.sample_rate().0;
         let channels = config.channels();
 
         // Build recording stream
-        let stream = self.build_recording_stream(&device, &config, samples_clone, complete_clone)?;
+        let stream = self.build_recording_stream(&device, &config, samples_clone, complete_clone)
+            .inspect_err(|_| AudioManager::clear_device_cache())?;
         
         // Start recording
-        stream.play().map_err(|e| BatcherbirdError::Audio(format!("Failed to start stream: {}", e)))?;
+        stream.play()
+            .map_err(|e| BatcherbirdError::Audio(format!("Failed to start stream: {}", e)))
+            .inspect_err(|_| AudioManager::clear_device_cache())?;
         
         let start_time = Instant::now();
         
+        // Clock starts with the capture; the pre-delay doubles as the lock-in time
+        let mut clock = self.config.clock.map(MidiClock::new);
+        if let Some(clock) = clock.as_mut() {
+            info!("   ⏱️ MIDI clock at {} BPM", clock.config().bpm);
+            clock.start(midi_conn)?;
+        }
+        
         // Pre-delay
         if self.config.pre_delay_ms > 0 {
-            tokio::time::sleep(Duration::from_millis(self.config.pre_delay_ms)).await;
+            MidiClock::wait_optional(clock.as_mut(), midi_conn, Duration::from_millis(self.config.pre_delay_ms)).await?;
         }
         
         // Safety: Clear any stuck notes on this channel before starting
-        MidiManager::send_channel_panic(midi_conn, self.config.midi_channel)?;
+        if self.config.panic_style != PanicStyle::None {
+            MidiManager::send_channel_panic(midi_conn, self.config.midi_channel)?;
+            
+            // Brief delay after panic to ensure hardware processes it
+            MidiClock::wait_optional(clock.as_mut(), midi_conn, Duration::from_millis(50)).await?;
+        }
         
-        // Brief delay after panic to ensure hardware processes it
-        tokio::time::sleep(Duration::from_millis(50)).await;
+        if let Some(clock) = clock.as_mut().filter(|clock| clock.config().restart_per_note) {
+            clock.restart(midi_conn)?;
+        }
         
         // Send MIDI note on
+        let sent_velocity = self.config.velocity_curve.apply(self.config.velocity);
+        let note_on_frame = audio_samples.lock().unwrap().len() / channels.max(1) as usize;
         let midi_start = Instant::now();
-        MidiManager::send_note_on(midi_conn, self.config.midi_channel, note, self.config.velocity)?;
+        MidiManager::send_note_on(midi_conn, self.config.midi_channel, note, sent_velocity)?;
         
         // Wait for note duration
-        tokio::time::sleep(Duration::from_millis(self.config.note_duration_ms)).await;
+        MidiClock::wait_optional(clock.as_mut(), midi_conn, Duration::from_millis(self.config.note_duration_ms)).await?;
         
         // Send MIDI note off
-        MidiManager::send_note_off(midi_conn, self.config.midi_channel, note, self.config.velocity)?;
+        MidiManager::send_note_off(midi_conn, self.config.midi_channel, note, sent_velocity)?;
         let midi_timing = midi_start.elapsed();
         
         // Wait for release
         if self.config.release_time_ms > 0 {
-            tokio::time::sleep(Duration::from_millis(self.config.release_time_ms)).await;
+            MidiClock::wait_optional(clock.as_mut(), midi_conn, Duration::from_millis(self.config.release_time_ms)).await?;
         }
         
         // Post delay
         if self.config.post_delay_ms > 0 {
-            tokio::time::sleep(Duration::from_millis(self.config.post_delay_ms)).await;
+            MidiClock::wait_optional(clock.as_mut(), midi_conn, Duration::from_millis(self.config.post_delay_ms)).await?;
+        }
+        
+        if let Some(clock) = clock.as_mut() {
+            clock.stop(midi_conn)?;
         }
         
         // Stop recording
@@ -379,7 +1078,10 @@ impl SamplingEngine {
         let audio_timing = start_time.elapsed();
         let audio_data = audio_samples.lock().unwrap().clone();
         
-        println!("   ✅ Captured {} samples in {:.1}ms", audio_data.len(), audio_timing.as_millis());
+        log_capture(note, &audio_data, audio_timing);
+        let envelope = AdsrEnvelope::estimate(&audio_data, channels, sample_rate);
+        let onset_latency_ms = onset_latency_ms(&audio_data, channels, sample_rate, note_on_frame);
+        self.config.hooks.run(HookPoint::PostNote, &HookContext::note(note, self.config.velocity))?;
         
         Ok(Sample {
             note,
@@ -390,6 +1092,12 @@ impl SamplingEngine {
             recorded_at: std::time::SystemTime::now(),
             midi_timing,
             audio_timing,
+            metadata: SampleMetadata {
+                tempo_bpm: self.config.clock.map(|clock| clock.bpm),
+                envelope,
+                onset_latency_ms,
+                ..Default::default()
+            },
         })
     }
 
@@ -429,7 +1137,7 @@ impl SamplingEngine {
                             audio_samples.extend_from_slice(data);
                         }
                     },
-                    |err| eprintln!("Audio input error: {}", err),
+                    |err| error!("Audio input error: {}", err),
                     None,
                 ).map_err(|e| BatcherbirdError::Audio(format!("Failed to build input stream: {}", e)))?
             }
@@ -456,7 +1164,7 @@ impl SamplingEngine {
                             audio_samples.extend(f32_samples);
                         }
                     },
-                    |err| eprintln!("Audio input error: {}", err),
+                    |err| error!("Audio input error: {}", err),
                     None,
                 ).map_err(|e| BatcherbirdError::Audio(format!("Failed to build input stream: {}", e)))?
             }
@@ -483,7 +1191,34 @@ impl SamplingEngine {
                             audio_samples.extend(f32_samples);
                         }
                     },
-                    |err| eprintln!("Audio input error: {}", err),
+                    |err| error!("Audio input error: {}", err),
+                    None,
+                ).map_err(|e| BatcherbirdError::Audio(format!("Failed to build input stream: {}", e)))?
+            }
+            SampleFormat::I32 => {
+                let level_state_clone = Arc::clone(&level_state);
+                let mut level_detector = AudioLevelDetector::new(sample_rate);
+                
+                device.build_input_stream(
+                    &stream_config,
+                    move |data: &[i32], _: &cpal::InputCallbackInfo| {
+                        let mut audio_samples = samples.lock().unwrap();
+                        let recording_complete = complete.lock().unwrap();
+                        
+                        if !*recording_complete {
+                            // Convert to f32 for level detection and storage
+                            let f32_samples: Vec<f32> = data.iter()
+                                .map(|&sample| i32_to_f32(sample))
+                                .collect();
+                            
+                            // Professional level detection in audio thread
+                            let levels = level_detector.process_samples(&f32_samples);
+                            level_state_clone.update_levels(levels);
+                            
+                            audio_samples.extend(f32_samples);
+                        }
+                    },
+                    |err| error!("Audio input error: {}", err),
                     None,
                 ).map_err(|e| BatcherbirdError::Audio(format!("Failed to build input stream: {}", e)))?
             }
@@ -498,13 +1233,12 @@ impl SamplingEngine {
     /// Build a persistent recording stream for range sampling (Ableton-style)
     /// Unlike build_recording_stream, this uses recording_active flag instead of recording_complete
     fn build_persistent_recording_stream(
-        &self,
+        level_state: Arc<LevelMeterState>,
         device: &cpal::Device,
         config: &cpal::SupportedStreamConfig,
         samples: Arc<Mutex<Vec<f32>>>,
         recording_active: Arc<Mutex<bool>>,
     ) -> Result<cpal::Stream> {
-        let level_state = Arc::clone(&self.level_meter_state);
         let sample_rate = config.sample_rate().0;
         use cpal::{SampleFormat, StreamConfig};
 
@@ -535,7 +1269,7 @@ impl SamplingEngine {
                         }
                         // Stream stays alive but ignores data when recording_active = false
                     },
-                    |err| eprintln!("Persistent stream audio input error: {}", err),
+                    |err| error!("Persistent stream audio input error: {}", err),
                     None,
                 ).map_err(|e| BatcherbirdError::Audio(format!("Failed to build persistent input stream: {}", e)))?
             }
@@ -562,7 +1296,7 @@ impl SamplingEngine {
                             audio_samples.extend(f32_samples);
                         }
                     },
-                    |err| eprintln!("Persistent stream audio input error: {}", err),
+                    |err| error!("Persistent stream audio input error: {}", err),
                     None,
                 ).map_err(|e| BatcherbirdError::Audio(format!("Failed to build persistent input stream: {}", e)))?
             }
@@ -589,7 +1323,34 @@ impl SamplingEngine {
                             audio_samples.extend(f32_samples);
                         }
                     },
-                    |err| eprintln!("Persistent stream audio input error: {}", err),
+                    |err| error!("Persistent stream audio input error: {}", err),
+                    None,
+                ).map_err(|e| BatcherbirdError::Audio(format!("Failed to build persistent input stream: {}", e)))?
+            }
+            SampleFormat::I32 => {
+                let level_state_clone = Arc::clone(&level_state);
+                let mut level_detector = AudioLevelDetector::new(sample_rate);
+                
+                device.build_input_stream(
+                    &stream_config,
+                    move |data: &[i32], _: &cpal::InputCallbackInfo| {
+                        // Convert to f32 for level detection
+                        let f32_samples: Vec<f32> = data.iter()
+                            .map(|&sample| i32_to_f32(sample))
+                            .collect();
+                        
+                        // Always update level meters
+                        let levels = level_detector.process_samples(&f32_samples);
+                        level_state_clone.update_levels(levels);
+                        
+                        let recording_flag = recording_active.lock().unwrap();
+                        
+                        if *recording_flag {
+                            let mut audio_samples = samples.lock().unwrap();
+                            audio_samples.extend(f32_samples);
+                        }
+                    },
+                    |err| error!("Persistent stream audio input error: {}", err),
                     None,
                 ).map_err(|e| BatcherbirdError::Audio(format!("Failed to build persistent input stream: {}", e)))?
             }
@@ -608,41 +1369,1326 @@ impl SamplingEngine {
         start_note: u8,
         end_note: u8,
     ) -> Result<Vec<Sample>> {
-        // Create dedicated runtime for this blocking operation
-        let rt = tokio::runtime::Runtime::new()
-            .map_err(|e| BatcherbirdError::Audio(format!("Failed to create runtime: {}", e)))?;
-        
-        // Execute the async operation in blocking context
-        rt.block_on(self.sample_note_range_async(midi_conn, start_note, end_note))
+        block_on(self.sample_note_range_async(midi_conn, start_note, end_note))
     }
 
-    /// Internal async implementation for range sampling with persistent stream (Ableton-style)
-    async fn sample_note_range_async(
+    /// Async range sampling with persistent stream (Ableton-style)
+    pub async fn sample_note_range_async(
         &self,
         midi_conn: &mut MidiOutputConnection,
         start_note: u8,
         end_note: u8,
     ) -> Result<Vec<Sample>> {
         let mut samples = Vec::new();
-        let total_notes = end_note - start_note + 1;
-        
-        println!("🎹 Range sampling with persistent stream: {} to {} ({} notes)", 
+        self.sample_note_range_each_async(midi_conn, start_note, end_note, |sample| {
+            samples.push(sample);
+            Ok(())
+        }).await?;
+        Ok(samples)
+    }
+
+    /// Blocking interface for range sampling that hands over each take as it is captured
+    pub fn sample_note_range_each_blocking(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        start_note: u8,
+        end_note: u8,
+        on_sample: impl FnMut(Sample) -> Result<()>,
+    ) -> Result<usize> {
+        block_on(self.sample_note_range_each_async(midi_conn, start_note, end_note, on_sample))
+    }
+
+    /// Range sampling that passes each take to `on_sample` right after capture (e.g. to an
+    /// `IncrementalExport`) instead of holding the range in memory. An error from `on_sample`
+    /// stops the range. Returns the number of takes captured.
+    pub async fn sample_note_range_each_async(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        start_note: u8,
+        end_note: u8,
+        mut on_sample: impl FnMut(Sample) -> Result<()>,
+    ) -> Result<usize> {
+        Self::check_note_range(start_note, end_note)?;
+        let mut captured = 0;
+        let total_notes = end_note - start_note + 1;
+        
+        info!("🎹 Range sampling with persistent stream: {} to {} ({} notes)", 
             Self::note_to_name(start_note), 
             Self::note_to_name(end_note), 
             total_notes
         );
         
-        // === PHASE 1: Setup persistent audio stream (like Ableton's audio engine) ===
-        println!("🔧 Setting up persistent audio stream...");
+        // === PHASE 1: Setup persistent audio stream (like Ableton's audio engine) ===
+        let mut session = self.open_capture_session(midi_conn).await?;
+        let mut watchdog = self.batch_watchdog();
+        
+        // === PHASE 2: Record each note using the same stream ===
+        for (index, note) in (start_note..=end_note).enumerate() {
+            info!("🎵 Recording note {}/{}: {} ({})", 
+                index + 1, total_notes, Self::note_to_name(note), note);
+            
+            let Some(sample) = self.capture_note_supervised(&mut session, midi_conn, &mut watchdog, note, self.config.velocity).await? else {
+                continue;
+            };
+            if let Err(e) = on_sample(sample) {
+                Self::close_capture_session(session, midi_conn)?;
+                return Err(e);
+            }
+            captured += 1;
+            
+            // Brief pause between notes (hardware stability)
+            if index < total_notes as usize - 1 {
+                info!("   ⏸️ Pausing 300ms between notes...");
+                MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(NOTE_GAP_MS)).await?;
+            }
+        }
+        
+        // === PHASE 3: Clean shutdown of persistent stream ===
+        Self::close_capture_session(session, midi_conn)?;
+        watchdog.report();
+        
+        info!("🎉 Range sampling complete: {} notes recorded successfully", captured);
+        Ok(captured)
+    }
+
+    /// Blocking interface for dual-input capture: each note is recorded once and split into
+    /// a direct and a processed sample, tagged with their source names
+    pub fn sample_dual_input_blocking(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        start_note: u8,
+        end_note: u8,
+        inputs: &DualInputConfig,
+    ) -> Result<Vec<Sample>> {
+        block_on(self.sample_dual_input_async(midi_conn, start_note, end_note, inputs))
+    }
+
+    pub async fn sample_dual_input_async(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        start_note: u8,
+        end_note: u8,
+        inputs: &DualInputConfig,
+    ) -> Result<Vec<Sample>> {
+        // Check the channel layout before playing anything
+        let (_, config) = self.audio_manager.input_device_and_config()?;
+        inputs.validate(config.channels())?;
+        info!("🔀 Dual-input capture: '{}' on {:?}, '{}' on {:?}",
+            inputs.direct.name, inputs.direct.channels, inputs.processed.name, inputs.processed.channels);
+        
+        let captures = self.sample_note_range_async(midi_conn, start_note, end_note).await?;
+        let mut samples = Vec::with_capacity(captures.len() * 2);
+        for capture in &captures {
+            samples.extend(inputs.split(capture)?);
+        }
+        Ok(samples)
+    }
+
+    /// Blocking interface for sampling an arbitrary note list (e.g. the pads of a drum kit)
+    pub fn sample_notes_blocking(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        notes: &[u8],
+    ) -> Result<Vec<Sample>> {
+        block_on(self.sample_notes_async(midi_conn, notes))
+    }
+
+    pub async fn sample_notes_async(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        notes: &[u8],
+    ) -> Result<Vec<Sample>> {
+        if notes.is_empty() {
+            return Err(BatcherbirdError::Session("No notes to sample".to_string()));
+        }
+
+        info!("🥁 Note list sampling: {} notes", notes.len());
+
+        let mut session = self.open_capture_session(midi_conn).await?;
+        let mut watchdog = self.batch_watchdog();
+        let mut samples = Vec::with_capacity(notes.len());
+
+        for (index, &note) in notes.iter().enumerate() {
+            info!("🎵 Recording note {}/{}: {} ({})",
+                index + 1, notes.len(), Self::note_to_name(note), note);
+
+            match self.capture_note_supervised(&mut session, midi_conn, &mut watchdog, note, self.config.velocity).await {
+                Ok(Some(sample)) => samples.push(sample),
+                Ok(None) => continue,
+                Err(e) => {
+                    Self::close_capture_session(session, midi_conn)?;
+                    return Err(e);
+                }
+            }
+
+            if index < notes.len() - 1 {
+                MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(NOTE_GAP_MS)).await?;
+            }
+        }
+
+        Self::close_capture_session(session, midi_conn)?;
+        watchdog.report();
+
+        info!("🎉 Note list sampling complete: {} notes recorded", samples.len());
+        Ok(samples)
+    }
+
+    /// Blocking interface for recording specific note/velocity pairs, e.g. takes flagged by QA.
+    /// The result lines up with `entries`; None marks an entry the watchdog skipped.
+    pub fn sample_entries_blocking(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        entries: &[(u8, u8)],
+    ) -> Result<Vec<Option<Sample>>> {
+        block_on(self.sample_entries_async(midi_conn, entries))
+    }
+
+    pub async fn sample_entries_async(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        entries: &[(u8, u8)],
+    ) -> Result<Vec<Option<Sample>>> {
+        if entries.is_empty() {
+            return Err(BatcherbirdError::Session("No notes to sample".to_string()));
+        }
+
+        info!("🔁 Recording {} selected takes", entries.len());
+
+        let mut session = self.open_capture_session(midi_conn).await?;
+        let mut watchdog = self.batch_watchdog();
+        let mut samples = Vec::with_capacity(entries.len());
+
+        for (index, &(note, velocity)) in entries.iter().enumerate() {
+            info!("🎵 Recording take {}/{}: {} ({}) vel {}",
+                index + 1, entries.len(), Self::note_to_name(note), note, velocity);
+
+            match self.capture_note_supervised(&mut session, midi_conn, &mut watchdog, note, velocity).await {
+                Ok(sample) => samples.push(sample),
+                Err(e) => {
+                    Self::close_capture_session(session, midi_conn)?;
+                    return Err(e);
+                }
+            }
+
+            if index < entries.len() - 1 {
+                MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(NOTE_GAP_MS)).await?;
+            }
+        }
+
+        Self::close_capture_session(session, midi_conn)?;
+        watchdog.report();
+
+        info!("🎉 Selected takes complete: {} of {} recorded", samples.iter().flatten().count(), entries.len());
+        Ok(samples)
+    }
+
+    /// Blocking interface for multi-take recording: every note is played `takes` times in a
+    /// row, each take tagged with its index (score and pick with `takes::select_takes`)
+    pub fn sample_takes_blocking(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        start_note: u8,
+        end_note: u8,
+        takes: u8,
+    ) -> Result<Vec<Sample>> {
+        block_on(self.sample_takes_async(midi_conn, start_note, end_note, takes))
+    }
+
+    pub async fn sample_takes_async(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        start_note: u8,
+        end_note: u8,
+        takes: u8,
+    ) -> Result<Vec<Sample>> {
+        Self::check_note_range(start_note, end_note)?;
+        if takes == 0 {
+            return Err(BatcherbirdError::Session("Multi-take recording needs at least one take".to_string()));
+        }
+
+        info!("🎬 Multi-take recording: {} notes x {} takes ({} to {})",
+            end_note - start_note + 1, takes,
+            Self::note_to_name(start_note), Self::note_to_name(end_note));
+
+        let mut session = self.open_capture_session(midi_conn).await?;
+        let mut watchdog = self.batch_watchdog();
+        let mut samples = Vec::new();
+
+        for note in start_note..=end_note {
+            for take in 1..=takes {
+                info!("🎬 Recording {} ({}) take {}/{}", Self::note_to_name(note), note, take, takes);
+
+                match self.capture_note_supervised(&mut session, midi_conn, &mut watchdog, note, self.config.velocity).await {
+                    Ok(Some(mut sample)) => {
+                        sample.metadata.take = Some(TakeInfo::new(take));
+                        samples.push(sample);
+                    }
+                    Ok(None) => continue,
+                    Err(e) => {
+                        Self::close_capture_session(session, midi_conn)?;
+                        return Err(e);
+                    }
+                }
+
+                MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(NOTE_GAP_MS)).await?;
+            }
+        }
+
+        Self::close_capture_session(session, midi_conn)?;
+        watchdog.report();
+
+        info!("🎉 Multi-take recording complete: {} takes recorded", samples.len());
+        Ok(samples)
+    }
+
+    /// Blocking interface for CC sweep sampling (follows TAURI_AUDIO_ARCHITECTURE.md)
+    pub fn sample_cc_sweep_blocking(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        start_note: u8,
+        end_note: u8,
+        sweep: &CcSweepConfig,
+    ) -> Result<Vec<Sample>> {
+        block_on(self.sample_cc_sweep_async(midi_conn, start_note, end_note, sweep))
+    }
+
+    /// Capture the note range once per CC value, tagging each sample with its layer
+    pub async fn sample_cc_sweep_async(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        start_note: u8,
+        end_note: u8,
+        sweep: &CcSweepConfig,
+    ) -> Result<Vec<Sample>> {
+        Self::check_note_range(start_note, end_note)?;
+        if sweep.values.is_empty() {
+            return Err(BatcherbirdError::Session("CC sweep needs at least one value".to_string()));
+        }
+        
+        let total_notes = (end_note - start_note + 1) as usize;
+        info!("🎛️ CC{} sweep: {} values x {} notes ({} to {})",
+            sweep.controller,
+            sweep.values.len(),
+            total_notes,
+            Self::note_to_name(start_note),
+            Self::note_to_name(end_note)
+        );
+        
+        let mut session = self.open_capture_session(midi_conn).await?;
+        let mut watchdog = self.batch_watchdog();
+        let mut samples = Vec::with_capacity(sweep.values.len() * total_notes);
+        
+        for &value in &sweep.values {
+            let layer = CcLayer { controller: sweep.controller, value: value & 0x7F };
+            info!("🎛️ Layer {}: sending CC and settling {}ms", layer.label(), sweep.settle_ms);
+            
+            MidiManager::send_control_change(midi_conn, self.config.midi_channel, layer.controller, layer.value)?;
+            MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(sweep.settle_ms)).await?;
+            
+            for (index, note) in (start_note..=end_note).enumerate() {
+                info!("🎵 [{}] Recording note {}/{}: {} ({})",
+                    layer.label(), index + 1, total_notes, Self::note_to_name(note), note);
+                
+                let Some(mut sample) = self.capture_note_supervised(&mut session, midi_conn, &mut watchdog, note, self.config.velocity).await? else {
+                    continue;
+                };
+                sample.metadata.cc_layer = Some(layer);
+                samples.push(sample);
+                
+                MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(NOTE_GAP_MS)).await?;
+            }
+        }
+        
+        Self::close_capture_session(session, midi_conn)?;
+        watchdog.report();
+        
+        info!("🎉 CC sweep complete: {} samples recorded", samples.len());
+        Ok(samples)
+    }
+
+    /// Blocking interface for patch iteration. `on_patch` is called with each
+    /// program's samples as soon as that patch is done, so exports are written
+    /// progressively instead of holding an overnight run in memory.
+    pub fn sample_patches_blocking<F>(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        start_note: u8,
+        end_note: u8,
+        patches: &PatchIterationConfig,
+        on_patch: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(u8, Vec<Sample>) -> Result<()>,
+    {
+        block_on(self.sample_patches_async(midi_conn, start_note, end_note, patches, on_patch))
+    }
+
+    /// Send Program Change, settle, and capture the note/velocity batch for every program
+    pub async fn sample_patches_async<F>(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        start_note: u8,
+        end_note: u8,
+        patches: &PatchIterationConfig,
+        mut on_patch: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(u8, Vec<Sample>) -> Result<()>,
+    {
+        Self::check_note_range(start_note, end_note)?;
+        if patches.programs.is_empty() {
+            return Err(BatcherbirdError::Session("Patch iteration needs at least one program".to_string()));
+        }
+        
+        let velocities = if patches.velocities.is_empty() {
+            vec![self.config.velocity]
+        } else {
+            patches.velocities.clone()
+        };
+        
+        info!("🎛️ Patch iteration: {} programs x {} velocities x {} notes",
+            patches.programs.len(), velocities.len(), end_note - start_note + 1);
+        
+        let mut session = self.open_capture_session(midi_conn).await?;
+        let mut watchdog = self.batch_watchdog();
+        let mut total_samples = 0;
+        
+        for (patch_index, &program) in patches.programs.iter().enumerate() {
+            let program = program & 0x7F;
+            info!("🎛️ Patch {}/{}: Program Change {} (settling {}ms)",
+                patch_index + 1, patches.programs.len(), program, patches.settle_ms);
+            
+            let mut samples = Vec::new();
+            let result: Result<()> = async {
+                MidiManager::send_program_change(midi_conn, self.config.midi_channel, program)?;
+                MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(patches.settle_ms)).await?;
+                
+                for &velocity in &velocities {
+                    for note in start_note..=end_note {
+                        info!("🎵 [Program {}] Recording {} ({}) vel {}",
+                            program, Self::note_to_name(note), note, velocity);
+                        
+                        let Some(mut sample) = self.capture_note_supervised(&mut session, midi_conn, &mut watchdog, note, velocity).await? else {
+                            continue;
+                        };
+                        sample.metadata.program = Some(program);
+                        samples.push(sample);
+                        
+                        MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(NOTE_GAP_MS)).await?;
+                    }
+                }
+                Ok(())
+            }.await;
+            
+            // Hand each patch off before moving on so a later failure keeps earlier patches
+            let handled = result.and_then(|_| {
+                total_samples += samples.len();
+                on_patch(program, samples)
+            });
+            if let Err(e) = handled {
+                error!("❌ Patch iteration stopped at program {}: {}", program, e);
+                Self::close_capture_session(session, midi_conn)?;
+                return Err(e);
+            }
+        }
+        
+        Self::close_capture_session(session, midi_conn)?;
+        watchdog.report();
+        
+        info!("🎉 Patch iteration complete: {} programs, {} samples", patches.programs.len(), total_samples);
+        Ok(total_samples)
+    }
+
+    /// Blocking interface for multi-channel sampling. `on_channel` receives each part's
+    /// samples as soon as the channel is done (e.g. to export a subfolder per channel).
+    pub fn sample_channels_blocking<F>(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        start_note: u8,
+        end_note: u8,
+        channels: &MultiChannelConfig,
+        on_channel: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(u8, Vec<Sample>) -> Result<()>,
+    {
+        block_on(self.sample_channels_async(midi_conn, start_note, end_note, channels, on_channel))
+    }
+
+    /// Capture the note/velocity batch on every channel over one persistent stream
+    pub async fn sample_channels_async<F>(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        start_note: u8,
+        end_note: u8,
+        channels: &MultiChannelConfig,
+        mut on_channel: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(u8, Vec<Sample>) -> Result<()>,
+    {
+        Self::check_note_range(start_note, end_note)?;
+        if channels.channels.is_empty() {
+            return Err(BatcherbirdError::Session("Multi-channel sampling needs at least one channel".to_string()));
+        }
+        
+        let velocities = if channels.velocities.is_empty() {
+            vec![self.config.velocity]
+        } else {
+            channels.velocities.clone()
+        };
+        
+        info!("🎚️ Multi-channel sampling: {} channels x {} velocities x {} notes",
+            channels.channels.len(), velocities.len(), end_note - start_note + 1);
+        
+        let mut session = self.open_capture_session(midi_conn).await?;
+        let mut watchdog = self.batch_watchdog();
+        let mut total_samples = 0;
+        
+        for (channel_index, &channel) in channels.channels.iter().enumerate() {
+            let channel = channel & 0x0F;
+            info!("🎚️ Part {}/{}: MIDI channel {}", channel_index + 1, channels.channels.len(), channel + 1);
+            session.midi_channel = channel;
+            
+            let mut samples = Vec::new();
+            let result: Result<()> = async {
+                MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(channels.settle_ms)).await?;
+                
+                for &velocity in &velocities {
+                    for note in start_note..=end_note {
+                        info!("🎵 [Ch {}] Recording {} ({}) vel {}",
+                            channel + 1, Self::note_to_name(note), note, velocity);
+                        
+                        let Some(mut sample) = self.capture_note_supervised(&mut session, midi_conn, &mut watchdog, note, velocity).await? else {
+                            continue;
+                        };
+                        sample.metadata.midi_channel = Some(channel);
+                        samples.push(sample);
+                        
+                        MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(NOTE_GAP_MS)).await?;
+                    }
+                }
+                Ok(())
+            }.await;
+            
+            // Hand each part off before moving on so a later failure keeps earlier parts
+            let handled = result.and_then(|_| {
+                total_samples += samples.len();
+                on_channel(channel, samples)
+            });
+            if let Err(e) = handled {
+                error!("❌ Multi-channel sampling stopped at channel {}: {}", channel + 1, e);
+                Self::close_capture_session(session, midi_conn)?;
+                return Err(e);
+            }
+        }
+        
+        Self::close_capture_session(session, midi_conn)?;
+        watchdog.report();
+        
+        info!("🎉 Multi-channel sampling complete: {} channels, {} samples", channels.channels.len(), total_samples);
+        Ok(total_samples)
+    }
+
+    /// Blocking interface for round robin sampling: every note is hit `round_robins` times
+    pub fn sample_round_robins_blocking(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        start_note: u8,
+        end_note: u8,
+        round_robins: u8,
+    ) -> Result<Vec<Sample>> {
+        block_on(self.sample_round_robins_async(midi_conn, start_note, end_note, round_robins))
+    }
+
+    pub async fn sample_round_robins_async(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        start_note: u8,
+        end_note: u8,
+        round_robins: u8,
+    ) -> Result<Vec<Sample>> {
+        Self::check_note_range(start_note, end_note)?;
+        if round_robins == 0 {
+            return Err(BatcherbirdError::Session("Round robin sampling needs at least one take".to_string()));
+        }
+
+        let hits: Vec<(u8, u8)> = (start_note..=end_note)
+            .flat_map(|note| (1..=round_robins).map(move |take| (note, take)))
+            .collect();
+
+        info!("🥁 Round robin sampling: {} notes x {} takes ({} to {})",
+            end_note - start_note + 1, round_robins,
+            Self::note_to_name(start_note), Self::note_to_name(end_note));
+
+        self.capture_round_robins_async(midi_conn, &hits).await
+    }
+
+    /// Blocking interface: re-capture specific (note, round robin) hits, e.g. ones flagged for retake
+    pub fn retake_round_robins_blocking(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        hits: &[(u8, u8)],
+    ) -> Result<Vec<Sample>> {
+        block_on(self.retake_round_robins_async(midi_conn, hits))
+    }
+
+    pub async fn retake_round_robins_async(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        hits: &[(u8, u8)],
+    ) -> Result<Vec<Sample>> {
+        info!("🔁 Retaking {} hits", hits.len());
+        self.capture_round_robins_async(midi_conn, hits).await
+    }
+
+    /// Capture each (note, round robin) hit on one persistent stream, tagging the take number
+    async fn capture_round_robins_async(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        hits: &[(u8, u8)],
+    ) -> Result<Vec<Sample>> {
+        let mut session = self.open_capture_session(midi_conn).await?;
+        let mut watchdog = self.batch_watchdog();
+        let mut samples = Vec::with_capacity(hits.len());
+
+        for (index, &(note, take)) in hits.iter().enumerate() {
+            info!("🥁 Recording hit {}/{}: {} ({}) RR{}",
+                index + 1, hits.len(), Self::note_to_name(note), note, take);
+
+            match self.capture_note_supervised(&mut session, midi_conn, &mut watchdog, note, self.config.velocity).await {
+                Ok(Some(mut sample)) => {
+                    sample.metadata.round_robin = Some(take);
+                    samples.push(sample);
+                }
+                Ok(None) => continue,
+                Err(e) => {
+                    Self::close_capture_session(session, midi_conn)?;
+                    return Err(e);
+                }
+            }
+
+            MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(NOTE_GAP_MS)).await?;
+        }
+
+        Self::close_capture_session(session, midi_conn)?;
+        watchdog.report();
+
+        info!("🎉 Round robin capture complete: {} hits recorded", samples.len());
+        Ok(samples)
+    }
+
+    /// Blocking interface for release sampling: each note is held for the note duration,
+    /// and only the audio from shortly before note-off is kept (tagged as a release sample)
+    pub fn sample_releases_blocking(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        start_note: u8,
+        end_note: u8,
+        release: &ReleaseCaptureConfig,
+    ) -> Result<Vec<Sample>> {
+        block_on(self.sample_releases_async(midi_conn, start_note, end_note, release))
+    }
+
+    pub async fn sample_releases_async(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        start_note: u8,
+        end_note: u8,
+        release: &ReleaseCaptureConfig,
+    ) -> Result<Vec<Sample>> {
+        Self::check_note_range(start_note, end_note)?;
+        if self.config.release_time_ms == 0 {
+            return Err(BatcherbirdError::Session("Release sampling needs a release time above 0ms".to_string()));
+        }
+
+        info!("🍂 Release sampling: {} to {} ({}ms pre-buffer)",
+            Self::note_to_name(start_note), Self::note_to_name(end_note), release.pre_buffer_ms);
+
+        let mut session = self.open_capture_session(midi_conn).await?;
+        let mut watchdog = self.batch_watchdog();
+        let mut samples = Vec::new();
+
+        for note in start_note..=end_note {
+            info!("🍂 Recording release {}/{}: {} ({})",
+                note - start_note + 1, end_note - start_note + 1, Self::note_to_name(note), note);
+
+            let sample = match self.capture_note_supervised(&mut session, midi_conn, &mut watchdog, note, self.config.velocity).await {
+                Ok(Some(sample)) => sample,
+                Ok(None) => continue,
+                Err(e) => {
+                    Self::close_capture_session(session, midi_conn)?;
+                    return Err(e);
+                }
+            };
+
+            let release_sample = Self::release_part(&session, &sample, release);
+            if release.keep_sustain {
+                samples.push(sample);
+            }
+            samples.push(release_sample);
+
+            MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(NOTE_GAP_MS)).await?;
+        }
+
+        Self::close_capture_session(session, midi_conn)?;
+        watchdog.report();
+
+        info!("🎉 Release capture complete: {} samples recorded", samples.len());
+        Ok(samples)
+    }
+
+    /// Blocking interface for legato sampling: every interval transition in the range
+    pub fn sample_legato_blocking(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        start_note: u8,
+        end_note: u8,
+        legato: &LegatoConfig,
+    ) -> Result<Vec<Sample>> {
+        block_on(self.sample_legato_async(midi_conn, start_note, end_note, legato))
+    }
+
+    pub async fn sample_legato_async(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        start_note: u8,
+        end_note: u8,
+        legato: &LegatoConfig,
+    ) -> Result<Vec<Sample>> {
+        Self::check_note_range(start_note, end_note)?;
+        let transitions = legato.transitions(start_note, end_note);
+        if transitions.is_empty() {
+            return Err(BatcherbirdError::Session("No legato transitions fit in the note range".to_string()));
+        }
+
+        info!("🎻 Legato sampling: {} transitions over {} to {} (intervals {:?})",
+            transitions.len(), Self::note_to_name(start_note), Self::note_to_name(end_note), legato.intervals);
+
+        self.sample_legato_transitions(midi_conn, &transitions, legato).await
+    }
+
+    async fn sample_legato_transitions(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        transitions: &[(u8, u8)],
+        legato: &LegatoConfig,
+    ) -> Result<Vec<Sample>> {
+        let mut session = self.open_capture_session(midi_conn).await?;
+        let mut watchdog = self.batch_watchdog();
+        let mut samples = Vec::with_capacity(transitions.len());
+
+        for (index, &(source, target)) in transitions.iter().enumerate() {
+            info!("🎻 Recording transition {}/{}: {} -> {}",
+                index + 1, transitions.len(), Self::note_to_name(source), Self::note_to_name(target));
+
+            let sample = match self.capture_supervised(&mut session, midi_conn, &mut watchdog, target, self.config.velocity, Some((source, legato))).await {
+                Ok(Some(sample)) => sample,
+                Ok(None) => continue,
+                Err(e) => {
+                    Self::close_capture_session(session, midi_conn)?;
+                    return Err(e);
+                }
+            };
+            samples.push(sample);
+
+            MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(NOTE_GAP_MS)).await?;
+        }
+
+        Self::close_capture_session(session, midi_conn)?;
+        watchdog.report();
+
+        info!("🎉 Legato capture complete: {} transitions recorded", samples.len());
+        Ok(samples)
+    }
+
+    /// Play source, then the target note-on before the source note-off, and keep only
+    /// the audio from the target note-on so the sample is the transition itself
+    async fn capture_legato_transition(
+        &self,
+        session: &mut CaptureSession,
+        midi_conn: &mut MidiOutputConnection,
+        source: u8,
+        target: u8,
+        legato: &LegatoConfig,
+        velocity: u8,
+    ) -> Result<Sample> {
+        let channel = self.config.midi_channel;
+        let sent_velocity = self.config.velocity_curve.apply(velocity);
+
+        session.start_take();
+        info!("   🔴 Recording started");
+
+        let start_time = Instant::now();
+
+        // Safety: Clear any stuck notes on this channel before starting
+        if self.config.panic_style != PanicStyle::None {
+            MidiManager::send_channel_panic(midi_conn, channel)?;
+            MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(50)).await?;
+        }
+
+        if let Some(clock) = session.clock.as_mut().filter(|clock| clock.config().restart_per_note) {
+            clock.restart(midi_conn)?;
+        }
+
+        MidiManager::send_note_on(midi_conn, channel, source, sent_velocity)?;
+        MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(legato.source_hold_ms)).await?;
+
+        // Mark where the transition starts before the target note-on
+        let transition_start = session.frames();
+        let midi_start = Instant::now();
+        MidiManager::send_note_on(midi_conn, channel, target, sent_velocity)?;
+        MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(legato.overlap_ms)).await?;
+        MidiManager::send_note_off(midi_conn, channel, source, sent_velocity)?;
+        info!("   🎹 Transition played ({}ms overlap)", legato.overlap_ms);
+
+        MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(self.config.note_duration_ms)).await?;
+        MidiManager::send_note_off(midi_conn, channel, target, sent_velocity)?;
+        let midi_timing = midi_start.elapsed();
+
+        if self.config.release_time_ms > 0 {
+            MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(self.config.release_time_ms)).await?;
+        }
+
+        *session.recording_active.lock().unwrap() = false;
+        let audio_timing = kept_take_timing(start_time.elapsed(), transition_start, session.sample_rate);
+        info!("   ⏹️ Recording stopped");
+
+        let (audio_data, channels) = session.take_audio(transition_start);
+        info!("   ✅ Captured {} samples from the transition", audio_data.len());
+
+        Ok(Sample {
+            note: target,
+            velocity,
+            audio_data,
+            sample_rate: session.sample_rate,
+            channels,
+            recorded_at: std::time::SystemTime::now(),
+            midi_timing,
+            audio_timing,
+            metadata: SampleMetadata {
+                legato_from: Some(source),
+                tempo_bpm: self.config.clock.map(|clock| clock.bpm),
+                ..Default::default()
+            },
+        })
+    }
+
+    /// The audio of a session's last take from its note-off, as a release sample
+    fn release_part(session: &CaptureSession, sample: &Sample, release: &ReleaseCaptureConfig) -> Sample {
+        // Cut at the note-off minus the pre-buffer, on a frame boundary
+        let channels = sample.channels.max(1) as usize;
+        let pre_buffer = (release.pre_buffer_ms * sample.sample_rate as u64 / 1000) as usize * channels;
+        let cut = (session.note_off_frame * channels).saturating_sub(pre_buffer);
+        let cut = (cut - cut % channels).min(sample.audio_data.len());
+
+        let mut release_sample = sample.clone();
+        release_sample.audio_data = sample.audio_data[cut..].to_vec();
+        release_sample.metadata.release = true;
+        info!("   ✂️ Release sample: {} samples from note-off", release_sample.audio_data.len());
+        release_sample
+    }
+
+    /// Blocking interface for a sampling queue
+    pub fn sample_queue_blocking(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        queue: &SamplingQueue,
+        settle_ms: u64,
+        on_sample: impl FnMut(Sample) -> Result<()>,
+    ) -> Result<usize> {
+        block_on(self.sample_queue_async(midi_conn, queue, settle_ms, on_sample))
+    }
+
+    /// Record every entry of a queue in order over one capture stream and one watchdog,
+    /// passing each take to `on_sample` as it is captured. Entries with a program send it
+    /// first and wait `settle_ms` for the patch to load (e.g. the profile's
+    /// `program_change_settle_ms`). Returns the number of takes captured.
+    pub async fn sample_queue_async(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        queue: &SamplingQueue,
+        settle_ms: u64,
+        mut on_sample: impl FnMut(Sample) -> Result<()>,
+    ) -> Result<usize> {
+        if queue.is_empty() {
+            return Err(BatcherbirdError::Session("The sampling queue is empty".to_string()));
+        }
+        info!("📋 Sampling queue: {} entries, {} takes", queue.entries().len(), queue.take_count());
+
+        let mut session = self.open_capture_session(midi_conn).await?;
+        let mut watchdog = self.batch_watchdog();
+        let mut captured = 0;
+        let result: Result<()> = async {
+            for (index, entry) in queue.entries().iter().enumerate() {
+                info!("📋 Entry {}/{}: {} to {} ({:?})", index + 1, queue.entries().len(),
+                    Self::note_to_name(entry.start_note), Self::note_to_name(entry.end_note), entry.articulation);
+                if let Some(program) = entry.program {
+                    MidiManager::send_program_change(midi_conn, self.config.midi_channel, program)?;
+                    MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(settle_ms)).await?;
+                }
+
+                let mut hand_over = |mut take: Sample| {
+                    take.metadata.program = entry.program;
+                    captured += 1;
+                    on_sample(take)
+                };
+                for velocity in entry.velocities_or(self.config.velocity) {
+                    if entry.articulation == Articulation::Legato {
+                        for (source, target) in entry.legato.transitions(entry.start_note, entry.end_note) {
+                            let Some(sample) = self.capture_supervised(&mut session, midi_conn, &mut watchdog, target, velocity, Some((source, &entry.legato))).await? else {
+                                continue;
+                            };
+                            hand_over(sample)?;
+                            MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(NOTE_GAP_MS)).await?;
+                        }
+                        continue;
+                    }
+                    for note in entry.start_note..=entry.end_note {
+                        let Some(sample) = self.capture_note_supervised(&mut session, midi_conn, &mut watchdog, note, velocity).await? else {
+                            continue;
+                        };
+                        if entry.articulation == Articulation::Release {
+                            let release_sample = Self::release_part(&session, &sample, &entry.release);
+                            if entry.release.keep_sustain {
+                                hand_over(sample)?;
+                            }
+                            hand_over(release_sample)?;
+                        } else {
+                            hand_over(sample)?;
+                        }
+                        MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(NOTE_GAP_MS)).await?;
+                    }
+                }
+            }
+            Ok(())
+        }.await;
+
+        Self::close_capture_session(session, midi_conn)?;
+        watchdog.report();
+        result?;
+
+        info!("🎉 Sampling queue complete: {} takes recorded", captured);
+        Ok(captured)
+    }
+
+    /// Blocking interface: play a MIDI file to the synth and record the whole performance
+    /// as one sample. `tail_ms` keeps recording after the last event for release tails.
+    pub fn sample_midi_file_blocking(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        file: &MidiFile,
+        tail_ms: u64,
+    ) -> Result<Sample> {
+        block_on(self.sample_midi_file_async(midi_conn, file, tail_ms))
+    }
+
+    pub async fn sample_midi_file_async(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        file: &MidiFile,
+        tail_ms: u64,
+    ) -> Result<Sample> {
+        if file.events.is_empty() {
+            return Err(BatcherbirdError::Session("MIDI file has no events to play".to_string()));
+        }
+
+        info!("🎼 Playing MIDI file: {} events, {} notes, {:.1}s (+{}ms tail)",
+            file.events.len(), file.note_count(), file.duration.as_secs_f32(), tail_ms);
+
+        let mut session = self.open_capture_session(midi_conn).await?;
+        // MIDI files carry their own timing, so a configured clock is not kept running
+        if let Some(clock) = session.clock.as_mut() {
+            clock.stop(midi_conn)?;
+        }
+        let result = self.play_midi_file(&session, midi_conn, file, tail_ms).await;
+        Self::close_capture_session(session, midi_conn)?;
+
+        let sample = result?;
+        info!("🎉 MIDI file capture complete: {:.1}s recorded",
+            sample.audio_data.len() as f32 / sample.channels.max(1) as f32 / sample.sample_rate as f32);
+        Ok(sample)
+    }
+
+    /// Send every event at its scheduled time while the session records
+    async fn play_midi_file(
+        &self,
+        session: &CaptureSession,
+        midi_conn: &mut MidiOutputConnection,
+        file: &MidiFile,
+        tail_ms: u64,
+    ) -> Result<Sample> {
+        session.start_take();
+        info!("   🔴 Recording started");
+
+        let start_time = Instant::now();
+        if self.config.pre_delay_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(self.config.pre_delay_ms)).await;
+        }
+
+        // Events are scheduled against one start instant so timing never drifts
+        let playback_start = Instant::now();
+        let mut held_notes: Vec<(u8, u8)> = Vec::new();
+        for event in &file.events {
+            tokio::time::sleep_until(playback_start + event.time).await;
+            MidiManager::send_message(midi_conn, &event.message)?;
+
+            if let [status, note, velocity] = event.message[..] {
+                match status & 0xF0 {
+                    0x90 if velocity > 0 => held_notes.push((status & 0x0F, note)),
+                    0x80 | 0x90 => held_notes.retain(|&held| held != (status & 0x0F, note)),
+                    _ => {}
+                }
+            }
+        }
+        tokio::time::sleep_until(playback_start + file.duration).await;
+        let midi_timing = playback_start.elapsed();
+
+        // A file that ends with notes still on would otherwise ring forever
+        for (channel, note) in held_notes {
+            MidiManager::send_note_off(midi_conn, channel, note, 0)?;
+        }
+        info!("   🎹 Playback finished");
+
+        tokio::time::sleep(Duration::from_millis(tail_ms)).await;
+
+        *session.recording_active.lock().unwrap() = false;
+        info!("   ⏹️ Recording stopped");
+
+        let (audio_data, channels) = session.take_audio(0);
+
+        // Name the take after the first note played
+        let (note, velocity) = file.events.iter()
+            .find_map(|event| match event.message[..] {
+                [status, note, velocity] if status & 0xF0 == 0x90 && velocity > 0 => Some((note, velocity)),
+                _ => None,
+            })
+            .unwrap_or((60, self.config.velocity));
+
+        Ok(Sample {
+            note,
+            velocity,
+            audio_data,
+            sample_rate: session.sample_rate,
+            channels,
+            recorded_at: std::time::SystemTime::now(),
+            midi_timing,
+            audio_timing: start_time.elapsed(),
+            metadata: SampleMetadata::default(),
+        })
+    }
+
+    /// Blocking interface for long-take sampling: one continuous recording while every
+    /// (note, velocity) pair is played in turn, then sliced into per-note samples at the
+    /// known note-on positions and detected onsets. Avoids restarting anything between notes.
+    pub fn sample_long_take_blocking(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        entries: &[(u8, u8)],
+        slice_config: &SliceConfig,
+    ) -> Result<Vec<Sample>> {
+        block_on(self.sample_long_take_async(midi_conn, entries, slice_config))
+    }
+
+    pub async fn sample_long_take_async(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        entries: &[(u8, u8)],
+        slice_config: &SliceConfig,
+    ) -> Result<Vec<Sample>> {
+        if entries.is_empty() {
+            return Err(BatcherbirdError::Session("No notes to sample".to_string()));
+        }
+
+        let slot_ms = self.config.note_duration_ms + self.config.release_time_ms + self.config.post_delay_ms;
+        info!("🎞️ Recording {} notes in one long take (~{:.1}s)",
+            entries.len(), (self.config.pre_delay_ms + slot_ms * entries.len() as u64) as f32 / 1000.0);
+
+        let mut session = self.open_capture_session(midi_conn).await?;
+        let result = self.play_long_take(&mut session, midi_conn, entries).await;
+        Self::close_capture_session(session, midi_conn)?;
+        let (take, marks) = result?;
+
+        let samples = slice_long_take(&take, &marks, slice_config);
+        info!("🎉 Long take complete: {:.1}s sliced into {} samples",
+            take.audio_data.len() as f32 / take.channels.max(1) as f32 / take.sample_rate as f32, samples.len());
+        Ok(samples)
+    }
+
+    /// Play every entry back to back while the session records, noting where each note-on
+    /// and note-off landed in the buffer
+    async fn play_long_take(
+        &self,
+        session: &mut CaptureSession,
+        midi_conn: &mut MidiOutputConnection,
+        entries: &[(u8, u8)],
+    ) -> Result<(Sample, Vec<NoteMark>)> {
+        session.start_take();
+        info!("   🔴 Recording started");
+        let start_time = Instant::now();
+        if self.config.pre_delay_ms > 0 {
+            MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(self.config.pre_delay_ms)).await?;
+        }
+
+        let mut marks = Vec::with_capacity(entries.len());
+        for (index, &(note, velocity)) in entries.iter().enumerate() {
+            info!("🎵 Note {}/{}: {} ({}) vel {}", index + 1, entries.len(), Self::note_to_name(note), note, velocity);
+            let sent_velocity = self.config.velocity_curve.apply(velocity);
+            let note_on_frame = session.frames();
+            MidiManager::send_note_on(midi_conn, session.midi_channel, note, sent_velocity)?;
+            MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(self.config.note_duration_ms)).await?;
+
+            let note_off_frame = session.frames();
+            MidiManager::send_note_off(midi_conn, session.midi_channel, note, sent_velocity)?;
+            marks.push(NoteMark { note, velocity, note_on_frame, note_off_frame });
+            MidiClock::wait_optional(session.clock.as_mut(), midi_conn,
+                Duration::from_millis(self.config.release_time_ms + self.config.post_delay_ms)).await?;
+        }
+
+        *session.recording_active.lock().unwrap() = false;
+        info!("   ⏹️ Recording stopped");
+        let (audio_data, channels) = session.take_audio(0);
+        if audio_data.is_empty() {
+            return Err(BatcherbirdError::Audio("Long take recorded no audio".to_string()));
+        }
+
+        let take = Sample {
+            note: entries[0].0,
+            velocity: entries[0].1,
+            audio_data,
+            sample_rate: session.sample_rate,
+            channels,
+            recorded_at: std::time::SystemTime::now(),
+            midi_timing: start_time.elapsed(),
+            audio_timing: start_time.elapsed(),
+            metadata: SampleMetadata {
+                tempo_bpm: self.config.clock.map(|clock| clock.bpm),
+                ..Default::default()
+            },
+        };
+        Ok((take, marks))
+    }
+
+    /// Assisted manual sampling: step through `slots` (note, velocity) while the player
+    /// performs each one by hand. `Next` starts a capture and a second `Next` keeps it and
+    /// moves on; `Retake` discards the running capture (or the last kept one) and records
+    /// it again; `Abort` ends the session. Returns the kept samples in slot order.
+    pub fn sample_assisted_blocking(
+        &self,
+        slots: &[(u8, u8)],
+        actions: &std::sync::mpsc::Receiver<TriggerAction>,
+    ) -> Result<Vec<Sample>> {
+        let mut capture = self.arm_capture()?;
+        let mut samples: Vec<Sample> = Vec::with_capacity(slots.len());
+
+        while let Some(&(note, velocity)) = slots.get(samples.len()) {
+            if !capture.is_recording() {
+                info!("🎯 [{}/{}] {} ({}) velocity {} - press next to record",
+                    samples.len() + 1, slots.len(), Self::note_to_name(note), note, velocity);
+            }
+
+            let Ok(action) = actions.recv() else {
+                warn!("⚠️ Trigger input closed");
+                break;
+            };
+            match action {
+                TriggerAction::Next if capture.is_recording() => {
+                    samples.push(capture.stop_take(note, velocity)?);
+                }
+                TriggerAction::Next => capture.start_take(),
+                TriggerAction::Retake if capture.is_recording() => {
+                    info!("🔁 Retaking {}", Self::note_to_name(note));
+                    capture.start_take();
+                }
+                TriggerAction::Retake => match samples.pop() {
+                    Some(last) => info!("🔁 Discarded {} velocity {}, record it again", Self::note_to_name(last.note), last.velocity),
+                    None => warn!("⚠️ Nothing to retake yet"),
+                },
+                TriggerAction::Abort => {
+                    if capture.is_recording() {
+                        warn!("⚠️ Discarding unfinished take of {}", Self::note_to_name(note));
+                    }
+                    info!("🛑 Session aborted");
+                    break;
+                }
+            }
+        }
+
+        capture.disarm();
+        info!("✅ Assisted session finished: {}/{} samples", samples.len(), slots.len());
+        Ok(samples)
+    }
+
+    /// Record notes played by hand: the input stream runs continuously into a rolling
+    /// buffer, and every note-on from `note_events` starts a take that includes the
+    /// pre-roll before it. Takes are monophonic; notes played while one is recording are
+    /// ignored. Runs until `stop` is set, the MIDI input goes away or `max_takes` is reached.
+    pub fn capture_performance_blocking<F>(
+        &self,
+        note_events: &std::sync::mpsc::Receiver<NoteEvent>,
+        config: &PassiveCaptureConfig,
+        stop: &AtomicBool,
+        mut on_sample: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(Sample) -> Result<()>,
+    {
+        use std::sync::mpsc::RecvTimeoutError;
+
+        // Recording stays on; the buffer is trimmed to the pre-roll whenever no take is running
+        let mut capture = self.arm_capture()?;
+        capture.start_take();
+        let audio_samples = Arc::clone(&capture.audio_samples);
+        let (sample_rate, channels) = (capture.sample_rate, capture.channels);
+        let frame = channels.max(1) as usize;
+        let pre_roll = (config.pre_roll_ms * sample_rate as u64 / 1000) as usize * frame;
+        let tail = Duration::from_millis(config.tail_ms);
+        let max_note = Duration::from_millis(config.max_note_ms);
+        info!("👂 Listening for notes ({}ms pre-roll, {}ms tail)", config.pre_roll_ms, config.tail_ms);
+
+        let mut dropped = 0usize;
+        let mut take: Option<PassiveTake> = None;
+        let mut takes = 0usize;
+        let mut result = Ok(());
+
+        while !stop.load(Ordering::Relaxed) {
+            match note_events.recv_timeout(Duration::from_millis(10)) {
+                Ok(event) if config.input_channel.is_none_or(|channel| channel == event.channel) => {
+                    match take.as_mut() {
+                        None if event.on => {
+                            let position = dropped + audio_samples.lock().unwrap().len();
+                            let start = (position.saturating_sub(pre_roll) / frame * frame).max(dropped);
+                            info!("🔴 {} ({}) velocity {} - recording", Self::note_to_name(event.note), event.note, event.velocity);
+                            take = Some(PassiveTake {
+                                note: event.note,
+                                velocity: event.velocity,
+                                channel: event.channel,
+                                start,
+                                started_at: Instant::now(),
+                                released_at: None,
+                            });
+                        }
+                        Some(current) if event.on => {
+                            warn!("⚠️ Ignoring {} while {} is recording", Self::note_to_name(event.note), Self::note_to_name(current.note));
+                        }
+                        Some(current) if current.note == event.note && current.released_at.is_none() => {
+                            current.released_at = Some(Instant::now());
+                        }
+                        _ => {}
+                    }
+                }
+                Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    warn!("⚠️ MIDI input closed");
+                    break;
+                }
+            }
+
+            let finished = take.as_ref().is_some_and(|current| {
+                current.released_at.is_some_and(|released| released.elapsed() >= tail)
+                    || current.started_at.elapsed() >= max_note
+            });
+            if finished {
+                let current = take.take().unwrap();
+                let audio_data = audio_samples.lock().unwrap()[current.start - dropped..].to_vec();
+                let audio_timing = current.started_at.elapsed();
+                log_capture(current.note, &audio_data, audio_timing);
+
+                let sample = Sample {
+                    note: current.note,
+                    velocity: current.velocity,
+                    audio_data,
+                    sample_rate,
+                    channels,
+                    recorded_at: std::time::SystemTime::now(),
+                    midi_timing: current.released_at.map_or(audio_timing, |released| released - current.started_at),
+                    audio_timing,
+                    metadata: SampleMetadata {
+                        midi_channel: config.input_channel.map(|_| current.channel),
+                        ..Default::default()
+                    },
+                };
+                if let Err(e) = on_sample(sample) {
+                    result = Err(e);
+                    break;
+                }
+                takes += 1;
+                if config.max_takes.is_some_and(|max| takes >= max) {
+                    break;
+                }
+            }
+
+            if take.is_none() {
+                let mut buffer = audio_samples.lock().unwrap();
+                // Trim in larger steps so the buffer isn't shifted on every poll
+                if buffer.len() > pre_roll * 2 + frame {
+                    let excess = (buffer.len() - pre_roll) / frame * frame;
+                    buffer.drain(..excess);
+                    dropped += excess;
+                }
+            }
+        }
+
+        if let Some(current) = take {
+            warn!("⚠️ Discarding unfinished take of {}", Self::note_to_name(current.note));
+        }
+        capture.disarm();
+        info!("✅ Passive capture finished: {} takes", takes);
+        result.map(|_| takes)
+    }
+
+    /// Blocking interface: capture one note at each probe velocity and measure its loudness
+    pub fn measure_velocity_response_blocking(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        note: u8,
+        probe_velocities: &[u8],
+    ) -> Result<VelocityResponse> {
+        block_on(self.measure_velocity_response_async(midi_conn, note, probe_velocities))
+    }
+
+    pub async fn measure_velocity_response_async(
+        &self,
+        midi_conn: &mut MidiOutputConnection,
+        note: u8,
+        probe_velocities: &[u8],
+    ) -> Result<VelocityResponse> {
+        info!("📈 Measuring velocity response of {} at {} velocities",
+            Self::note_to_name(note), probe_velocities.len());
+        
+        let mut session = self.open_capture_session(midi_conn).await?;
+        let mut probes = Vec::with_capacity(probe_velocities.len());
+        
+        for &velocity in probe_velocities {
+            match self.capture_note(&mut session, midi_conn, note, velocity).await {
+                Ok(sample) => probes.push(sample),
+                Err(e) => {
+                    Self::close_capture_session(session, midi_conn)?;
+                    return Err(e);
+                }
+            }
+            MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(NOTE_GAP_MS)).await?;
+        }
+        
+        Self::close_capture_session(session, midi_conn)?;
+        
+        let response = VelocityResponse::from_samples(&probes)?;
+        for (velocity, db) in &response.points {
+            info!(velocity, peak_db = (db * 10.0).round() / 10.0, "   🎚️ Probe");
+        }
+        Ok(response)
+    }
+
+    /// Start one persistent input stream to be reused for every note of a batch
+    async fn open_capture_session(&self, midi_conn: &mut MidiOutputConnection) -> Result<CaptureSession> {
+        info!("🔧 Setting up persistent audio stream...");
         
         // Safety: Clear any stuck notes before starting range recording session
-        println!("🚨 Sending MIDI panic before range recording for safety...");
-        MidiManager::send_midi_panic(midi_conn)?;
+        warn!("🚨 Sending MIDI panic before range recording for safety...");
+        MidiManager::send_panic(midi_conn, self.config.panic_style, self.config.midi_channel)?;
         tokio::time::sleep(Duration::from_millis(100)).await; // Give hardware time to process
         
-        let device = self.audio_manager.get_default_input_device()?;
-        let config = device.default_input_config()
-            .map_err(|e| BatcherbirdError::Audio(format!("Failed to get input config: {}", e)))?;
+        let (device, config) = self.audio_manager.input_device_and_config()?;
 
         let sample_rate = config.sample_rate().0;
         let channels = config.channels();
@@ -650,121 +2696,259 @@ impl SamplingEngine {
         // Shared audio buffer - reused for all notes
         let audio_samples = Arc::new(Mutex::new(Vec::new()));
         let recording_active = Arc::new(Mutex::new(false));
-        let samples_clone = audio_samples.clone();
-        let recording_clone = recording_active.clone();
 
         // Create ONE stream for entire range (like professional DAWs)
-        let stream = self.build_persistent_recording_stream(&device, &config, samples_clone, recording_clone)?;
+        let stream = Self::build_persistent_recording_stream(
+            Arc::clone(&self.level_meter_state), &device, &config, audio_samples.clone(), recording_active.clone())
+            .inspect_err(|_| AudioManager::clear_device_cache())?;
         
         // Start the persistent stream
-        stream.play().map_err(|e| BatcherbirdError::Audio(format!("Failed to start persistent stream: {}", e)))?;
-        println!("✅ Persistent audio stream started");
+        stream.play()
+            .map_err(|e| BatcherbirdError::Audio(format!("Failed to start persistent stream: {}", e)))
+            .inspect_err(|_| AudioManager::clear_device_cache())?;
+        info!("✅ Persistent audio stream started");
         
-        // === PHASE 2: Record each note using the same stream ===
-        for (index, note) in (start_note..=end_note).enumerate() {
-            println!("🎵 Recording note {}/{}: {} ({})", 
-                index + 1, total_notes, Self::note_to_name(note), note);
-            
-            // Clear the buffer for this note
-            {
-                let mut buffer = audio_samples.lock().unwrap();
-                buffer.clear();
-                println!("   🧹 Buffer cleared ({} samples removed)", buffer.len());
-            }
-            
-            // Start recording for this note
-            {
-                let mut recording = recording_active.lock().unwrap();
-                *recording = true;
-                println!("   🔴 Recording started");
+        let secondary = AudioManager::secondary_input()
+            .map(|secondary| SecondaryCapture::open(
+                &self.audio_manager, secondary, audio_samples.clone(), channels, sample_rate, recording_active.clone()))
+            .transpose()?;
+        
+        // Clock runs for the whole session so the synth stays locked between notes
+        let mut clock = self.config.clock.map(MidiClock::new);
+        if let Some(clock) = clock.as_mut() {
+            let config = *clock.config();
+            info!("⏱️ Starting MIDI clock at {} BPM ({} beat lead-in)", config.bpm, config.lead_in_beats);
+            clock.start(midi_conn)?;
+            clock.wait(midi_conn, Duration::from_secs_f64(60.0 / config.bpm as f64 * config.lead_in_beats as f64)).await?;
+        }
+        
+        Ok(CaptureSession {
+            stream,
+            audio_samples,
+            recording_active,
+            sample_rate,
+            channels,
+            panic_style: self.config.panic_style,
+            midi_channel: self.config.midi_channel,
+            clock,
+            note_off_frame: 0,
+            secondary,
+        })
+    }
+
+    /// Stop the persistent stream and leave the synth silent
+    fn close_capture_session(mut session: CaptureSession, midi_conn: &mut MidiOutputConnection) -> Result<()> {
+        if let Some(clock) = session.clock.as_mut() {
+            clock.stop(midi_conn)?;
+            info!("⏱️ MIDI clock stopped");
+        }
+        
+        info!("🔧 Shutting down persistent stream...");
+        session.stream.pause().map_err(|e| BatcherbirdError::Audio(format!("Failed to stop persistent stream: {}", e)))?;
+        if let Some(secondary) = &session.secondary {
+            secondary.pause();
+        }
+        let (panic_style, midi_channel) = (session.panic_style, session.midi_channel);
+        drop(session); // Explicit cleanup
+        info!("✅ Persistent stream shut down cleanly");
+        
+        // Safety: Final MIDI panic to ensure no stuck notes (professional practice)
+        warn!("🚨 Final MIDI panic after range recording for safety...");
+        MidiManager::send_panic(midi_conn, panic_style, midi_channel)?;
+        Ok(())
+    }
+
+    /// Watchdog for one batch, with the note limit derived from the take timings
+    fn batch_watchdog(&self) -> BatchWatchdog {
+        self.failed_notes.lock().unwrap().clear();
+        let expected = Duration::from_millis(
+            self.config.pre_delay_ms + self.config.note_duration_ms + self.config.release_time_ms + self.config.post_delay_ms);
+        BatchWatchdog::new(self.config.watchdog.clone(), expected)
+    }
+
+    /// `capture_note` under the watchdog. A hung note is cancelled, the synth silenced and the
+    /// stream replaced; Ok(None) means the note was skipped. Cancelling the engine's token
+    /// stops the note the same way but ends the batch with an error.
+    async fn capture_note_supervised(
+        &self,
+        session: &mut CaptureSession,
+        midi_conn: &mut MidiOutputConnection,
+        watchdog: &mut BatchWatchdog,
+        note: u8,
+        velocity: u8,
+    ) -> Result<Option<Sample>> {
+        self.capture_supervised(session, midi_conn, watchdog, note, velocity, None).await
+    }
+
+    /// A note, or with `legato` = (source note, config) the transition into it, under the watchdog
+    async fn capture_supervised(
+        &self,
+        session: &mut CaptureSession,
+        midi_conn: &mut MidiOutputConnection,
+        watchdog: &mut BatchWatchdog,
+        note: u8,
+        velocity: u8,
+        legato: Option<(u8, &LegatoConfig)>,
+    ) -> Result<Option<Sample>> {
+        self.cancel.check()?;
+        self.config.hooks.run(HookPoint::PreNote, &HookContext::note(note, velocity))?;
+        self.report(CaptureProgress::Started { note, velocity });
+        let failed = |reason: String| CaptureProgress::Failed { note, velocity, reason };
+        let capture = async {
+            match legato {
+                Some((source, legato)) => self.capture_legato_transition(session, midi_conn, source, note, legato, velocity).await,
+                None => self.capture_note(session, midi_conn, note, velocity).await,
             }
-            
-            let start_time = Instant::now();
-            
-            // Pre-delay
-            if self.config.pre_delay_ms > 0 {
-                tokio::time::sleep(Duration::from_millis(self.config.pre_delay_ms)).await;
+        };
+        let outcome = tokio::select! {
+            result = watchdog.supervise(note, velocity, capture) => Some(result),
+            _ = self.cancel.cancelled() => None,
+        };
+        let Some(sample) = outcome else {
+            warn!("⏹️ Recording cancelled during note {}, silencing the synth", note);
+            self.report(failed("cancelled".to_string()));
+            MidiManager::send_panic(midi_conn, session.panic_style, session.midi_channel)?;
+            return Err(CancelToken::error());
+        };
+        let sample = sample.inspect_err(|e| self.report(failed(e.to_string())))?;
+        if let Some(sample) = &sample {
+            self.config.hooks.run(HookPoint::PostNote, &HookContext::note(note, velocity))?;
+            let frames = sample.audio_data.len() / sample.channels.max(1) as usize;
+            if watchdog.track_clock(frames, sample.sample_rate, sample.audio_timing) {
+                self.restart_capture_session(session, midi_conn).await?;
             }
-            
-            // Safety: Clear any stuck notes on this channel before starting
-            MidiManager::send_channel_panic(midi_conn, self.config.midi_channel)?;
+        } else {
+            let reason = watchdog.failed().last().map_or_else(|| "timed out".to_string(), |failure| failure.reason.clone());
+            self.report(failed(reason));
+            *self.failed_notes.lock().unwrap() = watchdog.failed().to_vec();
+            // The note may still be sounding and the stream wedged
+            MidiManager::send_panic(midi_conn, session.panic_style, session.midi_channel)?;
+            watchdog.check_continue()?;
+            self.restart_capture_session(session, midi_conn).await?;
+        }
+        Ok(sample)
+    }
+
+    /// Tear down a session's stream and clock and start a fresh one in its place
+    async fn restart_capture_session(&self, session: &mut CaptureSession, midi_conn: &mut MidiOutputConnection) -> Result<()> {
+        info!("🔧 Replacing the capture stream...");
+        if let Some(clock) = session.clock.as_mut() {
+            clock.stop(midi_conn)?;
+        }
+        // Best effort: a wedged stream may refuse to pause; dropping it below closes it regardless
+        let _ = session.stream.pause();
+        if let Some(secondary) = &session.secondary {
+            secondary.pause();
+        }
+        let midi_channel = session.midi_channel;
+        let fresh = self.open_capture_session(midi_conn).await?;
+        *session = CaptureSession { midi_channel, ..fresh };
+        Ok(())
+    }
+
+    /// Record a single note on an already running capture session
+    #[tracing::instrument(name = "capture_note", skip_all, fields(note, velocity))]
+    async fn capture_note(
+        &self,
+        session: &mut CaptureSession,
+        midi_conn: &mut MidiOutputConnection,
+        note: u8,
+        velocity: u8,
+    ) -> Result<Sample> {
+        // Clear the buffers and start recording for this note
+        session.start_take();
+        info!("   🔴 Recording started");
+        
+        let start_time = Instant::now();
+        
+        // Pre-delay
+        if self.config.pre_delay_ms > 0 {
+            MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(self.config.pre_delay_ms)).await?;
+        }
+        
+        // Safety: Clear any stuck notes on this channel before starting
+        if self.config.panic_style != PanicStyle::None {
+            MidiManager::send_channel_panic(midi_conn, session.midi_channel)?;
             
             // Brief delay after panic to ensure hardware processes it
-            tokio::time::sleep(Duration::from_millis(50)).await;
-            
-            // Send MIDI note on
-            let midi_start = Instant::now();
-            MidiManager::send_note_on(midi_conn, self.config.midi_channel, note, self.config.velocity)?;
-            println!("   🎹 MIDI Note On sent");
-            
-            // Wait for note duration
-            tokio::time::sleep(Duration::from_millis(self.config.note_duration_ms)).await;
-            
-            // Send MIDI note off
-            MidiManager::send_note_off(midi_conn, self.config.midi_channel, note, self.config.velocity)?;
-            let midi_timing = midi_start.elapsed();
-            println!("   🎹 MIDI Note Off sent");
-            
-            // Wait for release
-            if self.config.release_time_ms > 0 {
-                tokio::time::sleep(Duration::from_millis(self.config.release_time_ms)).await;
-            }
-            
-            // Post delay
-            if self.config.post_delay_ms > 0 {
-                tokio::time::sleep(Duration::from_millis(self.config.post_delay_ms)).await;
-            }
-            
-            // Stop recording for this note
-            {
-                let mut recording = recording_active.lock().unwrap();
-                *recording = false;
-                println!("   ⏹️ Recording stopped");
-            }
-            
-            let audio_timing = start_time.elapsed();
-            
-            // Extract recorded audio data
-            let audio_data = {
-                let buffer = audio_samples.lock().unwrap();
-                buffer.clone()
-            };
-            
-            println!("   ✅ Captured {} samples in {:.1}ms", audio_data.len(), audio_timing.as_millis());
-            
-            // Create sample record
-            let sample = Sample {
-                note,
-                velocity: self.config.velocity,
-                audio_data,
-                sample_rate,
-                channels,
-                recorded_at: std::time::SystemTime::now(),
-                midi_timing,
-                audio_timing,
-            };
-            
-            samples.push(sample);
-            
-            // Brief pause between notes (hardware stability)
-            if index < total_notes as usize - 1 {
-                println!("   ⏸️ Pausing 300ms between notes...");
-                tokio::time::sleep(Duration::from_millis(300)).await;
-            }
+            MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(50)).await?;
         }
         
-        // === PHASE 3: Clean shutdown of persistent stream ===
-        println!("🔧 Shutting down persistent stream...");
-        stream.pause().map_err(|e| BatcherbirdError::Audio(format!("Failed to stop persistent stream: {}", e)))?;
-        drop(stream); // Explicit cleanup
-        println!("✅ Persistent stream shut down cleanly");
+        // Restart the clock so arps/synced LFOs start on beat one for every note
+        if let Some(clock) = session.clock.as_mut().filter(|clock| clock.config().restart_per_note) {
+            clock.restart(midi_conn)?;
+        }
         
-        // Safety: Final MIDI panic to ensure no stuck notes (professional practice)
-        println!("🚨 Final MIDI panic after range recording for safety...");
-        MidiManager::send_midi_panic(midi_conn)?;
+        // Send MIDI note on (sample keeps the requested velocity, the curve only affects what's sent)
+        let sent_velocity = self.config.velocity_curve.apply(velocity);
+        let note_on_frame = session.frames();
+        let midi_start = Instant::now();
+        MidiManager::send_note_on(midi_conn, session.midi_channel, note, sent_velocity)?;
+        if sent_velocity != velocity {
+            info!("   🎹 MIDI Note On sent (velocity {} -> {} via curve)", velocity, sent_velocity);
+        } else {
+            info!("   🎹 MIDI Note On sent");
+        }
         
-        println!("🎉 Range sampling complete: {} notes recorded successfully", samples.len());
-        Ok(samples)
+        // Wait for note duration
+        MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(self.config.note_duration_ms)).await?;
+        
+        // Send MIDI note off, remembering where it landed in the recording
+        session.note_off_frame = session.frames();
+        MidiManager::send_note_off(midi_conn, session.midi_channel, note, sent_velocity)?;
+        let midi_timing = midi_start.elapsed();
+        info!("   🎹 MIDI Note Off sent");
+        
+        // Wait for release
+        if self.config.release_time_ms > 0 {
+            MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(self.config.release_time_ms)).await?;
+        }
+        
+        // Post delay
+        if self.config.post_delay_ms > 0 {
+            MidiClock::wait_optional(session.clock.as_mut(), midi_conn, Duration::from_millis(self.config.post_delay_ms)).await?;
+        }
+        
+        // Stop recording for this note
+        {
+            let mut recording = session.recording_active.lock().unwrap();
+            *recording = false;
+            info!("   ⏹️ Recording stopped");
+        }
+        
+        let audio_timing = start_time.elapsed();
+        
+        // Extract recorded audio data
+        let (audio_data, channels) = session.take_audio(0);
+        
+        log_capture(note, &audio_data, audio_timing);
+        let envelope = AdsrEnvelope::estimate(&audio_data, channels, session.sample_rate);
+        let onset_latency_ms = onset_latency_ms(&audio_data, channels, session.sample_rate, note_on_frame);
+        
+        Ok(Sample {
+            note,
+            velocity,
+            audio_data,
+            sample_rate: session.sample_rate,
+            channels,
+            recorded_at: std::time::SystemTime::now(),
+            midi_timing,
+            audio_timing,
+            metadata: SampleMetadata {
+                tempo_bpm: self.config.clock.map(|clock| clock.bpm),
+                envelope,
+                onset_latency_ms,
+                ..Default::default()
+            },
+        })
+    }
+
+    /// Reject a range that runs backwards or past note 127 before anything is sent
+    fn check_note_range(start_note: u8, end_note: u8) -> Result<()> {
+        if start_note > end_note || end_note > 127 {
+            return Err(BatcherbirdError::Config(format!("Invalid note range {}-{}", start_note, end_note)));
+        }
+        Ok(())
     }
 
     fn note_to_name(note: u8) -> String {
@@ -776,21 +2960,55 @@ impl SamplingEngine {
 }
 
 impl Sample {
+    /// Track pitch across the note and keep the envelope in the metadata. When the drift
+    /// exceeds `drift_limit_cents` the take is flagged for a retake. Returns the drift in cents.
+    pub fn track_pitch_drift(&mut self, drift_limit_cents: Option<f32>) -> f32 {
+        let envelope = PitchEnvelope::track(&self.audio_data, self.channels, self.sample_rate, PITCH_ENVELOPE_HOP_MS);
+        let drift = envelope.drift_cents();
+        self.metadata.pitch_envelope = Some(envelope);
+
+        if let Some(limit) = drift_limit_cents.filter(|&limit| drift > limit) {
+            warn!("⚠️  {} ({}) drifts {:.0} cents within the note (limit {:.0}) - failing VCO? Marked for retake",
+                Self::note_to_name(self.note), self.note, drift, limit);
+            self.metadata.retake_reason = Some(format!("pitch drift {:.0} cents", drift));
+        }
+        drift
+    }
+
+    /// Check that the synth sounded the requested note (wrong transpose, stuck octave switch,
+    /// missed note-on). A mismatch marks the take for a retake; unpitched takes pass.
+    pub fn verify_played_note(&mut self) -> Option<PitchEstimate> {
+        let estimate = pitch::detect_pitch(&self.audio_data, self.channels, self.sample_rate)?;
+        if estimate.note != self.note {
+            warn!("⚠️  Requested {} ({}) but heard {} ({}, {:.0} Hz) - Marked for retake",
+                Self::note_to_name(self.note), self.note,
+                Self::note_to_name(estimate.note), estimate.note, estimate.frequency_hz);
+            let reason = format!("played {} instead of {}", Self::note_to_name(estimate.note), Self::note_to_name(self.note));
+            self.metadata.retake_reason = Some(match self.metadata.retake_reason.take() {
+                Some(existing) => format!("{}; {}", existing, reason),
+                None => reason,
+            });
+        } else {
+            self.metadata.tuning_cents = Some(estimate.cents);
+        }
+        Some(estimate)
+    }
+
     /// Apply sample detection and trimming to this sample
     pub fn apply_detection(&mut self, config: DetectionConfig) -> Result<DetectionResult> {
-        let detector = SampleDetector::new(config);
+        let detector = config.detector();
         let detection_result = detector.detect_boundaries(&self.audio_data, self.sample_rate)?;
         
         if detection_result.success {
-            println!("🎵 Applying detection to {} sample ({})", 
+            info!("🎵 Applying detection to {} sample ({})", 
                 Self::note_to_name(self.note), self.note);
             
             // Trim the audio data
             self.audio_data = detector.trim_audio(&self.audio_data, &detection_result);
             
-            println!("   Sample trimmed successfully");
+            info!("   Sample trimmed successfully");
         } else {
-            println!("⚠️  Detection failed for {} sample ({}): {}", 
+            warn!("⚠️  Detection failed for {} sample ({}): {}", 
                 Self::note_to_name(self.note), self.note,
                 detection_result.failure_reason.as_deref().unwrap_or("Unknown reason"));
         }
@@ -800,7 +3018,7 @@ impl Sample {
     
     /// Apply loop detection to find optimal loop points in the sample
     pub fn apply_loop_detection(&mut self, config: LoopDetectionConfig) -> Result<LoopDetectionResult> {
-        println!("🔄 Applying loop detection to {} sample ({})", 
+        info!("🔄 Applying loop detection to {} sample ({})", 
             Self::note_to_name(self.note), self.note);
         
         let detector = LoopDetector::new(config);
@@ -808,7 +3026,7 @@ impl Sample {
         
         if loop_result.success {
             if let Some(ref candidate) = loop_result.best_candidate {
-                println!("   ✅ Loop detected: {:.2}s length, quality {:.3}", 
+                info!("   ✅ Loop detected: {:.2}s length, quality {:.3}", 
                     candidate.length_samples as f32 / self.sample_rate as f32,
                     candidate.quality_score);
                 
@@ -818,11 +3036,11 @@ impl Sample {
                     candidate, 
                     self.sample_rate
                 ) {
-                    println!("   ⚠️ Failed to apply crossfade: {}", e);
+                    warn!("   ⚠️ Failed to apply crossfade: {}", e);
                 }
             }
         } else {
-            println!("   ⚠️ Loop detection failed: {}", 
+            warn!("   ⚠️ Loop detection failed: {}", 
                 loop_result.failure_reason.as_deref().unwrap_or("Unknown reason"));
         }
         
@@ -836,4 +3054,38 @@ impl Sample {
         let note_name = note_names[(note % 12) as usize];
         format!("{}{}", note_name, octave)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legato_take_timing_matches_kept_audio() {
+        // The 50 ms panic and 800 ms source hold are cut from a 1.85 s legato take, leaving 1 s
+        let take = Duration::from_millis(1850);
+        let timing = kept_take_timing(take, 40_800, 48000);
+        assert!((timing.as_secs_f64() - 1.0).abs() < 1e-6, "{:?}", timing);
+        assert_eq!(kept_take_timing(take, 480_000, 48000), Duration::ZERO);
+
+        // Over a batch the kept audio lines up with its wall time
+        let config = WatchdogConfig { resync_on_drift: true, ..Default::default() };
+        let mut watchdog = BatchWatchdog::new(config.clone(), take);
+        for _ in 0..200 {
+            assert!(!watchdog.track_clock(48_000, 48000, timing));
+        }
+        assert!(watchdog.clock_drift_ppm().unwrap().abs() < 1.0);
+
+        // Against the whole take's time the same audio reads as a clock running far slow
+        let mut whole = BatchWatchdog::new(config, take);
+        assert!((0..200).any(|_| whole.track_clock(48_000, 48000, take)));
+    }
+
+    #[test]
+    fn test_note_ranges_are_checked() {
+        assert!(SamplingEngine::check_note_range(60, 60).is_ok());
+        assert!(SamplingEngine::check_note_range(0, 127).is_ok());
+        assert!(SamplingEngine::check_note_range(72, 60).is_err());
+        assert!(SamplingEngine::check_note_range(60, 128).is_err());
+    }
+}
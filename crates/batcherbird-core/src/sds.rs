@@ -0,0 +1,255 @@
+use crate::edit::{self, LoopPoints};
+use crate::midi::MidiManager;
+use crate::{library, BatcherbirdError, Result};
+use midir::MidiOutputConnection;
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Universal non-realtime SysEx ID and the SDS sub-IDs
+const NON_REALTIME: u8 = 0x7E;
+const DUMP_HEADER: u8 = 0x01;
+const DATA_PACKET: u8 = 0x02;
+const ACK: u8 = 0x7F;
+const NAK: u8 = 0x7E;
+const CANCEL: u8 = 0x7D;
+const WAIT: u8 = 0x7C;
+/// Every data packet carries exactly 120 data bytes
+const PACKET_DATA_BYTES: usize = 120;
+/// Header fields are 3 x 7 bits wide
+const MAX_21_BIT: u32 = (1 << 21) - 1;
+/// How long the receiver gets to answer the header before we fall back to open loop
+const HEADER_TIMEOUT: Duration = Duration::from_secs(2);
+/// Wait for a packet reply (and the minimum gap between packets without one)
+const PACKET_TIMEOUT: Duration = Duration::from_millis(20);
+/// Resends of a packet the receiver keeps rejecting before giving up
+const MAX_RETRIES: usize = 5;
+
+/// Settings for sending one sample with the MIDI Sample Dump Standard
+#[derive(Debug, Clone)]
+pub struct SdsConfig {
+    /// SysEx device ID of the receiving sampler (usually its MIDI channel - 1)
+    pub device_id: u8,
+    /// Sample slot to write on the receiver (0-16383)
+    pub sample_number: u16,
+    /// Word size sent to the sampler (8-28; 12 for S900-class machines, 16 for later ones)
+    pub bits: u8,
+}
+
+impl Default for SdsConfig {
+    fn default() -> Self {
+        Self {
+            device_id: 0,
+            sample_number: 0,
+            bits: 16,
+        }
+    }
+}
+
+/// A sample encoded as SDS messages, ready to send
+#[derive(Debug, Clone)]
+pub struct SampleDump {
+    pub header: Vec<u8>,
+    pub packets: Vec<Vec<u8>>,
+}
+
+/// Receiver handshake replies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Reply {
+    Ack(u8),
+    Nak(u8),
+    Cancel,
+    Wait,
+}
+
+impl SampleDump {
+    /// Encode mono audio. Loop points are in frames with an exclusive end, as in `edit::LoopPoints`.
+    pub fn from_audio(audio: &[f32], sample_rate: u32, loop_points: Option<LoopPoints>, config: &SdsConfig) -> Result<Self> {
+        if !(8..=28).contains(&config.bits) {
+            return Err(BatcherbirdError::Config(format!("SDS word size must be 8-28 bits, not {}", config.bits)));
+        }
+        if config.device_id > 0x7F || config.sample_number > 0x3FFF {
+            return Err(BatcherbirdError::Config("SDS device ID must be 0-127 and sample number 0-16383".to_string()));
+        }
+        if audio.is_empty() || audio.len() as u32 > MAX_21_BIT || sample_rate == 0 {
+            return Err(BatcherbirdError::Config(format!("{} words can't be sent with SDS", audio.len())));
+        }
+
+        let (loop_start, loop_end, loop_type) = match loop_points {
+            Some(points) => (points.start as u32, points.end.saturating_sub(1) as u32, 0x00),
+            None => (0, audio.len() as u32 - 1, 0x7F),
+        };
+        let mut header = vec![0xF0, NON_REALTIME, config.device_id, DUMP_HEADER];
+        header.extend_from_slice(&[(config.sample_number & 0x7F) as u8, (config.sample_number >> 7) as u8, config.bits]);
+        for value in [1_000_000_000 / sample_rate, audio.len() as u32, loop_start, loop_end] {
+            header.extend_from_slice(&seven_bit_triplet(value));
+        }
+        header.extend_from_slice(&[loop_type, 0xF7]);
+
+        let bytes_per_word = (config.bits as usize).div_ceil(7);
+        let data: Vec<u8> = audio.iter().flat_map(|&sample| encode_word(sample, config.bits)).collect();
+        let words_per_packet = PACKET_DATA_BYTES / bytes_per_word;
+        let packets = data
+            .chunks(words_per_packet * bytes_per_word)
+            .enumerate()
+            .map(|(index, chunk)| {
+                let mut body = [0u8; PACKET_DATA_BYTES];
+                body[..chunk.len()].copy_from_slice(chunk);
+                let mut packet = vec![0xF0, NON_REALTIME, config.device_id, DATA_PACKET, (index % 128) as u8];
+                packet.extend_from_slice(&body);
+                packet.push(checksum(&packet[1..]));
+                packet.push(0xF7);
+                packet
+            })
+            .collect();
+
+        Ok(Self { header, packets })
+    }
+
+    /// Encode a WAV file, mixing stereo down to mono and sending its smpl loop if it has one
+    pub fn from_wav_file(path: &Path, config: &SdsConfig) -> Result<Self> {
+        let (audio, spec) = library::read_wav(path)?;
+        let channels = spec.channels.max(1) as usize;
+        let mono: Vec<f32> = audio.chunks(channels).map(|frame| frame.iter().sum::<f32>() / frame.len() as f32).collect();
+        Self::from_audio(&mono, spec.sample_rate, edit::read_loop_points(path)?, config)
+    }
+
+    /// Send the dump. With `replies` (SysEx from the sampler's MIDI out) the transfer is
+    /// handshaked; otherwise, or when the sampler never answers, packets are sent open loop.
+    pub fn send(&self, conn: &mut MidiOutputConnection, replies: Option<&mpsc::Receiver<Vec<u8>>>, device_id: u8) -> Result<()> {
+        info!("📤 Sending SDS dump: {} packets", self.packets.len());
+        MidiManager::send_sysex(conn, &self.header)?;
+        let mut handshake = match replies {
+            Some(rx) => match wait_for_reply(rx, device_id, HEADER_TIMEOUT) {
+                Some(Reply::Cancel) => return Err(BatcherbirdError::Session("Sampler cancelled the sample dump".to_string())),
+                Some(_) => Some(rx),
+                None => {
+                    warn!("   ⚠️ No reply to the dump header, sending open loop");
+                    None
+                }
+            },
+            None => {
+                std::thread::sleep(HEADER_TIMEOUT);
+                None
+            }
+        };
+
+        for (index, packet) in self.packets.iter().enumerate() {
+            let mut retries = 0;
+            loop {
+                MidiManager::send_sysex(conn, packet)?;
+                let Some(rx) = handshake else {
+                    std::thread::sleep(PACKET_TIMEOUT);
+                    break;
+                };
+                match wait_for_reply(rx, device_id, PACKET_TIMEOUT) {
+                    Some(Reply::Ack(_)) => break,
+                    Some(Reply::Nak(_)) if retries < MAX_RETRIES => retries += 1,
+                    Some(Reply::Nak(_)) => {
+                        return Err(BatcherbirdError::Session(format!("Packet {} rejected {} times", index, retries + 1)));
+                    }
+                    Some(Reply::Cancel) => {
+                        return Err(BatcherbirdError::Session(format!("Sampler cancelled the dump at packet {}", index)));
+                    }
+                    // Wait holds the transfer until the sampler sends its next reply
+                    Some(Reply::Wait) => match wait_for_reply(rx, device_id, Duration::from_secs(60)) {
+                        Some(Reply::Nak(_)) => retries += 1,
+                        Some(Reply::Cancel) => {
+                            return Err(BatcherbirdError::Session(format!("Sampler cancelled the dump at packet {}", index)));
+                        }
+                        _ => break,
+                    },
+                    None => {
+                        warn!("   ⚠️ Sampler stopped answering at packet {}, continuing open loop", index);
+                        handshake = None;
+                        break;
+                    }
+                }
+            }
+            if (index + 1) % 100 == 0 {
+                info!("   📦 {}/{} packets", index + 1, self.packets.len());
+            }
+        }
+
+        info!("✅ SDS dump sent");
+        Ok(())
+    }
+}
+
+/// 21-bit value as three 7-bit bytes, least significant first
+fn seven_bit_triplet(value: u32) -> [u8; 3] {
+    let value = value.min(MAX_21_BIT);
+    [(value & 0x7F) as u8, ((value >> 7) & 0x7F) as u8, ((value >> 14) & 0x7F) as u8]
+}
+
+/// One sample as an unsigned (offset binary) word, left-justified across 7-bit bytes
+fn encode_word(sample: f32, bits: u8) -> Vec<u8> {
+    let bytes = (bits as usize).div_ceil(7);
+    let half = 1i64 << (bits - 1);
+    let value = ((sample.clamp(-1.0, 1.0) as f64 * (half - 1) as f64).round() as i64 + half) as u64;
+    let justified = value << (bytes * 7 - bits as usize);
+    (0..bytes).rev().map(|i| ((justified >> (7 * i)) & 0x7F) as u8).collect()
+}
+
+/// XOR of everything between F0 and the checksum, as a 7-bit value
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |sum, byte| sum ^ byte) & 0x7F
+}
+
+fn parse_reply(message: &[u8], device_id: u8) -> Option<Reply> {
+    match message {
+        [0xF0, NON_REALTIME, id, kind, packet, 0xF7] if *id == device_id => match *kind {
+            ACK => Some(Reply::Ack(*packet)),
+            NAK => Some(Reply::Nak(*packet)),
+            CANCEL => Some(Reply::Cancel),
+            WAIT => Some(Reply::Wait),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn wait_for_reply(rx: &mpsc::Receiver<Vec<u8>>, device_id: u8, timeout: Duration) -> Option<Reply> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let message = rx.recv_timeout(deadline.saturating_duration_since(Instant::now())).ok()?;
+        if let Some(reply) = parse_reply(&message, device_id) {
+            return Some(reply);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_dump_encoding() {
+        let config = SdsConfig { device_id: 1, sample_number: 130, bits: 16 };
+        let audio: Vec<f32> = (0..100).map(|i| if i % 2 == 0 { 1.0 } else { -1.0 }).collect();
+        let dump = SampleDump::from_audio(&audio, 44100, Some(LoopPoints { start: 10, end: 90 }), &config).unwrap();
+
+        // Header: slot 130 = 02 01, period 22675ns, 100 words, loop 10-89 forward
+        assert_eq!(dump.header, vec![
+            0xF0, 0x7E, 0x01, 0x01, 0x02, 0x01, 16,
+            0x13, 0x31, 0x01, 100, 0, 0, 10, 0, 0, 89, 0, 0,
+            0x00, 0xF7,
+        ]);
+
+        // 16-bit words take 3 bytes, 40 per packet
+        assert_eq!(dump.packets.len(), 3);
+        let first = &dump.packets[0];
+        assert_eq!(first.len(), 127);
+        assert_eq!(&first[..5], &[0xF0, 0x7E, 0x01, 0x02, 0x00]);
+        // Full scale 0xFFFF and -full scale 0x0001, left-justified into 21 bits
+        assert_eq!(&first[5..11], &[0x7F, 0x7F, 0x60, 0x00, 0x00, 0x20]);
+        assert_eq!(first[125], checksum(&first[1..125]));
+        assert_eq!(dump.packets[2][4], 2);
+        assert!(dump.packets.iter().flatten().all(|&b| b < 0x80 || b == 0xF0 || b == 0xF7));
+
+        assert!(SampleDump::from_audio(&audio, 44100, None, &SdsConfig { bits: 6, ..config }).is_err());
+        assert_eq!(parse_reply(&[0xF0, 0x7E, 0x01, 0x7E, 0x05, 0xF7], 1), Some(Reply::Nak(5)));
+        assert_eq!(parse_reply(&[0xF0, 0x7E, 0x02, 0x7F, 0x05, 0xF7], 1), None);
+    }
+}
@@ -27,6 +27,12 @@ pub struct SessionManager {
     current_session: Option<Session>,
 }
 
+impl Default for SessionManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SessionManager {
     pub fn new() -> Self {
         Self {
@@ -0,0 +1,134 @@
+use crate::envelope::AdsrEnvelope;
+use crate::pitch::mixdown;
+use crate::sampler::{Sample, SampleMetadata};
+use std::time::Duration;
+use tracing::warn;
+
+/// Where a note was played in a long take, in frames from the start of the recording
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoteMark {
+    pub note: u8,
+    pub velocity: u8,
+    pub note_on_frame: usize,
+    pub note_off_frame: usize,
+}
+
+/// How notes are found in a long take
+#[derive(Debug, Clone)]
+pub struct SliceConfig {
+    /// How long after the note-on to look for the sound starting (covers driver and synth latency)
+    pub search_ms: f32,
+    /// Level the sound must reach to count as started (dBFS)
+    pub threshold_db: f32,
+    /// Audio kept before the detected onset (never reaching back before the note-on)
+    pub pre_roll_ms: f32,
+}
+
+impl Default for SliceConfig {
+    fn default() -> Self {
+        Self { search_ms: 250.0, threshold_db: -45.0, pre_roll_ms: 2.0 }
+    }
+}
+
+/// RMS window used to find onsets (ms)
+const ONSET_WINDOW_MS: f32 = 1.0;
+
+/// Cut a long take into one sample per mark. Each slice starts at the onset found after its
+/// note-on (or at the note-on when none is found) and runs up to the next note-on, so it
+/// holds the full release.
+pub fn slice_long_take(take: &Sample, marks: &[NoteMark], config: &SliceConfig) -> Vec<Sample> {
+    let channels = take.channels.max(1) as usize;
+    let frames = take.audio_data.len() / channels;
+    let mono = mixdown(&take.audio_data, take.channels);
+    let to_frames = |ms: f32| (ms.max(0.0) / 1000.0 * take.sample_rate as f32) as usize;
+
+    marks.iter().enumerate()
+        .filter(|(_, mark)| mark.note_on_frame < frames)
+        .map(|(index, mark)| {
+            let end = marks.get(index + 1).map_or(frames, |next| next.note_on_frame.min(frames));
+            let search_end = (mark.note_on_frame + to_frames(config.search_ms)).min(end);
+            let onset = find_onset(&mono[mark.note_on_frame..search_end], to_frames(ONSET_WINDOW_MS).max(1), config.threshold_db)
+                .map(|offset| mark.note_on_frame + offset);
+            if onset.is_none() {
+                warn!("   ⚠️ No onset found for note {} vel {}; slicing at the note-on", mark.note, mark.velocity);
+            }
+            let start = onset
+                .map_or(mark.note_on_frame, |onset| onset.saturating_sub(to_frames(config.pre_roll_ms)).max(mark.note_on_frame))
+                .min(end.saturating_sub(1));
+
+            let audio_data = take.audio_data[start * channels..end * channels].to_vec();
+            let envelope = AdsrEnvelope::estimate(&audio_data, take.channels, take.sample_rate);
+            let seconds = |frames: usize| Duration::from_secs_f64(frames as f64 / take.sample_rate.max(1) as f64);
+            Sample {
+                note: mark.note,
+                velocity: mark.velocity,
+                audio_data,
+                sample_rate: take.sample_rate,
+                channels: take.channels,
+                recorded_at: take.recorded_at,
+                midi_timing: seconds(mark.note_off_frame.saturating_sub(mark.note_on_frame)),
+                audio_timing: seconds(end - start),
+                metadata: SampleMetadata {
+                    envelope,
+                    onset_latency_ms: onset.map(|onset| (onset - mark.note_on_frame) as f32 * 1000.0 / take.sample_rate.max(1) as f32),
+                    ..take.metadata.clone()
+                },
+            }
+        })
+        .collect()
+}
+
+/// Offset of the first window whose RMS reaches `threshold_db`
+pub(crate) fn find_onset(mono: &[f32], window: usize, threshold_db: f32) -> Option<usize> {
+    let threshold = 10f32.powf(threshold_db / 20.0);
+    mono.chunks(window)
+        .position(|chunk| (chunk.iter().map(|s| s * s).sum::<f32>() / chunk.len() as f32).sqrt() >= threshold)
+        .map(|index| {
+            // Refine to the first sample in the window that crosses the threshold
+            let at = index * window;
+            at + mono[at..].iter().position(|s| s.abs() >= threshold).unwrap_or(0).min(window)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slice_long_take_at_onsets() {
+        // Three notes, each sounding 30ms after its note-on (latency) for 0.5s, one second apart
+        let rate = 10000;
+        let mut audio = vec![0.0f32; rate * 3];
+        let marks: Vec<NoteMark> = (0..3)
+            .map(|i| NoteMark { note: 60 + i as u8, velocity: 100, note_on_frame: i * rate, note_off_frame: i * rate + rate / 2 })
+            .collect();
+        for mark in &marks {
+            for frame in 0..rate / 2 {
+                audio[mark.note_on_frame + 300 + frame] = 0.5 * (frame as f32 * 0.3 + 1.0).sin();
+            }
+        }
+        // Stereo, to check frames are kept whole
+        let stereo: Vec<f32> = audio.iter().flat_map(|&s| [s, s]).collect();
+        let take = Sample {
+            note: 60,
+            velocity: 100,
+            audio_data: stereo,
+            sample_rate: rate as u32,
+            channels: 2,
+            recorded_at: std::time::SystemTime::now(),
+            midi_timing: Duration::ZERO,
+            audio_timing: Duration::ZERO,
+            metadata: Default::default(),
+        };
+
+        let slices = slice_long_take(&take, &marks, &SliceConfig::default());
+        assert_eq!(slices.iter().map(|s| s.note).collect::<Vec<_>>(), vec![60, 61, 62]);
+        for slice in &slices {
+            // Pre-roll of 2ms (20 frames) before the onset, up to the next note-on
+            assert_eq!(slice.audio_data.len(), (rate - 300 + 20) * 2, "note {}", slice.note);
+            assert!(slice.audio_data[..40].iter().all(|&s| s == 0.0));
+            assert!(slice.audio_data[40..60].iter().any(|&s| s != 0.0));
+            assert_eq!(slice.midi_timing, Duration::from_millis(500));
+        }
+    }
+}
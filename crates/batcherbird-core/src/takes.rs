@@ -0,0 +1,198 @@
+use crate::detection::DetectionConfig;
+use crate::pitch::mixdown;
+use crate::qa::clip_runs;
+use crate::sampler::Sample;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::info;
+
+/// Length of the windows used to find the noise floor (ms)
+const NOISE_WINDOW_MS: f32 = 10.0;
+/// Share of the quietest windows averaged into the noise floor
+const NOISE_FLOOR_FRACTION: f32 = 0.1;
+/// Consecutive full-scale samples that count as one clip
+const CLIP_RUN: usize = 3;
+
+/// Position of a take among the takes recorded for one note
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TakeInfo {
+    /// 1-based take index
+    pub index: u8,
+    /// Overall score from 0 to 1 (filled in by `select_takes`)
+    pub score: f32,
+    /// Whether this take was picked for the instrument
+    pub best: bool,
+}
+
+impl TakeInfo {
+    /// A freshly recorded, not yet scored take
+    pub fn new(index: u8) -> Self {
+        Self { index, score: 0.0, best: false }
+    }
+}
+
+/// What to keep of a note's takes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TakeSelection {
+    /// Keep only the highest scoring take
+    #[default]
+    KeepBest,
+    /// Keep every take; the best one is mapped in the instrument, the others get a take token
+    KeepAll,
+}
+
+/// Why a take scored as it did. Each part runs from 0 (bad) to 1 (good).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TakeScore {
+    /// Peak level: full marks from -12 to -0.5 dBFS
+    pub level: f32,
+    /// Distance from the noise floor to the peak
+    pub noise: f32,
+    /// Fewer clipped runs is better
+    pub clipping: f32,
+    /// How clearly the detector found the sound above its threshold
+    pub confidence: f32,
+    pub total: f32,
+}
+
+impl TakeScore {
+    pub fn score(sample: &Sample, detection: &DetectionConfig) -> Self {
+        let mono = mixdown(&sample.audio_data, sample.channels);
+        let peak_db = to_db(mono.iter().fold(0.0f32, |max, s| max.max(s.abs())));
+
+        let level = if peak_db > -0.5 {
+            0.8
+        } else if peak_db >= -12.0 {
+            1.0
+        } else {
+            ((peak_db + 48.0) / 36.0).clamp(0.0, 1.0)
+        };
+
+        let noise = ((peak_db - noise_floor_db(&mono, sample.sample_rate)) / 60.0).clamp(0.0, 1.0);
+
+        let clipping = 1.0 / (1.0 + clip_runs(&sample.audio_data, sample.channels, CLIP_RUN) as f32);
+
+        let confidence = match detection.detector().detect_boundaries(&mono, sample.sample_rate) {
+            Ok(result) if result.success => {
+                let loudest = result.rms_values.iter().fold(0.0f32, |max, &rms| max.max(rms));
+                ((to_db(loudest) - result.threshold_db) / 30.0).clamp(0.0, 1.0)
+            }
+            _ => 0.0,
+        };
+
+        let total = 0.3 * level + 0.25 * noise + 0.3 * clipping + 0.15 * confidence;
+        Self { level, noise, clipping, confidence, total }
+    }
+}
+
+/// Score every multi-take sample and keep the best of each note (or all, per `selection`).
+/// Samples without take info pass through unchanged; order is kept.
+pub fn select_takes(samples: Vec<Sample>, selection: TakeSelection, detection: &DetectionConfig) -> Vec<Sample> {
+    let mut samples = samples;
+    let mut best: HashMap<String, (usize, f32)> = HashMap::new();
+    for (index, sample) in samples.iter_mut().enumerate() {
+        if sample.metadata.take.is_none() {
+            continue;
+        }
+        let score = TakeScore::score(sample, detection);
+        if let Some(take) = sample.metadata.take.as_mut() {
+            take.score = score.total;
+            info!("   🎬 {} vel {} take {}: {:.2} (level {:.2}, noise {:.2}, clipping {:.2}, confidence {:.2})",
+                sample.note, sample.velocity, take.index, score.total, score.level, score.noise, score.clipping, score.confidence);
+        }
+
+        let entry = best.entry(slot_key(sample)).or_insert((index, f32::MIN));
+        if score.total > entry.1 {
+            *entry = (index, score.total);
+        }
+    }
+
+    let chosen: Vec<usize> = best.values().map(|&(index, _)| index).collect();
+    for &index in &chosen {
+        if let Some(take) = samples[index].metadata.take.as_mut() {
+            take.best = true;
+        }
+    }
+    info!("🏆 Picked the best take for {} notes", chosen.len());
+
+    match selection {
+        TakeSelection::KeepAll => samples,
+        TakeSelection::KeepBest => samples.into_iter()
+            .filter(|sample| sample.metadata.take.is_none_or(|take| take.best))
+            .collect(),
+    }
+}
+
+/// Takes compete with the other takes of the same note, velocity and layer
+fn slot_key(sample: &Sample) -> String {
+    let metadata = &sample.metadata;
+    format!("{}:{}:{:?}:{:?}:{:?}:{:?}:{:?}:{}",
+        sample.note, sample.velocity, metadata.cc_layer, metadata.program, metadata.midi_channel,
+        metadata.round_robin, metadata.legato_from, metadata.release)
+}
+
+/// Average level of the quietest windows, in dBFS
+fn noise_floor_db(mono: &[f32], sample_rate: u32) -> f32 {
+    let window = ((NOISE_WINDOW_MS / 1000.0) * sample_rate as f32).max(1.0) as usize;
+    let mut levels: Vec<f32> = mono.chunks_exact(window)
+        .map(|chunk| (chunk.iter().map(|s| s * s).sum::<f32>() / chunk.len() as f32).sqrt())
+        .collect();
+    if levels.is_empty() {
+        return -96.0;
+    }
+    levels.sort_by(f32::total_cmp);
+    let quietest = ((levels.len() as f32 * NOISE_FLOOR_FRACTION).ceil() as usize).max(1);
+    to_db(levels[..quietest].iter().sum::<f32>() / quietest as f32)
+}
+
+fn to_db(level: f32) -> f32 {
+    if level > 0.0 { 20.0 * level.log10() } else { -96.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_takes_prefers_clean_takes() {
+        let rate = 44100;
+        let take = |index: u8, audio_data: Vec<f32>| Sample {
+            note: 60,
+            velocity: 100,
+            audio_data,
+            sample_rate: rate,
+            channels: 1,
+            recorded_at: std::time::SystemTime::now(),
+            midi_timing: std::time::Duration::ZERO,
+            audio_timing: std::time::Duration::ZERO,
+            metadata: crate::sampler::SampleMetadata { take: Some(TakeInfo::new(index)), ..Default::default() },
+        };
+        let tone = |level: f32, hiss: f32| -> Vec<f32> {
+            let mut audio = vec![0.0f32; rate as usize / 10];
+            audio.extend((0..rate as usize).map(|i| level * (2.0 * std::f32::consts::PI * 261.6 * i as f32 / rate as f32).sin()));
+            audio.extend(vec![0.0f32; rate as usize / 5]);
+            // Deterministic pseudo-noise over the whole take
+            for (i, sample) in audio.iter_mut().enumerate() {
+                *sample += hiss * (((i as u32).wrapping_mul(2654435761) >> 16) as f32 / 32768.0 - 1.0);
+            }
+            audio
+        };
+
+        let clipped: Vec<f32> = tone(1.6, 0.0).into_iter().map(|s| s.clamp(-1.0, 1.0)).collect();
+        let samples = vec![take(1, clipped), take(2, tone(0.5, 0.0005)), take(3, tone(0.5, 0.05))];
+        let detection = DetectionConfig::default();
+
+        let scores: Vec<f32> = samples.iter().map(|s| TakeScore::score(s, &detection).total).collect();
+        assert!(scores[1] > scores[0] && scores[1] > scores[2], "scores {:?}", scores);
+
+        let kept = select_takes(samples.clone(), TakeSelection::KeepBest, &detection);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].metadata.take.map(|t| (t.index, t.best)), Some((2, true)));
+
+        let all = select_takes(samples, TakeSelection::KeepAll, &detection);
+        let best: Vec<bool> = all.iter().map(|s| s.metadata.take.unwrap().best).collect();
+        assert_eq!(best, vec![false, true, false]);
+        assert!(all.iter().all(|s| s.metadata.take.unwrap().score > 0.0));
+    }
+}
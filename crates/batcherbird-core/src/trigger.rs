@@ -0,0 +1,156 @@
+use crate::{Result, BatcherbirdError};
+use serde::{Deserialize, Serialize};
+
+/// What a footswitch / controller press does during an assisted session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerAction {
+    /// Start the next capture, or finish the one that is recording
+    Next,
+    /// Discard the running or last capture and record it again
+    Retake,
+    /// End the session, keeping what has been captured
+    Abort,
+}
+
+impl TriggerAction {
+    fn parse(name: &str) -> Result<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "next" | "start" => Ok(Self::Next),
+            "retake" | "redo" => Ok(Self::Retake),
+            "abort" | "stop" => Ok(Self::Abort),
+            other => Err(BatcherbirdError::Config(format!(
+                "Unknown trigger action '{}', expected next, retake or abort", other
+            ))),
+        }
+    }
+}
+
+/// The MIDI message a trigger listens for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerSource {
+    /// Control change, pressed at value 64 and above (sustain pedal style footswitches)
+    ControlChange { controller: u8 },
+    /// Note-on from a pad or key
+    Note { note: u8 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TriggerBinding {
+    pub source: TriggerSource,
+    /// 0-indexed MIDI channel (None = any channel)
+    pub channel: Option<u8>,
+    pub action: TriggerAction,
+}
+
+/// Bindings from controller messages to session actions, e.g. "cc64=next,cc67=retake,note36/10=abort"
+#[derive(Debug, Clone, Default)]
+pub struct TriggerMap {
+    bindings: Vec<TriggerBinding>,
+    /// Last pressed state per binding, so a held pedal fires once
+    pressed: Vec<bool>,
+}
+
+impl TriggerMap {
+    pub fn new(bindings: Vec<TriggerBinding>) -> Self {
+        let pressed = vec![false; bindings.len()];
+        Self { bindings, pressed }
+    }
+
+    /// Parse `source=action` entries. Sources are `ccN` or `noteN`, optionally followed
+    /// by `/channel` (1-16).
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut bindings = Vec::new();
+        for entry in spec.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (source, action) = entry.split_once('=').ok_or_else(|| {
+                BatcherbirdError::Config(format!("Invalid trigger '{}', expected source=action", entry))
+            })?;
+            let (source, channel) = match source.trim().split_once('/') {
+                Some((source, channel)) => (source, Some(Self::parse_channel(channel)?)),
+                None => (source.trim(), None),
+            };
+            let lower = source.to_lowercase();
+            let source = if let Some(number) = lower.strip_prefix("cc") {
+                TriggerSource::ControlChange { controller: Self::parse_data_byte(number, entry)? }
+            } else if let Some(number) = lower.strip_prefix("note") {
+                TriggerSource::Note { note: Self::parse_data_byte(number, entry)? }
+            } else {
+                return Err(BatcherbirdError::Config(format!(
+                    "Invalid trigger source in '{}', expected ccN or noteN", entry
+                )));
+            };
+            bindings.push(TriggerBinding { source, channel, action: TriggerAction::parse(action)? });
+        }
+        Ok(Self::new(bindings))
+    }
+
+    fn parse_data_byte(number: &str, entry: &str) -> Result<u8> {
+        number.trim().parse::<u8>().ok().filter(|value| *value <= 127).ok_or_else(|| {
+            BatcherbirdError::Config(format!("Invalid controller or note number in '{}'", entry))
+        })
+    }
+
+    fn parse_channel(channel: &str) -> Result<u8> {
+        match channel.trim().parse::<u8>() {
+            Ok(channel @ 1..=16) => Ok(channel - 1),
+            _ => Err(BatcherbirdError::Config(format!("Invalid trigger channel '{}', expected 1-16", channel))),
+        }
+    }
+
+    pub fn bindings(&self) -> &[TriggerBinding] {
+        &self.bindings
+    }
+
+    /// Action for an incoming MIDI message, firing once per press
+    pub fn action_for(&mut self, message: &[u8]) -> Option<TriggerAction> {
+        let (&status, data) = message.split_first()?;
+        let (&number, &value) = (data.first()?, data.get(1)?);
+        let channel = status & 0x0F;
+
+        for (binding, was_pressed) in self.bindings.iter().zip(self.pressed.iter_mut()) {
+            if binding.channel.is_some_and(|bound| bound != channel) {
+                continue;
+            }
+            let pressed = match (binding.source, status & 0xF0) {
+                (TriggerSource::ControlChange { controller }, 0xB0) if controller == number => value >= 64,
+                (TriggerSource::Note { note }, 0x90) if note == number => value > 0,
+                (TriggerSource::Note { note }, 0x80) if note == number => false,
+                _ => continue,
+            };
+            let fired = pressed && !*was_pressed;
+            *was_pressed = pressed;
+            if fired {
+                return Some(binding.action);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trigger_map_fires_once_per_press() {
+        let mut map = TriggerMap::parse("cc64=next, note36/10=abort, CC67=retake").unwrap();
+        assert_eq!(map.bindings().len(), 3);
+
+        // Pedal down fires, repeated "down" values while held don't, release re-arms
+        assert_eq!(map.action_for(&[0xB0, 64, 127]), Some(TriggerAction::Next));
+        assert_eq!(map.action_for(&[0xB0, 64, 100]), None);
+        assert_eq!(map.action_for(&[0xB0, 64, 0]), None);
+        assert_eq!(map.action_for(&[0xB3, 64, 127]), Some(TriggerAction::Next));
+        assert_eq!(map.action_for(&[0xB0, 67, 127]), Some(TriggerAction::Retake));
+
+        // Channel-bound note only fires on channel 10
+        assert_eq!(map.action_for(&[0x90, 36, 100]), None);
+        assert_eq!(map.action_for(&[0x99, 36, 100]), Some(TriggerAction::Abort));
+
+        assert!(TriggerMap::parse("cc128=next").is_err());
+        assert!(TriggerMap::parse("pitch=next").is_err());
+        assert!(TriggerMap::parse("cc64=jump").is_err());
+        assert!(TriggerMap::parse("cc64/17=next").is_err());
+    }
+}
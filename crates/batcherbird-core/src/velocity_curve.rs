@@ -0,0 +1,257 @@
+use crate::{Result, BatcherbirdError};
+use crate::sampler::Sample;
+use serde::{Deserialize, Serialize};
+
+/// Velocities probed when measuring a synth's velocity response
+pub const DEFAULT_PROBE_VELOCITIES: [u8; 8] = [1, 16, 32, 48, 64, 80, 100, 127];
+
+/// Remaps requested velocities before they are sent as note-on.
+///
+/// Many synths respond non-linearly, so evenly spaced layers (e.g. 32/64/96/127)
+/// can sound bunched together. The curve is applied on send only - samples keep the
+/// requested velocity so file names and instrument mapping stay evenly spaced.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub enum VelocityCurve {
+    /// Send velocities unchanged
+    #[default]
+    Linear,
+    /// out = 127 * (in/127)^exponent - exponent > 1 sends softer values
+    Exponential(f32),
+    /// Inverse of Exponential - exponent > 1 sends harder values
+    Logarithmic(f32),
+    /// Explicit lookup table: index = requested velocity (0-127), value = sent velocity
+    Table(Vec<u8>),
+}
+
+impl VelocityCurve {
+    /// Velocity to actually send for a requested velocity.
+    /// Never maps a non-zero velocity to 0 (that would be a note-off).
+    pub fn apply(&self, velocity: u8) -> u8 {
+        let velocity = velocity.min(127);
+        if velocity == 0 {
+            return 0;
+        }
+
+        let mapped = match self {
+            VelocityCurve::Linear => velocity,
+            VelocityCurve::Exponential(exponent) => Self::power(velocity, *exponent),
+            VelocityCurve::Logarithmic(exponent) => Self::power(velocity, 1.0 / exponent.max(f32::EPSILON)),
+            VelocityCurve::Table(table) => table.get(velocity as usize).copied().unwrap_or(velocity),
+        };
+
+        mapped.clamp(1, 127)
+    }
+
+    /// Parse a curve spec: "linear", "exp:2.0", "log:2.0" or
+    /// "custom:0=0,64=40,127=127" (points are linearly interpolated).
+    pub fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        let (kind, args) = spec.split_once(':').unwrap_or((spec, ""));
+
+        let parse_exponent = |args: &str| -> Result<f32> {
+            let exponent: f32 = if args.is_empty() {
+                2.0
+            } else {
+                args.trim().parse()
+                    .map_err(|_| BatcherbirdError::Config(format!("Invalid velocity curve exponent: {}", args)))?
+            };
+            if !(exponent > 0.0 && exponent.is_finite()) {
+                return Err(BatcherbirdError::Config(format!("Velocity curve exponent must be positive: {}", exponent)));
+            }
+            Ok(exponent)
+        };
+
+        match kind.to_lowercase().as_str() {
+            "linear" | "" => Ok(VelocityCurve::Linear),
+            "exp" | "exponential" => Ok(VelocityCurve::Exponential(parse_exponent(args)?)),
+            "log" | "logarithmic" => Ok(VelocityCurve::Logarithmic(parse_exponent(args)?)),
+            "custom" | "table" => Self::from_points(&Self::parse_points(args)?),
+            _ => Err(BatcherbirdError::Config(format!(
+                "Unknown velocity curve '{}'. Use linear, exp:N, log:N or custom:in=out,...", kind
+            ))),
+        }
+    }
+
+    /// Build a full 128-entry table from (requested, sent) points, interpolating between them
+    pub fn from_points(points: &[(u8, u8)]) -> Result<Self> {
+        let mut points: Vec<(u8, u8)> = points.iter().map(|&(i, o)| (i.min(127), o.min(127))).collect();
+        points.sort_by_key(|&(input, _)| input);
+        points.dedup_by_key(|&mut (input, _)| input);
+
+        if points.is_empty() {
+            return Err(BatcherbirdError::Config("Custom velocity curve needs at least one point".to_string()));
+        }
+
+        let table = (0..=127u8)
+            .map(|velocity| {
+                let after = points.iter().position(|&(input, _)| input >= velocity);
+                match after {
+                    Some(0) => points[0].1,
+                    Some(i) => {
+                        let (x0, y0) = points[i - 1];
+                        let (x1, y1) = points[i];
+                        let t = (velocity - x0) as f32 / (x1 - x0) as f32;
+                        (y0 as f32 + t * (y1 as f32 - y0 as f32)).round() as u8
+                    }
+                    None => points[points.len() - 1].1,
+                }
+            })
+            .collect();
+
+        Ok(VelocityCurve::Table(table))
+    }
+
+    fn parse_points(args: &str) -> Result<Vec<(u8, u8)>> {
+        args.split(',')
+            .map(str::trim)
+            .filter(|point| !point.is_empty())
+            .map(|point| {
+                let parsed = point.split_once('=').and_then(|(input, output)| {
+                    Some((input.trim().parse::<u8>().ok()?, output.trim().parse::<u8>().ok()?))
+                });
+                match parsed {
+                    Some((input, output)) if input <= 127 && output <= 127 => Ok((input, output)),
+                    _ => Err(BatcherbirdError::Config(format!("Invalid velocity curve point '{}', expected in=out (0-127)", point))),
+                }
+            })
+            .collect()
+    }
+
+    fn power(velocity: u8, exponent: f32) -> u8 {
+        (127.0 * (velocity as f32 / 127.0).powf(exponent)).round() as u8
+    }
+}
+
+/// Measured loudness per velocity for one note
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VelocityResponse {
+    pub note: u8,
+    /// (velocity, loudness in dBFS) sorted by velocity
+    pub points: Vec<(u8, f32)>,
+}
+
+impl VelocityResponse {
+    /// Build a response from probe captures of the same note at different velocities
+    pub fn from_samples(samples: &[Sample]) -> Result<Self> {
+        let note = samples.first()
+            .map(|s| s.note)
+            .ok_or_else(|| BatcherbirdError::Processing("No probe samples to measure".to_string()))?;
+
+        let mut points: Vec<(u8, f32)> = samples.iter()
+            .map(|sample| (sample.velocity, Self::loudness_db(sample)))
+            .collect();
+        points.sort_by_key(|&(velocity, _)| velocity);
+        points.dedup_by_key(|&mut (velocity, _)| velocity);
+
+        if points.len() < 2 {
+            return Err(BatcherbirdError::Processing("Velocity response needs at least two probe velocities".to_string()));
+        }
+
+        Ok(Self { note, points })
+    }
+
+    /// Choose `layers` velocities whose output loudness is evenly spaced between the
+    /// quietest and loudest probe, instead of evenly spaced velocity numbers.
+    pub fn place_layers(&self, layers: usize) -> Vec<u8> {
+        if layers == 0 || self.points.is_empty() {
+            return Vec::new();
+        }
+
+        // Loudness should never drop as velocity rises; flatten measurement noise
+        let mut monotonic = self.points.clone();
+        for i in 1..monotonic.len() {
+            if monotonic[i].1 < monotonic[i - 1].1 {
+                monotonic[i].1 = monotonic[i - 1].1;
+            }
+        }
+
+        let (min_velocity, quietest) = monotonic[0];
+        let (max_velocity, loudest) = monotonic[monotonic.len() - 1];
+        if layers == 1 || loudest - quietest < 0.5 {
+            // No usable dynamic range: fall back to even velocity spacing
+            return (1..=layers)
+                .map(|i| (min_velocity as f32 + (max_velocity - min_velocity) as f32 * i as f32 / layers as f32).round() as u8)
+                .collect();
+        }
+
+        // Top layer is always the loudest; lower layers split the dB range evenly
+        let mut velocities: Vec<u8> = (1..=layers)
+            .map(|i| {
+                let target = quietest + (loudest - quietest) * i as f32 / layers as f32;
+                Self::velocity_for_loudness(&monotonic, target)
+            })
+            .collect();
+
+        velocities.dedup();
+        velocities
+    }
+
+    /// Inverse lookup: smallest velocity reaching `target` dB, interpolated between probes
+    fn velocity_for_loudness(points: &[(u8, f32)], target: f32) -> u8 {
+        for pair in points.windows(2) {
+            let (v0, db0) = pair[0];
+            let (v1, db1) = pair[1];
+            if target <= db1 {
+                if db1 - db0 <= f32::EPSILON {
+                    return v1;
+                }
+                let t = ((target - db0) / (db1 - db0)).clamp(0.0, 1.0);
+                return (v0 as f32 + t * (v1 - v0) as f32).round().clamp(1.0, 127.0) as u8;
+            }
+        }
+        points[points.len() - 1].0
+    }
+
+    /// Loudest 50ms RMS window - robust to different note lengths and release tails
+    fn loudness_db(sample: &Sample) -> f32 {
+        let window = ((sample.sample_rate as usize * sample.channels.max(1) as usize) / 20).max(1);
+        let loudest = sample.audio_data
+            .chunks(window)
+            .map(|chunk| (chunk.iter().map(|s| s * s).sum::<f32>() / chunk.len() as f32).sqrt())
+            .fold(0.0f32, f32::max);
+
+        if loudest > 0.0 { 20.0 * loudest.log10() } else { -96.0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_velocity_curves() {
+        assert_eq!(VelocityCurve::Linear.apply(64), 64);
+
+        // Endpoints are fixed, the middle bends
+        let exp = VelocityCurve::parse("exp:2").unwrap();
+        assert_eq!(exp.apply(127), 127);
+        assert!(exp.apply(64) < 64);
+        assert_eq!(exp.apply(1), 1); // Never turned into a note-off
+
+        let log = VelocityCurve::parse("log:2").unwrap();
+        assert!(log.apply(64) > 64);
+
+        let custom = VelocityCurve::parse("custom:0=0,64=40,127=127").unwrap();
+        assert_eq!(custom.apply(64), 40);
+        assert_eq!(custom.apply(32), 20);
+        assert_eq!(custom.apply(127), 127);
+
+        assert!(VelocityCurve::parse("wobbly").is_err());
+    }
+
+    #[test]
+    fn test_layer_placement_follows_loudness() {
+        // Synth that is nearly silent below 64 and jumps in level above it
+        let response = VelocityResponse {
+            note: 60,
+            points: vec![(1, -40.0), (64, -36.0), (100, -12.0), (127, -4.0)],
+        };
+        let layers = response.place_layers(4);
+
+        assert_eq!(layers.len(), 4);
+        assert_eq!(*layers.last().unwrap(), 127);
+        // Naive spacing would waste layers in the flat region below 64
+        assert!(layers[0] > 64);
+        assert!(layers.windows(2).all(|w| w[0] < w[1]));
+    }
+}
@@ -0,0 +1,247 @@
+use crate::{BatcherbirdError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+/// Slack added to the expected length of a take before it counts as hung
+const NOTE_MARGIN: Duration = Duration::from_secs(5);
+/// Audio/wall clock divergence that counts as drift when none is configured. Crystals agree
+/// to well under 100 ppm; past this the interface runs at another rate than it reports or
+/// is dropping buffers.
+const DEFAULT_MAX_CLOCK_DRIFT_PPM: f32 = 2000.0;
+/// Takes the drift is measured over: long enough for per-take buffer jitter to average out,
+/// short enough to catch a clock that wanders hours into a run
+const CLOCK_WINDOW: Duration = Duration::from_secs(600);
+/// Recorded time needed before drift is judged
+const MIN_CLOCK_WINDOW: Duration = Duration::from_secs(120);
+
+/// Wall-time limits for a sampling batch
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WatchdogConfig {
+    /// Longest a single note may take (None = twice its expected length plus 5s)
+    pub max_note_secs: Option<f32>,
+    /// Longest the whole batch may take (None = unlimited)
+    pub max_batch_secs: Option<f32>,
+    /// Stop the batch at the first hung note instead of skipping it
+    pub abort_on_timeout: bool,
+    /// Divergence between captured frames and wall time, in ppm, that is reported as clock
+    /// drift (None = 2000)
+    #[serde(default)]
+    pub max_clock_drift_ppm: Option<f32>,
+    /// Reopen the capture stream when the clocks drift apart instead of only warning
+    #[serde(default)]
+    pub resync_on_drift: bool,
+}
+
+/// A note the watchdog gave up on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailedNote {
+    pub note: u8,
+    pub velocity: u8,
+    pub reason: String,
+}
+
+/// Supervises the notes of one batch. A capture that overruns its limit is cancelled at
+/// its next await point; the caller then tears down the stream and silences the synth.
+#[derive(Debug)]
+pub struct BatchWatchdog {
+    config: WatchdogConfig,
+    note_limit: Duration,
+    started: Instant,
+    failed: Vec<FailedNote>,
+    clock: ClockDriftMonitor,
+    /// Times the clocks were found apart
+    drift_events: usize,
+}
+
+impl BatchWatchdog {
+    /// `expected_note` is how long a take should normally last (pre-roll to post-delay)
+    pub fn new(config: WatchdogConfig, expected_note: Duration) -> Self {
+        let note_limit = config.max_note_secs
+            .map(Duration::from_secs_f32)
+            .unwrap_or(expected_note * 2 + NOTE_MARGIN);
+        Self { config, note_limit, started: Instant::now(), failed: Vec::new(), clock: ClockDriftMonitor::default(), drift_events: 0 }
+    }
+
+    /// Run one note's capture. Ok(None) means it timed out and was cancelled.
+    pub async fn supervise<T>(&mut self, note: u8, velocity: u8, capture: impl Future<Output = Result<T>>) -> Result<Option<T>> {
+        let limit = match self.batch_remaining() {
+            Some(remaining) if remaining.is_zero() => return Err(self.batch_expired()),
+            Some(remaining) => remaining.min(self.note_limit),
+            None => self.note_limit,
+        };
+
+        match tokio::time::timeout(limit, capture).await {
+            Ok(result) => result.map(Some),
+            Err(_) => {
+                let reason = format!("no result after {:.1}s", limit.as_secs_f32());
+                info!("⏰ Watchdog: note {} vel {} timed out ({})", note, velocity, reason);
+                self.failed.push(FailedNote { note, velocity, reason });
+                Ok(None)
+            }
+        }
+    }
+
+    /// Whether the batch may go on after a timed-out note
+    pub fn check_continue(&self) -> Result<()> {
+        if self.config.abort_on_timeout {
+            return Err(BatcherbirdError::Session("Batch aborted by watchdog after a hung note".to_string()));
+        }
+        match self.batch_remaining() {
+            Some(remaining) if remaining.is_zero() => Err(self.batch_expired()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Compare a take's `frames` at `sample_rate` with the `wall` time it ran. Warns when the
+    /// audio and wall clocks have drifted apart over the recent takes; returns true when the
+    /// caller should reopen the capture stream to re-sync (`resync_on_drift`).
+    pub fn track_clock(&mut self, frames: usize, sample_rate: u32, wall: Duration) -> bool {
+        if sample_rate == 0 || wall.is_zero() {
+            return false;
+        }
+        let Some(ppm) = self.clock.record(frames as f64 / sample_rate as f64, wall) else {
+            return false;
+        };
+        let limit = self.config.max_clock_drift_ppm.unwrap_or(DEFAULT_MAX_CLOCK_DRIFT_PPM) as f64;
+        if ppm.abs() <= limit {
+            return false;
+        }
+        warn!("⚠️ Clock drift: takes ran {:+.0} ppm off the wall clock over the last {:.0}s (limit {:.0} ppm); check the interface's sample rate and for dropped buffers",
+            ppm, self.clock.wall().as_secs_f32(), limit);
+        self.drift_events += 1;
+        self.clock.reset();
+        if self.config.resync_on_drift {
+            info!("🔄 Re-syncing: reopening the capture stream");
+        }
+        self.config.resync_on_drift
+    }
+
+    /// Audio/wall clock divergence over the recent takes, once enough has been recorded
+    pub fn clock_drift_ppm(&self) -> Option<f64> {
+        self.clock.ppm()
+    }
+
+    pub fn failed(&self) -> &[FailedNote] {
+        &self.failed
+    }
+
+    /// Print the notes that were skipped and any clock drift
+    pub fn report(&self) {
+        if self.drift_events > 0 {
+            warn!("⚠️ Audio and wall clocks drifted apart {} times during the batch", self.drift_events);
+        } else if let Some(ppm) = self.clock_drift_ppm() {
+            info!("🕰️ Audio clock within {:+.0} ppm of the wall clock", ppm);
+        }
+        if self.failed.is_empty() {
+            return;
+        }
+        warn!("⚠️ Watchdog skipped {} notes:", self.failed.len());
+        for failed in &self.failed {
+            info!("   {} vel {}: {}", failed.note, failed.velocity, failed.reason);
+        }
+    }
+
+    fn batch_remaining(&self) -> Option<Duration> {
+        self.config.max_batch_secs
+            .map(|secs| Duration::from_secs_f32(secs).saturating_sub(self.started.elapsed()))
+    }
+
+    fn batch_expired(&self) -> BatcherbirdError {
+        BatcherbirdError::Session(format!(
+            "Batch time limit of {:.0}s exceeded", self.config.max_batch_secs.unwrap_or_default()
+        ))
+    }
+}
+
+/// Recorded time by frame count against wall time, over a sliding window of takes
+#[derive(Debug, Default)]
+struct ClockDriftMonitor {
+    /// (seconds of audio the take's frames make, wall time it ran)
+    takes: VecDeque<(f64, Duration)>,
+}
+
+impl ClockDriftMonitor {
+    fn record(&mut self, audio_secs: f64, wall: Duration) -> Option<f64> {
+        self.takes.push_back((audio_secs, wall));
+        while self.takes.len() > 1 && self.wall() - self.takes[0].1 >= CLOCK_WINDOW {
+            self.takes.pop_front();
+        }
+        self.ppm()
+    }
+
+    fn ppm(&self) -> Option<f64> {
+        let wall = self.wall();
+        (wall >= MIN_CLOCK_WINDOW).then(|| {
+            let audio: f64 = self.takes.iter().map(|&(audio, _)| audio).sum();
+            (audio / wall.as_secs_f64() - 1.0) * 1e6
+        })
+    }
+
+    fn wall(&self) -> Duration {
+        self.takes.iter().map(|&(_, wall)| wall).sum()
+    }
+
+    fn reset(&mut self) {
+        self.takes.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_watchdog_cancels_hung_notes() {
+        let config = WatchdogConfig { max_note_secs: Some(0.05), max_batch_secs: Some(0.3), ..Default::default() };
+        let mut watchdog = BatchWatchdog::new(config.clone(), Duration::from_secs(3));
+
+        let quick = watchdog.supervise(60, 100, async { Ok(1) }).await.unwrap();
+        assert_eq!(quick, Some(1));
+        let hung = watchdog.supervise(61, 100, std::future::pending::<Result<u8>>()).await.unwrap();
+        assert_eq!(hung, None);
+        assert_eq!(watchdog.failed().len(), 1);
+        assert_eq!(watchdog.failed()[0].note, 61);
+        assert!(watchdog.check_continue().is_ok());
+
+        // Errors from the capture itself pass straight through
+        assert!(watchdog.supervise(62, 100, async { Err::<u8, _>(BatcherbirdError::Audio("x".into())) }).await.is_err());
+
+        // The batch limit cuts off the notes that would run past it
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(watchdog.supervise(63, 100, async { Ok(1) }).await.is_err());
+        assert!(watchdog.check_continue().is_err());
+
+        let strict = BatchWatchdog::new(WatchdogConfig { abort_on_timeout: true, ..config }, Duration::from_secs(3));
+        assert!(strict.check_continue().is_err());
+
+        // Without a limit the expected length sets it
+        assert_eq!(BatchWatchdog::new(WatchdogConfig::default(), Duration::from_secs(3)).note_limit, Duration::from_secs(11));
+    }
+
+    #[test]
+    fn test_clock_drift_is_warned_and_resynced() {
+        let take = Duration::from_secs(3);
+        let mut watchdog = BatchWatchdog::new(WatchdogConfig::default(), take);
+        // 50 ppm fast with half a buffer of jitter either way per take: fine
+        for index in 0..100 {
+            let frames = if index % 2 == 0 { 144_007 + 256 } else { 144_007 - 256 };
+            assert!(!watchdog.track_clock(frames, 48000, take));
+        }
+        let ppm = watchdog.clock_drift_ppm().unwrap();
+        assert!((ppm - 50.0).abs() < 50.0, "{}", ppm);
+        assert_eq!(watchdog.drift_events, 0);
+
+        // A device delivering 44.1 kHz while reporting 48 kHz is flagged once enough is recorded
+        let mut resync = BatchWatchdog::new(WatchdogConfig { resync_on_drift: true, ..Default::default() }, take);
+        let flagged: Vec<bool> = (0..60).map(|_| resync.track_clock(132_300, 48000, take)).collect();
+        assert_eq!(flagged.iter().position(|&flag| flag), Some(39));
+        assert_eq!(resync.drift_events, 1);
+        // Measuring starts over after a re-sync
+        assert_eq!(resync.clock_drift_ppm(), None);
+        assert!(!resync.track_clock(0, 48000, Duration::ZERO));
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{library, sampler::Sample, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Frames summarised by each peak at the finest level
+pub const BASE_FRAMES_PER_PEAK: usize = 32;
+/// Each coarser level merges this many peaks of the level below
+const LEVEL_FACTOR: usize = 4;
+/// Stop adding coarser levels once a level has this few peaks
+const MIN_LEVEL_PEAKS: usize = 256;
+
+/// Min/max envelope of every channel at one zoom resolution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeakLevel {
+    pub frames_per_peak: usize,
+    /// Per channel minimum of each block
+    pub min: Vec<Vec<f32>>,
+    /// Per channel maximum of each block
+    pub max: Vec<Vec<f32>>,
+}
+
+impl PeakLevel {
+    pub fn len(&self) -> usize {
+        self.max.first().map_or(0, Vec::len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn merge(&self, factor: usize) -> Self {
+        let reduce = |channels: &[Vec<f32>], pick: fn(f32, f32) -> f32| -> Vec<Vec<f32>> {
+            channels.iter()
+                .map(|peaks| peaks.chunks(factor).map(|chunk| chunk.iter().copied().reduce(pick).unwrap_or(0.0)).collect())
+                .collect()
+        };
+        Self {
+            frames_per_peak: self.frames_per_peak * factor,
+            min: reduce(&self.min, f32::min),
+            max: reduce(&self.max, f32::max),
+        }
+    }
+}
+
+/// Multi-resolution waveform overview, finest level first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaveformPeaks {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub frames: usize,
+    pub levels: Vec<PeakLevel>,
+}
+
+impl WaveformPeaks {
+    pub fn from_interleaved(audio: &[f32], channels: u16, sample_rate: u32) -> Self {
+        let channel_count = channels.max(1) as usize;
+        let frames = audio.len() / channel_count;
+        let block = BASE_FRAMES_PER_PEAK * channel_count;
+
+        let mut min = vec![Vec::with_capacity(frames.div_ceil(BASE_FRAMES_PER_PEAK)); channel_count];
+        let mut max = min.clone();
+        for chunk in audio[..frames * channel_count].chunks(block) {
+            for channel in 0..channel_count {
+                let values = chunk.iter().skip(channel).step_by(channel_count);
+                min[channel].push(values.clone().copied().fold(f32::MAX, f32::min));
+                max[channel].push(values.copied().fold(f32::MIN, f32::max));
+            }
+        }
+
+        let mut levels = vec![PeakLevel { frames_per_peak: BASE_FRAMES_PER_PEAK, min, max }];
+        while levels.last().is_some_and(|level| level.len() > MIN_LEVEL_PEAKS) {
+            let coarser = levels.last().unwrap().merge(LEVEL_FACTOR);
+            levels.push(coarser);
+        }
+
+        Self { sample_rate, channels, frames, levels }
+    }
+
+    pub fn from_sample(sample: &Sample) -> Self {
+        Self::from_interleaved(&sample.audio_data, sample.channels, sample.sample_rate)
+    }
+
+    pub fn from_wav_file(path: &Path) -> Result<Self> {
+        let (audio, spec) = library::read_wav(path)?;
+        Ok(Self::from_interleaved(&audio, spec.channels, spec.sample_rate))
+    }
+
+    /// Coarsest level that still has at least `peaks` entries (the finest level if none does),
+    /// so a view `peaks` pixels wide gets one peak per pixel or better
+    pub fn level_for_width(&self, peaks: usize) -> &PeakLevel {
+        self.levels.iter()
+            .rev()
+            .find(|level| level.len() >= peaks)
+            .unwrap_or(&self.levels[0])
+    }
+
+    pub fn duration_secs(&self) -> f32 {
+        self.frames as f32 / self.sample_rate.max(1) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_waveform_peak_levels() {
+        // Stereo ramp: left rises 0..1, right mirrors it negative
+        let frames = 48000;
+        let audio: Vec<f32> = (0..frames)
+            .flat_map(|i| {
+                let value = i as f32 / frames as f32;
+                [value, -value]
+            })
+            .collect();
+        let peaks = WaveformPeaks::from_interleaved(&audio, 2, 48000);
+
+        assert_eq!(peaks.frames, frames);
+        assert!((peaks.duration_secs() - 1.0).abs() < 1e-6);
+        assert_eq!(peaks.levels[0].len(), frames.div_ceil(BASE_FRAMES_PER_PEAK));
+        assert!(peaks.levels.last().unwrap().len() <= MIN_LEVEL_PEAKS);
+
+        // Each coarser level covers the same audio with fewer, wider blocks
+        for pair in peaks.levels.windows(2) {
+            assert_eq!(pair[1].frames_per_peak, pair[0].frames_per_peak * LEVEL_FACTOR);
+            assert_eq!(pair[1].len(), pair[0].len().div_ceil(LEVEL_FACTOR));
+        }
+        for level in &peaks.levels {
+            let last = level.len() - 1;
+            assert!(level.max[0][last] > 0.99 && level.min[0][0] == 0.0);
+            assert!(level.min[1][last] < -0.99 && level.max[1][0] == 0.0);
+        }
+
+        let level = peaks.level_for_width(800);
+        assert!(level.len() >= 800);
+        assert!(peaks.level_for_width(1_000_000).frames_per_peak == BASE_FRAMES_PER_PEAK);
+
+        let empty = WaveformPeaks::from_interleaved(&[], 1, 44100);
+        assert_eq!(empty.levels.len(), 1);
+        assert!(empty.levels[0].is_empty());
+    }
+}
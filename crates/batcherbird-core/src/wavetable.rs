@@ -0,0 +1,227 @@
+use crate::pitch::{estimate_frequency, mixdown};
+use crate::sampler::Sample;
+use crate::{library, BatcherbirdError, Result};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Frame size Serum and Vital expect by default
+pub const DEFAULT_FRAME_SIZE: usize = 2048;
+/// Share of the sound skipped at each end so frames come from the settled body, not the
+/// attack or release
+const BODY_MARGIN: f32 = 0.1;
+/// Level (fraction of the peak) where the sound is taken to start and end
+const SOUND_LEVEL: f32 = 0.1;
+
+/// How many single cycles to take from a sample and how long each frame is
+#[derive(Debug, Clone)]
+pub struct WavetableConfig {
+    /// Cycles taken at evenly spaced points through the sound (1 = a single-cycle waveform)
+    pub frames: usize,
+    /// Samples per frame after resampling (2048 for Serum/Vital)
+    pub frame_size: usize,
+}
+
+impl Default for WavetableConfig {
+    fn default() -> Self {
+        Self { frames: 1, frame_size: DEFAULT_FRAME_SIZE }
+    }
+}
+
+/// Single-cycle frames resampled to a common size, ready to be written back to back
+#[derive(Debug, Clone)]
+pub struct Wavetable {
+    pub frame_size: usize,
+    pub frames: Vec<Vec<f32>>,
+    /// Fundamental the cycles were cut at (Hz)
+    pub frequency_hz: f32,
+}
+
+impl Wavetable {
+    /// Cut `config.frames` single cycles out of a pitched, sustained sample. Each cycle starts
+    /// on a rising zero crossing so consecutive frames line up in phase.
+    pub fn extract(sample: &Sample, config: &WavetableConfig) -> Result<Self> {
+        if config.frames == 0 || config.frame_size < 8 {
+            return Err(BatcherbirdError::Config(format!(
+                "Wavetable needs at least one frame of 8+ samples (got {} x {})", config.frames, config.frame_size
+            )));
+        }
+        let mono = mixdown(&sample.audio_data, sample.channels);
+        let frequency_hz = estimate_frequency(&mono, sample.sample_rate).ok_or_else(|| {
+            BatcherbirdError::Processing("No stable pitch found; wavetables need a pitched, sustained sample".to_string())
+        })?;
+        let period = sample.sample_rate as f32 / frequency_hz;
+
+        // Body of the sound, between the attack and the release
+        let peak = mono.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+        let first = mono.iter().position(|s| s.abs() > peak * SOUND_LEVEL).unwrap_or(0);
+        let last = mono.iter().rposition(|s| s.abs() > peak * SOUND_LEVEL).unwrap_or(0);
+        let margin = ((last - first) as f32 * BODY_MARGIN) as usize;
+        // Room after the last start point for finding a crossing and reading a whole cycle
+        let cycle = period.ceil() as usize + 1;
+        let (body_start, body_end) = (first + margin, (last - margin).saturating_sub(2 * cycle));
+        if body_end <= body_start {
+            return Err(BatcherbirdError::Processing("Sample is too short to take a cycle from".to_string()));
+        }
+
+        let mut frames = Vec::with_capacity(config.frames);
+        for index in 0..config.frames {
+            let at = if config.frames == 1 {
+                (body_start + body_end) / 2
+            } else {
+                body_start + (body_end - body_start) * index / (config.frames - 1)
+            };
+            let start = rising_crossing(&mono, at, at + cycle)
+                .ok_or_else(|| BatcherbirdError::Processing(format!("No zero crossing near sample {}", at)))?;
+            frames.push(resample_cycle(&mono, start, period, config.frame_size));
+        }
+
+        // One gain for the whole table so the timbre's level changes survive
+        let table_peak = frames.iter().flatten().fold(0.0f32, |max, s| max.max(s.abs()));
+        if table_peak > 0.0 {
+            let gain = 0.99 / table_peak;
+            frames.iter_mut().flatten().for_each(|s| *s *= gain);
+        }
+
+        Ok(Self { frame_size: config.frame_size, frames, frequency_hz })
+    }
+
+    /// Write the frames back to back as a mono 32-bit float WAV with Serum's `clm ` marker,
+    /// which Serum and Vital use to find the frame size
+    pub fn save(&self, path: &Path, sample_rate: u32) -> Result<()> {
+        let spec = hound::WavSpec { channels: 1, sample_rate, bits_per_sample: 32, sample_format: hound::SampleFormat::Float };
+        let audio: Vec<f32> = self.frames.iter().flatten().copied().collect();
+        library::write_wav(path, &audio, spec)?;
+        append_clm_chunk(path, self.frame_size)?;
+        info!("🌊 Wrote {} frame wavetable ({} samples per frame, {:.1} Hz): {}",
+            self.frames.len(), self.frame_size, self.frequency_hz, path.display());
+        Ok(())
+    }
+}
+
+/// Where a wavetable cut from `source` is written by default: "Pad_C4_60_vel127_wavetable.wav"
+pub fn wavetable_file_path(source: &Path) -> PathBuf {
+    let stem = source.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    source.with_file_name(format!("{}_wavetable.wav", stem))
+}
+
+/// Cut a wavetable from a WAV file and write it to `destination`
+pub fn write_file_wavetable(source: &Path, destination: &Path, config: &WavetableConfig) -> Result<Wavetable> {
+    let (audio_data, spec) = library::read_wav(source)?;
+    let (note, velocity, metadata) = source.file_name()
+        .and_then(|name| library::parse_sample_filename(&name.to_string_lossy()))
+        .unwrap_or((60, 127, Default::default()));
+    let sample = Sample {
+        note,
+        velocity,
+        audio_data,
+        sample_rate: spec.sample_rate,
+        channels: spec.channels,
+        recorded_at: std::time::SystemTime::now(),
+        midi_timing: std::time::Duration::ZERO,
+        audio_timing: std::time::Duration::ZERO,
+        metadata,
+    };
+    let table = Wavetable::extract(&sample, config)?;
+    table.save(destination, spec.sample_rate)?;
+    Ok(table)
+}
+
+/// Fractional position of the first rising zero crossing in `from..to`
+fn rising_crossing(mono: &[f32], from: usize, to: usize) -> Option<f32> {
+    (from.max(1)..to.min(mono.len()))
+        .find(|&i| mono[i - 1] <= 0.0 && mono[i] > 0.0)
+        .map(|i| {
+            let (before, after) = (mono[i - 1], mono[i]);
+            (i - 1) as f32 + before.abs() / (before.abs() + after)
+        })
+}
+
+/// One cycle from `start`, `period` samples long, linearly resampled to `size` points with
+/// its DC offset removed
+fn resample_cycle(mono: &[f32], start: f32, period: f32, size: usize) -> Vec<f32> {
+    let mut cycle: Vec<f32> = (0..size)
+        .map(|i| {
+            let position = start + period * i as f32 / size as f32;
+            let index = position.floor() as usize;
+            let fraction = position - index as f32;
+            let current = mono.get(index).copied().unwrap_or(0.0);
+            let next = mono.get(index + 1).copied().unwrap_or(current);
+            current + (next - current) * fraction
+        })
+        .collect();
+    let mean = cycle.iter().sum::<f32>() / size as f32;
+    cycle.iter_mut().for_each(|s| *s -= mean);
+    cycle
+}
+
+/// Append Serum's `clm ` chunk ("<!>2048 ...") and fix up the RIFF size
+fn append_clm_chunk(path: &Path, frame_size: usize) -> Result<()> {
+    let mut text = format!("<!>{} 10000000 wavetable (batcherbird)", frame_size).into_bytes();
+    if text.len() % 2 == 1 {
+        text.push(0);
+    }
+    let mut chunk = Vec::with_capacity(8 + text.len());
+    chunk.extend_from_slice(b"clm ");
+    chunk.extend_from_slice(&(text.len() as u32).to_le_bytes());
+    chunk.extend_from_slice(&text);
+
+    let mut file = OpenOptions::new().append(true).open(path)?;
+    file.write_all(&chunk)?;
+    drop(file);
+
+    let mut bytes = fs::read(path)?;
+    let riff_size = (bytes.len() - 8) as u32;
+    bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    fs::write(path, bytes)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_single_cycles_from_pad() {
+        // A 220 Hz tone whose second harmonic fades in, so the frames should differ
+        let rate = 44100;
+        let audio_data: Vec<f32> = (0..rate as usize * 2)
+            .map(|i| {
+                let t = i as f32 / rate as f32;
+                let phase = 2.0 * std::f32::consts::PI * 220.0 * t;
+                0.5 * phase.sin() + 0.3 * (t / 2.0) * (2.0 * phase).sin()
+            })
+            .collect();
+        let sample = Sample {
+            note: 57,
+            velocity: 127,
+            audio_data,
+            sample_rate: rate,
+            channels: 1,
+            recorded_at: std::time::SystemTime::now(),
+            midi_timing: std::time::Duration::ZERO,
+            audio_timing: std::time::Duration::ZERO,
+            metadata: Default::default(),
+        };
+
+        let table = Wavetable::extract(&sample, &WavetableConfig { frames: 4, frame_size: 256 }).unwrap();
+        assert!((table.frequency_hz - 220.0).abs() < 2.0, "{}", table.frequency_hz);
+        assert_eq!(table.frames.len(), 4);
+        assert!(table.frames.iter().all(|frame| frame.len() == 256));
+        // Whole cycles from a rising crossing: starts near zero, first quarter positive
+        for frame in &table.frames {
+            assert!(frame[0].abs() < 0.1 && frame[32] > 0.3, "{:?}", &frame[..4]);
+        }
+        let difference: f32 = table.frames[0].iter().zip(&table.frames[3]).map(|(a, b)| (a - b).abs()).sum();
+        assert!(difference / 256.0 > 0.05);
+
+        let path = std::env::temp_dir().join(format!("batcherbird_wavetable_{}.wav", std::process::id()));
+        table.save(&path, rate).unwrap();
+        let (audio, spec) = library::read_wav(&path).unwrap();
+        assert_eq!((audio.len(), spec.channels), (4 * 256, 1));
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(bytes.windows(7).any(|w| w == b"<!>256 "));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -16,6 +16,7 @@ fn test_sfz_export() {
             recorded_at: SystemTime::now(),
             midi_timing: Duration::from_millis(100),
             audio_timing: Duration::from_millis(2000),
+            metadata: Default::default(),
         },
         Sample {
             note: 60, // C4
@@ -26,6 +27,7 @@ fn test_sfz_export() {
             recorded_at: SystemTime::now(),
             midi_timing: Duration::from_millis(100),
             audio_timing: Duration::from_millis(2000),
+            metadata: Default::default(),
         },
     ];
     
@@ -44,6 +46,7 @@ fn test_sfz_export() {
         detection_config: DetectionConfig::default(),
         creator_name: Some("Test User".to_string()),
         instrument_description: Some("Test SFZ instrument".to_string()),
+        ..ExportConfig::default()
     };
     
     let exporter = SampleExporter::new(config).unwrap();
@@ -53,7 +56,7 @@ fn test_sfz_export() {
     assert_eq!(files.len(), 3);
     
     // Find and verify SFZ file
-    let sfz_file = files.iter().find(|f| f.extension().map_or(false, |ext| ext == "sfz")).unwrap();
+    let sfz_file = files.iter().find(|f| f.extension().is_some_and(|ext| ext == "sfz")).unwrap();
     let sfz_content = std::fs::read_to_string(sfz_file).unwrap();
     
     assert!(sfz_content.contains("Generated by Batcherbird"));
@@ -79,6 +82,7 @@ fn test_decent_sampler_export() {
             recorded_at: SystemTime::now(),
             midi_timing: Duration::from_millis(100),
             audio_timing: Duration::from_millis(2000),
+            metadata: Default::default(),
         },
     ];
     
@@ -97,6 +101,7 @@ fn test_decent_sampler_export() {
         detection_config: DetectionConfig::default(),
         creator_name: Some("Test User".to_string()),
         instrument_description: Some("Test Decent Sampler instrument".to_string()),
+        ..ExportConfig::default()
     };
     
     let exporter = SampleExporter::new(config).unwrap();
@@ -106,7 +111,7 @@ fn test_decent_sampler_export() {
     assert_eq!(files.len(), 2);
     
     // Find and verify .dspreset file
-    let dspreset_file = files.iter().find(|f| f.extension().map_or(false, |ext| ext == "dspreset")).unwrap();
+    let dspreset_file = files.iter().find(|f| f.extension().is_some_and(|ext| ext == "dspreset")).unwrap();
     let dspreset_content = std::fs::read_to_string(dspreset_file).unwrap();
     
     assert!(dspreset_content.contains("Generated by Batcherbird"));
@@ -118,4 +123,1400 @@ fn test_decent_sampler_export() {
     
     // Cleanup
     std::fs::remove_dir_all(&temp_dir).ok();
-}
\ No newline at end of file
+}
+#[test]
+fn test_cc_sweep_sfz_export() {
+    use batcherbird_core::sampler::{CcLayer, SampleMetadata};
+
+    // Same note captured at two filter cutoff settings
+    let test_samples: Vec<Sample> = [0u8, 127u8].iter().map(|&value| Sample {
+        note: 48, // C3
+        velocity: 100,
+        audio_data: vec![0.5, 0.3, 0.1, 0.0, -0.1, -0.3, -0.5],
+        sample_rate: 44100,
+        channels: 1,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(2000),
+        metadata: SampleMetadata {
+            cc_layer: Some(CcLayer { controller: 74, value }),
+            ..Default::default()
+        },
+    }).collect();
+
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_cc_sweep");
+    std::fs::create_dir_all(&temp_dir).unwrap();
+
+    let config = ExportConfig {
+        output_directory: temp_dir.clone(),
+        naming_pattern: "Sweep_{note_name}_{note}_{velocity}.wav".to_string(),
+        sample_format: AudioFormat::SFZ,
+        fade_out_ms: 0.0,
+        apply_detection: false,
+        ..ExportConfig::default()
+    };
+
+    let exporter = SampleExporter::new(config).unwrap();
+    let files = exporter.export_samples(&test_samples).unwrap();
+
+    // Layers must not overwrite each other even though note/velocity match
+    assert_eq!(files.len(), 3);
+    assert!(files[0].to_string_lossy().ends_with("_cc74_000.wav"));
+    assert!(files[1].to_string_lossy().ends_with("_cc74_127.wav"));
+
+    let sfz_file = files.iter().find(|f| f.extension().is_some_and(|ext| ext == "sfz")).unwrap();
+    let sfz_content = std::fs::read_to_string(sfz_file).unwrap();
+
+    assert!(sfz_content.contains("locc74=0\nhicc74=63"));
+    assert!(sfz_content.contains("locc74=64\nhicc74=127"));
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_legato_sfz_export() {
+    use batcherbird_core::sampler::SampleMetadata;
+
+    // Plain D4 sustain plus the C4 -> D4 transition
+    let test_samples: Vec<Sample> = [None, Some(60u8)].iter().map(|&legato_from| Sample {
+        note: 62, // D4
+        velocity: 100,
+        audio_data: vec![0.5, 0.3, 0.1, 0.0, -0.1, -0.3, -0.5],
+        sample_rate: 44100,
+        channels: 1,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(2000),
+        metadata: SampleMetadata {
+            legato_from,
+            ..Default::default()
+        },
+    }).collect();
+
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_legato");
+    std::fs::create_dir_all(&temp_dir).unwrap();
+
+    let config = ExportConfig {
+        output_directory: temp_dir.clone(),
+        naming_pattern: "Legato_{note_name}_{note}_{velocity}.wav".to_string(),
+        sample_format: AudioFormat::SFZ,
+        fade_out_ms: 0.0,
+        apply_detection: false,
+        ..ExportConfig::default()
+    };
+
+    let exporter = SampleExporter::new(config).unwrap();
+    let files = exporter.export_samples(&test_samples).unwrap();
+
+    assert_eq!(files.len(), 3);
+    assert!(files[0].to_string_lossy().ends_with("D4_62_vel100.wav"));
+    assert!(files[1].to_string_lossy().ends_with("D4_62_vel100_from_C4.wav"));
+
+    let sfz_file = files.iter().find(|f| f.extension().is_some_and(|ext| ext == "sfz")).unwrap();
+    let sfz_content = std::fs::read_to_string(sfz_file).unwrap();
+
+    assert!(sfz_content.contains("key=62\ntrigger=first"));
+    assert!(sfz_content.contains("key=62\ntrigger=legato\nsw_previous=60"));
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_drum_kit_sfz_export() {
+    use batcherbird_core::drum_map::DrumMap;
+
+    let test_samples: Vec<Sample> = [36u8, 38u8].iter().map(|&note| Sample {
+        note,
+        velocity: 100,
+        audio_data: vec![0.9, 0.5, 0.2, 0.1, 0.0, -0.1, -0.2],
+        sample_rate: 44100,
+        channels: 1,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(500),
+        metadata: Default::default(),
+    }).collect();
+
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_drum_kit");
+    std::fs::create_dir_all(&temp_dir).unwrap();
+
+    let config = ExportConfig {
+        output_directory: temp_dir.clone(),
+        naming_pattern: "Kit_{note_name}_{velocity}.wav".to_string(),
+        sample_format: AudioFormat::SFZ,
+        fade_out_ms: 0.0,
+        apply_detection: false,
+        drum_map: Some(DrumMap::general_midi()),
+        ..ExportConfig::default()
+    };
+
+    let exporter = SampleExporter::new(config).unwrap();
+    let files = exporter.export_samples(&test_samples).unwrap();
+
+    assert!(files[0].to_string_lossy().ends_with("Kit_Kick_vel100.wav"));
+    assert!(files[1].to_string_lossy().ends_with("Kit_Snare_vel100.wav"));
+
+    let sfz_file = files.iter().find(|f| f.extension().is_some_and(|ext| ext == "sfz")).unwrap();
+    let sfz_content = std::fs::read_to_string(sfz_file).unwrap();
+
+    assert!(sfz_content.contains("loop_mode=one_shot\npitch_keytrack=0"));
+    assert!(sfz_content.contains("// Snare\n<region>"));
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_demo_project_generation() {
+    use batcherbird_core::demo::generate_demo_project;
+    use batcherbird_core::manifest::SessionManifest;
+
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_demo_project");
+    std::fs::remove_dir_all(&temp_dir).ok();
+
+    let project = generate_demo_project(&temp_dir).unwrap();
+
+    // 4 notes x 2 velocities, plus one .sfz and one .dspreset
+    assert_eq!(project.sample_files.len(), 8);
+    assert_eq!(project.instrument_files.len(), 2);
+    assert!(project.sample_files.iter().all(|f| f.exists()));
+    assert!(project.instrument_files.iter().all(|f| f.exists()));
+
+    let manifest = SessionManifest::load(&project.manifest_path).unwrap();
+    assert_eq!(manifest.samples.len(), 8);
+    assert_eq!(manifest.instrument_files.len(), 2);
+
+    let report = std::fs::read_to_string(&project.report_path).unwrap();
+    assert!(report.contains("Samples: 8"));
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_sidecar_export_and_render() {
+    use batcherbird_core::detection::SampleDetector;
+    use batcherbird_core::edit::{render_directory, SampleEdit};
+
+    // 100ms of silence, 500ms of tone at half scale, 500ms of silence
+    let audio_data: Vec<f32> = (0..48000)
+        .map(|i| if (4410..26460).contains(&i) { 0.5 * (i as f32 * 0.05).sin() } else { 0.0 })
+        .collect();
+    let sample = Sample {
+        note: 60,
+        velocity: 100,
+        audio_data: audio_data.clone(),
+        sample_rate: 44100,
+        channels: 1,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(1000),
+        metadata: Default::default(),
+    };
+
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_sidecar");
+    std::fs::remove_dir_all(&temp_dir).ok();
+    let config = ExportConfig {
+        output_directory: temp_dir.clone(),
+        naming_pattern: "Raw_{note_name}_{note}_{velocity}.wav".to_string(),
+        sample_format: AudioFormat::SFZ,
+        normalize: true,
+        sidecar_edits: true,
+        ..ExportConfig::default()
+    };
+    let files = SampleExporter::new(config).unwrap().export_samples(&[sample]).unwrap();
+    let wav = &files[0];
+
+    // The raw take is written untouched, the decisions go to the sidecar
+    assert_eq!(hound::WavReader::open(wav).unwrap().duration(), 48000);
+    let edit = SampleEdit::load_sidecar(wav).unwrap().expect("sidecar written");
+    let trim_end = edit.trim_end.unwrap();
+    let detection = SampleDetector::new(DetectionConfig::default()).detect_boundaries(&audio_data, 44100).unwrap();
+    assert_eq!(edit.trim_start, detection.start_sample);
+    assert_eq!(trim_end, detection.end_sample);
+    assert!(edit.trim_start > 0);
+    assert!((edit.gain_db - 20.0 * (0.95f32 / 0.5).log10()).abs() < 0.1);
+    assert_eq!(edit.fade_out_ms, 10.0);
+
+    let rendered_dir = temp_dir.join("Rendered");
+    let rendered = render_directory(&temp_dir, &rendered_dir).unwrap();
+    assert_eq!(rendered.len(), 1);
+    let mut reader = hound::WavReader::open(&rendered[0]).unwrap();
+    assert_eq!(reader.duration() as usize, trim_end - edit.trim_start);
+    let peak = reader.samples::<i32>().map(|s| s.unwrap().unsigned_abs()).max().unwrap() as f32 / 8388607.0;
+    assert!((peak - 0.95).abs() < 0.01);
+    // Instrument files come along, sidecars don't
+    assert!(rendered_dir.join(files[1].file_name().unwrap()).exists());
+    assert!(!SampleEdit::sidecar_path(&rendered[0]).exists());
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_dual_mono_export() {
+    // Left and right carry different levels so the split can be checked
+    let sample = Sample {
+        note: 48,
+        velocity: 127,
+        audio_data: (0..1000).flat_map(|_| [0.5, -0.25]).collect(),
+        sample_rate: 44100,
+        channels: 2,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(1000),
+        metadata: Default::default(),
+    };
+
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_dual_mono");
+    std::fs::remove_dir_all(&temp_dir).ok();
+    let config = ExportConfig {
+        output_directory: temp_dir.clone(),
+        naming_pattern: "Split_{note_name}_{note}_{velocity}.wav".to_string(),
+        sample_format: AudioFormat::SFZ,
+        fade_out_ms: 0.0,
+        apply_detection: false,
+        dual_mono: true,
+        ..ExportConfig::default()
+    };
+    let files = SampleExporter::new(config).unwrap().export_samples(&[sample]).unwrap();
+    let left = files[0].clone();
+    let left_name = left.file_name().unwrap().to_str().unwrap().to_string();
+    assert!(left_name.ends_with("_L.wav"));
+    let right = left.with_file_name(left_name.replace("_L.wav", "_R.wav"));
+
+    for (path, level) in [(&left, 0.5f32), (&right, -0.25f32)] {
+        let mut reader = hound::WavReader::open(path).unwrap();
+        assert_eq!(reader.spec().channels, 1);
+        assert_eq!(reader.duration(), 1000);
+        let first = reader.samples::<i32>().next().unwrap().unwrap() as f32 / 8388607.0;
+        assert!((first - level).abs() < 1e-3);
+    }
+
+    // The instrument plays both files as one hard-panned pair
+    let sfz = std::fs::read_to_string(&files[1]).unwrap();
+    assert!(sfz.contains(&format!("sample={}\nkey=48\npan=-100\n", left_name)));
+    assert!(sfz.contains(&format!("sample={}\nkey=48\npan=100\n", right.file_name().unwrap().to_str().unwrap())));
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_normalization_gains_in_manifest() {
+    use batcherbird_core::manifest::{NormalizationSummary, SessionManifest};
+
+    // Peaks of 0.475, 0.2375 and 0.095 need +6 dB, +12 dB and +20 dB to reach 0.95
+    let samples: Vec<Sample> = [(60, 0.475f32), (62, 0.2375), (64, 0.095)].iter()
+        .map(|&(note, peak)| Sample {
+            note,
+            velocity: 100,
+            audio_data: (0..1000).map(|i| if i % 2 == 0 { peak } else { -peak }).collect(),
+            sample_rate: 44100,
+            channels: 1,
+            recorded_at: SystemTime::now(),
+            midi_timing: Duration::from_millis(100),
+            audio_timing: Duration::from_millis(1000),
+            metadata: Default::default(),
+        })
+        .collect();
+
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_normalization_gains");
+    std::fs::remove_dir_all(&temp_dir).ok();
+    let config = ExportConfig {
+        output_directory: temp_dir.clone(),
+        naming_pattern: "Gain_{note_name}_{note}_{velocity}.wav".to_string(),
+        normalize: true,
+        fade_out_ms: 0.0,
+        apply_detection: false,
+        ..ExportConfig::default()
+    };
+    let exporter = SampleExporter::new(config).unwrap();
+    let files = exporter.export_samples(&samples).unwrap();
+
+    let mut manifest = SessionManifest::new("Gain");
+    for (sample, file) in samples.iter().zip(&files) {
+        manifest.add_sample(sample, file);
+    }
+    manifest.record_normalization_gains(&exporter.normalization_gains());
+    let gains: Vec<f32> = manifest.samples.iter().map(|entry| entry.normalization_gain_db.unwrap()).collect();
+    for (gain, expected) in gains.iter().zip([6.02f32, 12.04, 19.99]) {
+        assert!((gain - expected).abs() < 0.05, "gain {} vs {}", gain, expected);
+    }
+
+    let summary = manifest.normalization_summary().unwrap();
+    assert_eq!(summary.count, 3);
+    assert_eq!(summary.median_db, gains[1]);
+    assert!((summary.min_db - 6.02).abs() < 0.05 && (summary.max_db - 19.99).abs() < 0.05);
+    assert!(manifest.summary().contains("Normalization gain over 3 samples"));
+    assert_eq!(NormalizationSummary::from_gains(&[]), None);
+    assert_eq!(NormalizationSummary::from_gains(&[1.0, 3.0]).unwrap().median_db, 2.0);
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_timing_jitter_in_report() {
+    use batcherbird_core::manifest::{SessionManifest, TimingSummary};
+    use batcherbird_core::sampler::SampleMetadata;
+    use std::path::Path;
+
+    let take = |note: u8, onset_latency_ms: Option<f32>| Sample {
+        note,
+        velocity: 100,
+        audio_data: vec![0.1; 480],
+        sample_rate: 48000,
+        channels: 1,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(1000),
+        metadata: SampleMetadata { onset_latency_ms, ..Default::default() },
+    };
+
+    // A steady rig: a few tenths of a millisecond either way
+    let mut manifest = SessionManifest::new("Steady");
+    for (note, latency) in [(60, 4.0), (61, 4.2), (62, 3.9), (63, 4.1)] {
+        manifest.add_sample(&take(note, Some(latency)), Path::new(&format!("steady_{}.wav", note)));
+    }
+    manifest.add_sample(&take(64, None), Path::new("steady_64.wav"));
+    let timing = manifest.timing_summary().unwrap();
+    assert_eq!(timing.count, 4);
+    assert_eq!((timing.min_ms, timing.max_ms), (3.9, 4.2));
+    assert!((timing.median_ms - 4.05).abs() < 1e-4);
+    assert!(timing.stddev_ms < 0.2 && !timing.is_jittery());
+    let report = manifest.summary();
+    assert!(report.contains("Note-on latency over 4 samples"));
+    assert!(report.contains("lat 4.2 ms"));
+    assert!(!report.contains("check USB MIDI"));
+
+    // One note arriving a buffer late gets flagged
+    let mut manifest = SessionManifest::new("Jittery");
+    for (note, latency) in [(60, 4.0), (61, 4.1), (62, 14.7)] {
+        manifest.add_sample(&take(note, Some(latency)), Path::new(&format!("jittery_{}.wav", note)));
+    }
+    assert!(manifest.timing_summary().unwrap().is_jittery());
+    assert!(manifest.summary().contains("check USB MIDI hubs and interfaces"));
+    assert_eq!(TimingSummary::from_latencies(&[]), None);
+}
+
+#[test]
+fn test_tuning_correction_export() {
+    use batcherbird_core::sampler::SampleMetadata;
+
+    // C4 played 20 cents sharp, measured at export; D4 carries a tuning measured at capture
+    let rate = 48000;
+    let sharp_c4 = 261.63 * 2f32.powf(20.0 / 1200.0);
+    let take = |note: u8, audio_data: Vec<f32>, tuning_cents: Option<f32>| Sample {
+        note,
+        velocity: 127,
+        audio_data,
+        sample_rate: rate,
+        channels: 1,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(1000),
+        metadata: SampleMetadata { tuning_cents, ..Default::default() },
+    };
+    let samples = vec![
+        take(60, (0..rate).map(|i| 0.5 * (2.0 * std::f32::consts::PI * sharp_c4 * i as f32 / rate as f32).sin()).collect(), None),
+        take(62, vec![0.0; 4800], Some(-7.0)),
+    ];
+
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_tuning");
+    std::fs::remove_dir_all(&temp_dir).ok();
+    let config = ExportConfig {
+        output_directory: temp_dir.clone(),
+        naming_pattern: "Tune_{note_name}_{note}_{velocity}.wav".to_string(),
+        sample_format: AudioFormat::SFZ,
+        fade_out_ms: 0.0,
+        apply_detection: false,
+        ..ExportConfig::default()
+    };
+    let exporter = SampleExporter::new(config.clone()).unwrap();
+    let files = exporter.export_samples(&samples).unwrap();
+    let sfz = std::fs::read_to_string(&files[2]).unwrap();
+    assert!(sfz.contains("key=60\ntune=-20\n"), "{}", sfz);
+    assert!(sfz.contains("pitch_keycenter=62\nlokey=61\nhikey=63\ntune=7\n"));
+
+    let dspreset_exporter = SampleExporter::new(ExportConfig { sample_format: AudioFormat::DecentSampler, ..config }).unwrap();
+    let dspreset = std::fs::read_to_string(dspreset_exporter.generate_dspreset_file(&samples, &files[..2]).unwrap()).unwrap();
+    let region = |root: u8| dspreset.lines()
+        .find(|line| line.contains(&format!("rootNote=\"{}\"", root)))
+        .unwrap_or_else(|| panic!("no region for {} in {}", root, dspreset));
+    assert!(region(60).contains("tuning=\"-0.20\""), "{}", region(60));
+    assert!(region(62).contains("tuning=\"0.07\""), "{}", region(62));
+    // Both follow the instrument envelope on <groups>
+    for root in [60, 62] {
+        assert!(!region(root).contains("attack="), "{}", region(root));
+    }
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_bit_depth_and_hardware_copy_export() {
+    use batcherbird_core::export::{AudioOutput, BitDepth};
+    use batcherbird_core::hardware_target::TargetLibrary;
+
+    // A stereo 48kHz capture, written as a 16-bit SFZ plus an S900 copy (40kHz mono)
+    let sample = Sample {
+        note: 60,
+        velocity: 100,
+        audio_data: (0..4800).flat_map(|i| { let s = 0.5 * (i as f32 * 0.05).sin(); [s, s] }).collect(),
+        sample_rate: 48000,
+        channels: 2,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(1000),
+        metadata: Default::default(),
+    };
+    let s900 = TargetLibrary::builtin().require("s900").unwrap().clone();
+
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_bit_depth");
+    std::fs::remove_dir_all(&temp_dir).ok();
+    let config = ExportConfig {
+        output_directory: temp_dir.clone(),
+        naming_pattern: "Depth_{note_name}_{note}_{velocity}.wav".to_string(),
+        sample_format: AudioFormat::SFZ,
+        fade_out_ms: 0.0,
+        apply_detection: false,
+        instrument_bit_depth: BitDepth::Int16,
+        additional_outputs: vec![AudioOutput { subdirectory: "S900".to_string(), bit_depth: BitDepth::Int16, target: Some(s900) }],
+        ..ExportConfig::default()
+    };
+    let files = SampleExporter::new(config).unwrap().export_samples(&[sample]).unwrap();
+
+    // Copies aren't part of the returned sample + instrument list
+    assert_eq!(files.len(), 2);
+    let spec = hound::WavReader::open(&files[0]).unwrap().spec();
+    assert_eq!((spec.bits_per_sample, spec.channels, spec.sample_rate), (16, 2, 48000));
+
+    let copy = temp_dir.join("S900").join(files[0].file_name().unwrap());
+    let spec = hound::WavReader::open(&copy).unwrap().spec();
+    assert_eq!((spec.bits_per_sample, spec.channels, spec.sample_rate), (16, 1, 40000));
+    assert!(!temp_dir.join("S900").join("Depth.sfz").exists());
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+    assert_eq!(BitDepth::parse("32f"), Some(BitDepth::Float32));
+    assert_eq!(BitDepth::parse("20"), None);
+}
+
+#[test]
+fn test_multi_take_export() {
+    use batcherbird_core::sampler::SampleMetadata;
+    use batcherbird_core::takes::TakeInfo;
+
+    // Three takes of C4 kept side by side; take 2 was picked
+    let take = |index: u8, best: bool| Sample {
+        note: 60,
+        velocity: 127,
+        audio_data: (0..4800).map(|i| 0.5 * (i as f32 * 0.05).sin()).collect(),
+        sample_rate: 48000,
+        channels: 1,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(1000),
+        metadata: SampleMetadata { take: Some(TakeInfo { index, score: 0.5, best }), ..Default::default() },
+    };
+    let samples = vec![take(1, false), take(2, true), take(3, false)];
+
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_takes");
+    std::fs::remove_dir_all(&temp_dir).ok();
+    let config = ExportConfig {
+        output_directory: temp_dir.clone(),
+        naming_pattern: "Keys_{note_name}_{note}_{velocity}.wav".to_string(),
+        sample_format: AudioFormat::SFZ,
+        fade_out_ms: 0.0,
+        apply_detection: false,
+        ..ExportConfig::default()
+    };
+    let files = SampleExporter::new(config.clone()).unwrap().export_samples(&samples).unwrap();
+    let names: Vec<String> = files.iter().map(|f| f.file_name().unwrap().to_string_lossy().to_string()).collect();
+    assert_eq!(&names[..3], ["Keys_C4_60_vel127_take1.wav", "Keys_C4_60_vel127.wav", "Keys_C4_60_vel127_take3.wav"]);
+
+    // Only the picked take is mapped
+    let sfz = std::fs::read_to_string(temp_dir.join("Keys.sfz")).unwrap();
+    assert_eq!(sfz.matches("<region>").count(), 1);
+    assert!(sfz.contains("sample=Keys_C4_60_vel127.wav"));
+
+    // An explicit {take} token names every take
+    let tokened = SampleExporter::new(ExportConfig {
+        naming_pattern: "Keys_{note}_{take}.wav".to_string(),
+        sample_format: AudioFormat::Wav24Bit,
+        ..config
+    }).unwrap();
+    let files = tokened.export_samples(&samples[1..2]).unwrap();
+    assert!(files[0].ends_with("Keys_60_take2.wav"));
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_envelope_export() {
+    use batcherbird_core::envelope::AdsrEnvelope;
+    use batcherbird_core::sampler::SampleMetadata;
+
+    let envelope = AdsrEnvelope { attack_secs: 0.05, decay_secs: 0.2, sustain: 0.5, release_secs: 0.3 };
+    let samples = vec![Sample {
+        note: 60,
+        velocity: 127,
+        audio_data: vec![0.0; 4800],
+        sample_rate: 48000,
+        channels: 1,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(1000),
+        metadata: SampleMetadata { envelope: Some(envelope), ..Default::default() },
+    }];
+
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_envelope");
+    std::fs::remove_dir_all(&temp_dir).ok();
+    let config = ExportConfig {
+        output_directory: temp_dir.clone(),
+        naming_pattern: "Env_{note_name}_{note}_{velocity}.wav".to_string(),
+        sample_format: AudioFormat::SFZ,
+        fade_out_ms: 0.0,
+        apply_detection: false,
+        ..ExportConfig::default()
+    };
+    let exporter = SampleExporter::new(config.clone()).unwrap();
+    let files = exporter.export_samples(&samples).unwrap();
+    let sfz = std::fs::read_to_string(&files[1]).unwrap();
+    assert!(sfz.contains("ampeg_attack=0.050\nampeg_decay=0.200\nampeg_sustain=50.0\nampeg_release=0.300\n"), "{}", sfz);
+
+    let dspreset_exporter = SampleExporter::new(ExportConfig { sample_format: AudioFormat::DecentSampler, ..config }).unwrap();
+    let dspreset = std::fs::read_to_string(dspreset_exporter.generate_dspreset_file(&samples, &files[..1]).unwrap()).unwrap();
+    assert!(dspreset.contains("attack=\"0.050\" decay=\"0.200\" sustain=\"0.500\" release=\"0.300\""));
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_loop_detection_export() {
+    use batcherbird_core::edit::read_loop_points;
+    use batcherbird_core::envelope::AdsrEnvelope;
+    use batcherbird_core::sampler::SampleMetadata;
+
+    // Two seconds of a steady 220 Hz pad
+    let rate = 44100;
+    let audio_data: Vec<f32> = (0..rate * 2)
+        .map(|i| 0.5 * (2.0 * std::f32::consts::PI * 220.0 * i as f32 / rate as f32).sin())
+        .collect();
+    let envelope = AdsrEnvelope { attack_secs: 0.01, decay_secs: 0.0, sustain: 1.0, release_secs: 0.1 };
+    let samples = vec![Sample {
+        note: 57,
+        velocity: 127,
+        audio_data,
+        sample_rate: rate,
+        channels: 1,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(2000),
+        metadata: SampleMetadata { envelope: Some(envelope), ..Default::default() },
+    }];
+
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_loops");
+    std::fs::remove_dir_all(&temp_dir).ok();
+    let config = ExportConfig {
+        output_directory: temp_dir.clone(),
+        naming_pattern: "Pad_{note_name}_{note}_{velocity}.wav".to_string(),
+        sample_format: AudioFormat::SFZ,
+        apply_detection: false,
+        apply_loop_detection: true,
+        ..ExportConfig::default()
+    };
+    let exporter = SampleExporter::new(config.clone()).unwrap();
+    let files = exporter.export_samples(&samples).unwrap();
+
+    let points = read_loop_points(&files[0]).unwrap().expect("sustained sample should be looped");
+    assert!(points.start < points.end && points.end <= rate as usize * 2);
+    let sfz = std::fs::read_to_string(&files[1]).unwrap();
+    assert!(sfz.contains(&format!("loop_mode=loop_continuous\nloop_start={}\nloop_end={}\n", points.start, points.end - 1)), "{}", sfz);
+
+    let dspreset_exporter = SampleExporter::new(ExportConfig { sample_format: AudioFormat::DecentSampler, ..config }).unwrap();
+    let dspreset = std::fs::read_to_string(dspreset_exporter.generate_dspreset_file(&samples, &files[..1]).unwrap()).unwrap();
+    assert!(dspreset.contains(&format!("loopStart=\"{}\" loopEnd=\"{}\" loopEnabled=\"true\"", points.start, points.end - 1)));
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_classified_export() {
+    // A held pad and a pluck that dies away, classified from their envelopes
+    let rate = 44100;
+    let tone = |gain: &dyn Fn(usize) -> f32| -> Vec<f32> {
+        let mut audio: Vec<f32> = (0..rate as usize * 2)
+            .map(|i| gain(i) * (2.0 * std::f32::consts::PI * 220.0 * i as f32 / rate as f32).sin())
+            .collect();
+        audio.extend(vec![0.0; rate as usize / 5]);
+        audio
+    };
+    let sample = |note: u8, audio_data: Vec<f32>| Sample {
+        note,
+        velocity: 127,
+        audio_data,
+        sample_rate: rate,
+        channels: 1,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(2000),
+        metadata: Default::default(),
+    };
+    let samples = vec![
+        sample(57, tone(&|i| 0.5 * (i as f32 / 441.0).min(1.0))),
+        sample(60, tone(&|i| 0.8 * (-(i as f32) / (0.1 * rate as f32)).exp())),
+    ];
+
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_classified");
+    std::fs::remove_dir_all(&temp_dir).ok();
+    let config = ExportConfig {
+        output_directory: temp_dir.clone(),
+        naming_pattern: "Mix_{note_name}_{note}_{velocity}.wav".to_string(),
+        sample_format: AudioFormat::SFZ,
+        classify_samples: true,
+        ..ExportConfig::default()
+    };
+    let files = SampleExporter::new(config.clone()).unwrap().export_samples(&samples).unwrap();
+    let sfz = std::fs::read_to_string(&files[2]).unwrap();
+    let region = |name: &str| sfz.split("<region>").find(|region| region.contains(name)).unwrap().to_string();
+    assert!(region("Mix_A3_57").contains("loop_mode=loop_continuous"), "{}", sfz);
+    assert!(region("Mix_C4_60").contains("loop_mode=one_shot"), "{}", sfz);
+
+    let dspreset_exporter = SampleExporter::new(ExportConfig { sample_format: AudioFormat::DecentSampler, ..config }).unwrap();
+    let dspreset = std::fs::read_to_string(dspreset_exporter.generate_dspreset_file(&samples, &files[..2]).unwrap()).unwrap();
+    assert!(dspreset.contains("loopEnabled=\"true\"") && dspreset.contains("loopEnabled=\"false\""), "{}", dspreset);
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_hum_removal_export() {
+    // A note over 50 Hz hum, with a second of hum-only tail
+    let rate = 44100;
+    let audio_data: Vec<f32> = (0..rate as usize * 2)
+        .map(|i| {
+            let t = i as f32 / rate as f32;
+            let note = if i < rate as usize { 0.5 * (2.0 * std::f32::consts::PI * 330.0 * t).sin() } else { 0.0 };
+            note + 0.004 * (2.0 * std::f32::consts::PI * 50.0 * t).sin()
+        })
+        .collect();
+    let sample = Sample {
+        note: 64,
+        velocity: 100,
+        audio_data,
+        sample_rate: rate,
+        channels: 1,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(1000),
+        audio_timing: Duration::from_millis(2000),
+        metadata: Default::default(),
+    };
+
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_hum");
+    std::fs::remove_dir_all(&temp_dir).ok();
+    let config = ExportConfig {
+        output_directory: temp_dir.clone(),
+        sample_format: AudioFormat::Wav32BitFloat,
+        apply_detection: false,
+        fade_out_ms: 0.0,
+        remove_hum: true,
+        hooks: Default::default(),
+        catalog: None,
+        overwrite: Default::default(),
+        naming: Default::default(),
+        ..ExportConfig::default()
+    };
+    let files = SampleExporter::new(config).unwrap().export_samples(&[sample]).unwrap();
+
+    let mut reader = hound::WavReader::open(&files[0]).unwrap();
+    let audio: Vec<f32> = reader.samples::<f32>().map(|s| s.unwrap()).collect();
+    let tail_peak = audio[rate as usize * 3 / 2..].iter().fold(0.0f32, |max, s| max.max(s.abs()));
+    assert!(tail_peak < 0.0004, "{}", tail_peak);
+
+    // The QA report records the hum measured in the raw takes, for fixing the grounding
+    let qa = std::fs::read_to_string(temp_dir.join("batcherbird_qa.json")).unwrap();
+    assert!(qa.contains("\"mains_hz\": 50.0"), "{}", qa);
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_dual_input_export() {
+    use batcherbird_core::dual_input::{DualInputConfig, InputSource};
+
+    // Stereo capture: DI on the left input, the amp on the right
+    let capture = Sample {
+        note: 60,
+        velocity: 100,
+        audio_data: (0..4800).flat_map(|i| [0.5 * (i as f32 * 0.05).sin(), 0.3 * (i as f32 * 0.05).sin().signum()]).collect(),
+        sample_rate: 48000,
+        channels: 2,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(1000),
+        metadata: Default::default(),
+    };
+    let inputs = DualInputConfig { direct: InputSource::new("di", &[0]), processed: InputSource::new("amp", &[1]) };
+    let samples = inputs.split(&capture).unwrap().to_vec();
+
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_dual_input");
+    std::fs::remove_dir_all(&temp_dir).ok();
+    let config = ExportConfig {
+        output_directory: temp_dir.clone(),
+        naming_pattern: "Bass_{note_name}_{note}_{velocity}.wav".to_string(),
+        apply_detection: false,
+        ..ExportConfig::default()
+    };
+    let files = SampleExporter::new(config).unwrap().export_samples(&samples).unwrap();
+    assert!(files[0].ends_with("Bass_C4_60_vel100_di.wav"), "{:?}", files);
+    assert!(files[1].ends_with("Bass_C4_60_vel100_amp.wav"), "{:?}", files);
+
+    // Each source is written as its own mono file
+    let reader = hound::WavReader::open(&files[1]).unwrap();
+    assert_eq!(reader.spec().channels, 1);
+    assert_eq!(reader.len(), 4800);
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_registered_instrument_format_export() {
+    use batcherbird_core::instrument_format::{self, InstrumentExporter};
+    use std::path::PathBuf;
+
+    /// One line per region: file, root key and velocity
+    struct KeymapFormat;
+
+    impl InstrumentExporter for KeymapFormat {
+        fn name(&self) -> &str {
+            "keymap"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["keymap"]
+        }
+
+        fn write_samples(&self, exporter: &SampleExporter, samples: &[Sample], wav_files: &[PathBuf]) -> batcherbird_core::Result<PathBuf> {
+            let lines: Vec<String> = samples.iter().zip(wav_files).map(|(sample, file)| {
+                format!("{} {} {}", file.file_name().unwrap().to_string_lossy(), sample.note, sample.velocity)
+            }).collect();
+            let path = exporter.config().output_directory.join(format!("{}.keymap", exporter.instrument_name()));
+            std::fs::write(&path, lines.join("\n"))?;
+            Ok(path)
+        }
+    }
+
+    assert!(AudioFormat::parse("keymap").is_none());
+    instrument_format::register(std::sync::Arc::new(KeymapFormat)).unwrap();
+    let format = AudioFormat::parse("keymap").unwrap();
+    assert!(format.is_instrument());
+
+    let samples: Vec<Sample> = [48, 60].iter().map(|&note| Sample {
+        note,
+        velocity: 90,
+        audio_data: (0..4800).map(|i| 0.5 * (i as f32 * 0.05).sin()).collect(),
+        sample_rate: 48000,
+        channels: 1,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(1000),
+        metadata: Default::default(),
+    }).collect();
+
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_registered_format");
+    std::fs::remove_dir_all(&temp_dir).ok();
+    let config = ExportConfig {
+        output_directory: temp_dir.clone(),
+        naming_pattern: "Keys_{note_name}_{note}_{velocity}.wav".to_string(),
+        sample_format: format,
+        apply_detection: false,
+        ..ExportConfig::default()
+    };
+    let files = SampleExporter::new(config).unwrap().export_samples(&samples).unwrap();
+
+    // WAVs first, then the instrument file the registered format wrote
+    assert_eq!(files.len(), 3);
+    assert!(files[2].ends_with("Keys.keymap"), "{:?}", files);
+    let keymap = std::fs::read_to_string(&files[2]).unwrap();
+    assert_eq!(keymap, "Keys_C3_48_vel090.wav 48 90\nKeys_C4_60_vel090.wav 60 90");
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_instrument_package_export() {
+    use batcherbird_core::package::{self, PackageConfig, PackageFormat};
+    use std::io::Read;
+
+    let samples: Vec<Sample> = [48, 60].iter().map(|&note| Sample {
+        note,
+        velocity: 100,
+        audio_data: (0..4800).map(|i| 0.5 * (i as f32 * 0.05).sin()).collect(),
+        sample_rate: 48000,
+        channels: 1,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(1000),
+        metadata: Default::default(),
+    }).collect();
+
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_package");
+    std::fs::remove_dir_all(&temp_dir).ok();
+    let instrument_dir = temp_dir.join("Keys");
+    let config = ExportConfig {
+        output_directory: instrument_dir.clone(),
+        naming_pattern: "Keys_{note_name}_{note}_{velocity}.wav".to_string(),
+        sample_format: AudioFormat::DecentSampler,
+        apply_detection: false,
+        ..ExportConfig::default()
+    };
+    SampleExporter::new(config).unwrap().export_samples(&samples).unwrap();
+
+    let config = PackageConfig { format: PackageFormat::DsLibrary, license: Some("CC0".to_string()), ..PackageConfig::default() };
+    let archive = package::package_directory(&instrument_dir, &config).unwrap();
+    assert_eq!(archive, temp_dir.join("Keys.dslibrary"));
+
+    // Walk the local headers: name, then the (deflated or stored) body
+    let bytes = std::fs::read(&archive).unwrap();
+    let u16_at = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]) as usize;
+    let u32_at = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) as usize;
+    let mut entries = std::collections::BTreeMap::new();
+    let mut at = 0;
+    while u32_at(at) == 0x04034b50 {
+        let (method, size, name_len) = (u16_at(at + 8), u32_at(at + 18), u16_at(at + 26));
+        let name = String::from_utf8(bytes[at + 30..at + 30 + name_len].to_vec()).unwrap();
+        let body = &bytes[at + 30 + name_len..at + 30 + name_len + size];
+        let mut data = Vec::new();
+        if method == 8 {
+            flate2::read::DeflateDecoder::new(body).read_to_end(&mut data).unwrap();
+        } else {
+            data = body.to_vec();
+        }
+        entries.insert(name, data);
+        at += 30 + name_len + size;
+    }
+
+    // The central directory, which unzip tools read, lists the same entries
+    let end = bytes.len() - 22;
+    assert_eq!(u32_at(end), 0x06054b50);
+    assert_eq!(u16_at(end + 10), entries.len());
+    let mut record = u32_at(end + 16);
+    for _ in 0..entries.len() {
+        assert_eq!(u32_at(record), 0x02014b50);
+        let name_len = u16_at(record + 28);
+        assert!(entries.contains_key(std::str::from_utf8(&bytes[record + 46..record + 46 + name_len]).unwrap()));
+        record += 46 + name_len;
+    }
+    assert_eq!(record, end);
+
+    let names: Vec<&str> = entries.keys().map(String::as_str).collect();
+    assert_eq!(names, [
+        "Keys/DSLibraryInfo.xml", "Keys/Keys.dspreset", "Keys/Keys_C3_48_vel100.wav",
+        "Keys/Keys_C4_60_vel100.wav", "Keys/LICENSE.txt", "Keys/README.md",
+    ]);
+    let readme = String::from_utf8(entries["Keys/README.md"].clone()).unwrap();
+    assert!(readme.contains("Decent Sampler") && readme.contains("CC0"), "{}", readme);
+    assert_eq!(entries["Keys/Keys_C3_48_vel100.wav"], std::fs::read(instrument_dir.join("Keys_C3_48_vel100.wav")).unwrap());
+
+    // A plain zip has no .dspreset requirement; a .dslibrary does
+    std::fs::remove_file(instrument_dir.join("Keys.dspreset")).unwrap();
+    assert!(package::package_directory(&instrument_dir, &config).is_err());
+    let zip = package::package_directory(&instrument_dir, &PackageConfig::default()).unwrap();
+    assert_eq!(zip, temp_dir.join("Keys.zip"));
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_incremental_export_writes_manifest_per_take() {
+    use batcherbird_core::incremental::IncrementalExport;
+    use batcherbird_core::manifest::{SessionManifest, MANIFEST_FILENAME};
+
+    let take = |note: u8| Sample {
+        note,
+        velocity: 100,
+        audio_data: vec![0.0, 0.4, -0.4, 0.2, -0.2, 0.0],
+        sample_rate: 44100,
+        channels: 1,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(1000),
+        metadata: Default::default(),
+    };
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_incremental");
+    std::fs::remove_dir_all(&temp_dir).ok();
+    let config = ExportConfig {
+        output_directory: temp_dir.clone(),
+        naming_pattern: "Inc_{note_name}_{note}_{velocity}.wav".to_string(),
+        sample_format: AudioFormat::SFZ,
+        apply_detection: false,
+        ..ExportConfig::default()
+    };
+
+    // Dropped mid-range: the queued takes are written and listed, no instrument file
+    let export = IncrementalExport::start(ExportConfig { output_directory: temp_dir.join("crashed"), ..config.clone() }).unwrap();
+    export.push(take(60)).unwrap();
+    export.push(take(62)).unwrap();
+    drop(export);
+    let crashed = SessionManifest::load(&temp_dir.join("crashed").join(MANIFEST_FILENAME)).unwrap();
+    assert_eq!(crashed.samples.iter().map(|entry| entry.note).collect::<Vec<_>>(), vec![60, 62]);
+    assert!(crashed.instrument_files.is_empty());
+    assert!(crashed.samples.iter().all(|entry| temp_dir.join("crashed").join(&entry.file).is_file()));
+
+    // Finished: instrument file appended after the WAVs and recorded in the manifest
+    let (exported_tx, exported_rx) = std::sync::mpsc::channel();
+    let export = IncrementalExport::start_observed(config, move |sample, file| {
+        exported_tx.send((sample.note, file.to_path_buf())).unwrap();
+    }).unwrap();
+    for note in [60, 62, 64] {
+        export.push(take(note)).unwrap();
+    }
+    let output = export.finish().unwrap();
+    assert_eq!(output.samples.len(), 3);
+    assert_eq!(output.files.len(), 4);
+    let exported: Vec<(u8, std::path::PathBuf)> = exported_rx.try_iter().collect();
+    assert_eq!(exported.iter().map(|(note, _)| *note).collect::<Vec<_>>(), vec![60, 62, 64]);
+    assert_eq!(exported.iter().map(|(_, file)| file.clone()).collect::<Vec<_>>(), &output.files[..3]);
+    assert!(output.files[3].extension().is_some_and(|ext| ext == "sfz"));
+    let manifest = SessionManifest::load(&temp_dir.join(MANIFEST_FILENAME)).unwrap();
+    assert_eq!(manifest.samples.len(), 3);
+    assert_eq!(manifest.instrument_files.len(), 1);
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_parallel_export_keeps_sample_order() {
+    let samples: Vec<Sample> = (36u8..72).map(|note| Sample {
+        note,
+        velocity: 127,
+        audio_data: (0..4800).map(|i| 0.5 * (i as f32 * 0.05 * note as f32).sin()).collect(),
+        sample_rate: 48000,
+        channels: 1,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(1000),
+        metadata: Default::default(),
+    }).collect();
+
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_parallel");
+    std::fs::remove_dir_all(&temp_dir).ok();
+    let config = ExportConfig {
+        output_directory: temp_dir.clone(),
+        naming_pattern: "Par_{note}_{velocity}.wav".to_string(),
+        sample_format: AudioFormat::Wav24Bit,
+        normalize: true,
+        ..ExportConfig::default()
+    };
+    let exporter = SampleExporter::new(config).unwrap();
+    let files = exporter.export_samples(&samples).unwrap();
+
+    assert_eq!(files.len(), samples.len());
+    for (sample, file) in samples.iter().zip(&files) {
+        assert_eq!(file.file_name().unwrap().to_string_lossy(), format!("Par_{}_vel127.wav", sample.note));
+        assert!(file.is_file());
+    }
+    assert_eq!(exporter.normalization_gains().len(), samples.len());
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_overwrite_policies() {
+    use batcherbird_core::export::OverwritePolicy;
+
+    let sample = Sample {
+        note: 60,
+        velocity: 127,
+        audio_data: vec![0.0, 0.5, -0.5, 0.25, -0.25, 0.0],
+        sample_rate: 44100,
+        channels: 1,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(1000),
+        metadata: Default::default(),
+    };
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_overwrite");
+    std::fs::remove_dir_all(&temp_dir).ok();
+    let exporter = |overwrite| SampleExporter::new(ExportConfig {
+        output_directory: temp_dir.clone(),
+        naming_pattern: "Ow_{note}_{velocity}.wav".to_string(),
+        sample_format: AudioFormat::SFZ,
+        apply_detection: false,
+        overwrite,
+        ..ExportConfig::default()
+    }).unwrap();
+
+    let first = exporter(OverwritePolicy::Overwrite).export_samples(std::slice::from_ref(&sample)).unwrap();
+    assert_eq!(first[0], temp_dir.join("Ow_60_vel127.wav"));
+    let original = std::fs::read(&first[0]).unwrap();
+
+    let renamed = exporter(OverwritePolicy::AutoRenameWithIndex).export_samples(std::slice::from_ref(&sample)).unwrap();
+    assert_eq!(renamed[0], temp_dir.join("Ow_60_vel127_2.wav"));
+    assert_eq!(renamed[1].file_name().unwrap(), "Ow_2.sfz");
+    let sfz = std::fs::read_to_string(&renamed[1]).unwrap();
+    assert!(sfz.contains("Ow_60_vel127_2.wav"), "{}", sfz);
+
+    let louder = Sample { audio_data: sample.audio_data.iter().map(|s| s * 1.5).collect(), ..sample.clone() };
+    let skipped = exporter(OverwritePolicy::Skip).export_sample(&louder).unwrap();
+    assert_eq!(skipped, first[0]);
+    assert_eq!(std::fs::read(&first[0]).unwrap(), original);
+
+    let error = exporter(OverwritePolicy::Error).export_sample(&louder).unwrap_err();
+    assert!(error.to_string().contains("already exists"), "{}", error);
+
+    assert_eq!(OverwritePolicy::parse("rename"), Some(OverwritePolicy::AutoRenameWithIndex));
+    assert_eq!(OverwritePolicy::parse("clobber"), None);
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_naming_tokens_and_collisions() {
+    use batcherbird_core::naming::NamingContext;
+    use batcherbird_core::plan::{PlanAudio, PlanMatrix, SamplingPlan};
+    use batcherbird_core::sampler::{SampleMetadata, SamplingConfig};
+
+    let take = |note, velocity, metadata| Sample {
+        note,
+        velocity,
+        audio_data: vec![0.0, 0.5, -0.5, 0.25, -0.25, 0.0],
+        sample_rate: 44100,
+        channels: 1,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(1000),
+        metadata,
+    };
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_naming");
+    std::fs::remove_dir_all(&temp_dir).ok();
+    let config = ExportConfig {
+        output_directory: temp_dir.clone(),
+        naming_pattern: "{synth}_{session}_{note_name}_{channel}_{patch}_{articulation}.wav".to_string(),
+        apply_detection: false,
+        naming: NamingContext { session: Some("Pads".to_string()), synth: Some("Juno".to_string()) },
+        ..ExportConfig::default()
+    };
+    let exporter = SampleExporter::new(config.clone()).unwrap();
+    let files = exporter.export_samples(&[
+        take(60, 127, SampleMetadata { midi_channel: Some(1), program: Some(12), ..Default::default() }),
+        take(60, 127, SampleMetadata { midi_channel: Some(1), program: Some(12), release: true, ..Default::default() }),
+    ]).unwrap();
+    assert_eq!(files[0].file_name().unwrap(), "Juno_Pads_C4_ch02_prog012_sustain.wav");
+    assert_eq!(files[1].file_name().unwrap(), "Juno_Pads_C4_ch02_prog012_release.wav");
+    assert_eq!(exporter.instrument_name(), "JunoPads");
+
+    // Two velocities of one note under a pattern without {velocity}
+    let colliding = SampleExporter::new(ExportConfig { naming_pattern: "{note_name}.wav".to_string(), ..config.clone() }).unwrap();
+    let error = colliding.export_samples(&[take(62, 64, Default::default()), take(62, 127, Default::default())]).unwrap_err();
+    assert!(error.to_string().contains("D4.wav"), "{}", error);
+    assert!(!temp_dir.join("D4.wav").exists());
+
+    let matrix = PlanMatrix { start_note: 60, end_note: 62, velocities: vec![64, 127], round_robins: 2 };
+    let plan = SamplingPlan::new(&SamplingConfig::default(), &matrix, PlanAudio::default()).unwrap();
+    assert!(colliding.check_plan(&plan).is_err());
+    // Round robins get their own suffix even without {rr}
+    let distinct = SampleExporter::new(ExportConfig { naming_pattern: "{note}_{velocity}.wav".to_string(), ..config.clone() }).unwrap();
+    distinct.check_plan(&plan).unwrap();
+
+    assert!(SampleExporter::new(ExportConfig { naming_pattern: "{note}_{vel}.wav".to_string(), ..config }).is_err());
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_import_maps_names_and_pitch() {
+    use batcherbird_core::import::{import_directory, ImportConfig, NoteSource};
+    use batcherbird_core::library::write_wav;
+
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_import");
+    std::fs::remove_dir_all(&temp_dir).ok();
+    std::fs::create_dir_all(&temp_dir).unwrap();
+    let spec = hound::WavSpec { channels: 1, sample_rate: 44100, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+    let sine: Vec<f32> = (0..44100).map(|i| (2.0 * std::f32::consts::PI * 440.0 * i as f32 / 44100.0).sin() * 0.5).collect();
+    write_wav(&temp_dir.join("Strings-G2-soft.wav"), &sine[..1000], spec).unwrap();
+    write_wav(&temp_dir.join("Pad_C4_60_vel100_rr2.wav"), &sine[..1000], spec).unwrap();
+    write_wav(&temp_dir.join("mystery.wav"), &sine, spec).unwrap();
+
+    let config = ImportConfig { patterns: vec!["{synth}-{note_name}-{input}.wav".to_string()], ..ImportConfig::default() };
+    let imported = import_directory(&temp_dir, &config).unwrap();
+    let mapped: Vec<(String, u8, NoteSource)> = imported.samples.iter()
+        .map(|s| (s.path.file_name().unwrap().to_string_lossy().to_string(), s.sample.note, s.source.clone()))
+        .collect();
+    assert_eq!(mapped[0], ("Pad_C4_60_vel100_rr2.wav".to_string(), 60, NoteSource::BatcherbirdName));
+    assert_eq!(imported.samples[0].sample.metadata.round_robin, Some(2));
+    assert_eq!((mapped[1].0.as_str(), mapped[1].1), ("Strings-G2-soft.wav", 43));
+    assert_eq!(mapped[1].2, NoteSource::Pattern(0));
+    assert_eq!(imported.samples[1].sample.metadata.input_source.as_deref(), Some("soft"));
+    assert_eq!(mapped[2].1, 69);
+    assert!(matches!(mapped[2].2, NoteSource::Pitch { .. }));
+
+    let names_only = import_directory(&temp_dir, &ImportConfig { detect_pitch: false, ..config }).unwrap();
+    assert_eq!(names_only.unrecognized, vec![temp_dir.join("mystery.wav")]);
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_import_library_writes_preset_per_folder() {
+    use batcherbird_core::import::{import_library, ImportConfig};
+    use batcherbird_core::library::write_wav;
+
+    let root = std::env::temp_dir().join("batcherbird_test_import_library");
+    std::fs::remove_dir_all(&root).ok();
+    std::fs::create_dir_all(root.join("Keys")).unwrap();
+    std::fs::create_dir_all(root.join("Pads")).unwrap();
+    let spec = hound::WavSpec { channels: 1, sample_rate: 44100, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+    let sine: Vec<f32> = (0..44100).map(|i| (2.0 * std::f32::consts::PI * 440.0 * i as f32 / 44100.0).sin() * 0.5).collect();
+    write_wav(&root.join("Keys/EP_C4_60_vel100.wav"), &sine[..4410], spec).unwrap();
+    // Unnamed takes of the same pitch become round robins
+    write_wav(&root.join("Pads/take one.wav"), &sine, spec).unwrap();
+    write_wav(&root.join("Pads/take two.wav"), &sine, spec).unwrap();
+
+    let output = root.join("Imported");
+    let export = ExportConfig {
+        output_directory: output.clone(),
+        naming_pattern: "{session}_{note_name}_{note}_{velocity}.wav".to_string(),
+        sample_format: AudioFormat::SFZ,
+        apply_detection: false,
+        normalize: false,
+        fade_out_ms: 0.0,
+        ..ExportConfig::default()
+    };
+    let presets = import_library(&root, &output, &ImportConfig::default(), &export).unwrap();
+    assert_eq!(presets.len(), 2);
+    assert_eq!(presets[0].output_directory, output.join("Keys"));
+    assert!(output.join("Keys/Keys_C4_60_vel100.wav").exists());
+    assert!(output.join("Pads/Pads_A4_69_vel127_rr1.wav").exists());
+    assert!(output.join("Pads/Pads_A4_69_vel127_rr2.wav").exists());
+    let sfz = presets[1].files.iter().find(|file| file.extension().is_some_and(|ext| ext == "sfz")).unwrap();
+    assert!(std::fs::read_to_string(sfz).unwrap().contains("Pads_A4_69_vel127_rr2.wav"));
+
+    // The output folder inside the tree isn't imported again
+    let again = import_library(&root, &output, &ImportConfig::default(), &export).unwrap();
+    assert_eq!(again.len(), 2);
+
+    std::fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn test_dspreset_ui_velocity_layers_and_round_robins() {
+    use batcherbird_core::sampler::SampleMetadata;
+
+    let take = |velocity, round_robin| Sample {
+        note: 60,
+        velocity,
+        audio_data: vec![0.0, 0.5, -0.5, 0.25, -0.25, 0.0],
+        sample_rate: 44100,
+        channels: 1,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(1000),
+        metadata: SampleMetadata { round_robin, ..Default::default() },
+    };
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_dspreset_ui");
+    std::fs::remove_dir_all(&temp_dir).ok();
+    std::fs::create_dir_all(&temp_dir).unwrap();
+    let background = std::env::temp_dir().join("batcherbird_test_background.png");
+    std::fs::write(&background, b"png").unwrap();
+
+    let exporter = SampleExporter::new(ExportConfig {
+        output_directory: temp_dir.clone(),
+        naming_pattern: "Ui_{note}_{velocity}.wav".to_string(),
+        sample_format: AudioFormat::DecentSampler,
+        apply_detection: false,
+        ui_background: Some(background.clone()),
+        ..ExportConfig::default()
+    }).unwrap();
+    let files = exporter.export_samples(&[take(40, None), take(100, Some(1)), take(100, Some(2))]).unwrap();
+    let dspreset = std::fs::read_to_string(files.last().unwrap()).unwrap();
+
+    assert!(dspreset.contains("<ui width=\"812\" height=\"375\" bgImage=\"batcherbird_test_background.png\">"), "{}", dspreset);
+    assert!(temp_dir.join("batcherbird_test_background.png").exists());
+    for parameter in ["ENV_ATTACK", "ENV_DECAY", "ENV_SUSTAIN", "ENV_RELEASE"] {
+        assert!(dspreset.contains(&format!("parameter=\"{}\"", parameter)), "{}", dspreset);
+    }
+    assert!(dspreset.contains("<group seqMode=\"round_robin\" seqLength=\"2\">"), "{}", dspreset);
+    // Softest layer first, each reaching up to its own velocity and the loudest to 127
+    let soft = dspreset.find("loVel=\"1\" hiVel=\"40\"").unwrap();
+    let loud = dspreset.find("loVel=\"41\" hiVel=\"127\" seqPosition=\"2\"").unwrap();
+    assert!(soft < loud, "{}", dspreset);
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+    std::fs::remove_file(&background).ok();
+}
+
+#[test]
+fn test_sfz_key_spans_and_velocity_tracking() {
+    let take = |note, velocity| Sample {
+        note,
+        velocity,
+        audio_data: vec![0.0, 0.5, -0.5, 0.25, -0.25, 0.0],
+        sample_rate: 44100,
+        channels: 1,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(1000),
+        metadata: Default::default(),
+    };
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_sfz_key_spans");
+    std::fs::remove_dir_all(&temp_dir).ok();
+
+    let exporter = SampleExporter::new(ExportConfig {
+        output_directory: temp_dir.clone(),
+        naming_pattern: "Span_{note}_{velocity}.wav".to_string(),
+        sample_format: AudioFormat::SFZ,
+        apply_detection: false,
+        ..ExportConfig::default()
+    }).unwrap();
+    let files = exporter.export_samples(&[take(48, 127), take(55, 127), take(60, 127), take(60, 64)]).unwrap();
+    let sfz = std::fs::read_to_string(files.last().unwrap()).unwrap();
+
+    // Two velocity layers share the level range between them
+    assert!(sfz.contains("<global>\nampeg_release=0.5\namp_veltrack=50\n"), "{}", sfz);
+    // Skipped keys go to the nearest sample, and the outer samples reach as far out as in
+    assert!(sfz.contains("pitch_keycenter=48\nlokey=45\nhikey=51\n"), "{}", sfz);
+    assert!(sfz.contains("pitch_keycenter=55\nlokey=52\nhikey=57\n"), "{}", sfz);
+    assert!(sfz.contains("pitch_keycenter=60\nlokey=58\nhikey=62\n"), "{}", sfz);
+    // A layer with a single sample keeps it on its own key
+    assert!(sfz.contains("Span_60_vel064.wav\nkey=60\n"), "{}", sfz);
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_velocity_crossfades() {
+    let take = |velocity| Sample {
+        note: 60,
+        velocity,
+        audio_data: vec![0.0, 0.5, -0.5, 0.25, -0.25, 0.0],
+        sample_rate: 44100,
+        channels: 1,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(1000),
+        metadata: Default::default(),
+    };
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_velocity_crossfades");
+    std::fs::remove_dir_all(&temp_dir).ok();
+    let config = ExportConfig {
+        output_directory: temp_dir.clone(),
+        naming_pattern: "Xfade_{note}_{velocity}.wav".to_string(),
+        sample_format: AudioFormat::SFZ,
+        apply_detection: false,
+        velocity_crossfade: Some(50.0),
+        ..ExportConfig::default()
+    };
+    let samples = [take(64), take(127)];
+
+    // Half of the narrower layer overlaps, centred on the split
+    let files = SampleExporter::new(config.clone()).unwrap().export_samples(&samples).unwrap();
+    let sfz = std::fs::read_to_string(files.last().unwrap()).unwrap();
+    assert!(sfz.contains("lovel=1\nhivel=79\nxfout_lovel=48\nxfout_hivel=79\n"), "{}", sfz);
+    assert!(sfz.contains("lovel=48\nhivel=127\nxfin_lovel=48\nxfin_hivel=79\n"), "{}", sfz);
+
+    // Decent Sampler gets the fades as fixed-gain velocity steps
+    let exporter = SampleExporter::new(ExportConfig { sample_format: AudioFormat::DecentSampler, ..config.clone() }).unwrap();
+    let dspreset = std::fs::read_to_string(exporter.generate_dspreset_file(&samples, &files[..2]).unwrap()).unwrap();
+    assert!(dspreset.contains("loVel=\"1\" hiVel=\"48\" />"), "{}", dspreset);
+    assert!(dspreset.contains("loVel=\"49\" hiVel=\"52\" volume=\"-0.04dB\""), "{}", dspreset);
+    assert!(dspreset.contains("loVel=\"49\" hiVel=\"52\" volume=\"-20.17dB\""), "{}", dspreset);
+    assert!(dspreset.contains("loVel=\"81\" hiVel=\"127\" />"), "{}", dspreset);
+    assert_eq!(dspreset.matches("<sample ").count(), 2 * (1 + 8));
+
+    assert!(SampleExporter::new(ExportConfig { velocity_crossfade: Some(150.0), ..config }).is_err());
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_gain_matching_writes_region_volumes() {
+    use batcherbird_core::sampler::SampleMetadata;
+
+    // A bass-heavy patch: the low note is twice as loud as the middle one, the top one quieter
+    let take = |note: u8, level: f32, release: bool| Sample {
+        note,
+        velocity: 100,
+        audio_data: (0..24000).map(|i| level * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / 48000.0).sin()).collect(),
+        sample_rate: 48000,
+        channels: 1,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(1000),
+        metadata: SampleMetadata { release, ..Default::default() },
+    };
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_gain_matching");
+    std::fs::remove_dir_all(&temp_dir).ok();
+    let config = ExportConfig {
+        output_directory: temp_dir.clone(),
+        naming_pattern: "Match_{note}_{velocity}.wav".to_string(),
+        sample_format: AudioFormat::SFZ,
+        apply_detection: false,
+        gain_matching: true,
+        ..ExportConfig::default()
+    };
+    let samples = [take(48, 0.5, false), take(60, 0.25, false), take(72, 0.1, false), take(72, 0.02, true)];
+    let files = SampleExporter::new(config.clone()).unwrap().export_samples(&samples).unwrap();
+    let sfz = std::fs::read_to_string(files.last().unwrap()).unwrap();
+
+    // Matched to the middle note; the release tail follows its note
+    assert!(sfz.contains("volume=-6.02\n"), "{}", sfz);
+    assert_eq!(sfz.matches("volume=7.96\n").count(), 2, "{}", sfz);
+    assert_eq!(sfz.matches("volume=").count(), 3);
+    // The recordings themselves keep their level
+    let (audio, _) = batcherbird_core::library::read_wav(&files[0]).unwrap();
+    assert!((audio.iter().fold(0.0f32, |peak, s| peak.max(s.abs())) - 0.5).abs() < 1e-3);
+
+    let exporter = SampleExporter::new(ExportConfig { sample_format: AudioFormat::DecentSampler, ..config }).unwrap();
+    let dspreset = std::fs::read_to_string(exporter.generate_dspreset_file(&samples, &files[..4]).unwrap()).unwrap();
+    assert!(dspreset.contains("volume=\"-6.02dB\""), "{}", dspreset);
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn test_folder_layouts() {
+    use batcherbird_core::export::FolderLayout;
+    use batcherbird_core::library::LibrarySession;
+
+    let take = |velocity| Sample {
+        note: 60,
+        velocity,
+        audio_data: vec![0.0, 0.5, -0.5, 0.25, -0.25, 0.0],
+        sample_rate: 44100,
+        channels: 1,
+        recorded_at: SystemTime::now(),
+        midi_timing: Duration::from_millis(100),
+        audio_timing: Duration::from_millis(1000),
+        metadata: Default::default(),
+    };
+    let samples = [take(64), take(127)];
+    let temp_dir = std::env::temp_dir().join("batcherbird_test_folder_layouts");
+    std::fs::remove_dir_all(&temp_dir).ok();
+    let config = |layout, sample_format| ExportConfig {
+        output_directory: temp_dir.join(format!("{:?}", layout)),
+        naming_pattern: "Layout_{note}_{velocity}.wav".to_string(),
+        sample_format,
+        apply_detection: false,
+        layout,
+        ..ExportConfig::default()
+    };
+
+    // samples/ next to an SFZ whose default_path points there
+    let files = SampleExporter::new(config(FolderLayout::SamplesFolder, AudioFormat::SFZ)).unwrap().export_samples(&samples).unwrap();
+    let root = temp_dir.join("SamplesFolder");
+    assert_eq!(files[0], root.join("samples").join("Layout_60_vel064.wav"));
+    assert_eq!(files[2].parent(), Some(root.as_path()));
+    let sfz = std::fs::read_to_string(&files[2]).unwrap();
+    assert!(sfz.contains("<control>\ndefault_path=samples/\n"), "{}", sfz);
+    assert!(sfz.contains("sample=Layout_60_vel064.wav\n"), "{}", sfz);
+    assert_eq!(LibrarySession::load(&root).unwrap().samples.len(), 2);
+
+    // Per-velocity folders, referenced by relative path
+    let files = SampleExporter::new(config(FolderLayout::PerVelocity, AudioFormat::DecentSampler)).unwrap().export_samples(&samples).unwrap();
+    assert!(files[1].ends_with("vel127/Layout_60_vel127.wav"));
+    let dspreset = std::fs::read_to_string(&files[2]).unwrap();
+    assert!(dspreset.contains("path=\"vel064/Layout_60_vel064.wav\""), "{}", dspreset);
+
+    // Flat output no longer claims a samples/ folder
+    let files = SampleExporter::new(config(FolderLayout::Flat, AudioFormat::SFZ)).unwrap().export_samples(&samples).unwrap();
+    let sfz = std::fs::read_to_string(&files[2]).unwrap();
+    assert!(!sfz.contains("default_path") && sfz.contains("sample=Layout_60_vel127.wav\n"), "{}", sfz);
+
+    std::fs::remove_dir_all(&temp_dir).ok();
+}
@@ -234,6 +234,49 @@ async function loadMidiDevices() {
     }
 }
 
+// Route capture through the chosen input; monitoring is restarted on the new device
+async function applyAudioInputDevice(name) {
+    try {
+        const wasMonitoring = await invoke('set_audio_input_device', { name: name || null });
+        if (wasMonitoring) {
+            await startInputMonitoring();
+        }
+    } catch (error) {
+        showStatus(`Failed to select audio input: ${error}`, 'error');
+    }
+    await showAudioInputCapabilities();
+}
+
+// Show what the selected input can record with (rates, channels, formats)
+async function showAudioInputCapabilities() {
+    const info = document.getElementById('audio-input-capabilities');
+    const select = document.getElementById('audio-input-select');
+    if (!info || !select) return;
+    if (select.value === '') {
+        info.textContent = '';
+        return;
+    }
+    try {
+        const caps = await invoke('describe_audio_input_device', { index: parseInt(select.value) });
+        const rates = caps.sample_rates.map(rate => `${rate / 1000}`).join(' / ');
+        const current = caps.default_sample_rate
+            ? ` · recording at ${caps.default_sample_rate / 1000} kHz, ${caps.default_channels} ch ${caps.default_format}`
+            : '';
+        info.textContent = `${rates} kHz · ${caps.channel_counts.join(' / ')} ch · ${caps.sample_formats.join(', ')}${current}`;
+    } catch (error) {
+        info.textContent = `Capabilities unavailable: ${error}`;
+    }
+}
+
+// Route playback (auditions, previews) through the chosen output
+async function applyAudioOutputDevice(name) {
+    try {
+        await invoke('set_audio_output_device', { name: name || null });
+    } catch (error) {
+        showStatus(`Failed to select audio output: ${error}`, 'error');
+    }
+}
+
 async function loadAudioInputDevices() {
     const select = document.getElementById('audio-input-select');
     
@@ -256,6 +299,9 @@ async function loadAudioInputDevices() {
             
             select.appendChild(option);
         });
+        if (devices.includes(selectedAudioInputDevice)) {
+            await applyAudioInputDevice(selectedAudioInputDevice);
+        }
         
         showStatus(`Found ${devices.length} audio input devices`, 'success');
     } catch (error) {
@@ -290,6 +336,9 @@ async function loadAudioOutputDevices() {
             
             select.appendChild(option);
         });
+        if (devices.includes(selectedAudioOutputDevice)) {
+            await applyAudioOutputDevice(selectedAudioOutputDevice);
+        }
         
         showStatus(`Found ${devices.length} audio output devices`, 'success');
     } catch (error) {
@@ -368,7 +417,10 @@ window.addEventListener('DOMContentLoaded', () => {
                 console.log('Selected audio input device:', selectedAudioInputDevice, 'at index:', selectedIndex);
                 showStatus(`Selected audio input: ${selectedAudioInputDevice}`, 'success');
                 savePreferences();
+            } else {
+                selectedAudioInputDevice = '';
             }
+            applyAudioInputDevice(selectedAudioInputDevice);
         });
     } else {
         console.error('❌ Cannot add event listener - audio-input-select not found');
@@ -386,7 +438,10 @@ window.addEventListener('DOMContentLoaded', () => {
                 console.log('Selected audio output device:', selectedAudioOutputDevice, 'at index:', selectedIndex);
                 showStatus(`Selected audio output: ${selectedAudioOutputDevice}`, 'success');
                 savePreferences();
+            } else {
+                selectedAudioOutputDevice = '';
             }
+            applyAudioOutputDevice(selectedAudioOutputDevice);
         });
     } else {
         console.error('❌ Cannot add event listener - audio-output-select not found');
@@ -614,9 +669,42 @@ async function previewNote() {
     }
 }
 
+// record_sample/record_range return straight away; the result follows as a recording-complete event
+async function recordInBackground(command, args) {
+    let finish;
+    const done = new Promise((resolve, reject) => { finish = { resolve, reject }; });
+    const unlisten = await window.__TAURI__.event.listen('recording-complete', event => {
+        if (event.payload.command !== command) return;
+        event.payload.ok ? finish.resolve(event.payload.message) : finish.reject(event.payload.message);
+    });
+    try {
+        await invoke(command, args);
+        return await done;
+    } finally {
+        unlisten();
+    }
+}
+
+// Cut the current note short and silence the synth
+async function cancelRecording() {
+    try {
+        await invoke('cancel_recording');
+    } catch (error) {
+        console.log('ℹ️ Nothing to cancel:', error);
+    }
+}
+
+let isSampleRecording = false;
+
 async function recordSample() {
     console.log('🔴 recordSample() called - starting recording process...');
     
+    // The record button doubles as cancel while a take is running
+    if (isSampleRecording) {
+        await cancelRecording();
+        return;
+    }
+    
     const note = parseInt(document.getElementById('note-select').value);
     const velocity = parseInt(document.getElementById('velocity-input').value);
     const duration = parseInt(document.getElementById('duration-input').value);
@@ -629,9 +717,9 @@ async function recordSample() {
     const recordingText = document.getElementById('recording-text');
     
     try {
-        // Disable record button and show recording status
-        recordBtn.disabled = true;
-        recordBtn.textContent = '⏹️ Recording...';
+        // Turn the record button into cancel and show recording status
+        isSampleRecording = true;
+        recordBtn.textContent = '⏹️ Cancel';
         recordingStatus.style.display = 'block';
         progressFill.style.width = '0%';
         recordingText.textContent = 'Starting recording...';
@@ -662,7 +750,7 @@ async function recordSample() {
         console.log('📡 Calling backend record_sample with params:', { note, velocity, duration, outputDirectory, sampleName, exportFormat, creatorName, instrumentDescription });
         
         try {
-            const result = await invoke('record_sample', { 
+            const result = await recordInBackground('record_sample', { 
                 note: note, 
                 velocity: velocity, 
                 duration: duration,
@@ -670,7 +758,9 @@ async function recordSample() {
                 sampleName: sampleName || null,
                 exportFormat: exportFormat,
                 creatorName: creatorName || null,
-                instrumentDescription: instrumentDescription || null
+                instrumentDescription: instrumentDescription || null,
+                sidecarEdits: document.getElementById('sidecar-edits')?.checked || false,
+                overwritePolicy: document.getElementById('overwrite-policy')?.value || 'overwrite'
             });
             console.log('✅ Backend returned result:', result);
             
@@ -716,8 +806,8 @@ async function recordSample() {
         showStatus(`Recording failed: ${error}`, 'error');
         recordingStatus.style.display = 'none';
     } finally {
-        // Re-enable record button
-        recordBtn.disabled = false;
+        // Restore record button
+        isSampleRecording = false;
         recordBtn.textContent = '🔴 Record Sample';
     }
 }
@@ -749,7 +839,7 @@ function testButtonsWork() {
     showStatus('Button test successful!', 'success');
 }
 
-// Show samples folder in Finder
+// Show samples folder in the file manager
 async function showSamplesInFinder() {
     console.log('📁 showSamplesInFinder() called');
     try {
@@ -802,7 +892,7 @@ function getDurationForVelocityLayers() {
     return durationInput ? parseInt(durationInput.value) : 2000; // Default to 2000ms if not found
 }
 
-// Range sampling functions - using individual record_sample calls for real progress
+// Range sampling: one record_range run, with per-note progress from its range-note events
 async function recordRange() {
     console.log('🎹 recordRange() called - starting range recording process...');
     
@@ -856,6 +946,7 @@ async function recordRange() {
             ? `Recording ${totalNotes} notes × ${velocities.length} velocities...`
             : `Recording ${totalNotes} notes...`;
         rangeCurrentNote.textContent = `Starting range recording...`;
+        document.getElementById('range-note-list').innerHTML = '';
         
         // Hide previous range waveform if shown
         hideWaveform(true);
@@ -880,174 +971,55 @@ async function recordRange() {
         const sampleNameInput = document.getElementById('sample-name');
         const outputDirectory = outputDirInput ? outputDirInput.value : '';
         const sampleName = sampleNameInput ? sampleNameInput.value.trim() : '';
+        const velocityCurveSelect = document.getElementById('velocity-curve-select');
+        const velocityCurve = velocityCurveSelect ? velocityCurveSelect.value : 'linear';
         
-        // Function to convert MIDI note to name
-        const noteToName = (note) => {
-            const noteNames = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
-            const octave = Math.floor(note / 12) - 1;
-            const noteName = noteNames[note % 12];
-            return `${noteName}${octave}`;
-        };
-        
-        // Record each note individually for real progress AND working stop functionality
-        console.log('📡 Starting individual note recording loop with real stop capability...');
-        
-        let successfulRecordings = 0;
-        
-        // Record each note individually for real progress AND working stop functionality
-        console.log('📡 Starting individual note recording loop with real stop capability...');
+        const exportFormat = document.getElementById('export-format')?.value || 'wav';
+        const creatorName = document.getElementById('creator-name')?.value?.trim() || '';
+        const instrumentDescription = document.getElementById('instrument-description')?.value?.trim() || '';
         
-        for (let i = 0; i < velocities.length; i++) {
-            const velocity = velocities[i];
-            
-            if (rangeRecordingAbortController.signal.aborted) {
-                console.log('⚠️ Range recording aborted by user');
-                break;
-            }
-            
-            for (let currentNote = startNote; currentNote <= endNote; currentNote++) {
-                if (rangeRecordingAbortController.signal.aborted) {
-                    console.log('⚠️ Range recording aborted by user');
-                    break;
-                }
-                
-                const currentNoteName = noteToName(currentNote);
-                const sampleIndex = (i * totalNotes) + (currentNote - startNote) + 1;
-                
-                // Update UI
-                rangeCurrentNote.textContent = `${currentNoteName} (${sampleIndex}/${totalSamples})`;
-                rangeVelocityInfo.textContent = velocities.length > 1 
-                    ? `Velocity ${velocity} (${i + 1}/${velocities.length})`
-                    : `Velocity ${velocity}`;
-                
-                const progress = ((sampleIndex - 1) / totalSamples) * 100;
-                rangeProgressFill.style.width = `${progress}%`;
-                
-                try {
-                    console.log(`📡 Recording note ${currentNote} (${currentNoteName}) at velocity ${velocity}...`);
-                    
-                    // Record individual sample (WAV only now)
-                    const result = await invoke('record_sample', { 
-                        note: currentNote, 
-                        velocity: velocity, 
-                        duration: duration,
-                        outputDirectory: outputDirectory,
-                        sampleName: sampleName || null,
-                        exportFormat: 'wav24bit', // Always WAV for individual samples
-                        creatorName: '',  // No metadata for individual WAV files
-                        instrumentDescription: ''  // No metadata for individual WAV files
-                    });
-                    
-                    console.log(`✅ Note ${currentNoteName} recorded successfully`);
-                    successfulRecordings++;
-                    
-                } catch (error) {
-                    console.error(`❌ Failed to record note ${currentNoteName}:`, error);
-                    showStatus(`Error recording ${currentNoteName}: ${error}`, 'error');
-                    // Continue with other notes
-                }
+        // Progress follows the backend's range-note events; the per-note list fills from the same events
+        let finishedSamples = 0;
+        const unlistenProgress = await window.__TAURI__.event.listen('range-note', event => {
+            const { status, note, velocity } = event.payload;
+            if (status === 'started') {
+                rangeCurrentNote.textContent = `${midiNoteName(note)} (${finishedSamples + 1}/${totalSamples})`;
+                rangeVelocityInfo.textContent = `Velocity ${velocity}`;
+            } else if (status === 'exported' || status === 'failed') {
+                finishedSamples++;
+                rangeProgressFill.style.width = `${Math.min(finishedSamples / totalSamples, 1) * 100}%`;
             }
+        });
+        
+        // One backend run records every note and velocity layer and writes the instrument file
+        console.log('📡 Calling backend record_range...');
+        let result;
+        try {
+            result = await recordInBackground('record_range', {
+                startNote: startNote,
+                endNote: endNote,
+                velocity: velocities[0],
+                velocities: velocities,
+                duration: duration,
+                outputDirectory: outputDirectory,
+                sampleName: sampleName || null,
+                exportFormat: exportFormat,
+                creatorName: creatorName || null,
+                instrumentDescription: instrumentDescription || null,
+                velocityCurve: velocityCurve,
+                sidecarEdits: document.getElementById('sidecar-edits')?.checked || false,
+                overwritePolicy: document.getElementById('overwrite-policy')?.value || 'overwrite'
+            });
+        } finally {
+            unlistenProgress();
         }
         
         // Final UI update
         if (isRangeRecording) {
             rangeProgressFill.style.width = '100%';
-            if (successfulRecordings === totalSamples) {
-                rangeRecordingText.textContent = 'Range recording complete!';
-                rangeCurrentNote.textContent = `✅ Completed ${successfulRecordings} of ${totalSamples} samples successfully`;
-                showStatus(`Range recording complete! ${successfulRecordings} samples saved.`, 'success');
-                
-                // Generate instrument files (.dspreset/.sfz) from the recorded samples
-                const exportFormat = document.getElementById('export-format')?.value;
-                if (exportFormat && exportFormat !== 'wav') {
-                    try {
-                        console.log(`🎼 Generating ${exportFormat} instrument file from recorded samples...`);
-                        rangeRecordingText.textContent = 'Generating instrument files...';
-                        rangeCurrentNote.textContent = `Creating ${exportFormat} file...`;
-                        
-                        // Get creator info from metadata fields if they exist
-                        const creatorNameInput = document.getElementById('creator-name');
-                        const instrumentDescriptionInput = document.getElementById('instrument-description');
-                        const creatorName = creatorNameInput ? creatorNameInput.value.trim() : '';
-                        const instrumentDescription = instrumentDescriptionInput ? instrumentDescriptionInput.value.trim() : '';
-                        
-                        // Handle different export format cases
-                        if (exportFormat === 'all') {
-                            // Generate both SFZ and Decent Sampler files
-                            let baseDirectory = outputDirectory;
-                            if (!baseDirectory) {
-                                baseDirectory = sampleName ? 
-                                    `/Users/dryan/Desktop/Batcherbird Samples/${sampleName}` : 
-                                    '/Users/dryan/Desktop/Batcherbird Samples';
-                            }
-                            
-                            // Generate SFZ file
-                            rangeCurrentNote.textContent = `Creating SFZ file...`;
-                            const sfzResult = await invoke('generate_instrument_files', {
-                                directory: baseDirectory,
-                                exportFormat: 'sfz',
-                                sampleName: sampleName || null,
-                                creatorName: creatorName || null,
-                                instrumentDescription: instrumentDescription || null
-                            });
-                            console.log(`✅ SFZ file generated: ${sfzResult}`);
-                            
-                            // Generate Decent Sampler file
-                            rangeCurrentNote.textContent = `Creating Decent Sampler file...`;
-                            const dsResult = await invoke('generate_instrument_files', {
-                                directory: baseDirectory,
-                                exportFormat: 'decentsampler',
-                                sampleName: sampleName || null,
-                                creatorName: creatorName || null,
-                                instrumentDescription: instrumentDescription || null
-                            });
-                            console.log(`✅ Decent Sampler file generated: ${dsResult}`);
-                            
-                            rangeRecordingText.textContent = 'Range recording complete!';
-                            rangeCurrentNote.textContent = `✅ Generated SFZ + Decent Sampler files with ${successfulRecordings} samples`;
-                            showStatus(`Range recording complete! ${successfulRecordings} samples + SFZ + Decent Sampler files saved.`, 'success');
-                            
-                        } else {
-                            // Single format generation
-                            let backendFormat = exportFormat;
-                            if (exportFormat === 'kontakt') {
-                                backendFormat = 'sfz'; // Use SFZ as stepping stone to Kontakt
-                            }
-                            
-                            // Build the correct directory path that matches where samples were actually saved
-                            let targetDirectory = outputDirectory;
-                            if (!targetDirectory) {
-                                targetDirectory = sampleName ? 
-                                    `/Users/dryan/Desktop/Batcherbird Samples/${sampleName}` : 
-                                    '/Users/dryan/Desktop/Batcherbird Samples';
-                            }
-                            
-                            const instrumentResult = await invoke('generate_instrument_files', {
-                                directory: targetDirectory,
-                                exportFormat: backendFormat,
-                                sampleName: sampleName || null,
-                                creatorName: creatorName || null,
-                                instrumentDescription: instrumentDescription || null
-                            });
-                            
-                            console.log(`✅ Instrument file generated: ${instrumentResult}`);
-                            rangeRecordingText.textContent = 'Range recording complete!';
-                            rangeCurrentNote.textContent = `✅ Generated ${exportFormat} file with ${successfulRecordings} samples`;
-                            showStatus(`Range recording complete! ${successfulRecordings} samples + ${exportFormat} file saved.`, 'success');
-                        }
-                        
-                    } catch (error) {
-                        console.error(`❌ Failed to generate ${exportFormat} file:`, error);
-                        rangeRecordingText.textContent = 'Range recording complete (instrument file failed)';
-                        rangeCurrentNote.textContent = `⚠️ WAV files saved, but ${exportFormat} generation failed`;
-                        showStatus(`Range recording complete! ${successfulRecordings} samples saved, but ${exportFormat} generation failed.`, 'warning');
-                    }
-                }
-            } else {
-                rangeRecordingText.textContent = 'Range recording finished with errors';
-                rangeCurrentNote.textContent = `⚠️ Completed ${successfulRecordings} of ${totalSamples} samples`;
-                showStatus(`Range recording finished: ${successfulRecordings} of ${totalSamples} samples saved.`, 'error');
-            }
+            rangeRecordingText.textContent = 'Range recording complete!';
+            rangeCurrentNote.textContent = `✅ ${finishedSamples} of ${totalSamples} samples processed`;
+            showStatus(result, 'success');
         }
         
         // Hide recording status after 5 seconds
@@ -1084,6 +1056,7 @@ function stopRangeRecording() {
     if (rangeRecordingAbortController) {
         rangeRecordingAbortController.abort();
     }
+    cancelRecording();
     
     // Update UI immediately
     const rangeRecordingText = document.getElementById('range-recording-text');
@@ -1474,7 +1447,7 @@ async function recordNotesWithVelocityLayersResponsiveUI(startNote, endNote, vel
                 const instrumentDescription = document.getElementById('instrument-description')?.value?.trim() || '';
                 
                 // Record individual sample with specific velocity
-                const result = await invoke('record_sample', { 
+                const result = await recordInBackground('record_sample', { 
                     note: currentNote, 
                     velocity: velocity, 
                     duration: duration,
@@ -1482,7 +1455,9 @@ async function recordNotesWithVelocityLayersResponsiveUI(startNote, endNote, vel
                     sampleName: sampleName || null,
                     exportFormat: exportFormat,
                     creatorName: creatorName || null,
-                    instrumentDescription: instrumentDescription || null
+                    instrumentDescription: instrumentDescription || null,
+                    sidecarEdits: document.getElementById('sidecar-edits')?.checked || false,
+                    overwritePolicy: document.getElementById('overwrite-policy')?.value || 'overwrite'
                 });
                 
                 console.log(`✅ Sample ${noteName} vel ${velocity} recorded successfully: ${result}`);
@@ -1577,6 +1552,102 @@ function openSetupModal() {
         loadMidiDevicesWithStatus();
         loadAudioInputDevicesWithStatus();
         loadAudioOutputDevicesWithStatus();
+        loadMidiMonitorInputs();
+    }
+}
+
+// === MIDI monitor ===
+
+// Rows kept in the monitor log; older ones scroll off
+const MIDI_MONITOR_MAX_ROWS = 200;
+let midiMonitorUnlisten = null;
+
+async function loadMidiMonitorInputs() {
+    const select = document.getElementById('midi-monitor-input');
+    if (!select) return;
+    const channelSelect = document.getElementById('midi-monitor-channel');
+    if (channelSelect && channelSelect.options.length === 1) {
+        for (let channel = 1; channel <= 16; channel++) {
+            channelSelect.add(new Option(`Ch ${channel}`, channel.toString()));
+        }
+    }
+    try {
+        const devices = await invoke('list_midi_input_devices');
+        const previous = select.value;
+        select.innerHTML = devices.length === 0
+            ? '<option value="">No MIDI inputs found</option>'
+            : '<option value="">Select MIDI input...</option>';
+        devices.forEach((device, index) => {
+            const option = document.createElement('option');
+            option.value = index.toString();
+            option.textContent = device;
+            option.selected = option.value === previous;
+            select.appendChild(option);
+        });
+    } catch (error) {
+        console.error('❌ Failed to load MIDI inputs:', error);
+    }
+}
+
+function showMidiMessage(message) {
+    const log = document.getElementById('midi-monitor-log');
+    if (!log) return;
+    const row = document.createElement('div');
+    row.className = 'take-analysis-row';
+    row.innerHTML = `<span>${(message.time_ms / 1000).toFixed(3)}s</span><span style="flex: 1;">${message.description}</span>`;
+    log.appendChild(row);
+    while (log.children.length > MIDI_MONITOR_MAX_ROWS) {
+        log.removeChild(log.firstChild);
+    }
+    log.scrollTop = log.scrollHeight;
+}
+
+async function toggleMidiMonitor() {
+    if (midiMonitorUnlisten) {
+        await stopMidiMonitor();
+        return;
+    }
+    const input = document.getElementById('midi-monitor-input')?.value;
+    if (!input) {
+        showStatus('Select a MIDI input to monitor', 'error');
+        return;
+    }
+    const kinds = document.getElementById('midi-monitor-filter')?.value || null;
+    const channel = document.getElementById('midi-monitor-channel')?.value;
+    document.getElementById('midi-monitor-log').innerHTML = '';
+    midiMonitorUnlisten = await window.__TAURI__.event.listen('midi-message', event => showMidiMessage(event.payload));
+    try {
+        await invoke('start_midi_monitor', {
+            inputDeviceIndex: parseInt(input),
+            kinds,
+            channel: channel ? parseInt(channel) : null,
+            // Clock and active sensing only when asked for, they'd flood the log otherwise
+            includeClock: kinds === 'system',
+        });
+        document.getElementById('midi-monitor-btn').textContent = 'Stop';
+    } catch (error) {
+        midiMonitorUnlisten();
+        midiMonitorUnlisten = null;
+        showStatus(`${error}`, 'error');
+    }
+}
+
+async function stopMidiMonitor() {
+    if (midiMonitorUnlisten) {
+        midiMonitorUnlisten();
+        midiMonitorUnlisten = null;
+    }
+    const count = await invoke('stop_midi_monitor');
+    document.getElementById('midi-monitor-btn').textContent = 'Start';
+    showStatus(`MIDI monitor stopped (${count} messages)`, 'success');
+}
+
+async function saveMidiCapture() {
+    try {
+        const path = await invoke('save_midi_capture');
+        showStatus(`Saved MIDI capture to ${path}`, 'success');
+    } catch (error) {
+        showStatus(`${error}`, 'error');
     }
 }
 
@@ -1586,6 +1657,9 @@ function closeSetupModal() {
     if (setupModal) {
         setupModal.style.display = 'none';
     }
+    if (midiMonitorUnlisten) {
+        stopMidiMonitor();
+    }
     updateStatusBar();
 }
 
@@ -1755,6 +1829,9 @@ async function loadAudioInputDevicesWithStatus() {
                         option.selected = true;
                     }
                 });
+                if (devices.includes(selectedAudioInputDevice)) {
+                    await applyAudioInputDevice(selectedAudioInputDevice);
+                }
             }
         }
         
@@ -1790,6 +1867,9 @@ async function loadAudioOutputDevicesWithStatus() {
                         option.selected = true;
                     }
                 });
+                if (devices.includes(selectedAudioOutputDevice)) {
+                    await applyAudioOutputDevice(selectedAudioOutputDevice);
+                }
             }
         }
         
@@ -1818,6 +1898,8 @@ window.testLoopDetection = testLoopDetection;
 window.sendMidiPanic = sendMidiPanic;
 window.openSetupModal = openSetupModal;
 window.closeSetupModal = closeSetupModal;
+window.toggleMidiMonitor = toggleMidiMonitor;
+window.saveMidiCapture = saveMidiCapture;
 window.switchRecordingMode = switchRecordingMode;
 window.updateStatusBar = updateStatusBar;
 
@@ -1829,6 +1911,10 @@ let wavesurferInstance = null;
 let rangeWavesurferInstance = null;
 let currentSamplePath = null;
 
+// Native playback targets for scrubbing (file path + format), per waveform view
+const SCRUB_SNIPPET_SEC = 0.15;
+const scrubTargets = { single: null, range: null };
+
 // Initialize Wavesurfer.js when needed
 async function initializeWaveform(containerId) {
     console.log(`🌊 Initializing waveform in container: ${containerId}`);
@@ -1851,7 +1937,8 @@ async function initializeWaveform(containerId) {
             height: 128,
             normalize: true,
             fillParent: true,
-            responsive: true
+            responsive: true,
+            dragToSeek: true
         });
         
         console.log('✅ Wavesurfer instance created successfully');
@@ -1910,15 +1997,33 @@ async function showWaveform(samplePath, isRangeMode = false) {
             } else {
                 wavesurferInstance = wavesurfer;
             }
+            
+            // Clicking or dragging over the waveform scrubs through the output device
+            wavesurfer.on('interaction', (time) => scrubSample(isRangeMode, time));
         }
         
-        // Load the audio file using the proper asset URL
+        // Load the audio file using the proper asset URL, drawing from backend peaks when available
         console.log(`🌊 Loading waveform with asset URL: ${audioFileUrl}`);
-        await wavesurfer.load(audioFileUrl);
+        const peaks = await fetchWaveformPeaks(cleanPath, displayElement.clientWidth);
+        if (peaks) {
+            await wavesurfer.load(audioFileUrl, peaks.channelData, peaks.duration);
+        } else {
+            await wavesurfer.load(audioFileUrl);
+        }
         currentSamplePath = audioFileUrl;
         
+        try {
+            const info = await invoke('get_wav_info', { filePath: cleanPath });
+            scrubTargets[isRangeMode ? 'range' : 'single'] = { filePath: cleanPath, info };
+        } catch (infoError) {
+            console.log('ℹ️ Scrubbing unavailable for this file:', infoError);
+        }
+        
         // Update info display
         updateWaveformInfo(wavesurfer, isRangeMode);
+        if (!isRangeMode) {
+            await showDetectionBoundaries(cleanPath);
+        }
         
         console.log('✅ Waveform loaded successfully');
         
@@ -1928,6 +2033,21 @@ async function showWaveform(samplePath, isRangeMode = false) {
     }
 }
 
+// Fetch min/max peaks for a view `width` pixels wide, as per-channel arrays wavesurfer can draw
+async function fetchWaveformPeaks(filePath, width) {
+    try {
+        const peaks = await invoke('get_waveform_peaks', { filePath, width: Math.max(width, 1) * 2 });
+        const level = peaks.levels[0];
+        // Alternate each block's min and max so the drawn envelope keeps both extremes
+        const channelData = level.max.map((max, channel) =>
+            max.flatMap((value, i) => [level.min[channel][i], value]));
+        return { channelData, duration: peaks.frames / peaks.sample_rate };
+    } catch (error) {
+        console.log('ℹ️ Peak data unavailable, decoding in the browser:', error);
+        return null;
+    }
+}
+
 // Update waveform information display
 function updateWaveformInfo(wavesurfer, isRangeMode = false) {
     const duration = wavesurfer.getDuration();
@@ -1942,12 +2062,32 @@ function updateWaveformInfo(wavesurfer, isRangeMode = false) {
         if (durationSpan) {
             durationSpan.textContent = `Duration: ${duration.toFixed(2)}s`;
         }
-        
-        // TODO: Add auto-detection boundary info
-        const boundariesSpan = document.getElementById('waveform-boundaries');
-        if (boundariesSpan) {
-            boundariesSpan.textContent = `Auto-detected: Start 0.00s, End ${duration.toFixed(2)}s`;
-        }
+    }
+}
+
+// Show where trim detection would cut this file and the threshold behind it
+async function showDetectionBoundaries(filePath) {
+    const boundariesSpan = document.getElementById('waveform-boundaries');
+    const info = scrubTargets.single?.info;
+    if (!boundariesSpan || !info) {
+        return;
+    }
+    
+    try {
+        const detection = await invoke('analyze_sample_file', { filePath });
+        // Boundaries are indices into the interleaved sample data
+        const samplesPerSecond = info.sample_rate * info.channels;
+        const start = detection.start_sample / samplesPerSecond;
+        const end = detection.end_sample / samplesPerSecond;
+        const loudWindows = detection.rms_values.filter(rms => rms > detection.threshold_linear).length;
+        
+        boundariesSpan.textContent = detection.success
+            ? `Auto-detected: Start ${start.toFixed(2)}s, End ${end.toFixed(2)}s`
+            : `Auto-detection failed: ${detection.failure_reason}`;
+        boundariesSpan.title = `Threshold ${detection.threshold_db} dB: ${loudWindows} of ${detection.rms_values.length} RMS windows above it`;
+    } catch (error) {
+        console.log('ℹ️ Detection analysis unavailable:', error);
+        boundariesSpan.textContent = '';
     }
 }
 
@@ -1983,6 +2123,95 @@ function playRangeWaveform() {
     }
 }
 
+// Play a short snippet at the given time (seconds) through the native output device
+async function scrubSample(isRangeMode, time) {
+    const target = scrubTargets[isRangeMode ? 'range' : 'single'];
+    if (!target) {
+        return;
+    }
+    const startSample = Math.floor(time * target.info.sample_rate);
+    const endSample = Math.min(target.info.frames, startSample + Math.floor(SCRUB_SNIPPET_SEC * target.info.sample_rate));
+    if (startSample >= endSample) {
+        return;
+    }
+    try {
+        await invoke('play_sample_region', { filePath: target.filePath, startSample, endSample });
+    } catch (error) {
+        console.error('❌ Scrub playback failed:', error);
+    }
+}
+
+// Audition a region of the current sample, times in seconds
+async function auditionRegion(startSec, endSec, isRangeMode = false) {
+    const target = scrubTargets[isRangeMode ? 'range' : 'single'];
+    if (!target) {
+        showStatus('Load a sample before auditioning a region', 'error');
+        return;
+    }
+    const startSample = Math.floor(startSec * target.info.sample_rate);
+    const endSample = Math.min(target.info.frames, Math.floor(endSec * target.info.sample_rate));
+    try {
+        await invoke('play_sample_region', { filePath: target.filePath, startSample, endSample });
+    } catch (error) {
+        showStatus(`Region playback failed: ${error}`, 'error');
+    }
+}
+
+// Running loop audition; points are in samples so nudges stay sample-accurate
+let loopAudition = null;
+const LOOP_AUDITION_CROSSFADE_MS = 10;
+
+// Loop a region of a WAV with a temporary crossfade (the file is left untouched)
+async function auditionLoopSamples(filePath, loopStart, loopEnd, crossfadeMs = LOOP_AUDITION_CROSSFADE_MS) {
+    try {
+        await invoke('audition_loop', { filePath, loopStart, loopEnd, crossfadeMs });
+        loopAudition = { filePath, loopStart, loopEnd, crossfadeMs };
+        showStatus(`🔁 Looping samples ${loopStart}-${loopEnd}`, 'success');
+    } catch (error) {
+        loopAudition = null;
+        showStatus(`Loop audition failed: ${error}`, 'error');
+    }
+}
+
+// Loop a region of the current waveform, times in seconds
+async function auditionLoop(startSec, endSec, isRangeMode = false, crossfadeMs = LOOP_AUDITION_CROSSFADE_MS) {
+    const target = scrubTargets[isRangeMode ? 'range' : 'single'];
+    if (!target) {
+        showStatus('Load a sample before auditioning a loop', 'error');
+        return;
+    }
+    const loopStart = Math.floor(startSec * target.info.sample_rate);
+    const loopEnd = Math.min(target.info.frames, Math.floor(endSec * target.info.sample_rate));
+    await auditionLoopSamples(target.filePath, loopStart, loopEnd, crossfadeMs);
+}
+
+// Move the running loop by a number of samples without restarting playback
+async function nudgeAuditionLoop(startDelta, endDelta, crossfadeMs) {
+    if (!loopAudition) {
+        showStatus('Start a loop audition first', 'error');
+        return;
+    }
+    const loopStart = Math.max(0, loopAudition.loopStart + startDelta);
+    const loopEnd = Math.max(loopStart + 2, loopAudition.loopEnd + endDelta);
+    const fade = crossfadeMs ?? loopAudition.crossfadeMs;
+    try {
+        await invoke('update_audition_loop', { loopStart, loopEnd, crossfadeMs: fade });
+        loopAudition = { ...loopAudition, loopStart, loopEnd, crossfadeMs: fade };
+        showStatus(`🔁 Loop ${loopStart}-${loopEnd} (${loopEnd - loopStart} samples, ${fade}ms crossfade)`, 'success');
+    } catch (error) {
+        showStatus(`Loop update failed: ${error}`, 'error');
+    }
+}
+
+async function stopSamplePlayback() {
+    loopAudition = null;
+    try {
+        await invoke('stop_playback');
+    } catch (error) {
+        console.error('❌ Failed to stop playback:', error);
+    }
+}
+
 function resetWaveformView() {
     if (wavesurferInstance) {
         wavesurferInstance.zoom(1);
@@ -2010,6 +2239,11 @@ window.zoomWaveform = zoomWaveform;
 window.playWaveform = playWaveform;
 window.playRangeWaveform = playRangeWaveform;
 window.resetWaveformView = resetWaveformView;
+window.auditionRegion = auditionRegion;
+window.stopSamplePlayback = stopSamplePlayback;
+window.auditionLoop = auditionLoop;
+window.auditionLoopSamples = auditionLoopSamples;
+window.nudgeAuditionLoop = nudgeAuditionLoop;
 window.showBatchThumbnails = showBatchThumbnails;
 window.showWaveform = showWaveform;
 window.hideWaveform = hideWaveform;
@@ -2018,25 +2252,22 @@ window.hideWaveform = hideWaveform;
 // REAL-TIME LEVEL METERS SYSTEM - 60 FPS Professional Audio Monitoring
 // ============================================================================
 
-let levelMeterUpdateInterval = null;
+let levelMeterUnlisten = null;
 let isLevelMeterActive = false;
 let isInputMonitoringEnabled = false;
 
 // Professional level meter configuration
 const LEVEL_METER_CONFIG = {
-    updateIntervalMs: 100, // 10 FPS for development (less spam, still responsive)
+    meterRateHz: 60,       // `audio-levels` events per second pushed by the backend
+    kScale: 'K20',         // K-System scale for the K reading (K12, K14, K20)
     dbFloor: -60,          // Minimum dB level to display
     dbCeiling: 0,          // Maximum dB level (0 dBFS)
-    peakHoldTimeMs: 1500,  // Peak hold duration
-    peakDecayRate: 0.02    // Peak decay speed per frame
+    peakHoldTimeMs: 1500,  // Peak hold duration (held by the backend)
+    peakDecayDbPerSec: 20  // Peak fall speed after the hold
 };
 
-// Peak hold state for animation
-let inputPeakHold = {
-    level: LEVEL_METER_CONFIG.dbFloor,
-    timestamp: 0,
-    isDecaying: false
-};
+// Backend clip count when the clip LED was last cleared (click the LED to clear)
+let acknowledgedClipCount = 0;
 
 // AKAI-style input monitoring toggle function
 async function toggleInputMonitoring() {
@@ -2078,17 +2309,52 @@ async function toggleInputMonitoring() {
     }
 }
 
+// Hear the synth through batcherbird while setting up (needs monitoring running)
+async function toggleInputPassthrough() {
+    const button = document.getElementById('passthrough-btn');
+    const gainDb = parseFloat(document.getElementById('passthrough-gain').value);
+    
+    if (button.classList.contains('active')) {
+        await invoke('stop_input_passthrough');
+        button.classList.remove('active');
+        return;
+    }
+    try {
+        if (!isInputMonitoringEnabled) {
+            await toggleInputMonitoring();
+        }
+        await invoke('start_input_passthrough', { outputDevice: selectedAudioOutputDevice || null, gainDb });
+        button.classList.add('active');
+    } catch (error) {
+        showStatus(`Listen failed: ${error}`, 'error');
+    }
+}
+
+async function setPassthroughGain(gainDb) {
+    if (document.getElementById('passthrough-btn')?.classList.contains('active')) {
+        await invoke('set_passthrough_gain', { gainDb: parseFloat(gainDb) });
+    }
+}
+
 // Start input monitoring (professional sampler pattern)
 async function startInputMonitoring() {
     console.log('📊 Starting input monitoring with real-time level meters');
     
     try {
         // Start backend monitoring stream
-        const result = await invoke('start_input_monitoring');
+        const result = await invoke('start_input_monitoring', {
+            meterRateHz: LEVEL_METER_CONFIG.meterRateHz,
+            kScale: LEVEL_METER_CONFIG.kScale
+        });
         console.log('✅ Backend monitoring started:', result);
+        await invoke('set_meter_peak_hold', {
+            holdMs: LEVEL_METER_CONFIG.peakHoldTimeMs,
+            decayDbPerSec: LEVEL_METER_CONFIG.peakDecayDbPerSec
+        });
+        acknowledgedClipCount = 0;
         
         isInputMonitoringEnabled = true;
-        startLevelMeterUpdates();
+        await startLevelMeterUpdates();
         
     } catch (error) {
         console.error('❌ Failed to start backend monitoring:', error);
@@ -2116,35 +2382,22 @@ async function stopInputMonitoring() {
     
     isInputMonitoringEnabled = false;
     stopLevelMeterUpdates();
+    document.getElementById('passthrough-btn')?.classList.remove('active');
     
     // Reset meters to offline state
     updateLevelMeterDisplay(null);
 }
 
-// Internal function to start level meter UI updates
-function startLevelMeterUpdates() {
-    if (levelMeterUpdateInterval) {
-        clearInterval(levelMeterUpdateInterval);
-    }
-    
+// Internal function to start level meter UI updates (pushed by the backend, no polling)
+async function startLevelMeterUpdates() {
+    stopLevelMeterUpdates();
     isLevelMeterActive = true;
     
-    // Update loop only runs when monitoring is enabled
-    levelMeterUpdateInterval = setInterval(async () => {
-        if (!isLevelMeterActive || !isInputMonitoringEnabled) {
-            return;
+    levelMeterUnlisten = await window.__TAURI__.event.listen('audio-levels', event => {
+        if (isLevelMeterActive && isInputMonitoringEnabled) {
+            updateLevelMeterDisplay(event.payload);
         }
-        
-        try {
-            // Query backend for current audio levels
-            const audioLevels = await invoke('get_audio_levels');
-            updateLevelMeterDisplay(audioLevels);
-            
-        } catch (error) {
-            // Show offline state on error
-            updateLevelMeterDisplay(null);
-        }
-    }, LEVEL_METER_CONFIG.updateIntervalMs);
+    });
     
     console.log('✅ Level meter updates started');
 }
@@ -2153,9 +2406,9 @@ function startLevelMeterUpdates() {
 function stopLevelMeterUpdates() {
     isLevelMeterActive = false;
     
-    if (levelMeterUpdateInterval) {
-        clearInterval(levelMeterUpdateInterval);
-        levelMeterUpdateInterval = null;
+    if (levelMeterUnlisten) {
+        levelMeterUnlisten();
+        levelMeterUnlisten = null;
     }
     
     console.log('✅ Level meter updates stopped');
@@ -2188,18 +2441,16 @@ function updateLevelMeterDisplay(audioLevels) {
     metersPanel.classList.remove('meters-offline');
     
     const currentDb = audioLevels.rms_db;
-    const peakDb = audioLevels.peak_db;
     
     // Convert dB to percentage for visual display (professional VU-style)
     // -60dB = 0%, 0dB = 100%
     const rmsPercent = dbToPercent(currentDb);
-    const peakPercent = dbToPercent(peakDb);
     
     // Update RMS level bar (smooth VU-style movement)
     meterFill.style.width = `${rmsPercent}%`;
     
-    // Professional peak hold logic with decay animation
-    updatePeakHoldDisplay(peakDb, peakPercent, peakHold);
+    // Held peak with hold/decay ballistics applied by the backend
+    peakHold.style.left = `${dbToPercent(audioLevels.peak_hold_db)}%`;
     
     // Update digital readout with precision
     if (currentDb <= LEVEL_METER_CONFIG.dbFloor) {
@@ -2207,10 +2458,16 @@ function updateLevelMeterDisplay(audioLevels) {
     } else {
         levelReadout.textContent = `${currentDb.toFixed(1)} dB`;
     }
+    levelReadout.title = `${audioLevels.k_scale}: ${audioLevels.k_db >= 0 ? '+' : ''}${audioLevels.k_db.toFixed(1)}`;
     
-    // Clipping detection and warning
-    const isClipping = peakDb >= -0.1; // Near 0 dBFS
-    clippingWarning.style.display = isClipping ? 'block' : 'none';
+    // Clip LED stays lit until clicked, like a hardware interface
+    const newClips = audioLevels.clip_count - acknowledgedClipCount;
+    clippingWarning.style.display = newClips > 0 ? 'block' : 'none';
+    clippingWarning.textContent = newClips > 1 ? `⚠️ CLIPPING (${newClips})` : '⚠️ CLIPPING';
+    clippingWarning.onclick = () => {
+        acknowledgedClipCount = audioLevels.clip_count;
+        clippingWarning.style.display = 'none';
+    };
     
     // Professional color zones based on level
     updateMeterColors(meterFill, rmsPercent);
@@ -2227,39 +2484,6 @@ function dbToPercent(db) {
     return Math.max(0, Math.min(100, normalized * 100));
 }
 
-// Professional peak hold with decay animation
-function updatePeakHoldDisplay(currentPeakDb, currentPeakPercent, peakHoldElement) {
-    const now = Date.now();
-    
-    // If current peak is higher, update peak hold
-    if (currentPeakDb > inputPeakHold.level) {
-        inputPeakHold.level = currentPeakDb;
-        inputPeakHold.timestamp = now;
-        inputPeakHold.isDecaying = false;
-    }
-    
-    // Check if peak hold should start decaying
-    if (!inputPeakHold.isDecaying && 
-        (now - inputPeakHold.timestamp) > LEVEL_METER_CONFIG.peakHoldTimeMs) {
-        inputPeakHold.isDecaying = true;
-    }
-    
-    // Apply decay if active
-    if (inputPeakHold.isDecaying) {
-        inputPeakHold.level -= LEVEL_METER_CONFIG.peakDecayRate;
-        
-        // Don't decay below current level or floor
-        if (inputPeakHold.level < Math.max(currentPeakDb, LEVEL_METER_CONFIG.dbFloor)) {
-            inputPeakHold.level = Math.max(currentPeakDb, LEVEL_METER_CONFIG.dbFloor);
-            inputPeakHold.isDecaying = false;
-        }
-    }
-    
-    // Update peak hold position
-    const holdPercent = dbToPercent(inputPeakHold.level);
-    peakHoldElement.style.left = `${holdPercent}%`;
-}
-
 // Professional meter color zones (broadcast standard)
 function updateMeterColors(meterFill, percent) {
     // The CSS gradient handles colors automatically based on percentage:
@@ -2312,6 +2536,8 @@ document.addEventListener('DOMContentLoaded', () => {
 window.toggleInputMonitoring = toggleInputMonitoring;
 window.startInputMonitoring = startInputMonitoring;
 window.stopInputMonitoring = stopInputMonitoring;
+window.toggleInputPassthrough = toggleInputPassthrough;
+window.setPassthroughGain = setPassthroughGain;
 window.updateLevelMeterDisplay = updateLevelMeterDisplay;
 
 // ============================================================================
@@ -2443,6 +2669,17 @@ console.log('🔧 Functions exported to window:', {
 // ============================================================================
 
 // Test loop detection on the last recorded sample
+// Sample the last loop detection ran on, for auditioning its candidates
+let loopDetectionFile = null;
+
+async function auditionLoopCandidate(loopStart, loopEnd) {
+    if (!loopDetectionFile) {
+        showStatus('Run loop detection first', 'error');
+        return;
+    }
+    await auditionLoopSamples(loopDetectionFile, loopStart, loopEnd);
+}
+
 async function testLoopDetection() {
     console.log('🔄 Testing loop detection on last recorded sample...');
     
@@ -2478,6 +2715,7 @@ async function testLoopDetection() {
         console.log('✅ Loop detection result:', result);
         
         // Display results
+        loopDetectionFile = lastSamplePath;
         displayLoopDetectionResults(result, statusElement, resultsElement);
         
     } catch (error) {
@@ -2509,6 +2747,23 @@ function getLoopDetectionParams() {
     };
 }
 
+// Audition the most recent take in the output folder
+async function playLastSample() {
+    const filePath = await getLastRecordedSamplePath();
+    if (!filePath) {
+        showStatus('No recorded sample to play yet', 'error');
+        return;
+    }
+    try {
+        const result = await invoke('play_sample_file', { path: filePath });
+        showStatus(`▶️ ${result}`, 'success');
+    } catch (error) {
+        showStatus(`Playback failed: ${error}`, 'error');
+    }
+}
+
+window.playLastSample = playLastSample;
+
 // Get the path of the last recorded sample
 async function getLastRecordedSamplePath() {
     try {
@@ -2564,6 +2819,14 @@ function displayLoopDetectionResults(result, statusElement, resultsElement) {
                             <div>Quality Score: ${candidate.quality_score.toFixed(3)}</div>
                             <div>Correlation: ${candidate.correlation.toFixed(3)}</div>
                             <div>Zero-crossing aligned: ${candidate.zero_crossing_aligned ? 'Yes' : 'No'}</div>
+                            <div style="margin-top: 6px; display: flex; gap: 4px; flex-wrap: wrap;">
+                                <button onclick="auditionLoopCandidate(${candidate.start_sample}, ${candidate.end_sample})">🔁 Audition</button>
+                                <button onclick="nudgeAuditionLoop(-10, 0)">Start −10</button>
+                                <button onclick="nudgeAuditionLoop(10, 0)">Start +10</button>
+                                <button onclick="nudgeAuditionLoop(0, -10)">End −10</button>
+                                <button onclick="nudgeAuditionLoop(0, 10)">End +10</button>
+                                <button onclick="stopSamplePlayback()">⏹ Stop</button>
+                            </div>
                         </div>
                     `;
                 });
@@ -2637,6 +2900,149 @@ function setupLoopDetectionSliders() {
     }
 }
 
+// Per-take analysis sent by the backend right after each capture is exported
+const MAX_TAKE_ANALYSIS_ROWS = 12;
+
+function midiNoteName(note) {
+    const noteNames = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+    return `${noteNames[note % 12]}${Math.floor(note / 12) - 1}`;
+}
+
+function showTakeAnalysis({ file_path, analysis }) {
+    const fileName = file_path.split(/[\\/]/).pop();
+    const detected = analysis.detected_note === null
+        ? 'unpitched'
+        : `${midiNoteName(analysis.detected_note)} ${analysis.detected_cents >= 0 ? '+' : ''}${analysis.detected_cents.toFixed(0)}¢`;
+    // Flag takes that clip or came back on a different note than was sent
+    const clipping = analysis.peak_db > -0.1 || analysis.true_peak_db > 0;
+    const wrongNote = analysis.detected_note !== null && analysis.detected_note !== analysis.note;
+    const drift = analysis.pitch_drift_cents === null ? '' : `drift ${analysis.pitch_drift_cents.toFixed(0)}¢`;
+    
+    const row = document.createElement('div');
+    row.className = 'take-analysis-row';
+    row.title = file_path;
+    row.innerHTML = `
+        <span>${fileName}</span>
+        <span class="${clipping ? 'warning' : ''}">peak ${analysis.peak_db.toFixed(1)} dB</span>
+        <span>RMS ${analysis.rms_db.toFixed(1)} dB</span>
+        <span title="true peak ${analysis.true_peak_db.toFixed(1)} dBTP">${analysis.loudness_lufs === null ? '' : `${analysis.loudness_lufs.toFixed(1)} LUFS`}</span>
+        <span>${analysis.duration_secs.toFixed(2)}s</span>
+        <span class="${wrongNote ? 'warning' : ''}">${detected}</span>
+        <span class="${analysis.retake_reason ? 'warning' : ''}" title="${analysis.retake_reason || ''}">${analysis.retake_reason ? '🔁 ' : ''}${drift}</span>
+    `;
+    
+    document.querySelectorAll('.take-analysis-list').forEach(list => {
+        list.prepend(row.cloneNode(true));
+        while (list.children.length > MAX_TAKE_ANALYSIS_ROWS) {
+            list.lastElementChild.remove();
+        }
+    });
+}
+
+window.__TAURI__.event.listen('take-analysis', event => showTakeAnalysis(event.payload));
+
+// Per-note status sent by record_range: one row per note, updated as it is played, recorded and exported
+const RANGE_NOTE_ICONS = { started: '🔴', recorded: '🎙️', exported: '✅', failed: '❌' };
+
+function showRangeNote(event) {
+    const list = document.getElementById('range-note-list');
+    if (!list) return;
+    const key = `${event.note}-${event.velocity}`;
+    let row = list.querySelector(`[data-key="${key}"]`);
+    if (!row) {
+        row = document.createElement('div');
+        row.className = 'take-analysis-row';
+        row.dataset.key = key;
+        list.appendChild(row);
+    }
+    
+    let detail = '';
+    if (event.status === 'recorded') {
+        detail = `peak ${event.peak_db.toFixed(1)} dB, RMS ${event.rms_db.toFixed(1)} dB, ${event.duration_secs.toFixed(2)}s`;
+        row.dataset.levels = detail;
+    } else if (event.status === 'exported') {
+        detail = row.dataset.levels || '';
+        row.title = event.file_path;
+    } else if (event.status === 'failed') {
+        detail = event.reason;
+    }
+    // Reasons and paths come from the backend, so they are set as text, never as markup
+    const cell = (text, className = '') => {
+        const span = document.createElement('span');
+        span.className = className;
+        span.textContent = text;
+        return span;
+    };
+    row.replaceChildren(
+        cell(`${RANGE_NOTE_ICONS[event.status]} ${midiNoteName(event.note)} vel ${event.velocity}`),
+        cell(event.status, event.status === 'failed' ? 'warning' : ''),
+        cell(detail),
+    );
+}
+
+window.__TAURI__.event.listen('range-note', event => showRangeNote(event.payload));
+
+// Apply saved edit files to the raw takes in the output folder, writing finished samples to "Rendered"
+async function renderSampleEdits() {
+    const outputDirectory = document.getElementById('output-directory')?.value || '';
+    const sampleName = document.getElementById('sample-name')?.value?.trim() || '';
+    try {
+        const result = await invoke('render_sample_edits', { outputDirectory, sampleName: sampleName || null });
+        showStatus(result, 'success');
+    } catch (error) {
+        console.error('❌ Render failed:', error);
+        showStatus(`Render failed: ${error}`, 'error');
+    }
+}
+
+// Re-run trimming and fades over the samples in the output folder with the current detection settings
+async function reprocessSamples() {
+    const outputDirectory = document.getElementById('output-directory')?.value || '';
+    const sampleName = document.getElementById('sample-name')?.value?.trim() || '';
+    const exportFormat = document.getElementById('export-format')?.value || 'wav';
+    const applyDetection = document.getElementById('detection-enabled')?.checked ?? true;
+    const thresholdDb = parseFloat(document.getElementById('detection-threshold')?.value);
+    try {
+        const result = await invoke('reprocess_samples', {
+            outputDirectory,
+            sampleName: sampleName || null,
+            exportFormat,
+            applyDetection,
+            thresholdDb: Number.isNaN(thresholdDb) ? null : thresholdDb
+        });
+        showStatus(result, 'success');
+    } catch (error) {
+        console.error('❌ Reprocess failed:', error);
+        showStatus(`Reprocess failed: ${error}`, 'error');
+    }
+}
+
+async function rerecordSamples() {
+    const outputDirectory = document.getElementById('output-directory')?.value || '';
+    const sampleName = document.getElementById('sample-name')?.value?.trim() || '';
+    const exportFormat = document.getElementById('export-format')?.value || 'wav';
+    const notes = document.getElementById('rerecord-notes')?.value?.trim() || '';
+    const fromQa = document.getElementById('rerecord-from-qa')?.checked ?? false;
+    const keepVersions = document.getElementById('rerecord-keep-versions')?.checked ?? true;
+    const duration = parseInt(document.getElementById('range-duration-input')?.value) || 2000;
+    showStatus('Re-recording takes...', 'success');
+    try {
+        const result = await invoke('rerecord_samples', {
+            outputDirectory,
+            sampleName: sampleName || null,
+            exportFormat,
+            notes: notes || null,
+            fromQa,
+            keepVersions,
+            duration
+        });
+        showStatus(result, 'success');
+    } catch (error) {
+        console.error('❌ Re-record failed:', error);
+        showStatus(`Re-record failed: ${error}`, 'error');
+    }
+}
+
 // Initialize loop detection when page loads
 document.addEventListener('DOMContentLoaded', () => {
     console.log('🔄 Initializing loop detection system');
@@ -2645,5 +3051,9 @@ document.addEventListener('DOMContentLoaded', () => {
 
 // Export loop detection functions to global scope
 window.testLoopDetection = testLoopDetection;
+window.auditionLoopCandidate = auditionLoopCandidate;
+window.renderSampleEdits = renderSampleEdits;
+window.reprocessSamples = reprocessSamples;
+window.rerecordSamples = rerecordSamples;
 window.getLoopDetectionParams = getLoopDetectionParams;
 window.displayLoopDetectionResults = displayLoopDetectionResults;
\ No newline at end of file
@@ -0,0 +1,214 @@
+//! Persisted output folders with macOS security-scoped bookmarks.
+//!
+//! A sandboxed build only keeps access to a user-picked folder for the lifetime of
+//! the process. Storing a security-scoped bookmark lets us regain access on the
+//! next launch without asking the user to pick the folder again.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Keep the list short - it backs a "recent folders" menu
+const MAX_REMEMBERED_FOLDERS: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderBookmark {
+    /// Last known path of the folder
+    pub path: String,
+    /// Opaque security-scoped bookmark data (macOS only)
+    pub bookmark: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BookmarkStore {
+    /// Most recently chosen folder first
+    pub folders: Vec<FolderBookmark>,
+}
+
+impl BookmarkStore {
+    /// Load the store, starting empty if the file is missing or unreadable
+    pub fn load(file: &Path) -> Self {
+        std::fs::read_to_string(file)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, file: &Path) -> std::io::Result<()> {
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        std::fs::write(file, content)
+    }
+
+    /// Remember a folder the user just picked (while we still hold the sandbox grant for it)
+    pub fn remember(&mut self, folder: &Path) {
+        let path = folder.to_string_lossy().to_string();
+        self.folders.retain(|entry| entry.path != path);
+        self.folders.insert(0, FolderBookmark {
+            bookmark: platform::create_bookmark(folder),
+            path,
+        });
+        self.folders.truncate(MAX_REMEMBERED_FOLDERS);
+    }
+
+    /// Re-resolve every bookmark and start accessing it.
+    /// Folders that were moved get their path updated; folders that no longer
+    /// resolve are dropped. Returns the folders that are usable right now.
+    pub fn restore_access(&mut self) -> Vec<PathBuf> {
+        let mut restored = Vec::new();
+
+        self.folders.retain_mut(|entry| {
+            let resolved = match entry.bookmark.as_deref() {
+                Some(data) => match platform::resolve_bookmark(data) {
+                    Some((path, is_stale)) => {
+                        if is_stale {
+                            // Bookmark still works but should be regenerated for the new location
+                            entry.bookmark = platform::create_bookmark(&path);
+                        }
+                        entry.path = path.to_string_lossy().to_string();
+                        Some(path)
+                    }
+                    None => None,
+                },
+                // No bookmark (non-macOS, or creation failed) - plain path is all we have
+                None => Some(PathBuf::from(&entry.path)),
+            };
+
+            match resolved {
+                Some(path) if path.is_dir() => {
+                    restored.push(path);
+                    true
+                }
+                _ => {
+                    println!("⚠️ Dropping remembered output folder that no longer resolves: {}", entry.path);
+                    false
+                }
+            }
+        });
+
+        restored
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::c_void;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+
+    type CFTypeRef = *const c_void;
+    type CFIndex = isize;
+    type CFOptionFlags = usize;
+    type Boolean = u8;
+
+    const URL_BOOKMARK_CREATION_WITH_SECURITY_SCOPE: CFOptionFlags = 1 << 11;
+    const URL_BOOKMARK_RESOLUTION_WITH_SECURITY_SCOPE: CFOptionFlags = 1 << 10;
+    const MAX_PATH_BYTES: usize = 4096;
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFURLCreateFromFileSystemRepresentation(allocator: CFTypeRef, buffer: *const u8, buf_len: CFIndex, is_directory: Boolean) -> CFTypeRef;
+        fn CFURLCreateBookmarkData(allocator: CFTypeRef, url: CFTypeRef, options: CFOptionFlags, resource_properties: CFTypeRef, relative_to_url: CFTypeRef, error: *mut CFTypeRef) -> CFTypeRef;
+        fn CFURLCreateByResolvingBookmarkData(allocator: CFTypeRef, bookmark: CFTypeRef, options: CFOptionFlags, relative_to_url: CFTypeRef, resource_properties: CFTypeRef, is_stale: *mut Boolean, error: *mut CFTypeRef) -> CFTypeRef;
+        fn CFURLGetFileSystemRepresentation(url: CFTypeRef, resolve_against_base: Boolean, buffer: *mut u8, max_buf_len: CFIndex) -> Boolean;
+        fn CFURLStartAccessingSecurityScopedResource(url: CFTypeRef) -> Boolean;
+        fn CFDataCreate(allocator: CFTypeRef, bytes: *const u8, length: CFIndex) -> CFTypeRef;
+        fn CFDataGetLength(data: CFTypeRef) -> CFIndex;
+        fn CFDataGetBytePtr(data: CFTypeRef) -> *const u8;
+        fn CFRelease(cf: CFTypeRef);
+    }
+
+    pub fn create_bookmark(folder: &Path) -> Option<Vec<u8>> {
+        let bytes = folder.as_os_str().as_bytes();
+        unsafe {
+            let url = CFURLCreateFromFileSystemRepresentation(std::ptr::null(), bytes.as_ptr(), bytes.len() as CFIndex, 1);
+            if url.is_null() {
+                return None;
+            }
+
+            let mut error: CFTypeRef = std::ptr::null();
+            let data = CFURLCreateBookmarkData(
+                std::ptr::null(),
+                url,
+                URL_BOOKMARK_CREATION_WITH_SECURITY_SCOPE,
+                std::ptr::null(),
+                std::ptr::null(),
+                &mut error,
+            );
+            CFRelease(url);
+
+            if data.is_null() {
+                if !error.is_null() {
+                    CFRelease(error);
+                }
+                println!("⚠️ Could not create security-scoped bookmark for {}", folder.display());
+                return None;
+            }
+
+            let length = CFDataGetLength(data) as usize;
+            let bookmark = std::slice::from_raw_parts(CFDataGetBytePtr(data), length).to_vec();
+            CFRelease(data);
+            Some(bookmark)
+        }
+    }
+
+    /// Resolve bookmark data and start accessing the folder. Access is kept for the
+    /// lifetime of the app, so there is no matching stop call.
+    pub fn resolve_bookmark(bookmark: &[u8]) -> Option<(PathBuf, bool)> {
+        unsafe {
+            let data = CFDataCreate(std::ptr::null(), bookmark.as_ptr(), bookmark.len() as CFIndex);
+            if data.is_null() {
+                return None;
+            }
+
+            let mut is_stale: Boolean = 0;
+            let mut error: CFTypeRef = std::ptr::null();
+            let url = CFURLCreateByResolvingBookmarkData(
+                std::ptr::null(),
+                data,
+                URL_BOOKMARK_RESOLUTION_WITH_SECURITY_SCOPE,
+                std::ptr::null(),
+                std::ptr::null(),
+                &mut is_stale,
+                &mut error,
+            );
+            CFRelease(data);
+
+            if url.is_null() {
+                if !error.is_null() {
+                    CFRelease(error);
+                }
+                return None;
+            }
+
+            let mut buffer = vec![0u8; MAX_PATH_BYTES];
+            let has_path = CFURLGetFileSystemRepresentation(url, 1, buffer.as_mut_ptr(), buffer.len() as CFIndex) != 0;
+            let accessing = CFURLStartAccessingSecurityScopedResource(url) != 0;
+            CFRelease(url);
+
+            if !has_path || !accessing {
+                return None;
+            }
+
+            let length = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+            let path = PathBuf::from(std::ffi::OsStr::from_bytes(&buffer[..length]));
+            Some((path, is_stale != 0))
+        }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    use std::path::{Path, PathBuf};
+
+    // No sandbox bookmarks outside macOS - the stored path is used as-is
+    pub fn create_bookmark(_folder: &Path) -> Option<Vec<u8>> {
+        None
+    }
+
+    pub fn resolve_bookmark(_bookmark: &[u8]) -> Option<(PathBuf, bool)> {
+        None
+    }
+}
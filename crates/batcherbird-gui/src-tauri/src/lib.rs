@@ -1,125 +1,114 @@
 use batcherbird_core::{
-    midi::MidiManager, 
-    audio::AudioManager,
-    sampler::{SamplingEngine, SamplingConfig, AudioLevels},
-    export::{SampleExporter, ExportConfig, AudioFormat},
+    audio::{AudioManager, DeviceCapabilities},
+    controller::{EngineController, EngineState},
+    sampler::{SamplingEngine, SamplingConfig, AudioLevels, PeakHoldConfig, CaptureProgress, PatchIterationConfig, Sample, LegatoConfig, ReleaseCaptureConfig},
+    queue::{Articulation, QueueEntry, SamplingQueue},
+    metering::{KScale, DEFAULT_METER_RATE_HZ},
+    passthrough::PassthroughConfig,
+    export::{SampleExporter, ExportConfig, AudioFormat, BitDepth, OverwritePolicy},
+    incremental::IncrementalExport,
+    naming::NamingContext,
     loop_detection::LoopDetectionConfig,
+    output_location,
+    file_manager,
+    multi_device::SecondaryInputConfig,
+    midi_monitor::MonitorFilter,
+    velocity_curve::{VelocityCurve, DEFAULT_PROBE_VELOCITIES},
+    profiles::{ProfileLibrary, SynthProfile},
+    identity::SynthIdentity,
+    drum_map::{DrumMap, GM_DRUM_CHANNEL},
+    readme::ReadmeConfig,
+    analysis::{TakeAnalysis, DEFAULT_DRIFT_LIMIT_CENTS},
+    waveform::WaveformPeaks,
+    detection::DetectionResult,
+    library,
+    edit::{self, SampleEdit, LoopPoints},
+    reprocess,
+    rerecord::{self, RerecordEntry, RerecordMode},
+    catalog::{CatalogConfig, CatalogQuery, CatalogSession, SessionCatalog},
+    package::{self, PackageConfig, PackageFormat},
+    plan::{PlanAudio, PlanMatrix, SamplingPlan},
 };
-use midir::MidiOutputConnection;
-use std::sync::{Mutex, Arc};
+use tauri::Emitter;
+use std::sync::Mutex;
 use std::time::Duration;
-use std::process::Command;
 
-// Simple working pattern - don't break what works
-static MIDI_MANAGER: Mutex<Option<MidiManager>> = Mutex::new(None);
-static MIDI_CONNECTION: Mutex<Option<MidiOutputConnection>> = Mutex::new(None);
+mod bookmarks;
+use bookmarks::BookmarkStore;
 
-// Simplified monitoring state (professional approach - use existing SamplingEngine)
-static MONITORING_ACTIVE: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
-static GLOBAL_SAMPLING_ENGINE: Mutex<Option<Arc<SamplingEngine>>> = Mutex::new(None);
-static MONITORING_THREAD: Mutex<Option<std::thread::JoinHandle<()>>> = Mutex::new(None);
+// MIDI connection, input monitoring/passthrough, armed capture, playback and the sampling queue,
+// with the engine state
+static ENGINE: EngineController = EngineController::new();
+
+// Remembered output folders (security-scoped bookmarks when sandboxed on macOS)
+static BOOKMARK_STORE: Mutex<Option<(BookmarkStore, std::path::PathBuf)>> = Mutex::new(None);
+
+// Synth profiles (built-in + user TOML) and the one selected in the UI
+static PROFILE_LIBRARY: Mutex<Option<ProfileLibrary>> = Mutex::new(None);
+static SELECTED_PROFILE: Mutex<Option<SynthProfile>> = Mutex::new(None);
 
 
 /// Start audio input monitoring (simplified professional approach)
 #[tauri::command]
-async fn start_input_monitoring() -> Result<String, String> {
-    println!("🎛️ Starting audio input monitoring (professional approach)");
-    
-    // Check if already monitoring
-    if MONITORING_ACTIVE.load(std::sync::atomic::Ordering::Relaxed) {
-        return Ok("Audio monitoring already active".to_string());
-    }
-    
-    // Set monitoring flag first
-    MONITORING_ACTIVE.store(true, std::sync::atomic::Ordering::Relaxed);
-    
-    // Create monitoring in a separate thread (avoids Send+Sync issues)
-    // Note: Can't clone AtomicBool, but we can access the static directly from the thread
+async fn start_input_monitoring(app: tauri::AppHandle, meter_rate_hz: Option<f32>, k_scale: Option<String>) -> Result<String, String> {
+    println!("🎛️ Starting audio input monitoring");
+    let meter_rate_hz = meter_rate_hz.unwrap_or(DEFAULT_METER_RATE_HZ);
+    let k_scale = match k_scale {
+        Some(name) => KScale::from_name(&name).ok_or(format!("Unknown K-System scale: {}", name))?,
+        None => KScale::default(),
+    };
     
-    let handle = std::thread::spawn(move || {
-        println!("🧵 Monitoring thread started (using SamplingEngine)");
-        
-        // Create SamplingEngine in this thread
-        let config = SamplingConfig {
-            note_duration_ms: 0,     // Not used for monitoring
-            release_time_ms: 0,      // Not used for monitoring 
-            pre_delay_ms: 0,         // Not used for monitoring
-            post_delay_ms: 0,        // Not used for monitoring
-            midi_channel: 0,         // Not used for monitoring
-            velocity: 100,           // Not used for monitoring
-        };
-        
-        let sampling_engine = match SamplingEngine::new(config) {
-            Ok(engine) => {
-                println!("✅ SamplingEngine created for monitoring");
-                Arc::new(engine)
-            },
-            Err(e) => {
-                println!("❌ Failed to create SamplingEngine: {}", e);
-                MONITORING_ACTIVE.store(false, std::sync::atomic::Ordering::Relaxed);
-                return;
-            }
-        };
-        
-        // Store the engine globally so we can access levels from get_audio_levels
-        {
-            let mut engine_guard = GLOBAL_SAMPLING_ENGINE.lock().unwrap();
-            *engine_guard = Some(Arc::clone(&sampling_engine));
-        }
-        
-        // Start monitoring stream using SamplingEngine's built-in method
-        let stream = match sampling_engine.start_monitoring_stream() {
-            Ok(s) => s,
-            Err(e) => {
-                println!("❌ Failed to create monitoring stream: {}", e);
-                MONITORING_ACTIVE.store(false, std::sync::atomic::Ordering::Relaxed);
-                return;
-            }
-        };
-        
-        // Start the stream
-        use cpal::traits::StreamTrait;
-        if let Err(e) = stream.play() {
-            println!("❌ Failed to start monitoring stream: {}", e);
-            MONITORING_ACTIVE.store(false, std::sync::atomic::Ordering::Relaxed);
-            return;
-        }
-        
-        println!("✅ SamplingEngine monitoring stream started and playing");
-        
-        // Keep the stream alive while monitoring is active
-        while MONITORING_ACTIVE.load(std::sync::atomic::Ordering::Relaxed) {
-            std::thread::sleep(std::time::Duration::from_millis(100));
-        }
-        
-        // Stop the stream
-        if let Err(e) = stream.pause() {
-            println!("⚠️ Warning: Failed to pause monitoring stream: {}", e);
-        }
-        
-        println!("✅ SamplingEngine monitoring thread finished");
-    });
+    // Push levels to the UI as `audio-levels` events while monitoring runs
+    let started = ENGINE.start_monitoring(meter_rate_hz, k_scale, move |reading| {
+        let _ = app.emit("audio-levels", reading);
+    }).map_err(|e| format!("Failed to start monitoring: {}", e))?;
     
-    // Store the thread handle
-    {
-        let mut thread_guard = MONITORING_THREAD.lock().unwrap();
-        *thread_guard = Some(handle);
+    if started {
+        Ok("Audio input monitoring started".to_string())
+    } else {
+        Ok("Audio monitoring already active".to_string())
     }
-    
-    println!("✅ Audio input monitoring started (using SamplingEngine infrastructure)");
-    Ok("Audio input monitoring started".to_string())
 }
 
+/// Record from the chosen input device (None or "" = MiniFuse or the system default).
+/// Returns true when monitoring was stopped for the switch, so the UI can restart it.
+#[tauri::command]
+fn set_audio_input_device(name: Option<String>) -> Result<bool, String> {
+    let name = name.filter(|name| !name.is_empty());
+    println!("🎤 GUI: Selecting audio input: {:?}", name);
+    ENGINE.set_audio_input(name).map_err(|e| e.to_string())
+}
+
+/// Play back on the chosen output device (None or "" = MiniFuse or the system default)
+#[tauri::command]
+fn set_audio_output_device(name: Option<String>) -> Result<(), String> {
+    let name = name.filter(|name| !name.is_empty());
+    println!("🔊 GUI: Selecting audio output: {:?}", name);
+    ENGINE.set_audio_output(name).map_err(|e| e.to_string())
+}
+
+/// Pair a channel of a second device with the input for stereo range takes: the input's
+/// `primary_channel` on the left, the device's `channel` on the right (1-based; device
+/// None or "" records the input alone)
+#[tauri::command]
+fn set_secondary_audio_input(device: Option<String>, channel: Option<u16>, primary_channel: Option<u16>) -> Result<(), String> {
+    let config = device.filter(|device| !device.is_empty()).map(|device| SecondaryInputConfig {
+        device,
+        channel: channel.unwrap_or(1).saturating_sub(1),
+        primary_channel: primary_channel.unwrap_or(1).saturating_sub(1),
+    });
+    println!("🔀 GUI: Selecting second input: {:?}", config);
+    ENGINE.set_secondary_input(config).map_err(|e| e.to_string())
+}
 
 /// Generate instrument files from existing WAV samples in a directory
 #[tauri::command]
-fn generate_instrument_files(directory: String, export_format: String, sample_name: Option<String>, creator_name: Option<String>, instrument_description: Option<String>) -> Result<String, String> {
+fn generate_instrument_files(directory: String, export_format: String, sample_name: Option<String>, creator_name: Option<String>, instrument_description: Option<String>, filename_pattern: Option<String>) -> Result<String, String> {
     println!("🎹 GUI: Generating instrument files from directory: {}", directory);
     println!("   Format: {}, Sample name: {:?}", export_format, sample_name);
     
     use std::path::PathBuf;
-    use std::collections::HashMap;
-    use batcherbird_core::sampler::Sample;
+    use batcherbird_core::import::{self, ImportConfig};
     use batcherbird_core::export::{SampleExporter, ExportConfig, AudioFormat};
     use batcherbird_core::detection::DetectionConfig;
     
@@ -128,104 +117,29 @@ fn generate_instrument_files(directory: String, export_format: String, sample_na
         return Err(format!("Directory does not exist: {}", directory));
     }
     
-    // Scan directory for WAV files
-    let wav_files: Vec<PathBuf> = match std::fs::read_dir(&dir_path) {
-        Ok(entries) => {
-            entries.filter_map(|entry| {
-                let entry = entry.ok()?;
-                let path = entry.path();
-                if path.extension()?.to_str()? == "wav" {
-                    Some(path)
-                } else {
-                    None
-                }
-            }).collect()
-        },
-        Err(e) => return Err(format!("Failed to read directory: {}", e))
+    // Map files to notes: manifest, then file names, then the pitch detector for anything else
+    let import_config = ImportConfig {
+        patterns: filename_pattern.filter(|pattern| !pattern.trim().is_empty()).into_iter()
+            .chain(ImportConfig::default().patterns)
+            .collect(),
+        ..ImportConfig::default()
     };
-    
-    if wav_files.is_empty() {
-        return Err("No WAV files found in directory".to_string());
-    }
-    
-    println!("   📁 Found {} WAV files", wav_files.len());
-    
-    // Parse WAV filenames to extract note and velocity information
-    // Expected format: {prefix}_{note_name}_{note_number}_{velocity}.wav
-    let mut samples = Vec::new();
-    
-    for wav_file in &wav_files {
-        let filename = wav_file.file_stem()
-            .and_then(|stem| stem.to_str())
-            .unwrap_or("");
-        
-        // Parse filename: look for patterns like "Roland-EM1014_C4_60_vel127" or "Batcherbird_F4_v127_rk65"
-        let note_number;
-        let velocity;
-        
-        // Try pattern 1: "Roland-EM1014_C4_60_vel127" or "Roland-EM1017_B4_71_vel127"
-        if let Some(captures) = regex::Regex::new(r".*_([A-G][#b]?\d+)_(\d+)_vel(\d+)$")
-            .unwrap()
-            .captures(filename) {
-            
-            let note_str = &captures[2];
-            let velocity_str = &captures[3];
-            
-            if let (Ok(note), Ok(vel)) = (note_str.parse::<u8>(), velocity_str.parse::<u8>()) {
-                note_number = note;
-                velocity = vel;
-            } else {
-                println!("   ⚠️ Could not parse note/velocity from: {}", filename);
-                continue;
-            }
-        }
-        // Try pattern 2: "Batcherbird_F4_v127_rk65"  
-        else if let Some(captures) = regex::Regex::new(r".*_([A-G][#b]?\d+)_v(\d+)_rk(\d+)$")
-            .unwrap()
-            .captures(filename) {
-            
-            let velocity_str = &captures[2];
-            let note_str = &captures[3];
-            
-            if let (Ok(note), Ok(vel)) = (note_str.parse::<u8>(), velocity_str.parse::<u8>()) {
-                note_number = note;
-                velocity = vel;
-            } else {
-                println!("   ⚠️ Could not parse note/velocity from: {}", filename);
-                continue;
-            }
-        }
-        else {
-            println!("   ⚠️ Filename format not recognized: {}", filename);
-            continue;
-        }
-        
-        // Create a minimal sample struct (we only need note/velocity for instrument file generation)
-        let sample = Sample {
-            note: note_number,
-            velocity,
-            audio_data: vec![0.0], // Dummy data - not used for instrument file generation
-            sample_rate: 44100,   // Dummy data
-            channels: 1,          // Dummy data
-            recorded_at: std::time::SystemTime::now(),
-            midi_timing: std::time::Duration::from_millis(100),
-            audio_timing: std::time::Duration::from_millis(2000),
-        };
-        
-        samples.push(sample);
-        println!("   📄 Parsed: {} -> Note {}, Velocity {}", filename, note_number, velocity);
+    let imported = import::import_directory(&dir_path, &import_config).map_err(|e| e.to_string())?;
+    for file in &imported.unrecognized {
+        println!("   ⚠️ Filename format not recognized and no clear pitch: {}", file.display());
     }
-    
-    if samples.is_empty() {
+    if imported.samples.is_empty() {
         return Err("No valid samples found (could not parse filenames)".to_string());
     }
+    println!("   📁 Mapped {} WAV files", imported.samples.len());
+    let (samples, wav_files): (Vec<_>, Vec<_>) = imported.samples.into_iter()
+        .map(|imported| (imported.sample, imported.path))
+        .unzip();
     
     // Determine export format
-    let sample_format = match export_format.as_str() {
-        "decentsampler" => AudioFormat::DecentSampler,
-        "sfz" => AudioFormat::SFZ,
-        _ => return Err(format!("Unsupported export format: {}", export_format))
-    };
+    let sample_format = AudioFormat::parse(&export_format)
+        .filter(AudioFormat::is_instrument)
+        .ok_or_else(|| format!("Unsupported export format: {}", export_format))?;
     
     // Build naming pattern 
     let naming_pattern = if let Some(name) = sample_name.as_ref().filter(|n| !n.trim().is_empty()) {
@@ -246,6 +160,7 @@ fn generate_instrument_files(directory: String, export_format: String, sample_na
         detection_config: DetectionConfig::default(),
         creator_name: creator_name.clone(),
         instrument_description: instrument_description.clone(),
+        ..ExportConfig::default()
     };
     
     // Create exporter and generate instrument files
@@ -256,117 +171,86 @@ fn generate_instrument_files(directory: String, export_format: String, sample_na
     // Generate instrument files using existing sample generation logic
     println!("🎼 Generating {} instrument file...", export_format);
     
-    match sample_format {
-        AudioFormat::DecentSampler => {
-            // Group samples by velocity
-            let mut velocity_groups = HashMap::new();
-            for (i, sample) in samples.iter().enumerate() {
-                if i < wav_files.len() {
-                    velocity_groups.entry(sample.velocity)
-                        .or_insert_with(Vec::new)
-                        .push((sample, &wav_files[i]));
-                }
-            }
-            
-            let _preset_name = sample_name.unwrap_or_else(|| "Batcherbird_Instrument".to_string());
-            let dspreset_path = exporter.generate_dspreset_file(&samples, &wav_files)
-                .map_err(|e| format!("Failed to generate Decent Sampler file: {}", e))?;
-            
-            println!("   ✅ Generated: {}", dspreset_path.display());
-            Ok(format!("Generated Decent Sampler file: {}", dspreset_path.display()))
-        },
-        AudioFormat::SFZ => {
-            let sfz_path = exporter.generate_sfz_file(&samples, &wav_files)
-                .map_err(|e| format!("Failed to generate SFZ file: {}", e))?;
-            
-            println!("   ✅ Generated: {}", sfz_path.display());
-            Ok(format!("Generated SFZ file: {}", sfz_path.display()))
-        },
-        _ => Err("Invalid format for instrument file generation".to_string())
-    }
+    let instrument = sample_format.instrument_exporter()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Invalid format for instrument file generation".to_string())?;
+    let instrument_path = instrument.write_samples(&exporter, &samples, &wav_files)
+        .map_err(|e| format!("Failed to generate {} file: {}", instrument.display_name(), e))?;
+    
+    println!("   ✅ Generated: {}", instrument_path.display());
+    Ok(format!("Generated {} file: {}", instrument.display_name(), instrument_path.display()))
+}
+
+/// Zip an exported instrument folder for distribution ("zip" or "dslibrary")
+#[tauri::command]
+fn package_instrument(directory: String, format: String, name: Option<String>, license: Option<String>) -> Result<String, String> {
+    let format = PackageFormat::parse(&format).ok_or_else(|| format!("Unsupported package format: {}", format))?;
+    let config = PackageConfig {
+        format,
+        name: name.filter(|n| !n.trim().is_empty()),
+        license: license.filter(|l| !l.trim().is_empty()),
+        output: None,
+    };
+    let archive = package::package_directory(std::path::Path::new(&directory), &config).map_err(|e| e.to_string())?;
+    Ok(archive.display().to_string())
 }
 
 /// Stop audio input monitoring
 #[tauri::command]
 async fn stop_input_monitoring() -> Result<String, String> {
     println!("🎛️ Stopping audio input monitoring");
-    
-    // Clear monitoring flag - this will cause the monitoring thread to exit
-    MONITORING_ACTIVE.store(false, std::sync::atomic::Ordering::Relaxed);
-    
-    // Wait for the monitoring thread to finish
-    {
-        let mut thread_guard = MONITORING_THREAD.lock().unwrap();
-        if let Some(handle) = thread_guard.take() {
-            // Drop the lock before joining to avoid deadlock
-            drop(thread_guard);
-            
-            if let Err(e) = handle.join() {
-                println!("⚠️ Warning: SamplingEngine monitoring thread did not exit cleanly: {:?}", e);
-            } else {
-                println!("✅ SamplingEngine monitoring thread joined successfully");
-            }
-        }
-    }
-    
-    // Remove the global sampling engine
-    {
-        let mut engine_guard = GLOBAL_SAMPLING_ENGINE.lock().unwrap();
-        *engine_guard = None;
-    }
-    
-    println!("✅ Audio input monitoring stopped");
+    ENGINE.stop_monitoring();
     Ok("Audio input monitoring stopped".to_string())
 }
 
-/// Get current audio levels for UI meters (simplified professional approach)
+/// Get current audio levels for UI meters (the `audio-levels` event pushes the same
+/// readings while monitoring; this is for one-off reads)
 #[tauri::command]
 async fn get_audio_levels() -> Result<AudioLevels, String> {
-    // Only return real levels when monitoring is active
-    if !MONITORING_ACTIVE.load(std::sync::atomic::Ordering::Relaxed) {
-        // Return silent levels when monitoring is off (AKAI style)
-        return Ok(AudioLevels {
-            peak: 0.0,
-            rms: 0.0,
-            peak_db: -60.0,
-            rms_db: -60.0,
-        });
-    }
-    
-    // Get levels from the global sampling engine (reuse existing infrastructure)
-    let engine_guard = GLOBAL_SAMPLING_ENGINE.lock().unwrap();
-    if let Some(engine) = engine_guard.as_ref() {
-        let levels = engine.get_audio_levels();
-        Ok(levels)
-    } else {
-        // Engine not available, return silent levels
-        Ok(AudioLevels {
-            peak: 0.0,
-            rms: 0.0,
-            peak_db: -60.0,
-            rms_db: -60.0,
-        })
-    }
+    // Silent levels when monitoring is off (AKAI style)
+    Ok(ENGINE.audio_levels())
+}
+
+/// Play the monitored input on `output_device` (part of its name; None = default output)
+#[tauri::command]
+fn start_input_passthrough(output_device: Option<String>, gain_db: Option<f32>) -> Result<String, String> {
+    let config = PassthroughConfig {
+        output_device: output_device.filter(|name| !name.is_empty()),
+        gain_db: gain_db.unwrap_or(0.0),
+        ..PassthroughConfig::default()
+    };
+    ENGINE.start_passthrough(&config).map_err(|e| e.to_string())?;
+    Ok("Input passthrough started".to_string())
+}
+
+#[tauri::command]
+fn set_passthrough_gain(gain_db: f32) -> Result<(), String> {
+    ENGINE.set_passthrough_gain(gain_db).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn stop_input_passthrough() -> Result<String, String> {
+    ENGINE.stop_passthrough();
+    Ok("Input passthrough stopped".to_string())
+}
+
+/// Set the input meter's peak-hold time and fall rate
+#[tauri::command]
+async fn set_meter_peak_hold(hold_ms: f32, decay_db_per_sec: f32) -> Result<(), String> {
+    ENGINE.set_peak_hold(PeakHoldConfig { hold_ms, decay_db_per_sec }).map_err(|e| e.to_string())
+}
+
+/// Idle, Monitoring, Recording or Exporting
+#[tauri::command]
+fn get_engine_state() -> EngineState {
+    ENGINE.state()
 }
 
 #[tauri::command]
 async fn list_midi_devices() -> Result<Vec<String>, String> {
     println!("🎹 Listing MIDI devices...");
     
-    let mut manager_guard = MIDI_MANAGER.lock().unwrap();
-    let midi_manager = match manager_guard.as_mut() {
-        Some(manager) => manager,
-        None => {
-            let new_manager = MidiManager::new().map_err(|e| {
-                println!("❌ Failed to create MIDI manager: {}", e);
-                e.to_string()
-            })?;
-            *manager_guard = Some(new_manager);
-            manager_guard.as_mut().unwrap()
-        }
-    };
-    
-    let devices = midi_manager.list_output_devices().map_err(|e| {
+    let devices = ENGINE.list_midi_outputs().map_err(|e| {
         println!("❌ Failed to list MIDI devices: {}", e);
         e.to_string()
     })?;
@@ -379,9 +263,94 @@ async fn list_midi_devices() -> Result<Vec<String>, String> {
     Ok(devices)
 }
 
+#[tauri::command]
+async fn list_midi_input_devices() -> Result<Vec<String>, String> {
+    println!("🎹 Listing MIDI input devices...");
+    
+    ENGINE.list_midi_inputs().map_err(|e| {
+        println!("❌ Failed to list MIDI input devices: {}", e);
+        e.to_string()
+    })
+}
+
+/// Watch a MIDI input and push what arrives as `midi-message` events. `kinds` is a comma
+/// list of notes, cc, program, pitchbend, aftertouch, sysex, system (None = all);
+/// `channel` is 1-16 (None = any); Timing Clock and Active Sensing only come through with
+/// `include_clock`. Returns false if the monitor was already running.
+#[tauri::command]
+fn start_midi_monitor(app: tauri::AppHandle, input_device_index: usize, kinds: Option<String>, channel: Option<u8>, include_clock: Option<bool>) -> Result<bool, String> {
+    if channel.is_some_and(|channel| !(1..=16).contains(&channel)) {
+        return Err(format!("Invalid MIDI channel: {}", channel.unwrap_or_default()));
+    }
+    let kinds = kinds.as_deref().map(MonitorFilter::parse_kinds).transpose()
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+    let filter = MonitorFilter {
+        kinds,
+        channel: channel.map(|channel| channel - 1),
+        pass_timing: include_clock.unwrap_or(false),
+    };
+    println!("🎧 GUI: Monitoring MIDI input {} ({:?})", input_device_index, filter);
+    
+    ENGINE.start_midi_monitor(input_device_index, filter, move |message| {
+        let _ = app.emit("midi-message", message);
+    }).map_err(|e| format!("Failed to start MIDI monitor: {}", e))
+}
+
+/// Stop the MIDI monitor; returns how many messages it kept (for save_midi_capture)
+#[tauri::command]
+fn stop_midi_monitor() -> usize {
+    let Some(capture) = ENGINE.stop_midi_monitor() else {
+        return 0;
+    };
+    let count = capture.messages.len();
+    println!("🎧 GUI: MIDI monitor stopped after {} messages", count);
+    count
+}
+
+/// Save the running or last MIDI monitor's messages as .json or .mid, picked in a save dialog
+#[tauri::command]
+async fn save_midi_capture(app: tauri::AppHandle) -> Result<String, String> {
+    use tauri_plugin_dialog::DialogExt;
+    use std::sync::mpsc;
+    
+    let capture = ENGINE.midi_monitor_capture()
+        .filter(|capture| !capture.messages.is_empty())
+        .ok_or("No MIDI messages to save - start the monitor first")?;
+    
+    let (tx, rx) = mpsc::channel();
+    app.dialog()
+        .file()
+        .set_title("Save MIDI Capture")
+        .add_filter("MIDI file", &["mid"])
+        .add_filter("JSON", &["json"])
+        .set_file_name("MIDI Monitor.mid")
+        .save_file(move |file_path| {
+            let _ = tx.send(file_path);
+        });
+    
+    let path = match rx.recv() {
+        Ok(Some(path)) => std::path::PathBuf::from(path.to_string()),
+        Ok(None) => return Err("Save cancelled".to_string()),
+        Err(e) => return Err(format!("Save dialog failed: {}", e)),
+    };
+    capture.save(&path).map_err(|e| format!("Failed to save MIDI capture: {}", e))?;
+    println!("💾 Saved {} MIDI messages to {}", capture.messages.len(), path.display());
+    Ok(path.display().to_string())
+}
+
+/// Identify the connected synth via MIDI Device Inquiry; the reply names sessions/folders
+#[tauri::command]
+fn identify_synth(input_device_index: usize) -> Result<batcherbird_core::identity::SynthIdentity, String> {
+    ENGINE.identify_synth(input_device_index, Duration::from_secs(2))
+        .map_err(|e| format!("Synth did not identify itself: {}", e))
+}
+
 #[tauri::command]
 async fn list_audio_input_devices() -> Result<Vec<String>, String> {
     println!("🎤 Listing audio input devices...");
+    // A rescan may find a new interface; renegotiate on the next take
+    AudioManager::clear_device_cache();
     let audio_manager = AudioManager::new().map_err(|e| {
         println!("❌ Failed to create audio manager: {}", e);
         e.to_string()
@@ -400,9 +369,20 @@ async fn list_audio_input_devices() -> Result<Vec<String>, String> {
     Ok(devices)
 }
 
+/// Sample rates, channel counts and formats of the input device at `index` (as listed)
+#[tauri::command]
+async fn describe_audio_input_device(index: usize) -> Result<DeviceCapabilities, String> {
+    let audio_manager = AudioManager::new().map_err(|e| e.to_string())?;
+    audio_manager.describe_device(index).map_err(|e| {
+        println!("❌ Failed to query audio input {}: {}", index, e);
+        e.to_string()
+    })
+}
+
 #[tauri::command]
 async fn list_audio_output_devices() -> Result<Vec<String>, String> {
     println!("🔊 Listing audio output devices...");
+    AudioManager::clear_device_cache();
     let audio_manager = AudioManager::new().map_err(|e| {
         println!("❌ Failed to create audio manager: {}", e);
         e.to_string()
@@ -425,82 +405,25 @@ async fn list_audio_output_devices() -> Result<Vec<String>, String> {
 async fn connect_midi_device(device_index: usize) -> Result<String, String> {
     println!("🔌 Connecting to MIDI device index: {}", device_index);
     
-    let mut manager_guard = MIDI_MANAGER.lock().unwrap();
-    let midi_manager = match manager_guard.as_mut() {
-        Some(manager) => manager,
-        None => {
-            println!("❌ No MIDI manager available - list devices first");
-            return Err("MIDI manager not initialized. Please refresh MIDI devices first.".to_string());
-        }
-    };
-    
-    let connection = midi_manager.connect_output(device_index).map_err(|e| {
+    ENGINE.connect_midi(device_index).map_err(|e| {
         println!("❌ Failed to connect to MIDI device {}: {}", device_index, e);
         e.to_string()
     })?;
-    
-    drop(manager_guard); // Release the manager lock before taking connection lock
-    *MIDI_CONNECTION.lock().unwrap() = Some(connection);
-    println!("✅ MIDI device {} connected successfully", device_index);
     Ok("MIDI device connected successfully".to_string())
 }
 
 #[tauri::command]
 async fn test_midi_connection() -> Result<String, String> {
-    // Extract the connection from the mutex and drop the guard
-    let mut connection = {
-        let mut connection_guard = MIDI_CONNECTION.lock().unwrap();
-        match connection_guard.take() {
-            Some(conn) => conn,
-            None => return Err("No MIDI connection established. Please select a MIDI device first.".to_string()),
-        }
-    };
-    
-    // Now we can safely await without holding the guard
-    let result = MidiManager::send_test_note(&mut connection, 0, 60, 127, Duration::from_millis(500))
-        .await
-        .map_err(|e| e.to_string());
-    
-    // Put the connection back
-    *MIDI_CONNECTION.lock().unwrap() = Some(connection);
-    
-    match result {
-        Ok(_) => Ok("Test note sent successfully".to_string()),
-        Err(e) => Err(e),
-    }
+    ENGINE.play_note(0, 60, 127, Duration::from_millis(500)).map_err(|e| e.to_string())?;
+    Ok("Test note sent successfully".to_string())
 }
 
 #[tauri::command]
 async fn preview_note(note: u8, velocity: u8, duration: u32) -> Result<String, String> {
     println!("🎵 Preview note: {} (velocity: {}, duration: {}ms)", note, velocity, duration);
     
-    // Extract the connection from the mutex and drop the guard
-    let mut connection = {
-        let mut connection_guard = MIDI_CONNECTION.lock().unwrap();
-        match connection_guard.take() {
-            Some(conn) => conn,
-            None => return Err("No MIDI connection established. Please select a MIDI device first.".to_string()),
-        }
-    };
-    
-    // Send the note with custom parameters
-    let result = MidiManager::send_test_note(
-        &mut connection, 
-        0, // channel 0
-        note, 
-        velocity, 
-        Duration::from_millis(duration as u64)
-    )
-    .await
-    .map_err(|e| e.to_string());
-    
-    // Put the connection back
-    *MIDI_CONNECTION.lock().unwrap() = Some(connection);
-    
-    match result {
-        Ok(_) => Ok(format!("Preview note {} sent successfully", note)),
-        Err(e) => Err(e),
-    }
+    ENGINE.play_note(0, note, velocity, Duration::from_millis(duration as u64)).map_err(|e| e.to_string())?;
+    Ok(format!("Preview note {} sent successfully", note))
 }
 
 #[tauri::command]
@@ -508,7 +431,7 @@ async fn select_output_directory(app: tauri::AppHandle) -> Result<String, String
     use tauri_plugin_dialog::DialogExt;
     use std::sync::mpsc;
     
-    println!("📁 Opening native macOS directory picker...");
+    println!("📁 Opening native directory picker...");
     
     let (tx, rx) = mpsc::channel();
     
@@ -523,6 +446,16 @@ async fn select_output_directory(app: tauri::AppHandle) -> Result<String, String
         Ok(Some(path)) => {
             let path_str = path.to_string();
             println!("✅ User selected directory: {}", path_str);
+            
+            // Bookmark the folder now, while the picker's sandbox grant is still valid
+            let mut store_guard = BOOKMARK_STORE.lock().unwrap();
+            if let Some((store, store_file)) = store_guard.as_mut() {
+                store.remember(std::path::Path::new(&path_str));
+                if let Err(e) = store.save(store_file) {
+                    println!("⚠️ Failed to save output folder bookmarks: {}", e);
+                }
+            }
+            
             Ok(path_str)
         },
         Ok(None) => {
@@ -536,26 +469,308 @@ async fn select_output_directory(app: tauri::AppHandle) -> Result<String, String
     }
 }
 
+/// Previously chosen output folders that are still accessible (most recent first)
+#[tauri::command]
+fn get_recent_output_directories() -> Result<Vec<String>, String> {
+    let store_guard = BOOKMARK_STORE.lock().unwrap();
+    Ok(store_guard.as_ref()
+        .map(|(store, _)| store.folders.iter().map(|f| f.path.clone()).collect())
+        .unwrap_or_default())
+}
+
+/// Resolve where exports will actually be written (falls back when the sandbox denies a location)
+#[tauri::command]
+fn resolve_output_directory(output_directory: Option<String>) -> Result<output_location::ResolvedOutput, String> {
+    output_location::resolve_output_directory(output_directory.as_deref())
+        .map_err(|e| format!("No writable output directory: {}", e))
+}
+
+/// Generate a demo project (no hardware needed) so new users can explore the outputs
+#[tauri::command]
+fn generate_demo_project(output_directory: Option<String>) -> Result<batcherbird_core::demo::DemoProject, String> {
+    let resolved = output_location::resolve_output_directory(output_directory.as_deref())
+        .map_err(|e| format!("No writable output directory: {}", e))?;
+    
+    batcherbird_core::demo::generate_demo_project(&resolved.path.join("Batcherbird Demo"))
+        .map_err(|e| format!("Failed to generate demo project: {}", e))
+}
+
+/// Run `f` with the profile library, loading built-in + user profiles on first use
+fn with_profile_library<T>(f: impl FnOnce(&ProfileLibrary) -> T) -> T {
+    let mut library_guard = PROFILE_LIBRARY.lock().unwrap();
+    let library = library_guard.get_or_insert_with(ProfileLibrary::load_default);
+    f(library)
+}
+
+#[tauri::command]
+fn list_synth_profiles() -> Vec<SynthProfile> {
+    with_profile_library(|library| library.profiles().to_vec())
+}
+
+/// Past sessions for the library browser, newest first
+#[tauri::command]
+fn list_catalog_sessions(name: Option<String>, device: Option<String>, note: Option<u8>, with_issues: Option<bool>, limit: Option<usize>) -> Result<Vec<CatalogSession>, String> {
+    let catalog = SessionCatalog::open_default().map_err(|e| e.to_string())?;
+    let query = CatalogQuery { name, device, note, since: None, with_issues: with_issues.unwrap_or(false), limit };
+    catalog.query(&query).map_err(|e| e.to_string())
+}
+
+/// Forget a session in the library browser; its files stay where they are
+#[tauri::command]
+fn remove_catalog_session(id: String) -> Result<bool, String> {
+    let catalog = SessionCatalog::open_default().map_err(|e| e.to_string())?;
+    Ok(catalog.remove(&id).map_err(|e| e.to_string())?.is_some())
+}
+
+/// Select the synth profile used by recording commands (None = generic defaults)
+#[tauri::command]
+fn select_synth_profile(profile_id: Option<String>) -> Result<Option<SynthProfile>, String> {
+    let profile = match profile_id.as_deref().map(str::trim).filter(|id| !id.is_empty()) {
+        Some(id) => Some(with_profile_library(|library| library.require(id).cloned()).map_err(|e| e.to_string())?),
+        None => None,
+    };
+    
+    match &profile {
+        Some(p) => println!("🎛️ GUI: Selected synth profile: {} ({})", p.name, p.id),
+        None => println!("🎛️ GUI: Cleared synth profile"),
+    }
+    *SELECTED_PROFILE.lock().unwrap() = profile.clone();
+    Ok(profile)
+}
+
+/// Profile matching a Device Inquiry reply (see identify_synth), if any
+#[tauri::command]
+fn match_synth_profile(identity: SynthIdentity) -> Option<SynthProfile> {
+    with_profile_library(|library| library.find_for_identity(&identity).cloned())
+}
+
+/// Parse the optional velocity curve spec from the UI ("linear", "exp:2", "custom:0=0,64=40,127=127")
+fn parse_velocity_curve(spec: Option<String>) -> Result<VelocityCurve, String> {
+    match spec.as_deref().map(str::trim) {
+        None | Some("") => Ok(VelocityCurve::Linear),
+        Some(spec) => VelocityCurve::parse(spec).map_err(|e| e.to_string()),
+    }
+}
+
+/// Parse the optional overwrite policy from the UI ("error", "overwrite", "rename", "skip")
+fn parse_overwrite_policy(spec: Option<String>) -> Result<OverwritePolicy, String> {
+    match spec.as_deref().map(str::trim) {
+        None | Some("") => Ok(OverwritePolicy::default()),
+        Some(spec) => OverwritePolicy::parse(spec).ok_or_else(|| format!("Unknown overwrite policy: {}", spec)),
+    }
+}
+
+/// Raw measurements of a take, sent as a "take-analysis" event as soon as it is exported
+#[derive(Clone, serde::Serialize)]
+struct TakeAnalysisEvent {
+    file_path: String,
+    analysis: TakeAnalysis,
+}
+
+fn emit_take_analysis(app: &tauri::AppHandle, file_path: &std::path::Path, analysis: TakeAnalysis) {
+    let event = TakeAnalysisEvent { file_path: file_path.display().to_string(), analysis };
+    if let Err(e) = app.emit("take-analysis", event) {
+        println!("⚠️ GUI: Failed to send take analysis: {}", e);
+    }
+}
+
+/// Per-note status of a running record_range or run_sampling_queue, sent as range-note events
+#[derive(Clone, serde::Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum RangeNoteEvent {
+    Started { note: u8, velocity: u8 },
+    Recorded { note: u8, velocity: u8, peak_db: f32, rms_db: f32, duration_secs: f32 },
+    Exported { note: u8, velocity: u8, file_path: String },
+    Failed { note: u8, velocity: u8, reason: String },
+}
+
+impl From<CaptureProgress> for RangeNoteEvent {
+    fn from(progress: CaptureProgress) -> Self {
+        match progress {
+            CaptureProgress::Started { note, velocity } => RangeNoteEvent::Started { note, velocity },
+            CaptureProgress::Failed { note, velocity, reason } => RangeNoteEvent::Failed { note, velocity, reason },
+        }
+    }
+}
+
+fn emit_range_note(app: &tauri::AppHandle, event: RangeNoteEvent) {
+    if let Err(e) = app.emit("range-note", event) {
+        println!("⚠️ GUI: Failed to send note progress: {}", e);
+    }
+}
+
+/// Sent when a recording started by record_sample, record_range or run_sampling_queue has
+/// finished, failed or been cancelled
+#[derive(Clone, serde::Serialize)]
+struct RecordingCompleteEvent {
+    command: &'static str,
+    ok: bool,
+    message: String,
+}
+
+fn emit_recording_complete(app: &tauri::AppHandle, command: &'static str, result: Result<String, String>) {
+    let (ok, message) = match result {
+        Ok(message) => (true, message),
+        Err(message) => (false, message),
+    };
+    if let Err(e) = app.emit("recording-complete", RecordingCompleteEvent { command, ok, message }) {
+        println!("⚠️ GUI: Failed to send recording result: {}", e);
+    }
+}
+
+/// Fail fast instead of queueing a background recording behind a running one
+fn ensure_engine_free() -> Result<(), String> {
+    match ENGINE.state() {
+        state @ (EngineState::Recording | EngineState::Exporting) => Err(format!("Engine is busy ({:?})", state)),
+        _ => Ok(()),
+    }
+}
+
+/// Record one note in the background and return straight away; the saved file (or the
+/// error) arrives as a recording-complete event
+#[tauri::command]
+fn record_sample(app: tauri::AppHandle, note: u8, velocity: u8, duration: u32, output_directory: Option<String>, sample_name: Option<String>, export_format: Option<String>, creator_name: Option<String>, instrument_description: Option<String>, velocity_curve: Option<String>, sidecar_edits: Option<bool>, overwrite_policy: Option<String>) -> Result<(), String> {
+    ensure_engine_free()?;
+    std::thread::spawn(move || {
+        let result = record_sample_blocking(app.clone(), note, velocity, duration, output_directory, sample_name, export_format, creator_name, instrument_description, velocity_curve, sidecar_edits, overwrite_policy);
+        emit_recording_complete(&app, "record_sample", result);
+    });
+    Ok(())
+}
+
+/// Record a range in the background, once per entry of `velocities` (None = just `velocity`);
+/// progress arrives as range-note events, the result as recording-complete (see record_sample)
+#[tauri::command]
+fn record_range(app: tauri::AppHandle, start_note: u8, end_note: u8, velocity: u8, velocities: Option<Vec<u8>>, duration: u32, output_directory: Option<String>, sample_name: Option<String>, export_format: Option<String>, creator_name: Option<String>, instrument_description: Option<String>, velocity_curve: Option<String>, sidecar_edits: Option<bool>, overwrite_policy: Option<String>) -> Result<(), String> {
+    ensure_engine_free()?;
+    std::thread::spawn(move || {
+        let result = record_batch_blocking(app.clone(), Batch::Range { start_note, end_note, velocities: velocities.unwrap_or_default() }, velocity, duration, output_directory, sample_name, export_format, creator_name, instrument_description, velocity_curve, sidecar_edits, overwrite_policy);
+        emit_recording_complete(&app, "record_range", result);
+    });
+    Ok(())
+}
+
+/// The queue as the UI shows it
+#[derive(Clone, serde::Serialize)]
+struct QueueView {
+    entries: Vec<QueueEntry>,
+    take_count: usize,
+}
+
+fn queue_view(queue: &SamplingQueue) -> QueueView {
+    QueueView { entries: queue.entries().to_vec(), take_count: queue.take_count() }
+}
+
+#[tauri::command]
+fn get_sampling_queue() -> QueueView {
+    queue_view(&ENGINE.sampling_queue())
+}
+
+/// Append a range to the queue (articulation "sustain", "release" or "legato"; no velocities
+/// = the velocity given to run_sampling_queue; no legato/release settings = the defaults)
+#[tauri::command]
+fn queue_add_entry(
+    start_note: u8,
+    end_note: u8,
+    velocities: Option<Vec<u8>>,
+    articulation: Option<String>,
+    program: Option<u8>,
+    legato: Option<LegatoConfig>,
+    release: Option<ReleaseCaptureConfig>,
+) -> Result<QueueView, String> {
+    let articulation = match articulation.as_deref() {
+        Some(name) => Articulation::parse(name).ok_or_else(|| format!("Unknown articulation: {}", name))?,
+        None => Articulation::default(),
+    };
+    let entry = QueueEntry {
+        velocities: velocities.unwrap_or_default(),
+        articulation,
+        program,
+        legato: legato.unwrap_or_default(),
+        release: release.unwrap_or_default(),
+        ..QueueEntry::new(start_note, end_note)
+    };
+    ENGINE.edit_queue(|queue| {
+        queue.push(entry).map_err(|e| e.to_string())?;
+        Ok(queue_view(queue))
+    })
+}
+
+#[tauri::command]
+fn queue_remove_entry(id: u64) -> Result<QueueView, String> {
+    ENGINE.edit_queue(|queue| {
+        queue.remove(id).map_err(|e| e.to_string())?;
+        Ok(queue_view(queue))
+    })
+}
+
+/// Move an entry to a new position (0 = recorded first)
+#[tauri::command]
+fn queue_move_entry(id: u64, index: usize) -> Result<QueueView, String> {
+    ENGINE.edit_queue(|queue| {
+        queue.move_entry(id, index).map_err(|e| e.to_string())?;
+        Ok(queue_view(queue))
+    })
+}
+
+#[tauri::command]
+fn queue_clear() -> QueueView {
+    ENGINE.edit_queue(|queue| {
+        queue.clear();
+        queue_view(queue)
+    })
+}
+
+/// Record the whole queue in the background as one supervised run, exported into one
+/// instrument; progress arrives as range-note events, the result as recording-complete
+#[tauri::command]
+fn run_sampling_queue(app: tauri::AppHandle, velocity: u8, duration: u32, output_directory: Option<String>, sample_name: Option<String>, export_format: Option<String>, creator_name: Option<String>, instrument_description: Option<String>, velocity_curve: Option<String>, sidecar_edits: Option<bool>, overwrite_policy: Option<String>) -> Result<(), String> {
+    ensure_engine_free()?;
+    let queue = ENGINE.sampling_queue();
+    if queue.is_empty() {
+        return Err("The sampling queue is empty".to_string());
+    }
+    std::thread::spawn(move || {
+        let result = record_batch_blocking(app.clone(), Batch::Queue(queue), velocity, duration, output_directory, sample_name, export_format, creator_name, instrument_description, velocity_curve, sidecar_edits, overwrite_policy);
+        emit_recording_complete(&app, "run_sampling_queue", result);
+    });
+    Ok(())
+}
+
+/// Stop the running record_sample/record_range/run_sampling_queue: the note is cut short and the synth
+/// silenced. Takes already exported by a range stay on disk.
+#[tauri::command]
+fn cancel_recording() -> Result<String, String> {
+    if ENGINE.cancel_recording() {
+        Ok("Cancelling recording...".to_string())
+    } else {
+        Err("No recording in progress".to_string())
+    }
+}
+
 /// GUI Layer: Blocking orchestration following TAURI_AUDIO_ARCHITECTURE.md
 /// Uses dedicated thread + channels pattern for thread safety
-#[tauri::command]  // BLOCKING command (no async) - this is correct for audio
-fn record_sample(note: u8, velocity: u8, duration: u32, output_directory: Option<String>, sample_name: Option<String>, _export_format: Option<String>, _creator_name: Option<String>, _instrument_description: Option<String>) -> Result<String, String> {
+fn record_sample_blocking(app: tauri::AppHandle, note: u8, velocity: u8, duration: u32, output_directory: Option<String>, sample_name: Option<String>, _export_format: Option<String>, _creator_name: Option<String>, _instrument_description: Option<String>, velocity_curve: Option<String>, sidecar_edits: Option<bool>, overwrite_policy: Option<String>) -> Result<String, String> {
     println!("🎛️ GUI: Recording sample (note: {}, velocity: {}, duration: {}ms)", note, velocity, duration);
     
-    // Step 1: Get MIDI connection (GUI responsibility)
-    let mut connection = {
-        let mut connection_guard = MIDI_CONNECTION.lock().unwrap();
-        match connection_guard.take() {
-            Some(conn) => conn,
-            None => return Err("No MIDI connection established. Please select a MIDI device first.".to_string()),
-        }
+    let velocity_curve = parse_velocity_curve(velocity_curve)?;
+    let overwrite = parse_overwrite_policy(overwrite_policy)?;
+    let profile = SELECTED_PROFILE.lock().unwrap().clone();
+    let naming = NamingContext {
+        session: sample_name.as_ref().map(|name| name.trim().to_string()).filter(|name| !name.is_empty()),
+        synth: profile.as_ref().map(|p| p.name.clone()),
     };
-    
-    // Step 2: Audio processing in dedicated thread (follows architecture pattern)
+    let midi_channel = profile.as_ref().map_or(0, |p| p.channel_index());
+    // Channel 10 means drum mode: files and regions are named after the GM drum map
+    let drum_map = (midi_channel == GM_DRUM_CHANNEL).then(DrumMap::load_default);
+    let panic_style = profile.as_ref().map(|p| p.panic_style).unwrap_or_default();
+    let clock = profile.as_ref().and_then(|p| p.clock());
+    let detection_config = profile.as_ref().map(|p| p.detection_config()).unwrap_or_default();
+    
+    // Step 1: Audio processing on the controller's capture thread (holds the MIDI connection)
     println!("📡 GUI: Delegating to Core Audio Engine in dedicated thread...");
     
-    let (tx, rx) = std::sync::mpsc::channel();
-    std::thread::spawn(move || {
+    let recording_result = ENGINE.record_cancellable(move |connection, cancel| {
         println!("🧵 Audio thread started");
         
         // Configure Core Audio Engine
@@ -565,71 +780,48 @@ fn record_sample(note: u8, velocity: u8, duration: u32, output_directory: Option
             release_time_ms: 500,  // Professional standard: 500ms release capture
             pre_delay_ms: 100,     // Professional standard: 100ms pre-roll  
             post_delay_ms: 100,    // Clean buffer flush
-            midi_channel: 0,       // Channel 1 (0-indexed)
+            midi_channel,          // Channel 1 (0-indexed) unless the synth profile says otherwise
             velocity,
+            velocity_curve,
+            panic_style,
+            clock,
+            watchdog: Default::default(),
+            hooks: Default::default(),
         };
         
         println!("🎛️ Creating SamplingEngine with config: {:?}", sampling_config);
-        let sampling_engine = match SamplingEngine::new(sampling_config) {
-            Ok(engine) => {
-                println!("✅ SamplingEngine created successfully");
-                engine
-            },
-            Err(e) => {
-                println!("❌ Failed to create SamplingEngine: {}", e);
-                let _ = tx.send((Err(e), connection));
-                return;
-            }
-        };
+        let sampling_engine = SamplingEngine::new(sampling_config)
+            .inspect_err(|e| println!("❌ Failed to create SamplingEngine: {}", e))?
+            .with_cancel(cancel);
         
         // Use blocking method from Core Audio Engine
         println!("🎵 Starting sample recording for note {}", note);
-        let result = sampling_engine.sample_single_note_blocking(&mut connection, note);
+        let result = sampling_engine.sample_single_note_blocking(connection, note);
         
         match &result {
             Ok(sample) => println!("✅ Recording completed: {} samples", sample.audio_data.len()),
             Err(e) => println!("❌ Recording failed: {}", e),
         }
-        
-        // Send result back via channel
-        println!("📡 Sending result back to main thread");
-        let _ = tx.send((result, connection));
+        result
     });
     
-    // Step 3: Block until audio operation completes (this is correct for audio)
-    let (recording_result, returned_connection) = rx.recv()
-        .map_err(|e| format!("Audio thread communication failed: {}", e))?;
-    
-    // Put the connection back
-    *MIDI_CONNECTION.lock().unwrap() = Some(returned_connection);
-    
     match recording_result {
-        Ok(recorded_sample) => {
+        Ok(mut recorded_sample) => {
             println!("✅ GUI: Core Audio Engine completed recording successfully");
+            recorded_sample.track_pitch_drift(Some(DEFAULT_DRIFT_LIMIT_CENTS));
+            recorded_sample.verify_played_note();
             println!("📊 GUI: Received {} samples from Core Engine", recorded_sample.audio_data.len());
             
-            // Step 4: Handle export (GUI orchestration)
-            let output_dir = if let Some(dir) = output_directory {
-                if dir.trim().is_empty() {
-                    // Use Desktop/Batcherbird Samples when field is empty
-                    dirs::desktop_dir()
-                        .map(|desktop| desktop.join("Batcherbird Samples"))
-                        .unwrap_or_else(|| std::path::PathBuf::from("samples"))
-                        .to_string_lossy()
-                        .to_string()
-                } else {
-                    dir
-                }
-            } else {
-                // Default to Desktop/Batcherbird Samples
-                dirs::desktop_dir()
-                    .map(|desktop| desktop.join("Batcherbird Samples"))
-                    .unwrap_or_else(|| std::path::PathBuf::from("samples"))
-                    .to_string_lossy()
-                    .to_string()
-            };
+            // Step 2: Handle export (GUI orchestration)
+            let _exporting = ENGINE.begin(EngineState::Exporting).map_err(|e| e.to_string())?;
+            // Empty field means Desktop/Batcherbird Samples, with sandbox-safe fallbacks
+            let resolved = output_location::resolve_output_directory(output_directory.as_deref())
+                .map_err(|e| format!("No writable output directory: {}", e))?;
+            if resolved.used_fallback {
+                println!("⚠️ GUI: Requested output location not writable, using {}", resolved.path.display());
+            }
             
-            let mut output_path = std::path::PathBuf::from(&output_dir);
+            let mut output_path = resolved.path;
             
             // Create subfolder if sample name is provided (professional organization)
             if let Some(name) = sample_name.as_ref().filter(|n| !n.trim().is_empty()) {
@@ -663,9 +855,14 @@ fn record_sample(note: u8, velocity: u8, duration: u32, output_directory: Option
                 fade_in_ms: 0.0,
                 fade_out_ms: 10.0,
                 apply_detection: true, // Enable detection by default
-                detection_config: Default::default(),
+                detection_config,
                 creator_name: None, // No metadata needed for individual WAV files
                 instrument_description: None, // No metadata needed for individual WAV files
+                drum_map,
+                sidecar_edits: sidecar_edits.unwrap_or(false), // Raw take + edit file, rendered later
+                overwrite,
+                naming,
+                ..ExportConfig::default()
             };
             
             println!("🔧 GUI: Creating sample exporter...");
@@ -682,7 +879,13 @@ fn record_sample(note: u8, velocity: u8, duration: u32, output_directory: Option
             
             println!("💾 GUI: Sample exported: {}", file_path.display());
             
-            // Step 5: Return success to UI
+            // Analyse the raw capture so the take list can show levels and pitch right away
+            let analysis = TakeAnalysis::from_sample(&recorded_sample);
+            println!("📊 GUI: Peak {:.1} dB, RMS {:.1} dB, {:.2}s, detected note {:?}",
+                analysis.peak_db, analysis.rms_db, analysis.duration_secs, analysis.detected_note);
+            emit_take_analysis(&app, &file_path, analysis);
+            
+            // Step 3: Return success to UI
             let filename = file_path.file_name()
                 .map(|name| name.to_string_lossy().to_string())
                 .unwrap_or_else(|| "unknown".to_string());
@@ -700,189 +903,307 @@ fn record_sample(note: u8, velocity: u8, duration: u32, output_directory: Option
     }
 }
 
+/// Probe one note at several velocities and suggest layer velocities with evenly spaced loudness
 #[tauri::command]
-fn record_range(start_note: u8, end_note: u8, velocity: u8, duration: u32, output_directory: Option<String>, sample_name: Option<String>, export_format: Option<String>, creator_name: Option<String>, instrument_description: Option<String>) -> Result<String, String> {
-    println!("🎹 GUI: Recording range sampling (notes: {}-{}, velocity: {}, duration: {}ms)", start_note, end_note, velocity, duration);
+fn suggest_velocity_layers(note: u8, layers: usize, duration: u32) -> Result<Vec<u8>, String> {
+    println!("📈 GUI: Measuring velocity response (note: {}, layers: {})", note, layers);
+    
+    let profile = SELECTED_PROFILE.lock().unwrap().clone();
+    let midi_channel = profile.as_ref().map_or(0, |p| p.channel_index());
+    let panic_style = profile.as_ref().map(|p| p.panic_style).unwrap_or_default();
+    
+    let sampling_config = SamplingConfig {
+        note_duration_ms: duration as u64,
+        release_time_ms: 200,  // Only the loudest part matters for the measurement
+        midi_channel,
+        panic_style,
+        ..SamplingConfig::default()
+    };
     
-    // Step 1: Get MIDI connection (GUI responsibility)
-    let mut connection = {
-        let mut connection_guard = MIDI_CONNECTION.lock().unwrap();
-        match connection_guard.take() {
-            Some(conn) => conn,
-            None => return Err("No MIDI connection established. Please select a MIDI device first.".to_string()),
+    let result = ENGINE.record(move |connection| {
+        SamplingEngine::new(sampling_config).and_then(|engine| {
+            engine.measure_velocity_response_blocking(connection, note, &DEFAULT_PROBE_VELOCITIES)
+        })
+    });
+    
+    let response = result.map_err(|e| format!("Velocity measurement failed: {}", e))?;
+    let velocities = response.place_layers(layers);
+    println!("✅ GUI: Suggested velocity layers: {:?}", velocities);
+    Ok(velocities)
+}
+
+/// What record_range would record with these settings: every take, the recording time and
+/// the disk space, without touching MIDI or audio streams
+#[tauri::command]
+fn plan_recording(start_note: u8, end_note: u8, velocity: u8, duration: u32, export_format: Option<String>) -> Result<SamplingPlan, String> {
+    let sampling_config = SamplingConfig {
+        note_duration_ms: duration as u64,
+        release_time_ms: 500,
+        pre_delay_ms: 100,
+        post_delay_ms: 100,
+        velocity,
+        ..SamplingConfig::default()
+    };
+    // Same formats as record_range: instrument WAVs at the default depth, else 32-bit float
+    let bit_depth = match export_format.as_deref().and_then(AudioFormat::parse) {
+        Some(format) if format.is_instrument() => BitDepth::default(),
+        _ => BitDepth::Float32,
+    };
+    let input = AudioManager::new().ok().and_then(|audio| audio.input_device_and_config().ok());
+    let defaults = PlanAudio::default();
+    let audio = PlanAudio {
+        sample_rate: input.as_ref().map_or(defaults.sample_rate, |(_, config)| config.sample_rate().0),
+        channels: input.as_ref().map_or(defaults.channels, |(_, config)| config.channels()),
+        bit_depth,
+    };
+    let matrix = PlanMatrix { start_note, end_note, velocities: vec![velocity], round_robins: 1 };
+    SamplingPlan::new(&sampling_config, &matrix, audio).map_err(|e| e.to_string())
+}
+
+/// What a batch recording plays
+enum Batch {
+    /// Empty velocities = the batch velocity
+    Range { start_note: u8, end_note: u8, velocities: Vec<u8> },
+    Queue(SamplingQueue),
+}
+
+impl Batch {
+    fn label(&self) -> &'static str {
+        match self {
+            Batch::Range { .. } => "Range recording",
+            Batch::Queue(_) => "Sampling queue",
         }
+    }
+}
+
+fn record_batch_blocking(app: tauri::AppHandle, batch: Batch, velocity: u8, duration: u32, output_directory: Option<String>, sample_name: Option<String>, export_format: Option<String>, creator_name: Option<String>, instrument_description: Option<String>, velocity_curve: Option<String>, sidecar_edits: Option<bool>, overwrite_policy: Option<String>) -> Result<String, String> {
+    match &batch {
+        Batch::Range { start_note, end_note, .. } => println!("🎹 GUI: Recording range sampling (notes: {}-{}, velocity: {}, duration: {}ms)", start_note, end_note, velocity, duration),
+        Batch::Queue(queue) => println!("📋 GUI: Recording sampling queue ({} entries, {} takes, duration: {}ms)", queue.entries().len(), queue.take_count(), duration),
+    }
+    
+    let velocity_curve = parse_velocity_curve(velocity_curve)?;
+    let overwrite = parse_overwrite_policy(overwrite_policy)?;
+    let profile = SELECTED_PROFILE.lock().unwrap().clone();
+    let naming = NamingContext {
+        session: sample_name.as_ref().map(|name| name.trim().to_string()).filter(|name| !name.is_empty()),
+        synth: profile.as_ref().map(|p| p.name.clone()),
+    };
+    let midi_channel = profile.as_ref().map_or(0, |p| p.channel_index());
+    // Channel 10 means drum mode: files and regions are named after the GM drum map
+    let drum_map = (midi_channel == GM_DRUM_CHANNEL).then(DrumMap::load_default);
+    let panic_style = profile.as_ref().map(|p| p.panic_style).unwrap_or_default();
+    let clock = profile.as_ref().and_then(|p| p.clock());
+    let detection_config = profile.as_ref().map(|p| p.detection_config()).unwrap_or_default();
+    // How long queue entries wait after their Program Change
+    let settle_ms = profile.as_ref().map_or(PatchIterationConfig::default().settle_ms, |p| p.program_change_settle_ms);
+    
+    // Resolve the output folder up front: takes are exported as they are recorded
+    let resolved = output_location::resolve_output_directory(output_directory.as_deref())
+        .map_err(|e| format!("No writable output directory: {}", e))?;
+    if resolved.used_fallback {
+        println!("⚠️ GUI: Requested output location not writable, using {}", resolved.path.display());
+    }
+    
+    let mut output_path = resolved.path;
+    
+    // Create subfolder if sample name is provided (professional organization)
+    if let Some(name) = sample_name.as_ref().filter(|n| !n.trim().is_empty()) {
+        output_path = output_path.join(name.trim());
+        println!("📁 GUI: Creating subfolder for range samples: {}", name.trim());
+    }
+    
+    // Ensure output directory exists (including subfolder)
+    if let Err(e) = std::fs::create_dir_all(&output_path) {
+        println!("❌ GUI: Failed to create output directory: {}", e);
+        return Err(format!("Failed to create output directory '{}': {}", output_path.display(), e));
+    }
+    
+    println!("📁 GUI: Using output directory: {}", output_path.display());
+    
+    // Configure Core Audio Engine (kept here so the README can list the settings)
+    let sampling_config = SamplingConfig {
+        note_duration_ms: duration as u64,
+        release_time_ms: 500,  // Professional standard: 500ms release capture
+        pre_delay_ms: 100,     // Professional standard: 100ms pre-roll  
+        post_delay_ms: 100,    // Clean buffer flush
+        midi_channel,          // Channel 1 (0-indexed) unless the synth profile says otherwise
+        velocity,
+        velocity_curve,
+        panic_style,
+        clock,                 // Tempo-synced profiles send MIDI clock
+        watchdog: Default::default(), // Skips notes that hang instead of stalling the range
+        hooks: Default::default(),
     };
+    let readme_sampling = sampling_config.clone();
     
-    // Step 2: Range sampling in dedicated thread (follows architecture pattern)
+    // Queue entries on different patches play the same notes, so their names carry {patch};
+    // release and legato takes are told apart by the exporter
+    let patch_token = if matches!(&batch, Batch::Queue(queue) if queue.has_programs()) { "_{patch}" } else { "" };
+    // Build naming pattern with optional sample name prefix (consistent with single sample recording)
+    let naming_pattern = if let Some(name) = sample_name.as_ref().filter(|n| !n.trim().is_empty()) {
+        format!("{}_{{note_name}}_{{note}}_{{velocity}}{}.wav", name.trim(), patch_token)
+    } else {
+        format!("{{note_name}}_{{note}}_{{velocity}}{}.wav", patch_token)
+    };
+    
+    // Determine sample format based on frontend selection
+    let sample_format = match export_format.as_deref() {
+        Some("decentsampler") => AudioFormat::DecentSampler,
+        Some("sfz") => AudioFormat::SFZ,
+        Some("kontakt") => AudioFormat::Wav24Bit, // For future Kontakt export
+        Some("all") => AudioFormat::Wav24Bit, // Default for "all formats" 
+        // Registered instrument formats, else high-quality WAV
+        other => other.and_then(AudioFormat::parse)
+            .filter(AudioFormat::is_instrument)
+            .unwrap_or(AudioFormat::Wav32BitFloat),
+    };
+    
+    // Single export for all samples - this enables .dspreset/.sfz generation
+    let export_config = ExportConfig {
+        output_directory: output_path.clone(),
+        naming_pattern,
+        sample_format,
+        normalize: false,
+        fade_in_ms: 0.0,
+        fade_out_ms: 10.0,
+        apply_detection: true, // Enable detection by default
+        detection_config,
+        creator_name: creator_name.clone(),
+        instrument_description: instrument_description.clone(),
+        drum_map,
+        readme: Some(ReadmeConfig {
+            sampling: Some(readme_sampling.clone()),
+            ..ReadmeConfig::load_default()
+        }),
+        sidecar_edits: sidecar_edits.unwrap_or(false),
+        // Browsable later in the session library
+        catalog: Some(CatalogConfig {
+            path: None,
+            midi_device: ENGINE.midi_device_name(),
+            audio_device: AudioManager::new().ok().and_then(|audio| audio.input_device_name()),
+            sampling: Some(readme_sampling.clone()),
+        }),
+        overwrite,
+        naming,
+        ..ExportConfig::default()
+    };
+    
+    // Step 1: Range sampling on the controller's capture thread (holds the MIDI connection),
+    // each take handed to the export worker as soon as it is captured
     println!("📡 GUI: Delegating to Core Audio Engine for range sampling...");
     
-    let (tx, rx) = std::sync::mpsc::channel();
-    std::thread::spawn(move || {
+    let label = batch.label();
+    let progress_app = app.clone();
+    let recording_result = ENGINE.record_cancellable(move |connection, cancel| {
         println!("🧵 Range sampling thread started");
         
-        // Configure Core Audio Engine
-        println!("🔧 Configuring sampling engine for range...");
-        let sampling_config = SamplingConfig {
-            note_duration_ms: duration as u64,
-            release_time_ms: 500,  // Professional standard: 500ms release capture
-            pre_delay_ms: 100,     // Professional standard: 100ms pre-roll  
-            post_delay_ms: 100,    // Clean buffer flush
-            midi_channel: 0,       // Channel 1 (0-indexed)
-            velocity,
-        };
-        
         println!("🎛️ Creating SamplingEngine for range sampling...");
-        let sampling_engine = match SamplingEngine::new(sampling_config) {
-            Ok(engine) => {
-                println!("✅ SamplingEngine created successfully");
-                engine
-            },
-            Err(e) => {
-                println!("❌ Failed to create SamplingEngine: {}", e);
-                let _ = tx.send((Err(e), connection));
-                return;
+        let engine_app = progress_app.clone();
+        let sampling_engine = SamplingEngine::new(sampling_config)
+            .inspect_err(|e| println!("❌ Failed to create SamplingEngine: {}", e))?
+            .with_cancel(cancel)
+            .with_progress(move |progress| emit_range_note(&engine_app, progress.into()));
+        let export_app = progress_app.clone();
+        let export = IncrementalExport::start_observed(export_config, move |sample, file| {
+            emit_range_note(&export_app, RangeNoteEvent::Exported {
+                note: sample.note,
+                velocity: sample.velocity,
+                file_path: file.display().to_string(),
+            });
+        }).inspect_err(|e| println!("❌ Failed to start exporter: {}", e))?;
+        
+        let mut on_sample = |mut sample: Sample| {
+            if sample.audio_data.is_empty() {
+                println!("⚠️ GUI: Warning - Sample (note {}) has no audio data, skipping", sample.note);
+                emit_range_note(&progress_app, RangeNoteEvent::Failed {
+                    note: sample.note,
+                    velocity: sample.velocity,
+                    reason: "no audio captured".to_string(),
+                });
+                return Ok(());
             }
+            // Flag notes whose pitch wanders (failing or cold analog oscillators) or that
+            // sounded a different note than the one requested
+            sample.track_pitch_drift(Some(DEFAULT_DRIFT_LIMIT_CENTS));
+            sample.verify_played_note();
+            let analysis = TakeAnalysis::from_sample(&sample);
+            emit_range_note(&progress_app, RangeNoteEvent::Recorded {
+                note: sample.note,
+                velocity: sample.velocity,
+                peak_db: analysis.peak_db,
+                rms_db: analysis.rms_db,
+                duration_secs: analysis.duration_secs,
+            });
+            export.push(sample)
         };
         
-        // Use blocking range method from Core Audio Engine
-        println!("🎵 Starting range recording for notes {}-{}", start_note, end_note);
-        let result = sampling_engine.sample_note_range_blocking(&mut connection, start_note, end_note);
+        // Use blocking range/queue methods from Core Audio Engine
+        let result = match &batch {
+            Batch::Range { start_note, end_note, velocities } if velocities.len() > 1 => {
+                println!("🎵 Starting range recording for notes {}-{} at velocities {:?}", start_note, end_note, velocities);
+                // Velocity layers run as a one-entry queue over the same capture stream
+                let mut layers = SamplingQueue::new();
+                layers.push(QueueEntry { velocities: velocities.clone(), ..QueueEntry::new(*start_note, *end_note) })
+                    .and_then(|_| sampling_engine.sample_queue_blocking(connection, &layers, settle_ms, &mut on_sample))
+            }
+            Batch::Range { start_note, end_note, .. } => {
+                println!("🎵 Starting range recording for notes {}-{}", start_note, end_note);
+                sampling_engine.sample_note_range_each_blocking(connection, *start_note, *end_note, &mut on_sample)
+            }
+            Batch::Queue(queue) => {
+                println!("🎵 Starting sampling queue ({} takes)", queue.take_count());
+                sampling_engine.sample_queue_blocking(connection, queue, settle_ms, &mut on_sample)
+            }
+        };
         
+        // On failure the export is dropped, which still writes the takes already queued
         match &result {
-            Ok(samples) => println!("✅ Range recording completed: {} samples", samples.len()),
-            Err(e) => println!("❌ Range recording failed: {}", e),
+            Ok(count) => println!("✅ {} completed: {} samples", label, count),
+            Err(e) => println!("❌ {} failed: {}", label, e),
         }
-        
-        // Send result back via channel
-        println!("📡 Sending range result back to main thread");
-        let _ = tx.send((result, connection));
+        result.map(|_| export)
     });
     
-    // Step 3: Block until range operation completes
-    let (recording_result, returned_connection) = rx.recv()
-        .map_err(|e| format!("Range sampling thread communication failed: {}", e))?;
-    
-    // Put the connection back
-    *MIDI_CONNECTION.lock().unwrap() = Some(returned_connection);
-    
     match recording_result {
-        Ok(samples) => {
+        Ok(export) => {
             println!("✅ GUI: Core Audio Engine completed range recording successfully");
-            println!("📊 GUI: Received {} samples from Core Engine", samples.len());
             
-            // Step 4: Handle export for all samples
-            let output_dir = if let Some(dir) = output_directory {
-                if dir.trim().is_empty() {
-                    dirs::desktop_dir()
-                        .map(|desktop| desktop.join("Batcherbird Samples"))
-                        .unwrap_or_else(|| std::path::PathBuf::from("samples"))
-                        .to_string_lossy()
-                        .to_string()
-                } else {
-                    dir
-                }
-            } else {
-                dirs::desktop_dir()
-                    .map(|desktop| desktop.join("Batcherbird Samples"))
-                    .unwrap_or_else(|| std::path::PathBuf::from("samples"))
-                    .to_string_lossy()
-                    .to_string()
-            };
-            
-            let mut output_path = std::path::PathBuf::from(&output_dir);
-            
-            // Create subfolder if sample name is provided (professional organization)
-            if let Some(name) = sample_name.as_ref().filter(|n| !n.trim().is_empty()) {
-                output_path = output_path.join(name.trim());
-                println!("📁 GUI: Creating subfolder for range samples: {}", name.trim());
-            }
-            
-            // Ensure output directory exists (including subfolder)
-            if let Err(e) = std::fs::create_dir_all(&output_path) {
-                println!("❌ GUI: Failed to create output directory: {}", e);
-                return Err(format!("Failed to create output directory '{}': {}", output_path.display(), e));
-            }
-            
-            println!("📁 GUI: Using output directory: {}", output_path.display());
-            
-            // Filter out empty samples
-            let valid_samples: Vec<_> = samples.into_iter()
-                .filter(|sample| {
-                    if sample.audio_data.is_empty() {
-                        println!("⚠️ GUI: Warning - Sample (note {}) has no audio data, skipping", sample.note);
-                        false
-                    } else {
-                        true
-                    }
-                })
-                .collect();
+            // Step 2: Wait for the last takes, then write the instrument file and README
+            let _exporting = ENGINE.begin(EngineState::Exporting).map_err(|e| e.to_string())?;
+            let output = export.finish().map_err(|e| {
+                println!("❌ GUI: Export failed: {}", e);
+                format!("Failed to export samples: {}", e)
+            })?;
             
-            if valid_samples.is_empty() {
+            if output.samples.is_empty() {
                 return Err("No valid samples to export".to_string());
             }
             
-            // Build naming pattern with optional sample name prefix (consistent with single sample recording)
-            let naming_pattern = if let Some(name) = sample_name.as_ref().filter(|n| !n.trim().is_empty()) {
-                format!("{}_{{note_name}}_{{note}}_{{velocity}}.wav", name.trim())
-            } else {
-                "{note_name}_{note}_{velocity}.wav".to_string()
-            };
-            
-            // Determine sample format based on frontend selection
-            let sample_format = match export_format.as_deref() {
-                Some("decentsampler") => AudioFormat::DecentSampler,
-                Some("sfz") => AudioFormat::SFZ,
-                Some("kontakt") => AudioFormat::Wav24Bit, // For future Kontakt export
-                Some("all") => AudioFormat::Wav24Bit, // Default for "all formats" 
-                _ => AudioFormat::Wav32BitFloat, // Default: high-quality WAV
-            };
-            
-            // Create single exporter for all samples - this enables .dspreset/.sfz generation
-            let export_config = ExportConfig {
-                output_directory: output_path.clone(),
-                naming_pattern,
-                sample_format,
-                normalize: false,
-                fade_in_ms: 0.0,
-                fade_out_ms: 10.0,
-                apply_detection: true, // Enable detection by default
-                detection_config: Default::default(),
-                creator_name: creator_name.clone(),
-                instrument_description: instrument_description.clone(),
-            };
-            
-            println!("🔧 GUI: Creating batch exporter for {} samples...", valid_samples.len());
-            let exporter = SampleExporter::new(export_config).map_err(|e| {
-                println!("❌ GUI: Failed to create batch exporter: {}", e);
-                format!("Failed to create sample exporter: {}", e)
-            })?;
-            
-            // Export all samples as a batch - this will create .dspreset/.sfz files automatically
-            println!("💾 GUI: Batch exporting {} samples...", valid_samples.len());
-            let exported_file_paths = exporter.export_samples(&valid_samples).map_err(|e| {
-                println!("❌ GUI: Batch export failed: {}", e);
-                format!("Failed to export samples: {}", e)
-            })?;
+            // Sample files come first in the export list, in the same order as the samples
+            for (sample, path) in output.samples.iter().zip(&output.files) {
+                emit_take_analysis(&app, path, TakeAnalysis::from_sample(sample));
+            }
             
             // Convert paths to filenames for display
-            let exported_files: Vec<String> = exported_file_paths.iter()
+            let exported_files: Vec<String> = output.files.iter()
                 .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
                 .collect();
             
-            println!("✅ GUI: Successfully batch exported {} files:", exported_files.len());
+            println!("✅ GUI: Successfully exported {} files:", exported_files.len());
             for filename in &exported_files {
                 println!("   📄 {}", filename);
             }
             
-            let success_message = format!("Range recording complete! {} files saved to:\n{}", 
-                exported_files.len(), output_path.display());
+            let success_message = format!("{} complete! {} files saved to:\n{}", 
+                label, exported_files.len(), output_path.display());
             
             println!("✅ GUI: {}", success_message);
             Ok(success_message)
         }
         Err(e) => {
             println!("❌ GUI: Core Audio Engine reported range recording error: {}", e);
-            Err(format!("Range recording failed: {}", e))
+            Err(format!("{} failed (takes recorded so far are in {}): {}", label, output_path.display(), e))
         }
     }
 }
@@ -955,6 +1276,7 @@ fn detect_loop_points(file_path: String, min_loop_length: Option<f32>, max_loop_
                 recorded_at: std::time::SystemTime::now(),
                 midi_timing: std::time::Duration::from_millis(100),
                 audio_timing: std::time::Duration::from_millis(2000),
+                metadata: Default::default(),
             };
             
             // Configure loop detection
@@ -1012,60 +1334,350 @@ fn detect_loop_points(file_path: String, min_loop_length: Option<f32>, max_loop_
     }
 }
 
+/// Format of a WAV file, so the waveform view can convert times to sample frames
+#[derive(serde::Serialize)]
+struct WavInfo {
+    sample_rate: u32,
+    channels: u16,
+    frames: u32,
+}
+
+/// Run trim detection on a WAV file without touching it, returning the RMS timeline, threshold
+/// and chosen boundaries so the GUI can show why a take was trimmed where it was
 #[tauri::command]
-async fn send_midi_panic() -> Result<String, String> {
-    println!("🚨 MIDI Panic command called from UI");
-    
-    // Extract the connection from the mutex and drop the guard
-    let mut connection = {
-        let mut connection_guard = MIDI_CONNECTION.lock().unwrap();
-        match connection_guard.take() {
-            Some(conn) => conn,
-            None => return Err("No MIDI connection established. Please select a MIDI device first.".to_string()),
-        }
+fn analyze_sample_file(file_path: String) -> Result<DetectionResult, String> {
+    println!("🔍 GUI: Analyzing detection boundaries for {}", file_path);
+    let detection_config = SELECTED_PROFILE.lock().unwrap().as_ref()
+        .map(|p| p.detection_config())
+        .unwrap_or_default();
+    let (audio, spec) = library::read_wav(std::path::Path::new(&file_path))
+        .map_err(|e| format!("Failed to read sample: {}", e))?;
+    detection_config.detector()
+        .detect_boundaries(&audio, spec.sample_rate)
+        .map_err(|e| format!("Detection failed: {}", e))
+}
+
+/// Save user-adjusted trim and loop points as a new WAV (the original is kept).
+/// Frames refer to the original file; returns the path of the edited copy.
+#[tauri::command]
+fn apply_sample_edit(file_path: String, trim_start: usize, trim_end: Option<usize>, loop_start: Option<usize>, loop_end: Option<usize>, output_path: Option<String>) -> Result<String, String> {
+    let source = std::path::Path::new(&file_path);
+    let destination = output_path
+        .filter(|p| !p.trim().is_empty())
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| edit::edited_file_path(source));
+    let sample_edit = SampleEdit {
+        trim_start,
+        trim_end,
+        loop_points: loop_start.zip(loop_end).map(|(start, end)| LoopPoints { start, end }),
+        ..SampleEdit::default()
     };
     
-    // Send panic
-    let result = MidiManager::send_midi_panic(&mut connection)
-        .map_err(|e| e.to_string());
+    edit::apply_edit(source, &destination, &sample_edit)
+        .map_err(|e| format!("Failed to save edit: {}", e))?;
+    println!("✂️ GUI: Saved edited sample {}", destination.display());
+    Ok(destination.display().to_string())
+}
+
+/// Edit decisions saved next to a raw take, if any
+#[tauri::command]
+fn load_sample_edit(file_path: String) -> Result<Option<SampleEdit>, String> {
+    SampleEdit::load_sidecar(std::path::Path::new(&file_path))
+        .map_err(|e| format!("Failed to read edit file: {}", e))
+}
+
+/// Save adjusted trim/loop/gain decisions for a raw take without rendering it
+#[tauri::command]
+fn save_sample_edit(file_path: String, sample_edit: SampleEdit) -> Result<String, String> {
+    let sidecar = sample_edit.save_sidecar(std::path::Path::new(&file_path))
+        .map_err(|e| format!("Failed to save edit file: {}", e))?;
+    println!("📝 GUI: Saved edit decisions {}", sidecar.display());
+    Ok(sidecar.display().to_string())
+}
+
+/// Render every raw take with an edit file into a "Rendered" folder inside the output location
+#[tauri::command]
+fn render_sample_edits(output_directory: Option<String>, sample_name: Option<String>) -> Result<String, String> {
+    let resolved = output_location::resolve_output_directory(output_directory.as_deref())
+        .map_err(|e| format!("No writable output directory: {}", e))?;
+    let mut source = resolved.path;
+    if let Some(name) = sample_name.as_ref().filter(|n| !n.trim().is_empty()) {
+        source = source.join(name.trim());
+    }
+    let rendered_dir = source.join("Rendered");
     
-    // Put the connection back
-    *MIDI_CONNECTION.lock().unwrap() = Some(connection);
+    let rendered = edit::render_directory(&source, &rendered_dir)
+        .map_err(|e| format!("Failed to render edits: {}", e))?;
+    Ok(format!("Rendered {} samples to:\n{}", rendered.len(), rendered_dir.display()))
+}
+
+/// Re-run detection/trim, normalization and fades over the samples already in the output
+/// location with the current settings, writing to a "Reprocessed" folder beside them
+#[tauri::command]
+fn reprocess_samples(output_directory: Option<String>, sample_name: Option<String>, export_format: Option<String>, apply_detection: bool, threshold_db: Option<f32>) -> Result<String, String> {
+    let resolved = output_location::resolve_output_directory(output_directory.as_deref())
+        .map_err(|e| format!("No writable output directory: {}", e))?;
+    let mut source = resolved.path;
+    if let Some(name) = sample_name.as_ref().filter(|n| !n.trim().is_empty()) {
+        source = source.join(name.trim());
+    }
     
-    match result {
-        Ok(_) => Ok("MIDI Panic sent successfully - all notes stopped".to_string()),
-        Err(e) => Err(format!("MIDI Panic failed: {}", e)),
+    let mut detection_config = SELECTED_PROFILE.lock().unwrap().as_ref()
+        .map(|p| p.detection_config())
+        .unwrap_or_default();
+    if let Some(threshold_db) = threshold_db {
+        detection_config.threshold_db = threshold_db;
     }
+    let sample_format = match export_format.as_deref() {
+        Some("decentsampler") => AudioFormat::DecentSampler,
+        Some("sfz") => AudioFormat::SFZ,
+        other => other.and_then(AudioFormat::parse)
+            .filter(AudioFormat::is_instrument)
+            .unwrap_or(AudioFormat::Wav24Bit),
+    };
+    let instrument = sample_name.as_deref().map(str::trim).filter(|n| !n.is_empty()).unwrap_or("Reprocessed");
+    let config = ExportConfig {
+        output_directory: source.join("Reprocessed"),
+        naming_pattern: format!("{}_{{note_name}}_{{note}}_{{velocity}}.wav", instrument),
+        sample_format,
+        normalize: false, // Preserve original dynamics, as when recording
+        apply_detection,
+        detection_config,
+        ..ExportConfig::default()
+    };
+    
+    let output = config.output_directory.clone();
+    let result = reprocess::reprocess_directory(&source, config, None)
+        .map_err(|e| format!("Failed to reprocess: {}", e))?;
+    Ok(format!("Reprocessed {} samples to:\n{}", result.samples.len() + result.unrecognized.len(), output.display()))
 }
 
-
+/// Record chosen notes (and/or the QA report's suspects) again into the samples already in
+/// the output location, overwriting them or keeping the earlier takes in a "Takes" folder
 #[tauri::command]
-fn show_samples_in_finder() -> Result<String, String> {
-    println!("📁 Opening samples folder in Finder...");
-    
-    // Get the default samples directory
-    let samples_dir = dirs::desktop_dir()
-        .map(|desktop| desktop.join("Batcherbird Samples"))
-        .unwrap_or_else(|| std::path::PathBuf::from("samples"));
+fn rerecord_samples(output_directory: Option<String>, sample_name: Option<String>, export_format: Option<String>, notes: Option<String>, from_qa: bool, keep_versions: bool, duration: u32) -> Result<String, String> {
+    let resolved = output_location::resolve_output_directory(output_directory.as_deref())
+        .map_err(|e| format!("No writable output directory: {}", e))?;
+    let mut session = resolved.path;
+    if let Some(name) = sample_name.as_ref().filter(|n| !n.trim().is_empty()) {
+        session = session.join(name.trim());
+    }
     
-    // Create the directory if it doesn't exist
-    if let Err(e) = std::fs::create_dir_all(&samples_dir) {
-        return Err(format!("Failed to create samples directory: {}", e));
+    let mut entries = match notes.as_deref().filter(|n| !n.trim().is_empty()) {
+        Some(spec) => RerecordEntry::parse_list(spec).map_err(|e| e.to_string())?,
+        None => Vec::new(),
+    };
+    if from_qa {
+        entries.extend(RerecordEntry::from_qa_report(&session)
+            .map_err(|e| format!("No QA report to read: {}", e))?);
+    }
+    if entries.is_empty() {
+        return Err("Nothing to record: enter notes as note:velocity or use the QA report".to_string());
     }
     
-    // Open in Finder on macOS
-    match Command::new("open")
-        .arg(&samples_dir)
-        .status() {
-        Ok(_) => {
-            println!("✅ Opened {} in Finder", samples_dir.display());
-            Ok(format!("Opened samples folder: {}", samples_dir.display()))
+    let profile = SELECTED_PROFILE.lock().unwrap().clone();
+    let sampling_config = SamplingConfig {
+        note_duration_ms: duration as u64,
+        release_time_ms: 500,
+        pre_delay_ms: 100,
+        post_delay_ms: 100,
+        midi_channel: profile.as_ref().map_or(0, |p| p.channel_index()),
+        velocity: 127, // Each entry carries its own velocity
+        velocity_curve: VelocityCurve::Linear,
+        panic_style: profile.as_ref().map(|p| p.panic_style).unwrap_or_default(),
+        clock: profile.as_ref().and_then(|p| p.clock()),
+        watchdog: Default::default(),
+        hooks: Default::default(),
+    };
+    let naming_pattern = match sample_name.as_ref().filter(|n| !n.trim().is_empty()) {
+        Some(name) => format!("{}_{{note_name}}_{{note}}_{{velocity}}.wav", name.trim()),
+        None => "{note_name}_{note}_{velocity}.wav".to_string(),
+    };
+    let config = ExportConfig {
+        output_directory: session.clone(),
+        naming_pattern,
+        sample_format: match export_format.as_deref() {
+            Some("decentsampler") => AudioFormat::DecentSampler,
+            Some("sfz") => AudioFormat::SFZ,
+            other => other.and_then(AudioFormat::parse)
+                .filter(AudioFormat::is_instrument)
+                .unwrap_or(AudioFormat::Wav32BitFloat),
         },
-        Err(e) => {
-            println!("❌ Failed to open Finder: {}", e);
-            Err(format!("Failed to open Finder: {}", e))
-        }
+        normalize: false,
+        detection_config: profile.as_ref().map(|p| p.detection_config()).unwrap_or_default(),
+        ..ExportConfig::default()
+    };
+    let mode = if keep_versions { RerecordMode::Version } else { RerecordMode::Overwrite };
+    
+    // Capture runs on the controller's capture thread, as for range recording
+    let result = ENGINE.record(move |connection| {
+        SamplingEngine::new(sampling_config)
+            .and_then(|engine| rerecord::rerecord_session(&engine, connection, config, &entries, mode))
+    });
+    
+    let result = result.map_err(|e| format!("Re-record failed: {}", e))?;
+    let mut message = format!("Re-recorded {} takes ({} replaced, {} added) in:\n{}",
+        result.replaced.len() + result.added.len(), result.replaced.len(), result.added.len(), session.display());
+    if !result.skipped.is_empty() {
+        message.push_str(&format!("\n{} takes timed out and were not recorded", result.skipped.len()));
+    }
+    Ok(message)
+}
+
+/// Loop stored in a WAV's smpl chunk, so edited files reopen with their loop shown
+#[tauri::command]
+fn get_sample_loop_points(file_path: String) -> Result<Option<LoopPoints>, String> {
+    edit::read_loop_points(std::path::Path::new(&file_path))
+        .map_err(|e| format!("Failed to read loop points: {}", e))
+}
+
+/// Min/max peak overview of a WAV file. With `width` only the level best suited to a view of
+/// that many pixels is returned, otherwise every zoom level is.
+#[tauri::command]
+fn get_waveform_peaks(file_path: String, width: Option<usize>) -> Result<WaveformPeaks, String> {
+    let mut peaks = WaveformPeaks::from_wav_file(std::path::Path::new(&file_path))
+        .map_err(|e| format!("Failed to read waveform: {}", e))?;
+    if let Some(width) = width {
+        let level = peaks.level_for_width(width).clone();
+        peaks.levels = vec![level];
+    }
+    Ok(peaks)
+}
+
+#[tauri::command]
+fn get_wav_info(file_path: String) -> Result<WavInfo, String> {
+    let reader = hound::WavReader::open(&file_path)
+        .map_err(|e| format!("Failed to open WAV file: {}", e))?;
+    let spec = reader.spec();
+    Ok(WavInfo {
+        sample_rate: spec.sample_rate,
+        channels: spec.channels,
+        frames: reader.duration(),
+    })
+}
+
+/// Play frames start_sample..end_sample of a WAV through the output device (scrubbing / region audition)
+#[tauri::command]
+fn play_sample_region(file_path: String, start_sample: usize, end_sample: Option<usize>) -> Result<String, String> {
+    // Starting a region stops whatever is playing
+    ENGINE.play_region(std::path::Path::new(&file_path), start_sample, end_sample)
+        .map_err(|e| format!("Playback failed: {}", e))?;
+    Ok(format!("Playing from sample {}", start_sample))
+}
+
+/// Play a whole WAV, e.g. the take found by get_last_recorded_sample_path
+#[tauri::command]
+fn play_sample_file(path: String) -> Result<String, String> {
+    let path = std::path::PathBuf::from(path);
+    if !path.is_file() {
+        return Err(format!("File not found: {}", path.display()));
     }
+    ENGINE.play_region(&path, 0, None)
+        .map_err(|e| format!("Playback failed: {}", e))?;
+    let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    Ok(format!("Playing {}", name))
+}
+
+/// Stop any file, region or loop playback
+#[tauri::command]
+fn stop_playback() -> Result<String, String> {
+    ENGINE.stop_playback();
+    Ok("Playback stopped".to_string())
+}
+
+/// Open the input stream now so later takes start with no stream startup delay
+#[tauri::command]
+fn arm_capture() -> Result<String, String> {
+    if !ENGINE.arm_capture().map_err(|e| format!("Failed to arm capture: {}", e))? {
+        return Ok("Capture already armed".to_string());
+    }
+    let (sample_rate, channels) = ENGINE.armed_format().unwrap_or_default();
+    Ok(format!("Capture armed ({} Hz, {} channels)", sample_rate, channels))
+}
+
+#[tauri::command]
+fn disarm_capture() -> Result<String, String> {
+    ENGINE.disarm_capture();
+    Ok("Capture disarmed".to_string())
+}
+
+#[tauri::command]
+fn is_capture_armed() -> bool {
+    ENGINE.armed_format().is_some()
+}
+
+/// Start recording on the armed stream
+#[tauri::command]
+fn start_armed_take() -> Result<String, String> {
+    ENGINE.start_armed_take().map_err(|e| e.to_string())?;
+    Ok("Recording".to_string())
+}
+
+/// Stop the current take and save it as a 24-bit WAV
+#[tauri::command]
+fn stop_armed_take(note: u8, velocity: u8, file_path: String) -> Result<String, String> {
+    let sample = ENGINE.stop_armed_take(note, velocity).map_err(|e| e.to_string())?;
+    
+    let spec = hound::WavSpec {
+        channels: sample.channels,
+        sample_rate: sample.sample_rate,
+        bits_per_sample: 24,
+        sample_format: hound::SampleFormat::Int,
+    };
+    batcherbird_core::library::write_wav(std::path::Path::new(&file_path), &sample.audio_data, spec)
+        .map_err(|e| format!("Failed to save take: {}", e))?;
+    Ok(format!("Saved {:.2}s take to {}", sample.audio_timing.as_secs_f32(), file_path))
+}
+
+/// Loop start..end of a WAV with a temporary crossfade so loop points can be judged by ear
+#[tauri::command]
+fn audition_loop(file_path: String, loop_start: usize, loop_end: usize, crossfade_ms: f32) -> Result<String, String> {
+    let crossfade = Duration::from_secs_f32(crossfade_ms.max(0.0) / 1000.0);
+    ENGINE.play_loop(std::path::Path::new(&file_path), loop_start, loop_end, crossfade)
+        .map_err(|e| format!("Loop audition failed: {}", e))?;
+    Ok(format!("Looping samples {}..{}", loop_start, loop_end))
+}
+
+/// Move the loop points of the running audition without restarting it
+#[tauri::command]
+fn update_audition_loop(loop_start: usize, loop_end: usize, crossfade_ms: f32) -> Result<String, String> {
+    let crossfade = Duration::from_secs_f32(crossfade_ms.max(0.0) / 1000.0);
+    ENGINE.set_loop_points(loop_start, loop_end, crossfade)
+        .map_err(|e| format!("Failed to update loop: {}", e))?;
+    Ok(format!("Loop moved to samples {}..{}", loop_start, loop_end))
+}
+
+/// Current playback frame, or None when nothing is playing
+#[tauri::command]
+fn get_playback_position() -> Option<usize> {
+    ENGINE.playback_position()
+}
+
+#[tauri::command]
+async fn send_midi_panic() -> Result<String, String> {
+    println!("🚨 MIDI Panic command called from UI");
+    
+    ENGINE.send_panic().map_err(|e| format!("MIDI Panic failed: {}", e))?;
+    Ok("MIDI Panic sent successfully - all notes stopped".to_string())
+}
+
+
+/// Open the samples folder in Finder, Explorer or the Linux file manager
+#[tauri::command]
+fn show_samples_in_finder() -> Result<String, String> {
+    println!("📁 Opening samples folder in the {}...", file_manager::file_manager_name());
+    
+    // Get the default samples directory (created if missing, sandbox-safe fallback)
+    let samples_dir = output_location::resolve_output_directory(None)
+        .map_err(|e| format!("Failed to create samples directory: {}", e))?
+        .path;
+    
+    file_manager::reveal(&samples_dir).map_err(|e| {
+        println!("❌ Failed to open the {}: {}", file_manager::file_manager_name(), e);
+        format!("Failed to open samples folder: {}", e)
+    })?;
+    println!("✅ Opened {}", samples_dir.display());
+    Ok(format!("Opened samples folder: {}", samples_dir.display()))
 }
 
 /// Get the path of the most recently recorded sample file
@@ -1077,22 +1689,10 @@ fn get_last_recorded_sample_path(output_directory: Option<String>, sample_name:
     use std::fs;
     use std::time::SystemTime;
     
-    // Determine search directory
-    let search_dir = if let Some(dir) = output_directory {
-        if dir.trim().is_empty() {
-            // Use Desktop/Batcherbird Samples when field is empty
-            dirs::desktop_dir()
-                .map(|desktop| desktop.join("Batcherbird Samples"))
-                .unwrap_or_else(|| PathBuf::from("samples"))
-        } else {
-            PathBuf::from(dir)
-        }
-    } else {
-        // Default to Desktop/Batcherbird Samples
-        dirs::desktop_dir()
-            .map(|desktop| desktop.join("Batcherbird Samples"))
-            .unwrap_or_else(|| PathBuf::from("samples"))
-    };
+    // Determine search directory (same resolution as recording, so fallbacks are found too)
+    let search_dir: PathBuf = output_location::resolve_output_directory(output_directory.as_deref())
+        .map_err(|e| format!("No accessible output directory: {}", e))?
+        .path;
     
     // Add subdirectory if sample name is provided
     let mut search_path = search_dir;
@@ -1147,30 +1747,99 @@ pub fn run() {
     .invoke_handler(tauri::generate_handler![
       list_midi_devices, 
       list_audio_input_devices,
+      describe_audio_input_device,
       list_audio_output_devices,
+      set_audio_input_device,
+      set_audio_output_device,
+      set_secondary_audio_input,
       connect_midi_device,
       test_midi_connection,
       preview_note,
       record_sample,
       record_range,
+      cancel_recording,
+      get_sampling_queue,
+      queue_add_entry,
+      queue_remove_entry,
+      queue_move_entry,
+      queue_clear,
+      run_sampling_queue,
+      plan_recording,
       generate_instrument_files,
+      package_instrument,
       select_output_directory,
       show_samples_in_finder,
       send_midi_panic,
       start_input_monitoring,
       stop_input_monitoring,
       get_audio_levels,
+      set_meter_peak_hold,
+      get_engine_state,
+      start_input_passthrough,
+      set_passthrough_gain,
+      stop_input_passthrough,
       detect_loop_points,
-      get_last_recorded_sample_path
+      get_last_recorded_sample_path,
+      get_recent_output_directories,
+      resolve_output_directory,
+      generate_demo_project,
+      list_midi_input_devices,
+      start_midi_monitor,
+      stop_midi_monitor,
+      save_midi_capture,
+      identify_synth,
+      suggest_velocity_layers,
+      list_synth_profiles,
+      select_synth_profile,
+      match_synth_profile,
+      get_wav_info,
+      get_waveform_peaks,
+      analyze_sample_file,
+      apply_sample_edit,
+      get_sample_loop_points,
+      load_sample_edit,
+      save_sample_edit,
+      render_sample_edits,
+      reprocess_samples,
+      rerecord_samples,
+      play_sample_region,
+      play_sample_file,
+      stop_playback,
+      audition_loop,
+      update_audition_loop,
+      arm_capture,
+      disarm_capture,
+      is_capture_armed,
+      start_armed_take,
+      stop_armed_take,
+      get_playback_position,
+      list_catalog_sessions,
+      remove_catalog_session
     ])
     .setup(|app| {
-      if cfg!(debug_assertions) {
-        app.handle().plugin(
-          tauri_plugin_log::Builder::default()
-            .level(log::LevelFilter::Info)
-            .build(),
-        )?;
+      // Regain access to previously chosen output folders (sandboxed builds lose it on relaunch)
+      use tauri::Manager;
+      match app.path().app_config_dir() {
+        Ok(config_dir) => {
+          let store_file = config_dir.join("output_bookmarks.json");
+          let mut store = BookmarkStore::load(&store_file);
+          let restored = store.restore_access();
+          println!("📁 Restored access to {} remembered output folder(s)", restored.len());
+          if let Err(e) = store.save(&store_file) {
+            println!("⚠️ Failed to save output folder bookmarks: {}", e);
+          }
+          *BOOKMARK_STORE.lock().unwrap() = Some((store, store_file));
+        }
+        Err(e) => println!("⚠️ No app config directory, output folders won't be remembered: {}", e),
       }
+      
+      // Core logs through tracing; its `log` feature forwards the events here, so this
+      // plugin is what puts capture and export progress in the terminal and log file
+      app.handle().plugin(
+        tauri_plugin_log::Builder::default()
+          .level(log::LevelFilter::Info)
+          .build(),
+      )?;
       Ok(())
     })
     .run(tauri::generate_context!())